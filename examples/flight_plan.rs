@@ -11,6 +11,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         db_path: PathBuf::from("example_aerobase.db"),
         enable_wal: true,
         pool_size: 4,
+        ..Default::default()
     };
 
     println!("Initializing AeroBase...");
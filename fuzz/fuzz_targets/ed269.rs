@@ -0,0 +1,8 @@
+#![no_main]
+
+use airway_device_base_service::navdata::ed269;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    let _ = ed269::parse(data, 0);
+});
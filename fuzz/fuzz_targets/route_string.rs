@@ -0,0 +1,43 @@
+#![no_main]
+
+use airway_device_base_service::db::Database;
+use airway_device_base_service::flight::parser;
+use airway_device_base_service::Config;
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+use tempfile::TempDir;
+
+/// A small navdata set with a shared ident, so ambiguity handling is reached
+fn database() -> &'static Database {
+    static DB: OnceLock<(Database, TempDir)> = OnceLock::new();
+    &DB.get_or_init(|| {
+        let dir = TempDir::new().unwrap();
+        let config = Config {
+            db_path: dir.path().join("fuzz.db"),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Beijing', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Shanghai', 31.20, 121.33, 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'VYK', 39.40, 116.90, 'VOR', 0),
+                    ('W2', 'DOGAR', 36.00, 118.00, 'FIX', 0),
+                    ('W3', 'DOGAR', 10.00, 10.00, 'FIX', 0),
+                    ('W4', 'AND', 34.00, 119.50, 'VOR', 0);",
+            )
+            .unwrap();
+        (db, dir)
+    })
+    .0
+}
+
+fuzz_target!(|data: &str| {
+    let _ = parser::parse_route(database(), "ZBAA", data, "ZSSS");
+});
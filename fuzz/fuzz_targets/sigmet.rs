@@ -0,0 +1,15 @@
+#![no_main]
+
+use airway_device_base_service::weather::sigmet;
+use chrono::{TimeZone, Utc};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    let received = Utc.with_ymd_and_hms(2026, 10, 17, 5, 0, 0).unwrap();
+    if let Ok(sigmet::SigmetMessage::Issued(sigmet)) = sigmet::parse(data, received) {
+        // Parsed boundaries must be usable by the containment checks
+        for vertex in &sigmet.boundary {
+            let _ = sigmet.contains(*vertex);
+        }
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use airway_device_base_service::weather::taf;
+use chrono::{TimeZone, Utc};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    let received = Utc.with_ymd_and_hms(2026, 10, 17, 5, 0, 0).unwrap();
+    let _ = taf::parse(data, received);
+});
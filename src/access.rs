@@ -0,0 +1,227 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// 服务模式下的客户端角色（权限依次递增）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Role {
+    /// 只读查询导航数据与气象
+    Viewer,
+    /// 另可创建和修改飞行计划
+    Planner,
+    /// 另可触发同步、吊销设备和管理角色
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "VIEWER",
+            Role::Planner => "PLANNER",
+            Role::Admin => "ADMIN",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "VIEWER" => Some(Role::Viewer),
+            "PLANNER" => Some(Role::Planner),
+            "ADMIN" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// 服务模式接口分组
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Endpoint {
+    /// 导航数据、气象与搜索查询
+    Query,
+    /// 飞行计划的创建、修改与删除
+    FlightPlans,
+    /// 触发同步
+    Sync,
+    /// 吊销设备
+    RevokeDevice,
+    /// 授予或撤销角色
+    ManageAccess,
+}
+
+impl Endpoint {
+    /// 调用该接口所需的最低角色
+    pub fn required_role(&self) -> Role {
+        match self {
+            Endpoint::Query => Role::Viewer,
+            Endpoint::FlightPlans => Role::Planner,
+            Endpoint::Sync | Endpoint::RevokeDevice | Endpoint::ManageAccess => Role::Admin,
+        }
+    }
+}
+
+/// 客户端凭据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// 客户端证书的 SHA-256 指纹（十六进制，可含冒号）
+    Certificate(String),
+    /// Bearer 令牌，只保存其哈希
+    Token(String),
+}
+
+impl Credential {
+    /// Key stored in `access_roles`: a normalized certificate fingerprint,
+    /// or the SHA-256 of a token so the secret itself never hits the disk
+    fn principal(&self) -> String {
+        match self {
+            Credential::Certificate(fingerprint) => {
+                let hex: String = fingerprint
+                    .chars()
+                    .filter(|c| *c != ':' && !c.is_whitespace())
+                    .collect();
+                format!("cert:{}", hex.to_lowercase())
+            }
+            Credential::Token(token) => {
+                format!("token:{:x}", Sha256::digest(token.as_bytes()))
+            }
+        }
+    }
+}
+
+/// 服务模式的角色访问控制
+///
+/// 角色绑定保存在数据库中；请求处理程序在做任何数据库操作前调用 `authorize`，
+/// 返回的 `Forbidden` 对应 HTTP 403 / gRPC PERMISSION_DENIED。
+pub struct AccessControl {
+    db: Arc<Database>,
+}
+
+impl AccessControl {
+    /// 创建访问控制管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// 为凭据授予角色（替换已有角色）
+    pub fn grant(&self, credential: &Credential, role: Role) -> Result<()> {
+        let now = self.db.clock().timestamp();
+        self.db.get_conn()?.execute(
+            "INSERT INTO access_roles (principal, role, created_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(principal) DO UPDATE SET role = excluded.role",
+            rusqlite::params![credential.principal(), role.as_str(), now],
+        )?;
+        Ok(())
+    }
+
+    /// 撤销凭据的角色；返回是否存在绑定
+    pub fn revoke(&self, credential: &Credential) -> Result<bool> {
+        let removed = self.db.get_conn()?.execute(
+            "DELETE FROM access_roles WHERE principal = ?1",
+            [credential.principal()],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// 凭据绑定的角色
+    pub fn role_of(&self, credential: &Credential) -> Result<Option<Role>> {
+        let role: Option<String> = self
+            .db
+            .get_conn()?
+            .query_row(
+                "SELECT role FROM access_roles WHERE principal = ?1",
+                [credential.principal()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(role.and_then(|role| Role::from_str(&role)))
+    }
+
+    /// 检查凭据能否调用接口，返回其角色
+    pub fn authorize(&self, credential: &Credential, endpoint: Endpoint) -> Result<Role> {
+        let required = endpoint.required_role();
+        match self.role_of(credential)? {
+            Some(role) if role >= required => Ok(role),
+            Some(role) => Err(AeroBaseError::Forbidden(format!(
+                "{:?} requires {}, client has {}",
+                endpoint,
+                required.as_str(),
+                role.as_str()
+            ))),
+            None => Err(AeroBaseError::Forbidden(format!(
+                "{:?} requires {}, client has no role",
+                endpoint,
+                required.as_str()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup() -> (AccessControl, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (AccessControl::new(db).unwrap(), temp_file)
+    }
+
+    #[test]
+    fn test_authorize_by_role() {
+        let (access, _temp_file) = setup();
+        let viewer = Credential::Token("viewer-secret".to_string());
+        let admin = Credential::Certificate("AB:CD:EF:01".to_string());
+        access.grant(&viewer, Role::Viewer).unwrap();
+        access.grant(&admin, Role::Admin).unwrap();
+
+        assert_eq!(access.authorize(&viewer, Endpoint::Query).unwrap(), Role::Viewer);
+        assert!(matches!(
+            access.authorize(&viewer, Endpoint::Sync),
+            Err(AeroBaseError::Forbidden(_))
+        ));
+        for endpoint in [Endpoint::Query, Endpoint::Sync, Endpoint::RevokeDevice] {
+            assert_eq!(access.authorize(&admin, endpoint).unwrap(), Role::Admin);
+        }
+        // Fingerprints match however they are formatted
+        let same_cert = Credential::Certificate("abcdef01".to_string());
+        assert_eq!(access.role_of(&same_cert).unwrap(), Some(Role::Admin));
+
+        let stranger = Credential::Token("unknown".to_string());
+        let error = access.authorize(&stranger, Endpoint::Query).unwrap_err();
+        assert_eq!(error.code(), "forbidden");
+    }
+
+    #[test]
+    fn test_grant_replaces_and_revoke() {
+        let (access, _temp_file) = setup();
+        let client = Credential::Token("planner-secret".to_string());
+        access.grant(&client, Role::Viewer).unwrap();
+        access.grant(&client, Role::Planner).unwrap();
+        assert_eq!(access.authorize(&client, Endpoint::FlightPlans).unwrap(), Role::Planner);
+
+        // Only the token hash is stored
+        let stored: String = access
+            .db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT principal FROM access_roles", [], |row| row.get(0))
+            .unwrap();
+        assert!(stored.starts_with("token:"));
+        assert!(!stored.contains("planner-secret"));
+
+        assert!(access.revoke(&client).unwrap());
+        assert!(!access.revoke(&client).unwrap());
+        assert_eq!(access.role_of(&client).unwrap(), None);
+    }
+}
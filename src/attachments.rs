@@ -0,0 +1,544 @@
+use crate::clock::Clock;
+use crate::db::query::resolve_airport_id;
+use crate::db::Database;
+use crate::encryption::{AesEncryptor, EncryptedData};
+use crate::error::{AeroBaseError, Result};
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 附件可关联的对象类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TargetKind {
+    FlightPlan,
+    Airport,
+}
+
+impl TargetKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TargetKind::FlightPlan => "FLIGHT_PLAN",
+            TargetKind::Airport => "AIRPORT",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "FLIGHT_PLAN" => Some(TargetKind::FlightPlan),
+            "AIRPORT" => Some(TargetKind::Airport),
+            _ => None,
+        }
+    }
+}
+
+/// 附件存储位置与配额
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttachmentConfig {
+    /// 按内容寻址存放加密附件的目录；为 None 时附件存入用户库
+    pub blob_dir: Option<PathBuf>,
+    /// 所有附件的总字节数上限（相同内容只计一次），None 表示不限
+    pub quota: Option<u64>,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            blob_dir: None,
+            quota: Some(256 * 1024 * 1024),
+        }
+    }
+}
+
+/// 附件元数据
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    /// 文件名（如 "ZBAA briefing.pdf"）
+    pub name: String,
+    /// MIME 类型（如 "application/pdf"）
+    pub content_type: String,
+    /// 明文字节数
+    pub size: u64,
+    /// 明文的 SHA-256（十六进制）
+    pub sha256: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// 附件的存储占用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentUsage {
+    /// 不同内容的明文总字节数
+    pub bytes: u64,
+    pub attachments: usize,
+    pub quota: Option<u64>,
+}
+
+const COLUMNS: &str = "id, name, content_type, size, sha256, created_at, updated_at";
+
+fn from_row(row: &Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        content_type: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        sha256: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Path of the blob for `sha256` under `dir`, fanned out by its first byte
+fn blob_path(dir: &Path, sha256: &str) -> PathBuf {
+    dir.join(&sha256[..2]).join(sha256)
+}
+
+/// Copy blobs kept on disk under `dir` into `attachment_blobs`, so a
+/// snapshot of the user database carries every attachment
+///
+/// Returns the number of blobs copied. Blobs already in the table or
+/// missing from disk are skipped.
+pub(crate) fn inline_blobs(conn: &Connection, dir: &Path) -> Result<usize> {
+    let missing: Vec<(String, i64)> = conn
+        .prepare(
+            "SELECT sha256, MIN(created_at) FROM attachments
+             WHERE sha256 NOT IN (SELECT id FROM attachment_blobs)
+             GROUP BY sha256",
+        )?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+    let mut copied = 0;
+    for (sha256, created_at) in missing {
+        let Ok(data) = fs::read(blob_path(dir, &sha256)) else {
+            log::warn!("Attachment blob {} is missing from {}", sha256, dir.display());
+            continue;
+        };
+        conn.execute(
+            "INSERT INTO attachment_blobs (id, data, created_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![sha256, data, created_at],
+        )?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// 附件存储
+///
+/// 保存 PDF、图片等文件（如运营人讲评单），可关联到已保存的飞行计划和机场。
+/// 内容用调用方提供的密钥加密后按 SHA-256 去重存放：默认存入用户库，配置
+/// `blob_dir` 时存为磁盘文件。元数据、关联与存入用户库的内容都是用户数据，
+/// 随用户库同步；云端备份时磁盘上的内容也会一并写入快照。
+pub struct AttachmentStore {
+    db: Arc<Database>,
+    config: AttachmentConfig,
+    encryptor: AesEncryptor,
+    clock: Arc<dyn Clock>,
+}
+
+impl AttachmentStore {
+    /// 创建附件存储，内容用 `encryptor` 加解密
+    pub fn new(db: Arc<Database>, config: AttachmentConfig, encryptor: AesEncryptor) -> Self {
+        Self {
+            clock: Arc::clone(db.clock()),
+            db,
+            config,
+            encryptor,
+        }
+    }
+
+    /// 按 `clock` 记录创建时间
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 保存附件；相同内容只存一份，超出配额时返回错误
+    pub fn add(&self, name: &str, content_type: &str, data: &[u8]) -> Result<Attachment> {
+        if name.trim().is_empty() || content_type.trim().is_empty() {
+            return Err(AeroBaseError::InvalidInput(
+                "Attachment name and content type are required".to_string(),
+            ));
+        }
+        let sha256 = format!("{:x}", Sha256::digest(data));
+        let now = self.clock.timestamp();
+        let conn = self.db.get_conn()?;
+
+        let stored: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM attachments WHERE sha256 = ?1)",
+            [&sha256],
+            |row| row.get(0),
+        )?;
+        if !stored {
+            if let Some(quota) = self.config.quota {
+                let used = self.usage_bytes(&conn)?;
+                if used + data.len() as u64 > quota {
+                    return Err(AeroBaseError::InvalidInput(format!(
+                        "Attachment quota of {} bytes exceeded ({} in use, {} more requested)",
+                        quota,
+                        used,
+                        data.len()
+                    )));
+                }
+            }
+            self.write_blob(&conn, &sha256, data, now)?;
+        }
+
+        let attachment = Attachment {
+            id: self.db.new_id(),
+            name: name.trim().to_string(),
+            content_type: content_type.trim().to_lowercase(),
+            size: data.len() as u64,
+            sha256,
+            created_at: now,
+            updated_at: now,
+        };
+        conn.execute(
+            "INSERT INTO attachments (id, name, content_type, size, sha256, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            rusqlite::params![
+                attachment.id,
+                attachment.name,
+                attachment.content_type,
+                attachment.size as i64,
+                attachment.sha256,
+                now,
+            ],
+        )?;
+        Ok(attachment)
+    }
+
+    /// 获取附件元数据
+    pub fn get(&self, id: &str) -> Result<Option<Attachment>> {
+        let conn = self.db.get_conn()?;
+        let attachment = conn
+            .query_row(
+                &format!("SELECT {} FROM attachments WHERE id = ?1", COLUMNS),
+                [id],
+                from_row,
+            )
+            .optional()?;
+        Ok(attachment)
+    }
+
+    /// 读取并解密附件内容
+    pub fn read(&self, id: &str) -> Result<Vec<u8>> {
+        let attachment = self
+            .get(id)?
+            .ok_or_else(|| AeroBaseError::NotFound(format!("Attachment {} not found", id)))?;
+        let conn = self.db.get_conn()?;
+        let sealed: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT data FROM attachment_blobs WHERE id = ?1",
+                [&attachment.sha256],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let sealed = match (sealed, &self.config.blob_dir) {
+            (Some(sealed), _) => sealed,
+            (None, Some(dir)) => fs::read(blob_path(dir, &attachment.sha256))?,
+            (None, None) => {
+                return Err(AeroBaseError::NotFound(format!(
+                    "Content of attachment {} not found",
+                    id
+                )))
+            }
+        };
+        let encrypted: EncryptedData = serde_json::from_slice(&sealed)?;
+        let data = self.encryptor.decrypt(&encrypted)?;
+        if format!("{:x}", Sha256::digest(&data)) != attachment.sha256 {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Content of attachment {} does not match its checksum",
+                id
+            )));
+        }
+        Ok(data)
+    }
+
+    /// 删除附件及其关联；内容不再被引用时一并删除，返回附件是否存在
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        let Some(attachment) = self.get(id)? else {
+            return Ok(false);
+        };
+        let conn = self.db.get_conn()?;
+        conn.execute("DELETE FROM attachments WHERE id = ?1", [id])?;
+        let referenced: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM attachments WHERE sha256 = ?1)",
+            [&attachment.sha256],
+            |row| row.get(0),
+        )?;
+        if !referenced {
+            conn.execute("DELETE FROM attachment_blobs WHERE id = ?1", [&attachment.sha256])?;
+            if let Some(dir) = &self.config.blob_dir {
+                let _ = fs::remove_file(blob_path(dir, &attachment.sha256));
+            }
+        }
+        Ok(true)
+    }
+
+    /// 将附件关联到飞行计划（计划 ID）或机场（ICAO、IATA 或别名）
+    pub fn link(&self, id: &str, kind: TargetKind, target: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM attachments WHERE id = ?1)",
+            [id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(AeroBaseError::NotFound(format!("Attachment {} not found", id)));
+        }
+        let target_id = resolve_target(&conn, kind, target)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO attachment_links
+                 (id, attachment_id, target_type, target_id, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                self.db.new_id(),
+                id,
+                kind.as_str(),
+                target_id,
+                self.clock.timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 解除附件与对象的关联
+    pub fn unlink(&self, id: &str, kind: TargetKind, target: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        let target_id = resolve_target(&conn, kind, target)?;
+        conn.execute(
+            "DELETE FROM attachment_links
+             WHERE attachment_id = ?1 AND target_type = ?2 AND target_id = ?3",
+            rusqlite::params![id, kind.as_str(), target_id],
+        )?;
+        Ok(())
+    }
+
+    /// 关联到对象的附件（按创建时间排序）
+    pub fn for_target(&self, kind: TargetKind, target: &str) -> Result<Vec<Attachment>> {
+        let conn = self.db.get_conn()?;
+        let target_id = resolve_target(&conn, kind, target)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM attachments WHERE id IN (
+                 SELECT attachment_id FROM attachment_links
+                 WHERE target_type = ?1 AND target_id = ?2)
+             ORDER BY created_at, id",
+            COLUMNS
+        ))?;
+        let attachments = stmt
+            .query_map(rusqlite::params![kind.as_str(), target_id], from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(attachments)
+    }
+
+    /// 附件关联的对象
+    pub fn targets(&self, id: &str) -> Result<Vec<(TargetKind, String)>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT target_type, target_id FROM attachment_links
+             WHERE attachment_id = ?1 ORDER BY target_type, target_id",
+        )?;
+        let targets = stmt
+            .query_map([id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .filter_map(|row| match row {
+                Ok((kind, target)) => TargetKind::from_str(&kind).map(|kind| Ok((kind, target))),
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(targets)
+    }
+
+    /// 当前占用与配额
+    pub fn usage(&self) -> Result<AttachmentUsage> {
+        let conn = self.db.get_conn()?;
+        let attachments: i64 =
+            conn.query_row("SELECT COUNT(*) FROM attachments", [], |row| row.get(0))?;
+        Ok(AttachmentUsage {
+            bytes: self.usage_bytes(&conn)?,
+            attachments: attachments as usize,
+            quota: self.config.quota,
+        })
+    }
+
+    fn usage_bytes(&self, conn: &Connection) -> Result<u64> {
+        let bytes: i64 = conn.query_row(
+            "SELECT COALESCE(SUM(size), 0) FROM (
+                 SELECT MAX(size) AS size FROM attachments GROUP BY sha256)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(bytes as u64)
+    }
+
+    fn write_blob(&self, conn: &Connection, sha256: &str, data: &[u8], now: i64) -> Result<()> {
+        let sealed = serde_json::to_vec(&self.encryptor.encrypt(data)?)?;
+        match &self.config.blob_dir {
+            Some(dir) => {
+                let path = blob_path(dir, sha256);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                // Write beside and rename, so a crash never leaves a partial blob
+                let partial = path.with_extension("partial");
+                fs::write(&partial, &sealed)?;
+                fs::rename(&partial, &path)?;
+            }
+            None => {
+                conn.execute(
+                    "INSERT OR REPLACE INTO attachment_blobs (id, data, created_at)
+                     VALUES (?1, ?2, ?3)",
+                    rusqlite::params![sha256, sealed, now],
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stored id of a link target, checking that it exists
+fn resolve_target(conn: &Connection, kind: TargetKind, target: &str) -> Result<String> {
+    let resolved = match kind {
+        TargetKind::FlightPlan => conn
+            .query_row("SELECT id FROM flight_plans WHERE id = ?1", [target], |row| row.get(0))
+            .optional()?,
+        TargetKind::Airport => resolve_airport_id(conn, target)?,
+    };
+    resolved.ok_or_else(|| {
+        AeroBaseError::NotFound(format!("{} {} not found", kind.as_str(), target))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::repository::FlightPlanRepository;
+    use crate::flight::FlightPlan;
+    use crate::Config;
+    use tempfile::{NamedTempFile, TempDir};
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                 VALUES ('AP1', 'ZBAA', 'Beijing Capital', 40.08, 116.58, 0)",
+                [],
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    fn key() -> AesEncryptor {
+        AesEncryptor::from_key(vec![7; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_store_in_db_and_link() {
+        let (db, _temp_file) = setup_test_db();
+        let store = AttachmentStore::new(Arc::clone(&db), AttachmentConfig::default(), key());
+        let plans = FlightPlanRepository::new(Arc::clone(&db)).unwrap();
+        let plan = plans
+            .insert(
+                &FlightPlan {
+                    departure: "ZBAA".to_string(),
+                    destination: "ZBTJ".to_string(),
+                    alternate: None,
+                    cruise_altitude: 8000,
+                    cruise_speed: 120,
+                    route: vec![],
+                    ..Default::default()
+                },
+                None,
+                None,
+            )
+            .unwrap();
+
+        let pdf = b"%PDF-1.7 briefing".to_vec();
+        let attachment = store.add("briefing.pdf", "Application/PDF", &pdf).unwrap();
+        assert_eq!(attachment.content_type, "application/pdf");
+        assert_eq!(store.read(&attachment.id).unwrap(), pdf);
+
+        // Encrypted at rest
+        let sealed: Vec<u8> = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT data FROM attachment_blobs", [], |row| row.get(0))
+            .unwrap();
+        assert!(!sealed.windows(pdf.len()).any(|w| w == pdf.as_slice()));
+        let wrong_key = AttachmentStore::new(
+            Arc::clone(&db),
+            AttachmentConfig::default(),
+            AesEncryptor::from_key(vec![8; 32]).unwrap(),
+        );
+        assert!(wrong_key.read(&attachment.id).is_err());
+
+        store.link(&attachment.id, TargetKind::Airport, "zbaa").unwrap();
+        store.link(&attachment.id, TargetKind::FlightPlan, &plan.id).unwrap();
+        assert!(store.link(&attachment.id, TargetKind::Airport, "ZZZZ").is_err());
+        let linked = store.for_target(TargetKind::Airport, "ZBAA").unwrap();
+        assert_eq!(linked, vec![attachment.clone()]);
+        assert_eq!(store.targets(&attachment.id).unwrap().len(), 2);
+        store.unlink(&attachment.id, TargetKind::Airport, "ZBAA").unwrap();
+        assert!(store.for_target(TargetKind::Airport, "ZBAA").unwrap().is_empty());
+
+        assert!(store.remove(&attachment.id).unwrap());
+        assert!(!store.remove(&attachment.id).unwrap());
+        assert!(store.for_target(TargetKind::FlightPlan, &plan.id).unwrap().is_empty());
+        let blobs: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM attachment_blobs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(blobs, 0);
+    }
+
+    #[test]
+    fn test_content_addressed_on_disk_with_quota() {
+        let (db, _temp_file) = setup_test_db();
+        let dir = TempDir::new().unwrap();
+        let config = AttachmentConfig {
+            blob_dir: Some(dir.path().to_path_buf()),
+            quota: Some(100),
+        };
+        let store = AttachmentStore::new(Arc::clone(&db), config, key());
+
+        let chart = vec![0x89u8; 60];
+        let first = store.add("chart.png", "image/png", &chart).unwrap();
+        // Same content again: stored once and not counted twice
+        let second = store.add("copy.png", "image/png", &chart).unwrap();
+        assert_eq!(first.sha256, second.sha256);
+        assert!(blob_path(dir.path(), &first.sha256).exists());
+        let usage = store.usage().unwrap();
+        assert_eq!((usage.bytes, usage.attachments), (60, 2));
+
+        assert!(matches!(
+            store.add("big.png", "image/png", &[1u8; 50]),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+
+        // Inlining for a snapshot, then reading back from the table
+        {
+            let conn = db.get_conn().unwrap();
+            assert_eq!(inline_blobs(&conn, dir.path()).unwrap(), 1);
+            assert_eq!(inline_blobs(&conn, dir.path()).unwrap(), 0);
+        }
+        fs::remove_file(blob_path(dir.path(), &first.sha256)).unwrap();
+        assert_eq!(store.read(&second.id).unwrap(), chart);
+
+        store.remove(&first.id).unwrap();
+        assert_eq!(store.read(&second.id).unwrap(), chart);
+    }
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeDelta, Utc};
+use std::sync::{Arc, Mutex};
+
+/// 时间来源（设备、同步窗口、导航数据过期等逻辑通过它取当前时间）
+pub trait Clock: Send + Sync {
+    /// 当前 UTC 时间
+    fn now(&self) -> DateTime<Utc>;
+
+    /// 当前 Unix 时间戳（秒）
+    fn timestamp(&self) -> i64 {
+        self.now().timestamp()
+    }
+
+    /// 当前本地时间（同步时间窗口按本地时间配置）
+    fn local_now(&self) -> NaiveDateTime {
+        self.now().with_timezone(&Local).naive_local()
+    }
+}
+
+/// 系统时钟
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Default clock for components created without one
+pub(crate) fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// 手动控制的时钟，用于测试过期、同步窗口和 ETA 逻辑
+///
+/// 本地时间与 UTC 相同，测试结果不受运行环境时区影响。
+#[derive(Debug)]
+pub struct MockClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl MockClock {
+    /// 创建停在指定时刻的时钟
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    /// 创建停在指定 Unix 时间戳的时钟
+    pub fn at_timestamp(timestamp: i64) -> Self {
+        Self::new(DateTime::from_timestamp(timestamp, 0).unwrap_or_default())
+    }
+
+    /// 拨到指定时刻
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// 向前拨动
+    pub fn advance(&self, delta: TimeDelta) {
+        *self.now.lock().unwrap() += delta;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+
+    fn local_now(&self) -> NaiveDateTime {
+        self.now().naive_utc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::at_timestamp(1_700_000_000);
+        assert_eq!(clock.timestamp(), 1_700_000_000);
+        clock.advance(TimeDelta::minutes(90));
+        assert_eq!(clock.timestamp(), 1_700_005_400);
+        assert_eq!(clock.local_now(), clock.now().naive_utc());
+
+        let shared: Arc<dyn Clock> = Arc::new(SystemClock);
+        assert!((shared.timestamp() - Utc::now().timestamp()).abs() <= 1);
+    }
+}
@@ -0,0 +1,381 @@
+use crate::flight::validator::ValidationReport;
+use crate::flight::FlightRoute;
+use crate::maintenance::LogbookEntry;
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_MINUTE: i64 = 60;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// 机组飞行的一段：撤轮挡时间与轮挡时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrewFlight {
+    /// 撤轮挡时间（Unix 秒）
+    pub off_block: i64,
+    /// 轮挡时间（分钟）
+    pub block_minutes: i64,
+}
+
+impl CrewFlight {
+    /// Unix seconds at on-block
+    pub fn on_block(&self) -> i64 {
+        self.off_block + self.block_minutes * SECONDS_PER_MINUTE
+    }
+}
+
+impl From<&LogbookEntry> for CrewFlight {
+    fn from(entry: &LogbookEntry) -> Self {
+        Self {
+            off_block: entry.flown_at,
+            block_minutes: i64::from(entry.block_minutes),
+        }
+    }
+}
+
+/// 滚动时间窗内的累计飞行时间上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CumulativeLimit {
+    pub window_days: i64,
+    pub max_flight_minutes: i64,
+}
+
+/// 值勤与休息规则集
+///
+/// 所有时长以分钟计。默认值是通用的保守配置，不对应任何局方规章；
+/// 运营人应按适用规章填写。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrewRules {
+    pub name: String,
+    /// 首次撤轮挡前的报到时间
+    pub report_minutes: i64,
+    /// 最后一次挡轮挡后的下班时间
+    pub release_minutes: i64,
+    /// 一次值勤的最长时间
+    pub max_duty_minutes: i64,
+    /// 一次值勤内的最长飞行时间
+    pub max_flight_minutes: i64,
+    /// 两次值勤之间的最短休息时间
+    pub min_rest_minutes: i64,
+    /// 离岗至少这么长才视为两次值勤之间的休息，更短的间隔计入值勤
+    pub duty_break_minutes: i64,
+    pub cumulative: Vec<CumulativeLimit>,
+}
+
+impl Default for CrewRules {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            report_minutes: 60,
+            release_minutes: 30,
+            max_duty_minutes: 13 * 60,
+            max_flight_minutes: 9 * 60,
+            min_rest_minutes: 10 * 60,
+            duty_break_minutes: 6 * 60,
+            cumulative: vec![
+                CumulativeLimit {
+                    window_days: 28,
+                    max_flight_minutes: 100 * 60,
+                },
+                CumulativeLimit {
+                    window_days: 365,
+                    max_flight_minutes: 1000 * 60,
+                },
+            ],
+        }
+    }
+}
+
+impl CrewRules {
+    /// FAR 135.265/135.267 单人制机组的数值（日历月、日历年按 30、365 天滚动计算）
+    pub fn far_135_single_pilot() -> Self {
+        Self {
+            name: "FAR 135 single pilot".to_string(),
+            max_duty_minutes: 14 * 60,
+            max_flight_minutes: 8 * 60,
+            min_rest_minutes: 10 * 60,
+            cumulative: vec![
+                CumulativeLimit {
+                    window_days: 7,
+                    max_flight_minutes: 34 * 60,
+                },
+                CumulativeLimit {
+                    window_days: 30,
+                    max_flight_minutes: 120 * 60,
+                },
+                CumulativeLimit {
+                    window_days: 365,
+                    max_flight_minutes: 1200 * 60,
+                },
+            ],
+            ..Self::default()
+        }
+    }
+}
+
+/// 一次值勤：从报到到下班
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DutyPeriod {
+    /// 报到时间（Unix 秒）
+    pub start: i64,
+    /// 下班时间（Unix 秒）
+    pub end: i64,
+    pub flight_minutes: i64,
+    pub flights: usize,
+    /// 与上一次值勤之间的休息时间，首次值勤为 `None`
+    pub rest_before_minutes: Option<i64>,
+}
+
+impl DutyPeriod {
+    pub fn duty_minutes(&self) -> i64 {
+        (self.end - self.start) / SECONDS_PER_MINUTE
+    }
+}
+
+/// 被超出的限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrewLimit {
+    DutyTime,
+    FlightTime,
+    Rest,
+    Cumulative { window_days: i64 },
+}
+
+/// 机组时限告警
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrewWarning {
+    pub limit: CrewLimit,
+    /// 所在值勤的报到时间（Unix 秒）
+    pub duty_start: i64,
+    pub limit_minutes: i64,
+    pub actual_minutes: i64,
+}
+
+impl CrewWarning {
+    /// Rule identifier used in validation reports
+    pub fn rule(&self) -> &'static str {
+        match self.limit {
+            CrewLimit::DutyTime => "crew_duty_time",
+            CrewLimit::FlightTime => "crew_flight_time",
+            CrewLimit::Rest => "crew_rest",
+            CrewLimit::Cumulative { .. } => "crew_cumulative_flight_time",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        let what = match self.limit {
+            CrewLimit::DutyTime => "Duty period".to_string(),
+            CrewLimit::FlightTime => "Flight time in duty period".to_string(),
+            CrewLimit::Rest => "Rest before duty period".to_string(),
+            CrewLimit::Cumulative { window_days } => {
+                format!("Flight time in {} days", window_days)
+            }
+        };
+        let relation = if self.limit == CrewLimit::Rest {
+            "below the minimum"
+        } else {
+            "exceeds the limit"
+        };
+        format!(
+            "{} of {} {} of {}",
+            what,
+            format_minutes(self.actual_minutes),
+            relation,
+            format_minutes(self.limit_minutes)
+        )
+    }
+}
+
+fn format_minutes(minutes: i64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// 按规则把飞行划分为值勤（按时间排序）
+pub fn duty_periods(flights: &[CrewFlight], rules: &CrewRules) -> Vec<DutyPeriod> {
+    let mut flights = flights.to_vec();
+    flights.sort_by_key(|f| f.off_block);
+
+    let mut periods: Vec<DutyPeriod> = Vec::new();
+    for flight in flights {
+        let start = flight.off_block - rules.report_minutes * SECONDS_PER_MINUTE;
+        let end = flight.on_block() + rules.release_minutes * SECONDS_PER_MINUTE;
+        if let Some(current) = periods.last_mut() {
+            let gap_minutes = (start - current.end) / SECONDS_PER_MINUTE;
+            if gap_minutes < rules.duty_break_minutes {
+                current.end = current.end.max(end);
+                current.flight_minutes += flight.block_minutes;
+                current.flights += 1;
+                continue;
+            }
+        }
+        let rest_before_minutes = periods
+            .last()
+            .map(|previous| (start - previous.end) / SECONDS_PER_MINUTE);
+        periods.push(DutyPeriod {
+            start,
+            end,
+            flight_minutes: flight.block_minutes,
+            flights: 1,
+            rest_before_minutes,
+        });
+    }
+    periods
+}
+
+/// 按规则检查已飞或已排的飞行，返回超出的限制（按值勤时间排序）
+pub fn check(flights: &[CrewFlight], rules: &CrewRules) -> Vec<CrewWarning> {
+    let mut warnings = Vec::new();
+    for period in duty_periods(flights, rules) {
+        let mut warn = |limit, limit_minutes, actual_minutes| {
+            warnings.push(CrewWarning {
+                limit,
+                duty_start: period.start,
+                limit_minutes,
+                actual_minutes,
+            })
+        };
+        if let Some(rest) = period.rest_before_minutes.filter(|r| *r < rules.min_rest_minutes) {
+            warn(CrewLimit::Rest, rules.min_rest_minutes, rest);
+        }
+        if period.duty_minutes() > rules.max_duty_minutes {
+            warn(CrewLimit::DutyTime, rules.max_duty_minutes, period.duty_minutes());
+        }
+        if period.flight_minutes > rules.max_flight_minutes {
+            warn(CrewLimit::FlightTime, rules.max_flight_minutes, period.flight_minutes);
+        }
+        for limit in &rules.cumulative {
+            let window_start = period.end - limit.window_days * SECONDS_PER_DAY;
+            let flown: i64 = flights
+                .iter()
+                .filter(|f| f.off_block > window_start && f.off_block <= period.end)
+                .map(|f| f.block_minutes)
+                .sum();
+            if flown > limit.max_flight_minutes {
+                warn(
+                    CrewLimit::Cumulative {
+                        window_days: limit.window_days,
+                    },
+                    limit.max_flight_minutes,
+                    flown,
+                );
+            }
+        }
+    }
+    warnings
+}
+
+/// 规划时检查：把按 `route` 计划、`departure_time` 撤轮挡的飞行加入机组已飞的
+/// `history`，报告该飞行所在值勤超出的限制
+pub fn check_plan(
+    history: &[CrewFlight],
+    route: &FlightRoute,
+    departure_time: i64,
+    rules: &CrewRules,
+) -> ValidationReport {
+    let planned = CrewFlight {
+        off_block: departure_time,
+        block_minutes: i64::from(route.estimated_time),
+    };
+    let mut flights = history.to_vec();
+    flights.push(planned);
+    let planned_duty = duty_periods(&flights, rules)
+        .into_iter()
+        .find(|p| p.start <= planned.off_block && planned.on_block() <= p.end)
+        .map(|p| p.start);
+
+    let mut report = ValidationReport::default();
+    for warning in check(&flights, rules) {
+        if Some(warning.duty_start) == planned_duty {
+            report.warn(warning.rule(), warning.message());
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlan;
+
+    const HOUR: i64 = 3_600;
+
+    fn flight(off_block: i64, block_minutes: i64) -> CrewFlight {
+        CrewFlight {
+            off_block,
+            block_minutes,
+        }
+    }
+
+    #[test]
+    fn test_duty_periods() {
+        let rules = CrewRules::default();
+        // Two sectors with a 2 h turn, then a new duty after 20 h off
+        let flights = [
+            flight(10 * HOUR, 120),
+            flight(8 * HOUR, 90),
+            flight(40 * HOUR, 60),
+        ];
+        let periods = duty_periods(&flights, &rules);
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].start, 7 * HOUR);
+        assert_eq!(periods[0].end, 12 * HOUR + 30 * 60);
+        assert_eq!(periods[0].duty_minutes(), 330);
+        assert_eq!((periods[0].flight_minutes, periods[0].flights), (210, 2));
+        assert_eq!(periods[0].rest_before_minutes, None);
+        assert_eq!(periods[1].rest_before_minutes, Some(26 * 60 + 30));
+        assert!(check(&flights, &rules).is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let rules = CrewRules::far_135_single_pilot();
+        // 9 h of flying in one 11.5 h duty, then only 8 h rest
+        let flights = [
+            flight(HOUR, 300),
+            flight(7 * HOUR, 240),
+            flight(20 * HOUR + 30 * 60, 60),
+        ];
+        let warnings = check(&flights, &rules);
+        let limits: Vec<CrewLimit> = warnings.iter().map(|w| w.limit).collect();
+        assert_eq!(limits, vec![CrewLimit::FlightTime, CrewLimit::Rest]);
+        assert_eq!(warnings[0].actual_minutes, 540);
+        assert_eq!(
+            warnings[0].message(),
+            "Flight time in duty period of 9:00 exceeds the limit of 8:00"
+        );
+        assert_eq!(warnings[1].rule(), "crew_rest");
+
+        // 5 h a day for a week exceeds 34 h in 7 days
+        let week: Vec<CrewFlight> = (0..7).map(|day| flight(day * 24 * HOUR, 300)).collect();
+        let warnings = check(&week, &rules);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].limit, CrewLimit::Cumulative { window_days: 7 });
+        assert_eq!(warnings[0].actual_minutes, 35 * 60);
+    }
+
+    #[test]
+    fn test_check_plan() {
+        let route = FlightRoute {
+            plan: FlightPlan {
+                departure: "ZBAA".to_string(),
+                destination: "ZSSS".to_string(),
+                alternate: None,
+                cruise_altitude: 8000,
+                cruise_speed: 120,
+                route: vec![],
+                ..Default::default()
+            },
+            total_distance: 600.0,
+            estimated_time: 300,
+            waypoints: vec![],
+        };
+        let rules = CrewRules::default();
+        let history = [flight(0, 300)];
+
+        // Back-to-back with the morning flight: 10 h flying in one duty
+        let report = check_plan(&history, &route, 6 * HOUR, &rules);
+        let rules_hit: Vec<&str> = report.warnings.iter().map(|w| w.rule.as_str()).collect();
+        assert_eq!(rules_hit, vec!["crew_flight_time"]);
+
+        // Next day after full rest
+        assert!(check_plan(&history, &route, 24 * HOUR, &rules).warnings.is_empty());
+    }
+}
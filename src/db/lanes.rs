@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// 连接池通道
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Lane {
+    /// 界面等交互读写，可使用全部连接
+    Interactive,
+    /// 批量导入、恢复、清理等后台任务，最多占用 `Config::background_connections` 个连接
+    Background,
+}
+
+/// 单个通道的连接获取统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LaneStats {
+    /// 成功获取连接的次数
+    pub acquired: u64,
+    /// 超时未获取到连接的次数
+    pub timed_out: u64,
+    /// 当前借出的连接数
+    pub in_use: u32,
+    /// 累计等待时间（微秒）
+    pub total_wait_us: u64,
+    /// 单次最长等待时间（微秒）
+    pub max_wait_us: u64,
+}
+
+impl LaneStats {
+    /// Mean wait per acquisition
+    pub fn mean_wait(&self) -> Duration {
+        match self.acquired {
+            0 => Duration::ZERO,
+            n => Duration::from_micros(self.total_wait_us / n),
+        }
+    }
+}
+
+/// 连接池状态与各通道等待统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub max_connections: u32,
+    /// 池中已打开且空闲的连接数
+    pub idle_connections: u32,
+    /// 后台通道可同时占用的连接数
+    pub background_limit: u32,
+    pub interactive: LaneStats,
+    pub background: LaneStats,
+}
+
+/// Lock-free counters behind `LaneStats`
+#[derive(Debug, Default)]
+pub(crate) struct LaneCounters {
+    acquired: AtomicU64,
+    timed_out: AtomicU64,
+    in_use: AtomicU32,
+    total_wait_us: AtomicU64,
+    max_wait_us: AtomicU64,
+}
+
+impl LaneCounters {
+    pub fn record_acquired(&self, waited: Duration) {
+        let micros = waited.as_micros().min(u64::MAX as u128) as u64;
+        self.acquired.fetch_add(1, Ordering::Relaxed);
+        self.in_use.fetch_add(1, Ordering::Relaxed);
+        self.total_wait_us.fetch_add(micros, Ordering::Relaxed);
+        self.max_wait_us.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    pub fn record_timed_out(&self) {
+        self.timed_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_released(&self) {
+        self.in_use.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> LaneStats {
+        LaneStats {
+            acquired: self.acquired.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            in_use: self.in_use.load(Ordering::Relaxed),
+            total_wait_us: self.total_wait_us.load(Ordering::Relaxed),
+            max_wait_us: self.max_wait_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counting semaphore capping how many pooled connections a lane holds
+///
+/// r2d2 hands out connections first come, first served, so a lane can't be
+/// given priority inside the pool; capping the background lane instead
+/// keeps `max_connections - limit` connections free for interactive work.
+#[derive(Debug)]
+pub(crate) struct LaneGate {
+    limit: u32,
+    taken: Mutex<u32>,
+    released: Condvar,
+}
+
+impl LaneGate {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            taken: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Take a slot, waiting up to `timeout`; false on timeout
+    pub fn acquire(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut taken = self.taken.lock().unwrap_or_else(|e| e.into_inner());
+        while *taken >= self.limit {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            taken = self
+                .released
+                .wait_timeout(taken, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        *taken += 1;
+        true
+    }
+
+    pub fn release(&self) {
+        let mut taken = self.taken.lock().unwrap_or_else(|e| e.into_inner());
+        *taken = taken.saturating_sub(1);
+        self.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_lane_gate() {
+        let gate = Arc::new(LaneGate::new(1));
+        assert!(gate.acquire(Duration::from_millis(10)));
+        assert!(!gate.acquire(Duration::from_millis(10)));
+
+        let waiter = {
+            let gate = Arc::clone(&gate);
+            std::thread::spawn(move || gate.acquire(Duration::from_secs(5)))
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        gate.release();
+        assert!(waiter.join().unwrap());
+    }
+
+    #[test]
+    fn test_lane_counters() {
+        let counters = LaneCounters::default();
+        counters.record_acquired(Duration::from_micros(100));
+        counters.record_acquired(Duration::from_micros(300));
+        counters.record_released();
+        counters.record_timed_out();
+        let stats = counters.snapshot();
+        assert_eq!(
+            stats,
+            LaneStats {
+                acquired: 2,
+                timed_out: 1,
+                in_use: 1,
+                total_wait_us: 400,
+                max_wait_us: 300,
+            }
+        );
+        assert_eq!(stats.mean_wait(), Duration::from_micros(200));
+    }
+}
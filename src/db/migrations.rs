@@ -1,16 +1,30 @@
 use rusqlite::Connection;
-use crate::db::schema::{get_schema_sql, SCHEMA_VERSION};
+use crate::db::schema::{
+    get_migration_sql, get_user_migration_sql, SCHEMA_VERSION, USER_SCHEMA, USER_SCHEMA_VERSION,
+    USER_TABLES,
+};
 use crate::error::Result;
 
-/// 运行所有数据库迁移
-pub fn run_migrations(conn: &Connection) -> Result<()> {
+/// Change log kept in each database file (see `sync::changes`)
+const CHANGE_LOG: &str = "change_log";
+
+/// 运行所有数据库迁移（`now` 记为本次应用的迁移时间）
+pub fn run_migrations(conn: &Connection, now: i64) -> Result<()> {
+    // 旧版迁移依赖的函数，需在执行迁移前注册
+    crate::db::register_functions(conn)?;
+
     let current_version = get_current_version(conn)?;
     
     log::info!("当前数据库版本: {}", current_version);
     
     if current_version < SCHEMA_VERSION {
         log::info!("正在运行迁移，从版本 {} 到 {}", current_version, SCHEMA_VERSION);
-        apply_migrations(conn)?;
+        apply_migrations(conn, current_version, now)?;
+        // 版本 39 起 search_name、版本 42 起 geohash 为普通列，由程序计算
+        crate::repository::airport::refresh_search_names(conn)?;
+        crate::spatial::query::refresh_geohashes(conn)?;
+        // 版本 43 起变更记录由触发器写入 change_log
+        crate::sync::changes::track_changes(conn, "main")?;
     } else {
         log::info!("数据库模式已是最新");
     }
@@ -44,28 +58,232 @@ fn get_current_version(conn: &Connection) -> Result<i32> {
     }
 }
 
-/// 应用所有迁移
-fn apply_migrations(conn: &Connection) -> Result<()> {
+/// 按版本顺序应用尚未执行的迁移
+fn apply_migrations(conn: &Connection, current_version: i32, now: i64) -> Result<()> {
     let tx = conn.unchecked_transaction()?;
     
-    // 执行所有数据库模式 SQL 语句
-    for sql in get_schema_sql() {
-        tx.execute_batch(sql)?;
+    for version in (current_version + 1)..=SCHEMA_VERSION {
+        log::info!("应用迁移版本 {}", version);
+        for sql in get_migration_sql(version) {
+            tx.execute_batch(sql)?;
+        }
+        
+        // 记录迁移
+        tx.execute(
+            "INSERT OR REPLACE INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+            rusqlite::params![version, now],
+        )?;
     }
     
-    // 记录迁移
-    let now = chrono::Utc::now().timestamp();
-    tx.execute(
-        "INSERT OR REPLACE INTO schema_version (version, applied_at) VALUES (?1, ?2)",
-        rusqlite::params![SCHEMA_VERSION, now],
-    )?;
-    
     tx.commit()?;
     
     log::info!("迁移应用成功");
     Ok(())
 }
 
+/// 合并用户数据时与用户库已有行冲突、未能移入的行
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmergedRows {
+    pub table: String,
+    pub count: usize,
+    /// 用户库中保存这些行的表（`unmerged_<table>`，无约束）
+    pub kept_in: String,
+}
+
+/// 将用户数据表从主库移到附加的用户库
+///
+/// 用户库有自己的布局版本（`user_version`），先按 `get_user_migration_sql`
+/// 升级到 `USER_SCHEMA_VERSION`。迁移总是在主库中建表；附加了用户库时，用户库
+/// 中还没有的表连同索引和触发器一起复制过去。两边都有时（例如换了新的导航数据
+/// 文件），主库中的行按两边共有的列合并进用户库，与已有行冲突的行不覆盖，
+/// 而是保存到用户库的 `unmerged_<table>` 并在返回值中报告。最后删除主库中的
+/// 副本，未限定库名的查询即落到用户库。
+///
+/// 触发器只能写入自己所在的库，因此用户库另有一份 `change_log`，用户表的
+/// 变更记录在那里。
+pub fn separate_user_data(conn: &Connection) -> Result<Vec<UnmergedRows>> {
+    let attached: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_database_list WHERE name = ?1)",
+        [USER_SCHEMA],
+        |row| row.get(0),
+    )?;
+    if !attached {
+        return Ok(Vec::new());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    migrate_user_schema(&tx)?;
+    if !table_exists(&tx, USER_SCHEMA, CHANGE_LOG)? {
+        copy_schema(&tx, CHANGE_LOG)?;
+    }
+
+    let mut moved = Vec::new();
+    let mut unmerged = Vec::new();
+    for table in USER_TABLES {
+        if !table_exists(&tx, "main", table)? {
+            continue;
+        }
+        if !table_exists(&tx, USER_SCHEMA, table)? {
+            copy_schema(&tx, table)?;
+        }
+
+        let columns = shared_columns(&tx, table, USER_SCHEMA, table)?.join(", ");
+        let rowids: Vec<i64> = tx
+            .prepare(&format!("SELECT rowid FROM main.{} ORDER BY rowid", table))?
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+        let mut ignored = Vec::new();
+        {
+            let mut copy = tx.prepare(&format!(
+                "INSERT OR IGNORE INTO {schema}.{table} ({columns})
+                 SELECT {columns} FROM main.{table} WHERE rowid = ?1",
+                schema = USER_SCHEMA,
+            ))?;
+            for rowid in &rowids {
+                if copy.execute([rowid])? == 0 {
+                    ignored.push(*rowid);
+                }
+            }
+        }
+        log::info!(
+            "用户数据表 {} 已移至用户库（{} 行）",
+            table,
+            rowids.len() - ignored.len()
+        );
+        if !ignored.is_empty() {
+            let kept_in = keep_unmerged(&tx, table, &ignored)?;
+            log::warn!(
+                "用户数据表 {} 有 {} 行与用户库已有行冲突，已保存到 {}",
+                table,
+                ignored.len(),
+                kept_in
+            );
+            unmerged.push(UnmergedRows {
+                table: table.to_string(),
+                count: ignored.len(),
+                kept_in,
+            });
+        }
+        moved.push(*table);
+    }
+    // Children first, so dropping a parent doesn't trip its foreign keys
+    for table in moved.iter().rev() {
+        tx.execute_batch(&format!("DROP TABLE main.{}", table))?;
+        tx.execute("DELETE FROM main.change_log WHERE table_name = ?1", [table])?;
+    }
+    crate::sync::changes::track_changes(&tx, USER_SCHEMA)?;
+    tx.commit()?;
+    Ok(unmerged)
+}
+
+/// 将用户库升级到 `USER_SCHEMA_VERSION`
+///
+/// 还没有用户表的新库直接记为最新版本，表随后从主库按最新布局复制过来；
+/// 有表但未记录版本的旧库视为版本 1。
+fn migrate_user_schema(conn: &Connection) -> Result<()> {
+    let version: i32 = conn.query_row(
+        &format!("PRAGMA {}.user_version", USER_SCHEMA),
+        [],
+        |row| row.get(0),
+    )?;
+    if version >= USER_SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let mut populated = false;
+    for table in USER_TABLES {
+        populated |= table_exists(conn, USER_SCHEMA, table)?;
+    }
+    if populated {
+        for version in version.max(1) + 1..=USER_SCHEMA_VERSION {
+            log::info!("应用用户库迁移版本 {}", version);
+            for sql in get_user_migration_sql(version) {
+                conn.execute_batch(&sql.replace("{schema}", USER_SCHEMA))?;
+            }
+        }
+    }
+    conn.execute_batch(&format!(
+        "PRAGMA {}.user_version = {}",
+        USER_SCHEMA, USER_SCHEMA_VERSION
+    ))?;
+    Ok(())
+}
+
+/// Columns of `main.<table>` that `<schema>.<other>` also has, in the main order
+fn shared_columns(conn: &Connection, table: &str, schema: &str, other: &str) -> Result<Vec<String>> {
+    let columns = conn
+        .prepare(
+            "SELECT name FROM pragma_table_info(?1, 'main')
+             WHERE name IN (SELECT name FROM pragma_table_info(?2, ?3))
+             ORDER BY cid",
+        )?
+        .query_map([table, other, schema], |row| row.get(0))?
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(columns)
+}
+
+/// Keep the given rows of `main.<table>` in an unconstrained copy in the
+/// user-data database; returns the copy's name
+fn keep_unmerged(conn: &Connection, table: &str, rowids: &[i64]) -> Result<String> {
+    let kept_in = format!("unmerged_{}", table);
+    if !table_exists(conn, USER_SCHEMA, &kept_in)? {
+        conn.execute_batch(&format!(
+            "CREATE TABLE {}.{} AS SELECT * FROM main.{} WHERE 0",
+            USER_SCHEMA, kept_in, table
+        ))?;
+    }
+    let columns = shared_columns(conn, table, USER_SCHEMA, &kept_in)?.join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "INSERT INTO {}.{} ({columns}) SELECT {columns} FROM main.{} WHERE rowid = ?1",
+        USER_SCHEMA, kept_in, table
+    ))?;
+    for rowid in rowids {
+        stmt.execute([rowid])?;
+    }
+    Ok(kept_in)
+}
+
+/// Create `table` in the user-data database with the indexes and triggers
+/// it has in the main one
+fn copy_schema(conn: &Connection, table: &str) -> Result<()> {
+    let statements: Vec<String> = conn
+        .prepare(
+            "SELECT sql FROM main.sqlite_master WHERE tbl_name = ?1 AND sql IS NOT NULL
+             ORDER BY type <> 'table'",
+        )?
+        .query_map([table], |row| row.get(0))?
+        .collect::<std::result::Result<_, _>>()?;
+    for sql in statements {
+        let qualified = qualify(&sql, USER_SCHEMA).ok_or_else(|| {
+            crate::error::AeroBaseError::Unknown(format!("Unexpected schema SQL: {}", sql))
+        })?;
+        conn.execute_batch(&qualified)?;
+    }
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, schema: &str, table: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        &format!(
+            "SELECT EXISTS(SELECT 1 FROM {}.sqlite_master WHERE type = 'table' AND name = ?1)",
+            schema
+        ),
+        [table],
+        |row| row.get(0),
+    )?)
+}
+
+/// Put a stored `CREATE` statement into `schema`
+///
+/// SQLite normalizes the statements it keeps in `sqlite_master`, so the
+/// object name always directly follows the leading keywords.
+fn qualify(sql: &str, schema: &str) -> Option<String> {
+    ["CREATE TABLE ", "CREATE UNIQUE INDEX ", "CREATE INDEX ", "CREATE TRIGGER "]
+        .iter()
+        .find(|prefix| sql.starts_with(*prefix))
+        .map(|prefix| format!("{}{}.{}", prefix, schema, &sql[prefix.len()..]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,7 +296,7 @@ mod tests {
         let version_before = get_current_version(&conn).unwrap();
         assert_eq!(version_before, 0);
         
-        run_migrations(&conn).unwrap();
+        run_migrations(&conn, 0).unwrap();
         
         let version_after = get_current_version(&conn).unwrap();
         assert_eq!(version_after, SCHEMA_VERSION);
@@ -89,11 +307,337 @@ mod tests {
         let conn = Connection::open_in_memory().unwrap();
         
         // Run migrations twice
-        run_migrations(&conn).unwrap();
-        run_migrations(&conn).unwrap();
+        run_migrations(&conn, 0).unwrap();
+        run_migrations(&conn, 0).unwrap();
         
         // Should still be at the same version
         let version = get_current_version(&conn).unwrap();
         assert_eq!(version, SCHEMA_VERSION);
     }
+
+    #[test]
+    fn test_upgrade_from_v1_keeps_segments() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+
+        // Build a version 1 database with one airway segment
+        apply_migrations_to(&conn, 1);
+        conn.execute_batch(
+            "INSERT INTO waypoints (id, name, latitude, longitude, created_at)
+             VALUES ('WP1', 'AAA', 0.0, 0.0, 0), ('WP2', 'BBB', 1.0, 1.0, 0);
+             INSERT INTO airways (id, name, type, created_at) VALUES ('AW1', 'A1', 'HIGH', 0);
+             INSERT INTO airway_segments (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+             VALUES ('SEG1', 'AW1', 'WP1', 'WP2', 1, 0);",
+        )
+        .unwrap();
+
+        run_migrations(&conn, 0).unwrap();
+        assert_eq!(get_current_version(&conn).unwrap(), SCHEMA_VERSION);
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM airway_segments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Removing an endpoint an airway still uses is refused
+        assert!(conn.execute("DELETE FROM waypoints WHERE id = 'WP2'", []).is_err());
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM airway_segments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Removing the airway still takes its segments along
+        conn.execute("DELETE FROM airways WHERE id = 'AW1'", []).unwrap();
+        conn.execute("DELETE FROM waypoints WHERE id = 'WP2'", []).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM airway_segments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_search_name_becomes_plain_column() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::register_functions(&conn).unwrap();
+        apply_migrations_to(&conn, 38);
+        conn.execute(
+            "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+             VALUES ('AP1', 'ZBAA', '北京首都', 40.08, 116.58, 0)",
+            [],
+        )
+        .unwrap();
+
+        run_migrations(&conn, 0).unwrap();
+        let (hidden, search_name): (i64, String) = conn
+            .query_row(
+                "SELECT x.hidden, a.search_name
+                 FROM pragma_table_xinfo('airports') x, airports a
+                 WHERE x.name = 'search_name'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        // 0 is an ordinary column; generated ones report 2 or 3
+        assert_eq!(hidden, 0);
+        assert_eq!(search_name, "beijingshoudu");
+    }
+
+    #[test]
+    fn test_geohash_becomes_plain_column() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let conn = Connection::open(file.path()).unwrap();
+        crate::db::register_functions(&conn).unwrap();
+        apply_migrations_to(&conn, 41);
+        conn.execute_batch(
+            "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                VALUES ('AP1', 'ZBAA', 'Capital', 40.0801, 116.5846, 0);
+             INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                VALUES ('WP1', 'TEST', 39.9042, 116.4074, 'FIX', 0);",
+        )
+        .unwrap();
+        run_migrations(&conn, 0).unwrap();
+        drop(conn);
+
+        // Without the app's functions the tables still read and write
+        let conn = Connection::open(file.path()).unwrap();
+        for table in ["airports", "waypoints"] {
+            let hidden: i64 = conn
+                .query_row(
+                    "SELECT hidden FROM pragma_table_xinfo(?1) WHERE name = 'geohash'",
+                    [table],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(hidden, 0);
+        }
+        let hashes: Vec<String> = conn
+            .prepare("SELECT geohash FROM airports UNION ALL SELECT geohash FROM waypoints")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        let expected: Vec<String> = [(40.0801, 116.5846), (39.9042, 116.4074)]
+            .iter()
+            .map(|&(lat, lon)| {
+                crate::spatial::geohash::encode(crate::models::Coordinate::new(lat, lon), 7)
+            })
+            .collect();
+        assert_eq!(hashes, expected);
+        conn.execute_batch(
+            "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                VALUES ('WP2', 'TWO', 31.0, 121.0, 'FIX', 0);
+             SELECT * FROM airports;",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_secure_device_hardware_info_becomes_nullable() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::register_functions(&conn).unwrap();
+        apply_migrations_to(&conn, 40);
+        let insert = |id: &str, hardware_info: Option<&str>| {
+            conn.execute(
+                "INSERT INTO secure_devices (id, encrypted_fingerprint, encrypted_hardware_info,
+                     encrypted_aes_key, public_key_pem, created_at, last_seen)
+                 VALUES (?1, 'fp', ?2, 'key', 'pem', 0, 0)",
+                rusqlite::params![id, hardware_info],
+            )
+        };
+        insert("D1", Some("hw")).unwrap();
+        assert!(insert("D2", None).is_err());
+
+        run_migrations(&conn, 0).unwrap();
+        insert("D2", None).unwrap();
+        let hardware_info: Vec<Option<String>> = conn
+            .prepare("SELECT encrypted_hardware_info FROM secure_devices ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(hardware_info, vec![Some("hw".to_string()), None]);
+    }
+
+    #[test]
+    fn test_natural_keys_fold_duplicates() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys=ON;").unwrap();
+        crate::db::register_functions(&conn).unwrap();
+        apply_migrations_to(&conn, 36);
+        conn.execute_batch(
+            "INSERT INTO waypoints (id, name, latitude, longitude, region, created_at) VALUES
+                ('OLD', 'AAA', 0.0, 0.0, 'ZB', 1), ('NEW', 'AAA', 0.0, 0.0, 'ZB', 2),
+                ('BBB', 'BBB', 1.0, 1.0, 'ZB', 0),
+                ('N1', 'CCC', 2.0, 2.0, NULL, 0), ('N2', 'CCC', 2.0, 2.0, NULL, 0);
+             INSERT INTO navaids (id, name, type, latitude, longitude, region, created_at) VALUES
+                ('V1', 'PEK', 'VOR', 0.0, 0.0, 'ZB', 0), ('V2', 'PEK', 'VOR', 0.0, 0.0, 'ZB', 1),
+                ('V3', 'PEK', 'NDB', 0.0, 0.0, 'ZB', 1);
+             INSERT INTO airways (id, name, type, created_at) VALUES ('AW1', 'A1', 'HIGH', 0);
+             INSERT INTO airway_segments
+                (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+                VALUES ('SEG1', 'AW1', 'NEW', 'BBB', 1, 0), ('SEG2', 'AW1', 'BBB', 'NEW', 1, 1);",
+        )
+        .unwrap();
+
+        run_migrations(&conn, 0).unwrap();
+        let ids = |sql: &str| -> Vec<String> {
+            let mut stmt = conn.prepare(sql).unwrap();
+            stmt.query_map([], |row| row.get(0)).unwrap().map(|r| r.unwrap()).collect()
+        };
+        assert_eq!(ids("SELECT id FROM waypoints ORDER BY id"), vec!["BBB", "N1", "N2", "OLD"]);
+        assert_eq!(ids("SELECT id FROM navaids ORDER BY id"), vec!["V1", "V3"]);
+        // The segment followed its waypoint to the survivor; its later twin
+        // at the same sequence was dropped
+        let segments = ids("SELECT id || ':' || from_waypoint_id FROM airway_segments");
+        assert_eq!(segments, vec!["SEG1:OLD"]);
+
+        assert!(conn
+            .execute(
+                "INSERT INTO waypoints (id, name, latitude, longitude, region, created_at)
+                 VALUES ('DUP', 'BBB', 0.0, 0.0, 'ZB', 0)",
+                [],
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_separate_user_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.db");
+        let open = |name: &str| {
+            let conn = Connection::open(dir.path().join(name)).unwrap();
+            conn.execute("ATTACH DATABASE ?1 AS user", [user.to_str().unwrap()]).unwrap();
+            run_migrations(&conn, 0).unwrap();
+            separate_user_data(&conn).unwrap();
+            conn
+        };
+        let count = |conn: &Connection, sql: &str| -> i64 {
+            conn.query_row(sql, [], |row| row.get(0)).unwrap()
+        };
+
+        let conn = open("navdata-2401.db");
+        conn.execute_batch(
+            "INSERT INTO devices (id, fingerprint, created_at, last_seen) VALUES ('D1', 'fp', 0, 0);
+             INSERT INTO flight_plans (id, device_id, departure, destination, plan_json, created_at)
+             VALUES ('P1', 'D1', 'ZBAA', 'ZSSS', '{}', 5);",
+        )
+        .unwrap();
+        assert!(!table_exists(&conn, "main", "flight_plans").unwrap());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM user.flight_plans"), 1);
+        // Triggers and foreign keys moved along with the tables
+        assert_eq!(count(&conn, "SELECT updated_at FROM flight_plans"), 5);
+        conn.execute("DELETE FROM devices", []).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(device_id) FROM flight_plans"), 0);
+        drop(conn);
+
+        // A new navdata file keeps the user's plans
+        let conn = open("navdata-2402.db");
+        assert!(!table_exists(&conn, "main", "flight_plans").unwrap());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM flight_plans"), 1);
+        assert!(table_exists(&conn, "main", "airports").unwrap());
+        assert!(!table_exists(&conn, USER_SCHEMA, "airports").unwrap());
+        assert_eq!(count(&conn, "PRAGMA user.user_version"), USER_SCHEMA_VERSION as i64);
+    }
+
+    #[test]
+    fn test_separate_reports_unmerged_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.db");
+        let attach = |conn: &Connection| {
+            conn.execute("ATTACH DATABASE ?1 AS user", [user.to_str().unwrap()]).unwrap();
+        };
+
+        let conn = Connection::open(dir.path().join("navdata-2401.db")).unwrap();
+        attach(&conn);
+        run_migrations(&conn, 0).unwrap();
+        assert!(separate_user_data(&conn).unwrap().is_empty());
+        conn.execute(
+            "INSERT INTO favorites (id, profile_id, item_type, item_id, created_at)
+             VALUES ('F1', 'default', 'AIRPORT', 'ZBAA', 0)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        // Favorites saved while the user database wasn't attached
+        let conn = Connection::open(dir.path().join("navdata-2402.db")).unwrap();
+        run_migrations(&conn, 0).unwrap();
+        conn.execute_batch(
+            "INSERT INTO favorites (id, profile_id, item_type, item_id, created_at)
+             VALUES ('F1', 'default', 'AIRPORT', 'ZSSS', 1), ('F2', 'default', 'AIRPORT', 'ZGGG', 1);",
+        )
+        .unwrap();
+        attach(&conn);
+        let unmerged = separate_user_data(&conn).unwrap();
+        assert_eq!(
+            unmerged,
+            vec![UnmergedRows {
+                table: "favorites".to_string(),
+                count: 1,
+                kept_in: "unmerged_favorites".to_string(),
+            }]
+        );
+
+        let item = |sql: &str| -> String { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(item("SELECT item_id FROM favorites WHERE id = 'F1'"), "ZBAA");
+        assert_eq!(item("SELECT item_id FROM favorites WHERE id = 'F2'"), "ZGGG");
+        assert_eq!(item("SELECT item_id FROM user.unmerged_favorites WHERE id = 'F1'"), "ZSSS");
+    }
+
+    #[test]
+    fn test_unversioned_user_database_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.db");
+
+        // A user database split off before version 41, from before the user
+        // database carried a version
+        let legacy = Connection::open(&user).unwrap();
+        legacy
+            .execute_batch(
+                "CREATE TABLE secure_devices (
+                    id TEXT PRIMARY KEY,
+                    encrypted_fingerprint TEXT NOT NULL,
+                    encrypted_hardware_info TEXT NOT NULL,
+                    encrypted_aes_key TEXT NOT NULL,
+                    public_key_pem TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    last_seen INTEGER NOT NULL
+                );
+                INSERT INTO secure_devices VALUES ('D0', 'fp', 'hw', 'key', 'pem', 0, 0);",
+            )
+            .unwrap();
+        drop(legacy);
+        let insert = |conn: &Connection, id: &str| {
+            conn.execute(
+                "INSERT INTO secure_devices (id, encrypted_fingerprint, encrypted_hardware_info,
+                    encrypted_aes_key, public_key_pem, created_at, last_seen)
+                 VALUES (?1, 'fp', NULL, 'key', 'pem', 0, 0)",
+                [id],
+            )
+        };
+
+        let conn = Connection::open(dir.path().join("navdata-2402.db")).unwrap();
+        conn.execute("ATTACH DATABASE ?1 AS user", [user.to_str().unwrap()]).unwrap();
+        run_migrations(&conn, 0).unwrap();
+        separate_user_data(&conn).unwrap();
+        insert(&conn, "D1").unwrap();
+        let count = |sql: &str| -> i32 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM user.secure_devices"), 2);
+        assert_eq!(count("PRAGMA user.user_version"), USER_SCHEMA_VERSION);
+    }
+
+    fn apply_migrations_to(conn: &Connection, version: i32) {
+        for v in 1..=version {
+            for sql in get_migration_sql(v) {
+                conn.execute_batch(sql).unwrap();
+            }
+            conn.execute(
+                "INSERT INTO schema_version (version, applied_at) VALUES (?1, 0)",
+                [v],
+            )
+            .unwrap();
+        }
+    }
 }
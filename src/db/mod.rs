@@ -1,19 +1,127 @@
 pub mod connection;
+pub mod lanes;
 pub mod migrations;
+pub mod query;
+pub mod recovery;
 pub mod schema;
+pub mod slow_query;
 
+use crate::clock::{self, Clock};
 use crate::error::{AeroBaseError, Result};
+use crate::events::{Event, EventBus};
+use crate::ids::IdStrategy;
 use crate::Config;
-use r2d2::Pool;
+use lanes::{Lane, LaneCounters, LaneGate, PoolStats};
+use slow_query::SlowQueryLog;
+use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::Connection;
-use std::path::Path;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Instant;
+
+pub use query::Query;
 
 pub type DbPool = Pool<SqliteConnectionManager>;
 
+/// 外键约束违规记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForeignKeyViolation {
+    /// 包含违规行的子表
+    pub table: String,
+    /// 违规行的 rowid
+    pub rowid: Option<i64>,
+    /// 被引用的父表
+    pub parent: String,
+    /// 子表中的外键列
+    pub column: Option<String>,
+    /// 父表中被引用的列
+    pub parent_column: Option<String>,
+}
+
+/// 注册旧版 schema 依赖的 SQL 函数（geohash 供迁移 13 使用，search_key 供迁移 28 使用）
+pub fn register_functions(conn: &Connection) -> rusqlite::Result<()> {
+    use rusqlite::functions::FunctionFlags;
+
+    conn.create_scalar_function(
+        "geohash",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let latitude: Option<f64> = ctx.get(0)?;
+            let longitude: Option<f64> = ctx.get(1)?;
+            Ok(latitude.zip(longitude).map(|(lat, lon)| {
+                crate::spatial::geohash::encode(
+                    crate::models::Coordinate::new(lat, lon),
+                    crate::spatial::geohash::GEOHASH_PRECISION,
+                )
+            }))
+        },
+    )?;
+
+    conn.create_scalar_function(
+        "search_key",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let text: Option<String> = ctx.get(0)?;
+            Ok(text.map(|text| crate::navdata::text::search_key(&text)))
+        },
+    )
+}
+
+/// 从连接池借出的连接，归还时更新所属通道的统计
+pub struct DbConnection {
+    conn: PooledConnection<SqliteConnectionManager>,
+    lane: Lane,
+    lanes: Arc<PoolLanes>,
+}
+
+impl Deref for DbConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl DerefMut for DbConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+}
+
+impl Drop for DbConnection {
+    fn drop(&mut self) {
+        match self.lane {
+            Lane::Interactive => self.lanes.interactive.record_released(),
+            Lane::Background => {
+                self.lanes.background.record_released();
+                self.lanes.background_gate.release();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PoolLanes {
+    background_gate: LaneGate,
+    interactive: LaneCounters,
+    background: LaneCounters,
+}
+
 /// 数据库管理器
 pub struct Database {
     pool: DbPool,
+    user_db_path: Option<PathBuf>,
+    lanes: Arc<PoolLanes>,
+    ids: IdStrategy,
+    clock: Arc<dyn Clock>,
+    slow_queries: Arc<SlowQueryLog>,
+    /// 弱引用：分发器经由数据库持有总线时，总线仍能随所有者释放而关闭
+    events: Weak<EventBus>,
 }
 
 impl Database {
@@ -22,11 +130,30 @@ impl Database {
         let db_path = config.db_path.clone();
         let enable_wal = config.enable_wal;
         let pool_size = config.pool_size;
+        let user_db_path = config.user_db_path.clone();
+        let attach = user_db_path.clone();
         
         let manager = SqliteConnectionManager::file(db_path)
             .with_init(move |conn| {
+                // 用户数据放在单独的文件中，每个连接都附加
+                if let Some(path) = &attach {
+                    conn.execute(
+                        &format!("ATTACH DATABASE ?1 AS {}", schema::USER_SCHEMA),
+                        [path.to_string_lossy()],
+                    )?;
+                    conn.execute_batch(&format!(
+                        "PRAGMA {}.synchronous=NORMAL;",
+                        schema::USER_SCHEMA
+                    ))?;
+                }
                 if enable_wal {
                     conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+                    if attach.is_some() {
+                        conn.execute_batch(&format!(
+                            "PRAGMA {}.journal_mode=WAL;",
+                            schema::USER_SCHEMA
+                        ))?;
+                    }
                 }
                 conn.execute_batch(
                     "PRAGMA foreign_keys=ON;
@@ -34,28 +161,195 @@ impl Database {
                      PRAGMA cache_size=-64000;
                      PRAGMA temp_store=MEMORY;",
                 )?;
-                Ok(())
+                register_functions(conn)
             });
 
+        let clock = clock::system();
+        let slow_queries = Arc::new(SlowQueryLog::new(Arc::clone(&clock)));
         let pool = Pool::builder()
             .max_size(pool_size)
+            .connection_customizer(Box::new(slow_query::ProfileHook(Arc::clone(&slow_queries))))
             .build(manager)?;
 
-        Ok(Self { pool })
+        // The background lane always gets one connection, and leaves at
+        // least one to interactive work when the pool has room for it
+        let background_limit = config
+            .background_connections
+            .min(pool_size.saturating_sub(1))
+            .max(1);
+        let lanes = Arc::new(PoolLanes {
+            background_gate: LaneGate::new(background_limit),
+            interactive: LaneCounters::default(),
+            background: LaneCounters::default(),
+        });
+
+        Ok(Self {
+            pool,
+            user_db_path,
+            lanes,
+            ids: config.ids,
+            clock,
+            slow_queries,
+            events: Weak::new(),
+        })
+    }
+
+    /// 设置记录时间戳与主键时间分量所用的时钟（默认系统时钟）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.slow_queries.set_clock(Arc::clone(&clock));
+        self.clock = clock;
+        self
+    }
+
+    /// 设置同步完成、导航数据周期切换等数据层事件发布到的总线（默认不发布）
+    pub fn with_events(mut self, events: &Arc<EventBus>) -> Self {
+        self.events = Arc::downgrade(events);
+        self
+    }
+
+    /// 向 `with_events` 设置的总线发布事件；总线未设置或已释放时忽略
+    pub(crate) fn publish(&self, event: Event) {
+        if let Some(events) = self.events.upgrade() {
+            events.publish(event);
+        }
+    }
+
+    /// 本实例的时钟
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// 本实例的慢查询日志（阈值只对本实例的连接生效）
+    pub fn slow_query_log(&self) -> &SlowQueryLog {
+        &self.slow_queries
+    }
+
+    /// 本实例新记录主键的生成方式（来自 `Config::ids`）
+    pub fn id_strategy(&self) -> IdStrategy {
+        self.ids
+    }
+
+    /// 按本实例的主键生成方式生成新主键
+    pub fn new_id(&self) -> String {
+        self.ids.generate_at(self.clock.now().timestamp_millis())
+    }
+
+    /// 附加的用户数据库文件（未单独存放用户数据时为 None）
+    pub fn user_db_path(&self) -> Option<&Path> {
+        self.user_db_path.as_deref()
+    }
+
+    /// 从连接池获取数据库连接（交互通道）
+    pub fn get_conn(&self) -> Result<DbConnection> {
+        self.checkout(Lane::Interactive, Instant::now())
+    }
+
+    /// 为批量导入、恢复等后台任务获取连接
+    ///
+    /// 后台通道最多同时占用 `Config::background_connections` 个连接，其余连接
+    /// 留给界面读取，长时间导入不会让交互请求排队。
+    pub fn get_background_conn(&self) -> Result<DbConnection> {
+        let started = Instant::now();
+        if !self.lanes.background_gate.acquire(self.pool.connection_timeout()) {
+            self.lanes.background.record_timed_out();
+            return Err(AeroBaseError::Pool(
+                "Timed out waiting for a background connection".to_string(),
+            ));
+        }
+        self.checkout(Lane::Background, started).inspect_err(|_| {
+            self.lanes.background_gate.release();
+        })
+    }
+
+    fn checkout(&self, lane: Lane, started: Instant) -> Result<DbConnection> {
+        let counters = match lane {
+            Lane::Interactive => &self.lanes.interactive,
+            Lane::Background => &self.lanes.background,
+        };
+        match self.pool.get() {
+            Ok(conn) => {
+                counters.record_acquired(started.elapsed());
+                Ok(DbConnection {
+                    conn,
+                    lane,
+                    lanes: Arc::clone(&self.lanes),
+                })
+            }
+            Err(e) => {
+                counters.record_timed_out();
+                Err(AeroBaseError::Pool(e.to_string()))
+            }
+        }
+    }
+
+    /// 连接池状态与各通道的等待统计
+    pub fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            max_connections: self.pool.max_size(),
+            idle_connections: self.pool.state().idle_connections,
+            background_limit: self.lanes.background_gate.limit(),
+            interactive: self.lanes.interactive.snapshot(),
+            background: self.lanes.background.snapshot(),
+        }
     }
 
-    /// 从连接池获取数据库连接
-    pub fn get_conn(&self) -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
-        self.pool.get().map_err(|e| AeroBaseError::Pool(e.to_string()))
+    /// 连接池的最大连接数
+    pub fn max_connections(&self) -> u32 {
+        self.pool.max_size()
     }
 
     /// 运行数据库迁移
+    ///
+    /// 附加了用户库时，合并用户数据中未能移入的行会记录警告，
+    /// 详见 `migrations::separate_user_data`。
     pub fn migrate(&self) -> Result<()> {
         let conn = self.get_conn()?;
-        migrations::run_migrations(&conn)?;
+        migrations::run_migrations(&conn, self.clock.timestamp())?;
+        migrations::separate_user_data(&conn)?;
         Ok(())
     }
 
+    /// 检查所有外键约束，返回违规行
+    pub fn check_foreign_keys(&self) -> Result<Vec<ForeignKeyViolation>> {
+        let conn = self.get_conn()?;
+
+        let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+        let raw: Vec<(String, Option<i64>, String, i64)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut violations = Vec::with_capacity(raw.len());
+        for (table, rowid, parent, fk_id) in raw {
+            // foreign_key_list 的 id 与 foreign_key_check 的 fkid 对应
+            let columns: Option<(String, Option<String>)> = conn
+                .query_row(
+                    "SELECT \"from\", \"to\" FROM pragma_foreign_key_list(?1) WHERE id = ?2",
+                    rusqlite::params![&table, fk_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+
+            let (column, parent_column) = match columns {
+                Some((from, to)) => (Some(from), to),
+                None => (None, None),
+            };
+
+            violations.push(ForeignKeyViolation {
+                table,
+                rowid,
+                parent,
+                column,
+                parent_column,
+            });
+        }
+
+        if !violations.is_empty() {
+            log::warn!("发现 {} 条外键约束违规", violations.len());
+        }
+
+        Ok(violations)
+    }
+
     /// 检查数据库是否存在且有效
     pub fn exists(path: &Path) -> bool {
         if !path.exists() {
@@ -79,6 +373,7 @@ mod tests {
             db_path: temp_file.path().to_path_buf(),
             enable_wal: true,
             pool_size: 2,
+            ..Default::default()
         };
 
         let db = Database::new(&config).unwrap();
@@ -94,9 +389,190 @@ mod tests {
             db_path: path.to_path_buf(),
             enable_wal: false,
             pool_size: 1,
+            ..Default::default()
         };
 
         let _db = Database::new(&config).unwrap();
         assert!(Database::exists(path));
     }
+
+    #[test]
+    fn test_check_foreign_keys() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        assert!(db.check_foreign_keys().unwrap().is_empty());
+
+        // 绕过约束写入一条孤立的边界点
+        let conn = db.get_conn().unwrap();
+        conn.execute_batch(
+            "PRAGMA foreign_keys=OFF;
+             INSERT INTO airspace_boundaries (id, airspace_id, latitude, longitude, sequence)
+             VALUES ('B1', 'MISSING', 0.0, 0.0, 1);
+             PRAGMA foreign_keys=ON;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let violations = db.check_foreign_keys().unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].table, "airspace_boundaries");
+        assert_eq!(violations[0].parent, "airspaces");
+        assert_eq!(violations[0].column.as_deref(), Some("airspace_id"));
+        assert_eq!(violations[0].parent_column.as_deref(), Some("id"));
+    }
+
+    #[test]
+    fn test_user_db_attached() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            db_path: dir.path().join("navdata.db"),
+            user_db_path: Some(dir.path().join("user.db")),
+            enable_wal: true,
+            pool_size: 2,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        assert_eq!(db.user_db_path(), config.user_db_path.as_deref());
+
+        let first = db.get_conn().unwrap();
+        first
+            .execute(
+                "INSERT INTO favorites (id, profile_id, item_type, item_id, created_at)
+                 VALUES ('F1', 'default', 'AIRPORT', 'ZBAA', 0)",
+                [],
+            )
+            .unwrap();
+        // Every pooled connection sees the user database
+        let second = db.get_conn().unwrap();
+        let count: i64 = second
+            .query_row("SELECT COUNT(*) FROM user.favorites", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        drop((first, second));
+
+        // Replacing the navdata file leaves the favorite in place
+        drop(db);
+        std::fs::remove_file(&config.db_path).unwrap();
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        let count: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_clock_drives_ids_and_timestamps() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ids: IdStrategy::Ulid,
+            ..Default::default()
+        };
+        let clock = Arc::new(clock::MockClock::at_timestamp(1_700_000_000));
+        let db = Arc::new(Database::new(&config).unwrap().with_clock(clock.clone()));
+        db.migrate().unwrap();
+
+        let applied_at: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT MAX(applied_at) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied_at, 1_700_000_000);
+        assert_eq!(db.new_id()[..10], IdStrategy::Ulid.generate_at(1_700_000_000_000)[..10]);
+
+        clock.advance(chrono::TimeDelta::hours(1));
+        let plan = crate::flight::FlightPlanBuilder::new()
+            .departure("ZBAA")
+            .destination("ZSSS")
+            .cruise_altitude(29000)
+            .cruise_speed(450)
+            .build()
+            .unwrap();
+        let stored = crate::flight::repository::FlightPlanRepository::new(Arc::clone(&db))
+            .unwrap()
+            .insert(&plan, None, None)
+            .unwrap();
+        assert_eq!((stored.created_at, stored.updated_at), (1_700_003_600, 1_700_003_600));
+
+        // Components and importers built on the database follow its clock
+        let device = crate::device::DeviceManager::new(Arc::clone(&db))
+            .unwrap()
+            .get_or_create_fingerprint()
+            .unwrap();
+        assert_eq!(device.created_at, 1_700_003_600);
+        crate::navdata::ed269::import(&db, crate::navdata::ed269::tests::SAMPLE).unwrap();
+        let zones_created_at: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT MIN(created_at) FROM uas_zones", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(zones_created_at, 1_700_003_600);
+    }
+
+    #[test]
+    fn test_background_lane() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: true,
+            pool_size: 3,
+            background_connections: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        assert_eq!(db.pool_stats().background_limit, 1);
+
+        // An import holds the background lane; interactive reads still get
+        // the remaining connections at once
+        let import = db.get_background_conn().unwrap();
+        let reads = (db.get_conn().unwrap(), db.get_conn().unwrap());
+        drop(reads);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let waiter = {
+            let db = Arc::clone(&db);
+            std::thread::spawn(move || {
+                let conn = db.get_background_conn().unwrap();
+                sender.send(()).unwrap();
+                drop(conn);
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(db.pool_stats().background.in_use, 1);
+        drop(import);
+        waiter.join().unwrap();
+
+        let stats = db.pool_stats();
+        assert_eq!(stats.interactive.acquired, 2);
+        assert_eq!(stats.interactive.in_use, 0);
+        assert_eq!(stats.background.acquired, 2);
+        assert_eq!(stats.background.in_use, 0);
+        assert!(stats.background.max_wait_us >= 40_000);
+
+        // A single-connection pool shares it between both lanes
+        let config = Config {
+            pool_size: 1,
+            background_connections: 4,
+            ..config
+        };
+        let db = Database::new(&config).unwrap();
+        assert_eq!(db.pool_stats().background_limit, 1);
+        drop(db.get_background_conn().unwrap());
+        assert!(db.get_conn().is_ok());
+    }
 }
@@ -0,0 +1,782 @@
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::{
+    airport::Airport,
+    navaid::{Navaid, NavaidType},
+    waypoint::{Waypoint, WaypointType, WaypointUsage},
+    Coordinate,
+};
+use crate::navdata::text::search_key;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// Bounding box as (south-west, north-east) corners, as returned by
+/// `spatial::geometry::bounding_box`
+pub type BoundingBox = (Coordinate, Coordinate);
+
+/// Canonical form of an airport code: trimmed and upper case
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// Levenshtein distance between two idents, counted in characters
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Subquery resolving `?1`, a normalized airport code, to an airport id
+///
+/// An ICAO match wins over an alias, and an alias over an IATA code.
+pub(crate) const AIRPORT_ID_BY_CODE: &str = "(SELECT id FROM (
+        SELECT id, 0 AS rank FROM airports WHERE icao = ?1
+        UNION ALL SELECT airport_id, 1 FROM airport_aliases WHERE code = ?1
+        UNION ALL SELECT id, 2 FROM airports WHERE iata = ?1
+    ) ORDER BY rank LIMIT 1)";
+
+/// Resolve an ICAO code, IATA code or alias to an airport id
+pub fn resolve_airport_id(conn: &Connection, code: &str) -> Result<Option<String>> {
+    let id = conn
+        .query_row(
+            &format!("SELECT {}", AIRPORT_ID_BY_CODE),
+            [normalize_code(code)],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?;
+    Ok(id.flatten())
+}
+
+/// Near misses offered for an airport code that doesn't resolve
+pub const MAX_AIRPORT_SUGGESTIONS: usize = 5;
+
+/// Outcome of an airport lookup that offers "did you mean" suggestions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LookupResult {
+    Found(Airport),
+    /// No airport has the code; airports whose ICAO or IATA code is one
+    /// edit away, closest first
+    Missing {
+        code: String,
+        suggestions: Vec<Airport>,
+    },
+}
+
+impl LookupResult {
+    pub fn airport(&self) -> Option<&Airport> {
+        match self {
+            LookupResult::Found(airport) => Some(airport),
+            LookupResult::Missing { .. } => None,
+        }
+    }
+
+    pub fn into_airport(self) -> Option<Airport> {
+        match self {
+            LookupResult::Found(airport) => Some(airport),
+            LookupResult::Missing { .. } => None,
+        }
+    }
+
+    pub fn suggestions(&self) -> &[Airport] {
+        match self {
+            LookupResult::Found(_) => &[],
+            LookupResult::Missing { suggestions, .. } => suggestions,
+        }
+    }
+
+    /// `"did you mean ZSPD (Pudong) or ZSSS (Hongqiao)?"`, when there are
+    /// suggestions
+    pub fn did_you_mean(&self) -> Option<String> {
+        let names: Vec<String> = self
+            .suggestions()
+            .iter()
+            .map(|airport| format!("{} ({})", airport.icao, airport.name))
+            .collect();
+        let (last, rest) = names.split_last()?;
+        Some(if rest.is_empty() {
+            format!("did you mean {}?", last)
+        } else {
+            format!("did you mean {} or {}?", rest.join(", "), last)
+        })
+    }
+}
+
+/// Look up an airport by ICAO code, IATA code or alias, with near misses
+/// when nothing matches
+///
+/// A near miss is an airport whose ICAO or IATA code is within one edit of
+/// `code`, so "ZSPX" suggests ZSPD and "PKE" suggests PEK's airport.
+pub fn lookup_airport(conn: &Connection, code: &str) -> Result<LookupResult> {
+    let code = normalize_code(code);
+    let airport = conn
+        .query_row(
+            &format!("SELECT {} FROM airports WHERE id = {}", AIRPORT_COLUMNS, AIRPORT_ID_BY_CODE),
+            [&code],
+            airport_from_row,
+        )
+        .optional()?;
+    if let Some(airport) = airport {
+        return Ok(LookupResult::Found(airport));
+    }
+    let mut suggestions = near_miss_airports(conn, &code)?;
+    suggestions.truncate(MAX_AIRPORT_SUGGESTIONS);
+    Ok(LookupResult::Missing { code, suggestions })
+}
+
+/// All airports whose ICAO or IATA code is within one edit of `code`, a
+/// normalized code; exact matches first, then by ICAO code
+pub(crate) fn near_miss_airports(conn: &Connection, code: &str) -> Result<Vec<Airport>> {
+    let length = code.chars().count() as i64;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM airports
+         WHERE length(icao) BETWEEN ?1 AND ?2 OR length(iata) BETWEEN ?1 AND ?2",
+        AIRPORT_COLUMNS
+    ))?;
+    let airports = stmt
+        .query_map([length - 1, length + 1], airport_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut near: Vec<(usize, Airport)> = airports
+        .into_iter()
+        .filter_map(|airport| {
+            let iata = airport.iata.as_deref().map_or(usize::MAX, |iata| edit_distance(code, iata));
+            let edits = edit_distance(code, &airport.icao).min(iata);
+            (edits <= 1).then_some((edits, airport))
+        })
+        .collect();
+    near.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.icao.cmp(&b.1.icao)));
+    Ok(near.into_iter().map(|(_, airport)| airport).collect())
+}
+
+/// Entry point for the named query builders
+///
+/// Builders only accept values, never SQL fragments: every filter is
+/// compiled to a fixed clause with bound parameters.
+pub struct Query;
+
+impl Query {
+    /// Query the `airports` table
+    pub fn airports() -> AirportQuery {
+        AirportQuery::default()
+    }
+
+    /// Query the `waypoints` table
+    pub fn waypoints() -> WaypointQuery {
+        WaypointQuery::default()
+    }
+
+    /// Query the `navaids` table
+    pub fn navaids() -> NavaidQuery {
+        NavaidQuery::default()
+    }
+}
+
+/// Accumulated WHERE clauses and their parameters
+#[derive(Debug, Clone, Default)]
+struct Filters {
+    clauses: Vec<&'static str>,
+    params: Vec<Value>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+impl Filters {
+    fn push(&mut self, clause: &'static str, params: Vec<Value>) {
+        self.clauses.push(clause);
+        self.params.extend(params);
+    }
+
+    fn within(&mut self, bbox: BoundingBox) {
+        let (min, max) = bbox;
+        self.push(
+            "latitude BETWEEN ? AND ? AND longitude BETWEEN ? AND ?",
+            vec![
+                Value::Real(min.latitude),
+                Value::Real(max.latitude),
+                Value::Real(min.longitude),
+                Value::Real(max.longitude),
+            ],
+        );
+    }
+
+    fn modified_since(&mut self, timestamp: i64) {
+        self.push("updated_at >= ?", vec![Value::Integer(timestamp)]);
+    }
+
+    fn compile(&self, select: &str, order_by: &str) -> (String, Vec<Value>) {
+        let mut sql = select.to_string();
+        if !self.clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.clauses.join(" AND "));
+        }
+        if !order_by.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(order_by);
+        }
+
+        let mut params = self.params.clone();
+        match (self.limit, self.offset) {
+            (Some(limit), offset) => {
+                sql.push_str(" LIMIT ? OFFSET ?");
+                params.push(Value::Integer(limit as i64));
+                params.push(Value::Integer(offset.unwrap_or(0) as i64));
+            }
+            (None, Some(offset)) => {
+                sql.push_str(" LIMIT -1 OFFSET ?");
+                params.push(Value::Integer(offset as i64));
+            }
+            (None, None) => {}
+        }
+
+        (sql, params)
+    }
+
+    fn count_sql(&self, table: &str) -> (String, Vec<Value>) {
+        let mut sql = format!("SELECT COUNT(*) FROM {}", table);
+        if !self.clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&self.clauses.join(" AND "));
+        }
+        (sql, self.params.clone())
+    }
+}
+
+/// Escape `%`, `_` and `\` so user input is matched literally by LIKE
+pub(crate) fn like_prefix(prefix: &str) -> Value {
+    let mut escaped = String::with_capacity(prefix.len() + 1);
+    for c in prefix.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.push('%');
+    Value::Text(escaped)
+}
+
+pub(crate) fn like_contains(needle: &str) -> Value {
+    match like_prefix(needle) {
+        Value::Text(s) => Value::Text(format!("%{}", s)),
+        other => other,
+    }
+}
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+fn fetch_all<T>(
+    db: &Database,
+    sql: &str,
+    params: Vec<Value>,
+    map: fn(&Row) -> rusqlite::Result<T>,
+) -> Result<Vec<T>> {
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params), map)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn count_all(db: &Database, sql: &str, params: Vec<Value>) -> Result<i64> {
+    let conn = db.get_conn()?;
+    let count = conn.query_row(sql, rusqlite::params_from_iter(params), |row| row.get(0))?;
+    Ok(count)
+}
+
+pub(crate) const AIRPORT_COLUMNS: &str =
+    "id, icao, iata, name, latitude, longitude, elevation, country, region, created_at, \
+     transition_altitude, transition_level, magnetic_variation";
+
+pub(crate) fn airport_from_row(row: &Row) -> rusqlite::Result<Airport> {
+    Ok(Airport {
+        id: row.get(0)?,
+        icao: row.get(1)?,
+        iata: row.get(2)?,
+        name: row.get(3)?,
+        coordinate: Coordinate::new(row.get(4)?, row.get(5)?),
+        elevation: row.get(6)?,
+        country: row.get(7)?,
+        region: row.get(8)?,
+        created_at: row.get(9)?,
+        transition_altitude: row.get(10)?,
+        transition_level: row.get(11)?,
+        magnetic_variation: row.get(12)?,
+    })
+}
+
+pub(crate) const WAYPOINT_COLUMNS: &str =
+    "id, name, latitude, longitude, region, type, created_at, usage";
+
+pub(crate) fn waypoint_from_row(row: &Row) -> rusqlite::Result<Waypoint> {
+    Ok(Waypoint {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        coordinate: Coordinate::new(row.get(2)?, row.get(3)?),
+        region: row.get(4)?,
+        waypoint_type: WaypointType::from_str(
+            &row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        ),
+        usage: row
+            .get::<_, Option<String>>(7)?
+            .and_then(|usage| WaypointUsage::from_str(&usage)),
+        created_at: row.get(6)?,
+    })
+}
+
+pub(crate) const NAVAID_COLUMNS: &str =
+    "id, name, type, latitude, longitude, frequency, range_nm, elevation, region, created_at, \
+     declination";
+
+pub(crate) fn navaid_from_row(row: &Row) -> rusqlite::Result<Navaid> {
+    Ok(Navaid {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        navaid_type: NavaidType::from_str(&row.get::<_, String>(2)?),
+        coordinate: Coordinate::new(row.get(3)?, row.get(4)?),
+        frequency: row.get(5)?,
+        range_nm: row.get(6)?,
+        elevation: row.get(7)?,
+        region: row.get(8)?,
+        created_at: row.get(9)?,
+        declination: row.get(10)?,
+    })
+}
+
+/// Query builder for airports
+#[derive(Debug, Clone, Default)]
+pub struct AirportQuery {
+    filters: Filters,
+}
+
+impl AirportQuery {
+    /// Exact ICAO code match, also matching airports known by it as an alias
+    pub fn icao(mut self, icao: &str) -> Self {
+        let code = normalize_code(icao);
+        self.filters.push(
+            "(icao = ? OR id IN (SELECT airport_id FROM airport_aliases WHERE code = ?))",
+            vec![text(&code), text(&code)],
+        );
+        self
+    }
+
+    /// ICAO codes starting with the given prefix
+    pub fn icao_prefix(mut self, prefix: &str) -> Self {
+        self.filters.push(
+            "icao LIKE ? ESCAPE '\\'",
+            vec![like_prefix(&normalize_code(prefix))],
+        );
+        self
+    }
+
+    /// Exact IATA code match
+    pub fn iata(mut self, iata: &str) -> Self {
+        self.filters.push("iata = ?", vec![text(&normalize_code(iata))]);
+        self
+    }
+
+    /// Exact country match
+    pub fn country(mut self, country: &str) -> Self {
+        self.filters.push("country = ?", vec![text(country)]);
+        self
+    }
+
+    /// Exact region match
+    pub fn region(mut self, region: &str) -> Self {
+        self.filters.push("region = ?", vec![text(region)]);
+        self
+    }
+
+    /// Names containing the given text, in either script: the transliterated
+    /// needle is also matched against the transliterated name, so "beijing"
+    /// finds 北京首都 and "北京" finds "Beijing Capital"
+    pub fn name_contains(mut self, needle: &str) -> Self {
+        let key = search_key(needle);
+        if key.is_empty() {
+            self.filters
+                .push("name LIKE ? ESCAPE '\\'", vec![like_contains(needle)]);
+        } else {
+            self.filters.push(
+                "(name LIKE ? ESCAPE '\\' OR search_name LIKE ? ESCAPE '\\')",
+                vec![like_contains(needle), like_contains(&key)],
+            );
+        }
+        self
+    }
+
+    /// Rows created or changed at or after `timestamp` (Unix seconds)
+    ///
+    /// Inclusive, so a row changed later in the same second as the last
+    /// fetch isn't missed; callers de-duplicate by id.
+    pub fn modified_since(mut self, timestamp: i64) -> Self {
+        self.filters.modified_since(timestamp);
+        self
+    }
+
+    /// Restrict to a bounding box
+    pub fn within(mut self, bbox: BoundingBox) -> Self {
+        self.filters.within(bbox);
+        self
+    }
+
+    /// Maximum number of rows returned
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.filters.limit = Some(limit);
+        self
+    }
+
+    /// Number of rows skipped
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.filters.offset = Some(offset);
+        self
+    }
+
+    /// Compile to parameterized SQL
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        self.filters.compile(
+            &format!("SELECT {} FROM airports", AIRPORT_COLUMNS),
+            "icao",
+        )
+    }
+
+    /// Execute the query
+    pub fn fetch(&self, db: &Database) -> Result<Vec<Airport>> {
+        let (sql, params) = self.to_sql();
+        fetch_all(db, &sql, params, airport_from_row)
+    }
+
+    /// Count matching rows, ignoring limit and offset
+    pub fn count(&self, db: &Database) -> Result<i64> {
+        let (sql, params) = self.filters.count_sql("airports");
+        count_all(db, &sql, params)
+    }
+}
+
+/// Query builder for waypoints
+#[derive(Debug, Clone, Default)]
+pub struct WaypointQuery {
+    filters: Filters,
+}
+
+impl WaypointQuery {
+    /// Exact name (ident) match
+    pub fn name(mut self, name: &str) -> Self {
+        self.filters.push("name = ?", vec![text(name)]);
+        self
+    }
+
+    /// Names starting with the given prefix
+    pub fn name_prefix(mut self, prefix: &str) -> Self {
+        self.filters
+            .push("name LIKE ? ESCAPE '\\'", vec![like_prefix(prefix)]);
+        self
+    }
+
+    /// Exact region match
+    pub fn region(mut self, region: &str) -> Self {
+        self.filters.push("region = ?", vec![text(region)]);
+        self
+    }
+
+    /// Exact usage match; waypoints without a recorded usage never match
+    pub fn usage(mut self, usage: WaypointUsage) -> Self {
+        self.filters.push("usage = ?", vec![text(usage.as_str())]);
+        self
+    }
+
+    /// Waypoint type match
+    pub fn waypoint_type(mut self, waypoint_type: WaypointType) -> Self {
+        self.filters
+            .push("type = ?", vec![text(waypoint_type.as_str())]);
+        self
+    }
+
+    /// Rows created or changed at or after `timestamp` (Unix seconds)
+    ///
+    /// Inclusive, so a row changed later in the same second as the last
+    /// fetch isn't missed; callers de-duplicate by id.
+    pub fn modified_since(mut self, timestamp: i64) -> Self {
+        self.filters.modified_since(timestamp);
+        self
+    }
+
+    /// Restrict to a bounding box
+    pub fn within(mut self, bbox: BoundingBox) -> Self {
+        self.filters.within(bbox);
+        self
+    }
+
+    /// Maximum number of rows returned
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.filters.limit = Some(limit);
+        self
+    }
+
+    /// Number of rows skipped
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.filters.offset = Some(offset);
+        self
+    }
+
+    /// Compile to parameterized SQL
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        self.filters.compile(
+            &format!("SELECT {} FROM waypoints", WAYPOINT_COLUMNS),
+            "name, id",
+        )
+    }
+
+    /// Execute the query
+    pub fn fetch(&self, db: &Database) -> Result<Vec<Waypoint>> {
+        let (sql, params) = self.to_sql();
+        fetch_all(db, &sql, params, waypoint_from_row)
+    }
+
+    /// Count matching rows, ignoring limit and offset
+    pub fn count(&self, db: &Database) -> Result<i64> {
+        let (sql, params) = self.filters.count_sql("waypoints");
+        count_all(db, &sql, params)
+    }
+}
+
+/// Query builder for navaids
+#[derive(Debug, Clone, Default)]
+pub struct NavaidQuery {
+    filters: Filters,
+}
+
+impl NavaidQuery {
+    /// Exact name (ident) match
+    pub fn name(mut self, name: &str) -> Self {
+        self.filters.push("name = ?", vec![text(name)]);
+        self
+    }
+
+    /// Names starting with the given prefix
+    pub fn name_prefix(mut self, prefix: &str) -> Self {
+        self.filters
+            .push("name LIKE ? ESCAPE '\\'", vec![like_prefix(prefix)]);
+        self
+    }
+
+    /// Navaid type match
+    pub fn navaid_type(mut self, navaid_type: NavaidType) -> Self {
+        self.filters
+            .push("type = ?", vec![text(navaid_type.as_str())]);
+        self
+    }
+
+    /// Exact region match
+    pub fn region(mut self, region: &str) -> Self {
+        self.filters.push("region = ?", vec![text(region)]);
+        self
+    }
+
+    /// Rows created or changed at or after `timestamp` (Unix seconds)
+    ///
+    /// Inclusive, so a row changed later in the same second as the last
+    /// fetch isn't missed; callers de-duplicate by id.
+    pub fn modified_since(mut self, timestamp: i64) -> Self {
+        self.filters.modified_since(timestamp);
+        self
+    }
+
+    /// Restrict to a bounding box
+    pub fn within(mut self, bbox: BoundingBox) -> Self {
+        self.filters.within(bbox);
+        self
+    }
+
+    /// Maximum number of rows returned
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.filters.limit = Some(limit);
+        self
+    }
+
+    /// Number of rows skipped
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.filters.offset = Some(offset);
+        self
+    }
+
+    /// Compile to parameterized SQL
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        self.filters.compile(
+            &format!("SELECT {} FROM navaids", NAVAID_COLUMNS),
+            "name, id",
+        )
+    }
+
+    /// Execute the query
+    pub fn fetch(&self, db: &Database) -> Result<Vec<Navaid>> {
+        let (sql, params) = self.to_sql();
+        fetch_all(db, &sql, params, navaid_from_row)
+    }
+
+    /// Count matching rows, ignoring limit and offset
+    pub fn count(&self, db: &Database) -> Result<i64> {
+        let (sql, params) = self.filters.count_sql("navaids");
+        count_all(db, &sql, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::geometry;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+
+        let conn = db.get_conn().unwrap();
+        conn.execute_batch(
+            "INSERT INTO airports (id, icao, name, latitude, longitude, country, created_at)
+             VALUES ('AP1', 'ZBAA', 'Beijing Capital', 40.0801, 116.5846, 'China', 0);
+             INSERT INTO airports (id, icao, name, latitude, longitude, country, created_at)
+             VALUES ('AP2', 'ZBTJ', 'Tianjin Binhai', 39.1244, 117.3462, 'China', 0);
+             INSERT INTO airports (id, icao, name, latitude, longitude, country, created_at)
+             VALUES ('AP3', 'ZSSS', 'Shanghai Hongqiao', 31.1979, 121.3363, 'China', 0);
+             INSERT INTO airports (id, icao, name, latitude, longitude, country, created_at)
+             VALUES ('AP4', 'RJTT', 'Tokyo Haneda', 35.5523, 139.7798, 'Japan', 0);",
+        )
+        .unwrap();
+        drop(conn);
+
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_airport_query_filters() {
+        let (db, _temp_file) = setup_test_db();
+
+        let airports = Query::airports()
+            .country("China")
+            .icao_prefix("ZB")
+            .limit(100)
+            .fetch(&db)
+            .unwrap();
+        let icaos: Vec<_> = airports.iter().map(|a| a.icao.as_str()).collect();
+        assert_eq!(icaos, vec!["ZBAA", "ZBTJ"]);
+
+        let bbox = geometry::bounding_box(Coordinate::new(40.0801, 116.5846), 30.0);
+        let nearby = Query::airports().country("China").within(bbox).fetch(&db).unwrap();
+        assert_eq!(nearby.len(), 1);
+        assert_eq!(nearby[0].icao, "ZBAA");
+
+        assert_eq!(Query::airports().count(&db).unwrap(), 4);
+        assert_eq!(Query::airports().limit(1).offset(1).fetch(&db).unwrap()[0].icao, "ZBAA");
+    }
+
+    #[test]
+    fn test_code_normalization_and_aliases() {
+        let (db, _temp_file) = setup_test_db();
+        let conn = db.get_conn().unwrap();
+        conn.execute_batch(
+            "UPDATE airports SET iata = 'PEK' WHERE id = 'AP1';
+             INSERT INTO airport_aliases (id, airport_id, code, kind, created_at)
+             VALUES ('AL1', 'AP3', 'SHA', 'LOCAL', 0), ('AL2', 'AP4', 'ZBTJ', 'BOGUS', 0);",
+        )
+        .unwrap();
+
+        assert_eq!(resolve_airport_id(&conn, " zbaa ").unwrap().as_deref(), Some("AP1"));
+        assert_eq!(resolve_airport_id(&conn, "pek").unwrap().as_deref(), Some("AP1"));
+        assert_eq!(resolve_airport_id(&conn, "sha").unwrap().as_deref(), Some("AP3"));
+        // A real ICAO code wins over an alias claiming it
+        assert_eq!(resolve_airport_id(&conn, "ZBTJ").unwrap().as_deref(), Some("AP2"));
+        assert!(resolve_airport_id(&conn, "XXXX").unwrap().is_none());
+        drop(conn);
+
+        let found = Query::airports().icao("sha").fetch(&db).unwrap();
+        assert_eq!(found[0].icao, "ZSSS");
+        assert_eq!(Query::airports().icao_prefix("zb").count(&db).unwrap(), 2);
+        assert_eq!(Query::airports().iata(" pek").fetch(&db).unwrap()[0].icao, "ZBAA");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("ZSSS", "ZSSS"), 0);
+        assert_eq!(edit_distance("ZSPD", "ZSSS"), 2);
+        assert_eq!(edit_distance("ALPHA", "ALFA"), 2);
+        assert_eq!(edit_distance("BRAVO", "BRAV"), 1);
+        assert_eq!(edit_distance("", "ABC"), 3);
+    }
+
+    #[test]
+    fn test_filter_values_are_bound_not_interpolated() {
+        let (db, _temp_file) = setup_test_db();
+
+        let hostile = "China' OR '1'='1";
+        let (sql, params) = Query::airports().country(hostile).to_sql();
+        assert!(!sql.contains(hostile));
+        assert_eq!(params.len(), 1);
+        assert!(Query::airports().country(hostile).fetch(&db).unwrap().is_empty());
+
+        // LIKE wildcards in user input are matched literally
+        assert!(Query::airports().icao_prefix("%").fetch(&db).unwrap().is_empty());
+        assert_eq!(Query::airports().name_contains("Bin").fetch(&db).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_name_contains_transliterated() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, country, created_at)
+                 VALUES ('AP5', 'ZBAD', '北京大兴', 39.5098, 116.4105, 'China', 0)",
+                [],
+            )
+            .unwrap();
+        crate::repository::airport::refresh_search_names(&db.get_conn().unwrap()).unwrap();
+
+        let names = |needle: &str| -> Vec<String> {
+            let query = Query::airports().name_contains(needle);
+            query.fetch(&db).unwrap().into_iter().map(|a| a.name).collect()
+        };
+        assert_eq!(names("beijing"), vec!["Beijing Capital", "北京大兴"]);
+        assert_eq!(names("北京"), vec!["Beijing Capital", "北京大兴"]);
+        assert_eq!(names("Bei Jing Da"), vec!["北京大兴"]);
+        assert_eq!(names("大兴"), vec!["北京大兴"]);
+        // Punctuation-only needles still match literally rather than everything
+        assert!(names("-").is_empty());
+    }
+
+    #[test]
+    fn test_modified_since() {
+        let (db, _temp_file) = setup_test_db();
+
+        // Rows inserted without updated_at inherit created_at (0 here)
+        assert!(Query::airports().modified_since(1).fetch(&db).unwrap().is_empty());
+        assert!(!Query::airports().modified_since(0).fetch(&db).unwrap().is_empty());
+
+        let conn = db.get_conn().unwrap();
+        conn.execute("UPDATE airports SET name = 'Beijing Capital Intl' WHERE icao = 'ZBAA'", [])
+            .unwrap();
+        drop(conn);
+
+        let changed = Query::airports().modified_since(1).fetch(&db).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].icao, "ZBAA");
+    }
+}
@@ -0,0 +1,313 @@
+use super::register_functions;
+use crate::error::{AeroBaseError, Result};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 数据库恢复方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecoveryMethod {
+    /// 逐表导出可读数据并重新载入
+    Salvaged,
+    /// 从最近的备份恢复
+    RestoredBackup,
+}
+
+impl RecoveryMethod {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RecoveryMethod::Salvaged => "SALVAGED",
+            RecoveryMethod::RestoredBackup => "RESTORED_BACKUP",
+        }
+    }
+}
+
+/// 启动时检测到损坏并完成恢复的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    pub method: RecoveryMethod,
+    /// `PRAGMA quick_check` 报告的问题
+    pub problems: Vec<String>,
+    /// 无法读取而被清空的表
+    pub lost_tables: Vec<String>,
+    /// 损坏的原文件被移到的位置
+    pub corrupt_copy: PathBuf,
+}
+
+/// Default location of the backup for the database at `db_path`
+pub fn backup_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".bak");
+    PathBuf::from(path)
+}
+
+fn sibling(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Problems reported by `PRAGMA quick_check`; empty when the file is sound
+pub fn quick_check(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA quick_check")?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(rows.into_iter().filter(|r| r != "ok").collect())
+}
+
+/// Write a consistent copy of the open database to `dest`
+///
+/// The copy is written beside `dest` and renamed into place, so an
+/// interrupted backup never replaces a good one.
+pub fn backup(conn: &Connection, dest: &Path) -> Result<()> {
+    let partial = sibling(dest, ".partial");
+    let _ = fs::remove_file(&partial);
+    conn.execute("VACUUM INTO ?1", [partial.to_string_lossy()])?;
+    fs::rename(&partial, dest)?;
+    Ok(())
+}
+
+/// Open without the pool; the schema's generated columns need our functions
+fn open(path: &Path, flags: OpenFlags) -> Result<Connection> {
+    let conn = Connection::open_with_flags(path, flags)?;
+    register_functions(&conn)?;
+    Ok(conn)
+}
+
+fn file_problems(path: &Path) -> Vec<String> {
+    let result = open(path, OpenFlags::SQLITE_OPEN_READ_ONLY).and_then(|conn| quick_check(&conn));
+    match result {
+        Ok(problems) => problems,
+        Err(e) => vec![e.to_string()],
+    }
+}
+
+/// Check the database file before opening the pool and recover it if
+/// `PRAGMA quick_check` finds corruption
+///
+/// Every table is copied into a fresh file, skipping tables that can't be
+/// read. If nothing can be salvaged, the backup at `backup` is restored
+/// when it is itself sound. The damaged file is kept beside the database.
+/// Returns `None` when the file is missing or healthy.
+pub fn check_and_recover(db_path: &Path, backup: &Path) -> Result<Option<RecoveryReport>> {
+    if !db_path.exists() || fs::metadata(db_path)?.len() == 0 {
+        return Ok(None);
+    }
+    let problems = file_problems(db_path);
+    if problems.is_empty() {
+        return Ok(None);
+    }
+    log::error!("数据库 {} 已损坏: {}", db_path.display(), problems.join("; "));
+
+    let recovered = sibling(db_path, ".recovered");
+    let _ = fs::remove_file(&recovered);
+    let (method, lost_tables) = match salvage(db_path, &recovered) {
+        Ok(lost) if file_problems(&recovered).is_empty() => (RecoveryMethod::Salvaged, lost),
+        outcome => {
+            if let Err(e) = outcome {
+                log::warn!("无法导出损坏数据库中的数据: {}", e);
+            }
+            let _ = fs::remove_file(&recovered);
+            if !backup.exists() || !file_problems(backup).is_empty() {
+                return Err(AeroBaseError::Unknown(format!(
+                    "Database {} is corrupt and no usable backup exists at {}",
+                    db_path.display(),
+                    backup.display()
+                )));
+            }
+            fs::copy(backup, &recovered)?;
+            (RecoveryMethod::RestoredBackup, Vec::new())
+        }
+    };
+
+    let corrupt_copy = sibling(db_path, &format!(".corrupt-{}", chrono::Utc::now().timestamp()));
+    fs::rename(db_path, &corrupt_copy)?;
+    for suffix in ["-wal", "-shm"] {
+        let _ = fs::remove_file(sibling(db_path, suffix));
+    }
+    fs::rename(&recovered, db_path)?;
+
+    log::warn!(
+        "数据库已恢复（{}），丢失的表: {:?}，损坏文件保存在 {}",
+        method.as_str(),
+        lost_tables,
+        corrupt_copy.display()
+    );
+    Ok(Some(RecoveryReport {
+        method,
+        problems,
+        lost_tables,
+        corrupt_copy,
+    }))
+}
+
+/// Copy the schema and every readable row of `source` into a new file at
+/// `dest`, returning the tables whose rows couldn't be read
+fn salvage(source: &Path, dest: &Path) -> Result<Vec<String>> {
+    let conn = open(dest, OpenFlags::default())?;
+    conn.execute("ATTACH DATABASE ?1 AS damaged", [source.to_string_lossy()])?;
+
+    let mut stmt = conn.prepare(
+        "SELECT type, name, sql FROM damaged.sqlite_master
+         WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+         ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'index' THEN 1 ELSE 2 END, rowid",
+    )?;
+    let objects = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let mut lost = Vec::new();
+    for (kind, name, sql) in &objects {
+        conn.execute_batch(sql)?;
+        if kind != "table" {
+            continue;
+        }
+        // Generated columns are recomputed rather than copied
+        let columns: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_xinfo(?1) WHERE hidden = 0 ORDER BY cid")?
+            .query_map([name], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+        let columns = columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ");
+        // A single statement outside a transaction is all-or-nothing
+        let copy = conn.execute(
+            &format!("INSERT INTO main.\"{name}\" ({columns}) SELECT {columns} FROM damaged.\"{name}\""),
+            [],
+        );
+        if let Err(e) = copy {
+            log::warn!("表 {} 无法读取，已清空: {}", name, e);
+            lost.push(name.clone());
+        }
+    }
+
+    conn.execute("DETACH DATABASE damaged", [])?;
+    Ok(lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::Config;
+    use std::io::{Seek, SeekFrom, Write};
+    use tempfile::TempDir;
+
+    fn create(path: &Path) {
+        let config = Config {
+            db_path: path.to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        let conn = db.get_conn().unwrap();
+        let tx = conn.unchecked_transaction().unwrap();
+        for i in 0..2000 {
+            tx.execute(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                 VALUES (?1, ?1, ?2, 116.0, 'FIX', 0)",
+                rusqlite::params![format!("WP{:04}", i), 30.0 + i as f64 * 0.001],
+            )
+            .unwrap();
+        }
+        tx.execute(
+            "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+             VALUES ('AP1', 'ZBAA', 'Beijing', 40.0, 116.0, 0)",
+            [],
+        )
+        .unwrap();
+        tx.commit().unwrap();
+    }
+
+    /// Overwrite the root page of `object` with garbage
+    fn corrupt(path: &Path, object: &str) {
+        let (page_size, root): (i64, i64) = {
+            let conn = Connection::open(path).unwrap();
+            let page_size = conn.query_row("PRAGMA page_size", [], |row| row.get(0)).unwrap();
+            let root = conn
+                .query_row("SELECT rootpage FROM sqlite_master WHERE name = ?1", [object], |row| row.get(0))
+                .unwrap();
+            (page_size, root)
+        };
+        let mut file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.seek(SeekFrom::Start(((root - 1) * page_size) as u64)).unwrap();
+        file.write_all(&vec![0xA5; page_size as usize]).unwrap();
+    }
+
+    fn count(path: &Path, table: &str) -> i64 {
+        let conn = open(path, OpenFlags::default()).unwrap();
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_healthy_and_missing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("aerobase.db");
+        assert_eq!(check_and_recover(&path, &backup_path(&path)).unwrap(), None);
+        create(&path);
+        assert_eq!(check_and_recover(&path, &backup_path(&path)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_salvage_damaged_index() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("aerobase.db");
+        create(&path);
+        corrupt(&path, "idx_waypoints_name");
+
+        let report = check_and_recover(&path, &backup_path(&path)).unwrap().unwrap();
+        assert_eq!(report.method, RecoveryMethod::Salvaged);
+        assert!(!report.problems.is_empty());
+        assert!(report.lost_tables.is_empty());
+        assert!(report.corrupt_copy.exists());
+        assert!(file_problems(&path).is_empty());
+        assert_eq!(count(&path, "waypoints"), 2000);
+
+        let conn = open(&path, OpenFlags::default()).unwrap();
+        let version: i32 = conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, crate::db::schema::SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_salvage_damaged_table() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("aerobase.db");
+        create(&path);
+        corrupt(&path, "waypoints");
+
+        let report = check_and_recover(&path, &backup_path(&path)).unwrap().unwrap();
+        assert_eq!(report.method, RecoveryMethod::Salvaged);
+        assert_eq!(report.lost_tables, vec!["waypoints".to_string()]);
+        assert_eq!(count(&path, "waypoints"), 0);
+        assert_eq!(count(&path, "airports"), 1);
+    }
+
+    #[test]
+    fn test_restore_backup() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("aerobase.db");
+        let backup_file = backup_path(&path);
+        create(&path);
+        backup(&open(&path, OpenFlags::default()).unwrap(), &backup_file).unwrap();
+
+        // Nothing readable: the header page is gone
+        let mut file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all(&[0u8; 4096]).unwrap();
+        drop(file);
+
+        let report = check_and_recover(&path, &backup_file).unwrap().unwrap();
+        assert_eq!(report.method, RecoveryMethod::RestoredBackup);
+        assert_eq!(count(&path, "waypoints"), 2000);
+
+        // Without a backup the corruption is reported, not papered over
+        let mut file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all(&[0u8; 4096]).unwrap();
+        drop(file);
+        fs::remove_file(&backup_file).unwrap();
+        assert!(check_and_recover(&path, &backup_file).is_err());
+    }
+}
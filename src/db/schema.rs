@@ -1,7 +1,96 @@
 /// Database schema definitions
-pub const SCHEMA_VERSION: i32 = 1;
+pub const SCHEMA_VERSION: i32 = 44;
 
-/// Get all table creation SQL statements
+/// Tables carrying an `updated_at` column maintained by triggers
+pub const TIMESTAMPED_TABLES: &[&str] = &[
+    "waypoints",
+    "airports",
+    "airways",
+    "airway_segments",
+    "navaids",
+    "airspaces",
+    "flight_plans",
+    "runways",
+    "airport_frequencies",
+    "procedures",
+    "favorites",
+    "saved_searches",
+    "uas_zones",
+    "obstacles",
+    "terrain_cells",
+    "airport_aliases",
+    "procedure_legs",
+    "hazards",
+    "aircraft",
+    "logbook_entries",
+    "maintenance_items",
+    "attachments",
+    "attachment_blobs",
+    "attachment_links",
+    "charts",
+];
+
+/// Schema name of the attached user-data database
+pub const USER_SCHEMA: &str = "user";
+
+/// Tables holding user data, moved to the user-data database when one is
+/// attached (parents before the tables referencing them)
+///
+/// Changing the layout of one of these takes a migration in
+/// `get_migration_sql` for databases still holding the table and one in
+/// `get_user_migration_sql` for user-data databases that already have it.
+pub const USER_TABLES: &[&str] = &[
+    "devices",
+    "secure_devices",
+    "aircraft",
+    "flight_plans",
+    "flight_plan_aircraft",
+    "logbook_entries",
+    "maintenance_items",
+    "attachment_blobs",
+    "attachments",
+    "attachment_links",
+    "favorites",
+    "saved_searches",
+    "recent_items",
+    "route_history",
+    "track_points",
+    "webhooks",
+];
+
+/// Layout version of the user-data database, kept in its `user_version`
+pub const USER_SCHEMA_VERSION: i32 = 2;
+
+/// Add `updated_at` to a table, backfill it from `created_at` and keep it
+/// current on insert/update for writers that don't set it themselves
+macro_rules! updated_at_statements {
+    ($table:literal) => {
+        [
+            concat!("ALTER TABLE ", $table, " ADD COLUMN updated_at INTEGER"),
+            concat!("UPDATE ", $table, " SET updated_at = created_at"),
+            concat!(
+                "CREATE TRIGGER IF NOT EXISTS trg_", $table, "_insert_updated_at
+                 AFTER INSERT ON ", $table, " FOR EACH ROW WHEN NEW.updated_at IS NULL
+                 BEGIN
+                     UPDATE ", $table, " SET updated_at = NEW.created_at WHERE rowid = NEW.rowid;
+                 END"
+            ),
+            concat!(
+                "CREATE TRIGGER IF NOT EXISTS trg_", $table, "_update_updated_at
+                 AFTER UPDATE ON ", $table, " FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+                 BEGIN
+                     UPDATE ", $table, " SET updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+                     WHERE rowid = NEW.rowid;
+                 END"
+            ),
+            concat!(
+                "CREATE INDEX IF NOT EXISTS idx_", $table, "_updated_at ON ", $table, "(updated_at)"
+            ),
+        ]
+    };
+}
+
+/// Get all table creation SQL statements (schema version 1)
 pub fn get_schema_sql() -> Vec<&'static str> {
     vec![
         // Schema version table
@@ -169,6 +258,1018 @@ pub fn get_schema_sql() -> Vec<&'static str> {
     ]
 }
 
+/// Get the SQL statements that upgrade the schema to `version`
+///
+/// Version 1 is the base schema from `get_schema_sql`; every later version
+/// is applied incrementally on top of the previous one.
+pub fn get_migration_sql(version: i32) -> Vec<&'static str> {
+    match version {
+        1 => get_schema_sql(),
+        2 => vec![
+            // Airway segments: cascade when an endpoint waypoint is removed
+            r#"
+            CREATE TABLE airway_segments_new (
+                id TEXT PRIMARY KEY,
+                airway_id TEXT NOT NULL,
+                from_waypoint_id TEXT NOT NULL,
+                to_waypoint_id TEXT NOT NULL,
+                sequence INTEGER NOT NULL,
+                distance REAL,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (airway_id) REFERENCES airways(id) ON DELETE CASCADE,
+                FOREIGN KEY (from_waypoint_id) REFERENCES waypoints(id) ON DELETE CASCADE,
+                FOREIGN KEY (to_waypoint_id) REFERENCES waypoints(id) ON DELETE CASCADE
+            )
+            "#,
+            r#"
+            INSERT INTO airway_segments_new
+            SELECT id, airway_id, from_waypoint_id, to_waypoint_id, sequence, distance, created_at
+            FROM airway_segments
+            "#,
+            "DROP TABLE airway_segments",
+            "ALTER TABLE airway_segments_new RENAME TO airway_segments",
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airway_segments_airway
+            ON airway_segments(airway_id)
+            "#,
+            // Child-side indexes so cascades don't scan the whole table
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airway_segments_from
+            ON airway_segments(from_waypoint_id)
+            "#,
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airway_segments_to
+            ON airway_segments(to_waypoint_id)
+            "#,
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airspace_boundaries_airspace
+            ON airspace_boundaries(airspace_id)
+            "#,
+        ],
+        3 => vec![
+            // Saved flight plans (user data, optimistic concurrency via version)
+            r#"
+            CREATE TABLE IF NOT EXISTS flight_plans (
+                id TEXT PRIMARY KEY,
+                device_id TEXT,
+                name TEXT,
+                departure TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                plan_json TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE SET NULL
+            )
+            "#,
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_flight_plans_device
+            ON flight_plans(device_id)
+            "#,
+        ],
+        4 => [
+            updated_at_statements!("waypoints"),
+            updated_at_statements!("airports"),
+            updated_at_statements!("airways"),
+            updated_at_statements!("airway_segments"),
+            updated_at_statements!("navaids"),
+            updated_at_statements!("airspaces"),
+            updated_at_statements!("flight_plans"),
+        ]
+        .concat(),
+        5 => vec![
+            // Webhook subscriptions for event push (comma-separated event names)
+            r#"
+            CREATE TABLE IF NOT EXISTS webhooks (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                events TEXT NOT NULL,
+                active INTEGER NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+        6 => vec![
+            // Installed AIRAC cycles (dates as YYYY-MM-DD)
+            r#"
+            CREATE TABLE IF NOT EXISTS navdata_cycles (
+                cycle TEXT PRIMARY KEY,
+                effective_date TEXT NOT NULL,
+                expires_date TEXT NOT NULL,
+                installed_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+        7 => [
+            vec![
+                // Runway ends (one row per direction, e.g. 18L and 36R)
+                r#"
+                CREATE TABLE IF NOT EXISTS runways (
+                    id TEXT PRIMARY KEY,
+                    airport_id TEXT NOT NULL,
+                    ident TEXT NOT NULL,
+                    heading REAL,
+                    length_ft INTEGER,
+                    width_ft INTEGER,
+                    surface TEXT,
+                    latitude REAL,
+                    longitude REAL,
+                    elevation INTEGER,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (airport_id) REFERENCES airports(id) ON DELETE CASCADE
+                )
+                "#,
+                "CREATE INDEX IF NOT EXISTS idx_runways_airport ON runways(airport_id)",
+                r#"
+                CREATE TABLE IF NOT EXISTS airport_frequencies (
+                    id TEXT PRIMARY KEY,
+                    airport_id TEXT NOT NULL,
+                    type TEXT NOT NULL,
+                    frequency REAL NOT NULL,
+                    description TEXT,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (airport_id) REFERENCES airports(id) ON DELETE CASCADE
+                )
+                "#,
+                "CREATE INDEX IF NOT EXISTS idx_airport_frequencies_airport ON airport_frequencies(airport_id)",
+                // SIDs, STARs and approaches
+                r#"
+                CREATE TABLE IF NOT EXISTS procedures (
+                    id TEXT PRIMARY KEY,
+                    airport_id TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    type TEXT NOT NULL,
+                    runway TEXT,
+                    transition TEXT,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (airport_id) REFERENCES airports(id) ON DELETE CASCADE
+                )
+                "#,
+                "CREATE INDEX IF NOT EXISTS idx_procedures_airport ON procedures(airport_id)",
+            ]
+            .as_slice(),
+            &updated_at_statements!("runways"),
+            &updated_at_statements!("airport_frequencies"),
+            &updated_at_statements!("procedures"),
+        ]
+        .concat(),
+        8 => [
+            vec![
+                // Starred airports/waypoints per profile (user data)
+                r#"
+                CREATE TABLE IF NOT EXISTS favorites (
+                    id TEXT PRIMARY KEY,
+                    profile_id TEXT NOT NULL,
+                    item_type TEXT NOT NULL,
+                    item_id TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    UNIQUE (profile_id, item_type, item_id)
+                )
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS saved_searches (
+                    id TEXT PRIMARY KEY,
+                    profile_id TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    criteria_json TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    UNIQUE (profile_id, name)
+                )
+                "#,
+                // Recently opened items, local to the device
+                r#"
+                CREATE TABLE IF NOT EXISTS recent_items (
+                    profile_id TEXT NOT NULL,
+                    item_type TEXT NOT NULL,
+                    item_id TEXT NOT NULL,
+                    accessed_at INTEGER NOT NULL,
+                    PRIMARY KEY (profile_id, item_type, item_id)
+                )
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("favorites"),
+            &updated_at_statements!("saved_searches"),
+        ]
+        .concat(),
+        9 => vec![
+            // Recently flown routes, bounded per profile by the history module
+            r#"
+            CREATE TABLE IF NOT EXISTS route_history (
+                id TEXT PRIMARY KEY,
+                profile_id TEXT NOT NULL,
+                route_key TEXT NOT NULL,
+                plan_json TEXT NOT NULL,
+                times_flown INTEGER NOT NULL DEFAULT 1,
+                flown_at INTEGER NOT NULL,
+                UNIQUE (profile_id, route_key)
+            )
+            "#,
+        ],
+        10 => vec![
+            // Localizer serving each runway end, for approach radio tuning
+            "ALTER TABLE runways ADD COLUMN ils_ident TEXT",
+            "ALTER TABLE runways ADD COLUMN ils_frequency REAL",
+            "ALTER TABLE runways ADD COLUMN ils_course REAL",
+        ],
+        11 => [
+            // UAS geographical zones (ED-269); the bounding box columns let
+            // spatial queries skip zones before loading their boundaries
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS uas_zones (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    country TEXT,
+                    restriction TEXT NOT NULL,
+                    reasons TEXT,
+                    lower_limit INTEGER NOT NULL,
+                    upper_limit INTEGER NOT NULL,
+                    vertical_reference TEXT NOT NULL,
+                    message TEXT,
+                    min_latitude REAL NOT NULL,
+                    max_latitude REAL NOT NULL,
+                    min_longitude REAL NOT NULL,
+                    max_longitude REAL NOT NULL,
+                    created_at INTEGER NOT NULL
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_uas_zones_bbox
+                ON uas_zones(min_latitude, max_latitude, min_longitude, max_longitude)
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS uas_zone_boundaries (
+                    id TEXT PRIMARY KEY,
+                    zone_id TEXT NOT NULL,
+                    latitude REAL NOT NULL,
+                    longitude REAL NOT NULL,
+                    sequence INTEGER NOT NULL,
+                    FOREIGN KEY (zone_id) REFERENCES uas_zones(id) ON DELETE CASCADE
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_uas_zone_boundaries_zone
+                ON uas_zone_boundaries(zone_id)
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("uas_zones"),
+        ]
+        .concat(),
+        12 => [
+            // Obstacles and a max-elevation terrain grid (one cell per arc
+            // minute) for vertical clearance profiles
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS obstacles (
+                    id TEXT PRIMARY KEY,
+                    name TEXT,
+                    type TEXT NOT NULL,
+                    latitude REAL NOT NULL,
+                    longitude REAL NOT NULL,
+                    elevation INTEGER NOT NULL,
+                    height INTEGER,
+                    lighted INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_obstacles_location
+                ON obstacles(latitude, longitude)
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS terrain_cells (
+                    id TEXT PRIMARY KEY,
+                    cell_lat INTEGER NOT NULL,
+                    cell_lon INTEGER NOT NULL,
+                    max_elevation INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    UNIQUE (cell_lat, cell_lon)
+                )
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("obstacles"),
+            &updated_at_statements!("terrain_cells"),
+        ]
+        .concat(),
+        13 => vec![
+            // Geohash cells for coarse regional lookups; virtual generated
+            // columns stay current on every write without extra triggers.
+            // Requires the `geohash()` function from `db::register_functions`.
+            "ALTER TABLE airports ADD COLUMN geohash TEXT
+             GENERATED ALWAYS AS (geohash(latitude, longitude)) VIRTUAL",
+            "CREATE INDEX IF NOT EXISTS idx_airports_geohash ON airports(geohash)",
+            "ALTER TABLE waypoints ADD COLUMN geohash TEXT
+             GENERATED ALWAYS AS (geohash(latitude, longitude)) VIRTUAL",
+            "CREATE INDEX IF NOT EXISTS idx_waypoints_geohash ON waypoints(geohash)",
+        ],
+        14 => [
+            // Former and local codes an airport is also known by
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS airport_aliases (
+                    id TEXT PRIMARY KEY,
+                    airport_id TEXT NOT NULL,
+                    code TEXT UNIQUE NOT NULL,
+                    kind TEXT,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (airport_id) REFERENCES airports(id) ON DELETE CASCADE
+                )
+                "#,
+                "CREATE INDEX IF NOT EXISTS idx_airport_aliases_airport ON airport_aliases(airport_id)",
+                "CREATE INDEX IF NOT EXISTS idx_airports_iata ON airports(iata)",
+            ]
+            .as_slice(),
+            &updated_at_statements!("airport_aliases"),
+        ]
+        .concat(),
+        15 => vec![
+            // Terminal/enroute usage for telling same-named fixes apart,
+            // and a name index for ident lookups
+            "ALTER TABLE waypoints ADD COLUMN usage TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_waypoints_name ON waypoints(name)",
+        ],
+        16 => vec![
+            // Station declination (degrees, east positive) VOR radials are
+            // aligned to; differs from current magnetic variation
+            "ALTER TABLE navaids ADD COLUMN declination REAL",
+        ],
+        17 => [
+            // Coded procedure legs (ARINC 424 path terminators). Fixes may be
+            // waypoints or navaids, so they aren't foreign keys.
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS procedure_legs (
+                    id TEXT PRIMARY KEY,
+                    procedure_id TEXT NOT NULL,
+                    sequence INTEGER NOT NULL,
+                    path_terminator TEXT NOT NULL,
+                    fix_id TEXT,
+                    course REAL,
+                    turn_direction TEXT,
+                    altitude INTEGER,
+                    distance_nm REAL,
+                    center_fix_id TEXT,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (procedure_id) REFERENCES procedures(id) ON DELETE CASCADE,
+                    UNIQUE (procedure_id, sequence)
+                )
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("procedure_legs"),
+        ]
+        .concat(),
+        18 => vec![
+            // Transition altitude and level, both in feet
+            "ALTER TABLE airports ADD COLUMN transition_altitude INTEGER",
+            "ALTER TABLE airports ADD COLUMN transition_level INTEGER",
+        ],
+        19 => vec![
+            // Reduced vertical separation applies between FL290 and FL410
+            // inside the airspace (usually set on FIRs)
+            "ALTER TABLE airspaces ADD COLUMN rvsm INTEGER NOT NULL DEFAULT 0",
+        ],
+        20 => vec![
+            // Cruising-level table used inside the airspace (usually set on
+            // FIRs): ICAO, METRIC or QUADRANTAL
+            "ALTER TABLE airspaces ADD COLUMN level_scheme TEXT",
+        ],
+        21 => vec![
+            // Received terminal aerodrome forecasts, local to the device;
+            // amendments replace the forecast with the same issue time
+            r#"
+            CREATE TABLE IF NOT EXISTS tafs (
+                id TEXT PRIMARY KEY,
+                station TEXT NOT NULL,
+                issued_at INTEGER NOT NULL,
+                valid_from INTEGER NOT NULL,
+                valid_to INTEGER NOT NULL,
+                raw TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                UNIQUE (station, issued_at)
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_tafs_station_valid ON tafs(station, valid_to)",
+        ],
+        22 => vec![
+            // Received SIGMET/AIRMET advisories, local to the device; the
+            // bounding box prefilters route overlap queries
+            r#"
+            CREATE TABLE IF NOT EXISTS sigmets (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                fir TEXT NOT NULL,
+                series TEXT NOT NULL,
+                phenomenon TEXT NOT NULL,
+                valid_from INTEGER NOT NULL,
+                valid_to INTEGER NOT NULL,
+                lower_limit INTEGER,
+                upper_limit INTEGER,
+                raw TEXT NOT NULL,
+                min_latitude REAL NOT NULL,
+                max_latitude REAL NOT NULL,
+                min_longitude REAL NOT NULL,
+                max_longitude REAL NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_sigmets_valid ON sigmets(valid_to, valid_from)",
+            r#"
+            CREATE TABLE IF NOT EXISTS sigmet_boundaries (
+                id TEXT PRIMARY KEY,
+                sigmet_id TEXT NOT NULL,
+                latitude REAL NOT NULL,
+                longitude REAL NOT NULL,
+                sequence INTEGER NOT NULL,
+                FOREIGN KEY (sigmet_id) REFERENCES sigmets(id) ON DELETE CASCADE
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_sigmet_boundaries_sigmet ON sigmet_boundaries(sigmet_id)",
+        ],
+        23 => vec![
+            // Forecast winds and temperatures aloft on a lat/lon grid, local
+            // to the device; speeds in knots, temperatures in Celsius
+            r#"
+            CREATE TABLE IF NOT EXISTS winds_aloft (
+                valid_time INTEGER NOT NULL,
+                level_hpa INTEGER NOT NULL,
+                latitude REAL NOT NULL,
+                longitude REAL NOT NULL,
+                wind_direction REAL NOT NULL,
+                wind_speed REAL NOT NULL,
+                temperature REAL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (valid_time, level_hpa, latitude, longitude)
+            )
+            "#,
+        ],
+        24 => [
+            // Dynamic hazards (TFRs, volcanic ash, conflict zones) pushed by
+            // sync between navdata cycles
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS hazards (
+                    id TEXT PRIMARY KEY,
+                    kind TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    lower_limit INTEGER,
+                    upper_limit INTEGER,
+                    valid_from INTEGER NOT NULL,
+                    valid_to INTEGER,
+                    message TEXT,
+                    min_latitude REAL NOT NULL,
+                    max_latitude REAL NOT NULL,
+                    min_longitude REAL NOT NULL,
+                    max_longitude REAL NOT NULL,
+                    created_at INTEGER NOT NULL
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_hazards_bbox
+                ON hazards(min_latitude, max_latitude, min_longitude, max_longitude)
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS hazard_boundaries (
+                    id TEXT PRIMARY KEY,
+                    hazard_id TEXT NOT NULL,
+                    latitude REAL NOT NULL,
+                    longitude REAL NOT NULL,
+                    sequence INTEGER NOT NULL,
+                    FOREIGN KEY (hazard_id) REFERENCES hazards(id) ON DELETE CASCADE
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_hazard_boundaries_hazard
+                ON hazard_boundaries(hazard_id)
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("hazards"),
+        ]
+        .concat(),
+        25 => vec![
+            // Server-driven feature flags, replaced as a set on every sync
+            r#"
+            CREATE TABLE IF NOT EXISTS feature_flags (
+                name TEXT PRIMARY KEY,
+                enabled INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+        26 => vec![
+            // Opt-in error diagnostics, a ring buffer emptied by uploads
+            r#"
+            CREATE TABLE IF NOT EXISTS diagnostic_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                occurred_at INTEGER NOT NULL,
+                operation TEXT NOT NULL,
+                error_code TEXT NOT NULL,
+                message TEXT,
+                duration_ms INTEGER,
+                device TEXT
+            )
+            "#,
+        ],
+        27 => vec![
+            // Bulky device-local data kept under storage quotas
+            r#"
+            CREATE TABLE IF NOT EXISTS track_points (
+                track_id TEXT NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                latitude REAL NOT NULL,
+                longitude REAL NOT NULL,
+                altitude INTEGER,
+                ground_speed INTEGER,
+                PRIMARY KEY (track_id, recorded_at)
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS map_tiles (
+                layer TEXT NOT NULL,
+                zoom INTEGER NOT NULL,
+                x INTEGER NOT NULL,
+                y INTEGER NOT NULL,
+                data BLOB NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (layer, zoom, x, y)
+            )
+            "#,
+            // Last use of each evictable unit (a track, a forecast time, a tile)
+            r#"
+            CREATE TABLE IF NOT EXISTS storage_access (
+                dataset TEXT NOT NULL,
+                unit TEXT NOT NULL,
+                accessed_at INTEGER NOT NULL,
+                PRIMARY KEY (dataset, unit)
+            )
+            "#,
+        ],
+        28 => vec![
+            // Transliterated airport names so "beijing" finds 北京首都 and back.
+            // Requires the `search_key()` function from `db::register_functions`.
+            "ALTER TABLE airports ADD COLUMN search_name TEXT
+             GENERATED ALWAYS AS (search_key(name)) VIRTUAL",
+        ],
+        29 => vec![
+            // Server-mode roles keyed by certificate fingerprint or token hash
+            r#"
+            CREATE TABLE IF NOT EXISTS access_roles (
+                principal TEXT PRIMARY KEY,
+                role TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+        ],
+        30 => vec![
+            // Rows hidden from profiles lacking the category's entitlement
+            r#"
+            CREATE TABLE IF NOT EXISTS restricted_items (
+                item_type TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                category TEXT NOT NULL,
+                PRIMARY KEY (item_type, item_id)
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS profile_entitlements (
+                profile_id TEXT NOT NULL,
+                category TEXT NOT NULL,
+                PRIMARY KEY (profile_id, category)
+            )
+            "#,
+        ],
+        31 => [
+            // Fleet by registration (id), with the aircraft each saved plan uses
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS aircraft (
+                    id TEXT PRIMARY KEY,
+                    aircraft_type TEXT NOT NULL,
+                    cruise_speed INTEGER NOT NULL,
+                    cruise_altitude INTEGER NOT NULL,
+                    fuel_flow_gph REAL NOT NULL,
+                    equipment TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                )
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS flight_plan_aircraft (
+                    plan_id TEXT PRIMARY KEY,
+                    aircraft_id TEXT NOT NULL,
+                    FOREIGN KEY (plan_id) REFERENCES flight_plans(id) ON DELETE CASCADE,
+                    FOREIGN KEY (aircraft_id) REFERENCES aircraft(id) ON DELETE CASCADE
+                )
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("aircraft"),
+        ]
+        .concat(),
+        32 => [
+            // Aircraft logbook and maintenance intervals (counters derive from the logbook)
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS logbook_entries (
+                    id TEXT PRIMARY KEY,
+                    aircraft_id TEXT NOT NULL,
+                    flown_at INTEGER NOT NULL,
+                    block_minutes INTEGER NOT NULL,
+                    cycles INTEGER NOT NULL,
+                    plan_id TEXT,
+                    remarks TEXT,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (aircraft_id) REFERENCES aircraft(id) ON DELETE CASCADE
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_logbook_entries_aircraft
+                ON logbook_entries(aircraft_id, flown_at)
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS maintenance_items (
+                    id TEXT PRIMARY KEY,
+                    aircraft_id TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    interval_hours REAL,
+                    interval_cycles INTEGER,
+                    interval_days INTEGER,
+                    last_done_at INTEGER NOT NULL,
+                    last_done_hours REAL NOT NULL,
+                    last_done_cycles INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (aircraft_id) REFERENCES aircraft(id) ON DELETE CASCADE
+                )
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("logbook_entries"),
+            &updated_at_statements!("maintenance_items"),
+        ]
+        .concat(),
+        33 => [
+            // Encrypted attachments; blobs are keyed by the SHA-256 of their content
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS attachment_blobs (
+                    id TEXT PRIMARY KEY,
+                    data BLOB NOT NULL,
+                    created_at INTEGER NOT NULL
+                )
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS attachments (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    content_type TEXT NOT NULL,
+                    size INTEGER NOT NULL,
+                    sha256 TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_attachments_sha256 ON attachments(sha256)
+                "#,
+                r#"
+                CREATE TABLE IF NOT EXISTS attachment_links (
+                    id TEXT PRIMARY KEY,
+                    attachment_id TEXT NOT NULL,
+                    target_type TEXT NOT NULL,
+                    target_id TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    UNIQUE(attachment_id, target_type, target_id),
+                    FOREIGN KEY (attachment_id) REFERENCES attachments(id) ON DELETE CASCADE
+                )
+                "#,
+                r#"
+                CREATE INDEX IF NOT EXISTS idx_attachment_links_target
+                ON attachment_links(target_type, target_id)
+                "#,
+            ]
+            .as_slice(),
+            &updated_at_statements!("attachment_blobs"),
+            &updated_at_statements!("attachments"),
+            &updated_at_statements!("attachment_links"),
+        ]
+        .concat(),
+        34 => [
+            // Chart index: metadata and a file reference per chart and AIRAC cycle
+            vec![
+                r#"
+                CREATE TABLE IF NOT EXISTS charts (
+                    id TEXT PRIMARY KEY,
+                    airport_id TEXT NOT NULL,
+                    procedure_id TEXT,
+                    identifier TEXT NOT NULL,
+                    type TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    effective_cycle TEXT NOT NULL,
+                    file_ref TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    UNIQUE(airport_id, identifier, effective_cycle),
+                    FOREIGN KEY (airport_id) REFERENCES airports(id) ON DELETE CASCADE,
+                    FOREIGN KEY (procedure_id) REFERENCES procedures(id) ON DELETE SET NULL
+                )
+                "#,
+                "CREATE INDEX IF NOT EXISTS idx_charts_airport ON charts(airport_id, procedure_id)",
+            ]
+            .as_slice(),
+            &updated_at_statements!("charts"),
+        ]
+        .concat(),
+        35 => vec![
+            // Imported NOTAMs, local to the device; keyed by location and number
+            r#"
+            CREATE TABLE IF NOT EXISTS notams (
+                id TEXT PRIMARY KEY,
+                number TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                reference TEXT,
+                location TEXT NOT NULL,
+                fir TEXT,
+                q_code TEXT,
+                lower_limit INTEGER,
+                upper_limit INTEGER,
+                latitude REAL,
+                longitude REAL,
+                radius_nm REAL,
+                valid_from INTEGER NOT NULL,
+                valid_to INTEGER,
+                estimated_end INTEGER NOT NULL DEFAULT 0,
+                schedule TEXT,
+                text TEXT NOT NULL,
+                raw TEXT NOT NULL,
+                source TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_notams_location ON notams(location, valid_from)",
+            "CREATE INDEX IF NOT EXISTS idx_notams_valid ON notams(valid_to)",
+        ],
+        36 => vec![
+            // Latest ATIS broadcast per airport and kind, local to the device
+            r#"
+            CREATE TABLE IF NOT EXISTS atis (
+                id TEXT PRIMARY KEY,
+                station TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                letter TEXT,
+                text TEXT NOT NULL,
+                issued_at INTEGER NOT NULL,
+                received_at INTEGER NOT NULL,
+                UNIQUE(station, kind)
+            )
+            "#,
+        ],
+        37 => vec![
+            // Natural keys: a waypoint is ident + ICAO region, a navaid ident +
+            // type + region, an airway segment airway + sequence. Rows without
+            // a region stay unkeyed. Existing duplicates fold into the oldest
+            // row first, with airway segments pointed at the survivor.
+            r#"
+            UPDATE airway_segments SET from_waypoint_id = (
+                SELECT k.id FROM waypoints d
+                JOIN waypoints k ON k.name = d.name AND k.region = d.region
+                WHERE d.id = airway_segments.from_waypoint_id
+                ORDER BY k.created_at, k.id LIMIT 1
+            )
+            WHERE from_waypoint_id IN (
+                SELECT w.id FROM waypoints w WHERE w.region IS NOT NULL AND EXISTS (
+                    SELECT 1 FROM waypoints o WHERE o.name = w.name AND o.region = w.region
+                        AND (o.created_at, o.id) < (w.created_at, w.id)
+                )
+            )
+            "#,
+            r#"
+            UPDATE airway_segments SET to_waypoint_id = (
+                SELECT k.id FROM waypoints d
+                JOIN waypoints k ON k.name = d.name AND k.region = d.region
+                WHERE d.id = airway_segments.to_waypoint_id
+                ORDER BY k.created_at, k.id LIMIT 1
+            )
+            WHERE to_waypoint_id IN (
+                SELECT w.id FROM waypoints w WHERE w.region IS NOT NULL AND EXISTS (
+                    SELECT 1 FROM waypoints o WHERE o.name = w.name AND o.region = w.region
+                        AND (o.created_at, o.id) < (w.created_at, w.id)
+                )
+            )
+            "#,
+            r#"
+            DELETE FROM waypoints WHERE region IS NOT NULL AND EXISTS (
+                SELECT 1 FROM waypoints o
+                WHERE o.name = waypoints.name AND o.region = waypoints.region
+                    AND (o.created_at, o.id) < (waypoints.created_at, waypoints.id)
+            )
+            "#,
+            r#"
+            DELETE FROM navaids WHERE region IS NOT NULL AND EXISTS (
+                SELECT 1 FROM navaids o
+                WHERE o.name = navaids.name AND o.type = navaids.type
+                    AND o.region = navaids.region
+                    AND (o.created_at, o.id) < (navaids.created_at, navaids.id)
+            )
+            "#,
+            r#"
+            DELETE FROM airway_segments WHERE EXISTS (
+                SELECT 1 FROM airway_segments o
+                WHERE o.airway_id = airway_segments.airway_id
+                    AND o.sequence = airway_segments.sequence
+                    AND (o.created_at, o.id) < (airway_segments.created_at, airway_segments.id)
+            )
+            "#,
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_waypoints_natural ON waypoints(name, region)",
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_navaids_natural ON navaids(name, type, region)",
+            r#"
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_airway_segments_natural
+            ON airway_segments(airway_id, sequence)
+            "#,
+        ],
+        38 => vec![
+            // Opt-in slow query log, a ring buffer like diagnostic_events
+            r#"
+            CREATE TABLE IF NOT EXISTS slow_queries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                occurred_at INTEGER NOT NULL,
+                sql TEXT NOT NULL,
+                parameters TEXT NOT NULL,
+                duration_us INTEGER NOT NULL,
+                query_plan TEXT
+            )
+            "#,
+        ],
+        39 => vec![
+            // search_name becomes a plain column written by the airport
+            // repository, so the schema no longer needs the app's
+            // search_key() to read or write airports. `run_migrations`
+            // fills it in afterwards.
+            "ALTER TABLE airports DROP COLUMN search_name",
+            "ALTER TABLE airports ADD COLUMN search_name TEXT",
+        ],
+        40 => vec![
+            // Magnetic variation (degrees, east positive) turning runway
+            // designators, which are magnetic, into true headings
+            "ALTER TABLE airports ADD COLUMN magnetic_variation REAL",
+        ],
+        41 => vec![
+            // Secure devices may be registered without hardware info; SQLite
+            // can't drop NOT NULL in place, so the table is rebuilt
+            r#"
+            CREATE TABLE secure_devices_new (
+                id TEXT PRIMARY KEY,
+                encrypted_fingerprint TEXT NOT NULL,
+                encrypted_hardware_info TEXT,
+                encrypted_aes_key TEXT NOT NULL,
+                public_key_pem TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL
+            )
+            "#,
+            r#"
+            INSERT INTO secure_devices_new
+            SELECT id, encrypted_fingerprint, encrypted_hardware_info, encrypted_aes_key,
+                public_key_pem, created_at, last_seen
+            FROM secure_devices
+            "#,
+            "DROP TABLE secure_devices",
+            "ALTER TABLE secure_devices_new RENAME TO secure_devices",
+        ],
+        42 => vec![
+            // geohash becomes a plain column written alongside the position,
+            // as search_name did in 39, so reading or writing airports and
+            // waypoints no longer needs the app's geohash(). `run_migrations`
+            // fills it in afterwards.
+            "DROP INDEX IF EXISTS idx_airports_geohash",
+            "ALTER TABLE airports DROP COLUMN geohash",
+            "ALTER TABLE airports ADD COLUMN geohash TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_airports_geohash ON airports(geohash)",
+            "DROP INDEX IF EXISTS idx_waypoints_geohash",
+            "ALTER TABLE waypoints DROP COLUMN geohash",
+            "ALTER TABLE waypoints ADD COLUMN geohash TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_waypoints_geohash ON waypoints(geohash)",
+        ],
+        43 => vec![
+            // Change log for incremental sync: the latest change of each
+            // tracked row under a sequence that never repeats, kept as a
+            // tombstone once the row is deleted. The triggers feeding it are
+            // installed per schema by `sync::changes::track_changes`, since a
+            // trigger can only write to its own database file.
+            r#"
+            CREATE TABLE IF NOT EXISTS change_log (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                table_name TEXT NOT NULL,
+                row_id TEXT NOT NULL,
+                deleted INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_change_log_row ON change_log(table_name, row_id)",
+            "CREATE INDEX IF NOT EXISTS idx_change_log_seq ON change_log(table_name, seq)",
+            "ALTER TABLE sync_metadata ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 0",
+        ],
+        44 => vec![
+            // Airway segments: refuse to delete a waypoint an airway still
+            // uses, as `WaypointRepository::delete` does, instead of the
+            // cascade from version 2 cutting the airway. Importers that
+            // replace waypoints remap or drop the segments first.
+            r#"
+            CREATE TABLE airway_segments_new (
+                id TEXT PRIMARY KEY,
+                airway_id TEXT NOT NULL,
+                from_waypoint_id TEXT NOT NULL,
+                to_waypoint_id TEXT NOT NULL,
+                sequence INTEGER NOT NULL,
+                distance REAL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER,
+                FOREIGN KEY (airway_id) REFERENCES airways(id) ON DELETE CASCADE,
+                FOREIGN KEY (from_waypoint_id) REFERENCES waypoints(id) ON DELETE RESTRICT,
+                FOREIGN KEY (to_waypoint_id) REFERENCES waypoints(id) ON DELETE RESTRICT
+            )
+            "#,
+            r#"
+            INSERT INTO airway_segments_new
+            SELECT id, airway_id, from_waypoint_id, to_waypoint_id, sequence, distance,
+                created_at, updated_at
+            FROM airway_segments
+            "#,
+            "DROP TABLE airway_segments",
+            "ALTER TABLE airway_segments_new RENAME TO airway_segments",
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airway_segments_airway
+            ON airway_segments(airway_id)
+            "#,
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airway_segments_from
+            ON airway_segments(from_waypoint_id)
+            "#,
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airway_segments_to
+            ON airway_segments(to_waypoint_id)
+            "#,
+            r#"
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_airway_segments_natural
+            ON airway_segments(airway_id, sequence)
+            "#,
+            // Same as `updated_at_statements!`, minus adding the column
+            r#"
+            CREATE TRIGGER IF NOT EXISTS trg_airway_segments_insert_updated_at
+            AFTER INSERT ON airway_segments FOR EACH ROW WHEN NEW.updated_at IS NULL
+            BEGIN
+                UPDATE airway_segments SET updated_at = NEW.created_at WHERE rowid = NEW.rowid;
+            END
+            "#,
+            r#"
+            CREATE TRIGGER IF NOT EXISTS trg_airway_segments_update_updated_at
+            AFTER UPDATE ON airway_segments FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+            BEGIN
+                UPDATE airway_segments SET updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE rowid = NEW.rowid;
+            END
+            "#,
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_airway_segments_updated_at
+            ON airway_segments(updated_at)
+            "#,
+        ],
+        _ => vec![],
+    }
+}
+
+/// Migrations of the user-data database, `{schema}` standing for its name
+///
+/// Version 1 is the layout the main migrations give the user tables, copied
+/// over by `separate_user_data`, so it has no statements of its own. Files
+/// from before the user-data database was versioned are taken to be at
+/// version 1; their later migrations must be safe to repeat.
+pub fn get_user_migration_sql(version: i32) -> Vec<&'static str> {
+    match version {
+        2 => vec![
+            // As main version 41: hardware info becomes optional
+            r#"
+            CREATE TABLE {schema}.secure_devices_new (
+                id TEXT PRIMARY KEY,
+                encrypted_fingerprint TEXT NOT NULL,
+                encrypted_hardware_info TEXT,
+                encrypted_aes_key TEXT NOT NULL,
+                public_key_pem TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL
+            )
+            "#,
+            r#"
+            INSERT INTO {schema}.secure_devices_new
+            SELECT id, encrypted_fingerprint, encrypted_hardware_info, encrypted_aes_key,
+                public_key_pem, created_at, last_seen
+            FROM {schema}.secure_devices
+            "#,
+            "DROP TABLE {schema}.secure_devices",
+            "ALTER TABLE {schema}.secure_devices_new RENAME TO secure_devices",
+        ],
+        _ => vec![],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,6 +1278,45 @@ mod tests {
     fn test_schema_sql_count() {
         let statements = get_schema_sql();
         assert!(!statements.is_empty());
-        assert_eq!(statements.len(), 18); // Update if schema changes
+        // One entry per statement in `get_schema_sql`; update if schema changes
+        assert_eq!(statements.len(), 15);
+    }
+
+    #[test]
+    fn test_timestamped_tables_have_updated_at() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::register_functions(&conn).unwrap();
+        for version in 1..=SCHEMA_VERSION {
+            for sql in get_migration_sql(version) {
+                conn.execute_batch(sql).unwrap();
+            }
+        }
+
+        for table in TIMESTAMPED_TABLES {
+            let has_column: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = 'updated_at')",
+                    [table],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert!(has_column, "{} is missing updated_at", table);
+        }
+    }
+
+    #[test]
+    fn test_every_version_has_migration() {
+        for version in 1..=SCHEMA_VERSION {
+            assert!(!get_migration_sql(version).is_empty());
+        }
+        assert!(get_migration_sql(SCHEMA_VERSION + 1).is_empty());
+
+        for version in 2..=USER_SCHEMA_VERSION {
+            let statements = get_user_migration_sql(version);
+            assert!(!statements.is_empty());
+            // Every table reference is placed in the user-data database
+            assert!(statements.iter().all(|sql| sql.contains("{schema}.")));
+        }
+        assert!(get_user_migration_sql(USER_SCHEMA_VERSION + 1).is_empty());
     }
 }
@@ -0,0 +1,301 @@
+use crate::clock::Clock;
+use crate::error::Result;
+use rusqlite::{ffi, Connection};
+use serde::{Deserialize, Serialize};
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// Statements held in memory between flushes; later ones are dropped
+const MAX_PENDING: usize = 256;
+
+struct PendingQuery {
+    occurred_at: i64,
+    sql: String,
+    duration: Duration,
+}
+
+/// 一条慢查询记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlowQuery {
+    pub id: i64,
+    pub occurred_at: i64,
+    /// 语句文本（参数为占位符，不含绑定值）
+    pub sql: String,
+    /// 占位符形式，如 `?1, ?2, ?3`
+    pub parameters: String,
+    pub duration_us: i64,
+    /// `EXPLAIN QUERY PLAN` 输出，按层级缩进；无法解释的语句为 None
+    pub query_plan: Option<String>,
+}
+
+impl SlowQuery {
+    /// Whether the plan walks a whole table instead of searching an index
+    pub fn scans_table(&self) -> bool {
+        self.query_plan.as_deref().is_some_and(|plan| {
+            plan.lines().any(|line| {
+                let line = line.trim();
+                line.starts_with("SCAN ") && !line.contains(" USING ")
+            })
+        })
+    }
+}
+
+/// Slow query log of one database: its threshold and the statements
+/// caught by the profile hook but not yet written to `slow_queries`
+pub struct SlowQueryLog {
+    /// Threshold in microseconds; `u64::MAX` while the log is off
+    threshold_us: AtomicU64,
+    pending: Mutex<Vec<PendingQuery>>,
+    clock: RwLock<Arc<dyn Clock>>,
+}
+
+impl SlowQueryLog {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            threshold_us: AtomicU64::new(u64::MAX),
+            pending: Mutex::new(Vec::new()),
+            clock: RwLock::new(clock),
+        }
+    }
+
+    /// Record statements running at least `threshold`; `None` turns the
+    /// log off
+    pub fn set_threshold(&self, threshold: Option<Duration>) {
+        let micros = threshold.map_or(u64::MAX, |t| t.as_micros().min(u64::MAX as u128 - 1) as u64);
+        self.threshold_us.store(micros, Ordering::Relaxed);
+    }
+
+    /// Current threshold, or `None` while the log is off
+    pub fn threshold(&self) -> Option<Duration> {
+        match self.threshold_us.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    /// Clock stamping logged statements (the database's clock)
+    pub(crate) fn set_clock(&self, clock: Arc<dyn Clock>) {
+        *self.clock.write().unwrap_or_else(|e| e.into_inner()) = clock;
+    }
+
+    fn record(&self, sql: &str, duration: Duration) {
+        let threshold = self.threshold_us.load(Ordering::Relaxed);
+        if threshold == u64::MAX || (duration.as_micros() as u64) < threshold {
+            return;
+        }
+        // The log's own statements and plans would feed back into it
+        let explain = sql
+            .trim_start()
+            .get(..7)
+            .is_some_and(|head| head.eq_ignore_ascii_case("EXPLAIN"));
+        if explain || sql.contains("slow_queries") {
+            return;
+        }
+        let occurred_at = self.clock.read().unwrap_or_else(|e| e.into_inner()).timestamp();
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.len() < MAX_PENDING {
+            pending.push(PendingQuery {
+                occurred_at,
+                sql: sql.trim().to_string(),
+                duration,
+            });
+        }
+    }
+
+    /// Write queued statements with their plans, keeping the newest
+    /// `max_rows`; returns the number written
+    pub(crate) fn flush(&self, conn: &Connection, max_rows: usize) -> Result<usize> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        for query in &pending {
+            conn.execute(
+                "INSERT INTO slow_queries (occurred_at, sql, parameters, duration_us, query_plan)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    query.occurred_at,
+                    query.sql,
+                    parameter_shape(&query.sql),
+                    query.duration.as_micros() as i64,
+                    query_plan(conn, &query.sql),
+                ],
+            )?;
+        }
+        if !pending.is_empty() {
+            conn.execute(
+                "DELETE FROM slow_queries WHERE id NOT IN (
+                     SELECT id FROM slow_queries ORDER BY id DESC LIMIT ?1)",
+                [max_rows as i64],
+            )?;
+        }
+        Ok(pending.len())
+    }
+}
+
+impl std::fmt::Debug for SlowQueryLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlowQueryLog")
+            .field("threshold", &self.threshold())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Installs the profile hook on every pooled connection, feeding that
+/// database's log
+///
+/// rusqlite's `profile` takes a plain function, so the hook is registered
+/// through `sqlite3_profile` with the log as its context pointer. The pool
+/// keeps this customizer (and with it the log) alive for as long as any
+/// of its connections can run a statement.
+#[derive(Debug)]
+pub(crate) struct ProfileHook(pub(crate) Arc<SlowQueryLog>);
+
+impl r2d2::CustomizeConnection<Connection, rusqlite::Error> for ProfileHook {
+    fn on_acquire(&self, conn: &mut Connection) -> std::result::Result<(), rusqlite::Error> {
+        // Cheap while the log is off: the hook returns at once
+        let log = Arc::as_ptr(&self.0) as *mut c_void;
+        // SAFETY: the handle belongs to `conn`, and `log` outlives every
+        // statement run on it (see above)
+        unsafe {
+            ffi::sqlite3_profile(conn.handle(), Some(on_profile), log);
+        }
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn on_profile(log: *mut c_void, sql: *const c_char, nanoseconds: u64) {
+    let log = &*(log as *const SlowQueryLog);
+    let sql = CStr::from_ptr(sql).to_string_lossy();
+    // Never unwind into SQLite
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        log.record(&sql, Duration::from_nanos(nanoseconds))
+    }));
+}
+
+/// Placeholders of `sql` in order, e.g. `?1, ?2` or `:icao`
+///
+/// Only the shape is kept: bound values may be positions or identifiers.
+fn parameter_shape(sql: &str) -> String {
+    let mut placeholders = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+    let mut quote = None;
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '?' | ':' | '@' | '$') => {
+                let mut end = i + c.len_utf8();
+                while let Some(&(j, next)) = chars.peek() {
+                    let continues = if c == '?' {
+                        next.is_ascii_digit()
+                    } else {
+                        next.is_alphanumeric() || next == '_'
+                    };
+                    if !continues {
+                        break;
+                    }
+                    end = j + next.len_utf8();
+                    chars.next();
+                }
+                // A bare ':' or '$' is not a placeholder
+                if c == '?' || end > i + 1 {
+                    placeholders.push(&sql[i..end]);
+                }
+            }
+            _ => {}
+        }
+    }
+    placeholders.join(", ")
+}
+
+/// `EXPLAIN QUERY PLAN` of `sql`, one line per step indented by depth
+fn query_plan(conn: &Connection, sql: &str) -> Option<String> {
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql)).ok()?;
+    // Planning doesn't need values, so placeholders stay unbound
+    let steps = stmt
+        .raw_query()
+        .mapped(|row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(3)?)))
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .ok()?;
+
+    let mut depths: Vec<(i64, usize)> = Vec::new();
+    let mut lines = Vec::new();
+    for (id, parent, detail) in steps {
+        let depth = depths
+            .iter()
+            .find(|(step, _)| *step == parent)
+            .map_or(0, |(_, depth)| depth + 1);
+        depths.push((id, depth));
+        lines.push(format!("{}{}", "  ".repeat(depth), detail));
+    }
+    Some(lines.join("\n")).filter(|plan| !plan.is_empty())
+}
+
+/// Logged statements, oldest first
+pub(crate) fn list(conn: &Connection) -> Result<Vec<SlowQuery>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, occurred_at, sql, parameters, duration_us, query_plan
+         FROM slow_queries ORDER BY id",
+    )?;
+    let queries = stmt
+        .query_map([], |row| {
+            Ok(SlowQuery {
+                id: row.get(0)?,
+                occurred_at: row.get(1)?,
+                sql: row.get(2)?,
+                parameters: row.get(3)?,
+                duration_us: row.get(4)?,
+                query_plan: row.get(5)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(queries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameter_shape() {
+        assert_eq!(
+            parameter_shape("SELECT * FROM airports WHERE icao = ?1 AND name LIKE ?2"),
+            "?1, ?2"
+        );
+        assert_eq!(parameter_shape("SELECT ?, ? WHERE x IN (?, ?)"), "?, ?, ?, ?");
+        assert_eq!(parameter_shape("SELECT :icao, @lat, $lon"), ":icao, @lat, $lon");
+        assert_eq!(parameter_shape("SELECT '?1', \"a:b\" FROM t WHERE a = ?3"), "?3");
+        assert_eq!(parameter_shape("SELECT 1"), "");
+    }
+
+    #[test]
+    fn test_query_plan() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT, lat REAL);
+             CREATE INDEX idx_t_name ON t(name);",
+        )
+        .unwrap();
+
+        let scan = SlowQuery {
+            id: 1,
+            occurred_at: 0,
+            sql: String::new(),
+            parameters: String::new(),
+            duration_us: 0,
+            query_plan: query_plan(&conn, "SELECT * FROM t WHERE lat > ?1"),
+        };
+        assert!(scan.query_plan.as_deref().unwrap().starts_with("SCAN t"));
+        assert!(scan.scans_table());
+
+        let search = SlowQuery {
+            query_plan: query_plan(&conn, "SELECT * FROM t WHERE name = ?1"),
+            ..scan.clone()
+        };
+        assert!(search.query_plan.as_deref().unwrap().contains("USING INDEX idx_t_name"));
+        assert!(!search.scans_table());
+
+        assert_eq!(query_plan(&conn, "SELECT * FROM missing"), None);
+    }
+}
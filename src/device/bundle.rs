@@ -0,0 +1,648 @@
+use crate::db::query::{waypoint_from_row, WAYPOINT_COLUMNS};
+use crate::db::Database;
+use crate::device::Device;
+use crate::encryption::{generate_salt, AesEncryptor, EncryptedData};
+use crate::error::{AeroBaseError, Result};
+use crate::favorites::{ItemKind, SavedSearch};
+use crate::flight::format;
+use crate::flight::repository::{self, StoredFlightPlan};
+use crate::import::{ImportControl, ImportPhase};
+use crate::logging;
+use crate::models::waypoint::{Waypoint, WaypointType};
+use crate::repository::waypoint::WaypointRepository;
+use base64::{engine::general_purpose, Engine as _};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// 导出包格式版本
+///
+/// 版本 1 为整体加密的 JSON 信封；版本 2 起为二进制头加流式密文。
+pub const BUNDLE_FORMAT_VERSION: u32 = 2;
+
+/// 流式导出包的文件头标识
+const BUNDLE_MAGIC: &[u8; 8] = b"AEROBNDL";
+
+/// 设备数据导出包（明文内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceBundle {
+    pub format_version: u32,
+    pub exported_at: i64,
+    pub device: Device,
+    pub flight_plans: Vec<StoredFlightPlan>,
+    /// 用户航路点（GPS 类型）
+    #[serde(default)]
+    pub waypoints: Vec<Waypoint>,
+    #[serde(default)]
+    pub settings: UserSettings,
+    /// 仅在调用方显式提供时包含
+    pub private_key_pem: Option<String>,
+}
+
+/// 导出包中的用户设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserSettings {
+    pub favorites: Vec<Favorite>,
+    pub saved_searches: Vec<SavedSearch>,
+}
+
+/// 收藏记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Favorite {
+    pub id: String,
+    pub profile_id: String,
+    pub kind: ItemKind,
+    pub item_id: String,
+    pub created_at: i64,
+}
+
+/// 版本 1 的加密信封
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedBundle {
+    format_version: u32,
+    salt: String,
+    payload: EncryptedData,
+}
+
+/// 导入时未覆盖的本地记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleConflict {
+    pub entity: String,
+    pub id: String,
+    /// 导出包中的版本（无版本的记录为 None）
+    pub bundled_version: Option<i64>,
+    pub local_version: Option<i64>,
+}
+
+/// 导入结果摘要
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub device_id: String,
+    /// 新增或更新的航行计划数
+    pub flight_plans: usize,
+    pub waypoints: usize,
+    pub favorites: usize,
+    pub saved_searches: usize,
+    /// 本地版本更新或与导出包不一致而保留本地的记录
+    pub conflicts: Vec<BundleConflict>,
+}
+
+impl DeviceBundle {
+    /// 从数据库收集指定设备的数据
+    pub fn collect(db: &Database, device_id: &str) -> Result<Self> {
+        let conn = db.get_conn()?;
+
+        let device = conn
+            .query_row(
+                "SELECT id, fingerprint, hardware_info, created_at, last_seen
+                 FROM devices WHERE id = ?1",
+                [device_id],
+                |row| {
+                    Ok(Device {
+                        id: row.get(0)?,
+                        fingerprint: row.get(1)?,
+                        hardware_info: row.get(2)?,
+                        created_at: row.get(3)?,
+                        last_seen: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?
+            .ok_or_else(|| AeroBaseError::NotFound(format!("Device {} not found", device_id)))?;
+
+        // 设备自己的计划以及未绑定设备的计划都属于该设备上的用户数据
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM flight_plans WHERE device_id = ?1 OR device_id IS NULL
+             ORDER BY created_at, id",
+            repository::COLUMNS
+        ))?;
+        let flight_plans = stmt
+            .query_map([device_id], repository::from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM waypoints WHERE type = ?1 ORDER BY created_at, id",
+            WAYPOINT_COLUMNS
+        ))?;
+        let waypoints = stmt
+            .query_map([WaypointType::GPS.as_str()], waypoint_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: db.clock().timestamp(),
+            device,
+            flight_plans,
+            waypoints,
+            settings: collect_settings(&conn)?,
+            private_key_pem: None,
+        })
+    }
+
+    /// 附带私钥（PEM），由调用方决定是否允许导出
+    pub fn with_private_key(mut self, pem: String) -> Self {
+        self.private_key_pem = Some(pem);
+        self
+    }
+
+    /// 使用密码加密为单个文件内容
+    pub fn seal(&self, password: &str) -> Result<Vec<u8>> {
+        self.seal_to(Vec::new(), password)
+    }
+
+    /// 使用密码加密并流式写入 `writer`，返回写入器
+    pub fn seal_to<W: Write>(&self, mut writer: W, password: &str) -> Result<W> {
+        if password.is_empty() {
+            return Err(AeroBaseError::InvalidInput(
+                "Bundle password must not be empty".to_string(),
+            ));
+        }
+
+        let salt = generate_salt();
+        writer.write_all(BUNDLE_MAGIC)?;
+        writer.write_all(&BUNDLE_FORMAT_VERSION.to_be_bytes())?;
+        writer.write_all(&[salt.len() as u8])?;
+        writer.write_all(&salt)?;
+
+        let encryptor = AesEncryptor::from_password(password, &salt)?;
+        let mut stream = encryptor.stream_writer(writer)?;
+        serde_json::to_writer(&mut stream, self)?;
+        stream.finish()
+    }
+
+    /// 使用密码解密导出包（兼容版本 1 的 JSON 信封）
+    pub fn open(bytes: &[u8], password: &str) -> Result<Self> {
+        if bytes.starts_with(BUNDLE_MAGIC) {
+            return Self::open_from(bytes, password);
+        }
+
+        let sealed: SealedBundle = serde_json::from_slice(bytes)?;
+        if sealed.format_version != 1 {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Unsupported bundle format version {}",
+                sealed.format_version
+            )));
+        }
+
+        let salt = general_purpose::STANDARD
+            .decode(&sealed.salt)
+            .map_err(|e| AeroBaseError::InvalidInput(format!("Invalid bundle salt: {}", e)))?;
+        let encryptor = AesEncryptor::from_password(password, &salt)?;
+        let plaintext = encryptor.decrypt(&sealed.payload)?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// 从 `reader` 流式读取并解密导出包
+    pub fn open_from<R: Read>(mut reader: R, password: &str) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != BUNDLE_MAGIC {
+            return Err(AeroBaseError::InvalidInput("Not a device bundle".to_string()));
+        }
+
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_be_bytes(version);
+        if version < 2 || version > BUNDLE_FORMAT_VERSION {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Unsupported bundle format version {}",
+                version
+            )));
+        }
+
+        let mut salt_len = [0u8; 1];
+        reader.read_exact(&mut salt_len)?;
+        let mut salt = vec![0u8; salt_len[0] as usize];
+        reader.read_exact(&mut salt)?;
+
+        let encryptor = AesEncryptor::from_password(password, &salt)?;
+        let stream = encryptor.stream_reader(reader)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(stream))?)
+    }
+
+    /// 将导出包内容写入数据库（单个事务）
+    pub fn restore(&self, db: &Database) -> Result<ImportSummary> {
+        self.restore_with(db, &ImportControl::default())
+    }
+
+    /// 写入数据库并按条目报告进度；取消时事务回滚
+    ///
+    /// 航行计划按 `version` 合并：只有导出包中的版本更新时才覆盖本地，
+    /// 本地版本不低于导出包且内容不同的计划记入 `conflicts`。已存在的航路点、
+    /// 收藏和已保存搜索保持不变。
+    pub fn restore_with(&self, db: &Database, control: &ImportControl) -> Result<ImportSummary> {
+        let items = self.flight_plans.len()
+            + self.waypoints.len()
+            + self.settings.favorites.len()
+            + self.settings.saved_searches.len();
+        let total = Some(items);
+        let conn = db.get_background_conn()?;
+        let tx = conn.unchecked_transaction()?;
+        control.report(ImportPhase::Writing, 0, total)?;
+
+        tx.execute(
+            "INSERT OR IGNORE INTO devices (id, fingerprint, hardware_info, created_at, last_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                &self.device.id,
+                &self.device.fingerprint,
+                &self.device.hardware_info,
+                self.device.created_at,
+                self.device.last_seen,
+            ],
+        )?;
+
+        let mut summary = ImportSummary {
+            device_id: self.device.id.clone(),
+            ..Default::default()
+        };
+        let mut done = 0;
+
+        // 航路点先于引用它们的计划写入
+        for waypoint in &self.waypoints {
+            restore_waypoint(&tx, db, waypoint, &mut summary)?;
+            done += 1;
+            control.report(ImportPhase::Writing, done, total)?;
+        }
+
+        for stored in &self.flight_plans {
+            restore_flight_plan(&tx, stored, &mut summary)?;
+            done += 1;
+            control.report(ImportPhase::Writing, done, total)?;
+        }
+
+        for favorite in &self.settings.favorites {
+            summary.favorites += tx.execute(
+                "INSERT OR IGNORE INTO favorites (id, profile_id, item_type, item_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    &favorite.id,
+                    &favorite.profile_id,
+                    favorite.kind.as_str(),
+                    &favorite.item_id,
+                    favorite.created_at,
+                ],
+            )?;
+            done += 1;
+            control.report(ImportPhase::Writing, done, total)?;
+        }
+
+        for search in &self.settings.saved_searches {
+            summary.saved_searches += tx.execute(
+                "INSERT OR IGNORE INTO saved_searches (id, profile_id, name, criteria_json, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    &search.id,
+                    &search.profile_id,
+                    &search.name,
+                    serde_json::to_string(&search.criteria)?,
+                    search.created_at,
+                ],
+            )?;
+            done += 1;
+            control.report(ImportPhase::Writing, done, total)?;
+        }
+
+        control.report(ImportPhase::Committing, items, total)?;
+        tx.commit()?;
+
+        log::info!(
+            "Imported bundle for device {}: {} flight plan(s), {} waypoint(s), {} conflict(s)",
+            logging::id(&self.device.id),
+            summary.flight_plans,
+            summary.waypoints,
+            summary.conflicts.len()
+        );
+
+        Ok(summary)
+    }
+}
+
+/// 收藏和已保存搜索
+fn collect_settings(conn: &Connection) -> Result<UserSettings> {
+    let mut stmt = conn.prepare(
+        "SELECT id, profile_id, item_type, item_id, created_at
+         FROM favorites ORDER BY created_at, id",
+    )?;
+    let favorites = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter_map(|(id, profile_id, item_type, item_id, created_at)| {
+            Some(Favorite {
+                id,
+                profile_id,
+                kind: ItemKind::from_str(&item_type)?,
+                item_id,
+                created_at,
+            })
+        })
+        .collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT id, profile_id, name, criteria_json, created_at
+         FROM saved_searches ORDER BY created_at, id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let saved_searches = rows
+        .into_iter()
+        .map(|(id, profile_id, name, json, created_at)| {
+            Ok(SavedSearch {
+                id,
+                profile_id,
+                name,
+                criteria: serde_json::from_str(&json)?,
+                created_at,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(UserSettings {
+        favorites,
+        saved_searches,
+    })
+}
+
+/// 插入本地没有的航路点；同一标识和区域已被其他航路点占用时记为冲突
+fn restore_waypoint(
+    conn: &Connection,
+    db: &Database,
+    waypoint: &Waypoint,
+    summary: &mut ImportSummary,
+) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM waypoints WHERE id = ?1)",
+        [&waypoint.id],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Ok(());
+    }
+
+    match WaypointRepository::insert_in(conn, db.id_strategy(), waypoint) {
+        Ok(_) => summary.waypoints += 1,
+        Err(AeroBaseError::InvalidInput(_))
+            if WaypointRepository::natural_match(conn, waypoint)?.is_some() =>
+        {
+            summary.conflicts.push(BundleConflict {
+                entity: "waypoint".to_string(),
+                id: waypoint.id.clone(),
+                bundled_version: None,
+                local_version: None,
+            });
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+/// 按版本合并航行计划
+///
+/// 用 upsert 而不是 REPLACE：REPLACE 会先删除旧行，级联删掉
+/// `flight_plan_aircraft` 中的关联。
+fn restore_flight_plan(
+    conn: &Connection,
+    stored: &StoredFlightPlan,
+    summary: &mut ImportSummary,
+) -> Result<()> {
+    let plan_json = format::plan_to_json(&stored.plan)?;
+    let written = conn.execute(
+        "INSERT INTO flight_plans
+         (id, device_id, name, departure, destination, plan_json, version, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(id) DO UPDATE SET
+             device_id = excluded.device_id, name = excluded.name,
+             departure = excluded.departure, destination = excluded.destination,
+             plan_json = excluded.plan_json, version = excluded.version,
+             updated_at = excluded.updated_at
+         WHERE excluded.version > flight_plans.version",
+        rusqlite::params![
+            &stored.id,
+            &stored.device_id,
+            &stored.name,
+            &stored.plan.departure,
+            &stored.plan.destination,
+            &plan_json,
+            stored.version,
+            stored.created_at,
+            stored.updated_at,
+        ],
+    )?;
+    if written > 0 {
+        summary.flight_plans += 1;
+        return Ok(());
+    }
+
+    // 本地版本不低于导出包：内容相同视为已同步，否则保留本地并报告
+    let (local_version, local_name, local_json): (i64, Option<String>, String) = conn.query_row(
+        "SELECT version, name, plan_json FROM flight_plans WHERE id = ?1",
+        [&stored.id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    if local_name != stored.name || local_json != plan_json {
+        summary.conflicts.push(BundleConflict {
+            entity: "flight_plan".to_string(),
+            id: stored.id.clone(),
+            bundled_version: Some(stored.version),
+            local_version: Some(local_version),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::favorites::{FavoritesManager, SearchCriteria};
+    use crate::flight::planner::save_user_waypoints;
+    use crate::flight::repository::FlightPlanRepository;
+    use crate::flight::{FlightPlan, FlightPlanBuilder};
+    use crate::models::Coordinate;
+    use crate::Config;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn insert_device(db: &Database) {
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO devices (id, fingerprint, created_at, last_seen)
+                 VALUES ('DEV1', 'fp', 0, 0)",
+                [],
+            )
+            .unwrap();
+    }
+
+    fn shuttle() -> FlightPlan {
+        FlightPlanBuilder::new()
+            .departure("ZBAA")
+            .destination("ZSSS")
+            .cruise_altitude(35000)
+            .cruise_speed(450)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let (source, _source_file) = setup_test_db();
+        insert_device(&source);
+
+        let repo = FlightPlanRepository::new(Arc::clone(&source)).unwrap();
+        repo.insert(&shuttle(), Some("Shuttle"), Some("DEV1")).unwrap();
+        let waypoint_ids = save_user_waypoints(&source, "USR", &[Coordinate::new(35.0, 119.0)]).unwrap();
+        let favorites = FavoritesManager::new(Arc::clone(&source)).unwrap();
+        favorites.star("pilot", ItemKind::Waypoint, &waypoint_ids[0]).unwrap();
+        favorites
+            .save_search("pilot", "Beijing", &SearchCriteria {
+                ident_prefix: Some("ZB".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let bytes = DeviceBundle::collect(&source, "DEV1")
+            .unwrap()
+            .with_private_key("PEM".to_string())
+            .seal("correct horse")
+            .unwrap();
+        assert!(bytes.starts_with(BUNDLE_MAGIC));
+
+        assert!(DeviceBundle::open(&bytes, "wrong password").is_err());
+
+        let bundle = DeviceBundle::open(&bytes, "correct horse").unwrap();
+        assert_eq!(bundle.private_key_pem.as_deref(), Some("PEM"));
+
+        let (target, _target_file) = setup_test_db();
+        let summary = bundle.restore(&target).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                device_id: "DEV1".to_string(),
+                flight_plans: 1,
+                waypoints: 1,
+                favorites: 1,
+                saved_searches: 1,
+                conflicts: Vec::new(),
+            }
+        );
+
+        let restored = FlightPlanRepository::new(Arc::clone(&target)).unwrap().list().unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].name.as_deref(), Some("Shuttle"));
+        assert_eq!(restored[0].device_id.as_deref(), Some("DEV1"));
+
+        let waypoint = WaypointRepository::new(Arc::clone(&target))
+            .unwrap()
+            .get(&waypoint_ids[0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(waypoint.name, "USR01");
+        let favorites = FavoritesManager::new(target).unwrap();
+        assert_eq!(favorites.favorites("pilot").unwrap()[0].label, "USR01");
+        assert_eq!(favorites.saved_searches("pilot").unwrap()[0].name, "Beijing");
+
+        // Importing again changes nothing
+        let (again, _again_file) = setup_test_db();
+        bundle.restore(&again).unwrap();
+        let summary = bundle.restore(&again).unwrap();
+        assert_eq!(summary.flight_plans, 0);
+        assert_eq!(summary.waypoints, 0);
+        assert!(summary.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_restore_keeps_newer_local_plan() {
+        let (source, _source_file) = setup_test_db();
+        insert_device(&source);
+        let repo = FlightPlanRepository::new(Arc::clone(&source)).unwrap();
+        let stored = repo.insert(&shuttle(), Some("Shuttle"), Some("DEV1")).unwrap();
+        let bundle = DeviceBundle::collect(&source, "DEV1").unwrap();
+
+        // The local copy moved on after the export and has an aircraft assigned
+        let edited = FlightPlan {
+            cruise_altitude: 37000,
+            ..shuttle()
+        };
+        repo.update(&stored.id, &edited, Some("Shuttle"), stored.version).unwrap();
+        let conn = source.get_conn().unwrap();
+        conn.execute(
+            "INSERT INTO aircraft
+             (id, aircraft_type, cruise_speed, cruise_altitude, fuel_flow_gph, equipment, created_at)
+             VALUES ('AC1', 'A320', 450, 37000, 700.0, 'S', 0)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO flight_plan_aircraft (plan_id, aircraft_id) VALUES (?1, 'AC1')",
+            [&stored.id],
+        )
+        .unwrap();
+        drop(conn);
+
+        let summary = bundle.restore(&source).unwrap();
+        assert_eq!(summary.flight_plans, 0);
+        assert_eq!(
+            summary.conflicts,
+            vec![BundleConflict {
+                entity: "flight_plan".to_string(),
+                id: stored.id.clone(),
+                bundled_version: Some(1),
+                local_version: Some(2),
+            }]
+        );
+        let local = repo.get(&stored.id).unwrap().unwrap();
+        assert_eq!(local.plan.cruise_altitude, 37000);
+
+        // A newer bundled version replaces the plan without dropping its aircraft
+        let mut newer = DeviceBundle::collect(&source, "DEV1").unwrap();
+        newer.flight_plans[0].version = 3;
+        newer.flight_plans[0].plan.cruise_altitude = 39000;
+        let summary = newer.restore(&source).unwrap();
+        assert_eq!(summary.flight_plans, 1);
+        assert!(summary.conflicts.is_empty());
+        assert_eq!(repo.get(&stored.id).unwrap().unwrap().plan.cruise_altitude, 39000);
+        let linked: i64 = source
+            .get_conn()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM flight_plan_aircraft WHERE plan_id = ?1",
+                [&stored.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(linked, 1);
+    }
+}
@@ -47,7 +47,7 @@ pub fn get_hardware_info() -> Result<String> {
         "os_family": std::env::consts::FAMILY,
     });
     
-    serde_json::to_string(&info).map_err(|e| AeroBaseError::Serialization(e))
+    serde_json::to_string(&info).map_err(AeroBaseError::Serialization)
 }
 
 #[cfg(test)]
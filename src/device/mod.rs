@@ -1,11 +1,15 @@
+pub mod bundle;
 pub mod fingerprint;
 pub mod identity;
 pub mod secure;
 
+use crate::clock::Clock;
 use crate::db::Database;
 use crate::error::Result;
+use crate::import::{ImportControl, ImportPhase};
+use crate::logging;
+use crate::sync::backup::{RestoreSummary, UserDataBackup};
 use rusqlite::OptionalExtension;
-use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -19,13 +23,31 @@ pub struct Device {
     pub last_seen: i64,
 }
 
+/// 设备注册结果
+#[derive(Debug, Clone)]
+pub struct Enrollment {
+    pub device: Device,
+    /// 新设备从云端备份恢复的用户数据；已注册的设备或没有备份时为 None
+    pub restored: Option<RestoreSummary>,
+}
+
 pub struct DeviceManager {
     db: Arc<Database>,
+    clock: Arc<dyn Clock>,
 }
 
 impl DeviceManager {
     pub fn new(db: Arc<Database>) -> Result<Self> {
-        Ok(Self { db })
+        Ok(Self {
+            clock: Arc::clone(db.clock()),
+            db,
+        })
+    }
+
+    /// 设备的创建与最近在线时间取自 `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 
     pub fn get_or_create_fingerprint(&self) -> Result<Device> {
@@ -50,21 +72,22 @@ impl DeviceManager {
             .optional()?;
         
         if let Some(mut device) = existing {
-            device.last_seen = Utc::now().timestamp();
+            device.last_seen = self.clock.timestamp();
             conn.execute(
                 "UPDATE devices SET last_seen = ?1 WHERE id = ?2",
                 rusqlite::params![device.last_seen, &device.id],
             )?;
             
-            log::info!("Found existing device: {}", device.id);
+            log::info!("Found existing device: {}", logging::id(&device.id));
             Ok(device)
         } else {
+            let now = self.clock.timestamp();
             let device = Device {
                 id: Uuid::new_v4().to_string(),
                 fingerprint: fingerprint.clone(),
                 hardware_info: Some(fingerprint::get_hardware_info()?),
-                created_at: Utc::now().timestamp(),
-                last_seen: Utc::now().timestamp(),
+                created_at: now,
+                last_seen: now,
             };
             
             conn.execute(
@@ -79,7 +102,7 @@ impl DeviceManager {
                 ],
             )?;
             
-            log::info!("Created new device: {}", device.id);
+            log::info!("Created new device: {}", logging::id(&device.id));
             Ok(device)
         }
     }
@@ -129,11 +152,50 @@ impl DeviceManager {
         
         Ok(devices)
     }
+
+    /// 注册当前设备；本机首次注册时先从云端备份恢复用户数据
+    ///
+    /// 恢复在创建设备记录之前进行，同一硬件恢复出厂后重新注册会沿用备份中
+    /// 原来的设备 ID。
+    pub fn enroll(&self, backup: &UserDataBackup, password: &str) -> Result<Enrollment> {
+        let fingerprint = fingerprint::generate_fingerprint()?;
+        let known: bool = self.db.get_conn()?.query_row(
+            "SELECT EXISTS(SELECT 1 FROM devices WHERE fingerprint = ?1)",
+            [&fingerprint],
+            |row| row.get(0),
+        )?;
+        let restored = if known { None } else { backup.restore(password)? };
+        let device = self.get_or_create_fingerprint()?;
+        Ok(Enrollment { device, restored })
+    }
+
+    /// 将当前设备的数据导出为密码加密的导出包
+    pub fn export_bundle(&self, password: &str) -> Result<Vec<u8>> {
+        let device = self.get_or_create_fingerprint()?;
+        bundle::DeviceBundle::collect(&self.db, &device.id)?.seal(password)
+    }
+
+    /// 解密导出包并将其内容导入当前数据库
+    pub fn import_bundle(&self, bytes: &[u8], password: &str) -> Result<bundle::ImportSummary> {
+        self.import_bundle_with(bytes, password, &ImportControl::default())
+    }
+
+    /// 导入导出包，报告进度并支持取消
+    pub fn import_bundle_with(
+        &self,
+        bytes: &[u8],
+        password: &str,
+        control: &ImportControl,
+    ) -> Result<bundle::ImportSummary> {
+        control.report(ImportPhase::Parsing, 0, None)?;
+        bundle::DeviceBundle::open(bytes, password)?.restore_with(&self.db, control)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
     use crate::Config;
     use tempfile::NamedTempFile;
 
@@ -144,20 +206,61 @@ mod tests {
             db_path: temp_file.path().to_path_buf(),
             enable_wal: false,
             pool_size: 1,
+            ..Default::default()
         };
 
         let db = Arc::new(Database::new(&config).unwrap());
         db.migrate().unwrap();
 
-        let manager = DeviceManager::new(db).unwrap();
+        let clock = Arc::new(MockClock::at_timestamp(1_700_000_000));
+        let manager = DeviceManager::new(db).unwrap().with_clock(clock.clone());
         
         let device1 = manager.get_or_create_fingerprint().unwrap();
         assert!(!device1.id.is_empty());
+        assert_eq!(device1.created_at, 1_700_000_000);
 
+        clock.advance(chrono::TimeDelta::hours(1));
         let device2 = manager.get_or_create_fingerprint().unwrap();
         assert_eq!(device1.id, device2.id);
+        assert_eq!(device2.created_at, 1_700_000_000);
+        assert_eq!(device2.last_seen, 1_700_003_600);
         
         let devices = manager.list_devices().unwrap();
         assert_eq!(devices.len(), 1);
     }
+
+    #[test]
+    fn test_enroll_restores_backup() {
+        use crate::sync::backup::tests::{setup, MemoryStore};
+        use crate::sync::backup::BackupConfig;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(MemoryStore::default());
+
+        let old = setup(dir.path(), "old");
+        let backup = UserDataBackup::new(Arc::clone(&old), store.clone(), BackupConfig::default());
+        let manager = DeviceManager::new(Arc::clone(&old)).unwrap();
+        let first = manager.enroll(&backup, "secret").unwrap();
+        assert!(first.restored.is_none());
+        old.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO flight_plans (id, device_id, departure, destination, plan_json, created_at)
+                 VALUES ('P1', ?1, 'ZBAA', 'ZSSS', '{}', 0)",
+                [&first.device.id],
+            )
+            .unwrap();
+        backup.backup("secret").unwrap();
+
+        // Same hardware after a factory reset: plans and device ID come back
+        let new = setup(dir.path(), "new");
+        let backup = UserDataBackup::new(Arc::clone(&new), store, BackupConfig::default());
+        let manager = DeviceManager::new(Arc::clone(&new)).unwrap();
+        let enrollment = manager.enroll(&backup, "secret").unwrap();
+        assert!(enrollment.restored.unwrap().tables.contains(&("flight_plans".to_string(), 1)));
+        assert_eq!(enrollment.device.id, first.device.id);
+
+        // Already enrolled: nothing is restored again
+        assert!(manager.enroll(&backup, "secret").unwrap().restored.is_none());
+    }
 }
@@ -1,3 +1,4 @@
+use crate::clock::Clock;
 use crate::db::Database;
 use crate::encryption::{EncryptedData, HybridEncryptor, RsaKeyPair};
 use crate::error::Result;
@@ -18,18 +19,33 @@ pub struct SecureDevice {
 pub struct SecureDeviceManager {
     db: Arc<Database>,
     encryptor: HybridEncryptor,
+    clock: Arc<dyn Clock>,
 }
 
 impl SecureDeviceManager {
     pub fn new(db: Arc<Database>) -> Result<Self> {
         let encryptor = HybridEncryptor::new()?;
-        Ok(Self { db, encryptor })
+        Ok(Self {
+            clock: Arc::clone(db.clock()),
+            db,
+            encryptor,
+        })
     }
 
     /// 使用指定的RSA密钥对创建
     pub fn with_keypair(db: Arc<Database>, key_pair: RsaKeyPair) -> Result<Self> {
         let encryptor = HybridEncryptor::with_rsa_keypair(key_pair)?;
-        Ok(Self { db, encryptor })
+        Ok(Self {
+            clock: Arc::clone(db.clock()),
+            db,
+            encryptor,
+        })
+    }
+
+    /// 使用指定的时钟记录创建和最近在线时间
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 
     pub fn create_secure_device(
@@ -50,14 +66,15 @@ impl SecureDeviceManager {
 
         let public_key_pem = self.encryptor.public_key_pem()?;
 
+        let now = self.clock.timestamp();
         let device = SecureDevice {
             id: id.clone(),
             encrypted_fingerprint,
             encrypted_hardware_info,
             encrypted_aes_key: encrypted_key,
             public_key_pem,
-            created_at: chrono::Utc::now().timestamp(),
-            last_seen: chrono::Utc::now().timestamp(),
+            created_at: now,
+            last_seen: now,
         };
 
         self.save_to_db(&device)?;
@@ -92,7 +109,7 @@ impl SecureDeviceManager {
         let hardware_info_json = device
             .encrypted_hardware_info
             .as_ref()
-            .map(|hw| serde_json::to_string(hw))
+            .map(serde_json::to_string)
             .transpose()?;
 
         conn.execute(
@@ -164,7 +181,7 @@ impl SecureDeviceManager {
 
     pub fn update_last_seen(&self, device_id: &str) -> Result<()> {
         let conn = self.db.get_conn()?;
-        let now = chrono::Utc::now().timestamp();
+        let now = self.clock.timestamp();
 
         conn.execute(
             "UPDATE secure_devices SET last_seen = ?1 WHERE id = ?2",
@@ -241,12 +258,13 @@ mod tests {
     use crate::Config;
     use tempfile::NamedTempFile;
 
-    fn setup_test_db() -> Arc<Database> {
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
         let temp_file = NamedTempFile::new().unwrap();
         let config = Config {
             db_path: temp_file.path().to_path_buf(),
             enable_wal: false,
             pool_size: 1,
+            ..Default::default()
         };
 
         let db = Arc::new(Database::new(&config).unwrap());
@@ -268,12 +286,13 @@ mod tests {
         )
         .unwrap();
 
-        db
+        // NamedTempFile deletes the file on drop, so tests hold it until they end
+        (db, temp_file)
     }
 
     #[test]
     fn test_secure_device_creation() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
         let manager = SecureDeviceManager::new(db).unwrap();
 
         let device = manager
@@ -291,7 +310,7 @@ mod tests {
 
     #[test]
     fn test_encrypt_decrypt_fingerprint() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
         let manager = SecureDeviceManager::new(db).unwrap();
 
         let original_fingerprint = "my-secret-fingerprint";
@@ -309,7 +328,7 @@ mod tests {
 
     #[test]
     fn test_load_from_db() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
         let manager = SecureDeviceManager::new(db.clone()).unwrap();
 
         let device = manager
@@ -329,7 +348,7 @@ mod tests {
 
     #[test]
     fn test_list_devices() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
         let manager = SecureDeviceManager::new(db).unwrap();
 
         manager
@@ -0,0 +1,452 @@
+use crate::db::slow_query::{self, SlowQuery};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// 错误消息的脱敏策略
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedactionPolicy {
+    /// 只上报错误码和操作名，不保留消息
+    CodeOnly,
+    /// 保留消息，但替换其中的数字（坐标、高度等）、路径和 ID，并截断到指定长度
+    Scrubbed { max_message_len: usize },
+}
+
+impl RedactionPolicy {
+    /// Apply the policy to an error message
+    pub fn apply(&self, message: &str) -> Option<String> {
+        let RedactionPolicy::Scrubbed { max_message_len } = self else {
+            return None;
+        };
+        let scrubbed = message
+            .split(' ')
+            .map(|token| {
+                let word = token.trim_matches(|c: char| ",;:()\"'".contains(c));
+                if word.contains('/') || word.contains('\\') {
+                    token.replace(word, "<path>")
+                } else if word.len() >= 16 && word.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+                    token.replace(word, "<id>")
+                } else if word.chars().any(|c| c.is_ascii_digit())
+                    && word.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))
+                {
+                    token.replace(word, "<n>")
+                } else {
+                    token.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(scrubbed.chars().take(*max_message_len).collect())
+    }
+}
+
+/// 诊断信息收集配置（默认关闭，需用户同意后启用）
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticsConfig {
+    /// 是否收集错误事件
+    pub enabled: bool,
+    /// 本地最多保留的事件数，超出时丢弃最旧的事件
+    pub max_events: usize,
+    /// 单次上传的最大字节数
+    pub max_upload_bytes: usize,
+    /// 错误消息脱敏策略
+    pub redaction: RedactionPolicy,
+    /// 慢查询日志阈值（默认 None 不记录）；耗时达到阈值的语句连同查询计划写入本地，不上传
+    pub slow_query_threshold: Option<Duration>,
+    /// 本地最多保留的慢查询数，超出时丢弃最旧的记录
+    pub max_slow_queries: usize,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_events: 500,
+            max_upload_bytes: 64 * 1024,
+            redaction: RedactionPolicy::Scrubbed { max_message_len: 200 },
+            slow_query_threshold: None,
+            max_slow_queries: 200,
+        }
+    }
+}
+
+/// 一条错误诊断事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticEvent {
+    pub id: i64,
+    pub occurred_at: i64,
+    /// 出错的操作名
+    pub operation: String,
+    /// `AeroBaseError::code`
+    pub error_code: String,
+    /// 按脱敏策略处理后的消息
+    pub message: Option<String>,
+    /// 操作耗时
+    pub duration_ms: Option<i64>,
+    /// 匿名化的设备 ID
+    pub device: Option<String>,
+}
+
+/// Uploads a JSON array of diagnostic events
+///
+/// The crate ships no HTTP client; the host application plugs one in.
+pub trait DiagnosticsTransport: Send + Sync {
+    fn upload(&self, body: &str) -> Result<()>;
+}
+
+/// One-way device identifier: the ID itself never leaves the device
+fn anonymize(device_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"aerobase-diagnostics:");
+    hasher.update(device_id.as_bytes());
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// 错误诊断收集器（本地环形缓冲，同步时上传）
+pub struct Diagnostics {
+    db: Arc<Database>,
+    config: RwLock<DiagnosticsConfig>,
+    device: RwLock<Option<String>>,
+}
+
+impl Diagnostics {
+    /// 创建诊断收集器
+    pub fn new(db: Arc<Database>, config: DiagnosticsConfig) -> Result<Self> {
+        // The log belongs to the database; an instance without a threshold
+        // leaves one set elsewhere alone
+        if config.slow_query_threshold.is_some() {
+            db.slow_query_log().set_threshold(config.slow_query_threshold);
+        }
+        Ok(Self {
+            db,
+            config: RwLock::new(config),
+            device: RwLock::new(None),
+        })
+    }
+
+    /// 用户同意或撤回诊断收集；撤回时清空本地事件
+    pub fn set_enabled(&self, enabled: bool) -> Result<()> {
+        self.config.write().unwrap().enabled = enabled;
+        if !enabled {
+            self.db.get_conn()?.execute("DELETE FROM diagnostic_events", [])?;
+        }
+        Ok(())
+    }
+
+    /// 是否正在收集
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().unwrap().enabled
+    }
+
+    /// 设置设备 ID，事件中只保存其匿名哈希
+    pub fn set_device_id(&self, device_id: &str) {
+        *self.device.write().unwrap() = Some(anonymize(device_id));
+    }
+
+    /// 记录一次失败的操作
+    pub fn record(
+        &self,
+        operation: &str,
+        error: &AeroBaseError,
+        duration_ms: Option<i64>,
+    ) -> Result<()> {
+        let config = self.config.read().unwrap().clone();
+        if !config.enabled {
+            return Ok(());
+        }
+        let device = self.device.read().unwrap().clone();
+        let conn = self.db.get_conn()?;
+        conn.execute(
+            "INSERT INTO diagnostic_events
+                 (occurred_at, operation, error_code, message, duration_ms, device)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                self.db.clock().timestamp(),
+                operation,
+                error.code(),
+                config.redaction.apply(&error.to_string()),
+                duration_ms,
+                device,
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM diagnostic_events WHERE id NOT IN (
+                 SELECT id FROM diagnostic_events ORDER BY id DESC LIMIT ?1)",
+            [config.max_events as i64],
+        )?;
+        Ok(())
+    }
+
+    /// 执行操作并计时，失败时记录诊断事件后原样返回错误
+    pub fn span<T>(&self, operation: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if !self.is_enabled() {
+            return f();
+        }
+        let started = Instant::now();
+        let result = f();
+        if let Err(error) = &result {
+            let elapsed = started.elapsed().as_millis() as i64;
+            if let Err(e) = self.record(operation, error, Some(elapsed)) {
+                log::warn!("记录诊断事件失败: {}", e);
+            }
+        }
+        result
+    }
+
+    /// 本地缓存的事件（最旧在前）
+    pub fn events(&self) -> Result<Vec<DiagnosticEvent>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, occurred_at, operation, error_code, message, duration_ms, device
+             FROM diagnostic_events ORDER BY id",
+        )?;
+        let events = stmt
+            .query_map([], |row| {
+                Ok(DiagnosticEvent {
+                    id: row.get(0)?,
+                    occurred_at: row.get(1)?,
+                    operation: row.get(2)?,
+                    error_code: row.get(3)?,
+                    message: row.get(4)?,
+                    duration_ms: row.get(5)?,
+                    device: row.get(6)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(events)
+    }
+
+    /// 开启、调整或关闭（None）慢查询日志；阈值对该数据库的所有连接生效
+    pub fn set_slow_query_threshold(&self, threshold: Option<Duration>) {
+        self.config.write().unwrap().slow_query_threshold = threshold;
+        self.db.slow_query_log().set_threshold(threshold);
+    }
+
+    /// 慢查询日志（最旧在前），用于查找未命中索引的查询
+    ///
+    /// 先写入尚在内存中的记录并生成其查询计划。
+    pub fn slow_queries(&self) -> Result<Vec<SlowQuery>> {
+        let max_rows = self.config.read().unwrap().max_slow_queries;
+        let conn = self.db.get_conn()?;
+        self.db.slow_query_log().flush(&conn, max_rows)?;
+        slow_query::list(&conn)
+    }
+
+    /// 清空慢查询日志
+    pub fn clear_slow_queries(&self) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        self.db.slow_query_log().flush(&conn, 0)?;
+        conn.execute("DELETE FROM slow_queries", [])?;
+        Ok(())
+    }
+
+    /// 同步时上传一批事件（不超过上传大小上限），成功后从本地删除；返回上传的事件数
+    pub fn upload(&self, transport: &dyn DiagnosticsTransport) -> Result<usize> {
+        let max_bytes = self.config.read().unwrap().max_upload_bytes;
+        let mut batch = Vec::new();
+        let mut size = 2;
+        for event in self.events()? {
+            let encoded = serde_json::to_string(&event)?;
+            if size + encoded.len() + 1 > max_bytes {
+                break;
+            }
+            size += encoded.len() + 1;
+            batch.push(event);
+        }
+        let Some(last) = batch.last() else {
+            return Ok(0);
+        };
+
+        transport.upload(&serde_json::to_string(&batch)?)?;
+        self.db
+            .get_conn()?
+            .execute("DELETE FROM diagnostic_events WHERE id <= ?1", [last.id])?;
+        Ok(batch.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::sync::Mutex;
+    use tempfile::NamedTempFile;
+
+    #[derive(Default)]
+    struct Recorder {
+        bodies: Mutex<Vec<String>>,
+    }
+
+    impl DiagnosticsTransport for Recorder {
+        fn upload(&self, body: &str) -> Result<()> {
+            self.bodies.lock().unwrap().push(body.to_string());
+            Ok(())
+        }
+    }
+
+    fn setup(config: DiagnosticsConfig) -> (Diagnostics, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&db_config).unwrap());
+        db.migrate().unwrap();
+        (Diagnostics::new(db, config).unwrap(), temp_file)
+    }
+
+    #[test]
+    fn test_redaction() {
+        let policy = RedactionPolicy::Scrubbed { max_message_len: 200 };
+        assert_eq!(
+            policy.apply("Waypoint at 39.9125, -116.5 not found in /home/pilot/aerobase.db").unwrap(),
+            "Waypoint at <n>, <n> not found in <path>"
+        );
+        assert_eq!(
+            policy.apply("Device 3f2a9c1e-77b0-4c1d-9a7e-0d1c2b3a4f5e (ZBAA)").unwrap(),
+            "Device <id> (ZBAA)"
+        );
+        assert_eq!(RedactionPolicy::Scrubbed { max_message_len: 5 }.apply("abcdefgh").unwrap(), "abcde");
+        assert_eq!(RedactionPolicy::CodeOnly.apply("anything"), None);
+    }
+
+    #[test]
+    fn test_ring_buffer_and_upload() {
+        let (diagnostics, _temp_file) = setup(DiagnosticsConfig {
+            max_events: 3,
+            ..Default::default()
+        });
+        let error = AeroBaseError::NotFound("Airport XXXX not found".to_string());
+
+        // Off until the user opts in
+        diagnostics.record("lookup", &error, None).unwrap();
+        assert!(diagnostics.events().unwrap().is_empty());
+
+        diagnostics.set_enabled(true).unwrap();
+        diagnostics.set_device_id("device-1");
+        for i in 0..5 {
+            diagnostics.record(&format!("op{}", i), &error, None).unwrap();
+        }
+        let result: Result<()> = diagnostics.span("validate_plan", || Err(AeroBaseError::InvalidInput("bad".into())));
+        assert!(result.is_err());
+        assert_eq!(diagnostics.span("ok", || Ok(1)).unwrap(), 1);
+
+        let events = diagnostics.events().unwrap();
+        let operations: Vec<&str> = events.iter().map(|e| e.operation.as_str()).collect();
+        assert_eq!(operations, vec!["op3", "op4", "validate_plan"]);
+        assert_eq!(events[0].error_code, "not_found");
+        assert_eq!(events[2].error_code, "invalid_input");
+        assert!(events[2].duration_ms.is_some());
+        let device = events[0].device.as_deref().unwrap();
+        assert_eq!(device.len(), 16);
+        assert!(!device.contains("device-1"));
+
+        let transport = Recorder::default();
+        assert_eq!(diagnostics.upload(&transport).unwrap(), 3);
+        let uploaded: Vec<DiagnosticEvent> = serde_json::from_str(&transport.bodies.lock().unwrap()[0]).unwrap();
+        assert_eq!(uploaded, events);
+        assert!(diagnostics.events().unwrap().is_empty());
+        assert_eq!(diagnostics.upload(&transport).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_upload_size_cap() {
+        let (diagnostics, _temp_file) = setup(DiagnosticsConfig {
+            enabled: true,
+            max_upload_bytes: 400,
+            ..Default::default()
+        });
+        for _ in 0..4 {
+            diagnostics.record("sync", &AeroBaseError::Sync("timeout".into()), Some(30)).unwrap();
+        }
+        let transport = Recorder::default();
+        let first = diagnostics.upload(&transport).unwrap();
+        assert!(first > 0 && first < 4);
+        assert!(transport.bodies.lock().unwrap()[0].len() <= 400);
+        assert_eq!(diagnostics.events().unwrap().len(), 4 - first);
+
+        diagnostics.set_enabled(false).unwrap();
+        assert!(diagnostics.events().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_slow_query_log() {
+        let (diagnostics, _temp_file) = setup(DiagnosticsConfig::default());
+        let slow = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?1)
+                    SELECT COUNT(*) FROM n";
+        let run = || {
+            diagnostics
+                .db
+                .get_conn()
+                .unwrap()
+                .query_row(slow, [300_000], |row| row.get::<_, i64>(0))
+                .unwrap()
+        };
+        let logged = |queries: Vec<SlowQuery>| queries.into_iter().find(|q| q.sql == slow);
+
+        // Off by default
+        run();
+        assert!(logged(diagnostics.slow_queries().unwrap()).is_none());
+
+        diagnostics.set_slow_query_threshold(Some(Duration::from_millis(5)));
+        run();
+        diagnostics.set_slow_query_threshold(None);
+        let query = logged(diagnostics.slow_queries().unwrap()).unwrap();
+        assert_eq!(query.parameters, "?1");
+        assert!(query.duration_us >= 5_000);
+        assert!(query.query_plan.unwrap().contains("SCAN n"));
+
+        diagnostics.clear_slow_queries().unwrap();
+        assert!(diagnostics.slow_queries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_slow_query_log_is_per_database() {
+        let (logging, _logging_file) = setup(DiagnosticsConfig {
+            slow_query_threshold: Some(Duration::from_millis(5)),
+            ..Default::default()
+        });
+        let (quiet, _quiet_file) = setup(DiagnosticsConfig::default());
+        let slow = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 300000)
+                    SELECT COUNT(*) FROM n";
+        let run = |diagnostics: &Diagnostics| {
+            diagnostics.db.get_conn().unwrap().query_row(slow, [], |row| row.get::<_, i64>(0)).unwrap()
+        };
+
+        // Slow on both, but only the database with a threshold logs it
+        run(&quiet);
+        run(&logging);
+        assert!(quiet.db.slow_query_log().threshold().is_none());
+        assert!(quiet.slow_queries().unwrap().is_empty());
+        let logged = logging.slow_queries().unwrap();
+        assert_eq!(logged.iter().filter(|q| q.sql == slow).count(), 1);
+    }
+
+    #[test]
+    fn test_slow_query_stamped_with_database_clock() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let clock = Arc::new(crate::clock::MockClock::at_timestamp(1_700_000_000));
+        let db = Arc::new(Database::new(&db_config).unwrap().with_clock(clock));
+        db.migrate().unwrap();
+        let diagnostics = Diagnostics::new(
+            db,
+            DiagnosticsConfig { slow_query_threshold: Some(Duration::ZERO), ..Default::default() },
+        )
+        .unwrap();
+
+        diagnostics.db.get_conn().unwrap().query_row("SELECT 1", [], |row| row.get::<_, i64>(0)).unwrap();
+        let query = diagnostics.slow_queries().unwrap().into_iter().find(|q| q.sql == "SELECT 1").unwrap();
+        assert_eq!(query.occurred_at, 1_700_000_000);
+    }
+}
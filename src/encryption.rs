@@ -1,6 +1,9 @@
 use crate::error::{AeroBaseError, Result};
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{
+        stream::{DecryptorBE32, EncryptorBE32},
+        Aead, KeyInit, OsRng,
+    },
     Aes256Gcm, Key, Nonce,
 };
 use base64::{engine::general_purpose, Engine as _};
@@ -9,6 +12,7 @@ use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fmt;
+use std::io::{self, Read, Write};
 use zeroize::Zeroize;
 
 
@@ -17,6 +21,12 @@ const AES_KEY_SIZE: usize = 32;
 const NONCE_SIZE: usize = 12;
 const RSA_KEY_BITS: usize = 2048;
 const PBKDF2_ITERATIONS: u32 = 100_000;
+/// 流式加密每段明文的长度
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// STREAM（BE32）随机 nonce 前缀长度：96 位 nonce 减去 32 位计数器和 1 字节结束标志
+const STREAM_NONCE_SIZE: usize = 7;
+/// 每段密文附带的 GCM 认证标签长度
+const STREAM_TAG_SIZE: usize = 16;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptedData {
@@ -69,6 +79,31 @@ impl RsaKeyPair {
             .to_public_key_pem(rsa::pkcs8::LineEnding::LF)
             .map_err(|e| AeroBaseError::DeviceFingerprint(format!("公钥PEM导出失败: {}", e)))
     }
+
+    /// RSASSA-PKCS1-v1_5 / SHA-256 签名
+    pub fn sign(&self, data: &[u8]) -> Vec<u8> {
+        use rsa::signature::{SignatureEncoding, Signer};
+        let signing_key = rsa::pkcs1v15::SigningKey::<Sha256>::new(self.private_key.clone());
+        signing_key.sign(data).to_vec()
+    }
+}
+
+/// 使用公钥验证 `RsaKeyPair::sign` 生成的签名
+pub fn verify_signature(public_key: &RsaPublicKey, data: &[u8], signature: &[u8]) -> Result<()> {
+    use rsa::signature::Verifier;
+    let verifying_key = rsa::pkcs1v15::VerifyingKey::<Sha256>::new(public_key.clone());
+    let signature = rsa::pkcs1v15::Signature::try_from(signature)
+        .map_err(|e| AeroBaseError::DeviceFingerprint(format!("签名格式无效: {}", e)))?;
+    verifying_key
+        .verify(data, &signature)
+        .map_err(|_| AeroBaseError::DeviceFingerprint("签名验证失败".to_string()))
+}
+
+/// 从 PEM 导入公钥
+pub fn public_key_from_pem(pem: &str) -> Result<RsaPublicKey> {
+    use rsa::pkcs8::DecodePublicKey;
+    RsaPublicKey::from_public_key_pem(pem)
+        .map_err(|e| AeroBaseError::DeviceFingerprint(format!("公钥PEM导入失败: {}", e)))
 }
 
 impl Drop for RsaKeyPair {
@@ -140,7 +175,7 @@ impl AesEncryptor {
 
         Ok(EncryptedData {
             ciphertext: general_purpose::STANDARD.encode(&ciphertext),
-            nonce: general_purpose::STANDARD.encode(&nonce_bytes),
+            nonce: general_purpose::STANDARD.encode(nonce_bytes),
             algorithm: "AES-256-GCM".to_string(),
         })
     }
@@ -174,6 +209,36 @@ impl AesEncryptor {
         self.encrypt(plaintext.as_bytes())
     }
 
+    /// 流式加密写入 `writer`：按 64 KiB 分段的 STREAM 构造（AES-256-GCM），
+    /// 截断、重排或替换分段都会在解密时被发现；写完后必须调用 `finish`
+    pub fn stream_writer<W: Write>(&self, mut writer: W) -> Result<StreamEncryptor<W>> {
+        let mut nonce = [0u8; STREAM_NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+        writer.write_all(&nonce)?;
+        Ok(StreamEncryptor {
+            writer,
+            stream: Some(EncryptorBE32::from_aead(self.cipher(), nonce.as_slice().into())),
+            buffer: Vec::with_capacity(STREAM_CHUNK_SIZE),
+        })
+    }
+
+    /// 流式解密 `stream_writer` 写出的内容
+    pub fn stream_reader<R: Read>(&self, mut reader: R) -> Result<StreamDecryptor<R>> {
+        let mut nonce = [0u8; STREAM_NONCE_SIZE];
+        reader.read_exact(&mut nonce)?;
+        Ok(StreamDecryptor {
+            reader,
+            stream: Some(DecryptorBE32::from_aead(self.cipher(), nonce.as_slice().into())),
+            plaintext: Vec::new(),
+            pos: 0,
+            peeked: None,
+        })
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key))
+    }
+
     pub fn decrypt_string(&self, encrypted: &EncryptedData) -> Result<String> {
         let plaintext = self.decrypt(encrypted)?;
         String::from_utf8(plaintext)
@@ -181,6 +246,122 @@ impl AesEncryptor {
     }
 }
 
+/// 流式加密写入器（由 `AesEncryptor::stream_writer` 创建）
+pub struct StreamEncryptor<W: Write> {
+    writer: W,
+    stream: Option<EncryptorBE32<Aes256Gcm>>,
+    /// 尚未加密的明文，最多一个分段；最后一段留到 `finish` 时加密
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> StreamEncryptor<W> {
+    /// 加密最后一段并返回底层写入器；未调用时密文会被视为截断
+    pub fn finish(mut self) -> Result<W> {
+        let stream = self
+            .stream
+            .take()
+            .ok_or_else(|| AeroBaseError::DeviceFingerprint("加密流已结束".to_string()))?;
+        let ciphertext = stream
+            .encrypt_last(self.buffer.as_slice())
+            .map_err(|e| AeroBaseError::DeviceFingerprint(format!("AES加密失败: {}", e)))?;
+        self.writer.write_all(&ciphertext)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| io::Error::other("加密流已结束"))?;
+        let ciphertext = stream
+            .encrypt_next(self.buffer.as_slice())
+            .map_err(|e| io::Error::other(format!("AES加密失败: {}", e)))?;
+        self.writer.write_all(&ciphertext)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for StreamEncryptor<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // A full buffer is only written once more data shows it isn't the last
+        if self.buffer.len() == STREAM_CHUNK_SIZE && !data.is_empty() {
+            self.write_chunk()?;
+        }
+        let n = data.len().min(STREAM_CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// 流式解密读取器（由 `AesEncryptor::stream_reader` 创建）
+pub struct StreamDecryptor<R: Read> {
+    reader: R,
+    stream: Option<DecryptorBE32<Aes256Gcm>>,
+    plaintext: Vec<u8>,
+    pos: usize,
+    /// 为判断当前分段是否最后一段而预读的下一段首字节
+    peeked: Option<u8>,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let full = STREAM_CHUNK_SIZE + STREAM_TAG_SIZE;
+        let mut chunk = Vec::with_capacity(full);
+        chunk.extend(self.peeked.take());
+        (&mut self.reader)
+            .take((full - chunk.len()) as u64)
+            .read_to_end(&mut chunk)?;
+        if chunk.len() == full {
+            let mut byte = [0u8; 1];
+            self.peeked = loop {
+                match self.reader.read(&mut byte) {
+                    Ok(0) => break None,
+                    Ok(_) => break Some(byte[0]),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+        }
+
+        let decrypted = if self.peeked.is_some() {
+            self.stream
+                .as_mut()
+                .ok_or_else(|| io::Error::other("解密流已结束"))?
+                .decrypt_next(chunk.as_slice())
+        } else {
+            self.stream
+                .take()
+                .ok_or_else(|| io::Error::other("解密流已结束"))?
+                .decrypt_last(chunk.as_slice())
+        };
+        self.plaintext = decrypted
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("AES解密失败: {}", e)))?;
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plaintext.len() {
+            if self.stream.is_none() {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let n = out.len().min(self.plaintext.len() - self.pos);
+        out[..n].copy_from_slice(&self.plaintext[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
 impl Drop for AesEncryptor {
     fn drop(&mut self) {
         self.key.zeroize();
@@ -349,6 +530,40 @@ mod tests {
         assert_eq!(plaintext, decrypted);
     }
 
+    #[test]
+    fn test_stream_encryption() {
+        let encryptor = AesEncryptor::new().unwrap();
+        // Empty, short, exactly one chunk, and several chunks with a tail
+        for len in [0, 100, STREAM_CHUNK_SIZE, 3 * STREAM_CHUNK_SIZE + 5] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut writer = encryptor.stream_writer(Vec::new()).unwrap();
+            writer.write_all(&plaintext).unwrap();
+            let ciphertext = writer.finish().unwrap();
+
+            let mut decrypted = Vec::new();
+            encryptor
+                .stream_reader(ciphertext.as_slice())
+                .unwrap()
+                .read_to_end(&mut decrypted)
+                .unwrap();
+            assert_eq!(decrypted, plaintext);
+
+            // Dropping the final chunk is detected
+            if len > STREAM_CHUNK_SIZE {
+                let truncated = &ciphertext[..STREAM_NONCE_SIZE + STREAM_CHUNK_SIZE + STREAM_TAG_SIZE];
+                let mut reader = encryptor.stream_reader(truncated).unwrap();
+                assert!(reader.read_to_end(&mut Vec::new()).is_err());
+            }
+        }
+
+        let other = AesEncryptor::new().unwrap();
+        let mut writer = encryptor.stream_writer(Vec::new()).unwrap();
+        writer.write_all(b"secret").unwrap();
+        let ciphertext = writer.finish().unwrap();
+        let mut reader = other.stream_reader(ciphertext.as_slice()).unwrap();
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+    }
+
     #[test]
     fn test_rsa_encryption() {
         let encryptor = RsaEncryptor::new().unwrap();
@@ -403,6 +618,16 @@ mod tests {
         assert_eq!(plaintext, decrypted);
     }
 
+    #[test]
+    fn test_sign_and_verify() {
+        let key_pair = RsaKeyPair::generate().unwrap();
+        let signature = key_pair.sign(b"payload");
+
+        let public_key = public_key_from_pem(&key_pair.public_key_to_pem().unwrap()).unwrap();
+        assert!(verify_signature(&public_key, b"payload", &signature).is_ok());
+        assert!(verify_signature(&public_key, b"tampered", &signature).is_err());
+    }
+
     #[test]
     fn test_hash_sha256() {
         let data = b"test data";
@@ -1,3 +1,4 @@
+use crate::flight::parser::RouteSuggestion;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, AeroBaseError>;
@@ -31,6 +32,37 @@ pub enum AeroBaseError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    /// A route string token that couldn't be resolved; `position` is the
+    /// token's byte offset in the route string
+    #[error("Route error at {position} ({token}): {reason}")]
+    RouteParse {
+        position: usize,
+        token: String,
+        reason: String,
+        /// Likely intended fixes, best first
+        suggestions: Vec<RouteSuggestion>,
+    },
+
+    #[error("Version conflict on {entity} {id}: expected version {expected}, found {actual}")]
+    Conflict {
+        entity: String,
+        id: String,
+        expected: i64,
+        actual: i64,
+    },
+
+    #[error("Rate limited: client {client} may retry in {retry_after_ms} ms")]
+    RateLimited { client: String, retry_after_ms: u64 },
+
+    #[error("Daily quota of {limit} queries exceeded for device {device}")]
+    QuotaExceeded { device: String, limit: u32 },
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
     #[error("Pool error: {0}")]
     Pool(String),
 
@@ -38,6 +70,31 @@ pub enum AeroBaseError {
     Unknown(String),
 }
 
+impl AeroBaseError {
+    /// Stable machine-readable code for the error kind
+    pub fn code(&self) -> &'static str {
+        match self {
+            AeroBaseError::Database(_) => "database",
+            AeroBaseError::Io(_) => "io",
+            AeroBaseError::Serialization(_) => "serialization",
+            AeroBaseError::DeviceFingerprint(_) => "device_fingerprint",
+            AeroBaseError::SpatialQuery(_) => "spatial_query",
+            AeroBaseError::FlightPlanning(_) => "flight_planning",
+            AeroBaseError::Sync(_) => "sync",
+            AeroBaseError::InvalidInput(_) => "invalid_input",
+            AeroBaseError::NotFound(_) => "not_found",
+            AeroBaseError::RouteParse { .. } => "route_parse",
+            AeroBaseError::Conflict { .. } => "conflict",
+            AeroBaseError::RateLimited { .. } => "rate_limited",
+            AeroBaseError::QuotaExceeded { .. } => "quota_exceeded",
+            AeroBaseError::Forbidden(_) => "forbidden",
+            AeroBaseError::Cancelled(_) => "cancelled",
+            AeroBaseError::Pool(_) => "pool",
+            AeroBaseError::Unknown(_) => "unknown",
+        }
+    }
+}
+
 impl From<r2d2::Error> for AeroBaseError {
     fn from(err: r2d2::Error) -> Self {
         AeroBaseError::Pool(err.to_string())
@@ -0,0 +1,111 @@
+pub mod webhook;
+
+use crate::flight::active::{RnpState, SequenceReason};
+use crate::flight::fuel::FuelWarning;
+use crate::storage::tracks::TrackPoint;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// 默认事件通道容量
+const DEFAULT_CAPACITY: usize = 256;
+
+/// 服务内部事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    /// 同步层完成一次同步后发布
+    SyncCompleted { table: String, timestamp: i64 },
+    /// 导航数据周期切换
+    NavdataCycleChanged { cycle: String },
+    /// 飞行计划被创建或更新
+    FlightPlanSaved { id: String, version: i64 },
+    /// 启动时检测到数据库损坏并已恢复
+    DatabaseRecovered { method: String, lost_tables: Vec<String> },
+    /// 位置源（模拟回放、GNSS 等）报告新位置
+    PositionUpdated { source: String, point: TrackPoint },
+    /// 活动航线切换到新航段（to 为 None 表示航线已飞完）
+    ActiveLegChanged {
+        from: Option<String>,
+        to: Option<String>,
+        reason: SequenceReason,
+    },
+    /// 活动航段的偏航距离相对 RNP 的状态发生变化
+    RnpAlert {
+        waypoint: String,
+        cross_track_nm: f64,
+        rnp_nm: f64,
+        state: RnpState,
+    },
+    /// 燃油报告使预计落地油量出现新的（更严重的）告警
+    FuelWarning {
+        waypoint: String,
+        predicted_landing_fuel: f64,
+        warning: FuelWarning,
+    },
+}
+
+impl Event {
+    /// 事件名称（用于订阅过滤）
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::SyncCompleted { .. } => "SyncCompleted",
+            Event::NavdataCycleChanged { .. } => "NavdataCycleChanged",
+            Event::FlightPlanSaved { .. } => "FlightPlanSaved",
+            Event::DatabaseRecovered { .. } => "DatabaseRecovered",
+            Event::PositionUpdated { .. } => "PositionUpdated",
+            Event::ActiveLegChanged { .. } => "ActiveLegChanged",
+            Event::RnpAlert { .. } => "RnpAlert",
+            Event::FuelWarning { .. } => "FuelWarning",
+        }
+    }
+}
+
+/// 进程内事件总线
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    /// 创建新的事件总线
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(DEFAULT_CAPACITY);
+        Self { sender }
+    }
+
+    /// 发布事件，返回接收者数量（无订阅者时为 0）
+    pub fn publish(&self, event: Event) -> usize {
+        log::debug!("发布事件: {}", event.name());
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// 订阅之后发布的所有事件
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_subscribe() {
+        let bus = EventBus::new();
+        assert_eq!(bus.publish(Event::NavdataCycleChanged { cycle: "2401".to_string() }), 0);
+
+        let mut rx = bus.subscribe();
+        let event = Event::FlightPlanSaved {
+            id: "FP1".to_string(),
+            version: 2,
+        };
+        assert_eq!(bus.publish(event.clone()), 1);
+        assert_eq!(rx.try_recv().unwrap(), event);
+        assert_eq!(event.name(), "FlightPlanSaved");
+    }
+}
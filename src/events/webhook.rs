@@ -0,0 +1,342 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::events::Event;
+use rusqlite::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Event names a webhook may subscribe to
+pub const EVENT_NAMES: &[&str] = &["SyncCompleted", "NavdataCycleChanged", "FlightPlanSaved"];
+
+/// A registered webhook endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    pub active: bool,
+    pub created_at: i64,
+}
+
+impl Webhook {
+    /// Whether this webhook wants `event`
+    pub fn accepts(&self, event: &Event) -> bool {
+        self.active && self.events.iter().any(|e| e == event.name())
+    }
+}
+
+const COLUMNS: &str = "id, url, events, active, created_at";
+
+fn from_row(row: &Row) -> rusqlite::Result<Webhook> {
+    let events: String = row.get(2)?;
+    Ok(Webhook {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        events: events.split(',').map(str::to_string).collect(),
+        active: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// Delivers a JSON payload to a URL
+///
+/// The crate ships no HTTP client; the host application plugs one in.
+pub trait WebhookTransport: Send + Sync {
+    fn post(&self, url: &str, body: &str) -> Result<()>;
+}
+
+/// Persistent webhook registrations
+pub struct WebhookRegistry {
+    db: Arc<Database>,
+}
+
+impl WebhookRegistry {
+    /// Create a new registry
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Register `url` for the given event names
+    pub fn register(&self, url: &str, events: &[&str]) -> Result<Webhook> {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Webhook URL must be http(s): {}",
+                url
+            )));
+        }
+        if events.is_empty() {
+            return Err(AeroBaseError::InvalidInput(
+                "Webhook must subscribe to at least one event".to_string(),
+            ));
+        }
+        if let Some(unknown) = events.iter().find(|e| !EVENT_NAMES.contains(e)) {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Unknown event: {}",
+                unknown
+            )));
+        }
+
+        let webhook = Webhook {
+            id: self.db.new_id(),
+            url: url.to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            active: true,
+            created_at: self.db.clock().timestamp(),
+        };
+
+        let conn = self.db.get_conn()?;
+        conn.execute(
+            "INSERT INTO webhooks (id, url, events, active, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                &webhook.id,
+                &webhook.url,
+                webhook.events.join(","),
+                webhook.active,
+                webhook.created_at,
+            ],
+        )?;
+
+        Ok(webhook)
+    }
+
+    /// List all registered webhooks
+    pub fn list(&self) -> Result<Vec<Webhook>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM webhooks ORDER BY created_at, id",
+            COLUMNS
+        ))?;
+        let webhooks = stmt
+            .query_map([], from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(webhooks)
+    }
+
+    /// Enable or disable a webhook without deleting it
+    pub fn set_active(&self, id: &str, active: bool) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        let changed = conn.execute(
+            "UPDATE webhooks SET active = ?1 WHERE id = ?2",
+            rusqlite::params![active, id],
+        )?;
+        if changed == 0 {
+            return Err(AeroBaseError::NotFound(format!("Webhook {} not found", id)));
+        }
+        Ok(())
+    }
+
+    /// Remove a webhook
+    pub fn remove(&self, id: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        let changed = conn.execute("DELETE FROM webhooks WHERE id = ?1", [id])?;
+        if changed == 0 {
+            return Err(AeroBaseError::NotFound(format!("Webhook {} not found", id)));
+        }
+        Ok(())
+    }
+}
+
+/// Forwards bus events to matching webhooks
+pub struct WebhookDispatcher {
+    registry: WebhookRegistry,
+    transport: Arc<dyn WebhookTransport>,
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher over `registry` using `transport` for delivery
+    pub fn new(registry: WebhookRegistry, transport: Arc<dyn WebhookTransport>) -> Self {
+        Self {
+            registry,
+            transport,
+        }
+    }
+
+    /// Post `event` to every active webhook subscribed to it
+    ///
+    /// Delivery failures are logged and skipped so one bad endpoint does not
+    /// block the others. Returns the number of successful deliveries.
+    pub fn deliver(&self, event: &Event) -> Result<usize> {
+        let body = serde_json::to_string(event)?;
+        let mut delivered = 0;
+
+        for webhook in self.registry.list()?.iter().filter(|w| w.accepts(event)) {
+            match self.transport.post(&webhook.url, &body) {
+                Ok(()) => delivered += 1,
+                Err(e) => log::warn!(
+                    "Webhook {} delivery to {} failed: {}",
+                    webhook.id,
+                    webhook.url,
+                    e
+                ),
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// Deliver events from `receiver` until the bus is dropped
+    ///
+    /// `deliver` reads the registry and posts synchronously, so each event
+    /// is handed to the blocking pool rather than run on a runtime worker.
+    /// Events are still delivered one at a time, in order.
+    pub fn spawn(self, mut receiver: broadcast::Receiver<Event>) -> JoinHandle<()> {
+        let dispatcher = Arc::new(self);
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        let name = event.name();
+                        let dispatcher = Arc::clone(&dispatcher);
+                        match tokio::task::spawn_blocking(move || dispatcher.deliver(&event)).await {
+                            Ok(Ok(_)) => {}
+                            Ok(Err(e)) => log::warn!("Webhook dispatch for {} failed: {}", name, e),
+                            Err(e) => log::warn!("Webhook dispatch for {} panicked: {}", name, e),
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("Webhook dispatcher lagged, {} event(s) dropped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventBus;
+    use crate::Config;
+    use std::sync::Mutex;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[derive(Default)]
+    struct RecordingTransport {
+        posts: Mutex<Vec<(String, String)>>,
+    }
+
+    impl WebhookTransport for RecordingTransport {
+        fn post(&self, url: &str, body: &str) -> Result<()> {
+            if url.contains("down") {
+                return Err(AeroBaseError::Unknown("connection refused".to_string()));
+            }
+            self.posts
+                .lock()
+                .unwrap()
+                .push((url.to_string(), body.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_register_validates_input() {
+        let (db, _temp_file) = setup_test_db();
+        let registry = WebhookRegistry::new(db).unwrap();
+
+        assert!(registry.register("ftp://example.com", &["SyncCompleted"]).is_err());
+        assert!(registry.register("https://example.com", &[]).is_err());
+        assert!(registry.register("https://example.com", &["Bogus"]).is_err());
+
+        let hook = registry
+            .register("https://example.com/hook", &["SyncCompleted", "FlightPlanSaved"])
+            .unwrap();
+        assert_eq!(registry.list().unwrap(), vec![hook.clone()]);
+
+        registry.remove(&hook.id).unwrap();
+        assert!(registry.list().unwrap().is_empty());
+        assert!(matches!(registry.remove(&hook.id), Err(AeroBaseError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_deliver_filters_by_event() {
+        let (db, _temp_file) = setup_test_db();
+        let registry = WebhookRegistry::new(db).unwrap();
+        registry.register("https://a.example/hook", &["FlightPlanSaved"]).unwrap();
+        registry.register("https://b.example/hook", &["SyncCompleted"]).unwrap();
+        registry.register("https://down.example/hook", &["FlightPlanSaved"]).unwrap();
+        let paused = registry
+            .register("https://c.example/hook", &["FlightPlanSaved"])
+            .unwrap();
+        registry.set_active(&paused.id, false).unwrap();
+
+        let transport = Arc::new(RecordingTransport::default());
+        let dispatcher = WebhookDispatcher::new(registry, transport.clone());
+
+        let event = Event::FlightPlanSaved {
+            id: "FP1".to_string(),
+            version: 1,
+        };
+        assert_eq!(dispatcher.deliver(&event).unwrap(), 1);
+
+        let posts = transport.posts.lock().unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].0, "https://a.example/hook");
+        assert_eq!(serde_json::from_str::<Event>(&posts[0].1).unwrap(), event);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_dispatcher_follows_bus() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let bus = Arc::new(EventBus::new());
+        let db = Arc::new(Database::new(&config).unwrap().with_events(&bus));
+        db.migrate().unwrap();
+        let registry = WebhookRegistry::new(Arc::clone(&db)).unwrap();
+        registry
+            .register("https://a.example/hook", &["SyncCompleted", "NavdataCycleChanged"])
+            .unwrap();
+
+        let transport = Arc::new(RecordingTransport::default());
+        let handle = WebhookDispatcher::new(registry, transport.clone()).spawn(bus.subscribe());
+
+        // The data layer publishes; nothing here touches the bus directly
+        crate::sync::changes::record_sync(&db, "airports", 1, 0).unwrap();
+        let cycle = crate::navdata::cycle::AiracCycle::from_ident("2401").unwrap();
+        crate::navdata::cycle::install_cycle(&db, &cycle).unwrap();
+        // The dispatcher holds the database, which must not keep the bus open
+        drop(bus);
+        handle.await.unwrap();
+
+        let events: Vec<Event> = transport
+            .posts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_str(body).unwrap())
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                Event::SyncCompleted {
+                    table: "airports".to_string(),
+                    timestamp: 1,
+                },
+                Event::NavdataCycleChanged {
+                    cycle: "2401".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use crate::db::Database;
+use crate::error::Result;
+use crate::navdata::{table_columns, NAVDATA_TABLES};
+use rusqlite::types::Value;
+use std::io::Write;
+
+/// First line of every dump; bumped when the format changes
+pub const CANONICAL_DUMP_HEADER: &str = "# aerobase canonical navdata dump v1";
+
+/// Decimal places kept for floating-point values
+pub const FLOAT_DECIMALS: usize = 7;
+
+/// Bookkeeping columns that differ between otherwise identical builds
+const SKIPPED_COLUMNS: &[&str] = &["created_at", "updated_at"];
+
+/// Canonical text form of all navdata, for golden-file diffs
+///
+/// See `write_canonical_dump` for the format.
+pub fn canonical_dump(db: &Database) -> Result<String> {
+    let mut out = Vec::new();
+    write_canonical_dump(db, &mut out)?;
+    Ok(String::from_utf8(out).expect("dump is built from UTF-8 text"))
+}
+
+/// Write every navdata table in a canonical, line-oriented form
+///
+/// Two databases holding the same navdata produce byte-identical output,
+/// whatever order rows were inserted in and whenever they were built:
+///
+/// - tables come in `NAVDATA_TABLES` order, each introduced by a
+///   `## table` line and a `# column ...` line;
+/// - columns are sorted by name with `id` first, and `created_at` and
+///   `updated_at` are left out;
+/// - rows are sorted by `id`, one per line, values separated by tabs;
+/// - floats are rounded to `FLOAT_DECIMALS` places without trailing
+///   zeros, so `40.0` and `40` both print as `40`;
+/// - NULL is `\N`, blobs are `x'..'` hex, and tabs, line breaks and
+///   backslashes in text are escaped.
+pub fn write_canonical_dump(db: &Database, out: &mut impl Write) -> Result<()> {
+    let conn = db.get_background_conn()?;
+    // One snapshot, so a sync landing mid-dump can't mix cycles
+    let tx = conn.unchecked_transaction()?;
+    writeln!(out, "{}", CANONICAL_DUMP_HEADER)?;
+
+    for table in NAVDATA_TABLES {
+        let mut columns: Vec<String> = table_columns(&tx, table)?
+            .into_iter()
+            .filter(|column| !SKIPPED_COLUMNS.contains(&column.as_str()))
+            .collect();
+        columns.sort_by(|a, b| (a != "id").cmp(&(b != "id")).then_with(|| a.cmp(b)));
+
+        writeln!(out, "## {}", table)?;
+        writeln!(out, "# {}", columns.join("\t"))?;
+        let mut stmt = tx.prepare(&format!(
+            "SELECT {} FROM {} ORDER BY id",
+            columns.join(", "),
+            table
+        ))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let mut line = String::new();
+            for i in 0..columns.len() {
+                if i > 0 {
+                    line.push('\t');
+                }
+                line.push_str(&format_value(&row.get::<_, Value>(i)?));
+            }
+            writeln!(out, "{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Null => "\\N".to_string(),
+        Value::Integer(n) => n.to_string(),
+        Value::Real(x) => format_float(*x),
+        Value::Text(text) => escape(text),
+        Value::Blob(bytes) => {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("x'{}'", hex)
+        }
+    }
+}
+
+fn format_float(x: f64) -> String {
+    let rounded = format!("{:.*}", FLOAT_DECIMALS, x);
+    let trimmed = rounded.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_dump_is_canonical() {
+        let (first, _first_file) = setup_test_db();
+        let (second, _second_file) = setup_test_db();
+
+        // Same data, different insert order, timestamps and float noise
+        first
+            .get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'ALPHA', 40.08, 116.5, 'FIX', 100),
+                    ('W2', 'BRAVO', -0.0000000001, 117, 'FIX', 100);
+                 INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Capital	Intl\\East', 40.08, 116.58, 100);",
+            )
+            .unwrap();
+        second
+            .get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Capital	Intl\\East', 40.080000000001, 116.58, 999);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W2', 'BRAVO', 0.0, 117.0, 'FIX', 999),
+                    ('W1', 'ALPHA', 40.08, 116.50, 'FIX', 999);",
+            )
+            .unwrap();
+
+        let dump = canonical_dump(&first).unwrap();
+        assert_eq!(dump, canonical_dump(&second).unwrap());
+        assert!(dump.starts_with(CANONICAL_DUMP_HEADER));
+        assert!(!dump.contains("created_at"));
+
+        let waypoints: Vec<&str> = dump
+            .split("## waypoints\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .take(3)
+            .collect();
+        assert!(waypoints[0].starts_with("# id\t"));
+        assert!(waypoints[1].starts_with("W1\t"));
+        assert!(waypoints[1].contains("\t40.08\t116.5\t"));
+        assert!(waypoints[2].contains("\t0\t117\t"));
+        assert!(dump.contains("Capital\\tIntl\\\\East"));
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(&Value::Null), "\\N");
+        assert_eq!(format_value(&Value::Real(1.0 / 3.0)), "0.3333333");
+        assert_eq!(format_value(&Value::Real(-12.5)), "-12.5");
+        assert_eq!(format_value(&Value::Integer(-7)), "-7");
+        assert_eq!(format_value(&Value::Text("a\nb".into())), "a\\nb");
+        assert_eq!(format_value(&Value::Blob(vec![0, 255])), "x'00ff'");
+    }
+}
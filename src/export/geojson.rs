@@ -0,0 +1,277 @@
+use crate::db::Database;
+use crate::error::Result;
+use crate::flight::FlightRoute;
+use crate::models::airspace::Airspace;
+use crate::models::airway::AirwayType;
+use crate::models::Coordinate;
+use crate::spatial::query;
+use serde_json::{json, Value};
+
+/// GeoJSON position: longitude first
+fn position(coordinate: &Coordinate) -> Value {
+    json!([coordinate.longitude, coordinate.latitude])
+}
+
+fn feature(geometry: Value, properties: Value) -> Value {
+    json!({ "type": "Feature", "geometry": geometry, "properties": properties })
+}
+
+/// Wrap features in a `FeatureCollection`
+pub fn feature_collection(features: Vec<Value>) -> Value {
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+/// Closed polygon ring, counter-clockwise as RFC 7946 asks for exterior
+/// rings (Leaflet and Mapbox draw either way, but some validators don't)
+fn polygon_ring(boundary: &[Coordinate]) -> Vec<Value> {
+    // Shoelace sum in degrees: positive for counter-clockwise
+    let area: f64 = boundary
+        .iter()
+        .zip(boundary.iter().cycle().skip(1))
+        .map(|(a, b)| a.longitude * b.latitude - b.longitude * a.latitude)
+        .sum();
+    let mut ring: Vec<Value> = if area < 0.0 {
+        boundary.iter().rev().map(position).collect()
+    } else {
+        boundary.iter().map(position).collect()
+    };
+    if let Some(first) = ring.first().cloned() {
+        ring.push(first);
+    }
+    ring
+}
+
+/// One `Polygon` feature per airspace, with its type, class and vertical
+/// limits (feet) as properties; airspaces with fewer than three boundary
+/// points are left out
+pub fn airspace_features(airspaces: &[(Airspace, Vec<Coordinate>)]) -> Value {
+    let features = airspaces
+        .iter()
+        .filter(|(_, boundary)| boundary.len() >= 3)
+        .map(|(airspace, boundary)| {
+            feature(
+                json!({ "type": "Polygon", "coordinates": [polygon_ring(boundary)] }),
+                json!({
+                    "id": airspace.id,
+                    "name": airspace.name,
+                    "type": airspace.airspace_type.as_str(),
+                    "class": airspace.class.as_ref().map(|c| c.as_str()),
+                    "lower_limit_ft": airspace.lower_limit,
+                    "upper_limit_ft": airspace.upper_limit,
+                    "rvsm": airspace.rvsm,
+                }),
+            )
+        })
+        .collect();
+    feature_collection(features)
+}
+
+/// Airspaces overlapping `min`..`max` as a `FeatureCollection`
+pub fn airspaces(db: &Database, min: Coordinate, max: Coordinate) -> Result<Value> {
+    Ok(airspace_features(&query::find_airspaces_in(db, min, max)?))
+}
+
+/// Airway segments touching `min`..`max` as `LineString` features, one per
+/// segment, with the airway name and type and both waypoint names
+pub fn airways(db: &Database, min: Coordinate, max: Coordinate) -> Result<Value> {
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.name, a.type, s.id, s.sequence, s.distance,
+                f.name, f.latitude, f.longitude, t.name, t.latitude, t.longitude
+         FROM airway_segments s
+         JOIN airways a ON a.id = s.airway_id
+         JOIN waypoints f ON f.id = s.from_waypoint_id
+         JOIN waypoints t ON t.id = s.to_waypoint_id
+         WHERE MAX(f.latitude, t.latitude) >= ?1 AND MIN(f.latitude, t.latitude) <= ?2
+           AND MAX(f.longitude, t.longitude) >= ?3 AND MIN(f.longitude, t.longitude) <= ?4
+         ORDER BY a.name, a.id, s.sequence",
+    )?;
+    let features = stmt
+        .query_map(
+            rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude],
+            |row| {
+                let from = Coordinate::new(row.get(7)?, row.get(8)?);
+                let to = Coordinate::new(row.get(10)?, row.get(11)?);
+                Ok(feature(
+                    json!({
+                        "type": "LineString",
+                        "coordinates": [position(&from), position(&to)],
+                    }),
+                    json!({
+                        "id": row.get::<_, String>(3)?,
+                        "airway_id": row.get::<_, String>(0)?,
+                        "airway": row.get::<_, String>(1)?,
+                        "type": AirwayType::from_str(&row.get::<_, String>(2)?).as_str(),
+                        "sequence": row.get::<_, i32>(4)?,
+                        "distance_nm": row.get::<_, Option<f64>>(5)?,
+                        "from": row.get::<_, String>(6)?,
+                        "to": row.get::<_, String>(9)?,
+                    }),
+                ))
+            },
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(feature_collection(features))
+}
+
+impl FlightRoute {
+    /// 以 GeoJSON FeatureCollection 表示航线：一条 LineString 及每个航路点的 Point，
+    /// 可直接交给 Leaflet 或 Mapbox 显示
+    pub fn to_geojson(&self) -> Value {
+        let mut features = Vec::with_capacity(self.waypoints.len() + 1);
+        features.push(feature(
+            json!({
+                "type": "LineString",
+                "coordinates": self
+                    .waypoints
+                    .iter()
+                    .map(|w| position(&w.coordinate))
+                    .collect::<Vec<_>>(),
+            }),
+            json!({
+                "departure": self.plan.departure,
+                "destination": self.plan.destination,
+                "cruise_altitude_ft": self.plan.cruise_altitude,
+                "total_distance_nm": self.total_distance,
+                "estimated_time_min": self.estimated_time,
+            }),
+        ));
+        features.extend(self.waypoints.iter().enumerate().map(|(i, waypoint)| {
+            feature(
+                json!({ "type": "Point", "coordinates": position(&waypoint.coordinate) }),
+                json!({
+                    "id": waypoint.id,
+                    "name": waypoint.name,
+                    "sequence": i,
+                    "distance_from_previous_nm": waypoint.distance_from_previous,
+                    "cumulative_distance_nm": waypoint.cumulative_distance,
+                    "estimated_time_min": waypoint.estimated_time,
+                }),
+            )
+        }));
+        feature_collection(features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{FlightPlan, RouteWaypoint};
+    use crate::models::airspace::{AirspaceClass, AirspaceType};
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_airspace_features() {
+        let mut airspace = Airspace::new(
+            "AS1".to_string(),
+            "BEIJING TMA".to_string(),
+            AirspaceType::TerminalArea,
+        );
+        airspace.class = Some(AirspaceClass::ClassC);
+        airspace.upper_limit = Some(18000);
+        // Clockwise, so the ring comes back reversed
+        let boundary = vec![
+            Coordinate::new(40.0, 116.0),
+            Coordinate::new(41.0, 116.0),
+            Coordinate::new(41.0, 117.0),
+            Coordinate::new(40.0, 117.0),
+        ];
+        let sliver = (airspace.clone(), boundary[..2].to_vec());
+        let collection = airspace_features(&[(airspace, boundary), sliver]);
+
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        let ring = features[0]["geometry"]["coordinates"][0].as_array().unwrap();
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring[0], json!([117.0, 40.0]));
+        assert_eq!(ring[1], json!([117.0, 41.0]));
+        assert_eq!(ring[0], ring[4]);
+        assert_eq!(features[0]["properties"]["class"], "C");
+        assert_eq!(features[0]["properties"]["upper_limit_ft"], 18000);
+    }
+
+    #[test]
+    fn test_airways() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'ALPHA', 40.0, 116.0, 'FIX', 0),
+                    ('W2', 'BRAVO', 41.0, 117.0, 'FIX', 0),
+                    ('W3', 'CHARLIE', 50.0, 130.0, 'FIX', 0),
+                    ('W4', 'DELTA', 51.0, 131.0, 'FIX', 0);
+                 INSERT INTO airways (id, name, type, created_at) VALUES ('A1', 'A1', 'HIGH', 0);
+                 INSERT INTO airway_segments
+                    (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, distance,
+                     created_at) VALUES
+                    ('S1', 'A1', 'W1', 'W2', 1, 74.5, 0),
+                    ('S2', 'A1', 'W3', 'W4', 2, NULL, 0);",
+            )
+            .unwrap();
+
+        let collection =
+            airways(&db, Coordinate::new(39.0, 115.0), Coordinate::new(42.0, 118.0)).unwrap();
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["geometry"]["coordinates"], json!([[116.0, 40.0], [117.0, 41.0]]));
+        assert_eq!(features[0]["properties"]["airway"], "A1");
+        assert_eq!(features[0]["properties"]["from"], "ALPHA");
+        assert_eq!(features[0]["properties"]["distance_nm"], 74.5);
+
+        let collection =
+            airspaces(&db, Coordinate::new(39.0, 115.0), Coordinate::new(42.0, 118.0)).unwrap();
+        assert_eq!(collection["features"], json!([]));
+    }
+
+    #[test]
+    fn test_route_to_geojson() {
+        let waypoint = |id: &str, lat: f64, lon: f64, cumulative: f64| RouteWaypoint {
+            id: id.to_string(),
+            name: id.to_string(),
+            coordinate: Coordinate::new(lat, lon),
+            distance_from_previous: 0.0,
+            cumulative_distance: cumulative,
+            estimated_time: 0,
+        };
+        let route = FlightRoute {
+            plan: FlightPlan {
+                departure: "ZBAA".to_string(),
+                destination: "ZSSS".to_string(),
+                alternate: None,
+                cruise_altitude: 35000,
+                cruise_speed: 450,
+                route: vec![],
+                ..Default::default()
+            },
+            total_distance: 580.0,
+            estimated_time: 85,
+            waypoints: vec![
+                waypoint("ZBAA", 40.08, 116.58, 0.0),
+                waypoint("ZSSS", 31.2, 121.34, 580.0),
+            ],
+        };
+
+        let collection = route.to_geojson();
+        assert_eq!(collection["type"], "FeatureCollection");
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[0]["geometry"]["type"], "LineString");
+        assert_eq!(
+            features[0]["geometry"]["coordinates"],
+            json!([[116.58, 40.08], [121.34, 31.2]])
+        );
+        assert_eq!(features[0]["properties"]["total_distance_nm"], 580.0);
+        assert_eq!(features[2]["geometry"]["coordinates"], json!([121.34, 31.2]));
+        assert_eq!(features[2]["properties"]["sequence"], 1);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod canonical;
+pub mod geojson;
+
+pub use canonical::{canonical_dump, write_canonical_dump};
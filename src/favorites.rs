@@ -0,0 +1,475 @@
+use crate::db::{Database, Query};
+use crate::error::{AeroBaseError, Result};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Recent items kept per profile
+pub(crate) const MAX_RECENT_ITEMS: i64 = 50;
+
+/// 可收藏的条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ItemKind {
+    Airport,
+    Waypoint,
+}
+
+impl ItemKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ItemKind::Airport => "AIRPORT",
+            ItemKind::Waypoint => "WAYPOINT",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "AIRPORT" => Some(ItemKind::Airport),
+            "WAYPOINT" => Some(ItemKind::Waypoint),
+            _ => None,
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            ItemKind::Airport => "airports",
+            ItemKind::Waypoint => "waypoints",
+        }
+    }
+
+    /// Column shown as the item's label
+    fn label_column(&self) -> &'static str {
+        match self {
+            ItemKind::Airport => "icao",
+            ItemKind::Waypoint => "name",
+        }
+    }
+}
+
+/// 快速访问列表中的条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuickItem {
+    pub kind: ItemKind,
+    pub id: String,
+    /// ICAO code for airports, ident for waypoints
+    pub label: String,
+    pub favorite: bool,
+    pub last_used: Option<i64>,
+}
+
+/// 保存的搜索条件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchCriteria {
+    /// None searches both airports and waypoints
+    pub kind: Option<ItemKind>,
+    /// ICAO code or waypoint ident prefix
+    pub ident_prefix: Option<String>,
+    pub region: Option<String>,
+    /// Airports only
+    pub country: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Record `item_id` as just opened, keeping at most `MAX_RECENT_ITEMS` per profile
+pub(crate) fn touch_recent(
+    conn: &Connection,
+    profile_id: &str,
+    kind: ItemKind,
+    item_id: &str,
+    accessed_at: i64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO recent_items (profile_id, item_type, item_id, accessed_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![profile_id, kind.as_str(), item_id, accessed_at],
+    )?;
+    conn.execute(
+        "DELETE FROM recent_items WHERE profile_id = ?1 AND rowid NOT IN (
+             SELECT rowid FROM recent_items WHERE profile_id = ?1
+             ORDER BY accessed_at DESC, rowid DESC LIMIT ?2)",
+        rusqlite::params![profile_id, MAX_RECENT_ITEMS],
+    )?;
+    Ok(())
+}
+
+/// 命名的已保存搜索
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: String,
+    pub profile_id: String,
+    pub name: String,
+    pub criteria: SearchCriteria,
+    pub created_at: i64,
+}
+
+/// 收藏、最近使用与已保存搜索管理器
+pub struct FavoritesManager {
+    db: Arc<Database>,
+}
+
+impl FavoritesManager {
+    /// 创建新的收藏管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// 收藏条目（重复收藏无副作用）
+    pub fn star(&self, profile_id: &str, kind: ItemKind, item_id: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO favorites (id, profile_id, item_type, item_id, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                self.db.new_id(),
+                profile_id,
+                kind.as_str(),
+                item_id,
+                self.db.clock().timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 取消收藏
+    pub fn unstar(&self, profile_id: &str, kind: ItemKind, item_id: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        conn.execute(
+            "DELETE FROM favorites WHERE profile_id = ?1 AND item_type = ?2 AND item_id = ?3",
+            rusqlite::params![profile_id, kind.as_str(), item_id],
+        )?;
+        Ok(())
+    }
+
+    /// 收藏列表（按标签排序，已不存在的导航数据条目会被跳过）
+    pub fn favorites(&self, profile_id: &str) -> Result<Vec<QuickItem>> {
+        let mut items = Vec::new();
+        for kind in [ItemKind::Airport, ItemKind::Waypoint] {
+            items.extend(self.load(
+                profile_id,
+                kind,
+                "favorites",
+                "NULL",
+                &format!("t.{}", kind.label_column()),
+            )?);
+        }
+        for item in &mut items {
+            item.favorite = true;
+        }
+        items.sort_by(|a, b| a.label.cmp(&b.label).then(a.id.cmp(&b.id)));
+        Ok(items)
+    }
+
+    /// 记录最近打开的条目
+    pub fn record_recent(&self, profile_id: &str, kind: ItemKind, item_id: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        touch_recent(&conn, profile_id, kind, item_id, self.db.clock().timestamp())
+    }
+
+    /// 最近使用的条目（最新在前）
+    pub fn recent(&self, profile_id: &str) -> Result<Vec<QuickItem>> {
+        let mut items = Vec::new();
+        for kind in [ItemKind::Airport, ItemKind::Waypoint] {
+            items.extend(self.load(profile_id, kind, "recent_items", "s.accessed_at", "s.accessed_at")?);
+        }
+        items.sort_by(|a, b| b.last_used.cmp(&a.last_used).then(a.id.cmp(&b.id)));
+        Ok(items)
+    }
+
+    /// 快速访问列表：收藏在前，其后为未收藏的最近条目
+    pub fn recent_and_favorites(&self, profile_id: &str) -> Result<Vec<QuickItem>> {
+        let recent = self.recent(profile_id)?;
+        let mut items = self.favorites(profile_id)?;
+
+        for favorite in &mut items {
+            favorite.last_used = recent
+                .iter()
+                .find(|r| r.kind == favorite.kind && r.id == favorite.id)
+                .and_then(|r| r.last_used);
+        }
+        let others: Vec<QuickItem> = recent
+            .into_iter()
+            .filter(|r| !items.iter().any(|f| f.kind == r.kind && f.id == r.id))
+            .collect();
+        items.extend(others);
+        Ok(items)
+    }
+
+    /// 保存搜索（同名搜索会被覆盖）
+    pub fn save_search(
+        &self,
+        profile_id: &str,
+        name: &str,
+        criteria: &SearchCriteria,
+    ) -> Result<SavedSearch> {
+        if name.trim().is_empty() {
+            return Err(AeroBaseError::InvalidInput(
+                "Saved search name must not be empty".to_string(),
+            ));
+        }
+
+        let conn = self.db.get_conn()?;
+        let existing: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT id, created_at FROM saved_searches WHERE profile_id = ?1 AND name = ?2",
+                [profile_id, name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let now = self.db.clock().timestamp();
+        let (id, created_at) = existing.unwrap_or_else(|| (self.db.new_id(), now));
+
+        conn.execute(
+            "INSERT INTO saved_searches (id, profile_id, name, criteria_json, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET
+                 criteria_json = excluded.criteria_json, updated_at = excluded.updated_at",
+            rusqlite::params![
+                &id,
+                profile_id,
+                name,
+                serde_json::to_string(criteria)?,
+                created_at,
+                now,
+            ],
+        )?;
+
+        Ok(SavedSearch {
+            id,
+            profile_id: profile_id.to_string(),
+            name: name.to_string(),
+            criteria: criteria.clone(),
+            created_at,
+        })
+    }
+
+    /// 已保存的搜索（按名称排序）
+    pub fn saved_searches(&self, profile_id: &str) -> Result<Vec<SavedSearch>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, profile_id, name, criteria_json, created_at
+             FROM saved_searches WHERE profile_id = ?1 ORDER BY name",
+        )?;
+        let rows = stmt
+            .query_map([profile_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(id, profile_id, name, json, created_at)| {
+                Ok(SavedSearch {
+                    id,
+                    profile_id,
+                    name,
+                    criteria: serde_json::from_str(&json)?,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// 删除已保存的搜索
+    pub fn delete_search(&self, id: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        let changed = conn.execute("DELETE FROM saved_searches WHERE id = ?1", [id])?;
+        if changed == 0 {
+            return Err(AeroBaseError::NotFound(format!("Saved search {} not found", id)));
+        }
+        Ok(())
+    }
+
+    /// 执行搜索条件
+    pub fn run_search(&self, profile_id: &str, criteria: &SearchCriteria) -> Result<Vec<QuickItem>> {
+        let mut items = Vec::new();
+
+        if criteria.kind != Some(ItemKind::Waypoint) {
+            let mut query = Query::airports();
+            if let Some(prefix) = &criteria.ident_prefix {
+                query = query.icao_prefix(prefix);
+            }
+            if let Some(region) = &criteria.region {
+                query = query.region(region);
+            }
+            if let Some(country) = &criteria.country {
+                query = query.country(country);
+            }
+            if let Some(limit) = criteria.limit {
+                query = query.limit(limit);
+            }
+            items.extend(query.fetch(&self.db)?.into_iter().map(|a| QuickItem {
+                kind: ItemKind::Airport,
+                id: a.id,
+                label: a.icao,
+                favorite: false,
+                last_used: None,
+            }));
+        }
+
+        // Waypoints have no country column, so a country filter excludes them
+        if criteria.kind != Some(ItemKind::Airport) && criteria.country.is_none() {
+            let mut query = Query::waypoints();
+            if let Some(prefix) = &criteria.ident_prefix {
+                query = query.name_prefix(prefix);
+            }
+            if let Some(region) = &criteria.region {
+                query = query.region(region);
+            }
+            if let Some(limit) = criteria.limit {
+                query = query.limit(limit);
+            }
+            items.extend(query.fetch(&self.db)?.into_iter().map(|w| QuickItem {
+                kind: ItemKind::Waypoint,
+                id: w.id,
+                label: w.name,
+                favorite: false,
+                last_used: None,
+            }));
+        }
+
+        let favorites = self.favorites(profile_id)?;
+        for item in &mut items {
+            item.favorite = favorites.iter().any(|f| f.kind == item.kind && f.id == item.id);
+        }
+        Ok(items)
+    }
+
+    fn load(
+        &self,
+        profile_id: &str,
+        kind: ItemKind,
+        source: &str,
+        last_used: &str,
+        order_by: &str,
+    ) -> Result<Vec<QuickItem>> {
+        let conn = self.db.get_conn()?;
+        // Table and column names come from ItemKind and fixed call sites
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.id, t.{label}, {last_used} FROM {source} s
+             JOIN {table} t ON t.id = s.item_id
+             WHERE s.profile_id = ?1 AND s.item_type = ?2
+             ORDER BY {order_by}",
+            label = kind.label_column(),
+            last_used = last_used,
+            source = source,
+            table = kind.table(),
+            order_by = order_by,
+        ))?;
+        let items = stmt
+            .query_map([profile_id, kind.as_str()], |row| {
+                Ok(QuickItem {
+                    kind,
+                    id: row.get(0)?,
+                    label: row.get(1)?,
+                    favorite: false,
+                    last_used: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, country, created_at) VALUES
+                    ('AP1', 'ZBAA', 'Beijing', 40.0, 116.0, 'China', 0),
+                    ('AP2', 'ZSSS', 'Shanghai', 31.0, 121.0, 'China', 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                    VALUES ('W1', 'ZB001', 40.1, 116.1, 'FIX', 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_favorites_and_recent() {
+        let (db, _temp_file) = setup_test_db();
+        let manager = FavoritesManager::new(db).unwrap();
+
+        manager.star("pilot", ItemKind::Airport, "AP2").unwrap();
+        manager.star("pilot", ItemKind::Airport, "AP2").unwrap();
+        manager.star("pilot", ItemKind::Waypoint, "W1").unwrap();
+        manager.star("other", ItemKind::Airport, "AP1").unwrap();
+
+        let labels: Vec<String> = manager
+            .favorites("pilot")
+            .unwrap()
+            .into_iter()
+            .map(|i| i.label)
+            .collect();
+        assert_eq!(labels, vec!["ZB001", "ZSSS"]);
+
+        manager.record_recent("pilot", ItemKind::Airport, "AP1").unwrap();
+        manager.record_recent("pilot", ItemKind::Airport, "AP2").unwrap();
+
+        let quick = manager.recent_and_favorites("pilot").unwrap();
+        let summary: Vec<(&str, bool, bool)> = quick
+            .iter()
+            .map(|i| (i.label.as_str(), i.favorite, i.last_used.is_some()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("ZB001", true, false), ("ZSSS", true, true), ("ZBAA", false, true)]
+        );
+
+        manager.unstar("pilot", ItemKind::Airport, "AP2").unwrap();
+        assert_eq!(manager.favorites("pilot").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_saved_searches() {
+        let (db, _temp_file) = setup_test_db();
+        let manager = FavoritesManager::new(db).unwrap();
+
+        let criteria = SearchCriteria {
+            ident_prefix: Some("ZB".to_string()),
+            ..Default::default()
+        };
+        let first = manager.save_search("pilot", "Beijing area", &criteria).unwrap();
+        assert!(manager.save_search("pilot", " ", &criteria).is_err());
+
+        let narrowed = SearchCriteria {
+            kind: Some(ItemKind::Airport),
+            ..criteria.clone()
+        };
+        let second = manager.save_search("pilot", "Beijing area", &narrowed).unwrap();
+        assert_eq!(first.id, second.id);
+
+        let saved = manager.saved_searches("pilot").unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].criteria, narrowed);
+
+        manager.star("pilot", ItemKind::Airport, "AP1").unwrap();
+        let results = manager.run_search("pilot", &criteria).unwrap();
+        let labels: Vec<(&str, bool)> = results.iter().map(|i| (i.label.as_str(), i.favorite)).collect();
+        assert_eq!(labels, vec![("ZBAA", true), ("ZB001", false)]);
+
+        manager.delete_search(&first.id).unwrap();
+        assert!(manager.saved_searches("pilot").unwrap().is_empty());
+    }
+}
@@ -58,6 +58,7 @@ pub unsafe extern "C" fn aerobase_new(config: *const CAeroBaseConfig) -> *mut Ae
         db_path,
         enable_wal: c_config.enable_wal,
         pool_size: c_config.pool_size,
+        ..Default::default()
     };
 
     match tokio::runtime::Runtime::new() {
@@ -0,0 +1,147 @@
+use crate::db::Database;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+/// 服务器下发的功能开关
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub enabled: bool,
+    pub updated_at: i64,
+}
+
+/// 功能开关（同步时由服务器整体下发，用于分批灰度启用有风险的功能）
+pub struct FeatureFlags {
+    db: Arc<Database>,
+    cache: RwLock<Option<HashMap<String, bool>>>,
+}
+
+impl FeatureFlags {
+    /// 创建功能开关管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self {
+            db,
+            cache: RwLock::new(None),
+        })
+    }
+
+    /// 功能是否启用；未下发或读取失败时视为未启用
+    pub fn is_enabled(&self, name: &str) -> bool {
+        if let Some(flags) = self.cache.read().unwrap().as_ref() {
+            return flags.get(name).copied().unwrap_or(false);
+        }
+        match self.load() {
+            Ok(flags) => {
+                let enabled = flags.get(name).copied().unwrap_or(false);
+                *self.cache.write().unwrap() = Some(flags);
+                enabled
+            }
+            Err(e) => {
+                log::warn!("读取功能开关失败，{} 视为未启用: {}", name, e);
+                false
+            }
+        }
+    }
+
+    /// 全部已下发的功能开关（按名称排序）
+    pub fn list(&self) -> Result<Vec<FeatureFlag>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt =
+            conn.prepare("SELECT name, enabled, updated_at FROM feature_flags ORDER BY name")?;
+        let flags = stmt
+            .query_map([], |row| {
+                Ok(FeatureFlag {
+                    name: row.get(0)?,
+                    enabled: row.get(1)?,
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(flags)
+    }
+
+    /// 用同步下发的完整开关集合替换本地开关，未包含的开关被删除；返回发生变化的开关数
+    pub fn apply_sync(&self, flags: &BTreeMap<String, bool>) -> Result<usize> {
+        let conn = self.db.get_conn()?;
+        let tx = conn.unchecked_transaction()?;
+        let now = self.db.clock().timestamp();
+
+        let existing: Vec<String> = tx
+            .prepare("SELECT name FROM feature_flags")?
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<_, _>>()?;
+        let mut changed = 0;
+        for name in existing.iter().filter(|name| !flags.contains_key(*name)) {
+            changed += tx.execute("DELETE FROM feature_flags WHERE name = ?1", [name])?;
+        }
+        {
+            // Unchanged flags keep the time they last changed
+            let mut upsert = tx.prepare(
+                "INSERT INTO feature_flags (name, enabled, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(name) DO UPDATE SET
+                     enabled = excluded.enabled,
+                     updated_at = excluded.updated_at
+                 WHERE enabled <> excluded.enabled",
+            )?;
+            for (name, enabled) in flags {
+                changed += upsert.execute(rusqlite::params![name, enabled, now])?;
+            }
+        }
+        tx.commit()?;
+
+        *self.cache.write().unwrap() = Some(flags.iter().map(|(k, v)| (k.clone(), *v)).collect());
+        Ok(changed)
+    }
+
+    fn load(&self) -> Result<HashMap<String, bool>> {
+        Ok(self.list()?.into_iter().map(|f| (f.name, f.enabled)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_apply_sync() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let flags = FeatureFlags::new(Arc::clone(&db)).unwrap();
+        assert!(!flags.is_enabled("new_router"));
+
+        let set = |pairs: &[(&str, bool)]| -> BTreeMap<String, bool> {
+            pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+        };
+        assert_eq!(flags.apply_sync(&set(&[("new_router", true), ("metric_levels", false)])).unwrap(), 2);
+        assert!(flags.is_enabled("new_router"));
+        assert!(!flags.is_enabled("metric_levels"));
+
+        // Only new_router changes
+        db.get_conn()
+            .unwrap()
+            .execute("UPDATE feature_flags SET updated_at = 1", [])
+            .unwrap();
+        assert_eq!(flags.apply_sync(&set(&[("new_router", false), ("metric_levels", false)])).unwrap(), 1);
+        let listed = flags.list().unwrap();
+        assert_eq!(listed[0], FeatureFlag { name: "metric_levels".to_string(), enabled: false, updated_at: 1 });
+        assert!(!listed[1].enabled && listed[1].updated_at > 1);
+
+        assert_eq!(flags.apply_sync(&set(&[])).unwrap(), 2);
+        assert!(flags.list().unwrap().is_empty());
+
+        // A fresh manager reads what was stored
+        flags.apply_sync(&set(&[("new_router", true)])).unwrap();
+        assert!(FeatureFlags::new(db).unwrap().is_enabled("new_router"));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::FlightPlanBuilder;
+use rusqlite::{OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 机型的默认性能参数，按注册号规划时自动带入飞行计划
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AircraftPerformance {
+    /// 巡航真空速（节）
+    pub cruise_speed: i32,
+    /// 常用巡航高度（英尺）
+    pub cruise_altitude: i32,
+    /// 巡航燃油流量（加仑/小时）
+    pub fuel_flow_gph: f64,
+}
+
+/// 机队中的一架航空器
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Aircraft {
+    /// 注册号（大写保存，如 "B-1234"）
+    pub registration: String,
+    /// ICAO 机型代码（如 "C172"）
+    pub aircraft_type: String,
+    pub performance: AircraftPerformance,
+    /// 设备代码（ICAO 飞行计划第 10 项，如 "SDFGRY/S"）
+    pub equipment: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const COLUMNS: &str = "id, aircraft_type, cruise_speed, cruise_altitude, fuel_flow_gph, equipment, \
+     created_at, updated_at";
+
+fn from_row(row: &Row) -> rusqlite::Result<Aircraft> {
+    Ok(Aircraft {
+        registration: row.get(0)?,
+        aircraft_type: row.get(1)?,
+        performance: AircraftPerformance {
+            cruise_speed: row.get(2)?,
+            cruise_altitude: row.get(3)?,
+            fuel_flow_gph: row.get(4)?,
+        },
+        equipment: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// Registrations are matched case-insensitively and without surrounding spaces
+fn normalize(registration: &str) -> String {
+    registration.trim().to_uppercase()
+}
+
+/// 机队管理器
+///
+/// 按注册号保存机型、默认性能与设备代码，并记录每份已保存飞行计划所选的
+/// 航空器，签派可按注册号规划而无需重复输入性能数据。
+pub struct FleetManager {
+    db: Arc<Database>,
+}
+
+impl FleetManager {
+    /// 创建机队管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// 添加航空器或更新同一注册号的航空器
+    pub fn save(
+        &self,
+        registration: &str,
+        aircraft_type: &str,
+        performance: &AircraftPerformance,
+        equipment: &str,
+    ) -> Result<Aircraft> {
+        let registration = normalize(registration);
+        if registration.is_empty() {
+            return Err(AeroBaseError::InvalidInput("Registration is required".to_string()));
+        }
+        if performance.cruise_speed <= 0 || performance.fuel_flow_gph <= 0.0 {
+            return Err(AeroBaseError::InvalidInput(
+                "Cruise speed and fuel flow must be positive".to_string(),
+            ));
+        }
+
+        let now = self.db.clock().timestamp();
+        let conn = self.db.get_conn()?;
+        conn.execute(
+            "INSERT INTO aircraft
+                 (id, aircraft_type, cruise_speed, cruise_altitude, fuel_flow_gph, equipment,
+                  created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+             ON CONFLICT(id) DO UPDATE SET
+                 aircraft_type = excluded.aircraft_type,
+                 cruise_speed = excluded.cruise_speed,
+                 cruise_altitude = excluded.cruise_altitude,
+                 fuel_flow_gph = excluded.fuel_flow_gph,
+                 equipment = excluded.equipment,
+                 updated_at = excluded.updated_at",
+            rusqlite::params![
+                registration,
+                aircraft_type.trim().to_uppercase(),
+                performance.cruise_speed,
+                performance.cruise_altitude,
+                performance.fuel_flow_gph,
+                equipment.trim().to_uppercase(),
+                now,
+            ],
+        )?;
+        let aircraft = conn.query_row(
+            &format!("SELECT {} FROM aircraft WHERE id = ?1", COLUMNS),
+            [&registration],
+            from_row,
+        )?;
+        Ok(aircraft)
+    }
+
+    /// 按注册号获取航空器
+    pub fn get(&self, registration: &str) -> Result<Option<Aircraft>> {
+        let conn = self.db.get_conn()?;
+        let aircraft = conn
+            .query_row(
+                &format!("SELECT {} FROM aircraft WHERE id = ?1", COLUMNS),
+                [normalize(registration)],
+                from_row,
+            )
+            .optional()?;
+        Ok(aircraft)
+    }
+
+    /// 列出机队（按注册号排序）
+    pub fn list(&self) -> Result<Vec<Aircraft>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM aircraft ORDER BY id", COLUMNS))?;
+        let fleet = stmt
+            .query_map([], from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(fleet)
+    }
+
+    /// 删除航空器（同时解除其飞行计划选择）；返回是否存在
+    pub fn remove(&self, registration: &str) -> Result<bool> {
+        let removed = self
+            .db
+            .get_conn()?
+            .execute("DELETE FROM aircraft WHERE id = ?1", [normalize(registration)])?;
+        Ok(removed > 0)
+    }
+
+    /// 以航空器的默认性能开始规划
+    pub fn plan_builder(&self, registration: &str) -> Result<FlightPlanBuilder> {
+        let aircraft = self.require(registration)?;
+        Ok(FlightPlanBuilder::new().aircraft(&aircraft))
+    }
+
+    /// 为已保存的飞行计划选择航空器（替换已有选择）
+    pub fn assign(&self, plan_id: &str, registration: &str) -> Result<()> {
+        let aircraft = self.require(registration)?;
+        let conn = self.db.get_conn()?;
+        let plan_exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM flight_plans WHERE id = ?1)",
+            [plan_id],
+            |row| row.get(0),
+        )?;
+        if !plan_exists {
+            return Err(AeroBaseError::NotFound(format!("Flight plan {} not found", plan_id)));
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO flight_plan_aircraft (plan_id, aircraft_id) VALUES (?1, ?2)",
+            [plan_id, &aircraft.registration],
+        )?;
+        Ok(())
+    }
+
+    /// 取消飞行计划的航空器选择
+    pub fn unassign(&self, plan_id: &str) -> Result<()> {
+        self.db
+            .get_conn()?
+            .execute("DELETE FROM flight_plan_aircraft WHERE plan_id = ?1", [plan_id])?;
+        Ok(())
+    }
+
+    /// 飞行计划所选的航空器
+    pub fn aircraft_for_plan(&self, plan_id: &str) -> Result<Option<Aircraft>> {
+        let conn = self.db.get_conn()?;
+        let aircraft = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM aircraft
+                     WHERE id = (SELECT aircraft_id FROM flight_plan_aircraft WHERE plan_id = ?1)",
+                    COLUMNS
+                ),
+                [plan_id],
+                from_row,
+            )
+            .optional()?;
+        Ok(aircraft)
+    }
+
+    fn require(&self, registration: &str) -> Result<Aircraft> {
+        self.get(registration)?.ok_or_else(|| {
+            AeroBaseError::NotFound(format!("Aircraft {} not found", registration.trim()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::repository::FlightPlanRepository;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn performance() -> AircraftPerformance {
+        AircraftPerformance {
+            cruise_speed: 120,
+            cruise_altitude: 8500,
+            fuel_flow_gph: 9.0,
+        }
+    }
+
+    #[test]
+    fn test_crud() {
+        let (db, _temp_file) = setup_test_db();
+        let fleet = FleetManager::new(db).unwrap();
+
+        let saved = fleet.save(" b-1234 ", "c172", &performance(), "sdfgry/s").unwrap();
+        assert_eq!(saved.registration, "B-1234");
+        assert_eq!(saved.aircraft_type, "C172");
+        assert_eq!(saved.equipment, "SDFGRY/S");
+        fleet.save("B-5678", "PA28", &performance(), "S/C").unwrap();
+
+        let faster = AircraftPerformance {
+            cruise_speed: 125,
+            ..performance()
+        };
+        fleet.save("B-1234", "C172", &faster, "SDFGRY/S").unwrap();
+        assert_eq!(fleet.get("b-1234").unwrap().unwrap().performance.cruise_speed, 125);
+
+        let registrations: Vec<String> =
+            fleet.list().unwrap().into_iter().map(|a| a.registration).collect();
+        assert_eq!(registrations, vec!["B-1234", "B-5678"]);
+
+        assert!(fleet.remove("B-5678").unwrap());
+        assert!(!fleet.remove("B-5678").unwrap());
+        assert!(fleet.save("", "C172", &performance(), "S").is_err());
+        let stalled = AircraftPerformance {
+            cruise_speed: 0,
+            ..performance()
+        };
+        assert!(fleet.save("B-9", "C172", &stalled, "S").is_err());
+    }
+
+    #[test]
+    fn test_plan_by_tail_number() {
+        let (db, _temp_file) = setup_test_db();
+        let fleet = FleetManager::new(Arc::clone(&db)).unwrap();
+        let plans = FlightPlanRepository::new(db).unwrap();
+        fleet.save("B-1234", "C172", &performance(), "SDFGRY/S").unwrap();
+
+        let plan = fleet
+            .plan_builder("b-1234")
+            .unwrap()
+            .departure("ZBAA")
+            .destination("ZBTJ")
+            .build()
+            .unwrap();
+        assert_eq!((plan.cruise_speed, plan.cruise_altitude), (120, 8500));
+        assert_eq!(plan.aircraft.as_deref(), Some("B-1234"));
+        assert!(matches!(fleet.plan_builder("B-0000"), Err(AeroBaseError::NotFound(_))));
+
+        let stored = plans.insert(&plan, None, None).unwrap();
+        assert_eq!(fleet.aircraft_for_plan(&stored.id).unwrap(), None);
+        fleet.assign(&stored.id, "B-1234").unwrap();
+        let aircraft = fleet.aircraft_for_plan(&stored.id).unwrap().unwrap();
+        assert_eq!(aircraft.registration, "B-1234");
+        assert!(matches!(fleet.assign("missing", "B-1234"), Err(AeroBaseError::NotFound(_))));
+
+        // Removing the aircraft clears the selection
+        fleet.remove("B-1234").unwrap();
+        assert_eq!(fleet.aircraft_for_plan(&stored.id).unwrap(), None);
+
+        fleet.save("B-1234", "C172", &performance(), "SDFGRY/S").unwrap();
+        fleet.assign(&stored.id, "B-1234").unwrap();
+        fleet.unassign(&stored.id).unwrap();
+        assert_eq!(fleet.aircraft_for_plan(&stored.id).unwrap(), None);
+    }
+}
@@ -0,0 +1,453 @@
+use crate::error::{AeroBaseError, Result};
+use crate::events::{Event, EventBus};
+use crate::flight::descent::{self, DescentAdvisory, DescentPath};
+use crate::flight::holding::{angle_diff, turn_radius_nm};
+use crate::flight::{FlightRoute, RouteWaypoint};
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Course changes above this are anticipated as if they were this large,
+/// so a near reversal does not sequence miles early
+const MAX_ANTICIPATED_TURN_DEG: f64 = 120.0;
+
+/// Default RNP for legs without their own value (en-route RNP 2)
+pub const DEFAULT_RNP_NM: f64 = 2.0;
+
+/// Fraction of the RNP at which the deviation counts as approaching it
+const RNP_APPROACHING_FRACTION: f64 = 0.75;
+
+/// Why the active leg changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SequenceReason {
+    /// Within the turn lead distance of the active waypoint
+    TurnAnticipation,
+    /// Abeam or past the active waypoint
+    PassedAbeam,
+    /// Pilot selected direct to a waypoint
+    DirectTo,
+}
+
+/// A change of the active leg
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegChange {
+    /// Waypoint that was active before the change
+    pub from: Option<String>,
+    /// Newly active waypoint; `None` once the last waypoint is sequenced
+    pub to: Option<String>,
+    pub reason: SequenceReason,
+}
+
+/// Lateral deviation relative to the active leg's RNP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RnpState {
+    Within,
+    /// Beyond 75% of the RNP value
+    Approaching,
+    /// Beyond the RNP value
+    Exceeded,
+}
+
+/// Result of an RNP containment check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RnpCheck {
+    /// Waypoint the active leg ends at
+    pub waypoint: String,
+    /// Signed, nautical miles, positive right of course
+    pub cross_track_nm: f64,
+    pub rnp_nm: f64,
+    pub state: RnpState,
+}
+
+/// The route being flown, with FMS-style leg sequencing
+///
+/// The active leg runs from the previous waypoint (or the present position
+/// after a direct-to) to the active waypoint. Position updates sequence to
+/// the next leg when the aircraft is within the turn lead distance of the
+/// active waypoint, or has passed abeam it.
+pub struct ActiveRoute {
+    waypoints: Vec<RouteWaypoint>,
+    /// Index of the waypoint being flown to; `waypoints.len()` once complete
+    active: usize,
+    /// Leg origin set by a direct-to
+    direct_from: Option<Coordinate>,
+    events: Option<Arc<EventBus>>,
+    /// RNP of the leg ending at each waypoint index, when not the default
+    leg_rnp: HashMap<usize, f64>,
+    default_rnp: f64,
+    /// State of the last RNP check, so alerts fire on changes only
+    rnp_state: RnpState,
+}
+
+impl ActiveRoute {
+    /// Start flying `route` with the first leg active
+    pub fn new(route: &FlightRoute) -> Result<Self> {
+        if route.waypoints.len() < 2 {
+            return Err(AeroBaseError::InvalidInput(
+                "Active route needs at least two waypoints".to_string(),
+            ));
+        }
+        Ok(Self {
+            waypoints: route.waypoints.clone(),
+            active: 1,
+            direct_from: None,
+            events: None,
+            leg_rnp: HashMap::new(),
+            default_rnp: DEFAULT_RNP_NM,
+            rnp_state: RnpState::Within,
+        })
+    }
+
+    /// Publish `ActiveLegChanged` on `events` for every leg change
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// RNP for legs without their own value
+    pub fn with_default_rnp(mut self, rnp_nm: f64) -> Self {
+        self.default_rnp = rnp_nm;
+        self
+    }
+
+    /// Set the RNP of the leg(s) ending at `waypoint_id`, e.g. 0.3 on final
+    pub fn set_leg_rnp(&mut self, waypoint_id: &str, rnp_nm: f64) -> Result<()> {
+        if rnp_nm.is_nan() || rnp_nm <= 0.0 {
+            return Err(AeroBaseError::InvalidInput(format!("Invalid RNP value: {}", rnp_nm)));
+        }
+        let mut found = false;
+        for (i, waypoint) in self.waypoints.iter().enumerate().skip(1) {
+            if waypoint.id.eq_ignore_ascii_case(waypoint_id) {
+                self.leg_rnp.insert(i, rnp_nm);
+                found = true;
+            }
+        }
+        if !found {
+            return Err(AeroBaseError::NotFound(format!(
+                "Waypoint {} not in active route",
+                waypoint_id
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn waypoints(&self) -> &[RouteWaypoint] {
+        &self.waypoints
+    }
+
+    /// Waypoint being flown to, `None` once the route is complete
+    pub fn active_waypoint(&self) -> Option<&RouteWaypoint> {
+        self.waypoints.get(self.active)
+    }
+
+    /// Start and end of the active leg
+    pub fn active_leg(&self) -> Option<(Coordinate, Coordinate)> {
+        let to = self.active_waypoint()?.coordinate;
+        let from = self
+            .direct_from
+            .unwrap_or(self.waypoints[self.active - 1].coordinate);
+        Some((from, to))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.active >= self.waypoints.len()
+    }
+
+    /// Distance in nautical miles from `position` to the destination along
+    /// the remaining route
+    pub fn distance_to_go(&self, position: Coordinate) -> Option<f64> {
+        let to = self.active_waypoint()?.coordinate;
+        let remaining: f64 = self.waypoints[self.active..]
+            .windows(2)
+            .map(|pair| pair[0].coordinate.distance_to(&pair[1].coordinate))
+            .sum();
+        Some(position.distance_to(&to) + remaining)
+    }
+
+    /// Top-of-descent guidance from the present position and altitude
+    pub fn descent_advisory(
+        &self,
+        position: Coordinate,
+        altitude: i32,
+        ground_speed_kt: f64,
+        target_altitude: i32,
+        path: DescentPath,
+    ) -> Result<Option<DescentAdvisory>> {
+        let Some(distance_to_go) = self.distance_to_go(position) else {
+            return Ok(None);
+        };
+        let advisory =
+            descent::advisory(distance_to_go, altitude, target_altitude, ground_speed_kt, path)?;
+        Ok(Some(advisory))
+    }
+
+    /// Distance in nautical miles before the active waypoint at which the
+    /// turn onto the next leg starts (rate-one turn at `ground_speed_kt`)
+    pub fn turn_lead_nm(&self, ground_speed_kt: f64) -> f64 {
+        let next = self.waypoints.get(self.active + 1);
+        let (Some((from, to)), Some(next)) = (self.active_leg(), next) else {
+            return 0.0;
+        };
+        let inbound = (to.bearing_to(&from) + 180.0).rem_euclid(360.0);
+        let outbound = to.bearing_to(&next.coordinate);
+        let turn = angle_diff(inbound, outbound).abs().min(MAX_ANTICIPATED_TURN_DEG);
+        turn_radius_nm(ground_speed_kt.max(0.0)) * (turn / 2.0).to_radians().tan()
+    }
+
+    /// Sequence on a new position; returns the leg change, if any
+    pub fn update(&mut self, position: Coordinate, ground_speed_kt: f64) -> Option<LegChange> {
+        let (from, to) = self.active_leg()?;
+        let remaining = from.distance_to(&to) - geometry::along_track_distance(position, from, to);
+
+        let reason = if remaining <= 0.0 {
+            SequenceReason::PassedAbeam
+        } else if remaining <= self.turn_lead_nm(ground_speed_kt) {
+            SequenceReason::TurnAnticipation
+        } else {
+            return None;
+        };
+        Some(self.activate(self.active + 1, None, reason))
+    }
+
+    /// Check the cross-track deviation at `position` against the active leg's RNP
+    ///
+    /// With `with_events`, `RnpAlert` is published whenever the state changes,
+    /// including the return to within limits.
+    pub fn check_rnp(&mut self, position: Coordinate) -> Option<RnpCheck> {
+        let (from, to) = self.active_leg()?;
+        let rnp_nm = self.leg_rnp.get(&self.active).copied().unwrap_or(self.default_rnp);
+        let cross_track_nm = geometry::cross_track_distance(position, from, to);
+        let deviation = cross_track_nm.abs();
+        let state = if deviation > rnp_nm {
+            RnpState::Exceeded
+        } else if deviation > rnp_nm * RNP_APPROACHING_FRACTION {
+            RnpState::Approaching
+        } else {
+            RnpState::Within
+        };
+        let check = RnpCheck {
+            waypoint: self.waypoints[self.active].id.clone(),
+            cross_track_nm,
+            rnp_nm,
+            state,
+        };
+
+        if state != self.rnp_state {
+            self.rnp_state = state;
+            if let Some(events) = &self.events {
+                events.publish(Event::RnpAlert {
+                    waypoint: check.waypoint.clone(),
+                    cross_track_nm,
+                    rnp_nm,
+                    state,
+                });
+            }
+        }
+        Some(check)
+    }
+
+    /// Fly directly from `position` to `waypoint_id`
+    ///
+    /// The first occurrence at or after the active waypoint is preferred, so
+    /// a route that revisits a fix goes to the upcoming one.
+    pub fn direct_to(&mut self, waypoint_id: &str, position: Coordinate) -> Result<LegChange> {
+        let matches = |w: &RouteWaypoint| w.id.eq_ignore_ascii_case(waypoint_id);
+        let index = self
+            .waypoints
+            .iter()
+            .skip(self.active)
+            .position(matches)
+            .map(|i| i + self.active)
+            .or_else(|| self.waypoints.iter().position(matches))
+            .ok_or_else(|| {
+                AeroBaseError::NotFound(format!("Waypoint {} not in active route", waypoint_id))
+            })?;
+        Ok(self.activate(index, Some(position), SequenceReason::DirectTo))
+    }
+
+    fn activate(
+        &mut self,
+        index: usize,
+        direct_from: Option<Coordinate>,
+        reason: SequenceReason,
+    ) -> LegChange {
+        let change = LegChange {
+            from: self.active_waypoint().map(|w| w.id.clone()),
+            to: self.waypoints.get(index).map(|w| w.id.clone()),
+            reason,
+        };
+        self.active = index;
+        self.direct_from = direct_from;
+
+        if let Some(events) = &self.events {
+            events.publish(Event::ActiveLegChanged {
+                from: change.from.clone(),
+                to: change.to.clone(),
+                reason,
+            });
+        }
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlan;
+
+    /// North 60 nm, then east 60 nm
+    fn route() -> FlightRoute {
+        let waypoint = |id: &str, latitude: f64, longitude: f64| RouteWaypoint {
+            id: id.to_string(),
+            name: id.to_string(),
+            coordinate: Coordinate::new(latitude, longitude),
+            distance_from_previous: 0.0,
+            cumulative_distance: 0.0,
+            estimated_time: 0,
+        };
+        FlightRoute {
+            plan: FlightPlan {
+                departure: "AAAA".to_string(),
+                destination: "BBBB".to_string(),
+                alternate: None,
+                cruise_altitude: 8000,
+                cruise_speed: 240,
+                route: vec!["CORNR".to_string()],
+                ..Default::default()
+            },
+            total_distance: 0.0,
+            estimated_time: 0,
+            waypoints: vec![
+                waypoint("AAAA", 0.0, 0.0),
+                waypoint("CORNR", 1.0, 0.0),
+                waypoint("BBBB", 1.0, 1.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_turn_anticipation() {
+        let mut active = ActiveRoute::new(&route()).unwrap();
+        // 90° turn at 240 kt: rate-one radius ~1.27 nm, lead = radius
+        let lead = active.turn_lead_nm(240.0);
+        assert!((lead - 1.273).abs() < 0.01, "{}", lead);
+
+        assert_eq!(active.update(Coordinate::new(0.5, 0.0), 240.0), None);
+        assert_eq!(active.update(Coordinate::new(1.0 - 2.0 / 60.0, 0.0), 240.0), None);
+        let change = active.update(Coordinate::new(1.0 - 1.0 / 60.0, 0.0), 240.0).unwrap();
+        assert_eq!(change.reason, SequenceReason::TurnAnticipation);
+        assert_eq!(change.from.as_deref(), Some("CORNR"));
+        assert_eq!(change.to.as_deref(), Some("BBBB"));
+        assert_eq!(active.active_waypoint().unwrap().id, "BBBB");
+    }
+
+    #[test]
+    fn test_passing_abeam_and_completion() {
+        let bus = Arc::new(EventBus::new());
+        let mut receiver = bus.subscribe();
+        let mut active = ActiveRoute::new(&route()).unwrap().with_events(bus);
+
+        // Off course, abeam the corner: no turn lead at zero speed
+        let change = active.update(Coordinate::new(1.01, 0.3), 0.0).unwrap();
+        assert_eq!(change.reason, SequenceReason::PassedAbeam);
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            Event::ActiveLegChanged { to: Some(to), .. } if to == "BBBB"
+        ));
+
+        // The last waypoint has no turn to anticipate
+        assert_eq!(active.update(Coordinate::new(1.0, 0.99), 240.0), None);
+        let change = active.update(Coordinate::new(1.0, 1.01), 240.0).unwrap();
+        assert_eq!(change.to, None);
+        assert!(active.is_complete());
+        assert_eq!(active.update(Coordinate::new(1.0, 1.5), 240.0), None);
+    }
+
+    #[test]
+    fn test_rnp_containment() {
+        let bus = Arc::new(EventBus::new());
+        let mut receiver = bus.subscribe();
+        let mut active = ActiveRoute::new(&route())
+            .unwrap()
+            .with_default_rnp(1.0)
+            .with_events(bus);
+
+        // 0.5 nm right of the northbound leg
+        let check = active.check_rnp(Coordinate::new(0.5, 0.5 / 60.0)).unwrap();
+        assert_eq!(check.state, RnpState::Within);
+        assert!((check.cross_track_nm - 0.5).abs() < 0.01);
+        assert!(receiver.try_recv().is_err());
+
+        let check = active.check_rnp(Coordinate::new(0.5, -0.8 / 60.0)).unwrap();
+        assert_eq!(check.state, RnpState::Approaching);
+        assert!(check.cross_track_nm < 0.0);
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            Event::RnpAlert { state: RnpState::Approaching, .. }
+        ));
+        // Unchanged state: no repeat alert
+        active.check_rnp(Coordinate::new(0.5, -0.9 / 60.0)).unwrap();
+        assert!(receiver.try_recv().is_err());
+
+        // Tighter RNP on the leg to CORNR
+        active.set_leg_rnp("CORNR", 0.3).unwrap();
+        let check = active.check_rnp(Coordinate::new(0.5, 0.5 / 60.0)).unwrap();
+        assert_eq!((check.rnp_nm, check.state), (0.3, RnpState::Exceeded));
+        assert!(active.set_leg_rnp("ZZZZ", 0.3).is_err());
+        assert!(active.set_leg_rnp("CORNR", 0.0).is_err());
+    }
+
+    #[test]
+    fn test_descent_advisory() {
+        let mut active = ActiveRoute::new(&route()).unwrap();
+        let path = DescentPath::Rate {
+            vertical_speed_fpm: 1200.0,
+            ground_speed_kt: 240.0,
+        };
+        // Halfway up the first leg: 30 + 60 nm to go; 6000 ft at 300 ft/nm is 20 nm
+        let position = Coordinate::new(0.5, 0.0);
+        let to_go = active.distance_to_go(position).unwrap();
+        assert!((to_go - 90.0).abs() < 0.2, "{}", to_go);
+        let advisory = active
+            .descent_advisory(position, 8000, 240.0, 2000, path)
+            .unwrap()
+            .unwrap();
+        assert!((advisory.distance_to_tod_nm - 70.0).abs() < 0.2);
+        assert!(!advisory.descend_now);
+
+        active.update(Coordinate::new(1.0, 0.01), 240.0).unwrap();
+        let advisory = active
+            .descent_advisory(Coordinate::new(1.0, 0.9), 8000, 240.0, 2000, path)
+            .unwrap()
+            .unwrap();
+        assert!(advisory.descend_now);
+        // 6 nm to go at 240 kt: 6000 ft in 1.5 minutes
+        let required = advisory.required_vertical_speed_fpm.unwrap();
+        assert!((required - 4000.0).abs() < 50.0, "{}", required);
+
+        active.update(Coordinate::new(1.0, 1.01), 240.0).unwrap();
+        assert_eq!(active.descent_advisory(position, 8000, 240.0, 2000, path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_direct_to() {
+        let mut active = ActiveRoute::new(&route()).unwrap();
+        let position = Coordinate::new(0.5, 0.5);
+        let change = active.direct_to("bbbb", position).unwrap();
+        assert_eq!(change.reason, SequenceReason::DirectTo);
+        assert_eq!(change.from.as_deref(), Some("CORNR"));
+        assert_eq!(active.active_leg(), Some((position, Coordinate::new(1.0, 1.0))));
+        // Final waypoint: nothing to anticipate
+        assert_eq!(active.turn_lead_nm(240.0), 0.0);
+
+        assert!(matches!(
+            active.direct_to("ZZZZ", position),
+            Err(AeroBaseError::NotFound(_))
+        ));
+        // Back along the route
+        active.direct_to("CORNR", position).unwrap();
+        assert_eq!(active.active_waypoint().unwrap().id, "CORNR");
+    }
+}
@@ -0,0 +1,571 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::{planner, FlightPlan, FlightRoute};
+use crate::models::airway::AirwayType;
+use crate::models::hazard::Hazard;
+use crate::models::Coordinate;
+use crate::spatial::{geometry, query};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// How far from an airport to look for a fix joining the airway network
+pub const DEFAULT_CONNECTION_RADIUS_NM: f64 = 60.0;
+
+/// Limits on the airways `find_route` may use
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteConstraints {
+    /// Feet; airways whose altitude limits exclude it are not used
+    pub cruise_altitude: i32,
+    /// Knots, for the route's time estimates
+    pub cruise_speed: i32,
+    /// Airway fixes within this distance of the departure or destination can
+    /// be joined or left with a direct leg
+    pub connection_radius_nm: f64,
+    /// Allowed airway types; empty allows all
+    pub airway_types: Vec<AirwayType>,
+    /// Airway names not to fly
+    pub avoid_airways: Vec<String>,
+    /// Waypoint ids not to fly through
+    pub avoid_waypoints: Vec<String>,
+    /// Unix seconds; legs crossing a hazard active then at the cruise
+    /// altitude are not flown. `None` uses the current time
+    pub departure_time: Option<i64>,
+}
+
+impl RouteConstraints {
+    pub fn new(cruise_altitude: i32, cruise_speed: i32) -> Self {
+        Self {
+            cruise_altitude,
+            cruise_speed,
+            connection_radius_nm: DEFAULT_CONNECTION_RADIUS_NM,
+            airway_types: Vec::new(),
+            avoid_airways: Vec::new(),
+            avoid_waypoints: Vec::new(),
+            departure_time: None,
+        }
+    }
+
+    pub fn with_connection_radius(mut self, radius_nm: f64) -> Self {
+        self.connection_radius_nm = radius_nm;
+        self
+    }
+
+    pub fn with_airway_types(mut self, types: Vec<AirwayType>) -> Self {
+        self.airway_types = types;
+        self
+    }
+
+    pub fn avoid_airway(mut self, name: &str) -> Self {
+        self.avoid_airways.push(name.trim().to_uppercase());
+        self
+    }
+
+    pub fn avoid_waypoint(mut self, id: &str) -> Self {
+        self.avoid_waypoints.push(id.to_string());
+        self
+    }
+
+    pub fn with_departure_time(mut self, at: i64) -> Self {
+        self.departure_time = Some(at);
+        self
+    }
+}
+
+/// One leg of a generated route
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirwayLeg {
+    /// Waypoint id, or the departure code on the first leg
+    pub from: String,
+    /// Waypoint id, or the destination code on the last leg
+    pub to: String,
+    /// `None` for the direct legs joining and leaving the airway network
+    pub airway: Option<String>,
+    pub distance_nm: f64,
+}
+
+/// A route found through the airway network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirwayRoute {
+    pub route: FlightRoute,
+    /// One per pair of consecutive route waypoints
+    pub legs: Vec<AirwayLeg>,
+}
+
+impl AirwayRoute {
+    /// The route in flight plan notation, e.g. `"ALPHA A461 BRAVO G212 CHARL"`:
+    /// the entry fix, then each airway with the fix where it is left
+    pub fn route_string(&self) -> String {
+        let names: Vec<&str> = self.route.waypoints.iter().map(|w| w.name.as_str()).collect();
+        let Some(entry) = names.get(1).filter(|_| self.legs.len() > 1) else {
+            return "DCT".to_string();
+        };
+        let mut tokens = vec![entry.to_string()];
+        let airway_legs = &self.legs[1..self.legs.len() - 1];
+        for (i, leg) in airway_legs.iter().enumerate() {
+            let next = airway_legs.get(i + 1).and_then(|next| next.airway.as_ref());
+            if leg.airway.is_some() && next == leg.airway.as_ref() {
+                continue;
+            }
+            tokens.push(leg.airway.clone().unwrap_or_else(|| "DCT".to_string()));
+            tokens.push(names[i + 2].to_string());
+        }
+        tokens.join(" ")
+    }
+}
+
+struct Edge {
+    to: usize,
+    airway: Option<String>,
+    distance: f64,
+}
+
+/// Node index and its estimated total cost, ordered for a min-heap
+struct Visit {
+    estimate: f64,
+    node: usize,
+}
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate.total_cmp(&other.estimate) == Ordering::Equal
+    }
+}
+
+impl Eq for Visit {}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+const DEPARTURE: usize = 0;
+const DESTINATION: usize = 1;
+
+/// The airway network usable under some constraints
+struct Graph {
+    /// Waypoint ids by node; the first two are the airport codes
+    ids: Vec<String>,
+    coordinates: Vec<Coordinate>,
+    edges: Vec<Vec<Edge>>,
+}
+
+impl Graph {
+    fn node(&mut self, index: &mut HashMap<String, usize>, id: String, at: Coordinate) -> usize {
+        *index.entry(id.clone()).or_insert_with(|| {
+            self.ids.push(id);
+            self.coordinates.push(at);
+            self.edges.push(Vec::new());
+            self.ids.len() - 1
+        })
+    }
+
+    fn connect(&mut self, from: usize, to: usize, airway: Option<String>, distance: f64) {
+        self.edges[from].push(Edge { to, airway, distance });
+    }
+}
+
+/// Shortest airway routing from `departure` to `destination`
+///
+/// Airway segments are flown in either direction. The route joins the
+/// network with a direct leg to a fix within `connection_radius_nm` of the
+/// departure and leaves it the same way near the destination; between
+/// them A* picks the shortest path by distance. If either airport has no
+/// fix within the radius, a direct leg between the airports is offered
+/// instead.
+///
+/// No leg, airway or direct, may cross a hazard polygon active at the
+/// departure time (now by the database's clock, if unset) and cruise
+/// altitude.
+pub fn find_route(
+    db: &Database,
+    departure: &str,
+    destination: &str,
+    constraints: &RouteConstraints,
+) -> Result<AirwayRoute> {
+    let at = constraints
+        .departure_time
+        .unwrap_or_else(|| db.clock().timestamp());
+    let hazards: Vec<Hazard> = query::find_hazards_in(
+        db,
+        Coordinate::new(-90.0, -180.0),
+        Coordinate::new(90.0, 180.0),
+        at,
+        at,
+    )?
+    .into_iter()
+    .filter(|hazard| hazard.applies_at(constraints.cruise_altitude))
+    .collect();
+    let graph = {
+        let conn = db.get_conn()?;
+        let origin = planner::get_airport_coordinate(&conn, departure)?;
+        let target = planner::get_airport_coordinate(&conn, destination)?;
+        load_graph(&conn, (departure, origin), (destination, target), constraints, &hazards)?
+    };
+
+    let path = search(&graph).ok_or_else(|| {
+        AeroBaseError::FlightPlanning(format!(
+            "No airway route from {} to {} at {} ft",
+            departure, destination, constraints.cruise_altitude
+        ))
+    })?;
+
+    let plan = FlightPlan {
+        departure: departure.to_string(),
+        destination: destination.to_string(),
+        alternate: None,
+        cruise_altitude: constraints.cruise_altitude,
+        cruise_speed: constraints.cruise_speed,
+        route: path[1..path.len() - 1]
+            .iter()
+            .map(|(node, _)| graph.ids[*node].clone())
+            .collect(),
+        ..Default::default()
+    };
+    let route = planner::calculate_route(db, &plan)?;
+    let legs = path
+        .windows(2)
+        .zip(route.waypoints.iter().skip(1))
+        .map(|(pair, waypoint)| AirwayLeg {
+            from: graph.ids[pair[0].0].clone(),
+            to: graph.ids[pair[1].0].clone(),
+            airway: pair[1].1.clone(),
+            distance_nm: waypoint.distance_from_previous,
+        })
+        .collect();
+    Ok(AirwayRoute { route, legs })
+}
+
+fn load_graph(
+    conn: &rusqlite::Connection,
+    (departure, origin): (&str, Coordinate),
+    (destination, target): (&str, Coordinate),
+    constraints: &RouteConstraints,
+    hazards: &[Hazard],
+) -> Result<Graph> {
+    let blocked = |from: Coordinate, to: Coordinate| {
+        hazards
+            .iter()
+            .any(|hazard| geometry::segment_crosses_polygon(from, to, &hazard.boundary))
+    };
+    let mut graph = Graph {
+        ids: vec![departure.to_string(), destination.to_string()],
+        coordinates: vec![origin, target],
+        edges: vec![Vec::new(), Vec::new()],
+    };
+    let mut index = HashMap::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT a.name, a.type, a.min_altitude, a.max_altitude, s.distance,
+                s.from_waypoint_id, f.latitude, f.longitude,
+                s.to_waypoint_id, t.latitude, t.longitude
+         FROM airway_segments s
+         JOIN airways a ON a.id = s.airway_id
+         JOIN waypoints f ON f.id = s.from_waypoint_id
+         JOIN waypoints t ON t.id = s.to_waypoint_id",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(0)?;
+        let airway_type = AirwayType::from_str(&row.get::<_, String>(1)?);
+        let min_altitude: Option<i32> = row.get(2)?;
+        let max_altitude: Option<i32> = row.get(3)?;
+        let from_id: String = row.get(5)?;
+        let to_id: String = row.get(8)?;
+        if min_altitude.is_some_and(|min| constraints.cruise_altitude < min)
+            || max_altitude.is_some_and(|max| constraints.cruise_altitude > max)
+            || (!constraints.airway_types.is_empty()
+                && !constraints.airway_types.contains(&airway_type))
+            || constraints.avoid_airways.contains(&name.to_uppercase())
+            || constraints.avoid_waypoints.contains(&from_id)
+            || constraints.avoid_waypoints.contains(&to_id)
+        {
+            continue;
+        }
+
+        let from_at = Coordinate::new(row.get(6)?, row.get(7)?);
+        let to_at = Coordinate::new(row.get(9)?, row.get(10)?);
+        if blocked(from_at, to_at) {
+            continue;
+        }
+        let distance = row
+            .get::<_, Option<f64>>(4)?
+            .unwrap_or_else(|| from_at.distance_to(&to_at));
+        let from = graph.node(&mut index, from_id, from_at);
+        let to = graph.node(&mut index, to_id, to_at);
+        graph.connect(from, to, Some(name.clone()), distance);
+        graph.connect(to, from, Some(name), distance);
+    }
+
+    let (mut joined, mut left) = (false, false);
+    for node in 2..graph.ids.len() {
+        let at = graph.coordinates[node];
+        let from_origin = origin.distance_to(&at);
+        if from_origin <= constraints.connection_radius_nm {
+            joined = true;
+            if !blocked(origin, at) {
+                graph.connect(DEPARTURE, node, None, from_origin);
+            }
+        }
+        let to_target = at.distance_to(&target);
+        if to_target <= constraints.connection_radius_nm {
+            left = true;
+            if !blocked(at, target) {
+                graph.connect(node, DESTINATION, None, to_target);
+            }
+        }
+    }
+    if (!joined || !left) && !blocked(origin, target) {
+        graph.connect(DEPARTURE, DESTINATION, None, origin.distance_to(&target));
+    }
+    Ok(graph)
+}
+
+/// A* from the departure to the destination node; each node comes with the
+/// airway it was reached by
+fn search(graph: &Graph) -> Option<Vec<(usize, Option<String>)>> {
+    let target = graph.coordinates[DESTINATION];
+    let heuristic = |node: usize| graph.coordinates[node].distance_to(&target);
+
+    let mut cost = vec![f64::INFINITY; graph.ids.len()];
+    let mut came_from: Vec<Option<(usize, usize)>> = vec![None; graph.ids.len()];
+    let mut open = BinaryHeap::new();
+    cost[DEPARTURE] = 0.0;
+    open.push(Visit {
+        estimate: heuristic(DEPARTURE),
+        node: DEPARTURE,
+    });
+
+    while let Some(Visit { estimate, node }) = open.pop() {
+        if node == DESTINATION {
+            break;
+        }
+        // Stale entry for a node already reached more cheaply
+        if estimate > cost[node] + heuristic(node) + 1e-9 {
+            continue;
+        }
+        for (edge_index, edge) in graph.edges[node].iter().enumerate() {
+            let reached = cost[node] + edge.distance;
+            if reached < cost[edge.to] {
+                cost[edge.to] = reached;
+                came_from[edge.to] = Some((node, edge_index));
+                open.push(Visit {
+                    estimate: reached + heuristic(edge.to),
+                    node: edge.to,
+                });
+            }
+        }
+    }
+
+    came_from[DESTINATION]?;
+    let mut path = vec![];
+    let mut node = DESTINATION;
+    while let Some((previous, edge_index)) = came_from[node] {
+        path.push((node, graph.edges[previous][edge_index].airway.clone()));
+        node = previous;
+    }
+    path.push((DEPARTURE, None));
+    path.reverse();
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        // A high route via MIDWE on two airways, a longer high airway via
+        // EASTT, and a low airway there too
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'ENTRY', 39.80, 116.80, 'FIX', 0),
+                    ('W2', 'MIDWE', 36.00, 118.50, 'FIX', 0),
+                    ('W3', 'EASTT', 37.00, 121.50, 'FIX', 0),
+                    ('W4', 'EXITT', 31.50, 121.00, 'FIX', 0),
+                    ('W5', 'FARAW', 45.00, 100.00, 'FIX', 0);
+                 INSERT INTO airways (id, name, type, min_altitude, max_altitude, created_at) VALUES
+                    ('AW1', 'A461', 'HIGH', 20000, NULL, 0),
+                    ('AW2', 'G212', 'HIGH', 20000, NULL, 0),
+                    ('AW3', 'B208', 'LOW', NULL, 20000, 0),
+                    ('AW4', 'H123', 'HIGH', 20000, NULL, 0);
+                 INSERT INTO airway_segments
+                    (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at) VALUES
+                    ('S1', 'AW1', 'W1', 'W2', 1, 0),
+                    ('S2', 'AW2', 'W4', 'W2', 1, 0),
+                    ('S3', 'AW3', 'W1', 'W3', 1, 0),
+                    ('S4', 'AW3', 'W3', 'W4', 2, 0),
+                    ('S5', 'AW3', 'W5', 'W1', 0, 0),
+                    ('S6', 'AW4', 'W1', 'W3', 1, 0),
+                    ('S7', 'AW4', 'W3', 'W4', 2, 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_find_route() {
+        let (db, _temp_file) = setup_test_db();
+
+        let high = find_route(&db, "ZBAA", "ZSSS", &RouteConstraints::new(35000, 450)).unwrap();
+        assert_eq!(high.route.plan.route, vec!["W1", "W2", "W4"]);
+        let airways: Vec<_> = high.legs.iter().map(|leg| leg.airway.as_deref()).collect();
+        // G212 is flown against its segment direction
+        assert_eq!(airways, vec![None, Some("A461"), Some("G212"), None]);
+        assert_eq!((high.legs[0].from.as_str(), high.legs[3].to.as_str()), ("ZBAA", "ZSSS"));
+        assert_eq!(high.route_string(), "ENTRY A461 MIDWE G212 EXITT");
+        let total: f64 = high.legs.iter().map(|leg| leg.distance_nm).sum();
+        assert!((total - high.route.total_distance).abs() < 1e-6);
+
+        let low = find_route(&db, "ZBAA", "ZSSS", &RouteConstraints::new(15000, 250)).unwrap();
+        assert_eq!(low.route.plan.route, vec!["W1", "W3", "W4"]);
+        assert_eq!(low.route_string(), "ENTRY B208 EXITT");
+    }
+
+    fn assert_direct(route: &AirwayRoute) {
+        assert!(route.route.plan.route.is_empty());
+        assert_eq!(route.legs.len(), 1);
+        assert_eq!(route.legs[0].airway, None);
+        assert_eq!((route.legs[0].from.as_str(), route.legs[0].to.as_str()), ("ZBAA", "ZSSS"));
+        assert_eq!(route.route_string(), "DCT");
+    }
+
+    #[test]
+    fn test_altitude_band() {
+        let (db, _temp_file) = setup_test_db();
+        let route = |altitude| {
+            find_route(&db, "ZBAA", "ZSSS", &RouteConstraints::new(altitude, 300))
+                .unwrap()
+                .route
+                .plan
+                .route
+        };
+
+        // Both limits are inclusive, so at 20000 ft every airway is open
+        assert_eq!(route(20000), vec!["W1", "W2", "W4"]);
+        assert_eq!(route(19999), vec!["W1", "W3", "W4"]);
+        assert_eq!(route(20001), vec!["W1", "W2", "W4"]);
+    }
+
+    #[test]
+    fn test_avoid_airway_and_waypoint() {
+        let (db, _temp_file) = setup_test_db();
+
+        let no_a461 = RouteConstraints::new(35000, 450).avoid_airway("a461");
+        let found = find_route(&db, "ZBAA", "ZSSS", &no_a461).unwrap();
+        assert_eq!(found.route_string(), "ENTRY H123 EXITT");
+
+        let no_midwe = RouteConstraints::new(35000, 450).avoid_waypoint("W2");
+        let found = find_route(&db, "ZBAA", "ZSSS", &no_midwe).unwrap();
+        assert_eq!(found.route.plan.route, vec!["W1", "W3", "W4"]);
+
+        // Nothing high is left at ENTRY, so the airports are joined directly
+        let neither = no_a461.avoid_airway("H123");
+        assert_direct(&find_route(&db, "ZBAA", "ZSSS", &neither).unwrap());
+    }
+
+    #[test]
+    fn test_direct_fallback() {
+        let (db, _temp_file) = setup_test_db();
+
+        // Without EASTT, no low airway reaches EXITT
+        let avoiding = RouteConstraints::new(15000, 250).avoid_waypoint("W3");
+        assert_direct(&find_route(&db, "ZBAA", "ZSSS", &avoiding).unwrap());
+        let typed = RouteConstraints::new(35000, 450).with_airway_types(vec![AirwayType::RNAV]);
+        assert_direct(&find_route(&db, "ZBAA", "ZSSS", &typed).unwrap());
+        let near = RouteConstraints::new(35000, 450).with_connection_radius(5.0);
+        let direct = find_route(&db, "ZBAA", "ZSSS", &near).unwrap();
+        assert_direct(&direct);
+        assert!((direct.legs[0].distance_nm - direct.route.total_distance).abs() < 1e-6);
+    }
+
+    /// Rectangular hazard active from 0 to 3600
+    fn insert_hazard(
+        db: &Database,
+        id: &str,
+        (south, north): (f64, f64),
+        (west, east): (f64, f64),
+    ) {
+        let conn = db.get_conn().unwrap();
+        conn.execute(
+            "INSERT INTO hazards (id, kind, name, valid_from, valid_to, min_latitude,
+                max_latitude, min_longitude, max_longitude, created_at)
+             VALUES (?1, 'TFR', ?1, 0, 3600, ?2, ?3, ?4, ?5, 0)",
+            rusqlite::params![id, south, north, west, east],
+        )
+        .unwrap();
+        let corners = [(south, west), (north, west), (north, east), (south, east)];
+        for (i, (lat, lon)) in corners.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO hazard_boundaries (id, hazard_id, latitude, longitude, sequence)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![format!("{}-{}", id, i), id, lat, lon, i as i64],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_hazard_avoidance() {
+        let (db, _temp_file) = setup_test_db();
+        // Around MIDWE, also covering the direct line between the airports
+        insert_hazard(&db, "TFR1", (35.5, 36.5), (118.0, 119.0));
+
+        let active = RouteConstraints::new(35000, 450).with_departure_time(600);
+        let found = find_route(&db, "ZBAA", "ZSSS", &active).unwrap();
+        assert_eq!(found.route.plan.route, vec!["W1", "W3", "W4"]);
+        assert_eq!(found.route_string(), "ENTRY H123 EXITT");
+
+        let lifted = RouteConstraints::new(35000, 450).with_departure_time(7200);
+        let found = find_route(&db, "ZBAA", "ZSSS", &lifted).unwrap();
+        assert_eq!(found.route.plan.route, vec!["W1", "W2", "W4"]);
+
+        // Without a departure time, the database's clock decides
+        let unset = RouteConstraints::new(35000, 450);
+        let clock = std::sync::Arc::new(crate::clock::MockClock::at_timestamp(600));
+        let db = db.with_clock(clock.clone());
+        let found = find_route(&db, "ZBAA", "ZSSS", &unset).unwrap();
+        assert_eq!(found.route.plan.route, vec!["W1", "W3", "W4"]);
+        clock.advance(chrono::TimeDelta::hours(2));
+        let found = find_route(&db, "ZBAA", "ZSSS", &unset).unwrap();
+        assert_eq!(found.route.plan.route, vec!["W1", "W2", "W4"]);
+    }
+
+    #[test]
+    fn test_no_route() {
+        let (db, _temp_file) = setup_test_db();
+        // Across both the leg to ENTRY and the direct line
+        insert_hazard(&db, "TFR1", (39.90, 39.98), (116.62, 116.76));
+
+        let constraints = RouteConstraints::new(35000, 450).with_departure_time(0);
+        assert!(matches!(
+            find_route(&db, "ZBAA", "ZSSS", &constraints),
+            Err(AeroBaseError::FlightPlanning(_))
+        ));
+        let later = constraints.clone().with_departure_time(4000);
+        assert!(find_route(&db, "ZBAA", "ZSSS", &later).is_ok());
+        assert!(find_route(&db, "XXXX", "ZSSS", &constraints).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::error::{AeroBaseError, Result};
+use crate::flight::FlightRoute;
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+use serde::{Deserialize, Serialize};
+
+/// A recorded position fix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackPoint {
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    pub coordinate: Coordinate,
+    pub altitude: Option<i32>,
+    /// Fuel on board in gallons, when the recorder logs it
+    pub fuel_remaining: Option<f64>,
+}
+
+/// Planned versus flown figures for one route leg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegComparison {
+    pub from: String,
+    pub to: String,
+    pub planned_minutes: i32,
+    pub actual_minutes: f64,
+    pub max_cross_track_nm: f64,
+    pub avg_cross_track_nm: f64,
+}
+
+/// Post-flight comparison of a recorded track against its planned route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostFlightReport {
+    pub legs: Vec<LegComparison>,
+    pub max_cross_track_nm: f64,
+    pub avg_cross_track_nm: f64,
+    pub planned_minutes: i32,
+    pub actual_minutes: f64,
+    /// Planned trip fuel (no reserves or taxi), when a fuel flow was given
+    pub planned_fuel: Option<f64>,
+    /// Fuel burned according to the track's fuel readings
+    pub actual_fuel: Option<f64>,
+}
+
+impl PostFlightReport {
+    /// Actual minus planned fuel; positive means more was burned than planned
+    pub fn fuel_variance(&self) -> Option<f64> {
+        Some(self.actual_fuel? - self.planned_fuel?)
+    }
+
+    /// Serialize the report as JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One CSV row per leg followed by a TOTAL row carrying the fuel figures
+    pub fn to_csv(&self) -> String {
+        fn opt(value: Option<f64>) -> String {
+            value.map(|v| format!("{:.1}", v)).unwrap_or_default()
+        }
+
+        let mut csv = String::from(
+            "from,to,planned_minutes,actual_minutes,max_cross_track_nm,avg_cross_track_nm,planned_fuel,actual_fuel,fuel_variance\n",
+        );
+        for leg in &self.legs {
+            csv.push_str(&format!(
+                "{},{},{},{:.1},{:.2},{:.2},,,\n",
+                leg.from,
+                leg.to,
+                leg.planned_minutes,
+                leg.actual_minutes,
+                leg.max_cross_track_nm,
+                leg.avg_cross_track_nm
+            ));
+        }
+        csv.push_str(&format!(
+            "TOTAL,,{},{:.1},{:.2},{:.2},{},{},{}\n",
+            self.planned_minutes,
+            self.actual_minutes,
+            self.max_cross_track_nm,
+            self.avg_cross_track_nm,
+            opt(self.planned_fuel),
+            opt(self.actual_fuel),
+            opt(self.fuel_variance())
+        ));
+        csv
+    }
+}
+
+/// Compare a recorded track with the route it was meant to fly
+///
+/// Each route waypoint is matched to the closest track point after the
+/// previous waypoint's match; the points between two matches are measured
+/// against that leg.
+pub fn analyze(
+    route: &FlightRoute,
+    track: &[TrackPoint],
+    fuel_flow_gph: Option<f64>,
+) -> Result<PostFlightReport> {
+    if route.waypoints.len() < 2 {
+        return Err(AeroBaseError::InvalidInput(
+            "Route needs at least two waypoints".to_string(),
+        ));
+    }
+    if track.len() < 2 {
+        return Err(AeroBaseError::InvalidInput(
+            "Track needs at least two points".to_string(),
+        ));
+    }
+    if track.windows(2).any(|w| w[1].timestamp < w[0].timestamp) {
+        return Err(AeroBaseError::InvalidInput(
+            "Track points must be in time order".to_string(),
+        ));
+    }
+
+    let mut passages = Vec::with_capacity(route.waypoints.len());
+    let mut start = 0;
+    for waypoint in &route.waypoints {
+        let (index, _) = track[start..]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (start + i, p.coordinate.distance_to(&waypoint.coordinate)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("search window is never empty");
+        passages.push(index);
+        start = index;
+    }
+
+    let mut legs = Vec::with_capacity(route.waypoints.len() - 1);
+    let mut errors = Vec::new();
+    for (i, pair) in route.waypoints.windows(2).enumerate() {
+        let (from, to) = (&pair[0], &pair[1]);
+        let points = &track[passages[i]..=passages[i + 1]];
+        let leg_errors: Vec<f64> = points
+            .iter()
+            .map(|p| geometry::distance_to_leg(p.coordinate, from.coordinate, to.coordinate))
+            .collect();
+
+        legs.push(LegComparison {
+            from: from.id.clone(),
+            to: to.id.clone(),
+            planned_minutes: to.estimated_time - from.estimated_time,
+            actual_minutes: minutes_between(&track[passages[i]], &track[passages[i + 1]]),
+            max_cross_track_nm: leg_errors.iter().copied().fold(0.0, f64::max),
+            avg_cross_track_nm: mean(&leg_errors),
+        });
+        errors.extend(leg_errors);
+    }
+
+    let first = &track[0];
+    let last = &track[track.len() - 1];
+    let actual_fuel = first
+        .fuel_remaining
+        .zip(last.fuel_remaining)
+        .map(|(start, end)| start - end);
+    let planned_fuel = fuel_flow_gph.map(|flow| route.estimated_time as f64 / 60.0 * flow);
+
+    Ok(PostFlightReport {
+        max_cross_track_nm: errors.iter().copied().fold(0.0, f64::max),
+        avg_cross_track_nm: mean(&errors),
+        planned_minutes: route.estimated_time,
+        actual_minutes: minutes_between(first, last),
+        planned_fuel,
+        actual_fuel,
+        legs,
+    })
+}
+
+fn minutes_between(from: &TrackPoint, to: &TrackPoint) -> f64 {
+    (to.timestamp - from.timestamp) as f64 / 60.0
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{FlightPlan, RouteWaypoint};
+
+    fn waypoint(id: &str, latitude: f64, cumulative: f64, minutes: i32) -> RouteWaypoint {
+        RouteWaypoint {
+            id: id.to_string(),
+            name: id.to_string(),
+            coordinate: Coordinate::new(latitude, 0.0),
+            distance_from_previous: 0.0,
+            cumulative_distance: cumulative,
+            estimated_time: minutes,
+        }
+    }
+
+    fn route() -> FlightRoute {
+        FlightRoute {
+            plan: FlightPlan {
+                departure: "AAAA".to_string(),
+                destination: "BBBB".to_string(),
+                alternate: None,
+                cruise_altitude: 9000,
+                cruise_speed: 120,
+                route: vec!["MID".to_string()],
+                ..Default::default()
+            },
+            total_distance: 120.0,
+            estimated_time: 60,
+            waypoints: vec![
+                waypoint("AAAA", 0.0, 0.0, 0),
+                waypoint("MID", 1.0, 60.0, 30),
+                waypoint("BBBB", 2.0, 120.0, 60),
+            ],
+        }
+    }
+
+    fn point(minutes: i64, latitude: f64, longitude: f64, fuel: f64) -> TrackPoint {
+        TrackPoint {
+            timestamp: minutes * 60,
+            coordinate: Coordinate::new(latitude, longitude),
+            altitude: None,
+            fuel_remaining: Some(fuel),
+        }
+    }
+
+    #[test]
+    fn test_analyze_track() {
+        // Drift ~6 nm right on the first leg, on course for the second
+        let track = vec![
+            point(0, 0.0, 0.0, 50.0),
+            point(20, 0.5, 0.1, 44.0),
+            point(35, 1.0, 0.0, 40.0),
+            point(55, 1.5, 0.0, 35.0),
+            point(70, 2.0, 0.0, 30.0),
+        ];
+
+        let report = analyze(&route(), &track, Some(18.0)).unwrap();
+        assert_eq!(report.legs.len(), 2);
+        assert_eq!(report.legs[0].planned_minutes, 30);
+        assert_eq!(report.legs[0].actual_minutes, 35.0);
+        assert_eq!(report.legs[1].actual_minutes, 35.0);
+        assert!((report.legs[0].max_cross_track_nm - 6.0).abs() < 0.1);
+        assert!(report.legs[1].max_cross_track_nm < 0.01);
+        assert!((report.max_cross_track_nm - 6.0).abs() < 0.1);
+        assert_eq!(report.actual_minutes, 70.0);
+
+        assert_eq!(report.planned_fuel, Some(18.0));
+        assert_eq!(report.actual_fuel, Some(20.0));
+        assert_eq!(report.fuel_variance(), Some(2.0));
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().last().unwrap().ends_with(",18.0,20.0,2.0"));
+        assert!(report.to_json().unwrap().contains("\"max_cross_track_nm\""));
+    }
+
+    #[test]
+    fn test_analyze_rejects_bad_input() {
+        let track = vec![point(10, 0.0, 0.0, 50.0), point(0, 2.0, 0.0, 30.0)];
+        assert!(analyze(&route(), &track, None).is_err());
+        assert!(analyze(&route(), &track[..1], None).is_err());
+
+        let ordered = vec![point(0, 0.0, 0.0, 50.0), point(60, 2.0, 0.0, 30.0)];
+        let report = analyze(&route(), &ordered, None).unwrap();
+        assert_eq!(report.fuel_variance(), None);
+    }
+}
@@ -0,0 +1,543 @@
+use crate::db::query::normalize_code;
+use crate::db::Database;
+use crate::error::Result;
+use crate::flight::calculator::TAXI_FUEL_FRACTION;
+use crate::flight::fuel::FuelPlan;
+use crate::flight::hazards::{self, RouteHazard};
+use crate::flight::profile::{self, AirspaceSpan, ProfileOptions};
+use crate::flight::{FlightPlan, FlightRoute};
+use crate::navdata::cycle::{self, AiracCycle, ExpiryPolicy, NavdataStatus};
+use crate::notam::{self, Notam};
+use crate::spatial::geometry;
+use crate::weather::atis::{self, Atis};
+use crate::weather::sigmet::{self, RouteSigmet};
+use crate::weather::taf::Taf;
+use crate::weather::valid_taf;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Default distance either side of the route searched for area NOTAMs
+pub const NOTAM_CORRIDOR_NM: f64 = 10.0;
+
+/// What goes into a briefing besides the plan itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BriefingOptions {
+    /// Planned off-block time, Unix seconds
+    pub departure_time: i64,
+    /// Fuel on board and reserves; without it the briefing has no fuel section
+    pub fuel: Option<FuelPlan>,
+    /// NOTAMs whose area comes within this distance of the route are included
+    pub notam_corridor_nm: f64,
+}
+
+impl BriefingOptions {
+    pub fn new(departure_time: i64) -> Self {
+        Self {
+            departure_time,
+            fuel: None,
+            notam_corridor_nm: NOTAM_CORRIDOR_NM,
+        }
+    }
+
+    pub fn with_fuel(mut self, fuel: FuelPlan) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+}
+
+/// Which end of the flight an airport is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AirportRole {
+    Departure,
+    Destination,
+    Alternate,
+}
+
+impl AirportRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AirportRole::Departure => "Departure",
+            AirportRole::Destination => "Destination",
+            AirportRole::Alternate => "Alternate",
+        }
+    }
+}
+
+/// Weather at one airport around the time the flight uses it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportWeather {
+    pub role: AirportRole,
+    pub icao: String,
+    /// Departure or arrival time the forecast is picked for
+    pub at: i64,
+    /// Latest TAF valid at `at`
+    pub taf: Option<Taf>,
+    /// Cached ATIS broadcasts
+    pub atis: Vec<Atis>,
+}
+
+/// Fuel figures in gallons
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FuelBriefing {
+    pub trip: f64,
+    pub taxi: f64,
+    pub alternate: f64,
+    pub final_reserve: f64,
+    /// Trip, taxi, alternate and final reserve
+    pub required: f64,
+    pub on_board: f64,
+    /// On board minus required; negative when the plan is short
+    pub extra: f64,
+    /// Endurance at the planned flow, minutes
+    pub endurance_minutes: i32,
+}
+
+impl FuelBriefing {
+    fn new(route: &FlightRoute, plan: &FuelPlan) -> Self {
+        let trip = route.estimated_time as f64 / 60.0 * plan.fuel_flow_gph;
+        let taxi = trip * TAXI_FUEL_FRACTION;
+        let required = trip + taxi + plan.alternate_fuel + plan.final_reserve;
+        let endurance_minutes = if plan.fuel_flow_gph > 0.0 {
+            (plan.departure_fuel / plan.fuel_flow_gph * 60.0).floor() as i32
+        } else {
+            0
+        };
+        Self {
+            trip,
+            taxi,
+            alternate: plan.alternate_fuel,
+            final_reserve: plan.final_reserve,
+            required,
+            on_board: plan.departure_fuel,
+            extra: plan.departure_fuel - required,
+            endurance_minutes,
+        }
+    }
+}
+
+/// Installed navdata cycle and its status at briefing time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavdataBriefing {
+    pub cycle: Option<AiracCycle>,
+    pub status: NavdataStatus,
+}
+
+/// Preflight briefing package
+///
+/// Serializes as a structured document; `to_text` and `to_markdown` render
+/// it for printing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Briefing {
+    pub plan: FlightPlan,
+    pub generated_at: i64,
+    pub departure_time: i64,
+    pub arrival_time: i64,
+    pub total_distance: f64,
+    pub estimated_time: i32,
+    pub navdata: NavdataBriefing,
+    /// Departure, destination and alternate, in that order
+    pub weather: Vec<AirportWeather>,
+    /// SIGMETs and AIRMETs crossed while valid
+    pub sigmets: Vec<RouteSigmet>,
+    /// NOTAMs for the departure, destination and alternate
+    pub airport_notams: Vec<Notam>,
+    /// Other NOTAMs whose area comes within the corridor of the route
+    pub enroute_notams: Vec<Notam>,
+    /// Airspaces crossed, in route order
+    pub airspaces: Vec<AirspaceSpan>,
+    /// Dynamic hazards (TFRs, volcanic ash, conflict zones) crossed while active
+    pub hazards: Vec<RouteHazard>,
+    pub fuel: Option<FuelBriefing>,
+}
+
+/// Assemble the briefing for `route`
+///
+/// NOTAMs are those in force at any time between departure and arrival.
+/// The alternate's forecast is taken at the destination arrival time.
+pub fn briefing(
+    db: &Database,
+    route: &FlightRoute,
+    options: &BriefingOptions,
+    expiry: &ExpiryPolicy,
+    now: DateTime<Utc>,
+) -> Result<Briefing> {
+    let plan = &route.plan;
+    let departure_time = options.departure_time;
+    let arrival_time = departure_time + route.estimated_time as i64 * 60;
+
+    let installed = cycle::current_cycle(db)?;
+    let navdata = NavdataBriefing {
+        status: expiry.status(installed.as_ref(), now.date_naive()),
+        cycle: installed,
+    };
+    let sigmets = sigmet::sigmets_along_route(db, route, departure_time)?;
+    let hazards = hazards::hazards_along_route(db, route, departure_time)?;
+    let airspaces = profile::vertical_profile(db, route, &ProfileOptions::default())?.airspaces;
+
+    let mut airports = vec![
+        (AirportRole::Departure, normalize_code(&plan.departure), departure_time),
+        (AirportRole::Destination, normalize_code(&plan.destination), arrival_time),
+    ];
+    if let Some(alternate) = &plan.alternate {
+        airports.push((AirportRole::Alternate, normalize_code(alternate), arrival_time));
+    }
+
+    let conn = db.get_conn()?;
+    let mut weather = Vec::with_capacity(airports.len());
+    let mut airport_notams: Vec<Notam> = Vec::new();
+    for (role, icao, at) in &airports {
+        weather.push(AirportWeather {
+            role: *role,
+            icao: icao.clone(),
+            at: *at,
+            taf: valid_taf(&conn, icao, *at)?,
+            atis: atis::latest(&conn, icao)?,
+        });
+        for notam in notam::active(&conn, icao, departure_time, arrival_time)? {
+            if !airport_notams.iter().any(|n| n.key() == notam.key()) {
+                airport_notams.push(notam);
+            }
+        }
+    }
+
+    let legs: Vec<_> = route.waypoints.windows(2).collect();
+    let enroute_notams = notam::active_with_area(&conn, departure_time, arrival_time)?
+        .into_iter()
+        .filter(|n| !airports.iter().any(|(_, icao, _)| *icao == n.location))
+        .filter(|n| {
+            let (Some(center), reach) = (n.center, n.radius_nm.unwrap_or(0.0)) else {
+                return false;
+            };
+            let reach = reach + options.notam_corridor_nm;
+            match legs.as_slice() {
+                [] => route
+                    .waypoints
+                    .iter()
+                    .any(|w| w.coordinate.distance_to(&center) <= reach),
+                legs => legs.iter().any(|leg| {
+                    geometry::distance_to_leg(center, leg[0].coordinate, leg[1].coordinate)
+                        <= reach
+                }),
+            }
+        })
+        .collect();
+
+    Ok(Briefing {
+        plan: plan.clone(),
+        generated_at: now.timestamp(),
+        departure_time,
+        arrival_time,
+        total_distance: route.total_distance,
+        estimated_time: route.estimated_time,
+        navdata,
+        weather,
+        sigmets,
+        airport_notams,
+        enroute_notams,
+        airspaces,
+        hazards,
+        fuel: options.fuel.as_ref().map(|fuel| FuelBriefing::new(route, fuel)),
+    })
+}
+
+impl Briefing {
+    /// Plain text for printing or a monospaced display
+    pub fn to_text(&self) -> String {
+        self.render(false)
+    }
+
+    /// Markdown with a heading per section
+    pub fn to_markdown(&self) -> String {
+        self.render(true)
+    }
+
+    fn render(&self, markdown: bool) -> String {
+        let mut out = String::new();
+        let heading = |out: &mut String, level: usize, title: &str| {
+            if markdown {
+                let _ = writeln!(out, "\n{} {}\n", "#".repeat(level), title);
+            } else {
+                let title = if level <= 2 { title.to_uppercase() } else { title.to_string() };
+                let _ = writeln!(out, "\n{}", title);
+                if level <= 2 {
+                    let _ = writeln!(out, "{}", "=".repeat(title.chars().count()));
+                }
+            }
+        };
+
+        let plan = &self.plan;
+        let title = format!("Preflight briefing {} to {}", plan.departure, plan.destination);
+        if markdown {
+            let _ = writeln!(out, "# {}\n", title);
+        } else {
+            let _ = writeln!(out, "{}\n{}\n", title.to_uppercase(), "=".repeat(title.len()));
+        }
+        let _ = writeln!(out, "- Generated: {}", format_time(self.generated_at));
+        let _ = writeln!(out, "- Departure: {}", format_time(self.departure_time));
+        let _ = writeln!(out, "- Arrival: {}", format_time(self.arrival_time));
+        let _ = writeln!(
+            out,
+            "- Route: {} nm, {} min at {} kt, {} ft",
+            self.total_distance.round(),
+            self.estimated_time,
+            plan.cruise_speed,
+            plan.cruise_altitude
+        );
+        if let Some(alternate) = &plan.alternate {
+            let _ = writeln!(out, "- Alternate: {}", alternate);
+        }
+
+        heading(&mut out, 2, "Navdata");
+        let status = match self.navdata.status {
+            NavdataStatus::Current => "current".to_string(),
+            NavdataStatus::ExpiringSoon(days) => format!("expires in {} days", days),
+            NavdataStatus::Expired => "EXPIRED".to_string(),
+            NavdataStatus::Unknown => "no cycle installed".to_string(),
+        };
+        match &self.navdata.cycle {
+            Some(cycle) => {
+                let _ = writeln!(
+                    out,
+                    "- AIRAC {} ({} to {}): {}",
+                    cycle.ident, cycle.effective, cycle.expires, status
+                );
+            }
+            None => {
+                let _ = writeln!(out, "- {}", status);
+            }
+        }
+
+        heading(&mut out, 2, "Weather");
+        for airport in &self.weather {
+            heading(
+                &mut out,
+                3,
+                &format!(
+                    "{} {} at {}",
+                    airport.role.as_str(),
+                    airport.icao,
+                    format_time(airport.at)
+                ),
+            );
+            for atis in &airport.atis {
+                let letter = atis.letter.map(|l| format!(" {}", l)).unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "- ATIS {}{} ({}): {}",
+                    atis.kind.as_str(),
+                    letter,
+                    format_time(atis.issued_at),
+                    atis.text
+                );
+            }
+            match &airport.taf {
+                Some(taf) => {
+                    let _ = writeln!(out, "- TAF: {}", taf.raw);
+                }
+                None => {
+                    let _ = writeln!(out, "- No TAF valid at this time");
+                }
+            }
+        }
+        heading(&mut out, 3, "En route");
+        if self.sigmets.is_empty() {
+            let _ = writeln!(out, "- No SIGMETs or AIRMETs along the route");
+        }
+        for crossing in &self.sigmets {
+            let _ = writeln!(
+                out,
+                "- {} {} {}: {} from {:.0} to {:.0} nm, {} to {}",
+                crossing.sigmet.fir,
+                crossing.sigmet.kind.as_str(),
+                crossing.sigmet.series,
+                crossing.sigmet.phenomenon,
+                crossing.from_nm,
+                crossing.to_nm,
+                format_time(crossing.enter_at),
+                format_time(crossing.exit_at)
+            );
+        }
+
+        heading(&mut out, 2, "NOTAMs");
+        if self.airport_notams.is_empty() && self.enroute_notams.is_empty() {
+            let _ = writeln!(out, "- None in force during the flight");
+        }
+        for notam in self.airport_notams.iter().chain(&self.enroute_notams) {
+            let until = match notam.valid_to {
+                None => "PERM".to_string(),
+                Some(end) if notam.estimated_end => format!("{} EST", format_time(end)),
+                Some(end) => format_time(end),
+            };
+            let _ = writeln!(
+                out,
+                "- {} {} ({} to {}): {}",
+                notam.location,
+                notam.number,
+                format_time(notam.valid_from),
+                until,
+                notam.text.replace('\n', " ")
+            );
+        }
+
+        heading(&mut out, 2, "Airspace");
+        if self.airspaces.is_empty() && self.hazards.is_empty() {
+            let _ = writeln!(out, "- No airspace crossed");
+        }
+        for span in &self.airspaces {
+            let _ = writeln!(
+                out,
+                "- {} from {:.0} to {:.0} nm, {} to {}",
+                span.name,
+                span.from_nm,
+                span.to_nm,
+                format_limit(span.lower_limit, "SFC"),
+                format_limit(span.upper_limit, "UNL")
+            );
+        }
+        for crossing in &self.hazards {
+            let _ = writeln!(
+                out,
+                "- {} {} from {:.0} to {:.0} nm, {} to {}",
+                crossing.hazard.kind.as_str(),
+                crossing.hazard.name,
+                crossing.from_nm,
+                crossing.to_nm,
+                format_time(crossing.enter_at),
+                format_time(crossing.exit_at)
+            );
+        }
+
+        if let Some(fuel) = &self.fuel {
+            heading(&mut out, 2, "Fuel (gal)");
+            for (label, value) in [
+                ("Trip", fuel.trip),
+                ("Taxi", fuel.taxi),
+                ("Alternate", fuel.alternate),
+                ("Final reserve", fuel.final_reserve),
+                ("Required", fuel.required),
+                ("On board", fuel.on_board),
+                ("Extra", fuel.extra),
+            ] {
+                let _ = writeln!(out, "- {}: {:.1}", label, value);
+            }
+            let _ = writeln!(
+                out,
+                "- Endurance: {}:{:02}",
+                fuel.endurance_minutes / 60,
+                fuel.endurance_minutes % 60
+            );
+        }
+        out
+    }
+}
+
+fn format_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%MZ").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn format_limit(limit: Option<i32>, none: &str) -> String {
+    limit.map_or_else(|| none.to_string(), |feet| format!("{} ft", feet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlanner;
+    use crate::Config;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_briefing() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('AP1', 'ZBAA', 'Beijing', 40.08, 116.58, 0),
+                    ('AP2', 'ZBTJ', 'Tianjin', 39.12, 117.35, 0),
+                    ('AP3', 'ZBSJ', 'Shijiazhuang', 38.28, 114.70, 0);
+                 INSERT INTO airspaces (id, name, type, lower_limit, upper_limit, created_at)
+                    VALUES ('AS1', 'Beijing TMA', 'TMA', 0, 18000, 0);
+                 INSERT INTO airspace_boundaries (id, airspace_id, latitude, longitude, sequence)
+                    VALUES ('B1', 'AS1', 39.5, 116.5, 1), ('B2', 'AS1', 39.5, 117.5, 2),
+                           ('B3', 'AS1', 40.5, 117.5, 3), ('B4', 'AS1', 40.5, 116.5, 4);",
+            )
+            .unwrap();
+        let airac = AiracCycle::from_ident("2610").unwrap();
+        cycle::install_cycle(&db, &airac).unwrap();
+
+        let departure = Utc.with_ymd_and_hms(2026, 10, 17, 6, 0, 0).unwrap();
+        let weather = crate::weather::WeatherManager::new(Arc::clone(&db)).unwrap();
+        weather
+            .store_taf_received("TAF ZBTJ 170500Z 1706/1812 9999 SCT030", departure)
+            .unwrap();
+        let clock = Arc::new(crate::clock::MockClock::new(departure));
+        let notams = notam::NotamManager::new(Arc::clone(&db)).unwrap();
+        notams
+            .store_icao(
+                "(A0001/26 NOTAMN A) ZBAA B) 2610010000 C) PERM E) TWY J CLSD)
+(A0002/26 NOTAMN Q) ZBPE/QWPLW/IV/BO/W/000/050/3936N11700E003
+A) ZBPE B) 2610170000 C) 2610180000 E) PJE)
+(A0003/26 NOTAMN Q) ZBPE/QWPLW/IV/BO/W/000/050/3000N11000E003
+A) ZBPE B) 2610170000 C) 2610180000 E) FAR AWAY)",
+            )
+            .unwrap();
+
+        let planner = FlightPlanner::new(Arc::clone(&db)).unwrap().with_clock(clock);
+        let plan = FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZBTJ".to_string(),
+            alternate: Some("ZBSJ".to_string()),
+            cruise_altitude: 8000,
+            cruise_speed: 120,
+            route: vec![],
+            ..Default::default()
+        };
+        let options = BriefingOptions::new(departure.timestamp()).with_fuel(FuelPlan {
+            alternate_fuel: 8.0,
+            ..FuelPlan::new(60.0, 10.0)
+        });
+        let briefing = planner.briefing(&plan, &options).unwrap();
+
+        assert_eq!(briefing.navdata.status, NavdataStatus::Current);
+        let roles: Vec<AirportRole> = briefing.weather.iter().map(|w| w.role).collect();
+        let expected = [AirportRole::Departure, AirportRole::Destination, AirportRole::Alternate];
+        assert_eq!(roles, expected);
+        assert!(briefing.weather[0].taf.is_none());
+        assert!(briefing.weather[1].taf.is_some());
+        assert_eq!(briefing.airport_notams.len(), 1);
+        let enroute: Vec<&str> =
+            briefing.enroute_notams.iter().map(|n| n.text.as_str()).collect();
+        assert_eq!(enroute, ["PJE"]);
+        assert_eq!(briefing.airspaces[0].name, "Beijing TMA");
+        let fuel = briefing.fuel.unwrap();
+        assert!((fuel.required - (fuel.trip * 1.05 + 8.0 + 7.5)).abs() < 1e-9);
+        assert_eq!(fuel.endurance_minutes, 360);
+
+        let text = briefing.to_text();
+        assert!(text.starts_with("PREFLIGHT BRIEFING ZBAA TO ZBTJ"));
+        assert!(text.contains("AIRAC 2610"));
+        assert!(text.contains("- TAF: TAF ZBTJ 170500Z"));
+        let markdown = briefing.to_markdown();
+        assert!(markdown.contains("## NOTAMs"));
+        assert!(markdown.contains("### Alternate ZBSJ"));
+        assert!(markdown.contains("- ZBPE A0002/26"));
+
+        let json = serde_json::to_string(&briefing).unwrap();
+        let back: Briefing = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_markdown(), markdown);
+    }
+}
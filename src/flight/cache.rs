@@ -0,0 +1,202 @@
+use crate::db::Database;
+use crate::error::Result;
+use crate::flight::{FlightPlan, FlightRoute};
+use crate::navdata::cycle;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Routes kept by the planner's cache
+pub const ROUTE_CACHE_CAPACITY: usize = 64;
+
+#[derive(Default)]
+struct CacheState {
+    /// Navdata cycle the entries were computed against
+    cycle: Option<String>,
+    /// route hash -> (last use, route)
+    entries: HashMap<String, (u64, FlightRoute)>,
+    clock: u64,
+}
+
+/// Computed routes keyed by plan hash and navdata cycle
+///
+/// Installing a different navdata cycle empties the cache. Navdata edited
+/// in place without a cycle change is not detected; call `clear` after such
+/// edits.
+pub struct RouteCache {
+    state: Mutex<CacheState>,
+    capacity: usize,
+}
+
+impl RouteCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(CacheState::default()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Return the cached route for `plan`, or compute and remember it
+    ///
+    /// Errors from `compute` are returned as-is and not cached.
+    pub fn get_or_compute(
+        &self,
+        db: &Database,
+        plan: &FlightPlan,
+        compute: impl FnOnce() -> Result<FlightRoute>,
+    ) -> Result<FlightRoute> {
+        let current = cycle::current_cycle(db)?.map(|c| c.ident);
+        let hash = plan.route_hash();
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.cycle != current {
+                state.entries.clear();
+                state.cycle = current.clone();
+            }
+            state.clock += 1;
+            let now = state.clock;
+            if let Some((last_used, route)) = state.entries.get_mut(&hash) {
+                *last_used = now;
+                return Ok(route.clone());
+            }
+        }
+
+        // Compute without holding the lock; concurrent misses for the same
+        // plan just compute twice
+        let route = compute()?;
+
+        let mut state = self.state.lock().unwrap();
+        if state.cycle == current {
+            if state.entries.len() >= self.capacity && !state.entries.contains_key(&hash) {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, (last_used, _))| *last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    state.entries.remove(&oldest);
+                }
+            }
+            let now = state.clock;
+            state.entries.insert(hash, (now, route.clone()));
+        }
+        Ok(route)
+    }
+
+    /// Drop every cached route
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for RouteCache {
+    fn default() -> Self {
+        Self::new(ROUTE_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::planner;
+    use crate::navdata::cycle::AiracCycle;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                    VALUES ('WP1', 'MID', 36.0, 119.0, 'FIX', 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    fn plan(route: &[&str]) -> FlightPlan {
+        FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: None,
+            cruise_altitude: 29000,
+            cruise_speed: 450,
+            route: route.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cached_until_cycle_changes() {
+        let (db, _temp_file) = setup_test_db();
+        let cache = RouteCache::default();
+        let plan = plan(&["WP1"]);
+        let compute = || planner::calculate_route(&db, &plan);
+
+        let first = cache.get_or_compute(&db, &plan, compute).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        // Served from the cache even though the waypoint is gone
+        db.get_conn()
+            .unwrap()
+            .execute("DELETE FROM waypoints WHERE id = 'WP1'", [])
+            .unwrap();
+        let second = cache.get_or_compute(&db, &plan, compute).unwrap();
+        assert_eq!(second.total_distance, first.total_distance);
+
+        // A new cycle invalidates it, so the missing waypoint now fails
+        cycle::install_cycle(&db, &AiracCycle::from_ident("2401").unwrap()).unwrap();
+        assert!(cache.get_or_compute(&db, &plan, compute).is_err());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let (db, _temp_file) = setup_test_db();
+        let cache = RouteCache::new(2);
+        let direct = plan(&[]);
+        let via = plan(&["WP1"]);
+        let mut slow = plan(&[]);
+        slow.cruise_speed = 120;
+
+        for p in [&direct, &via, &direct, &slow] {
+            cache
+                .get_or_compute(&db, p, || planner::calculate_route(&db, p))
+                .unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+
+        // `via` was least recently used, so it was evicted
+        let evicted = cache.get_or_compute(&db, &via, || {
+            Err(crate::error::AeroBaseError::NotFound("recomputed".to_string()))
+        });
+        assert!(evicted.is_err());
+        let kept = cache.get_or_compute(&db, &direct, || {
+            Err(crate::error::AeroBaseError::NotFound("recomputed".to_string()))
+        });
+        assert!(kept.is_ok());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 use crate::error::{AeroBaseError, Result};
 use crate::flight::FlightRoute;
 
+/// Taxi fuel as a share of trip fuel
+pub const TAXI_FUEL_FRACTION: f64 = 0.05;
+
 /// Calculate fuel requirements in gallons
 pub fn calculate_fuel(route: &FlightRoute, fuel_flow_gph: f64) -> Result<f64> {
     if fuel_flow_gph <= 0.0 {
@@ -16,7 +19,7 @@ pub fn calculate_fuel(route: &FlightRoute, fuel_flow_gph: f64) -> Result<f64> {
     let reserve_fuel = (45.0 / 60.0) * fuel_flow_gph;
 
     // Add taxi fuel (typically 5% of trip)
-    let taxi_fuel = trip_fuel * 0.05;
+    let taxi_fuel = trip_fuel * TAXI_FUEL_FRACTION;
 
     let total_fuel = trip_fuel + reserve_fuel + taxi_fuel;
 
@@ -66,6 +69,17 @@ pub fn calculate_ground_speed(
     true_airspeed - head_wind
 }
 
+/// Headwind and crosswind components of a wind relative to a course
+///
+/// Headwind is negative for a tailwind; crosswind is positive from the right.
+pub fn calculate_wind_components(wind_direction: f64, wind_speed: f64, course: f64) -> (f64, f64) {
+    let wind_angle_rad = (wind_direction - course).to_radians();
+    (
+        wind_speed * wind_angle_rad.cos(),
+        wind_speed * wind_angle_rad.sin(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,6 +94,7 @@ mod tests {
             cruise_altitude: 35000,
             cruise_speed: 450,
             route: vec![],
+            ..Default::default()
         };
 
         let route = FlightRoute {
@@ -111,12 +126,24 @@ mod tests {
 
     #[test]
     fn test_calculate_ground_speed() {
-        // Headwind scenario
-        let gs = calculate_ground_speed(180.0, 20.0, 0.0, 200.0);
+        // Wind direction is where the wind blows from
+        // Headwind scenario (wind from the north while tracking north)
+        let gs = calculate_ground_speed(0.0, 20.0, 0.0, 200.0);
         assert!(gs < 200.0); // Should be slower than TAS
 
-        // Tailwind scenario
-        let gs = calculate_ground_speed(0.0, 20.0, 0.0, 200.0);
+        // Tailwind scenario (wind from the south while tracking north)
+        let gs = calculate_ground_speed(180.0, 20.0, 0.0, 200.0);
         assert!(gs > 200.0); // Should be faster than TAS
     }
+
+    #[test]
+    fn test_calculate_wind_components() {
+        let (head, cross) = calculate_wind_components(30.0, 20.0, 360.0);
+        assert!((head - 17.32).abs() < 0.01);
+        assert!((cross - 10.0).abs() < 0.01); // From the right
+
+        let (head, cross) = calculate_wind_components(180.0, 10.0, 360.0);
+        assert!((head + 10.0).abs() < 0.01); // Tailwind
+        assert!(cross.abs() < 0.01);
+    }
 }
@@ -0,0 +1,281 @@
+use crate::error::{AeroBaseError, Result};
+use crate::flight::FlightRoute;
+use serde::{Deserialize, Serialize};
+
+const FEET_PER_NM: f64 = 6076.12;
+
+/// Standard geometric approach path angle
+pub const DEFAULT_DESCENT_ANGLE: f64 = 3.0;
+
+/// How the descent path is flown
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DescentPath {
+    /// Fixed flight path angle in degrees (geometric VNAV path)
+    Angle(f64),
+    /// Fixed vertical speed at a planned ground speed (idle descent approximation)
+    Rate {
+        vertical_speed_fpm: f64,
+        ground_speed_kt: f64,
+    },
+}
+
+impl Default for DescentPath {
+    fn default() -> Self {
+        DescentPath::Angle(DEFAULT_DESCENT_ANGLE)
+    }
+}
+
+impl DescentPath {
+    /// Altitude lost per nautical mile flown
+    pub fn feet_per_nm(&self) -> Result<f64> {
+        match *self {
+            DescentPath::Angle(angle) if angle > 0.0 && angle <= 10.0 => {
+                Ok(angle.to_radians().tan() * FEET_PER_NM)
+            }
+            DescentPath::Rate {
+                vertical_speed_fpm,
+                ground_speed_kt,
+            } if vertical_speed_fpm > 0.0 && ground_speed_kt > 0.0 => {
+                Ok(vertical_speed_fpm * 60.0 / ground_speed_kt)
+            }
+            _ => Err(AeroBaseError::InvalidInput(format!(
+                "Invalid descent path {:?}",
+                self
+            ))),
+        }
+    }
+
+    /// Distance in nautical miles needed to lose `altitude_loss_ft`
+    pub fn distance_to_descend(&self, altitude_loss_ft: f64) -> Result<f64> {
+        Ok(altitude_loss_ft.max(0.0) / self.feet_per_nm()?)
+    }
+}
+
+/// Advisory altitude at a route waypoint after top of descent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvisoryAltitude {
+    pub waypoint_id: String,
+    /// Distance remaining to the destination
+    pub distance_to_go_nm: f64,
+    pub altitude: i32,
+}
+
+/// Vertical profile from cruise down to the destination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DescentProfile {
+    /// Top of descent, measured from the departure along the route
+    pub top_of_descent_nm: f64,
+    /// Top of descent, measured back from the destination
+    pub distance_to_descend_nm: f64,
+    pub advisories: Vec<AdvisoryAltitude>,
+}
+
+/// Compute the descent profile for a route ending at `target_altitude` feet
+///
+/// The path is anchored at the destination and projected backwards, so every
+/// waypoint after top of descent gets the altitude it should be crossed at.
+/// When the route is too short to lose the full altitude, top of descent is
+/// the departure point.
+pub fn compute_profile(
+    route: &FlightRoute,
+    target_altitude: i32,
+    path: DescentPath,
+) -> Result<DescentProfile> {
+    let cruise_altitude = route.plan.cruise_altitude;
+    if target_altitude > cruise_altitude {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "Target altitude {} is above cruise altitude {}",
+            target_altitude, cruise_altitude
+        )));
+    }
+
+    let feet_per_nm = path.feet_per_nm()?;
+    let distance_to_descend = path.distance_to_descend((cruise_altitude - target_altitude) as f64)?;
+    let top_of_descent = (route.total_distance - distance_to_descend).max(0.0);
+
+    let advisories = route
+        .waypoints
+        .iter()
+        .filter(|w| w.cumulative_distance >= top_of_descent)
+        .map(|w| {
+            let distance_to_go = (route.total_distance - w.cumulative_distance).max(0.0);
+            let altitude = target_altitude as f64 + distance_to_go * feet_per_nm;
+            AdvisoryAltitude {
+                waypoint_id: w.id.clone(),
+                distance_to_go_nm: distance_to_go,
+                altitude: (altitude.round() as i32).min(cruise_altitude),
+            }
+        })
+        .collect();
+
+    Ok(DescentProfile {
+        top_of_descent_nm: top_of_descent,
+        distance_to_descend_nm: distance_to_descend,
+        advisories,
+    })
+}
+
+/// Live top-of-descent guidance for the present position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DescentAdvisory {
+    pub distance_to_go_nm: f64,
+    /// Negative once past top of descent
+    pub distance_to_tod_nm: f64,
+    /// Minutes at the present ground speed, `None` once past top of descent
+    pub time_to_tod_min: Option<f64>,
+    pub descend_now: bool,
+    /// Vertical speed that still reaches the target by the destination,
+    /// given once past top of descent
+    pub required_vertical_speed_fpm: Option<f64>,
+}
+
+/// Recompute top-of-descent guidance from the present altitude and the
+/// distance still to fly to the destination
+pub fn advisory(
+    distance_to_go_nm: f64,
+    altitude: i32,
+    target_altitude: i32,
+    ground_speed_kt: f64,
+    path: DescentPath,
+) -> Result<DescentAdvisory> {
+    if ground_speed_kt <= 0.0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Ground speed must be positive".to_string(),
+        ));
+    }
+    let altitude_loss = (altitude - target_altitude).max(0) as f64;
+    let distance_to_tod = distance_to_go_nm - path.distance_to_descend(altitude_loss)?;
+    let descend_now = altitude_loss > 0.0 && distance_to_tod <= 0.0;
+
+    let required_vertical_speed_fpm = descend_now.then(|| {
+        let minutes_to_go = distance_to_go_nm.max(0.0) / ground_speed_kt * 60.0;
+        if minutes_to_go > 0.0 {
+            altitude_loss / minutes_to_go
+        } else {
+            f64::INFINITY
+        }
+    });
+
+    Ok(DescentAdvisory {
+        distance_to_go_nm,
+        distance_to_tod_nm: distance_to_tod,
+        time_to_tod_min: (distance_to_tod > 0.0).then(|| distance_to_tod / ground_speed_kt * 60.0),
+        descend_now,
+        required_vertical_speed_fpm,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{FlightPlan, RouteWaypoint};
+    use crate::models::Coordinate;
+
+    fn route(points: &[(&str, f64)]) -> FlightRoute {
+        let total = points.last().unwrap().1;
+        FlightRoute {
+            plan: FlightPlan {
+                departure: points[0].0.to_string(),
+                destination: points[points.len() - 1].0.to_string(),
+                alternate: None,
+                cruise_altitude: 35000,
+                cruise_speed: 450,
+                route: vec![],
+                ..Default::default()
+            },
+            total_distance: total,
+            estimated_time: 0,
+            waypoints: points
+                .iter()
+                .map(|(id, cumulative)| RouteWaypoint {
+                    id: id.to_string(),
+                    name: id.to_string(),
+                    coordinate: Coordinate::new(0.0, 0.0),
+                    distance_from_previous: 0.0,
+                    cumulative_distance: *cumulative,
+                    estimated_time: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_distance_to_descend() {
+        // Three degrees is roughly 318 ft per nm
+        let three = DescentPath::default();
+        assert!((three.feet_per_nm().unwrap() - 318.4).abs() < 0.5);
+        assert!((three.distance_to_descend(35000.0).unwrap() - 109.9).abs() < 0.5);
+
+        // 1800 fpm at 360 kt is 300 ft per nm
+        let rate = DescentPath::Rate {
+            vertical_speed_fpm: 1800.0,
+            ground_speed_kt: 360.0,
+        };
+        assert_eq!(rate.distance_to_descend(30000.0).unwrap(), 100.0);
+
+        assert!(DescentPath::Angle(0.0).feet_per_nm().is_err());
+        assert!(DescentPath::Rate {
+            vertical_speed_fpm: -100.0,
+            ground_speed_kt: 300.0
+        }
+        .feet_per_nm()
+        .is_err());
+    }
+
+    #[test]
+    fn test_compute_profile() {
+        let route = route(&[("ZBAA", 0.0), ("A", 300.0), ("B", 450.0), ("C", 500.0), ("ZSSS", 550.0)]);
+        let path = DescentPath::Rate {
+            vertical_speed_fpm: 3000.0,
+            ground_speed_kt: 360.0,
+        };
+
+        let profile = compute_profile(&route, 0, path).unwrap();
+        assert_eq!(profile.distance_to_descend_nm, 70.0);
+        assert_eq!(profile.top_of_descent_nm, 480.0);
+
+        let advisories: Vec<(&str, i32)> = profile
+            .advisories
+            .iter()
+            .map(|a| (a.waypoint_id.as_str(), a.altitude))
+            .collect();
+        assert_eq!(advisories, vec![("C", 25000), ("ZSSS", 0)]);
+
+        assert!(compute_profile(&route, 40000, path).is_err());
+    }
+
+    #[test]
+    fn test_advisory() {
+        let path = DescentPath::Rate {
+            vertical_speed_fpm: 1800.0,
+            ground_speed_kt: 360.0,
+        };
+        // 30000 ft to lose at 300 ft/nm needs 100 nm
+        let early = advisory(160.0, 30000, 0, 360.0, path).unwrap();
+        assert_eq!(early.distance_to_tod_nm, 60.0);
+        assert_eq!(early.time_to_tod_min, Some(10.0));
+        assert!(!early.descend_now);
+        assert_eq!(early.required_vertical_speed_fpm, None);
+
+        let late = advisory(80.0, 30000, 0, 360.0, path).unwrap();
+        assert_eq!(late.distance_to_tod_nm, -20.0);
+        assert_eq!(late.time_to_tod_min, None);
+        assert!(late.descend_now);
+        // 80 nm at 360 kt is 13.3 minutes
+        assert_eq!(late.required_vertical_speed_fpm, Some(2250.0));
+
+        // Already at the target altitude
+        assert!(!advisory(10.0, 3000, 3000, 180.0, path).unwrap().descend_now);
+        assert!(advisory(80.0, 30000, 0, 0.0, path).is_err());
+    }
+
+    #[test]
+    fn test_short_route_starts_descent_at_departure() {
+        let route = route(&[("ZBAA", 0.0), ("ZBTJ", 60.0)]);
+        let profile = compute_profile(&route, 0, DescentPath::default()).unwrap();
+        assert_eq!(profile.top_of_descent_nm, 0.0);
+        // The path is still anchored at the destination
+        assert!((profile.advisories[0].altitude - 19105).abs() <= 5);
+        assert_eq!(profile.advisories.len(), 2);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::error::{AeroBaseError, Result};
+use crate::flight::{calculator, FlightRoute, RouteWaypoint};
+use crate::models::waypoint::Waypoint;
+use serde::{Deserialize, Serialize};
+
+/// Single change to a route's waypoint list
+///
+/// Indexes refer to `plan.route`, i.e. they exclude the departure and
+/// destination airports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RouteEdit {
+    /// Insert before `index`; `index == route.len()` appends before the destination
+    Insert { index: usize, waypoint: Waypoint },
+    Remove { index: usize },
+    /// Swap the waypoint at `index` for another, e.g. after dragging it
+    Replace { index: usize, waypoint: Waypoint },
+}
+
+fn route_waypoint(waypoint: &Waypoint) -> RouteWaypoint {
+    RouteWaypoint {
+        id: waypoint.id.clone(),
+        name: waypoint.name.clone(),
+        coordinate: waypoint.coordinate,
+        distance_from_previous: 0.0,
+        cumulative_distance: 0.0,
+        estimated_time: 0,
+    }
+}
+
+impl FlightRoute {
+    /// Apply `edit` and return the updated route
+    ///
+    /// Only the legs touching the edited waypoint get new great circle
+    /// distances; cumulative distances and times after it are re-summed
+    /// from the stored leg lengths. The result matches a full
+    /// `calculate_route` of the edited plan.
+    pub fn apply_edit(&self, edit: &RouteEdit) -> Result<FlightRoute> {
+        let route_len = self.plan.route.len();
+        let (index, limit) = match edit {
+            RouteEdit::Insert { index, .. } => (*index, route_len),
+            RouteEdit::Remove { index } | RouteEdit::Replace { index, .. } => {
+                (*index, route_len.saturating_sub(1))
+            }
+        };
+        if (route_len == 0 && !matches!(edit, RouteEdit::Insert { .. })) || index > limit {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Route edit index {} out of range for {} route waypoint(s)",
+                index, route_len
+            )));
+        }
+        if self.waypoints.len() != route_len + 2 {
+            return Err(AeroBaseError::InvalidInput(
+                "Route waypoints do not match its plan".to_string(),
+            ));
+        }
+
+        let mut route = self.clone();
+        // Position in `waypoints`, which starts with the departure
+        let position = index + 1;
+        let relink = match edit {
+            RouteEdit::Insert { waypoint, .. } => {
+                route.plan.route.insert(index, waypoint.id.clone());
+                route.waypoints.insert(position, route_waypoint(waypoint));
+                vec![position, position + 1]
+            }
+            RouteEdit::Remove { .. } => {
+                route.plan.route.remove(index);
+                route.waypoints.remove(position);
+                vec![position]
+            }
+            RouteEdit::Replace { waypoint, .. } => {
+                route.plan.route[index] = waypoint.id.clone();
+                route.waypoints[position] = route_waypoint(waypoint);
+                vec![position, position + 1]
+            }
+        };
+
+        for i in relink {
+            let previous = route.waypoints[i - 1].coordinate;
+            let leg = &mut route.waypoints[i];
+            leg.distance_from_previous = previous.distance_to(&leg.coordinate);
+        }
+
+        let speed = route.plan.cruise_speed;
+        for i in position..route.waypoints.len() {
+            let cumulative =
+                route.waypoints[i - 1].cumulative_distance + route.waypoints[i].distance_from_previous;
+            let leg = &mut route.waypoints[i];
+            leg.cumulative_distance = cumulative;
+            leg.estimated_time = calculator::calculate_segment_time(cumulative, speed);
+        }
+
+        let destination = &route.waypoints[route.waypoints.len() - 1];
+        route.total_distance = destination.cumulative_distance;
+        route.estimated_time = destination.estimated_time;
+        Ok(route)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::flight::{planner, FlightPlan};
+    use crate::models::waypoint::WaypointType;
+    use crate::models::Coordinate;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('WP1', 'ONE', 38.5, 117.5, 'FIX', 0),
+                    ('WP2', 'TWO', 36.0, 119.0, 'FIX', 0),
+                    ('WP3', 'THREE', 33.5, 120.5, 'FIX', 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    fn plan(route: &[&str]) -> FlightPlan {
+        FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: None,
+            cruise_altitude: 29000,
+            cruise_speed: 450,
+            route: route.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn waypoint(id: &str, latitude: f64, longitude: f64) -> Waypoint {
+        Waypoint {
+            id: id.to_string(),
+            name: id.to_string(),
+            coordinate: Coordinate::new(latitude, longitude),
+            region: None,
+            waypoint_type: WaypointType::Fix,
+            usage: None,
+            created_at: 0,
+        }
+    }
+
+    fn assert_same(edited: &FlightRoute, full: &FlightRoute) {
+        assert_eq!(edited.plan.route, full.plan.route);
+        assert_eq!(edited.total_distance, full.total_distance);
+        assert_eq!(edited.estimated_time, full.estimated_time);
+        assert_eq!(edited.waypoints.len(), full.waypoints.len());
+        for (a, b) in edited.waypoints.iter().zip(&full.waypoints) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.distance_from_previous, b.distance_from_previous);
+            assert_eq!(a.cumulative_distance, b.cumulative_distance);
+            assert_eq!(a.estimated_time, b.estimated_time);
+        }
+    }
+
+    #[test]
+    fn test_edits_match_full_recalculation() {
+        let (db, _temp_file) = setup_test_db();
+        let route = planner::calculate_route(&db, &plan(&["WP1", "WP3"])).unwrap();
+
+        let inserted = route
+            .apply_edit(&RouteEdit::Insert {
+                index: 1,
+                waypoint: waypoint("WP2", 36.0, 119.0),
+            })
+            .unwrap();
+        assert_same(&inserted, &planner::calculate_route(&db, &plan(&["WP1", "WP2", "WP3"])).unwrap());
+
+        let removed = inserted.apply_edit(&RouteEdit::Remove { index: 0 }).unwrap();
+        assert_same(&removed, &planner::calculate_route(&db, &plan(&["WP2", "WP3"])).unwrap());
+
+        let replaced = removed
+            .apply_edit(&RouteEdit::Replace {
+                index: 1,
+                waypoint: waypoint("WP1", 38.5, 117.5),
+            })
+            .unwrap();
+        assert_same(&replaced, &planner::calculate_route(&db, &plan(&["WP2", "WP1"])).unwrap());
+
+        let appended = route
+            .apply_edit(&RouteEdit::Insert {
+                index: 2,
+                waypoint: waypoint("WP2", 36.0, 119.0),
+            })
+            .unwrap();
+        assert_same(&appended, &planner::calculate_route(&db, &plan(&["WP1", "WP3", "WP2"])).unwrap());
+    }
+
+    #[test]
+    fn test_rejects_out_of_range() {
+        let (db, _temp_file) = setup_test_db();
+        let direct = planner::calculate_route(&db, &plan(&[])).unwrap();
+        assert!(direct.apply_edit(&RouteEdit::Remove { index: 0 }).is_err());
+        assert!(direct
+            .apply_edit(&RouteEdit::Insert {
+                index: 1,
+                waypoint: waypoint("WP1", 38.5, 117.5),
+            })
+            .is_err());
+
+        let first = direct
+            .apply_edit(&RouteEdit::Insert {
+                index: 0,
+                waypoint: waypoint("WP1", 38.5, 117.5),
+            })
+            .unwrap();
+        assert_eq!(first.waypoints.len(), 3);
+        assert!(first.apply_edit(&RouteEdit::Replace { index: 1, waypoint: waypoint("X", 0.0, 0.0) }).is_err());
+    }
+}
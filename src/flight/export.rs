@@ -0,0 +1,197 @@
+use crate::flight::{FlightRoute, RouteWaypoint};
+use std::fmt::Write;
+
+/// Decimal places for coordinates (about 0.1 m)
+const COORDINATE_DECIMALS: usize = 6;
+
+/// Escape text for XML element content and attribute values
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn route_name(route: &FlightRoute) -> String {
+    escape(&format!("{}-{}", route.plan.departure, route.plan.destination))
+}
+
+fn route_description(route: &FlightRoute) -> String {
+    format!(
+        "{:.1} nm, {} min, {} ft",
+        route.total_distance, route.estimated_time, route.plan.cruise_altitude
+    )
+}
+
+/// Cumulative distance and time from departure
+fn waypoint_description(waypoint: &RouteWaypoint) -> String {
+    format!(
+        "{:.1} nm, {} min",
+        waypoint.cumulative_distance, waypoint.estimated_time
+    )
+}
+
+/// GPX 1.1 document with the route as a `<rte>` and every waypoint also as
+/// a `<wpt>`, for handheld GPS units that only import one of the two
+///
+/// Descriptions carry the cumulative distance (nm) and time (minutes) from
+/// departure.
+pub fn to_gpx(route: &FlightRoute) -> String {
+    let mut out = String::new();
+    let point = |out: &mut String, tag: &str, waypoint: &RouteWaypoint| {
+        let _ = writeln!(
+            out,
+            "  <{tag} lat=\"{lat:.prec$}\" lon=\"{lon:.prec$}\"><name>{name}</name>\
+             <desc>{desc}</desc></{tag}>",
+            tag = tag,
+            lat = waypoint.coordinate.latitude,
+            lon = waypoint.coordinate.longitude,
+            prec = COORDINATE_DECIMALS,
+            name = escape(&waypoint.name),
+            desc = waypoint_description(waypoint),
+        );
+    };
+
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<gpx version=\"1.1\" creator=\"AeroBase\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+    for waypoint in &route.waypoints {
+        point(&mut out, "wpt", waypoint);
+    }
+    let _ = writeln!(
+        out,
+        "  <rte><name>{}</name><desc>{}</desc>",
+        route_name(route),
+        route_description(route)
+    );
+    for waypoint in &route.waypoints {
+        out.push_str("  ");
+        point(&mut out, "rtept", waypoint);
+    }
+    out.push_str("  </rte>\n</gpx>\n");
+    out
+}
+
+/// KML 2.2 document for Google Earth: the route as a `LineString`
+/// placemark and a point placemark per waypoint
+///
+/// Lines follow the ground (`tessellate`) rather than the cruise altitude.
+pub fn to_kml(route: &FlightRoute) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    let _ = writeln!(out, "  <name>{}</name>", route_name(route));
+
+    let coordinates: Vec<String> = route
+        .waypoints
+        .iter()
+        .map(|w| {
+            format!(
+                "{:.prec$},{:.prec$},0",
+                w.coordinate.longitude,
+                w.coordinate.latitude,
+                prec = COORDINATE_DECIMALS
+            )
+        })
+        .collect();
+    let _ = writeln!(
+        out,
+        "  <Placemark><name>{}</name><description>{}</description>\
+         <LineString><tessellate>1</tessellate><coordinates>{}</coordinates></LineString>\
+         </Placemark>",
+        route_name(route),
+        route_description(route),
+        coordinates.join(" ")
+    );
+    for (waypoint, coordinate) in route.waypoints.iter().zip(&coordinates) {
+        let _ = writeln!(
+            out,
+            "  <Placemark><name>{}</name><description>{}</description>\
+             <Point><coordinates>{}</coordinates></Point></Placemark>",
+            escape(&waypoint.name),
+            waypoint_description(waypoint),
+            coordinate
+        );
+    }
+    out.push_str("</Document>\n</kml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlan;
+    use crate::models::Coordinate;
+
+    fn route() -> FlightRoute {
+        let waypoint = |name: &str, lat: f64, lon: f64, cumulative: f64, time: i32| RouteWaypoint {
+            id: name.to_string(),
+            name: name.to_string(),
+            coordinate: Coordinate::new(lat, lon),
+            distance_from_previous: 0.0,
+            cumulative_distance: cumulative,
+            estimated_time: time,
+        };
+        FlightRoute {
+            plan: FlightPlan {
+                departure: "ZBAA".to_string(),
+                destination: "ZSSS".to_string(),
+                alternate: None,
+                cruise_altitude: 35000,
+                cruise_speed: 450,
+                route: vec![],
+                ..Default::default()
+            },
+            total_distance: 580.0,
+            estimated_time: 85,
+            waypoints: vec![
+                waypoint("ZBAA", 40.08, 116.58, 0.0, 0),
+                waypoint("A&B", 35.5, 118.25, 310.4, 45),
+                waypoint("ZSSS", 31.2, 121.34, 580.0, 85),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_to_gpx() {
+        let gpx = to_gpx(&route());
+        assert!(gpx.starts_with("<?xml"));
+        assert_eq!(gpx.matches("<wpt ").count(), 3);
+        assert_eq!(gpx.matches("<rtept ").count(), 3);
+        assert!(gpx.contains(
+            "<rtept lat=\"35.500000\" lon=\"118.250000\"><name>A&amp;B</name>\
+             <desc>310.4 nm, 45 min</desc></rtept>"
+        ));
+        assert!(gpx.contains("<rte><name>ZBAA-ZSSS</name><desc>580.0 nm, 85 min, 35000 ft</desc>"));
+        assert!(gpx.trim_end().ends_with("</gpx>"));
+    }
+
+    #[test]
+    fn test_to_kml() {
+        let kml = to_kml(&route());
+        assert!(kml.contains(
+            "<coordinates>116.580000,40.080000,0 118.250000,35.500000,0 \
+             121.340000,31.200000,0</coordinates>"
+        ));
+        assert_eq!(kml.matches("<Point>").count(), 3);
+        assert!(kml.contains("<name>A&amp;B</name><description>310.4 nm, 45 min</description>"));
+        assert!(kml.trim_end().ends_with("</kml>"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::error::{AeroBaseError, Result};
+use crate::flight::{FlightPlan, FlightRoute};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Current `schema_version` written into plan and route JSON
+///
+/// Bump this when a field changes meaning or shape, and add a step to
+/// `upgrade` that rewrites the previous version's JSON into the new one.
+/// Plain field additions don't need a bump: unknown fields are ignored on
+/// read, and new fields should be `Option` or `#[serde(default)]`.
+pub const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// Version assumed for JSON written before `schema_version` existed
+const UNVERSIONED: u32 = 0;
+
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    body: &'a T,
+}
+
+/// Rewrite `value` from `version` to `PLAN_FORMAT_VERSION`, one step at a time
+fn upgrade(mut value: Value, mut version: u32) -> Result<Value> {
+    if version > PLAN_FORMAT_VERSION {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "Flight plan schema version {} is newer than supported version {}",
+            version, PLAN_FORMAT_VERSION
+        )));
+    }
+    while version < PLAN_FORMAT_VERSION {
+        match version {
+            // Unversioned JSON has the same fields as version 1
+            UNVERSIONED => {}
+            _ => unreachable!("no upgrade step from schema version {}", version),
+        }
+        version += 1;
+    }
+    if let Value::Object(fields) = &mut value {
+        fields.remove("schema_version");
+    }
+    Ok(value)
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    let version = match value.get("schema_version") {
+        None => UNVERSIONED,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| {
+                AeroBaseError::InvalidInput(format!("Invalid schema_version {}", v))
+            })?,
+    };
+    Ok(serde_json::from_value(upgrade(value, version)?)?)
+}
+
+fn to_value<T: Serialize>(body: &T) -> Result<Value> {
+    Ok(serde_json::to_value(Versioned {
+        schema_version: PLAN_FORMAT_VERSION,
+        body,
+    })?)
+}
+
+/// Serialize a plan as versioned JSON
+pub fn plan_to_json(plan: &FlightPlan) -> Result<String> {
+    Ok(serde_json::to_string(&to_value(plan)?)?)
+}
+
+/// Read a plan written by any supported version, including unversioned JSON
+pub fn plan_from_json(json: &str) -> Result<FlightPlan> {
+    from_value(serde_json::from_str(json)?)
+}
+
+/// Serialize a computed route as versioned JSON
+pub fn route_to_json(route: &FlightRoute) -> Result<String> {
+    Ok(serde_json::to_string(&to_value(route)?)?)
+}
+
+/// Read a route written by any supported version, including unversioned JSON
+pub fn route_from_json(json: &str) -> Result<FlightRoute> {
+    from_value(serde_json::from_str(json)?)
+}
+
+/// Versioned plan encoding for `#[serde(with = "crate::flight::format::versioned_plan")]`
+pub mod versioned_plan {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        plan: &FlightPlan,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        Versioned {
+            schema_version: PLAN_FORMAT_VERSION,
+            body: plan,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<FlightPlan, D::Error> {
+        from_value(Value::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::RouteWaypoint;
+    use crate::models::Coordinate;
+
+    fn sample_plan() -> FlightPlan {
+        FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: Some("ZSPD".to_string()),
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: vec!["WP1".to_string(), "WP2".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_round_trip() {
+        let plan = sample_plan();
+        let json = plan_to_json(&plan).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema_version"], PLAN_FORMAT_VERSION);
+        assert_eq!(value["departure"], "ZBAA");
+
+        let read = plan_from_json(&json).unwrap();
+        assert_eq!(read.route_hash(), plan.route_hash());
+    }
+
+    #[test]
+    fn test_reads_older_and_extended_json() {
+        // Written before schema_version existed
+        let legacy = serde_json::to_string(&sample_plan()).unwrap();
+        assert_eq!(plan_from_json(&legacy).unwrap().route_hash(), sample_plan().route_hash());
+
+        // Written by a newer build that added a field within the same version
+        let extended = r#"{"schema_version":1,"departure":"ZBAA","destination":"ZSSS",
+            "cruise_altitude":9000,"cruise_speed":250,"remarks":"VIP"}"#;
+        let plan = plan_from_json(extended).unwrap();
+        assert_eq!(plan.alternate, None);
+        assert!(plan.route.is_empty());
+
+        let future = r#"{"schema_version":99,"departure":"ZBAA"}"#;
+        assert!(matches!(plan_from_json(future), Err(AeroBaseError::InvalidInput(_))));
+        assert!(plan_from_json(r#"{"schema_version":"one"}"#).is_err());
+    }
+
+    #[test]
+    fn test_route_round_trip() {
+        let route = FlightRoute {
+            plan: sample_plan(),
+            total_distance: 12.5,
+            estimated_time: 2,
+            waypoints: vec![RouteWaypoint {
+                id: "WP1".to_string(),
+                name: "ONE".to_string(),
+                coordinate: Coordinate::new(30.0, 120.0),
+                distance_from_previous: 12.5,
+                cumulative_distance: 12.5,
+                estimated_time: 2,
+            }],
+        };
+        let read = route_from_json(&route_to_json(&route).unwrap()).unwrap();
+        assert_eq!(read.total_distance, route.total_distance);
+        assert_eq!(read.waypoints[0].name, "ONE");
+        assert_eq!(read.plan.route, route.plan.route);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::error::{AeroBaseError, Result};
+use crate::events::{Event, EventBus};
+use crate::flight::{FlightRoute, RouteWaypoint};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Final reserve used by `calculate_fuel`, in minutes at the planned flow
+const FINAL_RESERVE_MINUTES: f64 = 45.0;
+
+/// Fuel figures the flight was planned with, in gallons
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuelPlan {
+    /// Fuel on board at departure
+    pub departure_fuel: f64,
+    /// Planned gallons per hour
+    pub fuel_flow_gph: f64,
+    /// Fuel to fly from the destination to the alternate
+    pub alternate_fuel: f64,
+    /// Fuel that must remain on landing
+    pub final_reserve: f64,
+}
+
+impl FuelPlan {
+    /// Plan with the standard 45-minute final reserve and no alternate
+    pub fn new(departure_fuel: f64, fuel_flow_gph: f64) -> Self {
+        Self {
+            departure_fuel,
+            fuel_flow_gph,
+            alternate_fuel: 0.0,
+            final_reserve: fuel_flow_gph * FINAL_RESERVE_MINUTES / 60.0,
+        }
+    }
+
+    /// Fuel that must remain at the destination to divert and still land
+    /// with the final reserve
+    pub fn minimum_at_destination(&self) -> f64 {
+        self.alternate_fuel + self.final_reserve
+    }
+}
+
+/// Landing fuel problems, in order of severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FuelWarning {
+    /// Predicted landing fuel no longer covers the alternate plus reserve
+    BelowAlternateMinimum,
+    /// Predicted landing fuel is below the final reserve
+    BelowFinalReserve,
+}
+
+/// A fuel-on-board report compared with the plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuelStatus {
+    pub waypoint: String,
+    /// Unix seconds
+    pub recorded_at: i64,
+    pub fuel_on_board: f64,
+    pub planned_fuel_on_board: f64,
+    /// Actual minus planned; negative means more was burned than planned
+    pub deviation: f64,
+    /// Landing fuel if the rest of the flight burns at the observed rate
+    pub predicted_landing_fuel: f64,
+    pub warning: Option<FuelWarning>,
+}
+
+/// Tracks fuel on board against the planned burn during flight
+///
+/// Planned burn to each waypoint comes from its estimated time and the
+/// planned fuel flow. The remaining burn is scaled by the ratio of actual
+/// to planned burn so far, so a higher than planned flow lowers the
+/// predicted landing fuel.
+pub struct FuelTracker {
+    waypoints: Vec<RouteWaypoint>,
+    plan: FuelPlan,
+    reports: Vec<FuelStatus>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl FuelTracker {
+    /// Track fuel for `route` flown with `plan`
+    pub fn new(route: &FlightRoute, plan: FuelPlan) -> Result<Self> {
+        if route.waypoints.is_empty() {
+            return Err(AeroBaseError::InvalidInput("Route has no waypoints".to_string()));
+        }
+        if plan.fuel_flow_gph <= 0.0 || plan.departure_fuel <= 0.0 {
+            return Err(AeroBaseError::InvalidInput(
+                "Departure fuel and fuel flow must be positive".to_string(),
+            ));
+        }
+        Ok(Self {
+            waypoints: route.waypoints.clone(),
+            plan,
+            reports: Vec::new(),
+            events: None,
+        })
+    }
+
+    /// Publish `FuelWarning` on `events` when a report raises a new warning
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn plan(&self) -> &FuelPlan {
+        &self.plan
+    }
+
+    /// Planned fuel on board at each waypoint, in route order
+    pub fn planned_fuel(&self) -> Vec<(String, f64)> {
+        self.waypoints
+            .iter()
+            .map(|w| (w.id.clone(), self.planned_at(w)))
+            .collect()
+    }
+
+    /// Planned landing fuel at the destination
+    pub fn planned_landing_fuel(&self) -> f64 {
+        self.planned_at(&self.waypoints[self.waypoints.len() - 1])
+    }
+
+    /// Record fuel on board passing `waypoint_id` and compare it with the plan
+    pub fn record(
+        &mut self,
+        waypoint_id: &str,
+        fuel_on_board: f64,
+        recorded_at: i64,
+    ) -> Result<FuelStatus> {
+        let waypoint = self
+            .waypoints
+            .iter()
+            .find(|w| w.id.eq_ignore_ascii_case(waypoint_id))
+            .ok_or_else(|| {
+                AeroBaseError::NotFound(format!("Waypoint {} not in route", waypoint_id))
+            })?;
+        let planned = self.planned_at(waypoint);
+        let planned_burn = self.plan.departure_fuel - planned;
+        let actual_burn = self.plan.departure_fuel - fuel_on_board;
+        let burn_ratio = if planned_burn > 0.0 && actual_burn > 0.0 {
+            actual_burn / planned_burn
+        } else {
+            1.0
+        };
+        let remaining_burn = planned - self.planned_landing_fuel();
+        let predicted_landing_fuel = fuel_on_board - remaining_burn * burn_ratio;
+
+        let warning = if predicted_landing_fuel < self.plan.final_reserve {
+            Some(FuelWarning::BelowFinalReserve)
+        } else if predicted_landing_fuel < self.plan.minimum_at_destination() {
+            Some(FuelWarning::BelowAlternateMinimum)
+        } else {
+            None
+        };
+        let status = FuelStatus {
+            waypoint: waypoint.id.clone(),
+            recorded_at,
+            fuel_on_board,
+            planned_fuel_on_board: planned,
+            deviation: fuel_on_board - planned,
+            predicted_landing_fuel,
+            warning,
+        };
+
+        let previous = self.reports.last().and_then(|r| r.warning);
+        if let (Some(events), Some(level)) = (&self.events, warning) {
+            if previous.is_none_or(|previous| level > previous) {
+                events.publish(Event::FuelWarning {
+                    waypoint: status.waypoint.clone(),
+                    predicted_landing_fuel,
+                    warning: level,
+                });
+            }
+        }
+        self.reports.push(status.clone());
+        Ok(status)
+    }
+
+    /// Reports recorded so far, oldest first
+    pub fn reports(&self) -> &[FuelStatus] {
+        &self.reports
+    }
+
+    /// Latest predicted landing fuel, or the planned figure before any report
+    pub fn predicted_landing_fuel(&self) -> f64 {
+        self.reports
+            .last()
+            .map_or(self.planned_landing_fuel(), |r| r.predicted_landing_fuel)
+    }
+
+    fn planned_at(&self, waypoint: &RouteWaypoint) -> f64 {
+        self.plan.departure_fuel - self.plan.fuel_flow_gph * waypoint.estimated_time as f64 / 60.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlan;
+    use crate::models::Coordinate;
+
+    /// Two hours to BBBB, one hour in
+    fn route() -> FlightRoute {
+        let waypoint = |id: &str, minutes: i32| RouteWaypoint {
+            id: id.to_string(),
+            name: id.to_string(),
+            coordinate: Coordinate::new(40.0, 116.0),
+            distance_from_previous: 0.0,
+            cumulative_distance: 0.0,
+            estimated_time: minutes,
+        };
+        FlightRoute {
+            plan: FlightPlan {
+                departure: "AAAA".to_string(),
+                destination: "BBBB".to_string(),
+                alternate: Some("CCCC".to_string()),
+                cruise_altitude: 8000,
+                cruise_speed: 120,
+                route: vec!["MID".to_string()],
+                ..Default::default()
+            },
+            total_distance: 240.0,
+            estimated_time: 120,
+            waypoints: vec![waypoint("AAAA", 0), waypoint("MID", 60), waypoint("BBBB", 120)],
+        }
+    }
+
+    fn plan() -> FuelPlan {
+        FuelPlan {
+            alternate_fuel: 10.0,
+            ..FuelPlan::new(50.0, 10.0)
+        }
+    }
+
+    #[test]
+    fn test_on_plan() {
+        let mut tracker = FuelTracker::new(&route(), plan()).unwrap();
+        assert_eq!(tracker.plan().final_reserve, 7.5);
+        assert_eq!(tracker.planned_landing_fuel(), 30.0);
+        assert_eq!(tracker.planned_fuel()[1], ("MID".to_string(), 40.0));
+
+        let status = tracker.record("mid", 40.0, 3_600).unwrap();
+        assert_eq!(status.deviation, 0.0);
+        assert_eq!(status.predicted_landing_fuel, 30.0);
+        assert_eq!(status.warning, None);
+        assert!(tracker.record("ZZZZ", 40.0, 3_600).is_err());
+    }
+
+    #[test]
+    fn test_high_burn_warnings() {
+        let bus = Arc::new(EventBus::new());
+        let mut receiver = bus.subscribe();
+        let mut tracker = FuelTracker::new(&route(), plan()).unwrap().with_events(bus);
+
+        // Burned 16 instead of 10: the last hour is predicted to burn 16 too
+        let status = tracker.record("MID", 34.0, 3_600).unwrap();
+        assert_eq!(status.deviation, -6.0);
+        assert!((status.predicted_landing_fuel - 18.0).abs() < 1e-9);
+        assert_eq!(status.warning, None);
+
+        // 17.5 minimum at destination (10 alternate + 7.5 reserve)
+        let status = tracker.record("MID", 32.0, 3_700).unwrap();
+        assert_eq!(status.warning, Some(FuelWarning::BelowAlternateMinimum));
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            Event::FuelWarning { warning: FuelWarning::BelowAlternateMinimum, .. }
+        ));
+        // Same warning again: no repeat event
+        tracker.record("MID", 31.5, 3_800).unwrap();
+        assert!(receiver.try_recv().is_err());
+
+        let status = tracker.record("BBBB", 7.0, 7_200).unwrap();
+        assert_eq!(status.predicted_landing_fuel, 7.0);
+        assert_eq!(status.warning, Some(FuelWarning::BelowFinalReserve));
+        assert!(receiver.try_recv().is_ok());
+        assert_eq!(tracker.reports().len(), 4);
+        assert_eq!(tracker.predicted_landing_fuel(), 7.0);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::profile::{crossing_spans, route_samples, time_at};
+use crate::flight::validator::bounds;
+use crate::flight::FlightRoute;
+use crate::models::hazard::Hazard;
+use crate::spatial::query;
+use serde::{Deserialize, Serialize};
+
+/// Distance between route samples checked against hazard polygons
+const ROUTE_SAMPLE_SPACING_NM: f64 = 0.5;
+
+/// Stretch of a route flown inside a dynamic hazard while it is active
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteHazard {
+    pub hazard: Hazard,
+    pub from_nm: f64,
+    pub to_nm: f64,
+    /// Estimated times at `from_nm` and `to_nm`, Unix seconds
+    pub enter_at: i64,
+    pub exit_at: i64,
+}
+
+/// Dynamic hazards the route flies through while they are active
+///
+/// Positions are timed from `departure_time` at the plan's cruise speed,
+/// and compared with each hazard's vertical limits at the cruise altitude.
+pub fn hazards_along_route(
+    db: &Database,
+    route: &FlightRoute,
+    departure_time: i64,
+) -> Result<Vec<RouteHazard>> {
+    if route.waypoints.is_empty() || route.plan.cruise_speed <= 0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Route needs waypoints and a positive cruise speed".to_string(),
+        ));
+    }
+    let time_at = |distance| time_at(route, departure_time, distance);
+
+    let samples = route_samples(route, ROUTE_SAMPLE_SPACING_NM);
+    let (min, max) = bounds(&samples.iter().map(|s| s.1).collect::<Vec<_>>());
+    let candidates = query::find_hazards_in(db, min, max, departure_time, time_at(route.total_distance))?;
+
+    let mut crossings = Vec::new();
+    for hazard in candidates {
+        if !hazard.applies_at(route.plan.cruise_altitude) {
+            continue;
+        }
+        let spans = crossing_spans(samples.iter().copied(), |distance, point| {
+            hazard.is_active_at(time_at(distance)) && hazard.contains(point)
+        });
+        for (from_nm, to_nm) in spans {
+            crossings.push(RouteHazard {
+                hazard: hazard.clone(),
+                from_nm,
+                to_nm,
+                enter_at: time_at(from_nm),
+                exit_at: time_at(to_nm),
+            });
+        }
+    }
+    crossings.sort_by(|a, b| a.from_nm.total_cmp(&b.from_nm));
+    Ok(crossings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{planner, FlightPlan};
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_hazards_along_route() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        // A TFR from 30 to 60 nm east of WEST, active for the first hour
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'WEST', 'West', 0.0, 0.0, 0),
+                    ('A2', 'EAST', 'East', 0.0, 2.0, 0);
+                 INSERT INTO hazards (id, kind, name, valid_from, valid_to, min_latitude,
+                     max_latitude, min_longitude, max_longitude, created_at)
+                    VALUES ('TFR1', 'TFR', 'Stadium', 0, 3600, -0.5, 0.5, 0.5, 1.0, 0);
+                 INSERT INTO hazard_boundaries (id, hazard_id, latitude, longitude, sequence) VALUES
+                    ('B0', 'TFR1', -0.5, 0.5, 0),
+                    ('B1', 'TFR1', 0.5, 0.5, 1),
+                    ('B2', 'TFR1', 0.5, 1.0, 2),
+                    ('B3', 'TFR1', -0.5, 1.0, 3);",
+            )
+            .unwrap();
+
+        let plan = FlightPlan {
+            departure: "WEST".to_string(),
+            destination: "EAST".to_string(),
+            alternate: None,
+            cruise_altitude: 5000,
+            cruise_speed: 120,
+            route: vec![],
+            ..Default::default()
+        };
+        let route = planner::calculate_route(&db, &plan).unwrap();
+
+        // 120 kt: inside from 15 to 30 minutes after departure
+        let crossings = hazards_along_route(&db, &route, 0).unwrap();
+        assert_eq!(crossings.len(), 1);
+        assert_eq!(crossings[0].hazard.id, "TFR1");
+        assert!((crossings[0].from_nm - 30.5).abs() < 0.01 && (crossings[0].to_nm - 60.0).abs() < 0.01);
+        assert_eq!(crossings[0].exit_at, 1800);
+
+        // Lifted before the route reaches it
+        assert!(hazards_along_route(&db, &route, 3000).unwrap().is_empty());
+    }
+}
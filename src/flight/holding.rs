@@ -0,0 +1,189 @@
+use crate::error::{AeroBaseError, Result};
+use crate::models::hold::{Hold, TurnDirection};
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+use serde::{Deserialize, Serialize};
+
+/// Heading change per polyline step while turning
+const TURN_STEP_DEG: f64 = 5.0;
+
+/// Teardrop offset from the outbound course toward the holding side
+const TEARDROP_OFFSET_DEG: f64 = 30.0;
+
+/// Holding pattern entry procedure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HoldEntry {
+    Direct,
+    Parallel,
+    Teardrop,
+}
+
+/// Signed difference `to - from` in degrees, in (-180, 180]
+pub(crate) fn angle_diff(from: f64, to: f64) -> f64 {
+    let d = (to - from).rem_euclid(360.0);
+    if d > 180.0 {
+        d - 360.0
+    } else {
+        d
+    }
+}
+
+/// Entry procedure for an aircraft crossing the fix on `heading`
+///
+/// Uses the standard 70° sector line: for right turns, headings from 70°
+/// left to 110° right of the inbound course are direct, the next 70° are
+/// teardrop and the remaining 110° are parallel. Left-hand holds mirror this.
+pub fn entry(hold: &Hold, heading: f64) -> HoldEntry {
+    let relative = angle_diff(hold.inbound_course, heading) * hold.turn.sign();
+    if (-70.0..=110.0).contains(&relative) {
+        HoldEntry::Direct
+    } else if relative > 110.0 {
+        HoldEntry::Teardrop
+    } else {
+        HoldEntry::Parallel
+    }
+}
+
+/// Rate-one turn radius in nautical miles
+pub fn turn_radius_nm(ground_speed_kt: f64) -> f64 {
+    ground_speed_kt / (60.0 * std::f64::consts::PI)
+}
+
+/// Entry path from the fix back to the fix, for display
+///
+/// The aircraft turns onto the entry heading, flies one outbound leg, then
+/// turns (in the hold direction, or against it for a parallel entry) until
+/// it points at the fix and flies straight back to it.
+pub fn entry_path(hold: &Hold, heading: f64, ground_speed_kt: f64) -> Result<Vec<Coordinate>> {
+    if ground_speed_kt <= 0.0 || hold.leg_minutes <= 0.0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Hold ground speed and leg time must be positive".to_string(),
+        ));
+    }
+
+    let outbound = hold.outbound_course();
+    let kind = entry(hold, heading);
+    let (entry_heading, return_turn) = match kind {
+        HoldEntry::Direct => (outbound, hold.turn),
+        HoldEntry::Teardrop => (
+            (outbound - TEARDROP_OFFSET_DEG * hold.turn.sign()).rem_euclid(360.0),
+            hold.turn,
+        ),
+        HoldEntry::Parallel => (outbound, hold.turn.opposite()),
+    };
+
+    // A direct entry always turns with the hold; the others take the short way
+    let first_turn = match kind {
+        HoldEntry::Direct => hold.turn,
+        _ if angle_diff(heading, entry_heading) >= 0.0 => TurnDirection::Right,
+        _ => TurnDirection::Left,
+    };
+
+    let mut path = PathBuilder {
+        points: vec![hold.fix],
+        heading,
+        radius: turn_radius_nm(ground_speed_kt),
+    };
+    path.turn_to(first_turn, entry_heading);
+    path.straight(ground_speed_kt * hold.leg_minutes / 60.0);
+    path.turn_toward(return_turn, hold.fix);
+    path.points.push(hold.fix);
+
+    Ok(path.points)
+}
+
+struct PathBuilder {
+    points: Vec<Coordinate>,
+    heading: f64,
+    radius: f64,
+}
+
+impl PathBuilder {
+    fn position(&self) -> Coordinate {
+        *self.points.last().expect("path starts at the fix")
+    }
+
+    fn straight(&mut self, distance_nm: f64) {
+        let next = geometry::destination_point(self.position(), distance_nm, self.heading);
+        self.points.push(next);
+    }
+
+    /// One turn step, approximated by its chord
+    fn step(&mut self, turn: TurnDirection) {
+        let chord = 2.0 * self.radius * (TURN_STEP_DEG.to_radians() / 2.0).sin();
+        let chord_heading = self.heading + turn.sign() * TURN_STEP_DEG / 2.0;
+        let next = geometry::destination_point(self.position(), chord, chord_heading);
+        self.points.push(next);
+        self.heading = (self.heading + turn.sign() * TURN_STEP_DEG).rem_euclid(360.0);
+    }
+
+    fn turn_to(&mut self, turn: TurnDirection, heading: f64) {
+        for _ in 0..(360.0 / TURN_STEP_DEG) as usize {
+            if angle_diff(self.heading, heading).abs() < TURN_STEP_DEG {
+                break;
+            }
+            self.step(turn);
+        }
+        self.heading = heading;
+    }
+
+    fn turn_toward(&mut self, turn: TurnDirection, target: Coordinate) {
+        for _ in 0..(360.0 / TURN_STEP_DEG) as usize {
+            let bearing = self.position().bearing_to(&target);
+            if angle_diff(self.heading, bearing).abs() < TURN_STEP_DEG {
+                return;
+            }
+            self.step(turn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hold(turn: TurnDirection) -> Hold {
+        Hold {
+            turn,
+            ..Hold::new("ABC".to_string(), Coordinate::new(0.0, 0.0), 360.0)
+        }
+    }
+
+    #[test]
+    fn test_entry_sectors() {
+        let right = hold(TurnDirection::Right);
+        assert_eq!(entry(&right, 360.0), HoldEntry::Direct);
+        assert_eq!(entry(&right, 90.0), HoldEntry::Direct);
+        assert_eq!(entry(&right, 300.0), HoldEntry::Direct);
+        assert_eq!(entry(&right, 150.0), HoldEntry::Teardrop);
+        assert_eq!(entry(&right, 270.0), HoldEntry::Parallel);
+        assert_eq!(entry(&right, 200.0), HoldEntry::Parallel);
+
+        let left = hold(TurnDirection::Left);
+        assert_eq!(entry(&left, 270.0), HoldEntry::Direct);
+        assert_eq!(entry(&left, 210.0), HoldEntry::Teardrop);
+        assert_eq!(entry(&left, 90.0), HoldEntry::Parallel);
+    }
+
+    #[test]
+    fn test_entry_path_returns_to_fix() {
+        let right = hold(TurnDirection::Right);
+        for heading in [0.0, 150.0, 270.0] {
+            let path = entry_path(&right, heading, 180.0).unwrap();
+            assert_eq!(path.first(), Some(&right.fix));
+            assert_eq!(path.last(), Some(&right.fix));
+
+            // Everything stays within a few nm of the fix
+            assert!(path.iter().all(|p| p.distance_to(&right.fix) < 6.0));
+        }
+
+        // A right-hand hold on 360 lies east of the inbound course; a
+        // parallel entry flies the outbound leg on the west side
+        let teardrop = entry_path(&right, 150.0, 180.0).unwrap();
+        assert!(teardrop.iter().any(|p| p.longitude > 0.02));
+        let parallel = entry_path(&right, 270.0, 180.0).unwrap();
+        assert!(parallel.iter().any(|p| p.longitude < -0.01));
+
+        assert!(entry_path(&right, 0.0, 0.0).is_err());
+    }
+}
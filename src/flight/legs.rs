@@ -0,0 +1,379 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::holding;
+use crate::models::hold::{Hold, TurnDirection};
+use crate::models::procedure::{PathTerminator, ProcedureLeg};
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// Bearing change between points sampled along an RF arc
+const ARC_STEP_DEG: f64 = 5.0;
+
+/// CF legs closer than this to their course line fly straight to the fix
+const INTERCEPT_MIN_NM: f64 = 0.1;
+
+/// Inputs the coded legs leave to the aircraft and airport
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegOptions {
+    /// Degrees east, added to coded magnetic courses
+    pub magnetic_variation: f64,
+    /// Where the first leg starts when it doesn't begin at a fix, e.g. the
+    /// departure runway end for a SID starting with CA or VA
+    pub start: Option<Coordinate>,
+    /// Altitude at `start`, in feet
+    pub start_altitude: i32,
+    /// Feet per nautical mile, for altitude-terminated legs
+    pub climb_gradient: f64,
+    /// Ground speed used for turn radii and hold leg times
+    pub ground_speed_kt: f64,
+}
+
+impl Default for LegOptions {
+    fn default() -> Self {
+        Self {
+            magnetic_variation: 0.0,
+            start: None,
+            start_altitude: 0,
+            climb_gradient: 200.0,
+            ground_speed_kt: 210.0,
+        }
+    }
+}
+
+/// A leg with its fix and arc centre looked up
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedLeg {
+    pub leg: ProcedureLeg,
+    pub fix: Option<Coordinate>,
+    pub center: Option<Coordinate>,
+}
+
+/// Drawable geometry for one leg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegPath {
+    pub sequence: i32,
+    pub path_terminator: PathTerminator,
+    /// Starts where the previous leg ended
+    pub points: Vec<Coordinate>,
+}
+
+pub(crate) const LEG_COLUMNS: &str = "id, procedure_id, sequence, path_terminator, fix_id, course, \
+     turn_direction, altitude, distance_nm, center_fix_id, created_at";
+
+pub(crate) fn leg_from_row(row: &Row) -> rusqlite::Result<ProcedureLeg> {
+    let turn: Option<String> = row.get(6)?;
+    Ok(ProcedureLeg {
+        id: row.get(0)?,
+        procedure_id: row.get(1)?,
+        sequence: row.get(2)?,
+        path_terminator: PathTerminator::from_str(&row.get::<_, String>(3)?),
+        fix_id: row.get(4)?,
+        course: row.get(5)?,
+        turn: match turn.as_deref().map(str::to_uppercase).as_deref() {
+            Some("L" | "LEFT") => Some(TurnDirection::Left),
+            Some("R" | "RIGHT") => Some(TurnDirection::Right),
+            _ => None,
+        },
+        altitude: row.get(7)?,
+        distance_nm: row.get(8)?,
+        center_fix_id: row.get(9)?,
+        created_at: row.get(10)?,
+    })
+}
+
+/// Position of a waypoint or, failing that, a navaid
+fn fix_position(conn: &Connection, id: &str) -> Result<Coordinate> {
+    conn.query_row(
+        "SELECT latitude, longitude FROM waypoints WHERE id = ?1
+         UNION ALL SELECT latitude, longitude FROM navaids WHERE id = ?1
+         LIMIT 1",
+        [id],
+        |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)),
+    )
+    .optional()?
+    .ok_or_else(|| AeroBaseError::NotFound(format!("Procedure fix {} not found", id)))
+}
+
+/// Load a procedure's legs in sequence order with their fixes resolved
+pub fn load_legs(db: &Database, procedure_id: &str) -> Result<Vec<ResolvedLeg>> {
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM procedure_legs WHERE procedure_id = ?1 ORDER BY sequence",
+        LEG_COLUMNS
+    ))?;
+    let legs = stmt
+        .query_map([procedure_id], leg_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    legs.into_iter()
+        .map(|leg| {
+            let fix = leg.fix_id.as_deref().map(|id| fix_position(&conn, id)).transpose()?;
+            let center = leg
+                .center_fix_id
+                .as_deref()
+                .map(|id| fix_position(&conn, id))
+                .transpose()?;
+            Ok(ResolvedLeg { leg, fix, center })
+        })
+        .collect()
+}
+
+/// Geometry of a stored procedure, one path per leg
+pub fn procedure_paths(
+    db: &Database,
+    procedure_id: &str,
+    options: &LegOptions,
+) -> Result<Vec<LegPath>> {
+    let legs = load_legs(db, procedure_id)?;
+    if legs.is_empty() {
+        return Err(AeroBaseError::NotFound(format!(
+            "Procedure {} has no legs",
+            procedure_id
+        )));
+    }
+    leg_paths(&legs, options)
+}
+
+fn missing(leg: &ProcedureLeg, what: &str) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!(
+        "{} leg {} needs a {}",
+        leg.path_terminator.as_str(),
+        leg.sequence,
+        what
+    ))
+}
+
+/// Convert coded legs into polylines
+///
+/// Turns between legs are not modelled (legs join at their end points),
+/// and VA headings are flown as tracks since no wind is known.
+pub fn leg_paths(legs: &[ResolvedLeg], options: &LegOptions) -> Result<Vec<LegPath>> {
+    if options.climb_gradient <= 0.0 || options.ground_speed_kt <= 0.0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Climb gradient and ground speed must be positive".to_string(),
+        ));
+    }
+
+    let mut position = options.start;
+    let mut altitude = options.start_altitude as f64;
+    let mut paths = Vec::with_capacity(legs.len());
+
+    for resolved in legs {
+        let leg = &resolved.leg;
+        let fix = || resolved.fix.ok_or_else(|| missing(leg, "fix"));
+        let course = || {
+            leg.course
+                .map(|c| (c + options.magnetic_variation).rem_euclid(360.0))
+                .ok_or_else(|| missing(leg, "course"))
+        };
+        let from = || position.ok_or_else(|| missing(leg, "preceding position"));
+
+        let points = match leg.path_terminator {
+            PathTerminator::IF => vec![fix()?],
+            PathTerminator::TF | PathTerminator::DF => vec![from()?, fix()?],
+            PathTerminator::CF => course_to_fix(from()?, fix()?, course()?),
+            PathTerminator::RF => {
+                let center = resolved.center.ok_or_else(|| missing(leg, "arc centre"))?;
+                let turn = leg.turn.ok_or_else(|| missing(leg, "turn direction"))?;
+                arc(from()?, fix()?, center, turn)
+            }
+            PathTerminator::CA | PathTerminator::VA | PathTerminator::FA => {
+                let start = if leg.path_terminator == PathTerminator::FA {
+                    fix()?
+                } else {
+                    from()?
+                };
+                let target = leg.altitude.ok_or_else(|| missing(leg, "altitude"))? as f64;
+                let distance = ((target - altitude) / options.climb_gradient).max(0.0);
+                vec![start, geometry::destination_point(start, distance, course()?)]
+            }
+            PathTerminator::HA | PathTerminator::HF | PathTerminator::HM => {
+                let fix = fix()?;
+                let inbound = course()?;
+                let leg_minutes = leg
+                    .distance_nm
+                    .map_or(1.0, |d| d / options.ground_speed_kt * 60.0);
+                let hold = Hold {
+                    fix_id: leg.fix_id.clone().unwrap_or_default(),
+                    fix,
+                    inbound_course: inbound,
+                    turn: leg.turn.unwrap_or(TurnDirection::Right),
+                    leg_minutes,
+                };
+                // Arriving on the inbound course, the entry is one full circuit
+                holding::entry_path(&hold, inbound, options.ground_speed_kt)?
+            }
+            PathTerminator::Other => {
+                return Err(AeroBaseError::InvalidInput(format!(
+                    "Unsupported path terminator on leg {}",
+                    leg.sequence
+                )))
+            }
+        };
+
+        if let Some(target) = leg.altitude {
+            altitude = target as f64;
+        }
+        position = points.last().copied();
+        paths.push(LegPath {
+            sequence: leg.sequence,
+            path_terminator: leg.path_terminator,
+            points,
+        });
+    }
+    Ok(paths)
+}
+
+/// Intercept the inbound `course` to `fix`, then follow it
+fn course_to_fix(from: Coordinate, fix: Coordinate, course: f64) -> Vec<Coordinate> {
+    let reciprocal = (course + 180.0).rem_euclid(360.0);
+    let offset = (fix.bearing_to(&from) - reciprocal).to_radians();
+    let along = fix.distance_to(&from) * offset.cos();
+    if along > INTERCEPT_MIN_NM {
+        vec![from, geometry::destination_point(fix, along, reciprocal), fix]
+    } else {
+        vec![from, fix]
+    }
+}
+
+/// Constant-radius arc around `center` from `from` to `to`
+fn arc(from: Coordinate, to: Coordinate, center: Coordinate, turn: TurnDirection) -> Vec<Coordinate> {
+    let radius = center.distance_to(&to);
+    let start = center.bearing_to(&from);
+    let end = center.bearing_to(&to);
+    let sweep = ((end - start) * turn.sign()).rem_euclid(360.0);
+    let steps = (sweep / ARC_STEP_DEG).ceil().max(1.0) as usize;
+
+    let mut points = vec![from];
+    for i in 1..steps {
+        let bearing = start + turn.sign() * sweep * i as f64 / steps as f64;
+        points.push(geometry::destination_point(center, radius, bearing));
+    }
+    points.push(to);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('A1', 'ZBAA', 'Capital', 40.0, 116.0, 0);
+                 INSERT INTO procedures (id, airport_id, name, type, created_at)
+                    VALUES ('P1', 'A1', 'TEST1A', 'STAR', 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'ONE', 40.0, 115.0, 'FIX', 0),
+                    ('W2', 'TWO', 40.0, 115.5, 'FIX', 0),
+                    ('W3', 'THREE', 39.9, 115.63, 'FIX', 0),
+                    ('C1', 'CTR', 39.9, 115.5, 'FIX', 0);
+                 INSERT INTO navaids (id, name, type, latitude, longitude, created_at)
+                    VALUES ('N1', 'VOR', 'VOR', 39.95, 116.0, 0);
+                 INSERT INTO procedure_legs
+                    (id, procedure_id, sequence, path_terminator, fix_id, course, turn_direction,
+                     altitude, distance_nm, center_fix_id, created_at) VALUES
+                    ('L1', 'P1', 10, 'IF', 'W1', NULL, NULL, NULL, NULL, NULL, 0),
+                    ('L2', 'P1', 20, 'TF', 'W2', NULL, NULL, 8000, NULL, NULL, 0),
+                    ('L3', 'P1', 30, 'RF', 'W3', NULL, 'R', NULL, NULL, 'C1', 0),
+                    ('L4', 'P1', 40, 'CF', 'N1', 90.0, NULL, NULL, NULL, NULL, 0),
+                    ('L5', 'P1', 50, 'HM', 'N1', 90.0, 'L', NULL, 4.0, NULL, 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_procedure_paths() {
+        let (db, _temp_file) = setup_test_db();
+        let paths = procedure_paths(&db, "P1", &LegOptions::default()).unwrap();
+
+        let terminators: Vec<&str> = paths.iter().map(|p| p.path_terminator.as_str()).collect();
+        assert_eq!(terminators, vec!["IF", "TF", "RF", "CF", "HM"]);
+        assert_eq!(paths[0].points.len(), 1);
+        assert_eq!(paths[1].points, vec![Coordinate::new(40.0, 115.0), Coordinate::new(40.0, 115.5)]);
+
+        // The quarter-circle arc from north of the centre round to east of it
+        let center = Coordinate::new(39.9, 115.5);
+        let radius = center.distance_to(&Coordinate::new(40.0, 115.5));
+        assert!(paths[2].points.len() > 10);
+        for point in &paths[2].points[1..paths[2].points.len() - 1] {
+            assert!((center.distance_to(point) - radius).abs() < 0.1);
+            assert!(point.latitude > 39.9 && point.longitude > 115.5);
+        }
+
+        // CF intercepts the 090 course west of the navaid before reaching it
+        let cf = &paths[3].points;
+        assert_eq!(cf.len(), 3);
+        assert!((cf[1].latitude - 39.95).abs() < 0.001 && cf[1].longitude < 116.0);
+        assert_eq!(*cf.last().unwrap(), Coordinate::new(39.95, 116.0));
+
+        // A left-hand hold lies north of an eastbound inbound course
+        let hold = &paths[4].points;
+        assert_eq!(hold.first(), hold.last());
+        assert!(hold.iter().all(|p| p.latitude >= 39.95 - 1e-6));
+        assert!(hold.iter().any(|p| p.longitude < 116.0 - 3.5 / 60.0 / 0.77));
+    }
+
+    #[test]
+    fn test_altitude_legs() {
+        let leg = |sequence, path_terminator, course: Option<f64>, altitude| ResolvedLeg {
+            leg: ProcedureLeg {
+                id: format!("L{}", sequence),
+                procedure_id: "SID".to_string(),
+                sequence,
+                path_terminator,
+                fix_id: None,
+                course,
+                turn: None,
+                altitude,
+                distance_nm: None,
+                center_fix_id: None,
+                created_at: 0,
+            },
+            fix: None,
+            center: None,
+        };
+        let runway = Coordinate::new(0.0, 0.0);
+        let options = LegOptions {
+            magnetic_variation: -10.0,
+            start: Some(runway),
+            start_altitude: 1000,
+            ..Default::default()
+        };
+
+        // Climb 2000 ft at 200 ft/nm on 100° magnetic (090° true): 10 nm east
+        let legs = vec![
+            leg(1, PathTerminator::CA, Some(100.0), Some(3000)),
+            leg(2, PathTerminator::VA, Some(10.0), Some(5000)),
+        ];
+        let paths = leg_paths(&legs, &options).unwrap();
+        let end = paths[0].points[1];
+        assert!((runway.distance_to(&end) - 10.0).abs() < 0.01);
+        assert!(end.latitude.abs() < 1e-6);
+        assert!((end.distance_to(&paths[1].points[1]) - 10.0).abs() < 0.01);
+
+        // Missing data is reported per leg
+        let no_start = LegOptions::default();
+        assert!(leg_paths(&legs, &no_start).is_err());
+        let no_course = vec![leg(1, PathTerminator::CA, None, Some(3000))];
+        assert!(leg_paths(&no_course, &options).is_err());
+        let unsupported = vec![leg(1, PathTerminator::Other, None, None)];
+        assert!(leg_paths(&unsupported, &options).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use crate::db::query::{normalize_code, AIRPORT_ID_BY_CODE};
+use crate::db::Database;
+use crate::error::Result;
+use crate::flight::validator::{self, ValidationReport};
+use crate::flight::FlightPlan;
+use crate::models::airspace::{LevelScheme, RVSM_LOWER_LIMIT};
+use crate::models::Coordinate;
+use crate::spatial::{geometry, query};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Highest level listed by the feet tables, matching the plan validator
+const MAX_LEVEL: i32 = 60000;
+
+/// Quadrantal levels apply below FL245; semicircular ones above
+const QUADRANTAL_CEILING: i32 = 24500;
+
+/// China's metric levels for tracks 000-179, as (metres, feet flown)
+const METRIC_EASTBOUND: &[(i32, i32)] = &[
+    (900, 3000), (1500, 4900), (2100, 6900), (2700, 8900), (3300, 10800),
+    (3900, 12800), (4500, 14800), (5100, 16700), (5700, 18700), (6300, 20700),
+    (6900, 22600), (7500, 24600), (8100, 26600), (8900, 29100), (9500, 31100),
+    (10100, 33100), (10700, 35100), (11300, 37100), (11900, 39100), (12500, 41100),
+    (13700, 44900), (14900, 48900),
+];
+
+/// China's metric levels for tracks 180-359, as (metres, feet flown)
+const METRIC_WESTBOUND: &[(i32, i32)] = &[
+    (600, 2000), (1200, 3900), (1800, 5900), (2400, 7900), (3000, 9800),
+    (3600, 11800), (4200, 13800), (4800, 15700), (5400, 17700), (6000, 19700),
+    (6600, 21700), (7200, 23600), (7800, 25600), (8400, 27600), (9200, 30100),
+    (9800, 32100), (10400, 34100), (11000, 36100), (11600, 38100), (12200, 40100),
+    (13100, 43000), (14300, 46900),
+];
+
+fn eastbound(course: f64) -> bool {
+    course.rem_euclid(360.0) < 180.0
+}
+
+/// ICAO semicircular levels: odd thousands eastbound, even westbound, 2000 ft
+/// apart in RVSM airspace and 4000 ft apart above FL410
+fn icao_levels(course: f64) -> Vec<i32> {
+    let (below_rvsm, rvsm, above) = if eastbound(course) {
+        (1000..=27000, 29000..=41000, 45000..=MAX_LEVEL)
+    } else {
+        (2000..=28000, 30000..=40000, 43000..=MAX_LEVEL)
+    };
+    below_rvsm
+        .step_by(2000)
+        .chain(rvsm.step_by(2000))
+        .chain(above.step_by(4000))
+        .collect()
+}
+
+impl LevelScheme {
+    /// Cruising levels in feet for `course`, lowest first
+    ///
+    /// The tables are defined on magnetic track; courses are used as given.
+    pub fn levels(&self, course: f64) -> Vec<i32> {
+        match self {
+            LevelScheme::IcaoFeet => icao_levels(course),
+            LevelScheme::MetricRvsm => {
+                let table = if eastbound(course) {
+                    METRIC_EASTBOUND
+                } else {
+                    METRIC_WESTBOUND
+                };
+                table.iter().map(|&(_, feet)| feet).collect()
+            }
+            LevelScheme::Quadrantal => {
+                // 000-089 odd, 090-179 odd + 500, 180-269 even, 270-359 even + 500
+                let quadrant = (course.rem_euclid(360.0) / 90.0) as i32;
+                let first = 1000 * (1 + quadrant / 2) + 500 * (quadrant % 2);
+                (first..=QUADRANTAL_CEILING)
+                    .step_by(2000)
+                    .chain(icao_levels(course).into_iter().filter(|&l| l > QUADRANTAL_CEILING))
+                    .collect()
+            }
+        }
+    }
+
+    /// Whether `altitude` is a cruising level for `course`
+    pub fn is_level(&self, altitude: i32, course: f64) -> bool {
+        self.levels(course).contains(&altitude)
+    }
+
+    /// Cruising level closest to `altitude`, the lower one on a tie
+    pub fn nearest_level(&self, altitude: i32, course: f64) -> i32 {
+        self.levels(course)
+            .into_iter()
+            .min_by_key(|&l| ((l - altitude).abs(), l))
+            .unwrap_or(altitude)
+    }
+
+    /// Highest cruising level below the RVSM band, for aircraft without RVSM
+    /// approval
+    pub fn rvsm_capped_level(&self, course: f64) -> i32 {
+        self.levels(course)
+            .into_iter()
+            .filter(|&l| l < RVSM_LOWER_LIMIT)
+            .max()
+            .unwrap_or(RVSM_LOWER_LIMIT)
+    }
+
+    /// "FL350", or "10700 m (FL351)" for metric levels
+    pub fn label(&self, altitude: i32) -> String {
+        let flight_level = format!("FL{:03}", altitude / 100);
+        if *self != LevelScheme::MetricRvsm {
+            return flight_level;
+        }
+        METRIC_EASTBOUND
+            .iter()
+            .chain(METRIC_WESTBOUND)
+            .find(|&&(_, feet)| feet == altitude)
+            .map(|(metres, _)| format!("{} m ({})", metres, flight_level))
+            .unwrap_or(flight_level)
+    }
+}
+
+/// A route leg and the level scheme in force along it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegScheme {
+    pub from: Coordinate,
+    pub to: Coordinate,
+    pub course: f64,
+    pub distance_nm: f64,
+    pub scheme: LevelScheme,
+}
+
+/// Scheme used outside any airspace that sets one: the departure country's
+/// default, or ICAO feet
+pub(crate) fn default_scheme(db: &Database, plan: &FlightPlan) -> Result<LevelScheme> {
+    let country: Option<String> = db
+        .get_conn()?
+        .query_row(
+            &format!("SELECT country FROM airports WHERE id = {}", AIRPORT_ID_BY_CODE),
+            [normalize_code(&plan.departure)],
+            |row| row.get(0),
+        )
+        .unwrap_or(None);
+    Ok(country.map_or(LevelScheme::IcaoFeet, |c| LevelScheme::for_country(&c)))
+}
+
+/// The level scheme for each leg of `plan`
+///
+/// A leg takes the scheme of the airspace (typically a FIR) containing its
+/// midpoint at the cruise altitude.
+pub fn leg_schemes(db: &Database, plan: &FlightPlan) -> Result<Vec<LegScheme>> {
+    let positions = validator::plan_positions(db, plan)?;
+    let fallback = default_scheme(db, plan)?;
+
+    let mut min = positions[0];
+    let mut max = positions[0];
+    for p in &positions {
+        min = Coordinate::new(min.latitude.min(p.latitude), min.longitude.min(p.longitude));
+        max = Coordinate::new(max.latitude.max(p.latitude), max.longitude.max(p.longitude));
+    }
+    let regions: Vec<_> = query::find_airspaces_in(db, min, max)?
+        .into_iter()
+        .filter(|(a, _)| {
+            a.level_scheme.is_some() && a.is_altitude_in_airspace(plan.cruise_altitude)
+        })
+        .collect();
+
+    Ok(positions
+        .windows(2)
+        .map(|leg| {
+            let distance_nm = leg[0].distance_to(&leg[1]);
+            let course = leg[0].bearing_to(&leg[1]).rem_euclid(360.0);
+            let midpoint = geometry::destination_point(leg[0], distance_nm / 2.0, course);
+            let scheme = regions
+                .iter()
+                .find(|(_, boundary)| geometry::point_in_polygon(midpoint, boundary))
+                .and_then(|(a, _)| a.level_scheme)
+                .unwrap_or(fallback);
+            LegScheme {
+                from: leg[0],
+                to: leg[1],
+                course,
+                distance_nm,
+                scheme,
+            }
+        })
+        .collect())
+}
+
+/// Cruising level nearest the plan's cruise altitude
+///
+/// Uses the scheme covering most of the route distance and the overall
+/// departure-to-destination course.
+pub fn suggest_cruise_level(db: &Database, plan: &FlightPlan) -> Result<i32> {
+    let legs = leg_schemes(db, plan)?;
+    let mut coverage: HashMap<LevelScheme, f64> = HashMap::new();
+    for leg in &legs {
+        *coverage.entry(leg.scheme).or_default() += leg.distance_nm;
+    }
+    let scheme = coverage
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(LevelScheme::IcaoFeet, |(scheme, _)| scheme);
+
+    let course = legs[0].from.bearing_to(&legs[legs.len() - 1].to);
+    Ok(scheme.nearest_level(plan.cruise_altitude, course))
+}
+
+/// Warn for each leg whose scheme doesn't list the cruise altitude for its
+/// direction, naming the nearest level
+pub(crate) fn check_cruise_levels(
+    db: &Database,
+    plan: &FlightPlan,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let mut seen = Vec::new();
+    for leg in leg_schemes(db, plan)? {
+        if leg.scheme.is_level(plan.cruise_altitude, leg.course) {
+            continue;
+        }
+        let nearest = leg.scheme.nearest_level(plan.cruise_altitude, leg.course);
+        let message = format!(
+            "{} ft is not a {} cruising level on track {:03.0}; nearest is {}",
+            plan.cruise_altitude,
+            leg.scheme.as_str(),
+            leg.course,
+            leg.scheme.label(nearest)
+        );
+        if !seen.contains(&message) {
+            report.warn("cruise_level", message.clone());
+            seen.push(message);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_level_tables() {
+        let icao = LevelScheme::IcaoFeet;
+        assert!(icao.is_level(35000, 90.0));
+        assert!(!icao.is_level(35000, 270.0));
+        assert!(icao.is_level(43000, 270.0));
+        assert!(!icao.is_level(43000, 90.0));
+        assert_eq!(icao.nearest_level(36000, 90.0), 35000);
+        assert_eq!(icao.rvsm_capped_level(90.0), 27000);
+        assert_eq!(icao.rvsm_capped_level(270.0), 28000);
+
+        let metric = LevelScheme::MetricRvsm;
+        assert!(metric.is_level(29100, 45.0));
+        assert!(!metric.is_level(29000, 45.0));
+        assert_eq!(metric.nearest_level(35000, 150.0), 35100);
+        assert_eq!(metric.nearest_level(35000, 330.0), 34100);
+        assert_eq!(metric.rvsm_capped_level(90.0), 26600);
+        assert_eq!(metric.rvsm_capped_level(270.0), 27600);
+        assert_eq!(metric.label(35100), "10700 m (FL351)");
+
+        let quadrantal = LevelScheme::Quadrantal;
+        assert!(quadrantal.is_level(5000, 45.0));
+        assert!(quadrantal.is_level(5500, 135.0));
+        assert!(quadrantal.is_level(6000, 225.0));
+        assert!(quadrantal.is_level(6500, 315.0));
+        assert!(!quadrantal.is_level(6500, 45.0));
+        assert!(quadrantal.is_level(27000, 45.0) && quadrantal.is_level(26000, 315.0));
+        assert_eq!(quadrantal.label(6500), "FL065");
+    }
+
+    #[test]
+    fn test_route_schemes() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        // Eastbound along the equator; the second leg crosses a metric FIR
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, country, created_at) VALUES
+                    ('A1', 'WEST', 'West', 0.0, 0.0, 'XX', 0),
+                    ('A2', 'EAST', 'East', 0.0, 4.0, 'CN', 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                    VALUES ('W1', 'MID', 0.0, 1.0, 'FIX', 0);
+                 INSERT INTO airspaces (id, name, type, level_scheme, created_at)
+                    VALUES ('F1', 'Metric FIR', 'FIR', 'METRIC', 0);
+                 INSERT INTO airspace_boundaries (id, airspace_id, latitude, longitude, sequence) VALUES
+                    ('B1', 'F1', -1.0, 1.5, 0),
+                    ('B2', 'F1', 1.0, 1.5, 1),
+                    ('B3', 'F1', 1.0, 5.0, 2),
+                    ('B4', 'F1', -1.0, 5.0, 3);",
+            )
+            .unwrap();
+
+        let plan = FlightPlan {
+            departure: "WEST".to_string(),
+            destination: "EAST".to_string(),
+            alternate: None,
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: vec!["W1".to_string()],
+            ..Default::default()
+        };
+        let legs = leg_schemes(&db, &plan).unwrap();
+        let schemes: Vec<LevelScheme> = legs.iter().map(|l| l.scheme).collect();
+        assert_eq!(schemes, vec![LevelScheme::IcaoFeet, LevelScheme::MetricRvsm]);
+
+        // Three quarters of the distance is metric
+        assert_eq!(suggest_cruise_level(&db, &plan).unwrap(), 35100);
+
+        let mut report = ValidationReport::default();
+        check_cruise_levels(&db, &plan, &mut report).unwrap();
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].rule, "cruise_level");
+        assert!(report.warnings[0].message.contains("10700 m (FL351)"));
+    }
+}
@@ -0,0 +1,348 @@
+use crate::db::query::{normalize_code, AIRPORT_ID_BY_CODE};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::validator::ValidationReport;
+use crate::flight::{planner, FlightPlan};
+use crate::models::Coordinate;
+use crate::weather::{self, taf::Conditions};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// Best instrument approach an airport offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ApproachCapability {
+    /// No published instrument approach
+    Visual,
+    NonPrecision,
+    /// A runway end with an ILS
+    Precision,
+}
+
+/// Lowest ceiling (feet) and visibility (metres) accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Minima {
+    pub ceiling_ft: i32,
+    pub visibility_m: u32,
+}
+
+impl Minima {
+    pub fn new(ceiling_ft: i32, visibility_m: u32) -> Self {
+        Self {
+            ceiling_ft,
+            visibility_m,
+        }
+    }
+
+    /// Whether forecast conditions meet these minima; unreported elements
+    /// count as unrestricted
+    pub fn met_by(&self, conditions: &Conditions) -> bool {
+        conditions.ceiling_ft.is_none_or(|c| c >= self.ceiling_ft)
+            && conditions.visibility_m.is_none_or(|v| v >= self.visibility_m)
+    }
+}
+
+/// Minima for each approach capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinimaTable {
+    pub precision: Minima,
+    pub non_precision: Minima,
+    pub visual: Minima,
+}
+
+impl MinimaTable {
+    pub fn for_capability(&self, capability: ApproachCapability) -> Minima {
+        match capability {
+            ApproachCapability::Precision => self.precision,
+            ApproachCapability::NonPrecision => self.non_precision,
+            ApproachCapability::Visual => self.visual,
+        }
+    }
+}
+
+/// Operator weather minima for destination and alternate planning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorMinima {
+    pub destination: MinimaTable,
+    /// Planning minima an airport must forecast to be a legal alternate
+    pub alternate: MinimaTable,
+    /// Forecasts are checked from this long before to this long after each
+    /// estimated arrival
+    pub window_minutes: i64,
+    /// Whether TEMPO and PROB groups count against the minima
+    pub include_temporary: bool,
+}
+
+impl Default for OperatorMinima {
+    fn default() -> Self {
+        Self {
+            destination: MinimaTable {
+                precision: Minima::new(200, 550),
+                non_precision: Minima::new(500, 1500),
+                visual: Minima::new(1500, 5000),
+            },
+            alternate: MinimaTable {
+                precision: Minima::new(600, 3200),
+                non_precision: Minima::new(800, 3200),
+                visual: Minima::new(2000, 8000),
+            },
+            window_minutes: 60,
+            include_temporary: true,
+        }
+    }
+}
+
+/// Airport id, ICAO code and position for a plan code
+fn airport(conn: &Connection, code: &str) -> Result<(String, String, Coordinate)> {
+    conn.query_row(
+        &format!(
+            "SELECT id, icao, latitude, longitude FROM airports WHERE id = {}",
+            AIRPORT_ID_BY_CODE
+        ),
+        [normalize_code(code)],
+        |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                Coordinate::new(row.get(2)?, row.get(3)?),
+            ))
+        },
+    )
+    .map_err(|_| AeroBaseError::NotFound(format!("Airport {} not found", code)))
+}
+
+/// Best approach at an airport: ILS-equipped runway, else any published
+/// approach procedure, else visual
+pub fn approach_capability(conn: &Connection, airport_id: &str) -> Result<ApproachCapability> {
+    let (ils, approach): (bool, bool) = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM runways WHERE airport_id = ?1 AND ils_frequency IS NOT NULL),
+                EXISTS(SELECT 1 FROM procedures WHERE airport_id = ?1 AND type = 'APPROACH')",
+        [airport_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(if ils {
+        ApproachCapability::Precision
+    } else if approach {
+        ApproachCapability::NonPrecision
+    } else {
+        ApproachCapability::Visual
+    })
+}
+
+fn describe(conditions: &Conditions) -> String {
+    format!(
+        "ceiling {}, visibility {}",
+        conditions
+            .ceiling_ft
+            .map_or("none".to_string(), |c| format!("{} ft", c)),
+        conditions
+            .visibility_m
+            .map_or("unreported".to_string(), |v| format!("{} m", v))
+    )
+}
+
+/// Forecast at `station` around `eta` compared with `minima`; `None` when no
+/// stored TAF covers the arrival window
+fn forecast_meets(
+    conn: &Connection,
+    station: &str,
+    eta: i64,
+    minima: &Minima,
+    operator: &OperatorMinima,
+) -> Result<Option<(bool, Conditions)>> {
+    let from = eta - operator.window_minutes * 60;
+    let to = eta + operator.window_minutes * 60;
+    let Some(taf) = weather::valid_taf(conn, station, eta)? else {
+        return Ok(None);
+    };
+    Ok(taf
+        .worst_between(from, to, operator.include_temporary)
+        .map(|conditions| (minima.met_by(&conditions), conditions)))
+}
+
+/// Check TAF forecasts at the destination and alternate against operator
+/// minima
+///
+/// Arrival times come from the planned route and `departure_time` (Unix
+/// seconds); the alternate is reached at cruise speed directly from the
+/// destination. Findings are warnings: `destination_no_taf`,
+/// `destination_below_minima`, `alternate_required` (destination below
+/// minima and no alternate), `alternate_no_taf` and `alternate_illegal`.
+pub fn check_weather_minima(
+    db: &Database,
+    plan: &FlightPlan,
+    departure_time: i64,
+    operator: &OperatorMinima,
+) -> Result<ValidationReport> {
+    let route = planner::calculate_route(db, plan)?;
+    let conn = db.get_conn()?;
+    let mut report = ValidationReport::default();
+
+    let (destination_id, destination, destination_position) = airport(&conn, &plan.destination)?;
+    let eta = departure_time + route.estimated_time as i64 * 60;
+    let capability = approach_capability(&conn, &destination_id)?;
+    let minima = operator.destination.for_capability(capability);
+
+    match forecast_meets(&conn, &destination, eta, &minima, operator)? {
+        None => report.warn(
+            "destination_no_taf",
+            format!("No TAF for {} covers the arrival time", destination),
+        ),
+        Some((true, _)) => {}
+        Some((false, conditions)) => {
+            report.warn(
+                "destination_below_minima",
+                format!(
+                    "{} forecast {} is below {:?} minima of {} ft / {} m",
+                    destination,
+                    describe(&conditions),
+                    capability,
+                    minima.ceiling_ft,
+                    minima.visibility_m
+                ),
+            );
+            if plan.alternate.is_none() {
+                report.warn(
+                    "alternate_required",
+                    format!(
+                        "{} is forecast below minima and the plan has no alternate",
+                        destination
+                    ),
+                );
+            }
+        }
+    }
+
+    if let Some(code) = &plan.alternate {
+        let (alternate_id, alternate, position) = airport(&conn, code)?;
+        let hours = destination_position.distance_to(&position) / plan.cruise_speed as f64;
+        let alternate_eta = eta + (hours * 3600.0).round() as i64;
+        let capability = approach_capability(&conn, &alternate_id)?;
+        let minima = operator.alternate.for_capability(capability);
+
+        match forecast_meets(&conn, &alternate, alternate_eta, &minima, operator)? {
+            None => report.warn(
+                "alternate_no_taf",
+                format!("No TAF for alternate {} covers the arrival time", alternate),
+            ),
+            Some((true, _)) => {}
+            Some((false, conditions)) => report.warn(
+                "alternate_illegal",
+                format!(
+                    "Alternate {} forecast {} is below {:?} alternate minima of {} ft / {} m",
+                    alternate,
+                    describe(&conditions),
+                    capability,
+                    minima.ceiling_ft,
+                    minima.visibility_m
+                ),
+            ),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::WeatherManager;
+    use crate::Config;
+    use chrono::{TimeZone, Utc};
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        // ZSSS has an ILS, ZSPD only an RNP approach, ZSNJ nothing published
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0),
+                    ('A3', 'ZSPD', 'Pudong', 31.14, 121.81, 0),
+                    ('A4', 'ZSNJ', 'Lukou', 31.74, 118.86, 0);
+                 INSERT INTO runways (id, airport_id, ident, ils_ident, ils_frequency, created_at)
+                    VALUES ('R1', 'A2', '36R', 'IHQ', 109.9, 0);
+                 INSERT INTO procedures (id, airport_id, name, type, created_at)
+                    VALUES ('P1', 'A3', 'RNP16', 'APPROACH', 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_approach_capability() {
+        let (db, _temp_file) = setup_test_db();
+        let conn = db.get_conn().unwrap();
+        assert_eq!(approach_capability(&conn, "A2").unwrap(), ApproachCapability::Precision);
+        assert_eq!(approach_capability(&conn, "A3").unwrap(), ApproachCapability::NonPrecision);
+        assert_eq!(approach_capability(&conn, "A4").unwrap(), ApproachCapability::Visual);
+    }
+
+    #[test]
+    fn test_check_weather_minima() {
+        let (db, _temp_file) = setup_test_db();
+        let received = Utc.with_ymd_and_hms(2026, 10, 17, 0, 0, 0).unwrap();
+        let weather = WeatherManager::new(Arc::clone(&db)).unwrap();
+        // Low cloud at Hongqiao: fine for the ILS, not as an alternate
+        weather
+            .store_taf_received("TAF ZSSS 162300Z 1700/1806 2000 BR OVC004", received)
+            .unwrap();
+        weather
+            .store_taf_received("TAF ZSPD 162300Z 1700/1806 2000 BR OVC004", received)
+            .unwrap();
+        weather
+            .store_taf_received("TAF ZSNJ 162300Z 1700/1806 9999 SCT030 TEMPO 1706/1708 BKN015", received)
+            .unwrap();
+
+        let departure = Utc.with_ymd_and_hms(2026, 10, 17, 4, 0, 0).unwrap().timestamp();
+        let operator = OperatorMinima::default();
+        let mut plan = FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: Some("ZSPD".to_string()),
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: vec![],
+            ..Default::default()
+        };
+
+        let report = check_weather_minima(&db, &plan, departure, &operator).unwrap();
+        let rules: Vec<&str> = report.warnings.iter().map(|w| w.rule.as_str()).collect();
+        assert_eq!(rules, vec!["alternate_illegal"]);
+        assert!(report.warnings[0].message.contains("NonPrecision"));
+
+        // Nanjing has no approach; the TEMPO cloud breaks visual alternate minima
+        plan.alternate = Some("ZSNJ".to_string());
+        let report = check_weather_minima(&db, &plan, departure, &operator).unwrap();
+        assert_eq!(report.warnings[0].rule, "alternate_illegal");
+        let ignore_tempo = OperatorMinima {
+            include_temporary: false,
+            ..operator
+        };
+        assert!(check_weather_minima(&db, &plan, departure, &ignore_tempo)
+            .unwrap()
+            .warnings
+            .is_empty());
+
+        // Pudong as destination is below non-precision minima, with no alternate
+        plan.destination = "ZSPD".to_string();
+        plan.alternate = None;
+        let report = check_weather_minima(&db, &plan, departure, &operator).unwrap();
+        let rules: Vec<&str> = report.warnings.iter().map(|w| w.rule.as_str()).collect();
+        assert_eq!(rules, vec!["destination_below_minima", "alternate_required"]);
+
+        // No forecast for the arrival time
+        let late = departure + 3 * 86400;
+        let report = check_weather_minima(&db, &plan, late, &operator).unwrap();
+        assert_eq!(report.warnings[0].rule, "destination_no_taf");
+    }
+}
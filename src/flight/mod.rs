@@ -1,22 +1,138 @@
+pub mod active;
+pub mod airways;
+pub mod analysis;
+pub mod briefing;
+pub mod cache;
 pub mod calculator;
+pub mod descent;
+pub mod edit;
+pub mod export;
+pub mod format;
+pub mod fuel;
+pub mod hazards;
+pub mod holding;
+pub mod legs;
+pub mod levels;
+pub mod minima;
+pub mod navlog;
+pub mod parser;
 pub mod planner;
+pub mod profile;
+pub mod repository;
+pub mod sar;
+pub mod share;
+pub mod survey;
+pub mod template;
 pub mod validator;
+pub mod vfr;
 
+use crate::clock::Clock;
 use crate::db::Database;
 use crate::error::Result;
+use crate::freshness::FreshnessPolicy;
 use crate::models::Coordinate;
+use crate::navdata::cycle::ExpiryPolicy;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 /// 飞行计划
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FlightPlan {
     pub departure: String,      // ICAO 代码
     pub destination: String,    // ICAO 代码
+    #[serde(default)]
     pub alternate: Option<String>, // ICAO 代码
     pub cruise_altitude: i32,   // 英尺
     pub cruise_speed: i32,      // 节
+    #[serde(default)]
     pub route: Vec<String>,     // 航路点 ID
+    // 以下字段未设置时不写入 JSON，分享码与已保存计划保持原样
+    #[serde(default, skip_serializing_if = "PlanProcedures::is_empty")]
+    pub procedures: PlanProcedures,
+    /// 阶梯爬升/下降（按航路点顺序）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub altitude_profile: Vec<AltitudeStep>,
+    /// 计划起飞时间（Unix 秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub departure_time: Option<i64>,
+    /// 执飞航空器注册号（机队中的航空器）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aircraft: Option<String>,
+}
+
+/// 飞行计划选定的离场、进场与进近程序（程序标识）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlanProcedures {
+    #[serde(default)]
+    pub sid: Option<String>,
+    #[serde(default)]
+    pub star: Option<String>,
+    #[serde(default)]
+    pub approach: Option<String>,
+}
+
+impl PlanProcedures {
+    /// 未选择任何程序
+    pub fn is_empty(&self) -> bool {
+        self.sid.is_none() && self.star.is_none() && self.approach.is_none()
+    }
+}
+
+/// 高度剖面中的一步：自该航路点起改用新的巡航高度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AltitudeStep {
+    /// 航路点 ID
+    pub waypoint: String,
+    /// 新巡航高度（英尺）
+    pub altitude: i32,
+}
+
+impl FlightPlan {
+    /// 飞行计划的稳定哈希（SHA-256 十六进制）
+    ///
+    /// 对每个字段做规范编码（带长度前缀），与构建或序列化方式无关，
+    /// 可用作航线缓存与去重的键
+    pub fn route_hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        let mut field = |value: &str| {
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(value.as_bytes());
+        };
+        field(&self.departure);
+        field(&self.destination);
+        field(self.alternate.as_deref().map_or("-", |_| "+"));
+        field(self.alternate.as_deref().unwrap_or(""));
+        field(&self.cruise_altitude.to_string());
+        field(&self.cruise_speed.to_string());
+        field(&self.route.len().to_string());
+        for waypoint in &self.route {
+            field(waypoint);
+        }
+
+        // Plans without any of the later optional fields keep their old hash
+        let departure_time = self.departure_time.map(|t| t.to_string());
+        let optional = [
+            self.procedures.sid.as_deref(),
+            self.procedures.star.as_deref(),
+            self.procedures.approach.as_deref(),
+            departure_time.as_deref(),
+            self.aircraft.as_deref(),
+        ];
+        if optional.iter().any(Option::is_some) || !self.altitude_profile.is_empty() {
+            for value in optional {
+                field(value.map_or("-", |_| "+"));
+                field(value.unwrap_or(""));
+            }
+            field(&self.altitude_profile.len().to_string());
+            for step in &self.altitude_profile {
+                field(&step.waypoint);
+                field(&step.altitude.to_string());
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
 }
 
 /// 带有计算数据的飞行航线
@@ -42,107 +158,515 @@ pub struct RouteWaypoint {
 /// 飞行计划器
 pub struct FlightPlanner {
     db: Arc<Database>,
+    clock: Arc<dyn Clock>,
+    expiry_policy: ExpiryPolicy,
+    freshness_policy: FreshnessPolicy,
+    route_cache: cache::RouteCache,
 }
 
 impl FlightPlanner {
     /// 创建新的飞行计划器
     pub fn new(db: Arc<Database>) -> Result<Self> {
-        Ok(Self { db })
+        Ok(Self {
+            clock: Arc::clone(db.clock()),
+            db,
+            expiry_policy: ExpiryPolicy::default(),
+            freshness_policy: FreshnessPolicy::default(),
+            route_cache: cache::RouteCache::default(),
+        })
     }
 
-    /// 计算飞行计划的航线
+    /// 设置导航数据过期策略
+    pub fn with_expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = policy;
+        self
+    }
+
+    /// 设置气象、危险区等简报数据的过时阈值（验证时超过阈值给出警告）
+    pub fn with_freshness_policy(mut self, policy: FreshnessPolicy) -> Self {
+        self.freshness_policy = policy;
+        self
+    }
+
+    /// 设置验证时判断危险区生效和导航数据过期所用的时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 计算飞行计划的航线（按计划哈希与导航数据周期缓存）
     pub fn calculate_route(&self, plan: &FlightPlan) -> Result<FlightRoute> {
-        planner::calculate_route(&self.db, plan)
+        self.route_cache
+            .get_or_compute(&self.db, plan, || planner::calculate_route(&self.db, plan))
+    }
+
+    /// 并行计算多个飞行计划的航线（结果顺序与输入一致）
+    ///
+    /// 工作线程数不超过连接池大小与 CPU 核数，各线程共享连接池与航线缓存
+    pub fn calculate_many(&self, plans: &[FlightPlan]) -> Vec<Result<FlightRoute>> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let workers = plans
+            .len()
+            .min(self.db.max_connections() as usize)
+            .min(cores)
+            .max(1);
+        let next = AtomicUsize::new(0);
+
+        let mut results: Vec<(usize, Result<FlightRoute>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(plan) = plans.get(i) else { break };
+                            done.push((i, self.calculate_route(plan)));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("route worker panicked"))
+                .collect()
+        });
+
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, route)| route).collect()
+    }
+
+    /// 在航路网络中自动搜索起降机场之间的最短航路（A*），返回航线及每段所飞航路
+    ///
+    /// 避开在出发时刻生效的危险区；约束未指定出发时刻时按本规划器的时钟取当前时间
+    pub fn find_route(
+        &self,
+        departure: &str,
+        destination: &str,
+        constraints: &airways::RouteConstraints,
+    ) -> Result<airways::AirwayRoute> {
+        let mut constraints = constraints.clone();
+        constraints.departure_time.get_or_insert_with(|| self.clock.timestamp());
+        airways::find_route(&self.db, departure, destination, &constraints)
+    }
+
+    /// 清空航线缓存（在不切换周期的情况下修改导航数据后调用）
+    pub fn clear_route_cache(&self) {
+        self.route_cache.clear();
+    }
+
+    /// 将生成的航路点（搜救、航测等）存入航路点表并加入飞行计划
+    pub fn plan_through(
+        &self,
+        builder: FlightPlanBuilder,
+        prefix: &str,
+        points: &[Coordinate],
+    ) -> Result<FlightPlan> {
+        planner::save_user_waypoints(&self.db, prefix, points)?
+            .iter()
+            .fold(builder, |builder, id| builder.add_waypoint(id))
+            .build()
+    }
+
+    /// 将程序的编码航段（ARINC 424 航段终止码）转换为可绘制的折线
+    pub fn procedure_paths(
+        &self,
+        procedure_id: &str,
+        options: &legs::LegOptions,
+    ) -> Result<Vec<legs::LegPath>> {
+        legs::procedure_paths(&self.db, procedure_id, options)
+    }
+
+    /// 按策略（区域或就近）解析重名航路点
+    pub fn resolve_fix(
+        &self,
+        ident: &str,
+        policy: &parser::FixPolicy,
+    ) -> Result<Option<parser::FixMatch>> {
+        parser::resolve_fix(&self.db, ident, policy)
+    }
+
+    /// 批量将机场、航路点或导航台标识解析为坐标（失败项汇总在报告中）
+    pub fn geocode(&self, tokens: &[&str]) -> Result<parser::GeocodeReport> {
+        parser::geocode(&self.db, tokens)
+    }
+
+    /// 解析航路字符串（重名航路点产生警告）
+    pub fn parse_route(
+        &self,
+        departure: &str,
+        route: &str,
+        destination: &str,
+    ) -> Result<parser::ParsedRoute> {
+        parser::parse_route(&self.db, departure, route, destination)
+    }
+
+    /// 查找与未知航路点标识相近的航路点（就近、按编辑距离排序）
+    pub fn suggest_fixes(
+        &self,
+        ident: &str,
+        previous: Coordinate,
+    ) -> Result<Vec<parser::RouteSuggestion>> {
+        parser::suggest_fixes(&self.db, ident, previous)
     }
 
-    /// 验证飞行计划
+    /// 将计划航路写成可被 `parse_route` 原样解析回来的规范航路字符串
+    pub fn format_route(&self, plan: &FlightPlan) -> Result<String> {
+        parser::format_route(&self.db, plan)
+    }
+
+    /// 验证飞行计划（警告写入日志）
     pub fn validate_plan(&self, plan: &FlightPlan) -> Result<bool> {
-        validator::validate_plan(&self.db, plan)
+        let report = self.validate_plan_report(plan)?;
+        for warning in &report.warnings {
+            log::warn!("飞行计划验证警告 [{}]: {}", warning.rule, warning.message);
+        }
+        Ok(true)
+    }
+
+    /// 验证飞行计划并返回警告列表
+    pub fn validate_plan_report(&self, plan: &FlightPlan) -> Result<validator::ValidationReport> {
+        validator::validate_plan_report(
+            &self.db,
+            plan,
+            &self.expiry_policy,
+            &self.freshness_policy,
+            self.clock.now(),
+        )
+    }
+
+    /// 验证低空无人机飞行计划（检查无人机地理区域）
+    pub fn validate_uas_plan_report(&self, plan: &FlightPlan) -> Result<validator::ValidationReport> {
+        validator::validate_uas_plan_report(
+            &self.db,
+            plan,
+            &self.expiry_policy,
+            &self.freshness_policy,
+            self.clock.now(),
+        )
+    }
+
+    /// 检查非 RVSM 航空器的巡航高度是否进入 RVSM 空域（错误信息中给出建议高度）
+    pub fn validate_rvsm(&self, plan: &FlightPlan, rvsm_approved: bool) -> Result<()> {
+        validator::validate_rvsm(&self.db, plan, rvsm_approved)
+    }
+
+    /// 按航路经过区域的高度层方案（英尺、米制等）给出最接近的巡航高度层
+    pub fn suggest_cruise_level(&self, plan: &FlightPlan) -> Result<i32> {
+        levels::suggest_cruise_level(&self.db, plan)
+    }
+
+    /// 按运营人最低标准检查目的地与备降场的 TAF 预报（备降场不合格时给出警告）
+    pub fn check_weather_minima(
+        &self,
+        plan: &FlightPlan,
+        departure_time: i64,
+        minima: &minima::OperatorMinima,
+    ) -> Result<validator::ValidationReport> {
+        minima::check_weather_minima(&self.db, plan, departure_time, minima)
+    }
+
+    /// 航线在飞越时段内穿过的动态危险区（临时飞行限制、火山灰、冲突地区）
+    pub fn hazards_along_route(
+        &self,
+        route: &FlightRoute,
+        departure_time: i64,
+    ) -> Result<Vec<hazards::RouteHazard>> {
+        hazards::hazards_along_route(&self.db, route, departure_time)
+    }
+
+    /// 生成飞行前简报（起降和备降场气象、航路 NOTAM、穿越空域、燃油计划和导航数据周期状态）
+    pub fn briefing(
+        &self,
+        plan: &FlightPlan,
+        options: &briefing::BriefingOptions,
+    ) -> Result<briefing::Briefing> {
+        let route = self.calculate_route(plan)?;
+        briefing::briefing(&self.db, &route, options, &self.expiry_policy, self.clock.now())
+    }
+
+    /// 生成可打印的领航记录（各航段航向、距离、ETE/ETO、燃油和频率）
+    pub fn nav_log(
+        &self,
+        plan: &FlightPlan,
+        options: &navlog::NavLogOptions,
+    ) -> Result<navlog::NavLog> {
+        navlog::nav_log(&self.db, &self.calculate_route(plan)?, options)
     }
 
     /// 计算燃油需求
     pub fn calculate_fuel(&self, route: &FlightRoute, fuel_flow: f64) -> Result<f64> {
         calculator::calculate_fuel(route, fuel_flow)
     }
+
+    /// 计算下降剖面（顶点与各航路点建议高度）
+    pub fn descent_profile(
+        &self,
+        route: &FlightRoute,
+        target_altitude: i32,
+        path: descent::DescentPath,
+    ) -> Result<descent::DescentProfile> {
+        descent::compute_profile(route, target_altitude, path)
+    }
+
+    /// 计算航线垂直剖面（计划高度、地形、障碍物与空域）
+    pub fn vertical_profile(
+        &self,
+        route: &FlightRoute,
+        options: &profile::ProfileOptions,
+    ) -> Result<profile::VerticalProfile> {
+        profile::vertical_profile(&self.db, route, options)
+    }
+
+    /// 对比实际航迹与计划航线，生成飞行后报告
+    pub fn analyze_track(
+        &self,
+        route: &FlightRoute,
+        track: &[analysis::TrackPoint],
+        fuel_flow: Option<f64>,
+    ) -> Result<analysis::PostFlightReport> {
+        analysis::analyze(route, track, fuel_flow)
+    }
+}
+
+/// 草稿飞行计划（允许缺少字段，用于保存未完成的计划）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DraftFlightPlan {
+    pub departure: Option<String>,
+    pub destination: Option<String>,
+    pub alternate: Option<String>,
+    pub cruise_altitude: Option<i32>,
+    pub cruise_speed: Option<i32>,
+    #[serde(default)]
+    pub route: Vec<String>,
+    #[serde(default)]
+    pub procedures: PlanProcedures,
+    #[serde(default)]
+    pub altitude_profile: Vec<AltitudeStep>,
+    #[serde(default)]
+    pub departure_time: Option<i64>,
+    #[serde(default)]
+    pub aircraft: Option<String>,
+}
+
+impl DraftFlightPlan {
+    /// 尚未填写的必填字段
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if self.departure.is_none() {
+            missing.push("departure");
+        }
+        if self.destination.is_none() {
+            missing.push("destination");
+        }
+        if self.cruise_altitude.is_none() {
+            missing.push("cruise_altitude");
+        }
+        if self.cruise_speed.is_none() {
+            missing.push("cruise_speed");
+        }
+        missing
+    }
+
+    /// 转换为完整的飞行计划（缺少必填字段时返回错误）
+    pub fn complete(self) -> Result<FlightPlan> {
+        let departure = self.departure.ok_or_else(|| {
+            crate::error::AeroBaseError::InvalidInput("Departure required".to_string())
+        })?;
+
+        let destination = self.destination.ok_or_else(|| {
+            crate::error::AeroBaseError::InvalidInput("Destination required".to_string())
+        })?;
+
+        let cruise_altitude = self.cruise_altitude.ok_or_else(|| {
+            crate::error::AeroBaseError::InvalidInput("Cruise altitude required".to_string())
+        })?;
+
+        let cruise_speed = self.cruise_speed.ok_or_else(|| {
+            crate::error::AeroBaseError::InvalidInput("Cruise speed required".to_string())
+        })?;
+
+        Ok(FlightPlan {
+            departure,
+            destination,
+            alternate: self.alternate,
+            cruise_altitude,
+            cruise_speed,
+            route: self.route,
+            procedures: self.procedures,
+            altitude_profile: self.altitude_profile,
+            departure_time: self.departure_time,
+            aircraft: self.aircraft,
+        })
+    }
 }
 
 /// Flight plan builder
 pub struct FlightPlanBuilder {
-    departure: Option<String>,
-    destination: Option<String>,
-    alternate: Option<String>,
-    cruise_altitude: Option<i32>,
-    cruise_speed: Option<i32>,
-    route: Vec<String>,
+    draft: DraftFlightPlan,
+    /// Database and expiry policy `build` validates against, if enabled
+    validation: Option<(Arc<Database>, ExpiryPolicy)>,
+    /// Time source for validation in `build`; the database's clock if unset
+    clock: Option<Arc<dyn Clock>>,
+    /// Briefing data age limits `build` warns against
+    freshness_policy: FreshnessPolicy,
 }
 
 impl FlightPlanBuilder {
     pub fn new() -> Self {
+        Self::from_draft(DraftFlightPlan::default())
+    }
+
+    /// Resume editing a saved draft
+    pub fn from_draft(draft: DraftFlightPlan) -> Self {
         Self {
-            departure: None,
-            destination: None,
-            alternate: None,
-            cruise_altitude: None,
-            cruise_speed: None,
-            route: Vec::new(),
+            draft,
+            validation: None,
+            clock: None,
+            freshness_policy: FreshnessPolicy::default(),
         }
     }
 
+    /// Clock `build` validates at (hazard activity, navdata expiry,
+    /// freshness) instead of the validating database's clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Briefing data age limits `build` validates against, as in
+    /// `FlightPlanner::with_freshness_policy`
+    pub fn with_freshness_policy(mut self, policy: FreshnessPolicy) -> Self {
+        self.freshness_policy = policy;
+        self
+    }
+
     pub fn departure(mut self, icao: &str) -> Self {
-        self.departure = Some(icao.to_string());
+        self.draft.departure = Some(icao.to_string());
         self
     }
 
     pub fn destination(mut self, icao: &str) -> Self {
-        self.destination = Some(icao.to_string());
+        self.draft.destination = Some(icao.to_string());
         self
     }
 
     pub fn alternate(mut self, icao: &str) -> Self {
-        self.alternate = Some(icao.to_string());
+        self.draft.alternate = Some(icao.to_string());
         self
     }
 
     pub fn cruise_altitude(mut self, altitude: i32) -> Self {
-        self.cruise_altitude = Some(altitude);
+        self.draft.cruise_altitude = Some(altitude);
         self
     }
 
     pub fn cruise_speed(mut self, speed: i32) -> Self {
-        self.cruise_speed = Some(speed);
+        self.draft.cruise_speed = Some(speed);
         self
     }
 
     pub fn add_waypoint(mut self, waypoint_id: &str) -> Self {
-        self.route.push(waypoint_id.to_string());
+        self.draft.route.push(waypoint_id.to_string());
         self
     }
 
-    pub fn build(self) -> Result<FlightPlan> {
-        let departure = self.departure.ok_or_else(|| {
-            crate::error::AeroBaseError::InvalidInput("Departure required".to_string())
-        })?;
+    pub fn sid(mut self, procedure: &str) -> Self {
+        self.draft.procedures.sid = Some(procedure.to_string());
+        self
+    }
 
-        let destination = self.destination.ok_or_else(|| {
-            crate::error::AeroBaseError::InvalidInput("Destination required".to_string())
-        })?;
+    pub fn star(mut self, procedure: &str) -> Self {
+        self.draft.procedures.star = Some(procedure.to_string());
+        self
+    }
 
-        let cruise_altitude = self.cruise_altitude.ok_or_else(|| {
-            crate::error::AeroBaseError::InvalidInput("Cruise altitude required".to_string())
-        })?;
+    pub fn approach(mut self, procedure: &str) -> Self {
+        self.draft.procedures.approach = Some(procedure.to_string());
+        self
+    }
 
-        let cruise_speed = self.cruise_speed.ok_or_else(|| {
-            crate::error::AeroBaseError::InvalidInput("Cruise speed required".to_string())
-        })?;
+    /// Replace all selected procedures at once
+    pub fn procedures(mut self, procedures: PlanProcedures) -> Self {
+        self.draft.procedures = procedures;
+        self
+    }
 
-        Ok(FlightPlan {
-            departure,
-            destination,
-            alternate: self.alternate,
-            cruise_altitude,
-            cruise_speed,
-            route: self.route,
-        })
+    /// Change to `altitude` feet from `waypoint_id` onwards
+    pub fn altitude_step(mut self, waypoint_id: &str, altitude: i32) -> Self {
+        self.draft.altitude_profile.push(AltitudeStep {
+            waypoint: waypoint_id.to_string(),
+            altitude,
+        });
+        self
+    }
+
+    /// Replace the whole altitude profile
+    pub fn altitude_profile(mut self, steps: Vec<AltitudeStep>) -> Self {
+        self.draft.altitude_profile = steps;
+        self
+    }
+
+    /// Planned departure time (Unix seconds)
+    pub fn departure_time(mut self, timestamp: i64) -> Self {
+        self.draft.departure_time = Some(timestamp);
+        self
+    }
+
+    /// Fly the plan with this fleet aircraft, taking its cruise performance
+    pub fn aircraft(mut self, aircraft: &crate::fleet::Aircraft) -> Self {
+        self.draft.aircraft = Some(aircraft.registration.clone());
+        self.draft.cruise_speed = Some(aircraft.performance.cruise_speed);
+        self.draft.cruise_altitude = Some(aircraft.performance.cruise_altitude);
+        self
+    }
+
+    /// Run the plan validator against `db` in `build`
+    ///
+    /// Validation errors fail the build; warnings are logged, as in
+    /// `FlightPlanner::validate_plan`.
+    pub fn validate_on_build(self, db: Arc<Database>) -> Self {
+        self.validate_on_build_with(db, ExpiryPolicy::default())
+    }
+
+    /// Like `validate_on_build`, with an explicit navdata expiry policy
+    pub fn validate_on_build_with(mut self, db: Arc<Database>, expiry: ExpiryPolicy) -> Self {
+        self.validation = Some((db, expiry));
+        self
+    }
+
+    /// Return whatever has been set so far, without requiring any field
+    pub fn build_partial(self) -> DraftFlightPlan {
+        self.draft
+    }
+
+    pub fn build(self) -> Result<FlightPlan> {
+        let (plan, report) = self.build_report()?;
+        for warning in &report.warnings {
+            log::warn!("飞行计划验证警告 [{}]: {}", warning.rule, warning.message);
+        }
+        Ok(plan)
+    }
+
+    /// Like `build`, returning the validation warnings instead of logging them
+    ///
+    /// The report is empty unless validation was enabled with `validate_on_build`.
+    pub fn build_report(self) -> Result<(FlightPlan, validator::ValidationReport)> {
+        let plan = self.draft.complete()?;
+
+        let report = match &self.validation {
+            Some((db, expiry)) => validator::validate_plan_report(
+                db,
+                &plan,
+                expiry,
+                &self.freshness_policy,
+                self.clock.as_ref().unwrap_or(db.clock()).now(),
+            )?,
+            None => validator::ValidationReport::default(),
+        };
+        Ok((plan, report))
     }
 }
 
@@ -171,6 +695,322 @@ mod tests {
         assert_eq!(plan.destination, "ZSSS");
     }
 
+    #[test]
+    fn test_build_partial() {
+        let draft = FlightPlanBuilder::new()
+            .departure("ZBAA")
+            .add_waypoint("WP1")
+            .build_partial();
+        assert_eq!(draft.missing_fields(), vec!["destination", "cruise_altitude", "cruise_speed"]);
+
+        // Drafts survive storage and resume where they left off
+        let stored: DraftFlightPlan =
+            serde_json::from_str(&serde_json::to_string(&draft).unwrap()).unwrap();
+        assert_eq!(stored, draft);
+        assert!(FlightPlanBuilder::from_draft(stored.clone()).build().is_err());
+
+        let plan = FlightPlanBuilder::from_draft(stored)
+            .destination("ZSSS")
+            .cruise_altitude(29000)
+            .cruise_speed(450)
+            .build()
+            .unwrap();
+        assert_eq!(plan.route, vec!["WP1"]);
+    }
+
+    #[test]
+    fn test_validate_on_build() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = crate::Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0);",
+            )
+            .unwrap();
+        let builder = || {
+            FlightPlanBuilder::new()
+                .departure("ZBAA")
+                .cruise_altitude(29000)
+                .cruise_speed(450)
+        };
+
+        assert!(builder()
+            .destination("ZSSS")
+            .validate_on_build(Arc::clone(&db))
+            .build()
+            .is_ok());
+        // Builds unchecked, but fails once validation is enabled
+        assert!(builder().destination("XXXX").build().is_ok());
+        assert!(builder()
+            .destination("XXXX")
+            .validate_on_build(Arc::clone(&db))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_on_build_uses_clock() {
+        use crate::navdata::cycle::{self, ExpiredAction};
+
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = crate::Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0);",
+            )
+            .unwrap();
+        cycle::install_cycle(&db, &cycle::AiracCycle::from_ident("2401").unwrap()).unwrap();
+        let expiry = ExpiryPolicy {
+            expiring_soon_days: 7,
+            grace_days: 0,
+            on_expired: ExpiredAction::Error,
+        };
+        let build_at = |timestamp: i64| {
+            FlightPlanBuilder::new()
+                .departure("ZBAA")
+                .destination("ZSSS")
+                .cruise_altitude(29000)
+                .cruise_speed(450)
+                .validate_on_build_with(Arc::clone(&db), expiry.clone())
+                .with_clock(Arc::new(crate::clock::MockClock::at_timestamp(timestamp)))
+                .build()
+        };
+
+        // 2024-01-30 is inside cycle 2401; 2024-03-01 is after it expired
+        assert!(build_at(1_706_572_800).is_ok());
+        assert!(matches!(
+            build_at(1_709_251_200),
+            Err(crate::error::AeroBaseError::FlightPlanning(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_on_build_uses_freshness_policy() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = crate::Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0);
+                 INSERT INTO tafs (id, station, issued_at, valid_from, valid_to, raw, created_at)
+                    VALUES ('T1', 'ZBAA', 0, 0, 86400, 'TAF ZBAA', 0);",
+            )
+            .unwrap();
+        // The TAF arrived 7 h before the build
+        let stale_warnings = |policy: FreshnessPolicy| {
+            let (_, report) = FlightPlanBuilder::new()
+                .departure("ZBAA")
+                .destination("ZSSS")
+                .cruise_altitude(29000)
+                .cruise_speed(450)
+                .validate_on_build(Arc::clone(&db))
+                .with_clock(Arc::new(crate::clock::MockClock::at_timestamp(7 * 3600)))
+                .with_freshness_policy(policy)
+                .build_report()
+                .unwrap();
+            report
+                .warnings
+                .iter()
+                .filter(|w| w.rule == "stale_briefing")
+                .count()
+        };
+
+        assert_eq!(stale_warnings(FreshnessPolicy::default()), 1);
+        assert_eq!(
+            stale_warnings(FreshnessPolicy {
+                weather: chrono::TimeDelta::hours(12),
+                ..Default::default()
+            }),
+            0
+        );
+    }
+
+    #[test]
+    fn test_builder_plan_details() {
+        let aircraft = crate::fleet::Aircraft {
+            registration: "B-1234".to_string(),
+            aircraft_type: "C172".to_string(),
+            performance: crate::fleet::AircraftPerformance {
+                cruise_speed: 120,
+                cruise_altitude: 8500,
+                fuel_flow_gph: 9.0,
+            },
+            equipment: "SDFGRY/S".to_string(),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let base = || {
+            FlightPlanBuilder::new()
+                .departure("ZBAA")
+                .destination("ZSSS")
+                .aircraft(&aircraft)
+                .add_waypoint("WP1")
+                .add_waypoint("WP2")
+        };
+
+        let plan = base()
+            .sid("ELK1A")
+            .star("DOGAR1")
+            .approach("I36L")
+            .altitude_step("WP2", 10500)
+            .departure_time(1_700_000_000)
+            .build()
+            .unwrap();
+        assert_eq!(plan.aircraft.as_deref(), Some("B-1234"));
+        assert_eq!((plan.cruise_speed, plan.cruise_altitude), (120, 8500));
+        assert_eq!(
+            plan.procedures,
+            PlanProcedures {
+                sid: Some("ELK1A".to_string()),
+                star: Some("DOGAR1".to_string()),
+                approach: Some("I36L".to_string()),
+            }
+        );
+        assert_eq!(
+            plan.altitude_profile,
+            vec![AltitudeStep {
+                waypoint: "WP2".to_string(),
+                altitude: 10500,
+            }]
+        );
+        assert_eq!(plan.departure_time, Some(1_700_000_000));
+
+        // Bulk setters replace what the single setters added
+        let replaced = base()
+            .sid("ELK1A")
+            .procedures(PlanProcedures::default())
+            .altitude_step("WP1", 9500)
+            .altitude_profile(vec![])
+            .build()
+            .unwrap();
+        assert_eq!(replaced.procedures, PlanProcedures::default());
+        assert!(replaced.altitude_profile.is_empty());
+
+        // The details survive drafts and JSON, and take part in the hash
+        let draft = base().star("DOGAR1").departure_time(1_700_000_000).build_partial();
+        let stored: DraftFlightPlan =
+            serde_json::from_str(&serde_json::to_string(&draft).unwrap()).unwrap();
+        assert_eq!(stored, draft);
+        let resumed = FlightPlanBuilder::from_draft(stored).build().unwrap();
+        assert_eq!(resumed.procedures.star.as_deref(), Some("DOGAR1"));
+        let json = format::plan_to_json(&plan).unwrap();
+        assert_eq!(format::plan_from_json(&json).unwrap().route_hash(), plan.route_hash());
+        assert_ne!(resumed.route_hash(), replaced.route_hash());
+        assert_ne!(base().departure_time(0).build().unwrap().route_hash(), replaced.route_hash());
+    }
+
+    #[test]
+    fn test_calculate_many() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = crate::Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: true,
+            pool_size: 4,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0),
+                    ('A3', 'ZSPD', 'Pudong', 31.14, 121.81, 0);",
+            )
+            .unwrap();
+        let planner = FlightPlanner::new(Arc::clone(&db)).unwrap();
+
+        // Candidate levels and alternates, plus one plan that cannot be routed
+        let mut plans: Vec<FlightPlan> = (0..24)
+            .map(|i| FlightPlan {
+                departure: "ZBAA".to_string(),
+                destination: if i % 2 == 0 { "ZSSS" } else { "ZSPD" }.to_string(),
+                alternate: Some("ZSPD".to_string()),
+                cruise_altitude: 29000 + (i % 6) * 2000,
+                cruise_speed: 420 + i * 5,
+                route: vec![],
+                ..Default::default()
+            })
+            .collect();
+        plans[7].destination = "XXXX".to_string();
+
+        let routes = planner.calculate_many(&plans);
+        assert_eq!(routes.len(), plans.len());
+        for (plan, route) in plans.iter().zip(&routes) {
+            match route {
+                Ok(route) => {
+                    assert_eq!(route.plan.route_hash(), plan.route_hash());
+                    let expected = planner::calculate_route(&db, plan).unwrap();
+                    assert_eq!(route.estimated_time, expected.estimated_time);
+                }
+                Err(_) => assert_eq!(plan.destination, "XXXX"),
+            }
+        }
+        assert_eq!(routes.iter().filter(|r| r.is_err()).count(), 1);
+        assert!(planner.calculate_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_route_hash() {
+        let build = |alternate: Option<&str>, route: &[&str]| {
+            let mut builder = FlightPlanBuilder::new()
+                .departure("ZBAA")
+                .destination("ZSSS")
+                .cruise_altitude(35000)
+                .cruise_speed(450);
+            if let Some(alternate) = alternate {
+                builder = builder.alternate(alternate);
+            }
+            route
+                .iter()
+                .fold(builder, |b, id| b.add_waypoint(id))
+                .build()
+                .unwrap()
+        };
+
+        let plan = build(None, &["WP1", "WP2"]);
+        assert_eq!(plan.route_hash(), plan.clone().route_hash());
+        assert_eq!(plan.route_hash().len(), 64);
+
+        let json: FlightPlan = serde_json::from_str(&serde_json::to_string(&plan).unwrap()).unwrap();
+        assert_eq!(json.route_hash(), plan.route_hash());
+
+        assert_ne!(build(None, &["WP1WP2"]).route_hash(), plan.route_hash());
+        assert_ne!(build(None, &["WP2", "WP1"]).route_hash(), plan.route_hash());
+        assert_ne!(build(Some(""), &["WP1", "WP2"]).route_hash(), plan.route_hash());
+        assert_ne!(build(Some("ZSPD"), &["WP1", "WP2"]).route_hash(), plan.route_hash());
+    }
+
     #[test]
     fn test_flight_plan_builder_missing_fields() {
         let plan = FlightPlanBuilder::new()
@@ -0,0 +1,459 @@
+use crate::db::query::{navaid_from_row, normalize_code, AIRPORT_ID_BY_CODE, NAVAID_COLUMNS};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::fuel::FuelPlan;
+use crate::flight::{FlightPlan, FlightRoute};
+use crate::models::Coordinate;
+use crate::repository::TuningFrequency;
+use crate::spatial::geometry;
+use chrono::DateTime;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// A navaid this close to a route fix with the same name is listed as its frequency
+const COLOCATED_NAVAID_NM: f64 = 1.0;
+
+/// Page size of the PDF nav log: A4 landscape, in points
+const PAGE_WIDTH: f64 = 842.0;
+const PAGE_HEIGHT: f64 = 595.0;
+const MARGIN: f64 = 36.0;
+const FONT_SIZE: f64 = 8.0;
+const LINE_HEIGHT: f64 = 10.0;
+
+/// Settings for a navigation log
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NavLogOptions {
+    /// Planned off-block time (Unix seconds); without it legs have no ETO
+    pub departure_time: Option<i64>,
+    /// Without a fuel plan legs have no fuel columns
+    pub fuel: Option<FuelPlan>,
+    /// Degrees east, subtracted from true courses to give magnetic courses
+    pub magnetic_variation: f64,
+}
+
+/// One row of the nav log: the leg flown to `to`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavLogLeg {
+    pub from: String,
+    pub to: String,
+    pub true_course: f64,
+    pub magnetic_course: f64,
+    pub distance_nm: f64,
+    pub cumulative_nm: f64,
+    /// Minutes for this leg
+    pub ete_minutes: i32,
+    /// Minutes from departure
+    pub cumulative_minutes: i32,
+    /// Estimated time over `to`, Unix seconds
+    pub eto: Option<i64>,
+    /// Gallons burned on the leg
+    pub fuel_burn: Option<f64>,
+    /// Planned gallons on board over `to`
+    pub fuel_remaining: Option<f64>,
+    /// Navaid co-located with `to`, or the destination's frequencies on the last leg
+    pub frequencies: Vec<TuningFrequency>,
+}
+
+/// Navigation log for printing
+///
+/// The structured form can be rendered by the caller; `to_text` gives a
+/// fixed-width table and `to_pdf` a printable PDF of the same table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NavLog {
+    pub plan: FlightPlan,
+    pub departure_time: Option<i64>,
+    pub magnetic_variation: f64,
+    pub legs: Vec<NavLogLeg>,
+    pub total_distance: f64,
+    pub total_minutes: i32,
+    pub departure_fuel: Option<f64>,
+    pub landing_fuel: Option<f64>,
+    /// Departure, destination and alternate frequencies, by airport
+    pub airport_frequencies: Vec<(String, Vec<TuningFrequency>)>,
+}
+
+/// Build the nav log of `route`
+pub fn nav_log(db: &Database, route: &FlightRoute, options: &NavLogOptions) -> Result<NavLog> {
+    if route.waypoints.is_empty() {
+        return Err(AeroBaseError::InvalidInput("Route has no waypoints".to_string()));
+    }
+    let plan = &route.plan;
+    let conn = db.get_conn()?;
+
+    let mut airports = vec![plan.departure.clone(), plan.destination.clone()];
+    airports.extend(plan.alternate.clone());
+    let airport_frequencies = airports
+        .iter()
+        .map(|icao| Ok((normalize_code(icao), airport_frequencies(&conn, icao)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let fuel_at = |minutes: i32| {
+        options
+            .fuel
+            .as_ref()
+            .map(|fuel| fuel.departure_fuel - fuel.fuel_flow_gph * minutes as f64 / 60.0)
+    };
+    let last = route.waypoints.len() - 1;
+    let mut legs = Vec::with_capacity(last);
+    for (i, pair) in route.waypoints.windows(2).enumerate() {
+        let (from, to) = (&pair[0], &pair[1]);
+        let true_course = geometry::initial_bearing(from.coordinate, to.coordinate);
+        let frequencies = if i + 1 == last {
+            airport_frequencies[1].1.clone()
+        } else {
+            colocated_navaids(&conn, &to.name, to.coordinate)?
+        };
+        let remaining = fuel_at(to.estimated_time);
+        legs.push(NavLogLeg {
+            from: from.name.clone(),
+            to: to.name.clone(),
+            true_course,
+            magnetic_course: (true_course - options.magnetic_variation).rem_euclid(360.0),
+            distance_nm: to.distance_from_previous,
+            cumulative_nm: to.cumulative_distance,
+            ete_minutes: to.estimated_time - from.estimated_time,
+            cumulative_minutes: to.estimated_time,
+            eto: options.departure_time.map(|t| t + to.estimated_time as i64 * 60),
+            fuel_burn: fuel_at(from.estimated_time).zip(remaining).map(|(a, b)| a - b),
+            fuel_remaining: remaining,
+            frequencies,
+        });
+    }
+
+    Ok(NavLog {
+        plan: plan.clone(),
+        departure_time: options.departure_time,
+        magnetic_variation: options.magnetic_variation,
+        legs,
+        total_distance: route.total_distance,
+        total_minutes: route.estimated_time,
+        departure_fuel: options.fuel.as_ref().map(|f| f.departure_fuel),
+        landing_fuel: fuel_at(route.estimated_time),
+        airport_frequencies,
+    })
+}
+
+fn airport_frequencies(conn: &Connection, icao: &str) -> Result<Vec<TuningFrequency>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT f.type, f.frequency FROM airport_frequencies f
+         WHERE f.airport_id = {}
+         ORDER BY f.type, f.frequency",
+        AIRPORT_ID_BY_CODE
+    ))?;
+    let frequencies = stmt
+        .query_map([normalize_code(icao)], |row| {
+            let kind: String = row.get(0)?;
+            Ok(TuningFrequency::new(&normalize_code(icao), &kind, row.get(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(frequencies)
+}
+
+fn colocated_navaids(
+    conn: &Connection,
+    name: &str,
+    position: Coordinate,
+) -> Result<Vec<TuningFrequency>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM navaids WHERE name = ?1 COLLATE NOCASE AND frequency IS NOT NULL",
+        NAVAID_COLUMNS
+    ))?;
+    let navaids = stmt
+        .query_map([name], navaid_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(navaids
+        .into_iter()
+        .filter(|n| n.coordinate.distance_to(&position) <= COLOCATED_NAVAID_NM)
+        .filter_map(|n| {
+            let frequency = n.frequency?;
+            Some(TuningFrequency::new(&n.name, n.navaid_type.as_str(), frequency))
+        })
+        .collect())
+}
+
+impl NavLog {
+    /// Fixed-width table, one line per leg
+    pub fn to_text(&self) -> String {
+        self.lines().join("\n") + "\n"
+    }
+
+    /// Printable PDF (A4 landscape, Courier) of the `to_text` table
+    ///
+    /// The header repeats on every page. Characters outside ASCII print as `?`.
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let lines = self.lines();
+        let per_page = ((PAGE_HEIGHT - 2.0 * MARGIN) / LINE_HEIGHT).floor() as usize;
+        let header = lines.iter().position(|l| l.starts_with("FROM")).map_or(0, |i| i + 2);
+        let (head, body) = lines.split_at(header);
+        let rows_per_page = per_page.saturating_sub(head.len()).max(1);
+
+        let pages: Vec<Vec<&str>> = if body.is_empty() {
+            vec![head.iter().map(String::as_str).collect()]
+        } else {
+            body.chunks(rows_per_page)
+                .map(|rows| head.iter().chain(rows).map(String::as_str).collect())
+                .collect()
+        };
+        text_pdf(&pages)
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let plan = &self.plan;
+        let mut lines = vec![format!(
+            "NAV LOG {} - {}{}   {} ft   {} kt   VAR {:.0}{}",
+            plan.departure,
+            plan.destination,
+            plan.alternate.as_ref().map(|a| format!(" (ALTN {})", a)).unwrap_or_default(),
+            plan.cruise_altitude,
+            plan.cruise_speed,
+            self.magnetic_variation.abs(),
+            if self.magnetic_variation < 0.0 { "W" } else { "E" },
+        )];
+        let mut summary = format!(
+            "DIST {:.0} nm   ETE {}",
+            self.total_distance,
+            format_minutes(self.total_minutes)
+        );
+        if let Some(departure) = self.departure_time {
+            let _ = write!(summary, "   ETD {}", format_time(departure));
+        }
+        if let (Some(departure), Some(landing)) = (self.departure_fuel, self.landing_fuel) {
+            let _ = write!(summary, "   FUEL {:.1} / LDG {:.1}", departure, landing);
+        }
+        lines.push(summary);
+        for (icao, frequencies) in &self.airport_frequencies {
+            if !frequencies.is_empty() {
+                lines.push(format!("{} {}", icao, format_frequencies(frequencies)));
+            }
+        }
+        lines.push(String::new());
+
+        let header = format!(
+            "{:<8} {:<8} {:>3} {:>3} {:>6} {:>6} {:>5} {:>5} {:>6} {:>6} {:>7}  {}",
+            "FROM", "TO", "TC", "MC", "DIST", "CUM", "ETE", "ETO", "ATO", "BURN", "REM", "FREQ"
+        );
+        lines.push(header.clone());
+        lines.push("-".repeat(header.len() + 16));
+        for leg in &self.legs {
+            let fuel = |value: Option<f64>| value.map(|v| format!("{:.1}", v)).unwrap_or_default();
+            lines.push(format!(
+                "{:<8} {:<8} {:03.0} {:03.0} {:>6.1} {:>6.1} {:>5} {:>5} {:>6} {:>6} {:>7}  {}",
+                truncate(&leg.from, 8),
+                truncate(&leg.to, 8),
+                leg.true_course.round() % 360.0,
+                leg.magnetic_course.round() % 360.0,
+                leg.distance_nm,
+                leg.cumulative_nm,
+                format_minutes(leg.ete_minutes),
+                leg.eto.map(format_clock).unwrap_or_default(),
+                "______",
+                fuel(leg.fuel_burn),
+                fuel(leg.fuel_remaining),
+                format_frequencies(&leg.frequencies)
+            ));
+        }
+        lines
+    }
+}
+
+fn truncate(value: &str, width: usize) -> String {
+    value.chars().take(width).collect()
+}
+
+pub(crate) fn format_frequencies(frequencies: &[TuningFrequency]) -> String {
+    frequencies
+        .iter()
+        .map(|f| format!("{} {}", f.kind, f.display))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `1:05`
+pub(crate) fn format_minutes(minutes: i32) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// `0715` (UTC)
+pub(crate) fn format_clock(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%H%M").to_string())
+        .unwrap_or_default()
+}
+
+fn format_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H%MZ").to_string())
+        .unwrap_or_default()
+}
+
+/// Minimal PDF 1.4 writer: one Courier text block per page
+fn text_pdf(pages: &[Vec<&str>]) -> Vec<u8> {
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(), // page tree, filled in once the page ids are known
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    let mut kids = Vec::with_capacity(pages.len());
+    for lines in pages {
+        let mut content = format!(
+            "BT\n/F1 {} Tf\n{} TL\n{} {} Td\n",
+            FONT_SIZE,
+            LINE_HEIGHT,
+            MARGIN,
+            PAGE_HEIGHT - MARGIN - FONT_SIZE
+        );
+        for line in lines {
+            let _ = writeln!(content, "({}) Tj T*", escape_pdf(line));
+        }
+        content.push_str("ET\n");
+
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+        let contents_id = objects.len();
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, contents_id
+        ));
+        kids.push(format!("{} 0 R", objects.len()));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        kids.len()
+    );
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+fn escape_pdf(line: &str) -> String {
+    line.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            c if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlanner;
+    use crate::Config;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    fn planner() -> (FlightPlanner, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('AP1', 'AAAA', 'Alpha', 0.0, 0.0, 0),
+                    ('AP2', 'BBBB', 'Bravo', 0.0, 2.0, 0);
+                 INSERT INTO airport_frequencies (id, airport_id, type, frequency, created_at)
+                    VALUES ('F1', 'AP2', 'TWR', 118.1, 0), ('F2', 'AP1', 'GND', 121.9, 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, created_at)
+                    VALUES ('W1', 'MID', 0.0, 1.0, 0);
+                 INSERT INTO navaids (id, name, type, latitude, longitude, frequency, created_at)
+                    VALUES ('N1', 'MID', 'VORDME', 0.001, 1.0, 113.7, 0),
+                           ('N2', 'MID', 'NDB', 20.0, 1.0, 352.0, 0);",
+            )
+            .unwrap();
+        (FlightPlanner::new(db).unwrap(), temp_file)
+    }
+
+    #[test]
+    fn test_nav_log() {
+        let (planner, _temp) = planner();
+        let plan = FlightPlan {
+            departure: "AAAA".to_string(),
+            destination: "BBBB".to_string(),
+            alternate: None,
+            cruise_altitude: 6000,
+            cruise_speed: 120,
+            route: vec!["W1".to_string()],
+            ..Default::default()
+        };
+        let options = NavLogOptions {
+            departure_time: Some(1_700_000_000),
+            fuel: Some(FuelPlan::new(50.0, 12.0)),
+            magnetic_variation: -5.0,
+        };
+        let log = planner.nav_log(&plan, &options).unwrap();
+
+        assert_eq!(log.legs.len(), 2);
+        let first = &log.legs[0];
+        assert_eq!((first.from.as_str(), first.to.as_str()), ("AAAA", "MID"));
+        assert!((first.true_course - 90.0).abs() < 0.1);
+        assert!((first.magnetic_course - 95.0).abs() < 0.1);
+        assert_eq!(first.ete_minutes, 30);
+        assert_eq!(first.eto, Some(1_700_001_800));
+        assert_eq!(first.fuel_burn, Some(6.0));
+        assert_eq!(first.fuel_remaining, Some(44.0));
+        assert_eq!(first.frequencies.len(), 1);
+        assert_eq!(first.frequencies[0].display, "113.70");
+        assert_eq!(log.legs[1].frequencies[0].kind, "TWR");
+        assert_eq!(log.landing_fuel, Some(38.0));
+
+        let text = log.to_text();
+        assert!(text.starts_with("NAV LOG AAAA - BBBB"));
+        assert!(text.contains("VAR 5W"));
+        assert!(text.contains("AAAA     MID      090 095"));
+        assert!(text.contains("VORDME 113.70"));
+
+        let pdf = log.to_pdf();
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let body = String::from_utf8(pdf).unwrap();
+        assert!(body.contains("(AAAA     MID      090 095"));
+        assert!(body.contains("/Count 1"));
+        // xref offsets point at the objects
+        let xref = body.find("\nxref\n").unwrap() + 1;
+        let first_offset: usize = body[xref..].lines().nth(3).unwrap()[..10].parse().unwrap();
+        assert!(body[first_offset..].starts_with("1 0 obj"));
+    }
+
+    #[test]
+    fn test_pdf_pagination() {
+        let lines: Vec<String> = (0..120).map(|i| format!("row {} (x)", i)).collect();
+        let pages: Vec<Vec<&str>> =
+            lines.chunks(50).map(|c| c.iter().map(String::as_str).collect()).collect();
+        let body = String::from_utf8(text_pdf(&pages)).unwrap();
+        assert!(body.contains("/Count 3"));
+        assert!(body.contains("(row 7 \\(x\\)) Tj"));
+        assert_eq!(escape_pdf("ZBAA – 北京"), "ZBAA ? ??");
+    }
+}
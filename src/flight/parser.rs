@@ -0,0 +1,687 @@
+use crate::db::query::{
+    edit_distance, normalize_code, waypoint_from_row, AIRPORT_ID_BY_CODE, WAYPOINT_COLUMNS,
+};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::validator::ValidationWarning;
+use crate::flight::FlightPlan;
+use crate::models::waypoint::Waypoint;
+use crate::models::Coordinate;
+use crate::spatial::geometry::bounding_box;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// How to choose between waypoints sharing an ident
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixPolicy {
+    /// Only fixes in this ICAO region; the first by id if several remain
+    Region(String),
+    /// The fix closest to a position, typically the previous route point
+    Near(Coordinate),
+}
+
+/// A resolved fix and how many waypoints share its ident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixMatch {
+    pub waypoint: Waypoint,
+    /// Waypoints with this ident anywhere, including the chosen one
+    pub candidates: usize,
+}
+
+impl FixMatch {
+    pub fn is_ambiguous(&self) -> bool {
+        self.candidates > 1
+    }
+}
+
+/// Resolve a fix ident to one waypoint using `policy`
+///
+/// Returns `None` when no waypoint has the ident, or none satisfies a
+/// region policy.
+pub fn resolve_fix(db: &Database, ident: &str, policy: &FixPolicy) -> Result<Option<FixMatch>> {
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM waypoints WHERE name = ?1 ORDER BY id",
+        WAYPOINT_COLUMNS
+    ))?;
+    let waypoints = stmt
+        .query_map([normalize_code(ident)], waypoint_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let candidates = waypoints.len();
+
+    let chosen = match policy {
+        FixPolicy::Region(region) => {
+            let region = normalize_code(region);
+            waypoints
+                .into_iter()
+                .find(|w| w.region.as_deref().is_some_and(|r| normalize_code(r) == region))
+        }
+        FixPolicy::Near(position) => waypoints.into_iter().min_by(|a, b| {
+            a.distance_from(*position)
+                .total_cmp(&b.distance_from(*position))
+        }),
+    };
+    Ok(chosen.map(|waypoint| FixMatch {
+        waypoint,
+        candidates,
+    }))
+}
+
+/// What a geocoded token turned out to be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GeocodeKind {
+    Airport,
+    Waypoint,
+    Navaid,
+}
+
+/// A token resolved to a position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeocodedPoint {
+    /// Position of the token in the input
+    pub index: usize,
+    pub token: String,
+    pub kind: GeocodeKind,
+    /// Airport, waypoint or navaid id
+    pub id: String,
+    pub coordinate: Coordinate,
+    /// Points of this kind sharing the ident, including the chosen one
+    pub candidates: usize,
+}
+
+/// A token that could not be resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeocodeFailure {
+    pub index: usize,
+    pub token: String,
+    pub reason: String,
+}
+
+/// Outcome of geocoding a batch of tokens
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeocodeReport {
+    /// Resolved tokens in input order
+    pub points: Vec<GeocodedPoint>,
+    pub failures: Vec<GeocodeFailure>,
+}
+
+impl GeocodeReport {
+    /// True when every token resolved
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Resolve many idents or route tokens to coordinates in one pass
+///
+/// Meant for bulk validation of pasted schedules and route strings: all
+/// lookups share one connection and read transaction, and a token that
+/// doesn't resolve is reported rather than failing the batch. Speed/level
+/// suffixes are stripped and `DCT` is skipped. Each token is tried as an
+/// airport code (ICAO, IATA or alias), then a waypoint, then a navaid;
+/// shared idents pick the point nearest the previous resolved token, or the
+/// first by id for the first token.
+pub fn geocode(db: &Database, tokens: &[&str]) -> Result<GeocodeReport> {
+    let conn = db.get_conn()?;
+    let tx = conn.unchecked_transaction()?;
+
+    let mut report = GeocodeReport::default();
+    let mut previous: Option<Coordinate> = None;
+    for (index, raw) in tokens.iter().enumerate() {
+        let token = normalize_code(raw.split('/').next().unwrap_or_default());
+        if token == "DCT" {
+            continue;
+        }
+        if token.is_empty() {
+            report.failures.push(GeocodeFailure {
+                index,
+                token: raw.to_string(),
+                reason: "Empty token".to_string(),
+            });
+            continue;
+        }
+        match geocode_token(&tx, &token, previous)? {
+            Some((kind, id, coordinate, candidates)) => {
+                previous = Some(coordinate);
+                report.points.push(GeocodedPoint {
+                    index,
+                    token,
+                    kind,
+                    id,
+                    coordinate,
+                    candidates,
+                });
+            }
+            None => report.failures.push(GeocodeFailure {
+                index,
+                reason: format!("Unknown airport, fix or navaid {}", token),
+                token,
+            }),
+        }
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
+fn geocode_token(
+    conn: &Connection,
+    token: &str,
+    previous: Option<Coordinate>,
+) -> Result<Option<(GeocodeKind, String, Coordinate, usize)>> {
+    let airport = conn
+        .prepare_cached(&format!(
+            "SELECT id, latitude, longitude FROM airports WHERE id = {}",
+            AIRPORT_ID_BY_CODE
+        ))?
+        .query_row([token], |row| {
+            Ok((row.get::<_, String>(0)?, Coordinate::new(row.get(1)?, row.get(2)?)))
+        })
+        .optional()?;
+    if let Some((id, coordinate)) = airport {
+        return Ok(Some((GeocodeKind::Airport, id, coordinate, 1)));
+    }
+
+    for (kind, table) in [(GeocodeKind::Waypoint, "waypoints"), (GeocodeKind::Navaid, "navaids")] {
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT id, latitude, longitude FROM {} WHERE name = ?1 ORDER BY id",
+            table
+        ))?;
+        let found = stmt
+            .query_map([token], |row| {
+                Ok((row.get::<_, String>(0)?, Coordinate::new(row.get(1)?, row.get(2)?)))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let candidates = found.len();
+        let chosen = match previous {
+            Some(position) => found.into_iter().min_by(|a, b| {
+                a.1.distance_to(&position).total_cmp(&b.1.distance_to(&position))
+            }),
+            None => found.into_iter().next(),
+        };
+        if let Some((id, coordinate)) = chosen {
+            return Ok(Some((kind, id, coordinate, candidates)));
+        }
+    }
+    Ok(None)
+}
+
+fn airport_position(db: &Database, code: &str) -> Result<Coordinate> {
+    db.get_conn()?
+        .query_row(
+            &format!(
+                "SELECT latitude, longitude FROM airports WHERE id = {}",
+                AIRPORT_ID_BY_CODE
+            ),
+            [code],
+            |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| AeroBaseError::NotFound(format!("Airport {} not found", code)))
+}
+
+/// Waypoint ids parsed from a route string, with any ambiguity warnings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParsedRoute {
+    /// Ready for `FlightPlan::route`
+    pub route: Vec<String>,
+    pub warnings: Vec<ValidationWarning>,
+}
+
+/// Parse a space-separated route string such as `"ZBAA DCT ONE TWO ZSSS"`
+///
+/// `DCT`, the departure and destination codes, and speed/level suffixes
+/// (`ONE/N0450F350`) are skipped. Each fix is resolved nearest to the
+/// previous route point; idents shared by several waypoints produce an
+/// `ambiguous_fix` warning naming the one chosen. Airway designators are
+/// not expanded and fail as unknown fixes.
+///
+/// An unknown fix fails with `AeroBaseError::RouteParse`, carrying the
+/// token, its position and the waypoints it was most likely meant to be.
+pub fn parse_route(
+    db: &Database,
+    departure: &str,
+    route: &str,
+    destination: &str,
+) -> Result<ParsedRoute> {
+    let departure = normalize_code(departure);
+    let destination = normalize_code(destination);
+    let mut previous = airport_position(db, &departure)?;
+
+    let mut parsed = ParsedRoute::default();
+    for token in route_tokens(&departure, route, &destination) {
+        let ident = token.ident;
+        let Some(fix) = resolve_fix(db, &ident, &FixPolicy::Near(previous))? else {
+            return Err(AeroBaseError::RouteParse {
+                position: token.position,
+                token: token.raw.to_string(),
+                reason: format!("Unknown fix {}", ident),
+                suggestions: suggest_fixes(db, &ident, previous)?,
+            });
+        };
+        if fix.is_ambiguous() {
+            parsed.warnings.push(ValidationWarning {
+                rule: "ambiguous_fix".to_string(),
+                message: format!(
+                    "{} matches {} waypoints; using {} ({}) {:.0} nm from the previous point",
+                    ident,
+                    fix.candidates,
+                    fix.waypoint.id,
+                    fix.waypoint.region.as_deref().unwrap_or("no region"),
+                    fix.waypoint.distance_from(previous)
+                ),
+            });
+        }
+        previous = fix.waypoint.coordinate;
+        parsed.route.push(fix.waypoint.id);
+    }
+    Ok(parsed)
+}
+
+/// Waypoints within this distance of the previous route point are offered
+/// as suggestions for an unknown fix
+pub const SUGGESTION_RADIUS_NM: f64 = 500.0;
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A waypoint an unknown route token was probably meant to be
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteSuggestion {
+    pub ident: String,
+    pub waypoint_id: String,
+    /// Character edits turning the token into `ident`
+    pub edit_distance: usize,
+    /// Distance from the previous route point
+    pub distance_nm: f64,
+}
+
+/// Waypoints near `previous` whose ident is within a typo of `ident`,
+/// fewest edits first, then nearest
+///
+/// Up to two edits are allowed, one for idents of three characters or less.
+/// Each ident is offered once, as its waypoint nearest `previous`.
+pub fn suggest_fixes(
+    db: &Database,
+    ident: &str,
+    previous: Coordinate,
+) -> Result<Vec<RouteSuggestion>> {
+    let ident = normalize_code(ident);
+    let max_edits = if ident.chars().count() <= 3 { 1 } else { 2 };
+    let length = ident.chars().count();
+    let (min, max) = bounding_box(previous, SUGGESTION_RADIUS_NM);
+
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM waypoints
+         WHERE latitude BETWEEN ?1 AND ?2 AND longitude BETWEEN ?3 AND ?4
+           AND length(name) BETWEEN ?5 AND ?6",
+        WAYPOINT_COLUMNS
+    ))?;
+    let waypoints = stmt
+        .query_map(
+            rusqlite::params![
+                min.latitude,
+                max.latitude,
+                min.longitude,
+                max.longitude,
+                length.saturating_sub(max_edits) as i64,
+                (length + max_edits) as i64
+            ],
+            waypoint_from_row,
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut suggestions: Vec<RouteSuggestion> = Vec::new();
+    for waypoint in waypoints {
+        let name = normalize_code(&waypoint.name);
+        let edits = edit_distance(&ident, &name);
+        let distance_nm = waypoint.distance_from(previous);
+        if edits > max_edits || distance_nm > SUGGESTION_RADIUS_NM {
+            continue;
+        }
+        match suggestions.iter_mut().find(|s| s.ident == name) {
+            Some(existing) if existing.distance_nm <= distance_nm => {}
+            Some(existing) => {
+                existing.waypoint_id = waypoint.id;
+                existing.distance_nm = distance_nm;
+            }
+            None => suggestions.push(RouteSuggestion {
+                ident: name,
+                waypoint_id: waypoint.id,
+                edit_distance: edits,
+                distance_nm,
+            }),
+        }
+    }
+    suggestions.sort_by(|a, b| {
+        a.edit_distance
+            .cmp(&b.edit_distance)
+            .then(a.distance_nm.total_cmp(&b.distance_nm))
+            .then_with(|| a.ident.cmp(&b.ident))
+    });
+    suggestions.truncate(MAX_SUGGESTIONS);
+    Ok(suggestions)
+}
+
+/// A fix token of a route string
+struct RouteToken<'a> {
+    /// Byte offset in the route string
+    position: usize,
+    raw: &'a str,
+    ident: String,
+}
+
+/// Fix tokens of a route string, in order, with `DCT`, the departure and
+/// destination codes and speed/level suffixes dropped
+fn route_tokens<'a>(departure: &str, route: &'a str, destination: &str) -> Vec<RouteToken<'a>> {
+    let mut offset = 0;
+    let mut tokens = Vec::new();
+    for raw in route.split_whitespace() {
+        let position = offset + route[offset..].find(raw).unwrap_or_default();
+        offset = position + raw.len();
+        let ident = normalize_code(raw.split('/').next().unwrap_or_default());
+        if !ident.is_empty() && ident != "DCT" && ident != departure && ident != destination {
+            tokens.push(RouteToken {
+                position,
+                raw,
+                ident,
+            });
+        }
+    }
+    tokens
+}
+
+/// Fix idents of a route string, in order
+fn route_idents(departure: &str, route: &str, destination: &str) -> Vec<String> {
+    route_tokens(departure, route, destination)
+        .into_iter()
+        .map(|token| token.ident)
+        .collect()
+}
+
+/// Canonical form of a route string: upper-case fix idents joined by `DCT`,
+/// or just `DCT` for a direct flight
+///
+/// Everything `parse_route` ignores is dropped, so strings that parse to the
+/// same route normalize to the same text, suitable for hashing and display.
+pub fn normalize_route_string(departure: &str, route: &str, destination: &str) -> String {
+    join_idents(&route_idents(&normalize_code(departure), route, &normalize_code(destination)))
+}
+
+fn join_idents(idents: &[String]) -> String {
+    if idents.is_empty() {
+        "DCT".to_string()
+    } else {
+        idents.join(" DCT ")
+    }
+}
+
+/// Write a plan's route in the canonical form `parse_route` reads back
+///
+/// Fails when a waypoint wouldn't survive the round trip: its ident is
+/// shared with a waypoint nearer the previous route point, or it collides
+/// with `DCT` or the departure/destination codes.
+pub fn format_route(db: &Database, plan: &FlightPlan) -> Result<String> {
+    let departure = normalize_code(&plan.departure);
+    let destination = normalize_code(&plan.destination);
+    let mut previous = airport_position(db, &departure)?;
+
+    let mut idents = Vec::with_capacity(plan.route.len());
+    for id in &plan.route {
+        let waypoint = {
+            let conn = db.get_conn()?;
+            conn.query_row(
+                &format!("SELECT {} FROM waypoints WHERE id = ?1", WAYPOINT_COLUMNS),
+                [id],
+                waypoint_from_row,
+            )
+            .optional()?
+            .ok_or_else(|| AeroBaseError::NotFound(format!("Waypoint {} not found", id)))?
+        };
+        let ident = normalize_code(&waypoint.name);
+        if route_idents(&departure, &ident, &destination) != [ident.as_str()] {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Waypoint {} is named {}, which a route string can't express",
+                id, ident
+            )));
+        }
+        match resolve_fix(db, &ident, &FixPolicy::Near(previous))? {
+            Some(fix) if fix.waypoint.id == waypoint.id => {}
+            resolved => {
+                return Err(AeroBaseError::InvalidInput(format!(
+                    "{} would parse as {} rather than waypoint {}",
+                    ident,
+                    resolved.map_or("nothing".to_string(), |fix| fix.waypoint.id),
+                    id
+                )));
+            }
+        }
+        previous = waypoint.coordinate;
+        idents.push(ident);
+    }
+    Ok(join_idents(&idents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::waypoint::WaypointUsage;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        // Two ALPHAs: one near Beijing, one in Europe
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'ZBAA', 'Capital', 40.08, 116.58, 0),
+                    ('A2', 'ZSSS', 'Hongqiao', 31.20, 121.34, 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, region, usage, created_at) VALUES
+                    ('W1', 'ALPHA', 38.5, 117.5, 'FIX', 'ZB', 'ENROUTE', 0),
+                    ('W2', 'ALPHA', 50.0, 8.5, 'FIX', 'ED', 'TERMINAL', 0),
+                    ('W3', 'BRAVO', 34.0, 120.0, 'FIX', 'ZS', NULL, 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_resolve_fix() {
+        let (db, _temp_file) = setup_test_db();
+
+        let europe = resolve_fix(&db, "alpha", &FixPolicy::Region("ed".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(europe.waypoint.id, "W2");
+        assert_eq!(europe.waypoint.usage, Some(WaypointUsage::Terminal));
+        assert!(europe.is_ambiguous());
+
+        let near = resolve_fix(&db, "ALPHA", &FixPolicy::Near(Coordinate::new(40.0, 116.0)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(near.waypoint.id, "W1");
+
+        let bravo = resolve_fix(&db, "BRAVO", &FixPolicy::Near(Coordinate::new(0.0, 0.0)))
+            .unwrap()
+            .unwrap();
+        assert!(!bravo.is_ambiguous());
+        assert_eq!(bravo.waypoint.usage, None);
+
+        let elsewhere = FixPolicy::Region("K2".to_string());
+        assert!(resolve_fix(&db, "ALPHA", &elsewhere).unwrap().is_none());
+        let anywhere = FixPolicy::Near(Coordinate::new(0.0, 0.0));
+        assert!(resolve_fix(&db, "NONE", &anywhere).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_geocode() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO navaids (id, name, type, latitude, longitude, created_at)
+                 VALUES ('N1', 'PEK', 'VORDME', 40.20, 116.58, 0)",
+                [],
+            )
+            .unwrap();
+
+        let tokens = ["zbaa", "DCT", "ALPHA/N0450F350", "PEK", "NOPE", "/F350", "ZSSS"];
+        let report = geocode(&db, &tokens).unwrap();
+        assert!(!report.is_complete());
+
+        let resolved: Vec<_> = report
+            .points
+            .iter()
+            .map(|p| (p.index, p.kind, p.id.as_str()))
+            .collect();
+        assert_eq!(
+            resolved,
+            vec![
+                (0, GeocodeKind::Airport, "A1"),
+                (2, GeocodeKind::Waypoint, "W1"),
+                (3, GeocodeKind::Navaid, "N1"),
+                (6, GeocodeKind::Airport, "A2"),
+            ]
+        );
+        // The ALPHA near the departure wins over the one in Europe
+        assert_eq!(report.points[1].candidates, 2);
+
+        let failed: Vec<_> = report.failures.iter().map(|f| (f.index, f.token.as_str())).collect();
+        assert_eq!(failed, vec![(4, "NOPE"), (5, "/F350")]);
+
+        // The first token has no previous point, so the first ALPHA by id is used
+        let first = geocode(&db, &["ALPHA"]).unwrap();
+        assert!(first.is_complete());
+        assert_eq!(first.points[0].id, "W1");
+        assert!(geocode(&db, &[]).unwrap().is_complete());
+    }
+
+    #[test]
+    fn test_parse_route() {
+        let (db, _temp_file) = setup_test_db();
+
+        let route = "ZBAA DCT alpha BRAVO/N0450F350 DCT ZSSS";
+        let parsed = parse_route(&db, "zbaa", route, "ZSSS").unwrap();
+        assert_eq!(parsed.route, vec!["W1", "W3"]);
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].rule, "ambiguous_fix");
+        assert!(parsed.warnings[0].message.contains("W1 (ZB)"));
+
+        assert!(matches!(
+            parse_route(&db, "ZBAA", "ALPHA B208 BRAVO", "ZSSS"),
+            Err(AeroBaseError::RouteParse { position: 6, .. })
+        ));
+        assert!(parse_route(&db, "XXXX", "ALPHA", "ZSSS").is_err());
+    }
+
+    #[test]
+    fn test_parse_route_suggestions() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                 VALUES ('W4', 'BRAAV', 36.0, 118.0, 'FIX', 0)",
+                [],
+            )
+            .unwrap();
+
+        let Err(AeroBaseError::RouteParse {
+            position,
+            token,
+            reason,
+            suggestions,
+        }) = parse_route(&db, "ZBAA", "ZBAA  ALPHA BRAV0/F350 ZSSS", "ZSSS")
+        else {
+            panic!("expected a route parse error");
+        };
+        assert_eq!((position, token.as_str()), (12, "BRAV0/F350"));
+        assert_eq!(reason, "Unknown fix BRAV0");
+        let idents: Vec<_> = suggestions.iter().map(|s| s.ident.as_str()).collect();
+        // BRAVO is one edit away, BRAAV two but nearer
+        assert_eq!(idents, vec!["BRAVO", "BRAAV"]);
+        assert_eq!(suggestions[0].waypoint_id, "W3");
+
+        // Only the ALPHA near Beijing is in range
+        let suggestions = suggest_fixes(&db, "alpah", Coordinate::new(40.0, 116.0)).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!((suggestions[0].waypoint_id.as_str(), suggestions[0].edit_distance), ("W1", 2));
+        assert!(suggest_fixes(&db, "XYZ", Coordinate::new(40.0, 116.0)).unwrap().is_empty());
+    }
+
+    fn plan(route: &[&str]) -> FlightPlan {
+        FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: None,
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: route.iter().map(|id| id.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize_route_string() {
+        assert_eq!(
+            normalize_route_string("zbaa", " ZBAA dct alpha  BRAVO/N0450F350 DCT DCT zsss ", "ZSSS"),
+            "ALPHA DCT BRAVO"
+        );
+        assert_eq!(normalize_route_string("ZBAA", "ALPHA DCT BRAVO", "ZSSS"), "ALPHA DCT BRAVO");
+        assert_eq!(normalize_route_string("ZBAA", "ZBAA DCT ZSSS", "ZSSS"), "DCT");
+        assert_eq!(normalize_route_string("ZBAA", "", "ZSSS"), "DCT");
+    }
+
+    #[test]
+    fn test_format_route() {
+        let (db, _temp_file) = setup_test_db();
+
+        let text = format_route(&db, &plan(&["W1", "W3"])).unwrap();
+        assert_eq!(text, "ALPHA DCT BRAVO");
+        assert_eq!(parse_route(&db, "ZBAA", &text, "ZSSS").unwrap().route, vec!["W1", "W3"]);
+        assert_eq!(format_route(&db, &plan(&[])).unwrap(), "DCT");
+
+        // The European ALPHA would come back as the one near Beijing
+        assert!(matches!(
+            format_route(&db, &plan(&["W3", "W2"])),
+            Err(AeroBaseError::InvalidInput(message)) if message.contains("W1")
+        ));
+        assert!(matches!(format_route(&db, &plan(&["W9"])), Err(AeroBaseError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W4', 'BRAVO', 36.0, 119.0, 'FIX', 0),
+                    ('W5', 'CHARL', 35.0, 118.0, 'FIX', 0),
+                    ('W6', 'ZSSS', 32.0, 121.0, 'FIX', 0);",
+            )
+            .unwrap();
+        let ids = proptest::sample::select(vec!["W1", "W2", "W3", "W4", "W5", "W6"]);
+
+        proptest::proptest!(|(route in proptest::collection::vec(ids, 0..8))| {
+            let plan = plan(&route);
+            if let Ok(text) = format_route(&db, &plan) {
+                let parsed = parse_route(&db, &plan.departure, &text, &plan.destination).unwrap();
+                proptest::prop_assert_eq!(&parsed.route, &plan.route);
+                proptest::prop_assert_eq!(normalize_route_string("ZBAA", &text, "ZSSS"), text);
+            } else {
+                // Only the far ALPHA, a BRAVO hidden by the other one, or
+                // the waypoint named after the destination can fail
+                proptest::prop_assert!(route.iter().any(|id| ["W2", "W3", "W4", "W6"].contains(id)));
+            }
+        });
+    }
+}
@@ -1,7 +1,10 @@
+use crate::db::query::{normalize_code, AIRPORT_ID_BY_CODE};
 use crate::db::Database;
 use crate::error::{AeroBaseError, Result};
 use crate::flight::{calculator, FlightPlan, FlightRoute, RouteWaypoint};
+use crate::models::waypoint::WaypointType;
 use crate::models::Coordinate;
+use crate::spatial::geohash::{self, GEOHASH_PRECISION};
 
 /// Calculate route for a flight plan
 pub fn calculate_route(db: &Database, plan: &FlightPlan) -> Result<FlightRoute> {
@@ -67,14 +70,47 @@ pub fn calculate_route(db: &Database, plan: &FlightPlan) -> Result<FlightRoute>
     })
 }
 
+/// Store generated positions as GPS waypoints named `{prefix}01`, `{prefix}02`, ...
+///
+/// Returns the new waypoint ids in order, ready to use as a plan route.
+pub fn save_user_waypoints(db: &Database, prefix: &str, points: &[Coordinate]) -> Result<Vec<String>> {
+    let conn = db.get_conn()?;
+    let tx = conn.unchecked_transaction()?;
+    let now = db.clock().timestamp();
+
+    let mut ids = Vec::with_capacity(points.len());
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at, geohash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for (i, point) in points.iter().enumerate() {
+            let id = db.new_id();
+            stmt.execute(rusqlite::params![
+                &id,
+                format!("{}{:02}", prefix, i + 1),
+                point.latitude,
+                point.longitude,
+                WaypointType::GPS.as_str(),
+                now,
+                geohash::encode(*point, GEOHASH_PRECISION),
+            ])?;
+            ids.push(id);
+        }
+    }
+
+    tx.commit()?;
+    Ok(ids)
+}
+
 /// Get airport coordinate by ICAO code
-fn get_airport_coordinate(
+pub(crate) fn get_airport_coordinate(
     conn: &rusqlite::Connection,
     icao: &str,
 ) -> Result<Coordinate> {
     conn.query_row(
-        "SELECT latitude, longitude FROM airports WHERE icao = ?1",
-        [icao],
+        &format!("SELECT latitude, longitude FROM airports WHERE id = {}", AIRPORT_ID_BY_CODE),
+        [normalize_code(icao)],
         |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)),
     )
     .map_err(|_| {
@@ -123,6 +159,7 @@ mod tests {
             db_path: temp_file.path().to_path_buf(),
             enable_wal: false,
             pool_size: 1,
+            ..Default::default()
         };
 
         let db = Arc::new(Database::new(&config).unwrap());
@@ -142,6 +179,8 @@ mod tests {
             [],
         )
         .unwrap();
+        // Give back the pool's only connection before the planner takes one
+        drop(conn);
 
         let plan = FlightPlan {
             departure: "ZBAA".to_string(),
@@ -150,10 +189,23 @@ mod tests {
             cruise_altitude: 35000,
             cruise_speed: 450,
             route: vec![],
+            ..Default::default()
         };
 
         let route = calculate_route(&db, &plan).unwrap();
         assert!(route.total_distance > 0.0);
         assert_eq!(route.waypoints.len(), 2); // departure + destination
+
+        // Generated points become route waypoints
+        let ids = save_user_waypoints(
+            &db,
+            "SAR",
+            &[Coordinate::new(35.0, 119.0), Coordinate::new(35.5, 119.0)],
+        )
+        .unwrap();
+        let plan = FlightPlan { route: ids, ..plan };
+        let route = calculate_route(&db, &plan).unwrap();
+        assert_eq!(route.waypoints[1].name, "SAR01");
+        assert_eq!(route.waypoints[2].name, "SAR02");
     }
 }
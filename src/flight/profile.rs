@@ -0,0 +1,417 @@
+use crate::db::query::{normalize_code, AIRPORT_ID_BY_CODE};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::descent::DescentPath;
+use crate::flight::FlightRoute;
+use crate::models::airspace::Airspace;
+use crate::models::obstacle::TerrainCell;
+use crate::models::Coordinate;
+use crate::spatial::{geometry, query};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Sampling and planned-path settings for a vertical profile
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProfileOptions {
+    /// Distance between samples; route waypoints are always sampled too
+    pub sample_spacing_nm: f64,
+    /// Half-width of the corridor searched for terrain and obstacles
+    pub corridor_nm: f64,
+    /// Planned climb after departure, in feet per nautical mile
+    pub climb_gradient: f64,
+    pub descent: DescentPath,
+    /// Used when an airport has no transition altitude or level stored
+    #[serde(default = "default_transition_altitude")]
+    pub default_transition_altitude: i32,
+}
+
+fn default_transition_altitude() -> i32 {
+    18000
+}
+
+impl Default for ProfileOptions {
+    fn default() -> Self {
+        Self {
+            sample_spacing_nm: 1.0,
+            corridor_nm: 1.0,
+            climb_gradient: 300.0,
+            descent: DescentPath::default(),
+            default_transition_altitude: default_transition_altitude(),
+        }
+    }
+}
+
+/// Pressure setting an altitude is flown on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AltitudeReference {
+    /// Local QNH; shown as an altitude in feet
+    Altitude,
+    /// Standard pressure; shown as a flight level
+    FlightLevel,
+}
+
+impl AltitudeReference {
+    /// "ALT 5000" or "FL110"
+    pub fn label(self, altitude: i32) -> String {
+        match self {
+            AltitudeReference::Altitude => format!("ALT {}", altitude),
+            AltitudeReference::FlightLevel => {
+                format!("FL{:03}", (altitude as f64 / 100.0).round() as i32)
+            }
+        }
+    }
+}
+
+/// One point of the profile; elevations in feet AMSL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileSample {
+    pub distance_nm: f64,
+    pub coordinate: Coordinate,
+    pub planned_altitude: i32,
+    /// Climbing above the departure transition altitude switches to flight
+    /// levels; descending below the destination transition level switches
+    /// back, so the transition layer reads as a level on the way up and an
+    /// altitude on the way down
+    pub reference: AltitudeReference,
+    /// `planned_altitude` as "ALT 5000" or "FL110"
+    pub label: String,
+    /// Highest terrain in the corridor, when terrain data covers it
+    pub terrain: Option<i32>,
+    /// Highest obstacle top in the corridor
+    pub obstacle: Option<i32>,
+    /// Planned altitude above the higher of terrain and obstacle
+    pub clearance: Option<i32>,
+}
+
+/// Stretch of the route laterally inside an airspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirspaceSpan {
+    pub airspace_id: String,
+    pub name: String,
+    pub from_nm: f64,
+    pub to_nm: f64,
+    pub lower_limit: Option<i32>,
+    pub upper_limit: Option<i32>,
+}
+
+/// Altitude-versus-distance series along a route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerticalProfile {
+    pub samples: Vec<ProfileSample>,
+    pub airspaces: Vec<AirspaceSpan>,
+}
+
+impl VerticalProfile {
+    /// Sample with the least clearance above terrain and obstacles
+    pub fn lowest_clearance(&self) -> Option<&ProfileSample> {
+        self.samples
+            .iter()
+            .filter(|s| s.clearance.is_some())
+            .min_by_key(|s| s.clearance)
+    }
+}
+
+/// Build the vertical profile of `route`
+///
+/// The planned path climbs from the departure elevation, cruises, and
+/// descends to the destination elevation along `options.descent`.
+pub fn vertical_profile(
+    db: &Database,
+    route: &FlightRoute,
+    options: &ProfileOptions,
+) -> Result<VerticalProfile> {
+    if options.sample_spacing_nm.is_nan()
+        || options.sample_spacing_nm <= 0.0
+        || options.corridor_nm < 0.0
+        || options.climb_gradient <= 0.0
+    {
+        return Err(AeroBaseError::InvalidInput(
+            "Profile spacing and climb gradient must be positive, corridor non-negative"
+                .to_string(),
+        ));
+    }
+    if route.waypoints.is_empty() {
+        return Err(AeroBaseError::InvalidInput("Route has no waypoints".to_string()));
+    }
+    let descent_gradient = options.descent.feet_per_nm()?;
+
+    let conn = db.get_conn()?;
+    // Elevation, transition altitude and transition level; unknown
+    // airports or elevations count as sea level
+    let airport = |icao: &str| {
+        conn.query_row(
+            &format!(
+                "SELECT elevation, transition_altitude, transition_level
+                 FROM airports WHERE id = {}",
+                AIRPORT_ID_BY_CODE
+            ),
+            [normalize_code(icao)],
+            |row| {
+                Ok((
+                    row.get::<_, Option<i32>>(0)?.unwrap_or(0) as f64,
+                    row.get::<_, Option<i32>>(1)?,
+                    row.get::<_, Option<i32>>(2)?,
+                ))
+            },
+        )
+        .unwrap_or((0.0, None, None))
+    };
+    let (departure_elevation, transition_altitude, _) = airport(&route.plan.departure);
+    let (destination_elevation, destination_ta, transition_level) =
+        airport(&route.plan.destination);
+    drop(conn);
+    let transition_altitude = transition_altitude.unwrap_or(options.default_transition_altitude);
+    // Without a stored level, transition at the destination's altitude
+    let transition_level = transition_level
+        .or(destination_ta)
+        .unwrap_or(options.default_transition_altitude);
+    let cruise_on_standard = route.plan.cruise_altitude > transition_altitude;
+
+    // Route bounding box grown by the corridor
+    let (mut min, mut max) = (route.waypoints[0].coordinate, route.waypoints[0].coordinate);
+    for w in &route.waypoints {
+        min.latitude = min.latitude.min(w.coordinate.latitude);
+        min.longitude = min.longitude.min(w.coordinate.longitude);
+        max.latitude = max.latitude.max(w.coordinate.latitude);
+        max.longitude = max.longitude.max(w.coordinate.longitude);
+    }
+    let min = geometry::bounding_box(min, options.corridor_nm).0;
+    let max = geometry::bounding_box(max, options.corridor_nm).1;
+
+    let terrain: HashMap<(i32, i32), i32> = query::find_terrain_cells_in(db, min, max)?
+        .into_iter()
+        .map(|c| ((c.cell_lat, c.cell_lon), c.max_elevation))
+        .collect();
+    let obstacles = query::find_obstacles_in(db, min, max)?;
+    let airspaces = query::find_airspaces_in(db, min, max)?;
+
+    let total = route.total_distance;
+    let points = route_samples(route, options.sample_spacing_nm);
+
+    let mut samples = Vec::with_capacity(points.len());
+    for (distance, coordinate) in points {
+
+        let climb = (departure_elevation + distance * options.climb_gradient)
+            .min(route.plan.cruise_altitude as f64);
+        let descent = destination_elevation + (total - distance) * descent_gradient;
+        let planned = climb.min(descent).round() as i32;
+
+        let on_standard = if descent < climb {
+            cruise_on_standard && planned >= transition_level
+        } else {
+            planned > transition_altitude
+        };
+        let reference = if on_standard {
+            AltitudeReference::FlightLevel
+        } else {
+            AltitudeReference::Altitude
+        };
+
+        let (low, high) = geometry::bounding_box(coordinate, options.corridor_nm);
+        let (low, high) = (TerrainCell::index(low), TerrainCell::index(high));
+        let terrain_max = (low.0..=high.0)
+            .flat_map(|lat| (low.1..=high.1).map(move |lon| (lat, lon)))
+            .filter_map(|cell| terrain.get(&cell).copied())
+            .max();
+        let obstacle_max = obstacles
+            .iter()
+            .filter(|o| o.coordinate.distance_to(&coordinate) <= options.corridor_nm)
+            .map(|o| o.elevation)
+            .max();
+        let highest = terrain_max.max(obstacle_max);
+
+        samples.push(ProfileSample {
+            distance_nm: distance,
+            coordinate,
+            planned_altitude: planned,
+            reference,
+            label: reference.label(planned),
+            terrain: terrain_max,
+            obstacle: obstacle_max,
+            clearance: highest.map(|h| planned - h),
+        });
+    }
+
+    let mut spans = Vec::new();
+    for (airspace, boundary) in &airspaces {
+        let points = samples.iter().map(|s| (s.distance_nm, s.coordinate));
+        for (from, to) in crossing_spans(points, |_, p| geometry::point_in_polygon(p, boundary)) {
+            spans.push(span(airspace, from, to));
+        }
+    }
+    spans.sort_by(|a, b| a.from_nm.total_cmp(&b.from_nm));
+
+    Ok(VerticalProfile {
+        samples,
+        airspaces: spans,
+    })
+}
+
+fn span(airspace: &Airspace, from_nm: f64, to_nm: f64) -> AirspaceSpan {
+    AirspaceSpan {
+        airspace_id: airspace.id.clone(),
+        name: airspace.name.clone(),
+        from_nm,
+        to_nm,
+        lower_limit: airspace.lower_limit,
+        upper_limit: airspace.upper_limit,
+    }
+}
+
+/// Distance and position every `spacing_nm` along the route, plus at each
+/// waypoint
+pub(crate) fn route_samples(route: &FlightRoute, spacing_nm: f64) -> Vec<(f64, Coordinate)> {
+    let mut distances: Vec<f64> = route.waypoints.iter().map(|w| w.cumulative_distance).collect();
+    let steps = (route.total_distance / spacing_nm).floor() as usize;
+    distances.extend((1..=steps).map(|i| i as f64 * spacing_nm));
+    distances.sort_by(f64::total_cmp);
+    distances.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+    distances
+        .into_iter()
+        .map(|d| (d, position_at(route, d)))
+        .collect()
+}
+
+/// Unix time `distance` nautical miles along the route, flown at the plan's
+/// cruise speed from `departure_time`
+pub(crate) fn time_at(route: &FlightRoute, departure_time: i64, distance: f64) -> i64 {
+    departure_time + (distance / route.plan.cruise_speed as f64 * 3600.0).round() as i64
+}
+
+/// Distance ranges over which consecutive samples satisfy `inside`
+pub(crate) fn crossing_spans(
+    samples: impl IntoIterator<Item = (f64, Coordinate)>,
+    mut inside: impl FnMut(f64, Coordinate) -> bool,
+) -> Vec<(f64, f64)> {
+    let mut spans = Vec::new();
+    let mut open: Option<(f64, f64)> = None;
+    for (distance, coordinate) in samples {
+        if inside(distance, coordinate) {
+            let start = open.map_or(distance, |(start, _)| start);
+            open = Some((start, distance));
+        } else if let Some(span) = open.take() {
+            spans.push(span);
+        }
+    }
+    spans.extend(open);
+    spans
+}
+
+/// Point `distance` nautical miles along the route
+fn position_at(route: &FlightRoute, distance: f64) -> Coordinate {
+    for leg in route.waypoints.windows(2) {
+        if distance <= leg[1].cumulative_distance {
+            let along = (distance - leg[0].cumulative_distance).max(0.0);
+            let bearing = leg[0].coordinate.bearing_to(&leg[1].coordinate);
+            return geometry::destination_point(leg[0].coordinate, along, bearing);
+        }
+    }
+    route.waypoints[route.waypoints.len() - 1].coordinate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{planner, FlightPlan};
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_vertical_profile() {
+        let (db, _temp_file) = setup_test_db();
+        // 60 nm due east along the equator; a ridge and a mast near the middle,
+        // a TMA over the second half
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports
+                    (id, icao, name, latitude, longitude, elevation, transition_altitude,
+                     transition_level, created_at) VALUES
+                    ('A1', 'WEST', 'West', 0.0, 0.0, 500, 3000, 4000, 0),
+                    ('A2', 'EAST', 'East', 0.0, 1.0, 100, 3000, 4000, 0);
+                 INSERT INTO terrain_cells (id, cell_lat, cell_lon, max_elevation, created_at) VALUES
+                    ('0:30', 0, 30, 2500, 0),
+                    ('-1:30', -1, 30, 2400, 0);
+                 INSERT INTO obstacles (id, type, latitude, longitude, elevation, height, created_at)
+                    VALUES ('OB1', 'MAST', 0.005, 0.4, 1800, 600, 0);
+                 INSERT INTO airspaces (id, name, type, lower_limit, upper_limit, created_at)
+                    VALUES ('TMA1', 'East TMA', 'TMA', 2500, 9500, 0);
+                 INSERT INTO airspace_boundaries (id, airspace_id, latitude, longitude, sequence) VALUES
+                    ('B1', 'TMA1', -0.2, 0.55, 0),
+                    ('B2', 'TMA1', 0.2, 0.55, 1),
+                    ('B3', 'TMA1', 0.2, 1.2, 2),
+                    ('B4', 'TMA1', -0.2, 1.2, 3);",
+            )
+            .unwrap();
+
+        let plan = FlightPlan {
+            departure: "WEST".to_string(),
+            destination: "EAST".to_string(),
+            alternate: None,
+            cruise_altitude: 6500,
+            cruise_speed: 120,
+            route: vec![],
+            ..Default::default()
+        };
+        let route = planner::calculate_route(&db, &plan).unwrap();
+        let profile = vertical_profile(&db, &route, &ProfileOptions::default()).unwrap();
+
+        // 1° of longitude is just over 60 nm: 61 spaced samples plus the destination
+        assert_eq!(profile.samples.len(), 62);
+        assert_eq!(profile.samples[0].planned_altitude, 500);
+        assert_eq!(profile.samples[10].planned_altitude, 3500);
+        assert_eq!(profile.samples[30].planned_altitude, 6500);
+        assert_eq!(profile.samples[61].planned_altitude, 100);
+
+        // Climbing through the 3000-4000 ft transition layer reads as a level,
+        // descending through it as an altitude
+        assert_eq!(profile.samples[8].label, "ALT 2900");
+        assert_eq!(profile.samples[10].label, "FL035");
+        assert_eq!(profile.samples[30].label, "FL065");
+        let layer = profile
+            .samples
+            .iter()
+            .skip(31)
+            .find(|s| (3000..4000).contains(&s.planned_altitude))
+            .unwrap();
+        assert_eq!(layer.reference, AltitudeReference::Altitude);
+        assert_eq!(profile.samples[61].label, "ALT 100");
+
+        let ridge = &profile.samples[30];
+        assert_eq!(ridge.terrain, Some(2500));
+        assert_eq!(ridge.clearance, Some(4000));
+        let mast = &profile.samples[24];
+        assert_eq!(mast.obstacle, Some(1800));
+        assert_eq!(mast.clearance, Some(4700));
+        assert!(profile.samples[5].clearance.is_none());
+
+        assert_eq!(profile.airspaces.len(), 1);
+        let tma = &profile.airspaces[0];
+        assert_eq!(tma.airspace_id, "TMA1");
+        // Boundary at 33.0 nm; first sample inside is at 34 nm
+        assert!((tma.from_nm - 34.0).abs() < 0.01);
+        assert!((tma.to_nm - route.total_distance).abs() < 0.01);
+
+        assert!(profile.lowest_clearance().is_some());
+
+        let bad = ProfileOptions {
+            sample_spacing_nm: 0.0,
+            ..Default::default()
+        };
+        assert!(vertical_profile(&db, &route, &bad).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::events::{Event, EventBus};
+use crate::flight::{format, FlightPlan};
+use rusqlite::{OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A flight plan persisted in the `flight_plans` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredFlightPlan {
+    pub id: String,
+    pub device_id: Option<String>,
+    pub name: Option<String>,
+    #[serde(with = "format::versioned_plan")]
+    pub plan: FlightPlan,
+    /// Incremented on every update; callers pass it back to detect stale edits
+    pub version: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+pub(crate) const COLUMNS: &str = "id, device_id, name, plan_json, version, created_at, updated_at";
+
+pub(crate) fn from_row(row: &Row) -> rusqlite::Result<StoredFlightPlan> {
+    let plan_json: String = row.get(3)?;
+    let plan = format::plan_from_json(&plan_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+
+    Ok(StoredFlightPlan {
+        id: row.get(0)?,
+        device_id: row.get(1)?,
+        name: row.get(2)?,
+        plan,
+        version: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Repository for saved flight plans
+pub struct FlightPlanRepository {
+    db: Arc<Database>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl FlightPlanRepository {
+    /// Create a new repository
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db, events: None })
+    }
+
+    /// Publish `FlightPlanSaved` on `events` after every insert and update
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn saved(&self, stored: &StoredFlightPlan) {
+        if let Some(events) = &self.events {
+            events.publish(Event::FlightPlanSaved {
+                id: stored.id.clone(),
+                version: stored.version,
+            });
+        }
+    }
+
+    /// Save a new plan at version 1
+    pub fn insert(
+        &self,
+        plan: &FlightPlan,
+        name: Option<&str>,
+        device_id: Option<&str>,
+    ) -> Result<StoredFlightPlan> {
+        let now = self.db.clock().timestamp();
+        let stored = StoredFlightPlan {
+            id: self.db.new_id(),
+            device_id: device_id.map(str::to_string),
+            name: name.map(str::to_string),
+            plan: plan.clone(),
+            version: 1,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let conn = self.db.get_conn()?;
+        conn.execute(
+            "INSERT INTO flight_plans
+             (id, device_id, name, departure, destination, plan_json, version, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                &stored.id,
+                &stored.device_id,
+                &stored.name,
+                &plan.departure,
+                &plan.destination,
+                format::plan_to_json(plan)?,
+                stored.version,
+                stored.created_at,
+                stored.updated_at,
+            ],
+        )?;
+
+        self.saved(&stored);
+        Ok(stored)
+    }
+
+    /// Get a plan by id
+    pub fn get(&self, id: &str) -> Result<Option<StoredFlightPlan>> {
+        let conn = self.db.get_conn()?;
+        let plan = conn
+            .query_row(
+                &format!("SELECT {} FROM flight_plans WHERE id = ?1", COLUMNS),
+                [id],
+                from_row,
+            )
+            .optional()?;
+        Ok(plan)
+    }
+
+    /// List all plans, newest first
+    pub fn list(&self) -> Result<Vec<StoredFlightPlan>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM flight_plans ORDER BY created_at DESC, id",
+            COLUMNS
+        ))?;
+        let plans = stmt
+            .query_map([], from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(plans)
+    }
+
+    /// List plans created or changed at or after `timestamp` (Unix seconds), oldest change first
+    ///
+    /// Inclusive, so plans saved later in the same second as the last call
+    /// are returned again rather than lost; de-duplicate on `id` and
+    /// `version`. Deleted plans are reported by `sync::changes::changes_since`.
+    pub fn modified_since(&self, timestamp: i64) -> Result<Vec<StoredFlightPlan>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM flight_plans WHERE updated_at >= ?1 ORDER BY updated_at, id",
+            COLUMNS
+        ))?;
+        let plans = stmt
+            .query_map([timestamp], from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(plans)
+    }
+
+    /// Replace a plan's contents if it is still at `expected_version`
+    ///
+    /// Returns `AeroBaseError::Conflict` when another writer updated the plan
+    /// first, so the caller can reload and merge instead of clobbering it.
+    pub fn update(
+        &self,
+        id: &str,
+        plan: &FlightPlan,
+        name: Option<&str>,
+        expected_version: i64,
+    ) -> Result<StoredFlightPlan> {
+        let conn = self.db.get_conn()?;
+        let changed = conn.execute(
+            "UPDATE flight_plans
+             SET name = ?1, departure = ?2, destination = ?3, plan_json = ?4,
+                 version = version + 1, updated_at = ?5
+             WHERE id = ?6 AND version = ?7",
+            rusqlite::params![
+                name,
+                &plan.departure,
+                &plan.destination,
+                format::plan_to_json(plan)?,
+                self.db.clock().timestamp(),
+                id,
+                expected_version,
+            ],
+        )?;
+
+        if changed == 0 {
+            return Err(self.stale_or_missing(&conn, id, expected_version));
+        }
+
+        let updated = conn.query_row(
+            &format!("SELECT {} FROM flight_plans WHERE id = ?1", COLUMNS),
+            [id],
+            from_row,
+        )?;
+        self.saved(&updated);
+        Ok(updated)
+    }
+
+    /// Delete a plan if it is still at `expected_version`
+    pub fn delete(&self, id: &str, expected_version: i64) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        let changed = conn.execute(
+            "DELETE FROM flight_plans WHERE id = ?1 AND version = ?2",
+            rusqlite::params![id, expected_version],
+        )?;
+
+        if changed == 0 {
+            return Err(self.stale_or_missing(&conn, id, expected_version));
+        }
+        Ok(())
+    }
+
+    fn stale_or_missing(
+        &self,
+        conn: &rusqlite::Connection,
+        id: &str,
+        expected_version: i64,
+    ) -> AeroBaseError {
+        let actual: rusqlite::Result<Option<i64>> = conn
+            .query_row(
+                "SELECT version FROM flight_plans WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional();
+
+        match actual {
+            Ok(Some(actual)) => AeroBaseError::Conflict {
+                entity: "flight_plan".to_string(),
+                id: id.to_string(),
+                expected: expected_version,
+                actual,
+            },
+            Ok(None) => AeroBaseError::NotFound(format!("Flight plan {} not found", id)),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlanBuilder;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn sample_plan() -> FlightPlan {
+        FlightPlanBuilder::new()
+            .departure("ZBAA")
+            .destination("ZSSS")
+            .cruise_altitude(35000)
+            .cruise_speed(450)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = FlightPlanRepository::new(db).unwrap();
+
+        let stored = repo.insert(&sample_plan(), Some("Morning run"), None).unwrap();
+        assert_eq!(stored.version, 1);
+
+        let loaded = repo.get(&stored.id).unwrap().unwrap();
+        assert_eq!(loaded.plan.departure, "ZBAA");
+        assert_eq!(loaded.name.as_deref(), Some("Morning run"));
+        assert_eq!(repo.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_modified_since() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = FlightPlanRepository::new(Arc::clone(&db)).unwrap();
+
+        let old = repo.insert(&sample_plan(), Some("old"), None).unwrap();
+        let fresh = repo.insert(&sample_plan(), Some("fresh"), None).unwrap();
+        let conn = db.get_conn().unwrap();
+        conn.execute("UPDATE flight_plans SET updated_at = 100 WHERE id = ?1", [&old.id])
+            .unwrap();
+        drop(conn);
+
+        let changed = repo.modified_since(101).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].id, fresh.id);
+        assert_eq!(repo.modified_since(100).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_stale_update_conflicts() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = FlightPlanRepository::new(db).unwrap();
+        let stored = repo.insert(&sample_plan(), None, None).unwrap();
+
+        // Two windows load version 1; the first save wins
+        let mut first = stored.plan.clone();
+        first.cruise_altitude = 37000;
+        let updated = repo.update(&stored.id, &first, None, 1).unwrap();
+        assert_eq!(updated.version, 2);
+
+        let mut second = stored.plan.clone();
+        second.cruise_altitude = 39000;
+        match repo.update(&stored.id, &second, None, 1) {
+            Err(AeroBaseError::Conflict { expected, actual, .. }) => {
+                assert_eq!(expected, 1);
+                assert_eq!(actual, 2);
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+
+        let loaded = repo.get(&stored.id).unwrap().unwrap();
+        assert_eq!(loaded.plan.cruise_altitude, 37000);
+
+        assert!(updated.updated_at >= stored.updated_at);
+
+        assert!(matches!(repo.delete(&stored.id, 1), Err(AeroBaseError::Conflict { .. })));
+        repo.delete(&stored.id, 2).unwrap();
+        assert!(matches!(
+            repo.update(&stored.id, &second, None, 2),
+            Err(AeroBaseError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_saves_publish_events() {
+        let (db, _temp_file) = setup_test_db();
+        let bus = Arc::new(EventBus::new());
+        let mut rx = bus.subscribe();
+        let repo = FlightPlanRepository::new(db).unwrap().with_events(bus);
+
+        let stored = repo.insert(&sample_plan(), None, None).unwrap();
+        repo.update(&stored.id, &stored.plan, Some("renamed"), 1).unwrap();
+        assert!(repo.update(&stored.id, &stored.plan, None, 1).is_err());
+
+        for version in [1, 2] {
+            assert_eq!(
+                rx.try_recv().unwrap(),
+                Event::FlightPlanSaved {
+                    id: stored.id.clone(),
+                    version
+                }
+            );
+        }
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::{AeroBaseError, Result};
+use crate::models::hold::TurnDirection;
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+
+/// Turn between the legs of a sector search triangle
+const SECTOR_TURN_DEG: f64 = 120.0;
+
+fn require_positive(name: &str, value: f64) -> Result<()> {
+    if value.is_nan() || value <= 0.0 {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "{} must be positive, got {}",
+            name, value
+        )));
+    }
+    Ok(())
+}
+
+/// Expanding square search (SS) starting at the datum
+///
+/// Leg lengths run S, S, 2S, 2S, 3S, ... with a 90° turn after each leg.
+/// Returns the datum followed by one turn point per leg.
+pub fn expanding_square(
+    datum: Coordinate,
+    initial_track: f64,
+    spacing_nm: f64,
+    legs: usize,
+    turn: TurnDirection,
+) -> Result<Vec<Coordinate>> {
+    require_positive("Track spacing", spacing_nm)?;
+
+    let mut points = vec![datum];
+    let mut track = initial_track;
+    for leg in 0..legs {
+        let length = spacing_nm * (leg / 2 + 1) as f64;
+        let last = *points.last().expect("pattern starts at the datum");
+        points.push(geometry::destination_point(last, length, track));
+        track = (track + 90.0 * turn.sign()).rem_euclid(360.0);
+    }
+    Ok(points)
+}
+
+/// Sector search (VS): three triangles through the datum, all turns 120°
+///
+/// The aircraft flies straight through the datum between triangles.
+/// The six outer points end up 60° apart around the datum at `radius_nm`.
+pub fn sector_search(
+    datum: Coordinate,
+    initial_track: f64,
+    radius_nm: f64,
+    turn: TurnDirection,
+) -> Result<Vec<Coordinate>> {
+    require_positive("Sector radius", radius_nm)?;
+
+    let mut points = vec![datum];
+    let mut track = initial_track;
+    for leg in 1..=9 {
+        let last = *points.last().expect("pattern starts at the datum");
+        points.push(geometry::destination_point(last, radius_nm, track));
+        if leg % 3 != 0 {
+            track = (track + SECTOR_TURN_DEG * turn.sign()).rem_euclid(360.0);
+        }
+    }
+
+    // Every third leg ends back at the datum; pin it to avoid drift
+    for i in (3..points.len()).step_by(3) {
+        points[i] = datum;
+    }
+    Ok(points)
+}
+
+/// Parallel track search (PS) from a commence search point
+///
+/// Legs of `leg_length_nm` along `track` alternate direction, each offset
+/// `spacing_nm` to the `creep` side of the previous one.
+pub fn parallel_track(
+    start: Coordinate,
+    track: f64,
+    leg_length_nm: f64,
+    spacing_nm: f64,
+    legs: usize,
+    creep: TurnDirection,
+) -> Result<Vec<Coordinate>> {
+    require_positive("Leg length", leg_length_nm)?;
+    require_positive("Track spacing", spacing_nm)?;
+
+    let creep_track = (track + 90.0 * creep.sign()).rem_euclid(360.0);
+    let mut points = vec![start];
+    for leg in 0..legs {
+        let heading = if leg % 2 == 0 { track } else { track + 180.0 };
+        let last = *points.last().expect("pattern starts at the commence point");
+        let end = geometry::destination_point(last, leg_length_nm, heading);
+        points.push(end);
+        if leg + 1 < legs {
+            points.push(geometry::destination_point(end, spacing_nm, creep_track));
+        }
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATUM: Coordinate = Coordinate {
+        latitude: 30.0,
+        longitude: 120.0,
+    };
+
+    #[test]
+    fn test_expanding_square() {
+        let points = expanding_square(DATUM, 0.0, 1.0, 6, TurnDirection::Right).unwrap();
+        assert_eq!(points.len(), 7);
+
+        // N1, E1, S2, W2, N3, E3
+        let legs: Vec<f64> = points.windows(2).map(|w| w[0].distance_to(&w[1])).collect();
+        for (leg, expected) in legs.iter().zip([1.0, 1.0, 2.0, 2.0, 3.0, 3.0]) {
+            assert!((leg - expected).abs() < 0.01);
+        }
+        assert!(points[2].longitude > DATUM.longitude);
+
+        let left = expanding_square(DATUM, 0.0, 1.0, 2, TurnDirection::Left).unwrap();
+        assert!(left[2].longitude < DATUM.longitude);
+
+        assert!(expanding_square(DATUM, 0.0, 0.0, 4, TurnDirection::Right).is_err());
+    }
+
+    #[test]
+    fn test_sector_search() {
+        let points = sector_search(DATUM, 0.0, 2.0, TurnDirection::Right).unwrap();
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[3], DATUM);
+        assert_eq!(points[9], DATUM);
+
+        let mut bearings: Vec<f64> = points
+            .iter()
+            .filter(|p| **p != DATUM)
+            .map(|p| DATUM.bearing_to(p).rem_euclid(360.0).round())
+            .collect();
+        bearings.sort_by(f64::total_cmp);
+        assert_eq!(bearings, vec![0.0, 60.0, 120.0, 180.0, 240.0, 300.0]);
+        assert!(points.iter().all(|p| p.distance_to(&DATUM) < 2.01));
+    }
+
+    #[test]
+    fn test_parallel_track() {
+        let points = parallel_track(DATUM, 90.0, 10.0, 2.0, 3, TurnDirection::Right).unwrap();
+        // start, end1, step, end2, step, end3
+        assert_eq!(points.len(), 6);
+        assert!(points[1].longitude > DATUM.longitude);
+        assert!(points[3].latitude < DATUM.latitude);
+        assert!((points[3].longitude - DATUM.longitude).abs() < 0.01);
+        assert!((points[5].distance_to(&DATUM) - 10.77).abs() < 0.05);
+    }
+}
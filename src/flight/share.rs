@@ -0,0 +1,187 @@
+use crate::encryption::{verify_signature, RsaKeyPair};
+use crate::error::{AeroBaseError, Result};
+use crate::flight::{format, FlightPlan};
+use base64::{engine::general_purpose, Engine as _};
+use rsa::RsaPublicKey;
+
+/// Current share code format version
+pub const SHARE_FORMAT_VERSION: u8 = 1;
+
+const ZSTD_LEVEL: i32 = 19;
+
+/// Set in the flags byte when a signature follows
+const FLAG_SIGNED: u8 = 1;
+
+/// Hard cap on the decompressed plan JSON
+const MAX_PLAN_BYTES: usize = 64 * 1024;
+
+/// A plan read from a share code
+#[derive(Debug, Clone)]
+pub struct SharedPlan {
+    pub plan: FlightPlan,
+    /// Whether the code carried a signature (checked only when a key was given)
+    pub signed: bool,
+}
+
+/// Encode a plan as a short URL-safe string for QR codes or text messages
+///
+/// Layout before base64url (no padding):
+/// `version:u8 | flags:u8 | [sig_len:u16 | signature] | zstd(plan JSON)`.
+/// The plan uses the versioned JSON of `flight::format`, so codes made by
+/// an older build still decode. A signature (RSA, so about 340 extra
+/// characters) covers the compressed plan.
+pub fn encode_plan(plan: &FlightPlan, signer: Option<&RsaKeyPair>) -> Result<String> {
+    let payload = zstd::encode_all(format::plan_to_json(plan)?.as_bytes(), ZSTD_LEVEL)?;
+
+    let mut out = vec![SHARE_FORMAT_VERSION];
+    match signer {
+        Some(signer) => {
+            let signature = signer.sign(&payload);
+            let sig_len = u16::try_from(signature.len())
+                .map_err(|_| AeroBaseError::InvalidInput("Signature too large".to_string()))?;
+            out.push(FLAG_SIGNED);
+            out.extend_from_slice(&sig_len.to_be_bytes());
+            out.extend_from_slice(&signature);
+        }
+        None => out.push(0),
+    }
+    out.extend_from_slice(&payload);
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(out))
+}
+
+/// Decode a share code produced by `encode_plan`
+///
+/// A full link is accepted too: only the text after the last `/` is read.
+/// With `trusted`, the code must be signed by that key; without it any
+/// signature is ignored and the plan should be treated as unverified.
+pub fn decode_plan(code: &str, trusted: Option<&RsaPublicKey>) -> Result<SharedPlan> {
+    let code = code.trim();
+    let code = code.rsplit('/').next().unwrap_or(code);
+    let bytes = general_purpose::URL_SAFE_NO_PAD
+        .decode(code)
+        .map_err(|_| invalid("not a plan share code"))?;
+
+    let (&version, rest) = bytes.split_first().ok_or_else(|| invalid("empty"))?;
+    if version > SHARE_FORMAT_VERSION {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "Unsupported plan share format version {}",
+            version
+        )));
+    }
+    let (&flags, rest) = rest.split_first().ok_or_else(|| invalid("truncated"))?;
+    let signed = flags & FLAG_SIGNED != 0;
+    let (signature, payload) = if signed {
+        if rest.len() < 2 {
+            return Err(invalid("truncated"));
+        }
+        let sig_len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+        if rest.len() < 2 + sig_len {
+            return Err(invalid("truncated"));
+        }
+        (Some(&rest[2..2 + sig_len]), &rest[2 + sig_len..])
+    } else {
+        (None, rest)
+    };
+
+    if let Some(public_key) = trusted {
+        let signature = signature.ok_or_else(|| invalid("plan is not signed"))?;
+        verify_signature(public_key, payload, signature)?;
+    }
+
+    let mut decoder = zstd::Decoder::new(payload)?;
+    let mut json = Vec::new();
+    std::io::Read::read_to_end(
+        &mut std::io::Read::take(&mut decoder, MAX_PLAN_BYTES as u64 + 1),
+        &mut json,
+    )
+    .map_err(|_| invalid("corrupt payload"))?;
+    if json.len() > MAX_PLAN_BYTES {
+        return Err(invalid("payload exceeds size limit"));
+    }
+    let json = String::from_utf8(json).map_err(|_| invalid("payload is not UTF-8"))?;
+
+    Ok(SharedPlan {
+        plan: format::plan_from_json(&json)?,
+        signed,
+    })
+}
+
+fn invalid(reason: &str) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid plan share code: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> FlightPlan {
+        FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: Some("ZSPD".to_string()),
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: vec!["W1".to_string(), "W3".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_unsigned() {
+        let code = encode_plan(&plan(), None).unwrap();
+        assert!(code.len() < 200, "{} characters", code.len());
+        assert!(code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+
+        let shared = decode_plan(&code, None).unwrap();
+        assert!(!shared.signed);
+        assert_eq!(shared.plan.route, plan().route);
+        assert_eq!(shared.plan.alternate.as_deref(), Some("ZSPD"));
+
+        let link = format!("https://example.com/plan/{}", code);
+        assert_eq!(decode_plan(&link, None).unwrap().plan.departure, "ZBAA");
+    }
+
+    #[test]
+    fn test_signed() {
+        let signer = RsaKeyPair::generate().unwrap();
+        let code = encode_plan(&plan(), Some(&signer)).unwrap();
+
+        let shared = decode_plan(&code, Some(&signer.public_key)).unwrap();
+        assert!(shared.signed);
+        assert_eq!(shared.plan.destination, "ZSSS");
+        // Readable without a key, but then unverified
+        assert!(decode_plan(&code, None).unwrap().signed);
+
+        let other = RsaKeyPair::generate().unwrap();
+        assert!(decode_plan(&code, Some(&other.public_key)).is_err());
+
+        let unsigned = encode_plan(&plan(), None).unwrap();
+        assert!(matches!(
+            decode_plan(&unsigned, Some(&signer.public_key)),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+
+        // Flip a byte of the compressed plan
+        let mut bytes = general_purpose::URL_SAFE_NO_PAD.decode(&code).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered = general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        assert!(decode_plan(&tampered, Some(&signer.public_key)).is_err());
+    }
+
+    #[test]
+    fn test_rejects_malformed() {
+        for code in ["", "!!!", "AQ", "AQE"] {
+            let result = decode_plan(code, None);
+            assert!(matches!(result, Err(AeroBaseError::InvalidInput(_))), "{}", code);
+        }
+        let mut bytes = general_purpose::URL_SAFE_NO_PAD
+            .decode(encode_plan(&plan(), None).unwrap())
+            .unwrap();
+        bytes[0] = SHARE_FORMAT_VERSION + 1;
+        let future = general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        assert!(matches!(
+            decode_plan(&future, None),
+            Err(AeroBaseError::InvalidInput(message)) if message.contains("version")
+        ));
+    }
+}
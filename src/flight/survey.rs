@@ -0,0 +1,216 @@
+use crate::error::{AeroBaseError, Result};
+use crate::models::Coordinate;
+use serde::{Deserialize, Serialize};
+
+/// Photo line layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurveyOptions {
+    /// True direction of the first photo line
+    pub heading: f64,
+    /// Distance between adjacent photo lines
+    pub spacing_nm: f64,
+    /// Extension beyond the polygon at each end of a line, for the turn-around
+    pub run_out_nm: f64,
+}
+
+/// One photo line, in flying order
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PhotoLine {
+    pub start: Coordinate,
+    pub end: Coordinate,
+}
+
+/// Parallel photo lines covering a polygon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurveyGrid {
+    pub lines: Vec<PhotoLine>,
+}
+
+impl SurveyGrid {
+    /// Route through every line start and end; consecutive lines alternate
+    /// direction so each end-to-start hop is a turn-around
+    pub fn waypoints(&self) -> Vec<Coordinate> {
+        self.lines.iter().flat_map(|l| [l.start, l.end]).collect()
+    }
+}
+
+/// Flat-earth projection around a reference point, in nautical miles
+struct LocalFrame {
+    origin: Coordinate,
+    lon_scale: f64,
+}
+
+impl LocalFrame {
+    fn new(origin: Coordinate) -> Self {
+        Self {
+            origin,
+            lon_scale: 60.0 * origin.latitude.to_radians().cos(),
+        }
+    }
+
+    fn project(&self, c: Coordinate) -> (f64, f64) {
+        (
+            (c.longitude - self.origin.longitude) * self.lon_scale,
+            (c.latitude - self.origin.latitude) * 60.0,
+        )
+    }
+
+    fn unproject(&self, (x, y): (f64, f64)) -> Coordinate {
+        Coordinate::new(
+            self.origin.latitude + y / 60.0,
+            self.origin.longitude + x / self.lon_scale,
+        )
+    }
+}
+
+/// Fill `polygon` with parallel photo lines
+///
+/// Lines are spaced evenly across the polygon (half a spacing in from each
+/// side) and clipped to it; a line that crosses a concave notch is split
+/// into separate lines. Suited to survey-sized areas, where a flat-earth
+/// projection is accurate.
+pub fn survey_grid(polygon: &[Coordinate], options: &SurveyOptions) -> Result<SurveyGrid> {
+    if polygon.len() < 3 {
+        return Err(AeroBaseError::InvalidInput(
+            "Survey polygon needs at least three vertices".to_string(),
+        ));
+    }
+    if options.spacing_nm.is_nan() || options.spacing_nm <= 0.0 || options.run_out_nm < 0.0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Survey line spacing must be positive and run-out non-negative".to_string(),
+        ));
+    }
+
+    let frame = LocalFrame::new(polygon[0]);
+    let heading = options.heading.to_radians();
+    // Along-line and across-line unit vectors
+    let along = (heading.sin(), heading.cos());
+    let across = (heading.cos(), -heading.sin());
+    let dot = |(x, y): (f64, f64), (u, v): (f64, f64)| x * u + y * v;
+
+    let vertices: Vec<(f64, f64)> = polygon
+        .iter()
+        .map(|&c| {
+            let p = frame.project(c);
+            (dot(p, along), dot(p, across))
+        })
+        .collect();
+
+    let min_offset = vertices.iter().map(|v| v.1).fold(f64::INFINITY, f64::min);
+    let max_offset = vertices.iter().map(|v| v.1).fold(f64::NEG_INFINITY, f64::max);
+    let count = ((max_offset - min_offset) / options.spacing_nm).ceil().max(1.0) as usize;
+    let first = (min_offset + max_offset) / 2.0 - (count - 1) as f64 * options.spacing_nm / 2.0;
+
+    let to_coordinate = |t: f64, s: f64| {
+        frame.unproject((t * along.0 + s * across.0, t * along.1 + s * across.1))
+    };
+
+    let mut lines = Vec::new();
+    for i in 0..count {
+        let offset = first + i as f64 * options.spacing_nm;
+
+        let mut crossings: Vec<f64> = Vec::new();
+        for j in 0..vertices.len() {
+            let (t1, s1) = vertices[j];
+            let (t2, s2) = vertices[(j + 1) % vertices.len()];
+            // Half-open test so a line through a vertex counts it once
+            if (s1 <= offset) != (s2 <= offset) {
+                crossings.push(t1 + (offset - s1) / (s2 - s1) * (t2 - t1));
+            }
+        }
+        crossings.sort_by(f64::total_cmp);
+
+        let mut segments: Vec<(f64, f64)> = crossings
+            .chunks_exact(2)
+            .map(|pair| (pair[0] - options.run_out_nm, pair[1] + options.run_out_nm))
+            .collect();
+        // Alternate direction line by line (boustrophedon)
+        if lines.len() % 2 == 1 {
+            segments.reverse();
+            for segment in segments.iter_mut() {
+                *segment = (segment.1, segment.0);
+            }
+        }
+
+        for (from, to) in segments {
+            lines.push(PhotoLine {
+                start: to_coordinate(from, offset),
+                end: to_coordinate(to, offset),
+            });
+        }
+    }
+
+    Ok(SurveyGrid { lines })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 6 nm x 4 nm box: 0.1 degrees of latitude by 0.08 of longitude at 0°
+    fn square() -> Vec<Coordinate> {
+        vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.1, 0.0),
+            Coordinate::new(0.1, 0.08),
+            Coordinate::new(0.0, 0.08),
+        ]
+    }
+
+    #[test]
+    fn test_north_south_lines() {
+        let options = SurveyOptions {
+            heading: 0.0,
+            spacing_nm: 1.0,
+            run_out_nm: 0.5,
+        };
+        let grid = survey_grid(&square(), &options).unwrap();
+
+        // 4.8 nm wide at 1 nm spacing needs five lines
+        assert_eq!(grid.lines.len(), 5);
+        let first = grid.lines[0];
+        assert!(first.end.latitude > first.start.latitude);
+        assert!((first.start.distance_to(&first.end) - 7.0).abs() < 0.01);
+        assert!(grid.lines[1].end.latitude < grid.lines[1].start.latitude);
+
+        // Lines are evenly spread across the box
+        let spacing = grid.lines[0].start.distance_to(&grid.lines[1].end);
+        assert!((spacing - 1.0).abs() < 0.01);
+        assert!(grid.lines.iter().all(|l| l.start.longitude > 0.0 && l.start.longitude < 0.08));
+
+        assert_eq!(grid.waypoints().len(), 10);
+    }
+
+    #[test]
+    fn test_concave_polygon_splits_lines() {
+        // A "U" open to the north: east-west lines through the arms split in two
+        let u = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.1, 0.0),
+            Coordinate::new(0.1, 0.03),
+            Coordinate::new(0.03, 0.03),
+            Coordinate::new(0.03, 0.07),
+            Coordinate::new(0.1, 0.07),
+            Coordinate::new(0.1, 0.1),
+            Coordinate::new(0.0, 0.1),
+        ];
+        let options = SurveyOptions {
+            heading: 90.0,
+            spacing_nm: 1.0,
+            run_out_nm: 0.0,
+        };
+        let grid = survey_grid(&u, &options).unwrap();
+        assert_eq!(grid.lines.len(), 2 + 4 * 2);
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let options = SurveyOptions {
+            heading: 0.0,
+            spacing_nm: 0.0,
+            run_out_nm: 0.0,
+        };
+        assert!(survey_grid(&square(), &options).is_err());
+        assert!(survey_grid(&square()[..2], &SurveyOptions { spacing_nm: 1.0, ..options }).is_err());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::error::{AeroBaseError, Result};
+use crate::flight::navlog::{format_clock, format_frequencies, format_minutes, NavLog, NavLogLeg};
+use chrono::DateTime;
+
+/// Placeholders filled from the whole log, usable anywhere in a template
+pub const LOG_FIELDS: &[&str] = &[
+    "dep", "dest", "altn", "alt", "tas", "var", "dist", "ete", "etd", "date", "fuel", "ldg_fuel",
+];
+
+/// Placeholders filled per leg, usable only inside `{#legs}...{/legs}`
+pub const LEG_FIELDS: &[&str] = &[
+    "leg", "from", "to", "tc", "mc", "leg_dist", "cum_dist", "leg_ete", "cum_ete", "eto", "burn",
+    "rem", "freq",
+];
+
+const LEGS_OPEN: &str = "#legs";
+const LEGS_CLOSE: &str = "/legs";
+
+/// How field values are escaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    None,
+    /// Quote values containing commas, quotes or line breaks
+    Csv,
+}
+
+/// A company nav log form
+///
+/// Templates are plain text with placeholders:
+///
+/// - `{name}` inserts a field, e.g. `{dep}` or `{tc}`;
+/// - `{name:spec}` formats it with a Rust-style spec: fill and alignment,
+///   zero padding, width and precision, e.g. `{tc:03}`, `{leg_dist:>6.1}`,
+///   `{to:<8}` (text is truncated to the precision);
+/// - `{#legs}...{/legs}` repeats its content once per leg;
+/// - `{{` and `}}` are literal braces.
+///
+/// Field names are checked when the template is parsed, so a typo in a
+/// form fails up front rather than printing a blank.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavLogTemplate {
+    segments: Vec<Segment>,
+    escape: Escape,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field { name: String, spec: Spec },
+    Legs(Vec<Segment>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+enum Value {
+    Text(String),
+    /// Value and its default number of decimals
+    Number(f64, usize),
+    Empty,
+}
+
+impl NavLogTemplate {
+    /// Parse a text template
+    pub fn parse(template: &str) -> Result<Self> {
+        Self::parse_with(template, Escape::None)
+    }
+
+    /// Parse a CSV template; field values are quoted where CSV requires it
+    pub fn parse_csv(template: &str) -> Result<Self> {
+        Self::parse_with(template, Escape::Csv)
+    }
+
+    /// One CSV row per leg with a header row
+    pub fn csv() -> Self {
+        Self::parse_csv(
+            "leg,from,to,tc,mc,dist,cum_dist,ete,cum_ete,eto,burn,rem,freq\n\
+             {#legs}{leg},{from},{to},{tc:03},{mc:03},{leg_dist},{cum_dist},{leg_ete},\
+             {cum_ete},{eto},{burn},{rem},{freq}\n{/legs}",
+        )
+        .expect("built-in CSV template is valid")
+    }
+
+    fn parse_with(template: &str, escape: Escape) -> Result<Self> {
+        let mut stack: Vec<Vec<Segment>> = vec![Vec::new()];
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(invalid("unmatched '}'")),
+                '{' => {
+                    let mut tag = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => tag.push(c),
+                            None => return Err(invalid("unclosed '{'")),
+                        }
+                    }
+                    let in_legs = stack.len() > 1;
+                    let current = stack.last_mut().expect("stack is never empty");
+                    if !text.is_empty() {
+                        current.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    match tag.trim() {
+                        LEGS_OPEN if in_legs => return Err(invalid("nested {#legs}")),
+                        LEGS_OPEN => stack.push(Vec::new()),
+                        LEGS_CLOSE if !in_legs => return Err(invalid("{/legs} without {#legs}")),
+                        LEGS_CLOSE => {
+                            let legs = stack.pop().expect("inside a legs section");
+                            stack.last_mut().expect("outer section").push(Segment::Legs(legs));
+                        }
+                        tag => {
+                            let (name, spec) = match tag.split_once(':') {
+                                Some((name, spec)) => (name.trim(), parse_spec(spec)?),
+                                None => (tag, Spec::default()),
+                            };
+                            if LEG_FIELDS.contains(&name) && !in_legs {
+                                return Err(invalid(&format!(
+                                    "{{{}}} is a leg field, use it inside {{#legs}}",
+                                    name
+                                )));
+                            }
+                            if !LEG_FIELDS.contains(&name) && !LOG_FIELDS.contains(&name) {
+                                return Err(invalid(&format!("unknown field {{{}}}", name)));
+                            }
+                            let field = Segment::Field {
+                                name: name.to_string(),
+                                spec,
+                            };
+                            stack.last_mut().expect("stack is never empty").push(field);
+                        }
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+        if stack.len() > 1 {
+            return Err(invalid("{#legs} without {/legs}"));
+        }
+        let mut segments = stack.pop().expect("stack is never empty");
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments, escape })
+    }
+
+    /// Fill the template from `log`
+    pub fn render(&self, log: &NavLog) -> String {
+        let mut out = String::new();
+        self.render_segments(&self.segments, log, None, &mut out);
+        out
+    }
+
+    fn render_segments(
+        &self,
+        segments: &[Segment],
+        log: &NavLog,
+        leg: Option<(usize, &NavLogLeg)>,
+        out: &mut String,
+    ) {
+        for segment in segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Field { name, spec } => {
+                    let value = match leg {
+                        Some((index, leg)) if LEG_FIELDS.contains(&name.as_str()) => {
+                            leg_value(name, index, leg)
+                        }
+                        _ => log_value(name, log),
+                    };
+                    let formatted = spec.apply(value);
+                    match self.escape {
+                        Escape::Csv => out.push_str(&csv_escape(&formatted)),
+                        Escape::None => out.push_str(&formatted),
+                    }
+                }
+                Segment::Legs(body) => {
+                    for (index, leg) in log.legs.iter().enumerate() {
+                        self.render_segments(body, log, Some((index, leg)), out);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl NavLog {
+    /// One CSV row per leg, see `NavLogTemplate::csv`
+    pub fn to_csv(&self) -> String {
+        NavLogTemplate::csv().render(self)
+    }
+}
+
+fn log_value(name: &str, log: &NavLog) -> Value {
+    let plan = &log.plan;
+    let optional = |value: Option<f64>| value.map_or(Value::Empty, |v| Value::Number(v, 1));
+    match name {
+        "dep" => Value::Text(plan.departure.clone()),
+        "dest" => Value::Text(plan.destination.clone()),
+        "altn" => plan.alternate.clone().map_or(Value::Empty, Value::Text),
+        "alt" => Value::Number(plan.cruise_altitude as f64, 0),
+        "tas" => Value::Number(plan.cruise_speed as f64, 0),
+        "var" => Value::Number(log.magnetic_variation, 0),
+        "dist" => Value::Number(log.total_distance, 1),
+        "ete" => Value::Text(format_minutes(log.total_minutes)),
+        "etd" => log.departure_time.map_or(Value::Empty, |t| Value::Text(format_clock(t))),
+        "date" => log
+            .departure_time
+            .and_then(|t| DateTime::from_timestamp(t, 0))
+            .map_or(Value::Empty, |t| Value::Text(t.format("%Y-%m-%d").to_string())),
+        "fuel" => optional(log.departure_fuel),
+        "ldg_fuel" => optional(log.landing_fuel),
+        _ => Value::Empty,
+    }
+}
+
+fn leg_value(name: &str, index: usize, leg: &NavLogLeg) -> Value {
+    let optional = |value: Option<f64>| value.map_or(Value::Empty, |v| Value::Number(v, 1));
+    match name {
+        "leg" => Value::Number((index + 1) as f64, 0),
+        "from" => Value::Text(leg.from.clone()),
+        "to" => Value::Text(leg.to.clone()),
+        "tc" => Value::Number(leg.true_course.round() % 360.0, 0),
+        "mc" => Value::Number(leg.magnetic_course.round() % 360.0, 0),
+        "leg_dist" => Value::Number(leg.distance_nm, 1),
+        "cum_dist" => Value::Number(leg.cumulative_nm, 1),
+        "leg_ete" => Value::Text(format_minutes(leg.ete_minutes)),
+        "cum_ete" => Value::Text(format_minutes(leg.cumulative_minutes)),
+        "eto" => leg.eto.map_or(Value::Empty, |t| Value::Text(format_clock(t))),
+        "burn" => optional(leg.fuel_burn),
+        "rem" => optional(leg.fuel_remaining),
+        "freq" => Value::Text(format_frequencies(&leg.frequencies)),
+        _ => Value::Empty,
+    }
+}
+
+/// `[[fill]align][0][width][.precision]`
+fn parse_spec(spec: &str) -> Result<Spec> {
+    let bad = || invalid(&format!("bad format spec '{}'", spec));
+    let align = |c: char| match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    };
+    let chars: Vec<char> = spec.chars().collect();
+    let mut result = Spec::default();
+    let mut i = 0;
+    if let Some(a) = chars.get(1).copied().and_then(align) {
+        result.fill = Some(chars[0]);
+        result.align = Some(a);
+        i = 2;
+    } else if let Some(a) = chars.first().copied().and_then(align) {
+        result.align = Some(a);
+        i = 1;
+    }
+    if chars.get(i) == Some(&'0') {
+        result.zero = true;
+        i += 1;
+    }
+    let digits = |from: usize| chars[from..].iter().take_while(|c| c.is_ascii_digit()).count();
+    let width = digits(i);
+    if width > 0 {
+        result.width = chars[i..i + width].iter().collect::<String>().parse().map_err(|_| bad())?;
+        i += width;
+    }
+    if chars.get(i) == Some(&'.') {
+        let precision = digits(i + 1);
+        if precision == 0 {
+            return Err(bad());
+        }
+        let value: String = chars[i + 1..i + 1 + precision].iter().collect();
+        result.precision = Some(value.parse().map_err(|_| bad())?);
+        i += 1 + precision;
+    }
+    if i != chars.len() {
+        return Err(bad());
+    }
+    Ok(result)
+}
+
+impl Spec {
+    fn apply(&self, value: Value) -> String {
+        let (text, numeric) = match value {
+            Value::Empty => return " ".repeat(self.width),
+            Value::Text(text) => match self.precision {
+                Some(max) => (text.chars().take(max).collect(), false),
+                None => (text, false),
+            },
+            Value::Number(v, decimals) => {
+                (format!("{:.*}", self.precision.unwrap_or(decimals), v), true)
+            }
+        };
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let pad = self.width - len;
+        if self.zero && self.align.is_none() && numeric {
+            return match text.strip_prefix('-') {
+                Some(digits) => format!("-{}{}", "0".repeat(pad), digits),
+                None => format!("{}{}", "0".repeat(pad), text),
+            };
+        }
+        let fill = self.fill.unwrap_or(' ').to_string();
+        let align = self.align.unwrap_or(if numeric { Align::Right } else { Align::Left });
+        match align {
+            Align::Left => text + &fill.repeat(pad),
+            Align::Right => fill.repeat(pad) + &text,
+            Align::Center => fill.repeat(pad / 2) + &text + &fill.repeat(pad - pad / 2),
+        }
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn invalid(reason: &str) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid nav log template: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlan;
+    use crate::repository::TuningFrequency;
+
+    fn log() -> NavLog {
+        let leg = |from: &str, to: &str, course: f64, minutes: i32, cumulative: i32| NavLogLeg {
+            from: from.to_string(),
+            to: to.to_string(),
+            true_course: course,
+            magnetic_course: course + 5.0,
+            distance_nm: 60.04,
+            cumulative_nm: cumulative as f64 * 2.0,
+            ete_minutes: minutes,
+            cumulative_minutes: cumulative,
+            eto: Some(1_700_000_000 + cumulative as i64 * 60),
+            fuel_burn: Some(6.0),
+            fuel_remaining: Some(50.0 - cumulative as f64 / 5.0),
+            frequencies: vec![],
+        };
+        let mut last = leg("MID", "BBBB", 89.6, 30, 60);
+        last.frequencies = vec![
+            TuningFrequency::new("BBBB", "TWR", 118.1),
+            TuningFrequency::new("BBBB", "GND", 121.9),
+        ];
+        NavLog {
+            plan: FlightPlan {
+                departure: "AAAA".to_string(),
+                destination: "BBBB".to_string(),
+                alternate: None,
+                cruise_altitude: 6000,
+                cruise_speed: 120,
+                route: vec![],
+                ..Default::default()
+            },
+            departure_time: Some(1_700_000_000),
+            magnetic_variation: -5.0,
+            legs: vec![leg("AAAA", "MID", 7.2, 30, 30), last],
+            total_distance: 120.08,
+            total_minutes: 60,
+            departure_fuel: Some(50.0),
+            landing_fuel: Some(38.0),
+            airport_frequencies: vec![],
+        }
+    }
+
+    #[test]
+    fn test_render_company_form() {
+        // Sign flags are not part of the spec syntax
+        let template = NavLogTemplate::parse(
+            "{dep}-{dest} {alt}ft ETD {etd} {date} VAR {var:+}\n\
+             {#legs}{leg:>2}. {to:.<8} {tc:03} {leg_dist:5.0} {eto} {rem:>6.1}\n{/legs}\
+             TOTAL {dist:.0} NM {ete} {{landing {ldg_fuel:.0}}}",
+        );
+        assert!(template.is_err());
+
+        let template = NavLogTemplate::parse(
+            "{dep}-{dest} {alt}ft ETD {etd} {date} VAR {var:03}\n\
+             {#legs}{leg:>2}. {to:.<8} {tc:03} {leg_dist:5.0} {eto} {rem:>6.1}\n{/legs}\
+             TOTAL {dist:.0} NM {ete} {{landing {ldg_fuel:.0}}}",
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&log()),
+            "AAAA-BBBB 6000ft ETD 2213 2023-11-14 VAR -05\n \
+             1. MID..... 007    60 2243   44.0\n \
+             2. BBBB.... 090    60 2313   38.0\n\
+             TOTAL 120 NM 1:00 {landing 38}"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = log().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("leg,from,to,tc,mc"));
+        assert_eq!(lines[1], "1,AAAA,MID,007,012,60.0,60.0,0:30,0:30,2243,6.0,44.0,");
+        assert!(lines[2].ends_with(",\"TWR 118.10, GND 121.90\""));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for template in [
+            "{tc}",
+            "{#legs}{#legs}{/legs}{/legs}",
+            "{#legs}{to}",
+            "{/legs}",
+            "{nope}",
+            "{dep",
+            "dep}",
+            "{dist:.}",
+            "{dist:x5}",
+        ] {
+            assert!(NavLogTemplate::parse(template).is_err(), "{}", template);
+        }
+        assert!(NavLogTemplate::parse("{dep:^10} {{x}}").is_ok());
+    }
+}
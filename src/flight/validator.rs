@@ -1,43 +1,328 @@
+use crate::db::query::{lookup_airport, normalize_code, LookupResult, AIRPORT_ID_BY_CODE};
 use crate::db::Database;
 use crate::error::{AeroBaseError, Result};
-use crate::flight::FlightPlan;
+use crate::flight::{levels, FlightPlan};
+use crate::freshness::{self, FreshnessPolicy, FreshnessStatus};
+use crate::models::uas_zone::UasRestriction;
+use crate::models::Coordinate;
+use crate::navdata::cycle::{self, ExpiredAction, ExpiryPolicy};
+use crate::spatial::{geometry, query};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-/// Validate a flight plan
-pub fn validate_plan(db: &Database, plan: &FlightPlan) -> Result<bool> {
-    let conn = db.get_conn()?;
+/// A non-fatal validation finding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationWarning {
+    /// Stable identifier of the rule that produced the warning
+    pub rule: String,
+    pub message: String,
+}
 
-    // Validate departure airport exists
-    let dep_exists: bool = conn
-        .query_row(
-            "SELECT EXISTS(SELECT 1 FROM airports WHERE icao = ?1)",
-            [&plan.departure],
-            |row| row.get(0),
-        )?;
-
-    if !dep_exists {
+/// Result of a plan validation that passed, with any warnings raised
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub warnings: Vec<ValidationWarning>,
+}
+
+impl ValidationReport {
+    /// Add a warning for `rule`
+    pub fn warn(&mut self, rule: &str, message: impl Into<String>) {
+        self.warnings.push(ValidationWarning {
+            rule: rule.to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+/// Validate a plan and apply the navdata expiry policy, with hazards and
+/// navdata validity judged at `now`
+pub fn validate_plan_report(
+    db: &Database,
+    plan: &FlightPlan,
+    expiry: &ExpiryPolicy,
+    freshness: &FreshnessPolicy,
+    now: DateTime<Utc>,
+) -> Result<ValidationReport> {
+    validate_plan(db, plan)?;
+
+    let mut report = ValidationReport::default();
+    levels::check_cruise_levels(db, plan, &mut report)?;
+    check_hazards(db, &plan_positions(db, plan)?, plan.cruise_altitude, now.timestamp(), &mut report)?;
+    check_navdata_expiry(db, expiry, now.date_naive(), &mut report)?;
+    check_briefing_freshness(db, freshness, now.timestamp(), &mut report)?;
+    Ok(report)
+}
+
+/// Highest cruise altitude (feet) accepted for a low-altitude UAS plan
+pub const LOW_ALTITUDE_CEILING: i32 = 1000;
+
+/// Spacing of the points checked against UAS zones along each leg
+const UAS_SAMPLE_SPACING_NM: f64 = 0.05;
+
+/// Validate a low-altitude (UAS) plan against UAS geographical zones
+///
+/// Unlike manned plans, departure and destination may be the same site.
+/// Entering a prohibited zone at the planned altitude is an error; zones
+/// requiring authorisation or carrying conditions raise warnings.
+pub fn validate_uas_plan_report(
+    db: &Database,
+    plan: &FlightPlan,
+    expiry: &ExpiryPolicy,
+    freshness: &FreshnessPolicy,
+    now: DateTime<Utc>,
+) -> Result<ValidationReport> {
+    if plan.cruise_altitude < 0 || plan.cruise_altitude > LOW_ALTITUDE_CEILING {
         return Err(AeroBaseError::InvalidInput(format!(
-            "Departure airport {} not found",
-            plan.departure
+            "UAS plan altitude must be between 0 and {} feet",
+            LOW_ALTITUDE_CEILING
         )));
     }
+    if plan.cruise_speed <= 0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Cruise speed must be positive".to_string(),
+        ));
+    }
 
-    // Validate destination airport exists
-    let dest_exists: bool = conn
-        .query_row(
-            "SELECT EXISTS(SELECT 1 FROM airports WHERE icao = ?1)",
-            [&plan.destination],
-            |row| row.get(0),
-        )?;
-
-    if !dest_exists {
-        return Err(AeroBaseError::InvalidInput(format!(
-            "Destination airport {} not found",
-            plan.destination
+    let positions = plan_positions(db, plan)?;
+    let mut report = ValidationReport::default();
+    check_uas_zones(db, &positions, plan.cruise_altitude, &mut report)?;
+    check_hazards(db, &positions, plan.cruise_altitude, now.timestamp(), &mut report)?;
+    check_navdata_expiry(db, expiry, now.date_naive(), &mut report)?;
+    check_briefing_freshness(db, freshness, now.timestamp(), &mut report)?;
+    Ok(report)
+}
+
+/// Spacing of the points checked against RVSM airspace along each leg
+const RVSM_SAMPLE_SPACING_NM: f64 = 1.0;
+
+/// Reject a cruise level inside RVSM airspace for an aircraft without RVSM
+/// approval
+///
+/// The error names the airspace and suggests the highest level below
+/// FL290 for the direction of flight, from the airspace's level scheme.
+pub fn validate_rvsm(db: &Database, plan: &FlightPlan, rvsm_approved: bool) -> Result<()> {
+    if rvsm_approved {
+        return Ok(());
+    }
+    let positions = plan_positions(db, plan)?;
+    let samples = sample_positions(&positions, RVSM_SAMPLE_SPACING_NM);
+    let fallback = levels::default_scheme(db, plan)?;
+    let (min, max) = bounds(&samples);
+
+    for (airspace, boundary) in query::find_airspaces_in(db, min, max)? {
+        if !airspace.requires_rvsm(plan.cruise_altitude)
+            || !samples.iter().any(|p| geometry::point_in_polygon(*p, &boundary))
+        {
+            continue;
+        }
+        let course = positions[0].bearing_to(&positions[positions.len() - 1]);
+        let scheme = airspace.level_scheme.unwrap_or(fallback);
+        return Err(AeroBaseError::FlightPlanning(format!(
+            "Cruise altitude {} ft is RVSM airspace in {} ({}); aircraft is not RVSM approved, \
+             suggest {}",
+            plan.cruise_altitude,
+            airspace.name,
+            airspace.id,
+            scheme.label(scheme.rvsm_capped_level(course))
         )));
     }
+    Ok(())
+}
+
+/// Departure, route and destination positions, failing on unknown references
+pub(crate) fn plan_positions(db: &Database, plan: &FlightPlan) -> Result<Vec<Coordinate>> {
+    let conn = db.get_conn()?;
+    let lookup = |sql: &str, id: &str, kind: &str| {
+        conn.query_row(sql, [id], |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)))
+            .map_err(|_| AeroBaseError::InvalidInput(format!("{} {} not found", kind, id)))
+    };
+    let airport = format!("SELECT latitude, longitude FROM airports WHERE id = {}", AIRPORT_ID_BY_CODE);
+    let waypoint = "SELECT latitude, longitude FROM waypoints WHERE id = ?1";
+
+    let mut positions = vec![lookup(&airport, &normalize_code(&plan.departure), "Departure airport")?];
+    for id in &plan.route {
+        positions.push(lookup(waypoint, id, "Waypoint")?);
+    }
+    positions.push(lookup(&airport, &normalize_code(&plan.destination), "Destination airport")?);
+    Ok(positions)
+}
+
+/// Points at most `spacing_nm` apart along the legs joining `positions`
+fn sample_positions(positions: &[Coordinate], spacing_nm: f64) -> Vec<Coordinate> {
+    let mut samples = vec![positions[0]];
+    for leg in positions.windows(2) {
+        let distance = leg[0].distance_to(&leg[1]);
+        let bearing = leg[0].bearing_to(&leg[1]);
+        let steps = (distance / spacing_nm).ceil().max(1.0) as usize;
+        samples.extend((1..=steps).map(|i| {
+            geometry::destination_point(leg[0], distance * i as f64 / steps as f64, bearing)
+        }));
+    }
+    samples
+}
+
+pub(crate) fn bounds(samples: &[Coordinate]) -> (Coordinate, Coordinate) {
+    let min = Coordinate::new(
+        samples.iter().map(|c| c.latitude).fold(f64::INFINITY, f64::min),
+        samples.iter().map(|c| c.longitude).fold(f64::INFINITY, f64::min),
+    );
+    let max = Coordinate::new(
+        samples.iter().map(|c| c.latitude).fold(f64::NEG_INFINITY, f64::max),
+        samples.iter().map(|c| c.longitude).fold(f64::NEG_INFINITY, f64::max),
+    );
+    (min, max)
+}
+
+fn check_uas_zones(
+    db: &Database,
+    positions: &[Coordinate],
+    altitude: i32,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let samples = sample_positions(positions, UAS_SAMPLE_SPACING_NM);
+    let (min, max) = bounds(&samples);
+
+    for zone in query::find_uas_zones_in(db, min, max)? {
+        if !zone.applies_at(altitude) || !samples.iter().any(|p| zone.contains(*p)) {
+            continue;
+        }
+        let message = format!(
+            "Route enters UAS zone {} ({}, {}-{} ft {}){}",
+            zone.id,
+            zone.name,
+            zone.lower_limit,
+            zone.upper_limit,
+            zone.vertical_reference,
+            zone.message.as_deref().map(|m| format!(": {}", m)).unwrap_or_default()
+        );
+        match zone.restriction {
+            UasRestriction::Prohibited => return Err(AeroBaseError::FlightPlanning(message)),
+            UasRestriction::ReqAuthorisation => report.warn("uas_zone_authorisation", message),
+            UasRestriction::Conditional => report.warn("uas_zone_conditional", message),
+            UasRestriction::NoRestriction => {}
+        }
+    }
+    Ok(())
+}
+
+/// Spacing of the points checked against dynamic hazards along each leg
+const HAZARD_SAMPLE_SPACING_NM: f64 = 0.5;
+
+/// Entering an active TFR or ash area at the planned altitude is an error;
+/// other active hazards, and any that become active later, raise warnings
+fn check_hazards(
+    db: &Database,
+    positions: &[Coordinate],
+    altitude: i32,
+    now: i64,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    let samples = sample_positions(positions, HAZARD_SAMPLE_SPACING_NM);
+    let (min, max) = bounds(&samples);
+
+    for hazard in query::find_hazards_in(db, min, max, now, i64::MAX)? {
+        if !hazard.applies_at(altitude) || !samples.iter().any(|p| hazard.contains(*p)) {
+            continue;
+        }
+        let message = format!(
+            "Route enters {} {} ({}){}",
+            hazard.kind.as_str(),
+            hazard.id,
+            hazard.name,
+            hazard.message.as_deref().map(|m| format!(": {}", m)).unwrap_or_default()
+        );
+        if !hazard.is_active_at(now) {
+            report.warn("hazard_upcoming", format!("{}, active from {}", message, hazard.valid_from));
+        } else if hazard.kind.is_restrictive() {
+            return Err(AeroBaseError::FlightPlanning(message));
+        } else {
+            report.warn("hazard", message);
+        }
+    }
+    Ok(())
+}
+
+fn check_navdata_expiry(
+    db: &Database,
+    policy: &ExpiryPolicy,
+    today: chrono::NaiveDate,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    if policy.on_expired == ExpiredAction::Ignore {
+        return Ok(());
+    }
+
+    let current = cycle::current_cycle(db)?;
+    let Some(installed) = current.as_ref() else {
+        return Ok(());
+    };
+    let message = format!(
+        "Navdata cycle {} expired on {}",
+        installed.ident, installed.expires
+    );
+
+    match policy.action(current.as_ref(), today) {
+        ExpiredAction::Ignore => {}
+        ExpiredAction::Warn => report.warn("navdata_expired", message),
+        ExpiredAction::Error => return Err(AeroBaseError::FlightPlanning(message)),
+    }
+    Ok(())
+}
 
-    // Validate departure != destination
-    if plan.departure == plan.destination {
+/// Warn about weather, hazard, winds or NOTAM data older than `policy` allows
+///
+/// Datasets the device has never received are not reported: not every
+/// installation subscribes to every feed.
+fn check_briefing_freshness(
+    db: &Database,
+    policy: &FreshnessPolicy,
+    now: i64,
+    report: &mut ValidationReport,
+) -> Result<()> {
+    for data in freshness::data_freshness(db, policy, now)? {
+        if data.status == FreshnessStatus::Stale {
+            report.warn(
+                "stale_briefing",
+                format!(
+                    "Briefing data ({}) is {} old, older than {} h",
+                    data.dataset.as_str().to_lowercase(),
+                    data.age_text().unwrap_or_default(),
+                    data.max_age_secs / 3600
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `"Departure airport ZSPX not found; did you mean ZSPD (Pudong)?"`
+fn airport_not_found(role: &str, code: &str, lookup: &LookupResult) -> AeroBaseError {
+    let mut message = format!("{} airport {} not found", role, code);
+    if let Some(hint) = lookup.did_you_mean() {
+        message.push_str("; ");
+        message.push_str(&hint);
+    }
+    AeroBaseError::InvalidInput(message)
+}
+
+/// Validate a flight plan
+pub fn validate_plan(db: &Database, plan: &FlightPlan) -> Result<bool> {
+    let conn = db.get_conn()?;
+
+    // Validate departure airport exists
+    let departure_id = match lookup_airport(&conn, &plan.departure)? {
+        LookupResult::Found(airport) => airport.id,
+        missing => return Err(airport_not_found("Departure", &plan.departure, &missing)),
+    };
+
+    // Validate destination airport exists
+    let destination_id = match lookup_airport(&conn, &plan.destination)? {
+        LookupResult::Found(airport) => airport.id,
+        missing => return Err(airport_not_found("Destination", &plan.destination, &missing)),
+    };
+
+    // Validate departure != destination, however each is spelled
+    if departure_id == destination_id {
         return Err(AeroBaseError::InvalidInput(
             "Departure and destination cannot be the same".to_string(),
         ));
@@ -45,18 +330,9 @@ pub fn validate_plan(db: &Database, plan: &FlightPlan) -> Result<bool> {
 
     // Validate alternate airport if specified
     if let Some(ref alternate) = plan.alternate {
-        let alt_exists: bool = conn
-            .query_row(
-                "SELECT EXISTS(SELECT 1 FROM airports WHERE icao = ?1)",
-                [alternate],
-                |row| row.get(0),
-            )?;
-
-        if !alt_exists {
-            return Err(AeroBaseError::InvalidInput(format!(
-                "Alternate airport {} not found",
-                alternate
-            )));
+        let lookup = lookup_airport(&conn, alternate)?;
+        if lookup.airport().is_none() {
+            return Err(airport_not_found("Alternate", alternate, &lookup));
         }
     }
 
@@ -101,12 +377,13 @@ mod tests {
     use std::sync::Arc;
     use tempfile::NamedTempFile;
 
-    fn setup_test_db() -> Arc<Database> {
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
         let temp_file = NamedTempFile::new().unwrap();
         let config = Config {
             db_path: temp_file.path().to_path_buf(),
             enable_wal: false,
             pool_size: 1,
+            ..Default::default()
         };
 
         let db = Arc::new(Database::new(&config).unwrap());
@@ -126,12 +403,12 @@ mod tests {
         )
         .unwrap();
 
-        db
+        (db, temp_file)
     }
 
     #[test]
     fn test_valid_plan() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
 
         let plan = FlightPlan {
             departure: "ZBAA".to_string(),
@@ -140,6 +417,7 @@ mod tests {
             cruise_altitude: 35000,
             cruise_speed: 450,
             route: vec![],
+            ..Default::default()
         };
 
         let result = validate_plan(&db, &plan);
@@ -148,7 +426,7 @@ mod tests {
 
     #[test]
     fn test_invalid_departure() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
 
         let plan = FlightPlan {
             departure: "XXXX".to_string(),
@@ -157,15 +435,38 @@ mod tests {
             cruise_altitude: 35000,
             cruise_speed: 450,
             route: vec![],
+            ..Default::default()
         };
 
         let result = validate_plan(&db, &plan);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_missing_airport_suggestions() {
+        let (db, _temp_file) = setup_test_db();
+
+        let plan = FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSX".to_string(),
+            alternate: None,
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: vec![],
+            ..Default::default()
+        };
+        let Err(AeroBaseError::InvalidInput(message)) = validate_plan(&db, &plan) else {
+            panic!("expected an invalid destination");
+        };
+        assert_eq!(
+            message,
+            "Destination airport ZSSX not found; did you mean ZSSS (Shanghai)?"
+        );
+    }
+
     #[test]
     fn test_same_departure_destination() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
 
         let plan = FlightPlan {
             departure: "ZBAA".to_string(),
@@ -174,15 +475,271 @@ mod tests {
             cruise_altitude: 35000,
             cruise_speed: 450,
             route: vec![],
+            ..Default::default()
         };
 
         let result = validate_plan(&db, &plan);
         assert!(result.is_err());
+
+        // Still the same airport when spelled differently or by alias
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO airport_aliases (id, airport_id, code, created_at)
+                 VALUES ('AL1', 'AP1', 'ZBBB', 0)",
+                [],
+            )
+            .unwrap();
+        let plan = FlightPlan {
+            departure: "zbaa".to_string(),
+            destination: "ZBBB".to_string(),
+            ..plan
+        };
+        assert!(validate_plan(&db, &plan).is_err());
+
+        let plan = FlightPlan {
+            destination: " zsss".to_string(),
+            ..plan
+        };
+        assert!(validate_plan(&db, &plan).is_ok());
+    }
+
+    #[test]
+    fn test_expired_navdata_policy() {
+        let (db, _temp_file) = setup_test_db();
+        cycle::install_cycle(&db, &cycle::AiracCycle::from_ident("2401").unwrap()).unwrap();
+
+        let mut policy = ExpiryPolicy {
+            expiring_soon_days: 7,
+            grace_days: 2,
+            on_expired: ExpiredAction::Error,
+        };
+        let in_grace = chrono::NaiveDate::from_ymd_opt(2024, 2, 23).unwrap();
+        let after_grace = chrono::NaiveDate::from_ymd_opt(2024, 2, 24).unwrap();
+
+        let mut report = ValidationReport::default();
+        check_navdata_expiry(&db, &policy, in_grace, &mut report).unwrap();
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].rule, "navdata_expired");
+
+        assert!(matches!(
+            check_navdata_expiry(&db, &policy, after_grace, &mut ValidationReport::default()),
+            Err(AeroBaseError::FlightPlanning(_))
+        ));
+
+        policy.on_expired = ExpiredAction::Ignore;
+        let mut report = ValidationReport::default();
+        check_navdata_expiry(&db, &policy, after_grace, &mut report).unwrap();
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_stale_briefing() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO tafs (id, station, issued_at, valid_from, valid_to, raw, created_at)
+                    VALUES ('T1', 'ZBAA', 0, 0, 86400, 'TAF ZBAA', 0)",
+                [],
+            )
+            .unwrap();
+        let policy = FreshnessPolicy::default();
+
+        let mut report = ValidationReport::default();
+        check_briefing_freshness(&db, &policy, 5 * 3600, &mut report).unwrap();
+        assert!(report.warnings.is_empty());
+
+        // Winds, hazards and NOTAMs were never received, so only weather is reported
+        check_briefing_freshness(&db, &policy, 7 * 3600, &mut report).unwrap();
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].rule, "stale_briefing");
+        assert_eq!(report.warnings[0].message, "Briefing data (weather) is 7 h old, older than 6 h");
+
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO sync_metadata (table_name, last_sync) VALUES ('notams', 0)",
+                [],
+            )
+            .unwrap();
+        let mut report = ValidationReport::default();
+        check_briefing_freshness(&db, &policy, 7 * 3600, &mut report).unwrap();
+        let messages: Vec<&str> = report.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Briefing data (weather) is 7 h old, older than 6 h",
+                "Briefing data (notams) is 7 h old, older than 6 h"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uas_zones() {
+        let (db, _temp_file) = setup_test_db();
+        crate::navdata::ed269::import(&db, crate::navdata::ed269::tests::SAMPLE).unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('S1', 'SITE', 'Field site', 39.0, 116.9, 0),
+                    ('S2', 'CTRS', 'Inside CTR', 40.1, 116.1, 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                    VALUES ('EAST', 'EAST', 39.0, 117.1, 'GPS', 0);",
+            )
+            .unwrap();
+        let expiry = ExpiryPolicy::default();
+
+        // Out and back across the stadium rings at 300 ft: inner ring only
+        let mut plan = FlightPlan {
+            departure: "SITE".to_string(),
+            destination: "SITE".to_string(),
+            alternate: None,
+            cruise_altitude: 300,
+            cruise_speed: 30,
+            route: vec!["EAST".to_string()],
+            ..Default::default()
+        };
+        let report = validate_uas_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now()).unwrap();
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].rule, "uas_zone_authorisation");
+        assert!(report.warnings[0].message.contains("ZONE-B-1"));
+
+        plan.route.clear();
+        assert!(validate_uas_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now()).unwrap().warnings.is_empty());
+
+        plan.destination = "CTRS".to_string();
+        assert!(matches!(
+            validate_uas_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now()),
+            Err(AeroBaseError::FlightPlanning(_))
+        ));
+
+        plan.cruise_altitude = 5000;
+        assert!(matches!(
+            validate_uas_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now()),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_rvsm() {
+        let (db, _temp_file) = setup_test_db();
+        // RVSM FIR covering Shanghai but not Beijing
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airspaces (id, name, type, rvsm, created_at)
+                    VALUES ('ZSHA', 'Shanghai FIR', 'FIR', 1, 0);
+                 INSERT INTO airspace_boundaries (id, airspace_id, latitude, longitude, sequence) VALUES
+                    ('B1', 'ZSHA', 30.0, 118.0, 0),
+                    ('B2', 'ZSHA', 35.0, 118.0, 1),
+                    ('B3', 'ZSHA', 35.0, 123.0, 2),
+                    ('B4', 'ZSHA', 30.0, 123.0, 3);",
+            )
+            .unwrap();
+
+        let mut plan = FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: None,
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: vec![],
+            ..Default::default()
+        };
+        assert!(validate_rvsm(&db, &plan, true).is_ok());
+
+        // Southeast-bound: eastbound levels, so FL270
+        match validate_rvsm(&db, &plan, false) {
+            Err(AeroBaseError::FlightPlanning(message)) => {
+                assert!(message.contains("Shanghai FIR"));
+                assert!(message.contains("suggest FL270"));
+            }
+            other => panic!("expected an RVSM error, got {:?}", other),
+        }
+
+        plan.cruise_altitude = 27000;
+        assert!(validate_rvsm(&db, &plan, false).is_ok());
+
+        // A metric FIR suggests a metric level
+        db.get_conn()
+            .unwrap()
+            .execute("UPDATE airspaces SET level_scheme = 'METRIC'", [])
+            .unwrap();
+        plan.cruise_altitude = 35100;
+        let message = validate_rvsm(&db, &plan, false).unwrap_err().to_string();
+        assert!(message.contains("suggest 8100 m (FL266)"));
+    }
+
+    #[test]
+    fn test_hazards() {
+        let (db, _temp_file) = setup_test_db();
+        let now = chrono::Utc::now().timestamp();
+        let insert = |id: &str, kind: &str, upper: i32, from: i64, to: i64| {
+            let conn = db.get_conn().unwrap();
+            conn.execute(
+                "INSERT INTO hazards (id, kind, name, upper_limit, valid_from, valid_to, min_latitude,
+                     max_latitude, min_longitude, max_longitude, created_at)
+                 VALUES (?1, ?2, ?1, ?3, ?4, ?5, 35.0, 36.0, 118.0, 120.0, ?6)",
+                rusqlite::params![id, kind, upper, from, to, now],
+            )
+            .unwrap();
+            let corners = [(35.0, 118.0), (36.0, 118.0), (36.0, 120.0), (35.0, 120.0)];
+            for (i, (lat, lon)) in corners.iter().enumerate() {
+                conn.execute(
+                    "INSERT INTO hazard_boundaries (id, hazard_id, latitude, longitude, sequence)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![format!("{}-{}", id, i), id, lat, lon, i as i64],
+                )
+                .unwrap();
+            }
+        };
+        insert("CZ1", "CONFLICT_ZONE", 60000, now - 3600, now + 3600);
+        insert("TFR1", "TFR", 18000, now + 7200, now + 10800);
+        insert("ASH1", "VOLCANIC_ASH", 20000, now - 3600, now + 3600);
+        insert("TFR0", "TFR", 60000, now - 7200, now - 3600);
+
+        let plan = FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: None,
+            cruise_altitude: 15000,
+            cruise_speed: 450,
+            route: vec![],
+            ..Default::default()
+        };
+        let expiry = ExpiryPolicy::default();
+        match validate_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now()) {
+            Err(AeroBaseError::FlightPlanning(message)) => assert!(message.contains("VOLCANIC_ASH ASH1")),
+            other => panic!("expected an ash error, got {:?}", other),
+        }
+
+        // Above the ash and the upcoming TFR
+        let plan = FlightPlan {
+            cruise_altitude: 25000,
+            ..plan
+        };
+        let report = validate_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now()).unwrap();
+        let rules: Vec<&str> = report.warnings.iter().map(|w| w.rule.as_str()).collect();
+        assert_eq!(rules, vec!["hazard"]);
+        assert!(report.warnings[0].message.contains("CZ1"));
+
+        let plan = FlightPlan {
+            cruise_altitude: 17000,
+            ..plan
+        };
+        let report = validate_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now());
+        assert!(report.is_err());
+        db.get_conn().unwrap().execute("DELETE FROM hazards WHERE id = 'ASH1'", []).unwrap();
+        let report = validate_plan_report(&db, &plan, &expiry, &FreshnessPolicy::default(), Utc::now()).unwrap();
+        let rules: Vec<&str> = report.warnings.iter().map(|w| w.rule.as_str()).collect();
+        assert_eq!(rules, vec!["hazard", "hazard_upcoming"]);
     }
 
     #[test]
     fn test_invalid_altitude() {
-        let db = setup_test_db();
+        let (db, _temp_file) = setup_test_db();
 
         let plan = FlightPlan {
             departure: "ZBAA".to_string(),
@@ -191,6 +748,7 @@ mod tests {
             cruise_altitude: 100, // Too low
             cruise_speed: 450,
             route: vec![],
+            ..Default::default()
         };
 
         let result = validate_plan(&db, &plan);
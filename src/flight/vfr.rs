@@ -0,0 +1,136 @@
+use crate::error::{AeroBaseError, Result};
+use crate::models::hold::TurnDirection;
+use crate::models::runway::Runway;
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+use serde::{Deserialize, Serialize};
+
+const FEET_PER_NM: f64 = 6076.12;
+
+/// Traffic pattern shape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternOptions {
+    /// Pattern altitude above the runway threshold
+    pub altitude_agl: i32,
+    /// Direction of the turns; left-hand unless published otherwise
+    pub direction: TurnDirection,
+    /// Lateral spacing between the runway and the downwind leg
+    pub downwind_offset_nm: f64,
+    /// Length of the final leg
+    pub final_nm: f64,
+    /// Magnetic variation at the airport (degrees, east positive), turning
+    /// a runway known only by designator to true
+    pub magnetic_variation: Option<f64>,
+}
+
+impl Default for PatternOptions {
+    fn default() -> Self {
+        Self {
+            altitude_agl: 1000,
+            direction: TurnDirection::Left,
+            downwind_offset_nm: 0.75,
+            final_nm: 1.0,
+            magnetic_variation: None,
+        }
+    }
+}
+
+/// Pattern legs as polylines ready for the map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficPattern {
+    pub runway_ident: String,
+    pub direction: TurnDirection,
+    /// Pattern altitude in feet MSL
+    pub altitude: i32,
+    pub crosswind: Vec<Coordinate>,
+    pub downwind: Vec<Coordinate>,
+    pub base: Vec<Coordinate>,
+    pub final_approach: Vec<Coordinate>,
+}
+
+/// Generate the standard rectangular pattern for landing on `runway`
+///
+/// The runway end needs a threshold position; the heading falls back to the
+/// runway designator corrected by `magnetic_variation`, and a missing
+/// length is treated as zero.
+pub fn traffic_pattern(runway: &Runway, options: &PatternOptions) -> Result<TrafficPattern> {
+    let threshold = runway.threshold.ok_or_else(|| {
+        AeroBaseError::InvalidInput(format!("Runway {} has no threshold position", runway.ident))
+    })?;
+    let heading = runway.effective_heading(options.magnetic_variation).ok_or_else(|| {
+        AeroBaseError::InvalidInput(format!("Runway {} has no heading", runway.ident))
+    })?;
+    if options.downwind_offset_nm <= 0.0 || options.final_nm <= 0.0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Pattern offset and final length must be positive".to_string(),
+        ));
+    }
+
+    let length_nm = runway.length_ft.unwrap_or(0) as f64 / FEET_PER_NM;
+    let side = (heading + 90.0 * options.direction.sign()).rem_euclid(360.0);
+    let reciprocal = (heading + 180.0).rem_euclid(360.0);
+
+    let departure_end = geometry::destination_point(threshold, length_nm, heading);
+    let final_start = geometry::destination_point(threshold, options.final_nm, reciprocal);
+    let crosswind_end = geometry::destination_point(departure_end, options.downwind_offset_nm, side);
+    let base_start = geometry::destination_point(final_start, options.downwind_offset_nm, side);
+
+    Ok(TrafficPattern {
+        runway_ident: runway.ident.clone(),
+        direction: options.direction,
+        altitude: runway.elevation.unwrap_or(0) + options.altitude_agl,
+        crosswind: vec![departure_end, crosswind_end],
+        downwind: vec![crosswind_end, base_start],
+        base: vec![base_start, final_start],
+        final_approach: vec![final_start, threshold],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runway() -> Runway {
+        Runway {
+            heading: Some(360.0),
+            length_ft: Some(6076),
+            threshold: Some(Coordinate::new(0.0, 0.0)),
+            elevation: Some(500),
+            ..Runway::new("R1".to_string(), "AP1".to_string(), "36".to_string())
+        }
+    }
+
+    #[test]
+    fn test_left_pattern() {
+        let pattern = traffic_pattern(&runway(), &PatternOptions::default()).unwrap();
+        assert_eq!(pattern.altitude, 1500);
+
+        // Left traffic for runway 36 flies downwind west of the runway, southbound
+        let downwind_start = pattern.downwind[0];
+        let downwind_end = pattern.downwind[1];
+        assert!((downwind_start.longitude + 0.0125).abs() < 0.001);
+        assert!((downwind_start.latitude - 1.0 / 60.0).abs() < 0.001);
+        assert!((downwind_end.latitude + 1.0 / 60.0).abs() < 0.001);
+
+        assert_eq!(pattern.final_approach[1], Coordinate::new(0.0, 0.0));
+        assert_eq!(pattern.base[1], pattern.final_approach[0]);
+    }
+
+    #[test]
+    fn test_right_pattern_and_fallbacks() {
+        let mut runway = runway();
+        runway.heading = None;
+        let mut options = PatternOptions {
+            direction: TurnDirection::Right,
+            ..Default::default()
+        };
+        // The designator alone is magnetic
+        assert!(traffic_pattern(&runway, &options).is_err());
+        options.magnetic_variation = Some(0.0);
+        let pattern = traffic_pattern(&runway, &options).unwrap();
+        assert!(pattern.downwind[0].longitude > 0.01);
+
+        runway.threshold = None;
+        assert!(traffic_pattern(&runway, &options).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::db::Database;
+use crate::error::Result;
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+
+/// 会随时间过时的动态数据集
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DynamicDataset {
+    /// TAF 与 SIGMET/AIRMET
+    Weather,
+    /// 临时危险区（TFR、火山灰等）
+    Hazards,
+    /// 高空风温
+    Winds,
+    /// 航行通告
+    Notams,
+}
+
+impl DynamicDataset {
+    pub const ALL: [DynamicDataset; 4] = [
+        DynamicDataset::Weather,
+        DynamicDataset::Hazards,
+        DynamicDataset::Winds,
+        DynamicDataset::Notams,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            DynamicDataset::Weather => "WEATHER",
+            DynamicDataset::Hazards => "HAZARDS",
+            DynamicDataset::Winds => "WINDS",
+            DynamicDataset::Notams => "NOTAMS",
+        }
+    }
+
+    /// When the newest data of this kind reached the device
+    fn last_updated_sql(&self) -> &'static str {
+        match self {
+            DynamicDataset::Weather => {
+                "SELECT MAX(t) FROM (SELECT MAX(created_at) AS t FROM tafs
+                                     UNION ALL SELECT MAX(created_at) FROM sigmets)"
+            }
+            // A sync that brought no new hazards still confirms the set is current
+            DynamicDataset::Hazards => {
+                "SELECT MAX(t) FROM (SELECT MAX(updated_at) AS t FROM hazards
+                                     UNION ALL SELECT last_sync FROM sync_metadata
+                                               WHERE table_name = 'hazards')"
+            }
+            DynamicDataset::Winds => "SELECT MAX(created_at) FROM winds_aloft",
+            // Stored NOTAMs are rewritten on every fetch; the FAA import also
+            // records fetches that returned nothing
+            DynamicDataset::Notams => {
+                "SELECT MAX(t) FROM (SELECT MAX(created_at) AS t FROM notams
+                                     UNION ALL SELECT last_sync FROM sync_metadata
+                                               WHERE table_name = 'notams')"
+            }
+        }
+    }
+}
+
+/// 动态数据的过时阈值
+#[derive(Debug, Clone, PartialEq)]
+pub struct FreshnessPolicy {
+    pub weather: TimeDelta,
+    pub hazards: TimeDelta,
+    pub winds: TimeDelta,
+    pub notams: TimeDelta,
+}
+
+impl Default for FreshnessPolicy {
+    fn default() -> Self {
+        Self {
+            weather: TimeDelta::hours(6),
+            hazards: TimeDelta::hours(6),
+            winds: TimeDelta::hours(12),
+            notams: TimeDelta::hours(6),
+        }
+    }
+}
+
+impl FreshnessPolicy {
+    /// 数据集的过时阈值
+    pub fn max_age(&self, dataset: DynamicDataset) -> TimeDelta {
+        match dataset {
+            DynamicDataset::Weather => self.weather,
+            DynamicDataset::Hazards => self.hazards,
+            DynamicDataset::Winds => self.winds,
+            DynamicDataset::Notams => self.notams,
+        }
+    }
+}
+
+/// 数据新鲜度状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FreshnessStatus {
+    Fresh,
+    /// 超过过时阈值
+    Stale,
+    /// 设备上没有该类数据
+    Missing,
+}
+
+/// 一个动态数据集的新鲜度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataFreshness {
+    pub dataset: DynamicDataset,
+    pub status: FreshnessStatus,
+    /// 最新数据到达设备的时间
+    pub updated_at: Option<i64>,
+    /// 距今秒数
+    pub age_secs: Option<i64>,
+    /// 过时阈值（秒）
+    pub max_age_secs: i64,
+}
+
+impl DataFreshness {
+    /// Age for display, e.g. "7 h" or "25 min"
+    pub fn age_text(&self) -> Option<String> {
+        self.age_secs.map(|age| {
+            if age >= 3600 {
+                format!("{} h", age / 3600)
+            } else {
+                format!("{} min", age.max(0) / 60)
+            }
+        })
+    }
+}
+
+/// Freshness of every dynamic dataset at `now` (Unix seconds)
+pub fn data_freshness(db: &Database, policy: &FreshnessPolicy, now: i64) -> Result<Vec<DataFreshness>> {
+    let conn = db.get_conn()?;
+    DynamicDataset::ALL
+        .iter()
+        .map(|&dataset| {
+            let updated_at: Option<i64> =
+                conn.query_row(dataset.last_updated_sql(), [], |row| row.get(0))?;
+            let max_age_secs = policy.max_age(dataset).num_seconds();
+            let age_secs = updated_at.map(|t| now - t);
+            let status = match age_secs {
+                None => FreshnessStatus::Missing,
+                Some(age) if age > max_age_secs => FreshnessStatus::Stale,
+                Some(_) => FreshnessStatus::Fresh,
+            };
+            Ok(DataFreshness {
+                dataset,
+                status,
+                updated_at,
+                age_secs,
+                max_age_secs,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_data_freshness() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO tafs (id, station, issued_at, valid_from, valid_to, raw, created_at)
+                    VALUES ('T1', 'ZBAA', 0, 0, 86400, 'TAF ZBAA', 3600);
+                 INSERT INTO winds_aloft (valid_time, level_hpa, latitude, longitude,
+                     wind_direction, wind_speed, created_at)
+                    VALUES (0, 250, 40.0, 116.0, 270.0, 80.0, 1000);
+                 INSERT INTO sync_metadata (table_name, last_sync) VALUES ('hazards', 20000);",
+            )
+            .unwrap();
+
+        let now = 3600 + 7 * 3600;
+        let freshness = data_freshness(&db, &FreshnessPolicy::default(), now).unwrap();
+        let status = |dataset| freshness.iter().find(|f| f.dataset == dataset).unwrap();
+
+        let weather = status(DynamicDataset::Weather);
+        assert_eq!(weather.status, FreshnessStatus::Stale);
+        assert_eq!(weather.age_text().as_deref(), Some("7 h"));
+        assert_eq!(status(DynamicDataset::Winds).status, FreshnessStatus::Fresh);
+        // No hazards at all, but a recent sync said so
+        assert_eq!(status(DynamicDataset::Hazards).status, FreshnessStatus::Fresh);
+        assert_eq!(status(DynamicDataset::Hazards).age_text().as_deref(), Some("2 h"));
+
+        db.get_conn().unwrap().execute("DELETE FROM winds_aloft", []).unwrap();
+        let freshness = data_freshness(&db, &FreshnessPolicy::default(), now).unwrap();
+        let winds = freshness.iter().find(|f| f.dataset == DynamicDataset::Winds).unwrap();
+        assert_eq!((winds.status, winds.age_secs), (FreshnessStatus::Missing, None));
+    }
+
+    #[test]
+    fn test_notam_freshness() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        let notams = |now| {
+            data_freshness(&db, &FreshnessPolicy::default(), now)
+                .unwrap()
+                .into_iter()
+                .find(|f| f.dataset == DynamicDataset::Notams)
+                .unwrap()
+        };
+        assert_eq!(notams(0).status, FreshnessStatus::Missing);
+
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO notams (id, number, kind, location, valid_from, text, raw, source,
+                    created_at)
+                 VALUES ('ZBAA:A0001/24', 'A0001/24', 'NEW', 'ZBAA', 0, 'RWY CLSD', '', 'ICAO',
+                    1000)",
+                [],
+            )
+            .unwrap();
+        let stale = notams(1000 + 7 * 3600);
+        assert_eq!((stale.status, stale.max_age_secs), (FreshnessStatus::Stale, 6 * 3600));
+
+        // An empty fetch still counts as current
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO sync_metadata (table_name, last_sync) VALUES ('notams', 20000)",
+                [],
+            )
+            .unwrap();
+        let fresh = notams(1000 + 7 * 3600);
+        assert_eq!((fresh.status, fresh.updated_at), (FreshnessStatus::Fresh, Some(20000)));
+    }
+}
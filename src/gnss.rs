@@ -0,0 +1,273 @@
+use crate::error::{AeroBaseError, Result};
+use crate::events::{Event, EventBus};
+use crate::models::Coordinate;
+use crate::storage::tracks::TrackPoint;
+use chrono::{NaiveDate, NaiveTime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+/// `source` of the position events published by the adapter
+pub const GNSS_SOURCE: &str = "gnss";
+
+const FEET_PER_METER: f64 = 3.28084;
+
+/// A decoded NMEA 0183 sentence; other sentence types are skipped
+#[derive(Debug, Clone, PartialEq)]
+pub enum NmeaSentence {
+    /// Recommended minimum data: time, date, position, speed
+    Rmc {
+        time: NaiveTime,
+        date: NaiveDate,
+        /// False when the receiver flags the fix as void
+        valid: bool,
+        position: Option<Coordinate>,
+        /// Knots
+        ground_speed: Option<f64>,
+    },
+    /// Fix data: time, position, altitude
+    Gga {
+        time: NaiveTime,
+        position: Option<Coordinate>,
+        /// 0 means no fix
+        fix_quality: u8,
+        /// Meters above mean sea level
+        altitude_m: Option<f64>,
+    },
+}
+
+/// Parse one sentence such as `$GPRMC,...*6A`
+///
+/// The talker id (GP, GN, GL, ...) is ignored and a checksum, when present,
+/// must match. Returns `None` for well-formed sentences of other types.
+/// NMEA is plain ASCII, so lines with other characters (line noise) are
+/// rejected.
+pub fn parse_sentence(line: &str) -> Result<Option<NmeaSentence>> {
+    let line = line.trim();
+    if !line.is_ascii() {
+        return Err(invalid(line, "non-ASCII characters"));
+    }
+    let body = line
+        .strip_prefix('$')
+        .ok_or_else(|| invalid(line, "missing '$'"))?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum, 16)
+                .map_err(|_| invalid(line, "bad checksum"))?;
+            if body.bytes().fold(0, |acc, b| acc ^ b) != expected {
+                return Err(invalid(line, "checksum mismatch"));
+            }
+            body
+        }
+        None => body,
+    };
+
+    let fields: Vec<&str> = body.split(',').collect();
+    if fields[0].len() != 5 {
+        return Err(invalid(line, "bad sentence id"));
+    }
+    let field = |i: usize| fields.get(i).copied().unwrap_or("");
+
+    match fields[0].get(2..).unwrap_or("") {
+        "RMC" => Ok(Some(NmeaSentence::Rmc {
+            time: parse_time(field(1)).ok_or_else(|| invalid(line, "bad time"))?,
+            valid: field(2) == "A",
+            position: parse_position(field(3), field(4), field(5), field(6)),
+            ground_speed: field(7).parse().ok(),
+            date: NaiveDate::parse_from_str(field(9), "%d%m%y")
+                .map_err(|_| invalid(line, "bad date"))?,
+        })),
+        "GGA" => Ok(Some(NmeaSentence::Gga {
+            time: parse_time(field(1)).ok_or_else(|| invalid(line, "bad time"))?,
+            position: parse_position(field(2), field(3), field(4), field(5)),
+            fix_quality: field(6).parse().unwrap_or(0),
+            altitude_m: field(9).parse().ok(),
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// `hhmmss(.sss)`
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    let (whole, _) = value.split_once('.').unwrap_or((value, ""));
+    NaiveTime::parse_from_str(whole, "%H%M%S").ok()
+}
+
+/// `ddmm.mmmm,N,dddmm.mmmm,E`
+fn parse_position(lat: &str, ns: &str, lon: &str, ew: &str) -> Option<Coordinate> {
+    let degrees = |value: &str, degree_digits: usize| -> Option<f64> {
+        if value.len() < degree_digits + 2 {
+            return None;
+        }
+        let whole: f64 = value.get(..degree_digits)?.parse().ok()?;
+        let minutes: f64 = value.get(degree_digits..)?.parse().ok()?;
+        Some(whole + minutes / 60.0)
+    };
+    let latitude = match ns {
+        "N" => degrees(lat, 2)?,
+        "S" => -degrees(lat, 2)?,
+        _ => return None,
+    };
+    let longitude = match ew {
+        "E" => degrees(lon, 3)?,
+        "W" => -degrees(lon, 3)?,
+        _ => return None,
+    };
+    Some(Coordinate::new(latitude, longitude))
+}
+
+fn invalid(line: &str, reason: &str) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid NMEA sentence ({}): {}", reason, line))
+}
+
+/// Turns a stream of NMEA sentences into track points
+///
+/// RMC sentences carry the date and produce the points; the altitude comes
+/// from the GGA sentence of the same epoch, which receivers usually send
+/// just before or after. Void fixes produce nothing.
+#[derive(Debug, Clone, Default)]
+pub struct NmeaDecoder {
+    /// Time and altitude (feet) of the latest GGA fix
+    last_altitude: Option<(NaiveTime, i32)>,
+}
+
+impl NmeaDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one line; returns a point when it completes a valid fix
+    pub fn feed(&mut self, line: &str) -> Result<Option<TrackPoint>> {
+        match parse_sentence(line)? {
+            Some(NmeaSentence::Gga {
+                time,
+                fix_quality,
+                altitude_m: Some(altitude_m),
+                ..
+            }) if fix_quality > 0 => {
+                self.last_altitude = Some((time, (altitude_m * FEET_PER_METER).round() as i32));
+                Ok(None)
+            }
+            Some(NmeaSentence::Rmc {
+                time,
+                date,
+                valid: true,
+                position: Some(position),
+                ground_speed,
+            }) => Ok(Some(TrackPoint {
+                recorded_at: date.and_time(time).and_utc().timestamp(),
+                position,
+                altitude: self
+                    .last_altitude
+                    .filter(|(fix_time, _)| *fix_time == time)
+                    .map(|(_, altitude)| altitude),
+                ground_speed: ground_speed.map(|knots| knots.round() as i32),
+            })),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Read NMEA lines from `reader` (a serial port, file or socket) and publish
+/// each fix on `bus` as `PositionUpdated` until end of input
+///
+/// Malformed sentences are logged and skipped, as receivers emit partial
+/// lines on connect. Returns the number of positions published.
+pub async fn run<R: AsyncRead + Unpin>(reader: R, bus: &EventBus) -> Result<usize> {
+    let mut lines = BufReader::new(reader).lines();
+    let mut decoder = NmeaDecoder::new();
+    let mut published = 0;
+    while let Some(line) = lines.next_line().await? {
+        match decoder.feed(&line) {
+            Ok(Some(point)) => {
+                bus.publish(Event::PositionUpdated {
+                    source: GNSS_SOURCE.to_string(),
+                    point,
+                });
+                published += 1;
+            }
+            Ok(None) => {}
+            Err(e) => log::debug!("Skipping NMEA line: {}", e),
+        }
+    }
+    Ok(published)
+}
+
+/// Connect to an NMEA-over-TCP feed (e.g. a receiver's network port) and `run` it
+pub async fn run_tcp<A: ToSocketAddrs>(addr: A, bus: &EventBus) -> Result<usize> {
+    run(TcpStream::connect(addr).await?, bus).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GGA: &str = "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47";
+    const RMC: &str = "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A";
+
+    #[test]
+    fn test_parse_sentences() {
+        match parse_sentence(RMC).unwrap() {
+            Some(NmeaSentence::Rmc {
+                valid,
+                position: Some(position),
+                ground_speed,
+                date,
+                ..
+            }) => {
+                assert!(valid);
+                assert!((position.latitude - 48.1173).abs() < 1e-4);
+                assert!((position.longitude - 11.516_667).abs() < 1e-4);
+                assert_eq!(ground_speed, Some(22.4));
+                assert_eq!(date, NaiveDate::from_ymd_opt(1994, 3, 23).unwrap());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            parse_sentence(GGA).unwrap(),
+            Some(NmeaSentence::Gga { fix_quality: 1, altitude_m: Some(a), .. }) if a == 545.4
+        ));
+
+        assert_eq!(parse_sentence("$GPGSV,1,1,00").unwrap(), None);
+        assert!(parse_sentence(&RMC.replace("*6A", "*6B")).is_err());
+        assert!(parse_sentence("GPRMC,123519").is_err());
+    }
+
+    #[test]
+    fn test_non_ascii_input_is_rejected() {
+        // Five bytes, but the third one is inside a multibyte character
+        assert!(parse_sentence("$GéGA,123519").is_err());
+        assert!(parse_sentence("$GPRMC,123519,A,4é07.038,N,01131.000,E,022.4,084.4,230394,,").is_err());
+        assert_eq!(parse_position("4é07.038", "N", "01131.000", "E"), None);
+        assert_eq!(parse_position("4807.038", "N", "01é31.000", "E"), None);
+    }
+
+    #[test]
+    fn test_decoder_combines_gga_and_rmc() {
+        let mut decoder = NmeaDecoder::new();
+        assert_eq!(decoder.feed(GGA).unwrap(), None);
+        let point = decoder.feed(RMC).unwrap().unwrap();
+        assert_eq!(point.recorded_at, 764_426_119);
+        assert_eq!(point.altitude, Some(1789));
+        assert_eq!(point.ground_speed, Some(22));
+
+        // Void fix
+        let void = "$GPRMC,123520,V,,,,,,,230394,,";
+        assert_eq!(decoder.feed(void).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_publishes_positions() {
+        let bus = EventBus::new();
+        let mut receiver = bus.subscribe();
+        let input = format!("{}\n$GPRMC,garbage\n{}\n", GGA, RMC);
+
+        assert_eq!(run(input.as_bytes(), &bus).await.unwrap(), 1);
+        match receiver.try_recv().unwrap() {
+            Event::PositionUpdated { source, point } => {
+                assert_eq!(source, GNSS_SOURCE);
+                assert_eq!(point.altitude, Some(1789));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
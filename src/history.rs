@@ -0,0 +1,222 @@
+use crate::db::query::{airport_from_row, resolve_airport_id, AIRPORT_COLUMNS};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::favorites::{touch_recent, ItemKind};
+use crate::flight::{format, FlightPlan};
+use crate::models::airport::Airport;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Profile used when the embedding UI has no notion of profiles
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Routes kept per profile
+const MAX_ROUTE_HISTORY: i64 = 50;
+
+/// 最近飞行的航线
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteHistoryEntry {
+    pub plan: FlightPlan,
+    pub times_flown: i64,
+    pub flown_at: i64,
+}
+
+/// Normalized identity of a route: departure, route points and destination
+fn route_key(plan: &FlightPlan) -> String {
+    std::iter::once(plan.departure.as_str())
+        .chain(plan.route.iter().map(String::as_str))
+        .chain(std::iter::once(plan.destination.as_str()))
+        .map(str::to_uppercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 最近浏览机场与最近飞行航线的历史记录
+pub struct HistoryManager {
+    db: Arc<Database>,
+    profile_id: String,
+}
+
+impl HistoryManager {
+    /// 创建使用默认配置文件的历史记录管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self {
+            db,
+            profile_id: DEFAULT_PROFILE.to_string(),
+        })
+    }
+
+    /// 切换到指定配置文件的历史记录
+    pub fn for_profile(&self, profile_id: &str) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            profile_id: profile_id.to_string(),
+        }
+    }
+
+    /// 记录浏览了某机场
+    pub fn record_airport_view(&self, icao: &str) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        let id = resolve_airport_id(&conn, icao)?
+            .ok_or_else(|| AeroBaseError::NotFound(format!("Airport {} not found", icao)))?;
+        touch_recent(&conn, &self.profile_id, ItemKind::Airport, &id, self.db.clock().timestamp())
+    }
+
+    /// 最近浏览的机场（最新在前）
+    pub fn recent_airports(&self, limit: usize) -> Result<Vec<Airport>> {
+        let conn = self.db.get_conn()?;
+        let columns: Vec<String> = AIRPORT_COLUMNS
+            .split(", ")
+            .map(|c| format!("a.{}", c))
+            .collect();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM recent_items r JOIN airports a ON a.id = r.item_id
+             WHERE r.profile_id = ?1 AND r.item_type = ?2
+             ORDER BY r.accessed_at DESC, r.rowid DESC LIMIT ?3",
+            columns.join(", ")
+        ))?;
+        let airports = stmt
+            .query_map(
+                rusqlite::params![&self.profile_id, ItemKind::Airport.as_str(), limit as i64],
+                airport_from_row,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(airports)
+    }
+
+    /// 记录飞行过的航线（相同航线累计次数）
+    pub fn record_route(&self, plan: &FlightPlan) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        conn.execute(
+            "INSERT INTO route_history (id, profile_id, route_key, plan_json, times_flown, flown_at)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5)
+             ON CONFLICT(profile_id, route_key) DO UPDATE SET
+                 plan_json = excluded.plan_json,
+                 times_flown = times_flown + 1,
+                 flown_at = excluded.flown_at",
+            rusqlite::params![
+                self.db.new_id(),
+                &self.profile_id,
+                route_key(plan),
+                format::plan_to_json(plan)?,
+                self.db.clock().timestamp(),
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM route_history WHERE profile_id = ?1 AND rowid NOT IN (
+                 SELECT rowid FROM route_history WHERE profile_id = ?1
+                 ORDER BY flown_at DESC, rowid DESC LIMIT ?2)",
+            rusqlite::params![&self.profile_id, MAX_ROUTE_HISTORY],
+        )?;
+        Ok(())
+    }
+
+    /// 最近飞行的航线（最新在前）
+    pub fn recent_routes(&self, limit: usize) -> Result<Vec<RouteHistoryEntry>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT plan_json, times_flown, flown_at FROM route_history
+             WHERE profile_id = ?1 ORDER BY flown_at DESC, rowid DESC LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![&self.profile_id, limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(json, times_flown, flown_at)| {
+                Ok(RouteHistoryEntry {
+                    plan: format::plan_from_json(&json)?,
+                    times_flown,
+                    flown_at,
+                })
+            })
+            .collect()
+    }
+
+    /// 清空当前配置文件的历史记录
+    pub fn clear(&self) -> Result<()> {
+        let conn = self.db.get_conn()?;
+        conn.execute("DELETE FROM recent_items WHERE profile_id = ?1", [&self.profile_id])?;
+        conn.execute("DELETE FROM route_history WHERE profile_id = ?1", [&self.profile_id])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::FlightPlanBuilder;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('AP1', 'ZBAA', 'Beijing', 40.0, 116.0, 0),
+                    ('AP2', 'ZSSS', 'Shanghai', 31.0, 121.0, 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_recent_airports() {
+        let (db, _temp_file) = setup_test_db();
+        let history = HistoryManager::new(db).unwrap();
+
+        history.record_airport_view("ZBAA").unwrap();
+        history.record_airport_view("ZSSS").unwrap();
+        history.record_airport_view("ZBAA").unwrap();
+        assert!(history.record_airport_view("XXXX").is_err());
+
+        let icaos: Vec<String> = history
+            .recent_airports(10)
+            .unwrap()
+            .into_iter()
+            .map(|a| a.icao)
+            .collect();
+        assert_eq!(icaos, vec!["ZBAA", "ZSSS"]);
+        assert_eq!(history.recent_airports(1).unwrap().len(), 1);
+        assert!(history.for_profile("copilot").recent_airports(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_routes() {
+        let (db, _temp_file) = setup_test_db();
+        let history = HistoryManager::new(db).unwrap();
+        let plan = FlightPlanBuilder::new()
+            .departure("ZBAA")
+            .destination("ZSSS")
+            .cruise_altitude(35000)
+            .cruise_speed(450)
+            .build()
+            .unwrap();
+
+        history.record_route(&plan).unwrap();
+        let mut faster = plan.clone();
+        faster.cruise_speed = 480;
+        history.record_route(&faster).unwrap();
+
+        let routes = history.recent_routes(10).unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].times_flown, 2);
+        assert_eq!(routes[0].plan.cruise_speed, 480);
+
+        history.clear().unwrap();
+        assert!(history.recent_routes(10).unwrap().is_empty());
+    }
+}
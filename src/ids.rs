@@ -0,0 +1,489 @@
+use crate::db::{schema, Database};
+use crate::error::{AeroBaseError, Result};
+use crate::navdata::table_columns;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use uuid::{NoContext, Timestamp, Uuid};
+
+/// Crockford base32, as used by ULIDs
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// 新记录主键的生成方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdStrategy {
+    /// 随机 UUID（v4），早期版本的行为
+    UuidV4,
+    /// 按时间排序的 UUID（v7），新记录写在索引末尾
+    #[default]
+    UuidV7,
+    /// ULID：26 位 Crockford Base32，按时间排序且更短
+    Ulid,
+    /// 机场、航路点、导航设施使用源数据的自然键（如 "ZB:ALPHA"），其余记录使用 UUIDv7
+    Natural,
+}
+
+impl IdStrategy {
+    pub fn as_str(&self) -> &str {
+        match self {
+            IdStrategy::UuidV4 => "UUIDV4",
+            IdStrategy::UuidV7 => "UUIDV7",
+            IdStrategy::Ulid => "ULID",
+            IdStrategy::Natural => "NATURAL",
+        }
+    }
+
+    /// 未知取值返回 None
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "UUIDV4" => Some(IdStrategy::UuidV4),
+            "UUIDV7" => Some(IdStrategy::UuidV7),
+            "ULID" => Some(IdStrategy::Ulid),
+            "NATURAL" => Some(IdStrategy::Natural),
+            _ => None,
+        }
+    }
+
+    /// A new id whose time component is `millis` (Unix milliseconds)
+    pub fn generate_at(&self, millis: i64) -> String {
+        let millis = millis.max(0) as u64;
+        match self {
+            IdStrategy::UuidV4 => Uuid::new_v4().to_string(),
+            IdStrategy::UuidV7 | IdStrategy::Natural => {
+                let timestamp = Timestamp::from_unix(
+                    NoContext,
+                    millis / 1000,
+                    (millis % 1000) as u32 * 1_000_000,
+                );
+                Uuid::new_v7(timestamp).to_string()
+            }
+            IdStrategy::Ulid => {
+                let random = rand::random::<u128>() & ((1 << 80) - 1);
+                let value = ((millis as u128 & 0xFFFF_FFFF_FFFF) << 80) | random;
+                (0..26)
+                    .map(|i| ULID_ALPHABET[((value >> ((25 - i) * 5)) & 31) as usize] as char)
+                    .collect()
+            }
+        }
+    }
+
+    /// Id for a navdata row identified in the source by `parts` (e.g.
+    /// region and ident); a fresh id created at `millis` unless the
+    /// strategy is `Natural`
+    pub fn navdata_id(&self, parts: &[&str], millis: i64) -> String {
+        match self {
+            IdStrategy::Natural => natural_key(parts),
+            _ => self.generate_at(millis),
+        }
+    }
+
+    /// Whether `id` already has this strategy's form
+    fn matches(&self, id: &str) -> bool {
+        match self {
+            IdStrategy::UuidV4 | IdStrategy::UuidV7 => {
+                let version = if *self == IdStrategy::UuidV4 { 4 } else { 7 };
+                Uuid::parse_str(id).is_ok_and(|uuid| uuid.get_version_num() == version)
+            }
+            IdStrategy::Ulid => id.len() == 26 && id.bytes().all(|b| ULID_ALPHABET.contains(&b)),
+            IdStrategy::Natural => false,
+        }
+    }
+}
+
+/// Source parts upper-cased and joined with ':', skipping empty ones
+pub fn natural_key(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|part| part.trim().to_uppercase())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Columns forming the natural key of tables that have one
+fn natural_columns(table: &str) -> Option<&'static [&'static str]> {
+    match table {
+        "airports" => Some(&["icao"]),
+        "waypoints" => Some(&["region", "name"]),
+        "navaids" => Some(&["region", "name", "type"]),
+        _ => None,
+    }
+}
+
+/// A reference that isn't a foreign key: (table, column, kind column, kind)
+type SoftReference = (&'static str, &'static str, &'static str, &'static str);
+
+fn soft_references(table: &str) -> &'static [SoftReference] {
+    match table {
+        "airports" => &[
+            ("favorites", "item_id", "item_type", "AIRPORT"),
+            ("recent_items", "item_id", "item_type", "AIRPORT"),
+            ("restricted_items", "item_id", "item_type", "AIRPORT"),
+        ],
+        "waypoints" => &[
+            ("favorites", "item_id", "item_type", "WAYPOINT"),
+            ("recent_items", "item_id", "item_type", "WAYPOINT"),
+        ],
+        "airspaces" => &[("restricted_items", "item_id", "item_type", "AIRSPACE")],
+        _ => &[],
+    }
+}
+
+/// Rewrite the ids of existing rows in `table` to `strategy`
+///
+/// Rows whose id already has the strategy's form are left alone. Time-based
+/// ids take their time from `created_at`, so they sort in creation order.
+/// Foreign keys pointing at the table, favorites, recent items, redaction
+/// marks and the waypoint lists in saved flight plans and route history
+/// are updated to match, all in one transaction. Returns the number of
+/// rows rekeyed.
+///
+/// `Natural` applies to airports, waypoints and navaids only; it fails with
+/// `InvalidInput`, changing nothing, if two rows share a natural key.
+pub fn rekey(db: &Database, table: &str, strategy: IdStrategy) -> Result<usize> {
+    let conn = db.get_background_conn()?;
+    let columns = table_columns(&conn, table)?;
+    if !columns.iter().any(|column| column == "id") {
+        return Err(AeroBaseError::InvalidInput(format!("{} has no id column", table)));
+    }
+    let natural = match strategy {
+        IdStrategy::Natural => Some(natural_columns(table).ok_or_else(|| {
+            AeroBaseError::InvalidInput(format!("{} has no natural key", table))
+        })?),
+        _ => None,
+    };
+    // Rows without a creation time get an id created now
+    let now = db.clock().now().timestamp_millis();
+    let created_at = if columns.iter().any(|column| column == "created_at") {
+        "created_at"
+    } else {
+        "NULL"
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    let mut stmt = tx.prepare(&format!(
+        "SELECT id, {}, {} FROM {} ORDER BY {}, id",
+        created_at,
+        natural.map(|columns| columns.join(", ")).unwrap_or_else(|| "NULL".to_string()),
+        table,
+        created_at
+    ))?;
+    let mut rows = stmt.query([])?;
+    let mut mapping = Vec::new();
+    let mut kept = HashSet::new();
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let new_id = match natural {
+            Some(columns) => {
+                let parts = (0..columns.len())
+                    .map(|i| Ok(row.get::<_, Option<String>>(i + 2)?.unwrap_or_default()))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                natural_key(&parts.iter().map(String::as_str).collect::<Vec<_>>())
+            }
+            None if strategy.matches(&id) => id.clone(),
+            None => {
+                let created_at: Option<i64> = row.get(1)?;
+                strategy.generate_at(created_at.map_or(now, |s| s * 1000))
+            }
+        };
+        if new_id == id {
+            kept.insert(id);
+        } else {
+            mapping.push((id, new_id));
+        }
+    }
+    drop(rows);
+    drop(stmt);
+
+    let mut seen = kept;
+    for (_, new_id) in &mapping {
+        if !seen.insert(new_id.clone()) {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "{} has more than one row with key {}",
+                table, new_id
+            )));
+        }
+    }
+    if mapping.is_empty() {
+        return Ok(0);
+    }
+
+    // Parents and children change one at a time; check keys at commit
+    tx.execute_batch("PRAGMA defer_foreign_keys = ON")?;
+    let children = foreign_key_children(&tx, table, db.user_db_path().is_some())?;
+    for (old, new) in &mapping {
+        tx.execute(&format!("UPDATE {} SET id = ?2 WHERE id = ?1", table), [old, new])?;
+        for (child, column) in &children {
+            tx.execute(
+                &format!("UPDATE {} SET {col} = ?2 WHERE {col} = ?1", child, col = column),
+                [old, new],
+            )?;
+        }
+        for (child, column, kind_column, kind) in soft_references(table) {
+            tx.execute(
+                &format!(
+                    "UPDATE {} SET {col} = ?2 WHERE {col} = ?1 AND {} = ?3",
+                    child,
+                    kind_column,
+                    col = column
+                ),
+                [old, new, &kind.to_string()],
+            )?;
+        }
+    }
+    if table == "waypoints" {
+        let mapping: HashMap<&str, &str> =
+            mapping.iter().map(|(old, new)| (old.as_str(), new.as_str())).collect();
+        rewrite_plan_routes(&tx, "flight_plans", &mapping)?;
+        rewrite_plan_routes(&tx, "route_history", &mapping)?;
+    }
+    tx.commit()?;
+    log::info!("Rekeyed {} rows of {} to {}", mapping.len(), table, strategy.as_str());
+    Ok(mapping.len())
+}
+
+/// (table, column) pairs with a foreign key to `table`, including tables in
+/// the attached user-data database when `user_schema` is set
+fn foreign_key_children(
+    conn: &Connection,
+    table: &str,
+    user_schema: bool,
+) -> Result<Vec<(String, String)>> {
+    let mut schemas = vec!["main"];
+    if user_schema {
+        schemas.push(schema::USER_SCHEMA);
+    }
+    let mut children = Vec::new();
+    for schema in schemas {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT m.name, f.\"from\"
+             FROM {0}.sqlite_master m, pragma_foreign_key_list(m.name, '{0}') f
+             WHERE m.type = 'table' AND f.\"table\" = ?1 ORDER BY m.name, f.\"from\"",
+            schema
+        ))?;
+        let found = stmt
+            .query_map([table], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<(String, String)>, _>>()?;
+        children.extend(found);
+    }
+    Ok(children)
+}
+
+/// Map waypoint ids in the `route` list of each stored plan
+fn rewrite_plan_routes(
+    conn: &Connection,
+    table: &str,
+    mapping: &HashMap<&str, &str>,
+) -> Result<()> {
+    let exists: Option<i64> = conn
+        .query_row(
+            "SELECT 1 FROM pragma_table_list WHERE name = ?1",
+            [table],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if exists.is_none() {
+        return Ok(());
+    }
+    let plans = conn
+        .prepare(&format!("SELECT id, plan_json FROM {}", table))?
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    for (id, json) in plans {
+        let mut plan: serde_json::Value = serde_json::from_str(&json)?;
+        let Some(route) = plan.get_mut("route").and_then(|route| route.as_array_mut()) else {
+            continue;
+        };
+        let mut changed = false;
+        for entry in route.iter_mut() {
+            if let Some(new) = entry.as_str().and_then(|old| mapping.get(old)) {
+                *entry = serde_json::Value::String(new.to_string());
+                changed = true;
+            }
+        }
+        if changed {
+            conn.execute(
+                &format!("UPDATE {} SET plan_json = ?2 WHERE id = ?1", table),
+                [&id, &plan.to_string()],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_generated_ids() {
+        let v7 = IdStrategy::UuidV7.generate_at(1_700_000_000_000);
+        assert!(IdStrategy::UuidV7.matches(&v7));
+        assert!(!IdStrategy::UuidV4.matches(&v7));
+        assert!(IdStrategy::UuidV4.matches(&IdStrategy::UuidV4.generate_at(0)));
+
+        // Time-ordered strategies sort by creation time
+        for strategy in [IdStrategy::UuidV7, IdStrategy::Ulid] {
+            let earlier = strategy.generate_at(1_700_000_000_000);
+            let later = strategy.generate_at(1_700_000_000_001);
+            assert!(earlier < later, "{:?}", strategy);
+        }
+        let ulid = IdStrategy::Ulid.generate_at(1_700_000_000_000);
+        assert_eq!(ulid.len(), 26);
+        assert!(ulid.starts_with("01HF"));
+        assert!(IdStrategy::Ulid.matches(&ulid));
+
+        assert_eq!(IdStrategy::Natural.navdata_id(&["zb", " alpha"], 0), "ZB:ALPHA");
+        assert_eq!(IdStrategy::Natural.navdata_id(&["", "ALPHA"], 0), "ALPHA");
+        let v7 = IdStrategy::UuidV7.navdata_id(&["ZB", "ALPHA"], 1_700_000_000_000);
+        assert!(IdStrategy::UuidV7.matches(&v7));
+        assert_eq!(v7[..13], IdStrategy::UuidV7.generate_at(1_700_000_000_000)[..13]);
+        assert_eq!(IdStrategy::from_str("ulid"), Some(IdStrategy::Ulid));
+        assert_eq!(IdStrategy::from_str(IdStrategy::Natural.as_str()), Some(IdStrategy::Natural));
+    }
+
+    #[test]
+    fn test_rekey() {
+        let (db, _temp_file) = setup_test_db();
+        let old = "6f9619ff-8b86-4011-b42d-00cf4fc964ff";
+        db.get_conn()
+            .unwrap()
+            .execute_batch(&format!(
+                "INSERT INTO waypoints (id, name, latitude, longitude, region, type, created_at)
+                    VALUES ('{old}', 'ALPHA', 40.0, 116.0, 'ZB', 'FIX', 100),
+                           ('W2', 'BRAVO', 41.0, 117.0, NULL, 'FIX', 200);
+                 INSERT INTO airways (id, name, type, created_at) VALUES ('A1', 'A1', 'HIGH', 0);
+                 INSERT INTO airway_segments
+                    (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+                    VALUES ('S1', 'A1', '{old}', 'W2', 1, 0);
+                 INSERT INTO favorites (id, profile_id, item_type, item_id, created_at)
+                    VALUES ('F1', 'default', 'WAYPOINT', '{old}', 0);
+                 INSERT INTO flight_plans (id, departure, destination, plan_json, created_at)
+                    VALUES ('P1', 'ZBAA', 'ZSPD', '{{\"route\":[\"{old}\",\"W2\"]}}', 0);"
+            ))
+            .unwrap();
+
+        assert_eq!(rekey(&db, "waypoints", IdStrategy::Natural).unwrap(), 2);
+        // Already natural
+        assert_eq!(rekey(&db, "waypoints", IdStrategy::Natural).unwrap(), 0);
+
+        let conn = db.get_conn().unwrap();
+        let segment: (String, String) = conn
+            .query_row(
+                "SELECT from_waypoint_id, to_waypoint_id FROM airway_segments",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(segment, ("ZB:ALPHA".to_string(), "BRAVO".to_string()));
+        let favorite: String =
+            conn.query_row("SELECT item_id FROM favorites", [], |row| row.get(0)).unwrap();
+        assert_eq!(favorite, "ZB:ALPHA");
+        let plan: String =
+            conn.query_row("SELECT plan_json FROM flight_plans", [], |row| row.get(0)).unwrap();
+        assert_eq!(plan, r#"{"route":["ZB:ALPHA","BRAVO"]}"#);
+        drop(conn);
+        assert!(db.check_foreign_keys().unwrap().is_empty());
+
+        // Time-ordered ids follow created_at
+        assert_eq!(rekey(&db, "waypoints", IdStrategy::UuidV7).unwrap(), 2);
+        let ids: Vec<String> = db
+            .get_conn()
+            .unwrap()
+            .prepare("SELECT id FROM waypoints ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        let alpha = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT id FROM waypoints WHERE name = 'ALPHA'", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .unwrap();
+        assert_eq!(ids[0], alpha);
+        assert!(matches!(
+            rekey(&db, "airways", IdStrategy::Natural),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_rekey_user_tables() {
+        // Aircraft and their logbook live in the attached user database
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            db_path: dir.path().join("navdata.db"),
+            user_db_path: Some(dir.path().join("user.db")),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO aircraft (id, aircraft_type, cruise_speed, cruise_altitude,
+                    fuel_flow_gph, equipment, created_at)
+                    VALUES ('AC1', 'C172', 110, 6000, 8.5, 'G', 100);
+                 INSERT INTO logbook_entries (id, aircraft_id, flown_at, block_minutes, cycles,
+                    created_at) VALUES ('L1', 'AC1', 0, 60, 1, 0);",
+            )
+            .unwrap();
+
+        assert_eq!(rekey(&db, "aircraft", IdStrategy::Ulid).unwrap(), 1);
+        let conn = db.get_conn().unwrap();
+        let id: String = conn.query_row("SELECT id FROM aircraft", [], |row| row.get(0)).unwrap();
+        assert!(IdStrategy::Ulid.matches(&id));
+        let logged: String = conn
+            .query_row("SELECT aircraft_id FROM user.logbook_entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(logged, id);
+        drop(conn);
+        assert!(db.check_foreign_keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rekey_natural_key_clash() {
+        // Rows without a region aren't held unique by the schema
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                    VALUES ('W1', 'ALPHA', 40.0, 116.0, 'FIX', 0),
+                           ('W2', 'ALPHA', 41.0, 117.0, 'FIX', 0);",
+            )
+            .unwrap();
+        assert!(matches!(
+            rekey(&db, "waypoints", IdStrategy::Natural),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+        let count: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM waypoints WHERE id IN ('W1', 'W2')", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
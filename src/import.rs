@@ -0,0 +1,214 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+#[cfg(feature = "import")]
+use crate::navdata::seed::SeedSummary;
+use crate::navdata::text::TextPolicy;
+use crate::navdata::xplane::{self, XPlaneSummary};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Records between progress reports within a phase
+const REPORT_EVERY: usize = 100;
+
+/// 导入阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImportPhase {
+    /// 解析、解密或校验输入
+    Parsing,
+    /// 在暂存事务中写入记录
+    Writing,
+    /// 提交事务
+    Committing,
+}
+
+/// 导入进度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub phase: ImportPhase,
+    /// 本阶段已处理的记录数
+    pub processed: usize,
+    /// 本阶段的记录总数（未知时为 None）
+    pub total: Option<usize>,
+}
+
+/// 导入取消令牌，可克隆后交给其他线程
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消；导入在下一次报告进度时回滚并返回 `Cancelled`
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+type ProgressCallback = Box<dyn Fn(&ImportProgress) + Send + Sync>;
+
+/// 导入的进度回调、进度通道与取消令牌
+#[derive(Default)]
+pub struct ImportControl {
+    callback: Option<ProgressCallback>,
+    sender: Option<watch::Sender<ImportProgress>>,
+    token: CancellationToken,
+}
+
+impl ImportControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 每次报告进度时调用 `callback`（在导入线程上执行）
+    pub fn on_progress(mut self, callback: impl Fn(&ImportProgress) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// 通过 watch 通道发布进度，适合异步界面轮询最新状态
+    pub fn watch(mut self) -> (Self, watch::Receiver<ImportProgress>) {
+        let (sender, receiver) = watch::channel(ImportProgress {
+            phase: ImportPhase::Parsing,
+            processed: 0,
+            total: None,
+        });
+        self.sender = Some(sender);
+        (self, receiver)
+    }
+
+    /// 使用外部创建的取消令牌
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// 本次导入的取消令牌
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Report progress and fail with `Cancelled` once cancellation is requested
+    ///
+    /// Importers call this at the start of each phase (`processed` 0) and
+    /// after every record; listeners hear about every `REPORT_EVERY`th
+    /// record and the last one. Returning the error before commit drops the
+    /// staging transaction, which rolls it back.
+    pub(crate) fn report(&self, phase: ImportPhase, processed: usize, total: Option<usize>) -> Result<()> {
+        if self.token.is_cancelled() {
+            return Err(AeroBaseError::Cancelled(format!(
+                "Import cancelled while {:?} after {} record(s)",
+                phase, processed
+            )));
+        }
+        if !processed.is_multiple_of(REPORT_EVERY) && Some(processed) != total {
+            return Ok(());
+        }
+        let progress = ImportProgress {
+            phase,
+            processed,
+            total,
+        };
+        if let Some(callback) = &self.callback {
+            callback(&progress);
+        }
+        if let Some(sender) = &self.sender {
+            sender.send_replace(progress);
+        }
+        Ok(())
+    }
+}
+
+/// 从公开数据源导入导航数据，由 `AeroBase::import()` 创建
+pub struct Importer {
+    db: Arc<Database>,
+    source_text: TextPolicy,
+}
+
+impl Importer {
+    pub(crate) fn new(db: Arc<Database>, source_text: TextPolicy) -> Self {
+        Self { db, source_text }
+    }
+
+    /// 从 X-Plane 的 earth_fix/earth_nav/earth_awy.dat 目录导入
+    pub fn from_xplane(&self, dir: impl AsRef<Path>) -> Result<XPlaneSummary> {
+        self.from_xplane_with(dir, &ImportControl::default())
+    }
+
+    /// 同 `from_xplane`，带进度回调与取消
+    pub fn from_xplane_with(
+        &self,
+        dir: impl AsRef<Path>,
+        control: &ImportControl,
+    ) -> Result<XPlaneSummary> {
+        xplane::import_dir(&self.db, dir.as_ref(), &self.source_text, control)
+    }
+
+    /// 从 OurAirports 下载目录（airports.csv，可选 runways.csv、navaids.csv）导入，
+    /// 用于以公开数据初始化新数据库
+    #[cfg(feature = "import")]
+    pub fn from_ourairports(&self, dir: impl AsRef<Path>) -> Result<SeedSummary> {
+        self.from_ourairports_with(dir, &ImportControl::default())
+    }
+
+    /// 同 `from_ourairports`，带进度回调与取消
+    #[cfg(feature = "import")]
+    pub fn from_ourairports_with(
+        &self,
+        dir: impl AsRef<Path>,
+        control: &ImportControl,
+    ) -> Result<SeedSummary> {
+        crate::navdata::ourairports::import_dir(&self.db, dir.as_ref(), &self.source_text, control)
+    }
+
+    /// 从 OpenAIP 导出目录（*_apt.json、*_nav.json）导入
+    #[cfg(feature = "import")]
+    pub fn from_openaip(&self, dir: impl AsRef<Path>) -> Result<SeedSummary> {
+        self.from_openaip_with(dir, &ImportControl::default())
+    }
+
+    /// 同 `from_openaip`，带进度回调与取消
+    #[cfg(feature = "import")]
+    pub fn from_openaip_with(
+        &self,
+        dir: impl AsRef<Path>,
+        control: &ImportControl,
+    ) -> Result<SeedSummary> {
+        crate::navdata::openaip::import_dir(&self.db, dir.as_ref(), &self.source_text, control)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_report_and_cancel() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+        let (control, receiver) = ImportControl::new()
+            .on_progress(move |p| recorder.lock().unwrap().push(p.processed))
+            .watch();
+
+        for i in 0..=250 {
+            control.report(ImportPhase::Writing, i, Some(250)).unwrap();
+        }
+        assert_eq!(*seen.lock().unwrap(), vec![0, 100, 200, 250]);
+        assert_eq!(receiver.borrow().processed, 250);
+
+        control.cancellation_token().cancel();
+        assert!(matches!(
+            control.report(ImportPhase::Committing, 250, Some(250)),
+            Err(AeroBaseError::Cancelled(_))
+        ));
+    }
+}
@@ -1,15 +1,48 @@
+pub mod access;
+pub mod attachments;
+pub mod clock;
+pub mod crew;
 pub mod db;
 pub mod device;
+pub mod diagnostics;
 pub mod encryption;
 pub mod error;
+pub mod events;
+pub mod export;
+pub mod favorites;
+pub mod flags;
+pub mod fleet;
 pub mod flight;
+pub mod freshness;
+pub mod import;
+pub mod history;
+pub mod ids;
+pub mod logging;
+pub mod maintenance;
 pub mod models;
+pub mod navdata;
+pub mod notam;
+pub mod ratelimit;
+pub mod redaction;
+pub mod repository;
+pub mod selftest;
+pub mod simulation;
 pub mod spatial;
+pub mod storage;
 pub mod sync;
+pub mod weather;
 
 #[cfg(feature = "ffi")]
 pub mod ffi;
 
+/// NMEA 0183 position input for embedded devices
+#[cfg(feature = "gnss")]
+pub mod gnss;
+
+/// Protobuf encodings of the core models, see `proto/aerobase.proto`
+#[cfg(feature = "protobuf")]
+pub mod proto;
+
 use error::Result;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -19,18 +52,60 @@ use std::sync::Arc;
 pub struct Config {
     /// 数据库文件路径
     pub db_path: PathBuf,
+    /// 用户数据（航行计划、轨迹、收藏、设备等）单独存放的数据库文件
+    ///
+    /// 设置后更换导航数据文件或将其恢复出厂都不会影响用户数据；为 None 时
+    /// 用户数据与导航数据同在 `db_path` 中。
+    pub user_db_path: Option<PathBuf>,
     /// 启用 WAL 模式以提高并发性能
     pub enable_wal: bool,
     /// 连接池大小
     pub pool_size: u32,
+    /// 批量导入、恢复等后台任务可同时占用的连接数（默认 1），其余连接留给交互读取
+    pub background_connections: u32,
+    /// 服务模式下的接口限流与每日配额
+    pub rate_limit: ratelimit::RateLimitConfig,
+    /// 同步带宽上限与允许的同步时间窗口
+    pub sync: sync::SyncConfig,
+    /// 导航数据过期提醒与验证策略
+    pub navdata_expiry: navdata::ExpiryPolicy,
+    /// 错误诊断收集（默认关闭）
+    pub diagnostics: diagnostics::DiagnosticsConfig,
+    /// 轨迹、高空风温格点和瓦片缓存的存储配额
+    pub storage: storage::StorageConfig,
+    /// 气象、危险区和高空风数据的过时阈值
+    pub freshness: freshness::FreshnessPolicy,
+    /// 用户数据云端备份的周期与名称
+    pub cloud_backup: sync::backup::BackupConfig,
+    /// 导入源数据中非 UTF-8 文本（机场名等）的字符集与音译回退
+    pub source_text: navdata::text::TextPolicy,
+    /// 附件的存放位置与配额
+    pub attachments: attachments::AttachmentConfig,
+    /// 日志中设备 ID、指纹等标识符的脱敏方式
+    pub logging: logging::LoggingConfig,
+    /// 新记录主键的生成方式
+    pub ids: ids::IdStrategy,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             db_path: PathBuf::from("aerobase.db"),
+            user_db_path: None,
             enable_wal: true,
             pool_size: 4,
+            background_connections: 1,
+            rate_limit: ratelimit::RateLimitConfig::default(),
+            sync: sync::SyncConfig::default(),
+            navdata_expiry: navdata::ExpiryPolicy::default(),
+            diagnostics: diagnostics::DiagnosticsConfig::default(),
+            storage: storage::StorageConfig::default(),
+            freshness: freshness::FreshnessPolicy::default(),
+            cloud_backup: sync::backup::BackupConfig::default(),
+            source_text: navdata::text::TextPolicy::default(),
+            attachments: attachments::AttachmentConfig::default(),
+            logging: logging::LoggingConfig::default(),
+            ids: ids::IdStrategy::default(),
         }
     }
 }
@@ -38,42 +113,161 @@ impl Default for Config {
 /// AeroBase 服务主入口
 pub struct AeroBase {
     db: Arc<db::Database>,
+    clock: Arc<dyn clock::Clock>,
+    backup_path: PathBuf,
+    self_test_path: PathBuf,
+    recovery: Option<db::recovery::RecoveryReport>,
     device_manager: Arc<device::DeviceManager>,
+    diagnostics: Arc<diagnostics::Diagnostics>,
     spatial_engine: Arc<spatial::SpatialEngine>,
     flight_planner: Arc<flight::FlightPlanner>,
+    navdata_expiry: navdata::ExpiryPolicy,
+    freshness: freshness::FreshnessPolicy,
+    cloud_backup: sync::backup::BackupConfig,
+    source_text: navdata::text::TextPolicy,
+    attachments: attachments::AttachmentConfig,
+    flight_plans: Arc<flight::repository::FlightPlanRepository>,
+    airports: Arc<repository::AirportRepository>,
+    waypoints: Arc<repository::WaypointRepository>,
+    navaids: Arc<repository::NavaidRepository>,
+    favorites: Arc<favorites::FavoritesManager>,
+    flags: Arc<flags::FeatureFlags>,
+    fleet: Arc<fleet::FleetManager>,
+    maintenance: Arc<maintenance::MaintenanceTracker>,
+    notams: Arc<notam::NotamManager>,
+    history: Arc<history::HistoryManager>,
+    storage: Arc<storage::StorageManager>,
+    weather: Arc<weather::WeatherManager>,
+    events: Arc<events::EventBus>,
+    sync_scheduler: Arc<sync::SyncScheduler>,
+    access: Arc<access::AccessControl>,
+    redaction: Arc<redaction::RedactionPolicy>,
+    rate_limiter: Arc<ratelimit::RateLimiter>,
 }
 
 impl AeroBase {
     /// 创建新的 AeroBase 实例
     pub async fn new(config: Config) -> Result<Self> {
+        Self::with_clock(config, Arc::new(clock::SystemClock)).await
+    }
+
+    /// 使用指定时钟创建 AeroBase 实例（测试中可注入 `MockClock`）
+    pub async fn with_clock(config: Config, clock: Arc<dyn clock::Clock>) -> Result<Self> {
+        logging::set_config(config.logging.clone());
         log::info!("正在初始化 AeroBase，配置: {:?}", config);
 
-        // 初始化数据库
-        let db = Arc::new(db::Database::new(&config)?);
+        // 启动前检查数据库文件，损坏时尝试恢复
+        let backup_path = db::recovery::backup_path(&config.db_path);
+        let self_test_path = selftest::scratch_path(&config.db_path);
+        let recovery = db::recovery::check_and_recover(&config.db_path, &backup_path)?;
+
+        // 初始化数据库；同步完成、周期切换等数据层事件发布到服务的事件总线
+        let events = Arc::new(events::EventBus::new());
+        let db = Arc::new(
+            db::Database::new(&config)?
+                .with_clock(Arc::clone(&clock))
+                .with_events(&events),
+        );
         
         // 运行数据库迁移
         db.migrate()?;
 
         // 初始化各个组件
         let device_manager = Arc::new(device::DeviceManager::new(Arc::clone(&db))?);
+        let diagnostics = Arc::new(diagnostics::Diagnostics::new(
+            Arc::clone(&db),
+            config.diagnostics.clone(),
+        )?);
         let spatial_engine = Arc::new(spatial::SpatialEngine::new(Arc::clone(&db))?);
-        let flight_planner = Arc::new(flight::FlightPlanner::new(Arc::clone(&db))?);
+        let flight_planner = Arc::new(
+            flight::FlightPlanner::new(Arc::clone(&db))?
+                .with_expiry_policy(config.navdata_expiry.clone())
+                .with_freshness_policy(config.freshness.clone()),
+        );
+        let airports = Arc::new(repository::AirportRepository::new(Arc::clone(&db))?);
+        let waypoints = Arc::new(repository::WaypointRepository::new(Arc::clone(&db))?);
+        let navaids = Arc::new(repository::NavaidRepository::new(Arc::clone(&db))?);
+        let favorites = Arc::new(favorites::FavoritesManager::new(Arc::clone(&db))?);
+        let flags = Arc::new(flags::FeatureFlags::new(Arc::clone(&db))?);
+        let fleet = Arc::new(fleet::FleetManager::new(Arc::clone(&db))?);
+        let maintenance = Arc::new(maintenance::MaintenanceTracker::new(Arc::clone(&db))?);
+        let notams = Arc::new(notam::NotamManager::new(Arc::clone(&db))?);
+        let history = Arc::new(history::HistoryManager::new(Arc::clone(&db))?);
+        let storage = Arc::new(storage::StorageManager::new(Arc::clone(&db), config.storage.clone())?);
+        let weather = Arc::new(
+            weather::WeatherManager::new(Arc::clone(&db))?.with_storage(Arc::clone(&storage)),
+        );
+        let sync_scheduler = Arc::new(
+            sync::SyncScheduler::new(config.sync.clone()).with_clock(Arc::clone(&clock)),
+        );
+        let access = Arc::new(access::AccessControl::new(Arc::clone(&db))?);
+        let redaction = Arc::new(redaction::RedactionPolicy::new(Arc::clone(&db))?);
+        let rate_limiter = Arc::new(
+            ratelimit::RateLimiter::new(config.rate_limit.clone()).with_clock(Arc::clone(&clock)),
+        );
+        let flight_plans = Arc::new(
+            flight::repository::FlightPlanRepository::new(Arc::clone(&db))?
+                .with_events(Arc::clone(&events)),
+        );
+        if let Some(report) = &recovery {
+            events.publish(events::Event::DatabaseRecovered {
+                method: report.method.as_str().to_string(),
+                lost_tables: report.lost_tables.clone(),
+            });
+        }
 
         log::info!("AeroBase 初始化成功");
 
         Ok(Self {
             db,
+            clock,
+            backup_path,
+            self_test_path,
+            recovery,
             device_manager,
+            diagnostics,
             spatial_engine,
             flight_planner,
+            navdata_expiry: config.navdata_expiry.clone(),
+            freshness: config.freshness.clone(),
+            cloud_backup: config.cloud_backup.clone(),
+            source_text: config.source_text.clone(),
+            attachments: config.attachments.clone(),
+            flight_plans,
+            airports,
+            waypoints,
+            navaids,
+            favorites,
+            flags,
+            fleet,
+            maintenance,
+            notams,
+            history,
+            storage,
+            weather,
+            events,
+            sync_scheduler,
+            access,
+            redaction,
+            rate_limiter,
         })
     }
 
+    /// 获取时钟
+    pub fn clock(&self) -> &Arc<dyn clock::Clock> {
+        &self.clock
+    }
+
     /// 获取设备管理器
     pub fn device(&self) -> &device::DeviceManager {
         &self.device_manager
     }
 
+    /// 获取错误诊断收集器
+    pub fn diagnostics(&self) -> &diagnostics::Diagnostics {
+        &self.diagnostics
+    }
+
     /// 获取空间查询引擎
     pub fn spatial(&self) -> &spatial::SpatialEngine {
         &self.spatial_engine
@@ -84,6 +278,183 @@ impl AeroBase {
         &self.flight_planner
     }
 
+    /// 获取已保存飞行计划的存储库
+    pub fn flight_plans(&self) -> &flight::repository::FlightPlanRepository {
+        &self.flight_plans
+    }
+
+    /// 获取机场数据仓库
+    pub fn airports(&self) -> &repository::AirportRepository {
+        &self.airports
+    }
+
+    /// 获取航路点数据仓库
+    pub fn waypoints(&self) -> &repository::WaypointRepository {
+        &self.waypoints
+    }
+
+    /// 获取导航设施数据仓库
+    pub fn navaids(&self) -> &repository::NavaidRepository {
+        &self.navaids
+    }
+
+    /// 获取收藏与已保存搜索管理器
+    pub fn favorites(&self) -> &favorites::FavoritesManager {
+        &self.favorites
+    }
+
+    /// 获取服务器下发的功能开关
+    pub fn flags(&self) -> &flags::FeatureFlags {
+        &self.flags
+    }
+
+    /// 获取机队管理器
+    pub fn fleet(&self) -> &fleet::FleetManager {
+        &self.fleet
+    }
+
+    /// 获取飞行记录本与维护计数管理器
+    pub fn maintenance(&self) -> &maintenance::MaintenanceTracker {
+        &self.maintenance
+    }
+
+    /// 获取航行通告管理器
+    pub fn notams(&self) -> &notam::NotamManager {
+        &self.notams
+    }
+
+    /// 获取最近浏览/飞行历史记录
+    pub fn history(&self) -> &history::HistoryManager {
+        &self.history
+    }
+
+    /// 获取轨迹、瓦片缓存等大体积数据的存储管理器
+    pub fn storage(&self) -> &storage::StorageManager {
+        &self.storage
+    }
+
+    /// 获取气象数据管理器
+    pub fn weather(&self) -> &weather::WeatherManager {
+        &self.weather
+    }
+
+    /// 获取事件总线
+    pub fn events(&self) -> &Arc<events::EventBus> {
+        &self.events
+    }
+
+    /// 获取同步调度器
+    pub fn sync_scheduler(&self) -> &sync::SyncScheduler {
+        &self.sync_scheduler
+    }
+
+    /// 获取服务模式的角色访问控制
+    pub fn access(&self) -> &access::AccessControl {
+        &self.access
+    }
+
+    /// 获取服务模式的接口限流器（限额来自 `Config::rate_limit`）
+    pub fn rate_limiter(&self) -> &ratelimit::RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// 获取按配置文件隐藏敏感数据的策略
+    pub fn redaction(&self) -> &redaction::RedactionPolicy {
+        &self.redaction
+    }
+
+    /// 当前导航数据周期的有效状态
+    pub fn navdata_status(&self) -> Result<navdata::NavdataStatus> {
+        let current = navdata::cycle::current_cycle(&self.db)?;
+        Ok(self
+            .navdata_expiry
+            .status(current.as_ref(), self.clock.now().date_naive()))
+    }
+
+    /// 清空全部导航数据（保留用户数据），用于完整重新导入前
+    pub fn purge_navdata(&self) -> Result<usize> {
+        navdata::purge_navdata(&self.db)
+    }
+
+    /// 各动态数据集（气象、危险区、高空风）的新鲜度，供界面状态栏显示
+    pub fn data_freshness(&self) -> Result<Vec<freshness::DataFreshness>> {
+        freshness::data_freshness(&self.db, &self.freshness, self.clock.timestamp())
+    }
+
+    /// 验证并应用签名的导航数据增量包，成功后发布周期变更事件
+    pub fn apply_navdata_package(
+        &self,
+        package: &[u8],
+        public_key: &rsa::RsaPublicKey,
+    ) -> Result<sync::delta::DeltaSummary> {
+        self.apply_navdata_package_with(package, public_key, &import::ImportControl::default())
+    }
+
+    /// 应用签名的导航数据增量包，报告进度并支持取消（取消时数据库保持不变）
+    pub fn apply_navdata_package_with(
+        &self,
+        package: &[u8],
+        public_key: &rsa::RsaPublicKey,
+        control: &import::ImportControl,
+    ) -> Result<sync::delta::DeltaSummary> {
+        self.diagnostics.span("apply_navdata_package", || {
+            control.report(import::ImportPhase::Parsing, 0, None)?;
+            let delta = navdata::NavdataDelta::decode_with(package, public_key, &self.source_text)?;
+            sync::delta::apply_with(&self.db, &delta, control)
+        })
+    }
+
+    /// 获取导入器，从 X-Plane、OurAirports 或 OpenAIP 数据导入导航数据
+    pub fn import(&self) -> import::Importer {
+        import::Importer::new(Arc::clone(&self.db), self.source_text.clone())
+    }
+
+    /// 获取 Webhook 注册表
+    pub fn webhooks(&self) -> Result<events::webhook::WebhookRegistry> {
+        events::webhook::WebhookRegistry::new(Arc::clone(&self.db))
+    }
+
+    /// 本次启动时的数据库恢复结果；数据库完好时为 None
+    pub fn recovery_report(&self) -> Option<&db::recovery::RecoveryReport> {
+        self.recovery.as_ref()
+    }
+
+    /// 备份数据库，供下次启动时无法导出数据的情况下恢复
+    pub fn backup(&self) -> Result<()> {
+        db::recovery::backup(&*self.db.get_conn()?, &self.backup_path)
+    }
+
+    /// 启动自检：空间查询、航线计算、加解密往返与 fsync 延迟，用于在设备启动时发现存储性能下降
+    ///
+    /// 在数据库旁的临时文件上运行，不写入正式数据库
+    pub fn self_test(&self) -> selftest::SelfTestReport {
+        self.self_test_with(&selftest::SelfTestLimits::default())
+    }
+
+    /// 按指定耗时上限运行启动自检
+    pub fn self_test_with(&self, limits: &selftest::SelfTestLimits) -> selftest::SelfTestReport {
+        selftest::run(&self.self_test_path, limits)
+    }
+
+    /// 用户数据云端备份（需要配置 `user_db_path`），备份写入 `store`
+    pub fn user_data_backup(
+        &self,
+        store: Arc<dyn sync::backup::BackupStore>,
+    ) -> sync::backup::UserDataBackup {
+        sync::backup::UserDataBackup::new(Arc::clone(&self.db), store, self.cloud_backup.clone())
+            .with_attachment_dir(self.attachments.blob_dir.clone())
+    }
+
+    /// 附件存储，内容用 `encryptor`（如由用户密码或系统钥匙串中的密钥创建）加解密
+    pub fn attachments(&self, encryptor: encryption::AesEncryptor) -> attachments::AttachmentStore {
+        attachments::AttachmentStore::new(Arc::clone(&self.db), self.attachments.clone(), encryptor)
+    }
+
+    /// 连接池状态与交互、后台通道的等待统计
+    pub fn pool_stats(&self) -> db::lanes::PoolStats {
+        self.db.pool_stats()
+    }
+
     /// 获取数据库句柄
     pub fn db(&self) -> &db::Database {
         &self.db
@@ -104,9 +475,114 @@ mod tests {
             db_path,
             enable_wal: true,
             pool_size: 2,
+            ..Default::default()
         };
 
         let aerobase = AeroBase::new(config).await;
         assert!(aerobase.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_recover_from_backup() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            db_path: dir.path().join("test.db"),
+            enable_wal: true,
+            pool_size: 2,
+            ..Default::default()
+        };
+
+        let aerobase = AeroBase::new(config.clone()).await.unwrap();
+        assert!(aerobase.recovery_report().is_none());
+        aerobase
+            .flags()
+            .apply_sync(&[("new_router".to_string(), true)].into_iter().collect())
+            .unwrap();
+        aerobase.backup().unwrap();
+        drop(aerobase);
+
+        std::fs::write(&config.db_path, vec![0u8; 8192]).unwrap();
+        let aerobase = AeroBase::new(config).await.unwrap();
+        let report = aerobase.recovery_report().unwrap();
+        assert_eq!(report.method, db::recovery::RecoveryMethod::RestoredBackup);
+        assert!(aerobase.flags().is_enabled("new_router"));
+    }
+
+    #[tokio::test]
+    async fn test_id_strategy_per_instance() {
+        use models::waypoint::{Waypoint, WaypointType};
+
+        let dir = tempdir().unwrap();
+        let config = |name: &str, ids| Config {
+            db_path: dir.path().join(name),
+            enable_wal: false,
+            pool_size: 1,
+            ids,
+            ..Default::default()
+        };
+        let first = AeroBase::new(config("first.db", ids::IdStrategy::Natural)).await.unwrap();
+        let second = AeroBase::new(config("second.db", ids::IdStrategy::Ulid)).await.unwrap();
+
+        // Opening the second instance leaves the first one's strategy alone
+        let waypoint = |name: &str| {
+            let mut waypoint = Waypoint::new(
+                String::new(),
+                name.to_string(),
+                models::Coordinate::new(40.0, 116.0),
+                WaypointType::Fix,
+            );
+            waypoint.region = Some("ZB".to_string());
+            waypoint
+        };
+        assert_eq!(first.waypoints().insert(&waypoint("ALPHA")).unwrap().id, "ZB:ALPHA");
+        let ulid = second.waypoints().insert(&waypoint("ALPHA")).unwrap().id;
+        assert_eq!(ulid.len(), 26);
+        assert_eq!(first.db().id_strategy(), ids::IdStrategy::Natural);
+    }
+
+    #[tokio::test]
+    async fn test_navdata_status_follows_clock() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            db_path: dir.path().join("test.db"),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let cycle = navdata::AiracCycle::from_ident("2401").unwrap();
+        let clock = Arc::new(clock::MockClock::new(cycle.effective.and_hms_opt(12, 0, 0).unwrap().and_utc()));
+        let aerobase = AeroBase::with_clock(config, clock.clone()).await.unwrap();
+        navdata::cycle::install_cycle(aerobase.db(), &cycle).unwrap();
+
+        assert_eq!(aerobase.navdata_status().unwrap(), navdata::NavdataStatus::Current);
+        clock.advance(chrono::TimeDelta::days(navdata::cycle::CYCLE_DAYS));
+        assert_eq!(aerobase.navdata_status().unwrap(), navdata::NavdataStatus::Expired);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_from_config() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            db_path: dir.path().join("test.db"),
+            enable_wal: false,
+            pool_size: 1,
+            rate_limit: ratelimit::RateLimitConfig {
+                daily_quota: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let clock = Arc::new(clock::MockClock::at_timestamp(1_704_067_200));
+        let aerobase = AeroBase::with_clock(config, clock.clone()).await.unwrap();
+
+        let limiter = aerobase.rate_limiter();
+        assert!(limiter.check("tablet", Some("dev")).is_ok());
+        assert!(matches!(
+            limiter.check("tablet", Some("dev")),
+            Err(error::AeroBaseError::QuotaExceeded { limit: 1, .. })
+        ));
+        // The quota rolls over on the service clock's day
+        clock.advance(chrono::TimeDelta::days(1));
+        assert!(limiter.check("tablet", Some("dev")).is_ok());
+    }
 }
@@ -0,0 +1,131 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::RwLock;
+
+/// Hex digits kept from a hashed identifier
+const HASH_LEN: usize = 12;
+
+const DEFAULT_SALT: &str = "aerobase-logging";
+
+/// 日志中设备 ID、指纹、硬件信息等标识符的输出方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdentifierPolicy {
+    /// 原样输出
+    Plain,
+    /// 只保留前几个字符
+    Truncate { chars: usize },
+    /// 输出加盐哈希，同一标识符始终得到相同结果，便于关联日志
+    Hash,
+}
+
+impl IdentifierPolicy {
+    /// The identifier as it should appear in a log line
+    pub fn apply(&self, value: &str, salt: &str) -> String {
+        match self {
+            IdentifierPolicy::Plain => value.to_string(),
+            IdentifierPolicy::Truncate { chars } => {
+                let mut kept: String = value.chars().take(*chars).collect();
+                if kept.len() < value.len() {
+                    kept.push('…');
+                }
+                kept
+            }
+            IdentifierPolicy::Hash => {
+                let mut hasher = Sha256::new();
+                hasher.update(salt.as_bytes());
+                hasher.update(b":");
+                hasher.update(value.as_bytes());
+                format!("#{}", &format!("{:x}", hasher.finalize())[..HASH_LEN])
+            }
+        }
+    }
+}
+
+/// 日志脱敏配置（默认输出加盐哈希）
+#[derive(Clone, PartialEq)]
+pub struct LoggingConfig {
+    /// 标识符的输出方式
+    pub identifiers: IdentifierPolicy,
+    /// 哈希盐；各部署使用不同的盐，日志之间无法相互关联，也无法用字典还原
+    pub salt: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            identifiers: IdentifierPolicy::Hash,
+            salt: DEFAULT_SALT.to_string(),
+        }
+    }
+}
+
+// The configuration itself is logged at startup, so keep the salt out of it
+impl fmt::Debug for LoggingConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoggingConfig")
+            .field("identifiers", &self.identifiers)
+            .field("salt", &"<redacted>")
+            .finish()
+    }
+}
+
+// Log macros are global, so the policy is too
+static CONFIG: RwLock<Option<LoggingConfig>> = RwLock::new(None);
+
+/// 设置进程内日志脱敏配置（`AeroBase::new` 按 `Config::logging` 调用）
+pub fn set_config(config: LoggingConfig) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+}
+
+/// 当前日志脱敏配置
+pub fn config() -> LoggingConfig {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// An identifier formatted under the current policy when displayed
+///
+/// Use it for every device ID, fingerprint or hardware string in a log
+/// message: `log::info!("Created device {}", logging::id(&device.id))`.
+pub fn id(value: &str) -> Identifier<'_> {
+    Identifier(value)
+}
+
+pub struct Identifier<'a>(&'a str);
+
+impl fmt::Display for Identifier<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = CONFIG.read().unwrap_or_else(|e| e.into_inner());
+        let text = match config.as_ref() {
+            Some(config) => config.identifiers.apply(self.0, &config.salt),
+            None => IdentifierPolicy::Hash.apply(self.0, DEFAULT_SALT),
+        };
+        f.write_str(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_policies() {
+        let id = "3f1c2b9e-8d47-4c1a-9b0e-5d2f6a7c8e91";
+
+        assert_eq!(IdentifierPolicy::Plain.apply(id, "s"), id);
+        assert_eq!(IdentifierPolicy::Truncate { chars: 8 }.apply(id, "s"), "3f1c2b9e…");
+        assert_eq!(IdentifierPolicy::Truncate { chars: 8 }.apply("short", "s"), "short");
+
+        // Stable for correlation, different per salt, never the raw value
+        let hashed = IdentifierPolicy::Hash.apply(id, "site-a");
+        assert_eq!(hashed.len(), HASH_LEN + 1);
+        assert_eq!(hashed, IdentifierPolicy::Hash.apply(id, "site-a"));
+        assert_ne!(hashed, IdentifierPolicy::Hash.apply(id, "site-b"));
+        assert!(!hashed.contains("3f1c2b9e"));
+
+        let config = LoggingConfig {
+            salt: "secret-salt".to_string(),
+            ..Default::default()
+        };
+        assert!(!format!("{:?}", config).contains("secret-salt"));
+    }
+}
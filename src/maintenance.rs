@@ -0,0 +1,513 @@
+use crate::clock::Clock;
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// 航空器飞行记录本中的一条记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogbookEntry {
+    pub id: String,
+    /// 航空器注册号
+    pub registration: String,
+    /// 撤轮挡时间（Unix 秒）
+    pub flown_at: i64,
+    /// 轮挡时间（分钟）
+    pub block_minutes: i32,
+    /// 起落次数
+    pub cycles: i32,
+    /// 对应的已保存飞行计划
+    pub plan_id: Option<String>,
+    pub remarks: Option<String>,
+    pub created_at: i64,
+}
+
+/// 累计使用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageCounters {
+    pub hours: f64,
+    pub cycles: i64,
+}
+
+/// 维护间隔，任一项到期即到期；未设置的项不参与判断
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceInterval {
+    pub hours: Option<f64>,
+    pub cycles: Option<i64>,
+    pub days: Option<i64>,
+}
+
+impl MaintenanceInterval {
+    fn is_empty(&self) -> bool {
+        self.hours.is_none() && self.cycles.is_none() && self.days.is_none()
+    }
+}
+
+/// 维护项目（如 100 小时检、年检）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceItem {
+    pub id: String,
+    pub registration: String,
+    pub name: String,
+    pub interval: MaintenanceInterval,
+    /// 上次完成时间及当时的累计使用量
+    pub last_done_at: i64,
+    pub last_done: UsageCounters,
+}
+
+/// 维护项目的到期状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DueStatus {
+    /// 在提醒余量之内
+    DueSoon,
+    /// 已超过间隔
+    Overdue,
+}
+
+/// 即将到期或已到期的维护项目，剩余量为负表示已超出
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DueItem {
+    pub item: MaintenanceItem,
+    pub status: DueStatus,
+    pub hours_remaining: Option<f64>,
+    pub cycles_remaining: Option<i64>,
+    pub days_remaining: Option<i64>,
+}
+
+/// 到期提醒余量：剩余量不超过它时列为即将到期
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DueMargin {
+    pub hours: f64,
+    pub cycles: i64,
+    pub days: i64,
+}
+
+impl Default for DueMargin {
+    fn default() -> Self {
+        Self {
+            hours: 10.0,
+            cycles: 10,
+            days: 30,
+        }
+    }
+}
+
+const ENTRY_COLUMNS: &str =
+    "id, aircraft_id, flown_at, block_minutes, cycles, plan_id, remarks, created_at";
+
+const ITEM_COLUMNS: &str = "id, aircraft_id, name, interval_hours, interval_cycles, interval_days, \
+     last_done_at, last_done_hours, last_done_cycles";
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<LogbookEntry> {
+    Ok(LogbookEntry {
+        id: row.get(0)?,
+        registration: row.get(1)?,
+        flown_at: row.get(2)?,
+        block_minutes: row.get(3)?,
+        cycles: row.get(4)?,
+        plan_id: row.get(5)?,
+        remarks: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+fn item_from_row(row: &Row) -> rusqlite::Result<MaintenanceItem> {
+    Ok(MaintenanceItem {
+        id: row.get(0)?,
+        registration: row.get(1)?,
+        name: row.get(2)?,
+        interval: MaintenanceInterval {
+            hours: row.get(3)?,
+            cycles: row.get(4)?,
+            days: row.get(5)?,
+        },
+        last_done_at: row.get(6)?,
+        last_done: UsageCounters {
+            hours: row.get(7)?,
+            cycles: row.get(8)?,
+        },
+    })
+}
+
+/// Hours and cycles logged for `registration`
+fn counters(conn: &Connection, registration: &str) -> Result<UsageCounters> {
+    let (minutes, cycles): (i64, i64) = conn.query_row(
+        "SELECT COALESCE(SUM(block_minutes), 0), COALESCE(SUM(cycles), 0)
+         FROM logbook_entries WHERE aircraft_id = ?1",
+        [registration],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(UsageCounters {
+        hours: minutes as f64 / 60.0,
+        cycles,
+    })
+}
+
+/// 维护计数管理器
+///
+/// 航空器的累计小时与起落次数由飞行记录本汇总得出；维护项目记录间隔与上次
+/// 完成时的计数，据此列出即将到期和已到期的项目。记录本与维护项目同属用户数据，
+/// 随用户库一起同步和备份。
+pub struct MaintenanceTracker {
+    db: Arc<Database>,
+    clock: Arc<dyn Clock>,
+}
+
+impl MaintenanceTracker {
+    /// 创建维护计数管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self {
+            clock: Arc::clone(db.clock()),
+            db,
+        })
+    }
+
+    /// 按 `clock` 计算日历间隔和记录时间
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 向飞行记录本添加一条记录
+    pub fn log_flight(
+        &self,
+        registration: &str,
+        flown_at: i64,
+        block_minutes: i32,
+        cycles: i32,
+        plan_id: Option<&str>,
+        remarks: Option<&str>,
+    ) -> Result<LogbookEntry> {
+        if block_minutes < 0 || cycles < 0 {
+            return Err(AeroBaseError::InvalidInput(
+                "Block time and cycles cannot be negative".to_string(),
+            ));
+        }
+        let conn = self.db.get_conn()?;
+        let registration = self.require_aircraft(&conn, registration)?;
+        let entry = LogbookEntry {
+            id: self.db.new_id(),
+            registration,
+            flown_at,
+            block_minutes,
+            cycles,
+            plan_id: plan_id.map(str::to_string),
+            remarks: remarks.map(str::to_string),
+            created_at: self.clock.timestamp(),
+        };
+        conn.execute(
+            &format!(
+                "INSERT INTO logbook_entries ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                ENTRY_COLUMNS
+            ),
+            rusqlite::params![
+                entry.id,
+                entry.registration,
+                entry.flown_at,
+                entry.block_minutes,
+                entry.cycles,
+                entry.plan_id,
+                entry.remarks,
+                entry.created_at,
+            ],
+        )?;
+        Ok(entry)
+    }
+
+    /// 航空器的记录本（按时间排序）
+    pub fn logbook(&self, registration: &str) -> Result<Vec<LogbookEntry>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM logbook_entries WHERE aircraft_id = ?1 ORDER BY flown_at, id",
+            ENTRY_COLUMNS
+        ))?;
+        let entries = stmt
+            .query_map([registration.trim().to_uppercase()], entry_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// 删除记录本中的一条记录
+    pub fn delete_entry(&self, entry_id: &str) -> Result<bool> {
+        let removed = self
+            .db
+            .get_conn()?
+            .execute("DELETE FROM logbook_entries WHERE id = ?1", [entry_id])?;
+        Ok(removed > 0)
+    }
+
+    /// 航空器的累计小时与起落次数
+    pub fn counters(&self, registration: &str) -> Result<UsageCounters> {
+        let conn = self.db.get_conn()?;
+        let registration = self.require_aircraft(&conn, registration)?;
+        counters(&conn, &registration)
+    }
+
+    /// 添加维护项目，以当前时间和累计使用量作为起点
+    pub fn add_item(
+        &self,
+        registration: &str,
+        name: &str,
+        interval: MaintenanceInterval,
+    ) -> Result<MaintenanceItem> {
+        if interval.is_empty() {
+            return Err(AeroBaseError::InvalidInput(
+                "Maintenance interval needs hours, cycles or days".to_string(),
+            ));
+        }
+        let conn = self.db.get_conn()?;
+        let registration = self.require_aircraft(&conn, registration)?;
+        let now = self.clock.timestamp();
+        let item = MaintenanceItem {
+            id: self.db.new_id(),
+            last_done: counters(&conn, &registration)?,
+            registration,
+            name: name.to_string(),
+            interval,
+            last_done_at: now,
+        };
+        conn.execute(
+            &format!(
+                "INSERT INTO maintenance_items ({}, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                ITEM_COLUMNS
+            ),
+            rusqlite::params![
+                item.id,
+                item.registration,
+                item.name,
+                interval.hours,
+                interval.cycles,
+                interval.days,
+                item.last_done_at,
+                item.last_done.hours,
+                item.last_done.cycles,
+                now,
+            ],
+        )?;
+        Ok(item)
+    }
+
+    /// 记录维护项目已完成，以当前累计使用量重新计算间隔
+    pub fn complete(&self, item_id: &str) -> Result<MaintenanceItem> {
+        let conn = self.db.get_conn()?;
+        let item = conn
+            .query_row(
+                &format!("SELECT {} FROM maintenance_items WHERE id = ?1", ITEM_COLUMNS),
+                [item_id],
+                item_from_row,
+            )
+            .optional()?
+            .ok_or_else(|| {
+                AeroBaseError::NotFound(format!("Maintenance item {} not found", item_id))
+            })?;
+        let now = self.clock.timestamp();
+        let usage = counters(&conn, &item.registration)?;
+        conn.execute(
+            "UPDATE maintenance_items
+             SET last_done_at = ?1, last_done_hours = ?2, last_done_cycles = ?3, updated_at = ?1
+             WHERE id = ?4",
+            rusqlite::params![now, usage.hours, usage.cycles, item_id],
+        )?;
+        Ok(MaintenanceItem {
+            last_done_at: now,
+            last_done: usage,
+            ..item
+        })
+    }
+
+    /// 删除维护项目
+    pub fn remove_item(&self, item_id: &str) -> Result<bool> {
+        let removed = self
+            .db
+            .get_conn()?
+            .execute("DELETE FROM maintenance_items WHERE id = ?1", [item_id])?;
+        Ok(removed > 0)
+    }
+
+    /// 航空器的维护项目（按名称排序）
+    pub fn items(&self, registration: &str) -> Result<Vec<MaintenanceItem>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM maintenance_items WHERE aircraft_id = ?1 ORDER BY name, id",
+            ITEM_COLUMNS
+        ))?;
+        let items = stmt
+            .query_map([registration.trim().to_uppercase()], item_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    /// 即将到期和已到期的维护项目，已到期的在前
+    pub fn due(&self, registration: &str, margin: &DueMargin) -> Result<Vec<DueItem>> {
+        let usage = self.counters(registration)?;
+        let now = self.clock.timestamp();
+
+        let mut due: Vec<DueItem> = self
+            .items(registration)?
+            .into_iter()
+            .filter_map(|item| {
+                let interval = item.interval;
+                let hours = interval
+                    .hours
+                    .map(|hours| item.last_done.hours + hours - usage.hours);
+                let cycles = interval
+                    .cycles
+                    .map(|cycles| item.last_done.cycles + cycles - usage.cycles);
+                let days = interval.days.map(|days| {
+                    (item.last_done_at + days * SECONDS_PER_DAY - now) / SECONDS_PER_DAY
+                });
+
+                let overdue = hours.is_some_and(|h| h < 0.0)
+                    || cycles.is_some_and(|c| c < 0)
+                    || days.is_some_and(|d| d < 0);
+                let due_soon = hours.is_some_and(|h| h <= margin.hours)
+                    || cycles.is_some_and(|c| c <= margin.cycles)
+                    || days.is_some_and(|d| d <= margin.days);
+                let status = if overdue {
+                    DueStatus::Overdue
+                } else if due_soon {
+                    DueStatus::DueSoon
+                } else {
+                    return None;
+                };
+                Some(DueItem {
+                    item,
+                    status,
+                    hours_remaining: hours,
+                    cycles_remaining: cycles,
+                    days_remaining: days,
+                })
+            })
+            .collect();
+        due.sort_by_key(|d| std::cmp::Reverse(d.status));
+        Ok(due)
+    }
+
+    fn require_aircraft(&self, conn: &Connection, registration: &str) -> Result<String> {
+        let registration = registration.trim().to_uppercase();
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM aircraft WHERE id = ?1)",
+            [&registration],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(AeroBaseError::NotFound(format!("Aircraft {} not found", registration)));
+        }
+        Ok(registration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::fleet::{AircraftPerformance, FleetManager};
+    use crate::Config;
+    use chrono::TimeDelta;
+    use tempfile::NamedTempFile;
+
+    fn setup() -> (MaintenanceTracker, Arc<MockClock>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let performance = AircraftPerformance {
+            cruise_speed: 120,
+            cruise_altitude: 8500,
+            fuel_flow_gph: 9.0,
+        };
+        FleetManager::new(Arc::clone(&db))
+            .unwrap()
+            .save("B-1234", "C172", &performance, "S")
+            .unwrap();
+        let clock = Arc::new(MockClock::at_timestamp(1_700_000_000));
+        let tracker = MaintenanceTracker::new(db).unwrap().with_clock(clock.clone());
+        (tracker, clock, temp_file)
+    }
+
+    #[test]
+    fn test_counters_from_logbook() {
+        let (tracker, _clock, _temp_file) = setup();
+        assert_eq!(tracker.counters("B-1234").unwrap(), UsageCounters::default());
+
+        tracker.log_flight("b-1234", 200, 90, 1, None, Some("Pattern work")).unwrap();
+        let first = tracker.log_flight("B-1234", 100, 60, 3, None, None).unwrap();
+        assert_eq!(
+            tracker.counters("B-1234").unwrap(),
+            UsageCounters { hours: 2.5, cycles: 4 }
+        );
+        assert_eq!(tracker.logbook("B-1234").unwrap()[0].id, first.id);
+
+        assert!(tracker.delete_entry(&first.id).unwrap());
+        assert_eq!(tracker.counters("B-1234").unwrap().cycles, 1);
+        assert!(matches!(
+            tracker.log_flight("B-0000", 0, 60, 1, None, None),
+            Err(AeroBaseError::NotFound(_))
+        ));
+        assert!(tracker.log_flight("B-1234", 0, -5, 1, None, None).is_err());
+    }
+
+    #[test]
+    fn test_due_items() {
+        let (tracker, clock, _temp_file) = setup();
+        let hundred_hour = tracker
+            .add_item(
+                "B-1234",
+                "100-hour",
+                MaintenanceInterval {
+                    hours: Some(100.0),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        tracker
+            .add_item(
+                "B-1234",
+                "Annual",
+                MaintenanceInterval {
+                    days: Some(365),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(tracker
+            .add_item("B-1234", "Nothing", MaintenanceInterval::default())
+            .is_err());
+        let margin = DueMargin::default();
+        assert!(tracker.due("B-1234", &margin).unwrap().is_empty());
+
+        // 95 hours flown: the 100-hour check is within the 10-hour margin
+        tracker.log_flight("B-1234", 0, 95 * 60, 40, None, None).unwrap();
+        let due = tracker.due("B-1234", &margin).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].status, DueStatus::DueSoon);
+        assert_eq!(due[0].hours_remaining, Some(5.0));
+
+        // 340 days later, over 100 hours: both listed, overdue first
+        clock.advance(TimeDelta::days(340));
+        tracker.log_flight("B-1234", 0, 10 * 60, 5, None, None).unwrap();
+        let due = tracker.due("B-1234", &margin).unwrap();
+        let names: Vec<(&str, DueStatus)> =
+            due.iter().map(|d| (d.item.name.as_str(), d.status)).collect();
+        assert_eq!(
+            names,
+            vec![("100-hour", DueStatus::Overdue), ("Annual", DueStatus::DueSoon)]
+        );
+        assert_eq!(due[1].days_remaining, Some(25));
+
+        let done = tracker.complete(&hundred_hour.id).unwrap();
+        assert_eq!(done.last_done.hours, 105.0);
+        assert_eq!(tracker.due("B-1234", &margin).unwrap().len(), 1);
+        assert!(tracker.remove_item(&hundred_hour.id).unwrap());
+        assert_eq!(tracker.items("B-1234").unwrap().len(), 1);
+    }
+}
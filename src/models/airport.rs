@@ -13,6 +13,33 @@ pub struct Airport {
     pub country: Option<String>,
     pub region: Option<String>,
     pub created_at: i64,
+    #[serde(default)]
+    pub transition_altitude: Option<i32>, // 过渡高度，英尺
+    #[serde(default)]
+    pub transition_level: Option<i32>,    // 过渡高度层，英尺（FL110 即 11000）
+    #[serde(default)]
+    pub magnetic_variation: Option<f64>,  // 磁差（度，东偏为正）
+}
+
+/// 机场通信频率
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirportFrequency {
+    pub id: String,
+    pub airport_id: String,
+    pub frequency_type: String,      // 例如 ATIS、TWR、GND、APP
+    pub frequency: f64,              // MHz
+    pub description: Option<String>,
+    pub created_at: i64,
+}
+
+/// 机场别名（旧代码、地方代码等）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirportAlias {
+    pub id: String,
+    pub airport_id: String,
+    pub code: String,
+    pub kind: Option<String>, // 例如 FORMER、LOCAL
+    pub created_at: i64,
 }
 
 impl Airport {
@@ -33,6 +60,9 @@ impl Airport {
             country: None,
             region: None,
             created_at: chrono::Utc::now().timestamp(),
+            transition_altitude: None,
+            transition_level: None,
+            magnetic_variation: None,
         }
     }
 
@@ -26,6 +26,7 @@ impl AirspaceClass {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_uppercase().as_str() {
             "A" | "CLASS A" => AirspaceClass::ClassA,
@@ -64,6 +65,7 @@ impl AirspaceType {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_uppercase().as_str() {
             "CTR" | "CONTROL ZONE" => AirspaceType::ControlZone,
@@ -77,6 +79,48 @@ impl AirspaceType {
     }
 }
 
+/// 巡航高度层配备方案
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LevelScheme {
+    IcaoFeet,    // ICAO 半圆规则（英尺）
+    MetricRvsm,  // 米制 RVSM（中国）
+    Quadrantal,  // 英国象限规则（历史，FL245 以下）
+}
+
+impl LevelScheme {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LevelScheme::IcaoFeet => "ICAO",
+            LevelScheme::MetricRvsm => "METRIC",
+            LevelScheme::Quadrantal => "QUADRANTAL",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "ICAO" | "FEET" => Some(LevelScheme::IcaoFeet),
+            "METRIC" | "METRIC_RVSM" => Some(LevelScheme::MetricRvsm),
+            "QUADRANTAL" => Some(LevelScheme::Quadrantal),
+            _ => None,
+        }
+    }
+
+    /// 国家默认的高度层方案（ISO 3166 二位或三位代码，或英文国名）
+    pub fn for_country(country: &str) -> Self {
+        match country.trim().to_uppercase().as_str() {
+            "CN" | "CHN" | "CHINA" => LevelScheme::MetricRvsm,
+            _ => LevelScheme::IcaoFeet,
+        }
+    }
+}
+
+/// RVSM 高度范围下限（FL290，英尺）
+pub const RVSM_LOWER_LIMIT: i32 = 29000;
+
+/// RVSM 高度范围上限（FL410，英尺）
+pub const RVSM_UPPER_LIMIT: i32 = 41000;
+
 /// 空域
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Airspace {
@@ -87,6 +131,10 @@ pub struct Airspace {
     pub lower_limit: Option<i32>, // 英尺
     pub upper_limit: Option<i32>, // 英尺
     pub created_at: i64,
+    #[serde(default)]
+    pub rvsm: bool, // 是否实施缩小垂直间隔（RVSM）
+    #[serde(default)]
+    pub level_scheme: Option<LevelScheme>, // 区域内使用的高度层方案
 }
 
 impl Airspace {
@@ -100,9 +148,18 @@ impl Airspace {
             lower_limit: None,
             upper_limit: None,
             created_at: chrono::Utc::now().timestamp(),
+            rvsm: false,
+            level_scheme: None,
         }
     }
 
+    /// 检查高度是否处于本空域的 RVSM 高度范围（FL290–FL410）
+    pub fn requires_rvsm(&self, altitude: i32) -> bool {
+        self.rvsm
+            && (RVSM_LOWER_LIMIT..=RVSM_UPPER_LIMIT).contains(&altitude)
+            && self.is_altitude_in_airspace(altitude)
+    }
+
     /// 检查高度是否在空域范围内
     pub fn is_altitude_in_airspace(&self, altitude: i32) -> bool {
         match (self.lower_limit, self.upper_limit) {
@@ -172,10 +229,38 @@ mod tests {
         assert!(!airspace.is_altitude_in_airspace(20000));
     }
 
+    #[test]
+    fn test_requires_rvsm() {
+        let mut fir = Airspace::new(
+            "FIR1".to_string(),
+            "Beijing FIR".to_string(),
+            AirspaceType::FlightInfoRegion,
+        );
+        assert!(!fir.requires_rvsm(35000));
+
+        fir.rvsm = true;
+        assert!(fir.requires_rvsm(29000));
+        assert!(fir.requires_rvsm(41000));
+        assert!(!fir.requires_rvsm(28000));
+        assert!(!fir.requires_rvsm(43000));
+
+        fir.upper_limit = Some(33000);
+        assert!(!fir.requires_rvsm(35000));
+    }
+
     #[test]
     fn test_airspace_class_conversion() {
         assert_eq!(AirspaceClass::from_str("B"), AirspaceClass::ClassB);
         assert_eq!(AirspaceClass::from_str("class c"), AirspaceClass::ClassC);
         assert_eq!(AirspaceClass::ClassD.as_str(), "D");
     }
+
+    #[test]
+    fn test_level_scheme() {
+        assert_eq!(LevelScheme::from_str("metric"), Some(LevelScheme::MetricRvsm));
+        assert_eq!(LevelScheme::Quadrantal.as_str(), "QUADRANTAL");
+        assert_eq!(LevelScheme::from_str("NONE"), None);
+        assert_eq!(LevelScheme::for_country("chn"), LevelScheme::MetricRvsm);
+        assert_eq!(LevelScheme::for_country("GB"), LevelScheme::IcaoFeet);
+    }
 }
@@ -19,6 +19,7 @@ impl AirwayType {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_uppercase().as_str() {
             "HIGH" => AirwayType::High,
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// 航图类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChartType {
+    Airport,    // 机场图
+    Departure,  // 离场图（SID）
+    Arrival,    // 进场图（STAR）
+    Approach,   // 进近图
+    Other,
+}
+
+impl ChartType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ChartType::Airport => "AIRPORT",
+            ChartType::Departure => "DEPARTURE",
+            ChartType::Arrival => "ARRIVAL",
+            ChartType::Approach => "APPROACH",
+            ChartType::Other => "OTHER",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "AIRPORT" | "APD" => ChartType::Airport,
+            "DEPARTURE" | "SID" | "DP" => ChartType::Departure,
+            "ARRIVAL" | "STAR" => ChartType::Arrival,
+            "APPROACH" | "IAP" | "APP" => ChartType::Approach,
+            _ => ChartType::Other,
+        }
+    }
+}
+
+/// 航图索引条目（图本身由 EFB 按 `file_ref` 打开）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chart {
+    pub id: String,
+    pub airport_id: String,
+    /// 描绘的程序；机场图等非程序航图为 None
+    pub procedure_id: Option<String>,
+    pub identifier: String,       // 航图编号，例如 "ZBAA-7A" 或 "11-1"
+    pub chart_type: ChartType,
+    pub title: String,
+    pub effective_cycle: String,  // 生效的 AIRAC 周期，例如 "2410"
+    pub file_ref: String,         // 航图文件的路径或 URL
+    pub created_at: i64,
+}
@@ -0,0 +1,99 @@
+use super::Coordinate;
+use crate::spatial::geometry;
+use serde::{Deserialize, Serialize};
+
+/// 动态危险区类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HazardKind {
+    Tfr,           // 临时飞行限制
+    VolcanicAsh,   // 火山灰
+    ConflictZone,  // 冲突地区
+    Other,
+}
+
+impl HazardKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            HazardKind::Tfr => "TFR",
+            HazardKind::VolcanicAsh => "VOLCANIC_ASH",
+            HazardKind::ConflictZone => "CONFLICT_ZONE",
+            HazardKind::Other => "OTHER",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "TFR" => HazardKind::Tfr,
+            "VOLCANIC_ASH" | "ASH" => HazardKind::VolcanicAsh,
+            "CONFLICT_ZONE" => HazardKind::ConflictZone,
+            _ => HazardKind::Other,
+        }
+    }
+
+    /// 是否禁止进入（临时飞行限制和火山灰）；其余类别仅作提示
+    pub fn is_restrictive(&self) -> bool {
+        matches!(self, HazardKind::Tfr | HazardKind::VolcanicAsh)
+    }
+}
+
+/// 动态危险区（在导航数据周期之间由同步下发）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hazard {
+    pub id: String,
+    pub kind: HazardKind,
+    pub name: String,
+    pub lower_limit: Option<i32>,   // 英尺，None 表示地面
+    pub upper_limit: Option<i32>,   // 英尺，None 表示无上限
+    pub valid_from: i64,
+    pub valid_to: Option<i64>,      // None 表示直至撤销
+    pub message: Option<String>,
+    pub boundary: Vec<Coordinate>,
+}
+
+impl Hazard {
+    /// 判断坐标是否在危险区水平范围内
+    pub fn contains(&self, point: Coordinate) -> bool {
+        geometry::point_in_polygon(point, &self.boundary)
+    }
+
+    /// 判断高度是否在危险区垂直范围内
+    pub fn applies_at(&self, altitude: i32) -> bool {
+        self.lower_limit.is_none_or(|l| altitude >= l) && self.upper_limit.is_none_or(|u| altitude <= u)
+    }
+
+    /// 判断危险区在指定时刻是否生效
+    pub fn is_active_at(&self, at: i64) -> bool {
+        at >= self.valid_from && self.valid_to.is_none_or(|to| at <= to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hazard_limits() {
+        let hazard = Hazard {
+            id: "H1".to_string(),
+            kind: HazardKind::from_str("volcanic_ash"),
+            name: "Ash".to_string(),
+            lower_limit: None,
+            upper_limit: Some(35000),
+            valid_from: 100,
+            valid_to: None,
+            message: None,
+            boundary: vec![
+                Coordinate::new(0.0, 0.0),
+                Coordinate::new(0.0, 1.0),
+                Coordinate::new(1.0, 1.0),
+                Coordinate::new(1.0, 0.0),
+            ],
+        };
+        assert!(hazard.kind.is_restrictive());
+        assert!(hazard.contains(Coordinate::new(0.5, 0.5)));
+        assert!(hazard.applies_at(0) && hazard.applies_at(35000) && !hazard.applies_at(37000));
+        assert!(!hazard.is_active_at(99) && hazard.is_active_at(i64::MAX));
+        assert_eq!(HazardKind::from_str("NOTAM"), HazardKind::Other);
+    }
+}
@@ -0,0 +1,67 @@
+use super::Coordinate;
+use serde::{Deserialize, Serialize};
+
+/// 转弯方向（等待程序、起落航线）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TurnDirection {
+    Left,
+    Right,
+}
+
+impl TurnDirection {
+    /// 相反的转弯方向
+    pub fn opposite(self) -> Self {
+        match self {
+            TurnDirection::Left => TurnDirection::Right,
+            TurnDirection::Right => TurnDirection::Left,
+        }
+    }
+
+    /// 航向变化的符号（右转为正）
+    pub fn sign(self) -> f64 {
+        match self {
+            TurnDirection::Left => -1.0,
+            TurnDirection::Right => 1.0,
+        }
+    }
+}
+
+/// 等待程序
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hold {
+    pub fix_id: String,
+    pub fix: Coordinate,
+    pub inbound_course: f64,   // 入航航迹（度）
+    pub turn: TurnDirection,
+    pub leg_minutes: f64,      // 出航边时间（分钟）
+}
+
+impl Hold {
+    /// 创建标准右转、1 分钟出航边的等待程序
+    pub fn new(fix_id: String, fix: Coordinate, inbound_course: f64) -> Self {
+        Self {
+            fix_id,
+            fix,
+            inbound_course,
+            turn: TurnDirection::Right,
+            leg_minutes: 1.0,
+        }
+    }
+
+    /// 出航航迹（度）
+    pub fn outbound_course(&self) -> f64 {
+        (self.inbound_course + 180.0).rem_euclid(360.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outbound_course() {
+        let hold = Hold::new("ABC".to_string(), Coordinate::new(0.0, 0.0), 270.0);
+        assert_eq!(hold.outbound_course(), 90.0);
+        assert_eq!(hold.turn.opposite(), TurnDirection::Left);
+    }
+}
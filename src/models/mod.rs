@@ -1,7 +1,14 @@
 pub mod airport;
 pub mod airspace;
 pub mod airway;
+pub mod chart;
+pub mod hazard;
+pub mod hold;
 pub mod navaid;
+pub mod obstacle;
+pub mod procedure;
+pub mod runway;
+pub mod uas_zone;
 pub mod waypoint;
 
 use geo::Point;
@@ -1,4 +1,5 @@
 use super::Coordinate;
+use crate::spatial::geometry;
 use serde::{Deserialize, Serialize};
 
 /// 导航设施类型
@@ -24,6 +25,7 @@ impl NavaidType {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_uppercase().as_str() {
             "VOR" => NavaidType::VOR,
@@ -48,6 +50,8 @@ pub struct Navaid {
     pub elevation: Option<i32>, // 英尺
     pub region: Option<String>,
     pub created_at: i64,
+    #[serde(default)]
+    pub declination: Option<f64>, // 台站磁偏角（度，东偏为正）
 }
 
 impl Navaid {
@@ -68,6 +72,7 @@ impl Navaid {
             elevation: None,
             region: None,
             created_at: chrono::Utc::now().timestamp(),
+            declination: None,
         }
     }
 
@@ -76,6 +81,22 @@ impl Navaid {
         self.coordinate.distance_to(&coord)
     }
 
+    /// 坐标所在的径向线（磁方位 0..360）
+    ///
+    /// 使用台站磁偏角而不是当地磁差：VOR 按台站磁偏角校准，两者可能相差数度。
+    /// 未录入磁偏角时返回 None
+    pub fn radial_of(&self, coord: Coordinate) -> Option<f64> {
+        let declination = self.declination?;
+        Some((self.coordinate.bearing_to(&coord) - declination).rem_euclid(360.0))
+    }
+
+    /// 径向线上指定距离（海里）处的坐标；未录入磁偏角时返回 None
+    pub fn point_on_radial(&self, radial: f64, distance_nm: f64) -> Option<Coordinate> {
+        let declination = self.declination?;
+        let true_bearing = (radial + declination).rem_euclid(360.0);
+        Some(geometry::destination_point(self.coordinate, distance_nm, true_bearing))
+    }
+
     /// 检查坐标是否在导航设施范围内
     pub fn is_in_range(&self, coord: Coordinate) -> bool {
         if let Some(range) = self.range_nm {
@@ -122,6 +143,32 @@ mod tests {
         assert!(!navaid.is_in_range(Coordinate::new(10.0, 10.0)));
     }
 
+    #[test]
+    fn test_radials_use_station_declination() {
+        let mut vor = Navaid::new(
+            "NAV001".to_string(),
+            "TEST".to_string(),
+            NavaidType::VOR,
+            Coordinate::new(0.0, 0.0),
+        );
+        let east = Coordinate::new(0.0, 1.0);
+        assert_eq!(vor.radial_of(east), None);
+        assert_eq!(vor.point_on_radial(90.0, 60.0), None);
+
+        // 10° east declination: a point due east lies on the 080 radial
+        vor.declination = Some(10.0);
+        assert!((vor.radial_of(east).unwrap() - 80.0).abs() < 1e-6);
+        let point = vor.point_on_radial(80.0, 60.0).unwrap();
+        assert!(point.latitude.abs() < 1e-6);
+        assert!((vor.distance_from(point) - 60.0).abs() < 0.01);
+
+        // Westerly declination wraps through north
+        vor.declination = Some(-5.0);
+        assert!((vor.radial_of(Coordinate::new(1.0, 0.0)).unwrap() - 5.0).abs() < 1e-6);
+        vor.declination = Some(5.0);
+        assert!((vor.radial_of(Coordinate::new(1.0, 0.0)).unwrap() - 355.0).abs() < 1e-6);
+    }
+
     #[test]
     fn test_navaid_type_conversion() {
         assert_eq!(NavaidType::from_str("VOR-DME"), NavaidType::VORDME);
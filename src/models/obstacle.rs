@@ -0,0 +1,59 @@
+use super::Coordinate;
+use serde::{Deserialize, Serialize};
+
+/// 地形网格分辨率（每度的格数，1 角分一格）
+pub const TERRAIN_CELLS_PER_DEGREE: f64 = 60.0;
+
+/// 障碍物（塔、烟囱、风机等）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub id: String,
+    pub name: Option<String>,
+    pub obstacle_type: String,
+    pub coordinate: Coordinate,
+    pub elevation: i32,          // 顶端海拔，英尺
+    pub height: Option<i32>,     // 离地高度，英尺
+    pub lighted: bool,
+    pub created_at: i64,
+}
+
+/// 地形网格单元（记录格内最高地形海拔）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TerrainCell {
+    pub cell_lat: i32,
+    pub cell_lon: i32,
+    pub max_elevation: i32, // 英尺
+}
+
+impl TerrainCell {
+    /// 计算坐标所在网格的索引
+    pub fn index(coord: Coordinate) -> (i32, i32) {
+        (
+            (coord.latitude * TERRAIN_CELLS_PER_DEGREE).floor() as i32,
+            (coord.longitude * TERRAIN_CELLS_PER_DEGREE).floor() as i32,
+        )
+    }
+
+    /// 网格单元的数据库主键
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.cell_lat, self.cell_lon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terrain_cell_index() {
+        assert_eq!(TerrainCell::index(Coordinate::new(30.51, 120.02)), (1830, 7201));
+        assert_eq!(TerrainCell::index(Coordinate::new(-0.01, -0.01)), (-1, -1));
+
+        let cell = TerrainCell {
+            cell_lat: 1830,
+            cell_lon: 7201,
+            max_elevation: 1200,
+        };
+        assert_eq!(cell.id(), "1830:7201");
+    }
+}
@@ -0,0 +1,133 @@
+use super::hold::TurnDirection;
+use serde::{Deserialize, Serialize};
+
+/// 程序类型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProcedureType {
+    SID,      // 标准仪表离场
+    STAR,     // 标准终端进场
+    Approach, // 进近程序
+    Other,
+}
+
+impl ProcedureType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ProcedureType::SID => "SID",
+            ProcedureType::STAR => "STAR",
+            ProcedureType::Approach => "APPROACH",
+            ProcedureType::Other => "OTHER",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "SID" => ProcedureType::SID,
+            "STAR" => ProcedureType::STAR,
+            "APPROACH" | "APP" | "IAP" => ProcedureType::Approach,
+            _ => ProcedureType::Other,
+        }
+    }
+}
+
+/// 机场程序（SID / STAR / 进近）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Procedure {
+    pub id: String,
+    pub airport_id: String,
+    pub name: String,
+    pub procedure_type: ProcedureType,
+    pub runway: Option<String>,    // 适用跑道，例如 "18L"
+    pub transition: Option<String>,
+    pub created_at: i64,
+}
+
+/// ARINC 424 航段终止码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathTerminator {
+    IF, // 起始定位点
+    TF, // 定位点间航迹
+    CF, // 沿航迹至定位点
+    DF, // 直飞定位点
+    RF, // 固定半径圆弧
+    CA, // 沿航迹至高度
+    VA, // 沿航向至高度
+    FA, // 自定位点沿航迹至高度
+    HA, // 等待至高度
+    HF, // 等待一圈后离开
+    HM, // 等待至人工终止
+    Other,
+}
+
+impl PathTerminator {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PathTerminator::IF => "IF",
+            PathTerminator::TF => "TF",
+            PathTerminator::CF => "CF",
+            PathTerminator::DF => "DF",
+            PathTerminator::RF => "RF",
+            PathTerminator::CA => "CA",
+            PathTerminator::VA => "VA",
+            PathTerminator::FA => "FA",
+            PathTerminator::HA => "HA",
+            PathTerminator::HF => "HF",
+            PathTerminator::HM => "HM",
+            PathTerminator::Other => "OTHER",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "IF" => PathTerminator::IF,
+            "TF" => PathTerminator::TF,
+            "CF" => PathTerminator::CF,
+            "DF" => PathTerminator::DF,
+            "RF" => PathTerminator::RF,
+            "CA" => PathTerminator::CA,
+            "VA" => PathTerminator::VA,
+            "FA" => PathTerminator::FA,
+            "HA" => PathTerminator::HA,
+            "HF" => PathTerminator::HF,
+            "HM" => PathTerminator::HM,
+            _ => PathTerminator::Other,
+        }
+    }
+}
+
+/// 程序航段（按 sequence 顺序飞行）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcedureLeg {
+    pub id: String,
+    pub procedure_id: String,
+    pub sequence: i32,
+    pub path_terminator: PathTerminator,
+    pub fix_id: Option<String>,        // 终止定位点（航路点或导航台）
+    pub course: Option<f64>,           // 磁航迹/航向（度）；等待航段为入航航迹
+    pub turn: Option<TurnDirection>,   // RF 与等待航段的转弯方向
+    pub altitude: Option<i32>,         // 英尺，CA/VA/FA/HA 的终止高度
+    pub distance_nm: Option<f64>,      // 等待航段出航边长度（海里）
+    pub center_fix_id: Option<String>, // RF 圆弧中心
+    pub created_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_procedure_type() {
+        assert_eq!(ProcedureType::from_str("app"), ProcedureType::Approach);
+        assert_eq!(ProcedureType::from_str("SID").as_str(), "SID");
+        assert_eq!(ProcedureType::from_str("xyz"), ProcedureType::Other);
+    }
+
+    #[test]
+    fn test_path_terminator() {
+        assert_eq!(PathTerminator::from_str("rf"), PathTerminator::RF);
+        assert_eq!(PathTerminator::HM.as_str(), "HM");
+        assert_eq!(PathTerminator::from_str("PI"), PathTerminator::Other);
+    }
+}
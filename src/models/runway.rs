@@ -0,0 +1,103 @@
+use super::Coordinate;
+use serde::{Deserialize, Serialize};
+
+/// 地面风
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Wind {
+    pub direction: f64,   // 风向（真方位，风的来向，度）
+    pub speed: f64,       // 节
+}
+
+impl Wind {
+    /// 创建新的风数据
+    pub fn new(direction: f64, speed: f64) -> Self {
+        Self { direction, speed }
+    }
+}
+
+/// 跑道（每个跑道端一条记录，例如 18L 与 36R 分别存储）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Runway {
+    pub id: String,
+    pub airport_id: String,
+    pub ident: String,               // 例如 "18L"
+    pub heading: Option<f64>,        // 真航向（度）
+    pub length_ft: Option<i32>,      // 英尺
+    pub width_ft: Option<i32>,       // 英尺
+    pub surface: Option<String>,
+    pub threshold: Option<Coordinate>,
+    pub elevation: Option<i32>,      // 入口标高（英尺）
+    pub ils: Option<Localizer>,
+    pub created_at: i64,
+}
+
+/// 跑道端的航向台（ILS/LOC）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Localizer {
+    pub ident: String,
+    pub frequency: f64,              // MHz
+    pub course: Option<f64>,         // 航向道航迹（度）
+}
+
+impl Runway {
+    /// 创建新的跑道端
+    pub fn new(id: String, airport_id: String, ident: String) -> Self {
+        Self {
+            id,
+            airport_id,
+            ident,
+            heading: None,
+            length_ft: None,
+            width_ft: None,
+            surface: None,
+            threshold: None,
+            elevation: None,
+            ils: None,
+            created_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// 跑道真航向；缺失时由跑道号推算的磁航向加上机场磁差
+    /// `magnetic_variation`（度，东偏为正）得出，磁差未知时返回 None
+    pub fn effective_heading(&self, magnetic_variation: Option<f64>) -> Option<f64> {
+        self.heading.or_else(|| {
+            Some((self.designator_heading()? + magnetic_variation?).rem_euclid(360.0))
+        })
+    }
+
+    /// 跑道号对应的磁航向近似值（度），例如 "18L" -> 180
+    pub fn designator_heading(&self) -> Option<f64> {
+        let digits: String = self.ident.chars().take_while(|c| c.is_ascii_digit()).collect();
+        match digits.parse::<u32>() {
+            Ok(n) if (1..=36).contains(&n) => Some(f64::from(n * 10)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_designator_heading() {
+        let runway = Runway::new("R1".to_string(), "AP1".to_string(), "18L".to_string());
+        assert_eq!(runway.designator_heading(), Some(180.0));
+
+        let helipad = Runway::new("R2".to_string(), "AP1".to_string(), "H1".to_string());
+        assert_eq!(helipad.designator_heading(), None);
+    }
+
+    #[test]
+    fn test_effective_heading_is_true() {
+        let mut runway = Runway::new("R1".to_string(), "AP1".to_string(), "36".to_string());
+        // Magnetic 360 with 5° west variation is 355 true
+        assert_eq!(runway.effective_heading(Some(-5.0)), Some(355.0));
+        assert_eq!(runway.effective_heading(Some(10.0)), Some(10.0));
+        assert_eq!(runway.effective_heading(None), None);
+
+        runway.heading = Some(352.0);
+        assert_eq!(runway.effective_heading(None), Some(352.0));
+        assert_eq!(runway.effective_heading(Some(-5.0)), Some(352.0));
+    }
+}
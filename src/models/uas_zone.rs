@@ -0,0 +1,94 @@
+use super::Coordinate;
+use crate::spatial::geometry;
+use serde::{Deserialize, Serialize};
+
+/// 无人机地理区域限制类别（ED-269）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UasRestriction {
+    Prohibited,        // 禁飞
+    ReqAuthorisation,  // 需授权
+    Conditional,       // 有条件飞行
+    NoRestriction,
+}
+
+impl UasRestriction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            UasRestriction::Prohibited => "PROHIBITED",
+            UasRestriction::ReqAuthorisation => "REQ_AUTHORISATION",
+            UasRestriction::Conditional => "CONDITIONAL",
+            UasRestriction::NoRestriction => "NO_RESTRICTION",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "PROHIBITED" => UasRestriction::Prohibited,
+            "REQ_AUTHORISATION" | "REQ_AUTHORIZATION" => UasRestriction::ReqAuthorisation,
+            "CONDITIONAL" => UasRestriction::Conditional,
+            _ => UasRestriction::NoRestriction,
+        }
+    }
+}
+
+/// 无人机地理区域
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UasZone {
+    pub id: String,
+    pub name: String,
+    pub country: Option<String>,
+    pub restriction: UasRestriction,
+    pub reasons: Vec<String>,
+    pub lower_limit: i32,              // 英尺
+    pub upper_limit: i32,              // 英尺
+    pub vertical_reference: String,    // "AGL" / "AMSL"
+    pub message: Option<String>,
+    pub boundary: Vec<Coordinate>,
+    pub created_at: i64,
+}
+
+impl UasZone {
+    /// 判断坐标是否在区域水平范围内
+    pub fn contains(&self, point: Coordinate) -> bool {
+        geometry::point_in_polygon(point, &self.boundary)
+    }
+
+    /// 判断高度是否在区域垂直范围内
+    pub fn applies_at(&self, altitude: i32) -> bool {
+        altitude >= self.lower_limit && altitude <= self.upper_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_contains() {
+        let zone = UasZone {
+            id: "Z1".to_string(),
+            name: "Stadium".to_string(),
+            country: None,
+            restriction: UasRestriction::from_str("req_authorisation"),
+            reasons: vec![],
+            lower_limit: 0,
+            upper_limit: 400,
+            vertical_reference: "AGL".to_string(),
+            message: None,
+            boundary: vec![
+                Coordinate::new(0.0, 0.0),
+                Coordinate::new(0.0, 1.0),
+                Coordinate::new(1.0, 1.0),
+                Coordinate::new(1.0, 0.0),
+            ],
+            created_at: 0,
+        };
+
+        assert_eq!(zone.restriction, UasRestriction::ReqAuthorisation);
+        assert!(zone.contains(Coordinate::new(0.5, 0.5)));
+        assert!(!zone.contains(Coordinate::new(1.5, 0.5)));
+        assert!(zone.applies_at(300));
+        assert!(!zone.applies_at(500));
+    }
+}
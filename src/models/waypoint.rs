@@ -24,6 +24,7 @@ impl WaypointType {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_uppercase().as_str() {
             "AIRPORT" => WaypointType::Airport,
@@ -36,14 +37,45 @@ impl WaypointType {
     }
 }
 
+/// 航路点用途
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WaypointUsage {
+    Terminal, // 终端区
+    Enroute,  // 航路
+    Both,
+}
+
+impl WaypointUsage {
+    pub fn as_str(&self) -> &str {
+        match self {
+            WaypointUsage::Terminal => "TERMINAL",
+            WaypointUsage::Enroute => "ENROUTE",
+            WaypointUsage::Both => "BOTH",
+        }
+    }
+
+    /// 未知取值返回 None
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "TERMINAL" => Some(WaypointUsage::Terminal),
+            "ENROUTE" => Some(WaypointUsage::Enroute),
+            "BOTH" => Some(WaypointUsage::Both),
+            _ => None,
+        }
+    }
+}
+
 /// 航路点
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Waypoint {
     pub id: String,
     pub name: String,
     pub coordinate: Coordinate,
-    pub region: Option<String>,
+    pub region: Option<String>, // ICAO 区域代码
     pub waypoint_type: WaypointType,
+    #[serde(default)]
+    pub usage: Option<WaypointUsage>,
     pub created_at: i64,
 }
 
@@ -61,6 +93,7 @@ impl Waypoint {
             coordinate,
             region: None,
             waypoint_type,
+            usage: None,
             created_at: chrono::Utc::now().timestamp(),
         }
     }
@@ -100,4 +133,12 @@ mod tests {
         assert_eq!(WaypointType::from_str("vor"), WaypointType::VOR);
         assert_eq!(WaypointType::GPS.as_str(), "GPS");
     }
+
+    #[test]
+    fn test_waypoint_usage_conversion() {
+        assert_eq!(WaypointUsage::from_str("terminal"), Some(WaypointUsage::Terminal));
+        assert_eq!(WaypointUsage::from_str("ENROUTE"), Some(WaypointUsage::Enroute));
+        assert_eq!(WaypointUsage::from_str("?"), None);
+        assert_eq!(WaypointUsage::Both.as_str(), "BOTH");
+    }
 }
@@ -0,0 +1,276 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::events::Event;
+use chrono::{Datelike, NaiveDate, TimeDelta};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Length of an AIRAC cycle in days
+pub const CYCLE_DAYS: i64 = 28;
+
+/// Effective date of AIRAC 2001, the reference for cycle arithmetic
+fn reference_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2020, 1, 2).expect("valid reference date")
+}
+
+/// Index (relative to the reference) of the first cycle effective in `year`
+fn first_index_of_year(year: i32) -> Result<i64> {
+    let jan1 = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| AeroBaseError::InvalidInput(format!("Invalid AIRAC year {}", year)))?;
+    let days = (jan1 - reference_date()).num_days();
+    Ok(days.div_euclid(CYCLE_DAYS) + i64::from(days.rem_euclid(CYCLE_DAYS) != 0))
+}
+
+/// One AIRAC cycle, e.g. `2402`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AiracCycle {
+    pub ident: String,
+    pub effective: NaiveDate,
+    /// First day the cycle is no longer valid (next cycle's effective date)
+    pub expires: NaiveDate,
+}
+
+impl AiracCycle {
+    fn from_index(index: i64) -> Self {
+        let effective = reference_date() + TimeDelta::days(index * CYCLE_DAYS);
+        let first = first_index_of_year(effective.year()).expect("year in range");
+        Self {
+            ident: format!("{:02}{:02}", effective.year() % 100, index - first + 1),
+            effective,
+            expires: effective + TimeDelta::days(CYCLE_DAYS),
+        }
+    }
+
+    /// Parse a `YYNN` cycle identifier
+    pub fn from_ident(ident: &str) -> Result<Self> {
+        let invalid = || AeroBaseError::InvalidInput(format!("Invalid AIRAC cycle {}", ident));
+        if ident.len() != 4 || !ident.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let year = 2000 + ident[..2].parse::<i32>().map_err(|_| invalid())?;
+        let number = ident[2..].parse::<i64>().map_err(|_| invalid())?;
+        if number < 1 {
+            return Err(invalid());
+        }
+
+        let cycle = Self::from_index(first_index_of_year(year)? + number - 1);
+        if cycle.effective.year() != year {
+            return Err(invalid());
+        }
+        Ok(cycle)
+    }
+
+    /// The cycle in force on `date`
+    pub fn containing(date: NaiveDate) -> Self {
+        Self::from_index((date - reference_date()).num_days().div_euclid(CYCLE_DAYS))
+    }
+}
+
+/// Freshness of the installed navdata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NavdataStatus {
+    Current,
+    /// Expires in the given number of days
+    ExpiringSoon(u32),
+    Expired,
+    /// No cycle has been installed
+    Unknown,
+}
+
+/// What validation does when planning on expired navdata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpiredAction {
+    #[default]
+    Ignore,
+    Warn,
+    Error,
+}
+
+/// Navdata expiry thresholds and enforcement
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiryPolicy {
+    /// Report `ExpiringSoon` this many days before expiry
+    pub expiring_soon_days: u32,
+    /// Days after expiry during which `Error` is downgraded to a warning
+    pub grace_days: u32,
+    pub on_expired: ExpiredAction,
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        Self {
+            expiring_soon_days: 7,
+            grace_days: 0,
+            on_expired: ExpiredAction::Ignore,
+        }
+    }
+}
+
+impl ExpiryPolicy {
+    /// Status of `cycle` on `today`
+    pub fn status(&self, cycle: Option<&AiracCycle>, today: NaiveDate) -> NavdataStatus {
+        let Some(cycle) = cycle else {
+            return NavdataStatus::Unknown;
+        };
+        let days_left = (cycle.expires - today).num_days();
+        if days_left <= 0 {
+            NavdataStatus::Expired
+        } else if days_left <= i64::from(self.expiring_soon_days) {
+            NavdataStatus::ExpiringSoon(days_left as u32)
+        } else {
+            NavdataStatus::Current
+        }
+    }
+
+    /// Action to take when planning with `cycle` on `today`
+    pub fn action(&self, cycle: Option<&AiracCycle>, today: NaiveDate) -> ExpiredAction {
+        let Some(cycle) = cycle else {
+            return ExpiredAction::Ignore;
+        };
+        if self.status(Some(cycle), today) != NavdataStatus::Expired {
+            return ExpiredAction::Ignore;
+        }
+        let days_expired = (today - cycle.expires).num_days();
+        if self.on_expired == ExpiredAction::Error && days_expired < i64::from(self.grace_days) {
+            ExpiredAction::Warn
+        } else {
+            self.on_expired
+        }
+    }
+}
+
+/// Record `cycle` as installed at `installed_at`; returns whether that
+/// changed the current cycle
+///
+/// Callers writing inside a transaction publish `NavdataCycleChanged`
+/// once it has committed.
+pub(crate) fn record_cycle(
+    conn: &Connection,
+    cycle: &AiracCycle,
+    installed_at: i64,
+) -> Result<bool> {
+    let previous = current_ident(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO navdata_cycles (cycle, effective_date, expires_date, installed_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            &cycle.ident,
+            cycle.effective.to_string(),
+            cycle.expires.to_string(),
+            installed_at,
+        ],
+    )?;
+    Ok(current_ident(conn)? != previous)
+}
+
+/// Record `cycle` as the installed navdata cycle
+///
+/// Publishes `NavdataCycleChanged` when this switches the current cycle.
+pub fn install_cycle(db: &Database, cycle: &AiracCycle) -> Result<()> {
+    let conn = db.get_conn()?;
+    let changed = record_cycle(&conn, cycle, db.clock().timestamp())?;
+    log::info!("Installed navdata cycle {}", cycle.ident);
+    if changed {
+        db.publish(Event::NavdataCycleChanged {
+            cycle: cycle.ident.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// The most recently installed cycle, if any
+pub fn current_cycle(db: &Database) -> Result<Option<AiracCycle>> {
+    let conn = db.get_conn()?;
+    current_ident(&conn)?
+        .map(|i| AiracCycle::from_ident(&i))
+        .transpose()
+}
+
+/// Identifier of the most recently installed cycle, if any
+pub(crate) fn current_ident(conn: &Connection) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT cycle FROM navdata_cycles ORDER BY installed_at DESC, effective_date DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_airac_dates() {
+        let cycle = AiracCycle::from_ident("2401").unwrap();
+        assert_eq!(cycle.effective, date(2024, 1, 25));
+        assert_eq!(cycle.expires, date(2024, 2, 22));
+        assert_eq!(AiracCycle::from_ident("2001").unwrap().effective, date(2020, 1, 2));
+        // 2020 had a 14th cycle
+        assert_eq!(AiracCycle::from_ident("2014").unwrap().effective, date(2020, 12, 31));
+        assert!(AiracCycle::from_ident("2414").is_err());
+        assert!(AiracCycle::from_ident("24AB").is_err());
+
+        assert_eq!(AiracCycle::containing(date(2024, 2, 21)).ident, "2401");
+        assert_eq!(AiracCycle::containing(date(2024, 2, 22)).ident, "2402");
+        assert_eq!(AiracCycle::containing(date(2024, 1, 1)).ident, "2313");
+    }
+
+    #[test]
+    fn test_status_and_grace() {
+        let cycle = AiracCycle::from_ident("2401").unwrap();
+        let policy = ExpiryPolicy {
+            expiring_soon_days: 7,
+            grace_days: 3,
+            on_expired: ExpiredAction::Error,
+        };
+
+        assert_eq!(policy.status(None, date(2024, 2, 1)), NavdataStatus::Unknown);
+        assert_eq!(policy.status(Some(&cycle), date(2024, 2, 1)), NavdataStatus::Current);
+        assert_eq!(
+            policy.status(Some(&cycle), date(2024, 2, 20)),
+            NavdataStatus::ExpiringSoon(2)
+        );
+        assert_eq!(policy.status(Some(&cycle), date(2024, 2, 22)), NavdataStatus::Expired);
+
+        assert_eq!(policy.action(Some(&cycle), date(2024, 2, 20)), ExpiredAction::Ignore);
+        assert_eq!(policy.action(Some(&cycle), date(2024, 2, 24)), ExpiredAction::Warn);
+        assert_eq!(policy.action(Some(&cycle), date(2024, 2, 25)), ExpiredAction::Error);
+    }
+
+    #[test]
+    fn test_install_and_current() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let bus = std::sync::Arc::new(crate::events::EventBus::new());
+        let db = Database::new(&config).unwrap().with_events(&bus);
+        db.migrate().unwrap();
+        let mut events = bus.subscribe();
+
+        assert_eq!(current_cycle(&db).unwrap(), None);
+        let cycle = AiracCycle::from_ident("2402").unwrap();
+        install_cycle(&db, &cycle).unwrap();
+        assert_eq!(current_cycle(&db).unwrap(), Some(cycle.clone()));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            Event::NavdataCycleChanged {
+                cycle: "2402".to_string()
+            }
+        );
+        // Reinstalling the current cycle changes nothing
+        install_cycle(&db, &cycle).unwrap();
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use crate::db::query::{waypoint_from_row, WAYPOINT_COLUMNS};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::format;
+use crate::models::waypoint::Waypoint;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Waypoints sharing an ident within the merge threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    pub name: String,
+    /// Surviving waypoint (oldest, then lowest id)
+    pub kept: String,
+    /// Waypoints folded into `kept`
+    pub merged: Vec<String>,
+}
+
+/// What a dedupe run changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DedupeReport {
+    pub clusters: Vec<DuplicateCluster>,
+    /// Airway segments whose endpoints were remapped
+    pub segments_remapped: usize,
+    /// Segments dropped because both endpoints collapsed onto one waypoint
+    pub segments_removed: usize,
+    /// Saved flight plans whose routes were remapped
+    pub flight_plans_updated: usize,
+}
+
+impl DedupeReport {
+    /// Number of waypoint rows removed
+    pub fn waypoints_removed(&self) -> usize {
+        self.clusters.iter().map(|c| c.merged.len()).sum()
+    }
+}
+
+/// Find near-duplicate waypoints without changing anything
+///
+/// Waypoints with the same name are clustered by single linkage: any two
+/// within `threshold_nm` of each other end up in the same cluster.
+pub fn find_duplicates(db: &Database, threshold_nm: f64) -> Result<Vec<DuplicateCluster>> {
+    if threshold_nm.is_nan() || threshold_nm < 0.0 {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "Invalid dedupe threshold {}",
+            threshold_nm
+        )));
+    }
+
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM waypoints ORDER BY name, created_at, id",
+        WAYPOINT_COLUMNS
+    ))?;
+    let waypoints = stmt
+        .query_map([], waypoint_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut by_name: BTreeMap<&str, Vec<&Waypoint>> = BTreeMap::new();
+    for waypoint in &waypoints {
+        by_name.entry(&waypoint.name).or_default().push(waypoint);
+    }
+
+    let mut clusters = Vec::new();
+    for (name, group) in by_name.into_iter().filter(|(_, g)| g.len() > 1) {
+        for members in link(&group, threshold_nm) {
+            let mut ids = members.into_iter().map(|i| group[i].id.clone());
+            // Group is sorted by created_at, id so the first member survives
+            let kept = ids.next().expect("cluster is non-empty");
+            let merged: Vec<String> = ids.collect();
+            if !merged.is_empty() {
+                clusters.push(DuplicateCluster {
+                    name: name.to_string(),
+                    kept,
+                    merged,
+                });
+            }
+        }
+    }
+
+    Ok(clusters)
+}
+
+/// Single-linkage clusters as sorted index lists
+fn link(group: &[&Waypoint], threshold_nm: f64) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..group.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for i in 0..group.len() {
+        for j in (i + 1)..group.len() {
+            if group[i].coordinate.distance_to(&group[j].coordinate) <= threshold_nm {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..group.len() {
+        let r = root(&mut parent, i);
+        clusters.entry(r).or_default().push(i);
+    }
+    clusters.into_values().collect()
+}
+
+/// Merge near-duplicate waypoints in one transaction
+///
+/// References in `airway_segments` and saved flight plan routes are pointed
+/// at the surviving waypoint before the duplicates are deleted.
+pub fn dedupe(db: &Database, threshold_nm: f64) -> Result<DedupeReport> {
+    let clusters = find_duplicates(db, threshold_nm)?;
+    let mut report = DedupeReport::default();
+    if clusters.is_empty() {
+        return Ok(report);
+    }
+
+    let remap: HashMap<&str, &str> = clusters
+        .iter()
+        .flat_map(|c| c.merged.iter().map(move |m| (m.as_str(), c.kept.as_str())))
+        .collect();
+
+    let conn = db.get_background_conn()?;
+    let tx = conn.unchecked_transaction()?;
+    let now = db.clock().timestamp();
+
+    for (from, to) in &remap {
+        report.segments_remapped += tx.execute(
+            "UPDATE airway_segments SET from_waypoint_id = ?1, updated_at = ?3
+             WHERE from_waypoint_id = ?2",
+            rusqlite::params![to, from, now],
+        )?;
+        report.segments_remapped += tx.execute(
+            "UPDATE airway_segments SET to_waypoint_id = ?1, updated_at = ?3
+             WHERE to_waypoint_id = ?2",
+            rusqlite::params![to, from, now],
+        )?;
+    }
+    report.segments_removed =
+        tx.execute("DELETE FROM airway_segments WHERE from_waypoint_id = to_waypoint_id", [])?;
+
+    report.flight_plans_updated = remap_flight_plans(&tx, &remap, now)?;
+
+    for id in remap.keys() {
+        tx.execute("DELETE FROM waypoints WHERE id = ?1", [id])?;
+    }
+
+    tx.commit()?;
+
+    report.clusters = clusters;
+    log::info!(
+        "Merged {} duplicate waypoint(s) in {} cluster(s)",
+        report.waypoints_removed(),
+        report.clusters.len()
+    );
+    Ok(report)
+}
+
+fn remap_flight_plans(conn: &Connection, remap: &HashMap<&str, &str>, now: i64) -> Result<usize> {
+    let mut stmt = conn.prepare("SELECT id, plan_json FROM flight_plans")?;
+    let plans = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut updated = 0;
+    for (id, json) in plans {
+        let mut plan = format::plan_from_json(&json)?;
+        let mut changed = false;
+        for waypoint in plan.route.iter_mut() {
+            if let Some(kept) = remap.get(waypoint.as_str()) {
+                *waypoint = kept.to_string();
+                changed = true;
+            }
+        }
+        if changed {
+            // Bump the version so open editors see the change as a conflict
+            conn.execute(
+                "UPDATE flight_plans SET plan_json = ?1, version = version + 1, updated_at = ?3
+                 WHERE id = ?2",
+                rusqlite::params![format::plan_to_json(&plan)?, id, now],
+            )?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::repository::FlightPlanRepository;
+    use crate::flight::FlightPlanBuilder;
+    use crate::Config;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+
+        // DUP1/DUP2/DUP3 chain within 0.1 nm of each other; FAR shares the
+        // name but is a real, distinct fix
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('DUP1', 'ABC', 39.0000, 116.0, 'FIX', 1),
+                    ('DUP2', 'ABC', 39.0012, 116.0, 'FIX', 2),
+                    ('DUP3', 'ABC', 39.0024, 116.0, 'FIX', 3),
+                    ('FAR', 'ABC', 45.0, 116.0, 'FIX', 0),
+                    ('XYZ', 'XYZ', 39.0, 116.0, 'FIX', 0);
+                 INSERT INTO airways (id, name, type, created_at) VALUES ('W1', 'W1', 'HIGH', 0);
+                 INSERT INTO airway_segments
+                    (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at) VALUES
+                    ('S1', 'W1', 'DUP2', 'XYZ', 1, 0),
+                    ('S2', 'W1', 'DUP1', 'DUP3', 2, 0);",
+            )
+            .unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let (db, _temp_file) = setup_test_db();
+        let clusters = find_duplicates(&db, 0.1).unwrap();
+        assert_eq!(
+            clusters,
+            vec![DuplicateCluster {
+                name: "ABC".to_string(),
+                kept: "DUP1".to_string(),
+                merged: vec!["DUP2".to_string(), "DUP3".to_string()],
+            }]
+        );
+        assert!(find_duplicates(&db, 0.01).unwrap().is_empty());
+        assert!(find_duplicates(&db, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_dedupe_remaps_references() {
+        let (db, _temp_file) = setup_test_db();
+        let plan = FlightPlanBuilder::new()
+            .departure("ZBAA")
+            .destination("ZSSS")
+            .cruise_altitude(35000)
+            .cruise_speed(450)
+            .add_waypoint("DUP3")
+            .add_waypoint("XYZ")
+            .build()
+            .unwrap();
+        let repo = FlightPlanRepository::new(Arc::clone(&db)).unwrap();
+        let stored = repo.insert(&plan, None, None).unwrap();
+
+        let report = dedupe(&db, 0.1).unwrap();
+        assert_eq!(report.waypoints_removed(), 2);
+        assert_eq!(report.segments_remapped, 2);
+        assert_eq!(report.segments_removed, 1);
+        assert_eq!(report.flight_plans_updated, 1);
+
+        let reloaded = repo.get(&stored.id).unwrap().unwrap();
+        assert_eq!(reloaded.plan.route, vec!["DUP1".to_string(), "XYZ".to_string()]);
+        assert_eq!(reloaded.version, 2);
+
+        let conn = db.get_conn().unwrap();
+        let (from, remaining): (String, i64) = conn
+            .query_row(
+                "SELECT from_waypoint_id, (SELECT COUNT(*) FROM waypoints) FROM airway_segments",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(from, "DUP1");
+        assert_eq!(remaining, 3);
+        drop(conn);
+
+        assert_eq!(dedupe(&db, 0.1).unwrap(), DedupeReport::default());
+    }
+}
@@ -0,0 +1,373 @@
+use crate::encryption::{verify_signature, RsaKeyPair};
+use crate::error::{AeroBaseError, Result};
+use crate::navdata::text::{self, TextPolicy};
+use rsa::RsaPublicKey;
+use rusqlite::types::Value;
+
+/// Package magic bytes
+const MAGIC: &[u8; 4] = b"ADBD";
+
+/// Current package format version
+pub const DELTA_FORMAT_VERSION: u8 = 1;
+
+const ZSTD_LEVEL: i32 = 19;
+
+/// Hard cap on the decompressed payload, guards against decompression bombs
+const MAX_PAYLOAD_BYTES: usize = 1 << 30;
+
+const TAG_NULL: u8 = 0;
+const TAG_INTEGER: u8 = 1;
+const TAG_REAL: u8 = 2;
+const TAG_TEXT: u8 = 3;
+const TAG_BLOB: u8 = 4;
+
+/// Changes to one navdata table, rows keyed by their `id` column
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDelta {
+    pub table: String,
+    /// Column order for every row in `upserts`
+    pub columns: Vec<String>,
+    pub upserts: Vec<Vec<Value>>,
+    pub deletes: Vec<String>,
+}
+
+/// A navdata update between two cycles
+///
+/// Wire layout: `ADBD | version:u8 | sig_len:u16 | signature | zstd(payload)`.
+/// The signature covers the compressed payload so it is checked before
+/// anything is decompressed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavdataDelta {
+    pub from_cycle: Option<String>,
+    pub to_cycle: String,
+    pub tables: Vec<TableDelta>,
+}
+
+impl NavdataDelta {
+    /// Total number of upserted and deleted rows
+    pub fn change_count(&self) -> usize {
+        self.tables
+            .iter()
+            .map(|t| t.upserts.len() + t.deletes.len())
+            .sum()
+    }
+
+    /// Whether the delta changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.change_count() == 0
+    }
+
+    /// Compress and sign into a package
+    pub fn encode(&self, signer: &RsaKeyPair) -> Result<Vec<u8>> {
+        let payload = zstd::encode_all(self.to_payload()?.as_slice(), ZSTD_LEVEL)?;
+        let signature = signer.sign(&payload);
+        let sig_len = u16::try_from(signature.len())
+            .map_err(|_| AeroBaseError::InvalidInput("Signature too large".to_string()))?;
+
+        let mut out = Vec::with_capacity(7 + signature.len() + payload.len());
+        out.extend_from_slice(MAGIC);
+        out.push(DELTA_FORMAT_VERSION);
+        out.extend_from_slice(&sig_len.to_be_bytes());
+        out.extend_from_slice(&signature);
+        out.extend_from_slice(&payload);
+        Ok(out)
+    }
+
+    /// Verify and decode a package produced by `encode`
+    pub fn decode(bytes: &[u8], public_key: &RsaPublicKey) -> Result<Self> {
+        Self::decode_with(bytes, public_key, &TextPolicy::default())
+    }
+
+    /// Verify and decode a package, decoding text values that are not UTF-8
+    /// (names copied verbatim from legacy source data) per `policy`
+    pub fn decode_with(bytes: &[u8], public_key: &RsaPublicKey, policy: &TextPolicy) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("not a navdata delta package"));
+        }
+        let version = reader.u8()?;
+        if version > DELTA_FORMAT_VERSION {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Unsupported delta format version {}",
+                version
+            )));
+        }
+        let sig_len = u16::from_be_bytes(reader.array()?) as usize;
+        let signature = reader.take(sig_len)?;
+        let payload = reader.rest();
+
+        verify_signature(public_key, payload, signature)?;
+
+        let mut decoder = zstd::Decoder::new(payload)?;
+        let mut plain = Vec::new();
+        std::io::Read::read_to_end(
+            &mut std::io::Read::take(&mut decoder, MAX_PAYLOAD_BYTES as u64 + 1),
+            &mut plain,
+        )?;
+        if plain.len() > MAX_PAYLOAD_BYTES {
+            return Err(invalid("payload exceeds size limit"));
+        }
+
+        Self::from_payload(&plain, policy)
+    }
+
+    fn to_payload(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        write_str(&mut out, self.from_cycle.as_deref().unwrap_or(""));
+        write_str(&mut out, &self.to_cycle);
+        write_len(&mut out, self.tables.len())?;
+
+        for table in &self.tables {
+            write_str(&mut out, &table.table);
+            write_len(&mut out, table.columns.len())?;
+            for column in &table.columns {
+                write_str(&mut out, column);
+            }
+
+            write_len(&mut out, table.upserts.len())?;
+            for row in &table.upserts {
+                if row.len() != table.columns.len() {
+                    return Err(AeroBaseError::InvalidInput(format!(
+                        "Row in {} has {} values for {} columns",
+                        table.table,
+                        row.len(),
+                        table.columns.len()
+                    )));
+                }
+                for value in row {
+                    write_value(&mut out, value)?;
+                }
+            }
+
+            write_len(&mut out, table.deletes.len())?;
+            for id in &table.deletes {
+                write_str(&mut out, id);
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn from_payload(bytes: &[u8], policy: &TextPolicy) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let from_cycle = Some(reader.string()?).filter(|c| !c.is_empty());
+        let to_cycle = reader.string()?;
+
+        let table_count = reader.len()?;
+        let mut tables = Vec::with_capacity(table_count.min(64));
+        for _ in 0..table_count {
+            let table = reader.string()?;
+            let column_count = reader.len()?;
+            let columns = (0..column_count)
+                .map(|_| reader.string())
+                .collect::<Result<Vec<_>>>()?;
+
+            let row_count = reader.len()?;
+            let mut upserts = Vec::with_capacity(row_count.min(1 << 16));
+            for _ in 0..row_count {
+                let row = (0..column_count)
+                    .map(|_| reader.value(policy))
+                    .collect::<Result<Vec<_>>>()?;
+                upserts.push(row);
+            }
+
+            let delete_count = reader.len()?;
+            let deletes = (0..delete_count)
+                .map(|_| reader.string())
+                .collect::<Result<Vec<_>>>()?;
+
+            tables.push(TableDelta {
+                table,
+                columns,
+                upserts,
+                deletes,
+            });
+        }
+
+        if !reader.rest().is_empty() {
+            return Err(invalid("trailing bytes after payload"));
+        }
+
+        Ok(Self {
+            from_cycle,
+            to_cycle,
+            tables,
+        })
+    }
+}
+
+fn invalid(reason: &str) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid navdata delta: {}", reason))
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) -> Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid("section too large"))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) -> Result<()> {
+    match value {
+        Value::Null => out.push(TAG_NULL),
+        Value::Integer(i) => {
+            out.push(TAG_INTEGER);
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        Value::Real(f) => {
+            out.push(TAG_REAL);
+            out.extend_from_slice(&f.to_bits().to_le_bytes());
+        }
+        Value::Text(s) => {
+            out.push(TAG_TEXT);
+            write_str(out, s);
+        }
+        Value::Blob(b) => {
+            out.push(TAG_BLOB);
+            write_len(out, b.len())?;
+            out.extend_from_slice(b);
+        }
+    }
+    Ok(())
+}
+
+/// Bounds-checked cursor over a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            return Err(invalid("unexpected end of data"));
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(self.take(N)?);
+        Ok(buf)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.len()?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| invalid("string is not UTF-8"))
+    }
+
+    fn value(&mut self, policy: &TextPolicy) -> Result<Value> {
+        match self.u8()? {
+            TAG_NULL => Ok(Value::Null),
+            TAG_INTEGER => Ok(Value::Integer(i64::from_le_bytes(self.array()?))),
+            TAG_REAL => Ok(Value::Real(f64::from_bits(u64::from_le_bytes(self.array()?)))),
+            TAG_TEXT => {
+                let len = self.len()?;
+                Ok(Value::Text(text::decode(self.take(len)?, policy)))
+            }
+            TAG_BLOB => {
+                let len = self.len()?;
+                Ok(Value::Blob(self.take(len)?.to_vec()))
+            }
+            tag => Err(AeroBaseError::InvalidInput(format!(
+                "Invalid navdata delta: unknown value tag {}",
+                tag
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_delta() -> NavdataDelta {
+        NavdataDelta {
+            from_cycle: Some("2401".to_string()),
+            to_cycle: "2402".to_string(),
+            tables: vec![TableDelta {
+                table: "waypoints".to_string(),
+                columns: vec!["id".to_string(), "latitude".to_string(), "region".to_string()],
+                upserts: vec![vec![
+                    Value::Text("WPT1".to_string()),
+                    Value::Real(39.123456789012345),
+                    Value::Null,
+                ]],
+                deletes: vec!["WPT2".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let delta = sample_delta();
+        let payload = delta.to_payload().unwrap();
+        assert_eq!(NavdataDelta::from_payload(&payload, &TextPolicy::default()).unwrap(), delta);
+        assert_eq!(delta.change_count(), 2);
+
+        // Truncated input is rejected rather than panicking
+        for len in 0..payload.len() {
+            assert!(NavdataDelta::from_payload(&payload[..len], &TextPolicy::default()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_legacy_encoded_names() {
+        use crate::navdata::text::SourceEncoding;
+
+        let mut delta = sample_delta();
+        delta.tables[0].upserts[0][0] = Value::Text("BJ-ABC".to_string());
+        let mut payload = delta.to_payload().unwrap();
+        // Swap in a GBK-encoded name of the same length, as copied from source data
+        let at = payload.windows(6).position(|w| w == b"BJ-ABC").unwrap();
+        payload[at..at + 6].copy_from_slice(b"\xb1\xb1\xbe\xa9AB");
+
+        let gbk = TextPolicy {
+            encoding: SourceEncoding::Gbk,
+            ..Default::default()
+        };
+        let decoded = NavdataDelta::from_payload(&payload, &gbk).unwrap();
+        assert_eq!(decoded.tables[0].upserts[0][0], Value::Text("北京AB".to_string()));
+
+        // Declared UTF-8: transliterated instead of failing the whole package
+        let decoded = NavdataDelta::from_payload(&payload, &TextPolicy::default()).unwrap();
+        assert_eq!(decoded.tables[0].upserts[0][0], Value::Text("????AB".to_string()));
+    }
+
+    #[test]
+    fn test_signed_package() {
+        let signer = RsaKeyPair::generate().unwrap();
+        let delta = sample_delta();
+        let mut package = delta.encode(&signer).unwrap();
+
+        assert_eq!(
+            NavdataDelta::decode(&package, &signer.public_key).unwrap(),
+            delta
+        );
+
+        let last = package.len() - 1;
+        package[last] ^= 0xff;
+        assert!(NavdataDelta::decode(&package, &signer.public_key).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::navdata::delta::{NavdataDelta, TableDelta};
+use crate::navdata::{table_columns, NAVDATA_TABLES};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+
+/// Compute the delta that turns `old`'s navdata into `new`'s
+///
+/// Rows are matched by `id`; a row is upserted when it is new or any column
+/// other than `updated_at` differs. Tables with no changes are omitted.
+pub fn diff(
+    old: &Database,
+    new: &Database,
+    from_cycle: Option<&str>,
+    to_cycle: &str,
+) -> Result<NavdataDelta> {
+    let old_conn = old.get_conn()?;
+    let new_conn = new.get_conn()?;
+    let mut tables = Vec::new();
+
+    for table in NAVDATA_TABLES {
+        let columns = table_columns(&new_conn, table)?;
+        let id_index = columns
+            .iter()
+            .position(|c| c == "id")
+            .ok_or_else(|| AeroBaseError::InvalidInput(format!("Table {} has no id column", table)))?;
+
+        let old_rows = load_rows(&old_conn, table, &columns, id_index)?;
+        let new_rows = load_rows(&new_conn, table, &columns, id_index)?;
+
+        let upserts: Vec<Vec<Value>> = new_rows
+            .iter()
+            .filter(|(id, row)| old_rows.get(*id) != Some(*row))
+            .map(|(_, row)| row.clone())
+            .collect();
+        let deletes: Vec<String> = old_rows
+            .keys()
+            .filter(|id| !new_rows.contains_key(*id))
+            .cloned()
+            .collect();
+
+        if !upserts.is_empty() || !deletes.is_empty() {
+            log::debug!(
+                "{}: {} upsert(s), {} delete(s)",
+                table,
+                upserts.len(),
+                deletes.len()
+            );
+            tables.push(TableDelta {
+                table: table.to_string(),
+                columns,
+                upserts,
+                deletes,
+            });
+        }
+    }
+
+    Ok(NavdataDelta {
+        from_cycle: from_cycle.map(str::to_string),
+        to_cycle: to_cycle.to_string(),
+        tables,
+    })
+}
+
+fn load_rows(
+    conn: &Connection,
+    table: &str,
+    columns: &[String],
+    id_index: usize,
+) -> Result<BTreeMap<String, Vec<Value>>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM {}", columns.join(", "), table))?;
+    let mut rows = stmt.query([])?;
+    let mut out = BTreeMap::new();
+
+    while let Some(row) = rows.next()? {
+        let values = (0..columns.len())
+            .map(|i| row.get::<_, Value>(i))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let id = match &values[id_index] {
+            Value::Text(id) => id.clone(),
+            other => {
+                return Err(AeroBaseError::InvalidInput(format!(
+                    "Non-text id {:?} in {}",
+                    other, table
+                )))
+            }
+        };
+        out.insert(id, values);
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 2,
+            ..Default::default()
+        };
+
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn insert_waypoint(db: &Database, id: &str, lat: f64) {
+        let conn = db.get_conn().unwrap();
+        conn.execute(
+            "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+             VALUES (?1, ?1, ?2, 116.0, 'FIX', 0)",
+            rusqlite::params![id, lat],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_diff_upserts_and_deletes() {
+        let (old, _old_file) = setup_test_db();
+        let (new, _new_file) = setup_test_db();
+
+        insert_waypoint(&old, "SAME", 39.0);
+        insert_waypoint(&old, "MOVED", 39.0);
+        insert_waypoint(&old, "GONE", 39.0);
+        insert_waypoint(&new, "SAME", 39.0);
+        insert_waypoint(&new, "MOVED", 39.5);
+        insert_waypoint(&new, "ADDED", 40.0);
+
+        let delta = diff(&old, &new, Some("2401"), "2402").unwrap();
+        assert_eq!(delta.tables.len(), 1);
+
+        let waypoints = &delta.tables[0];
+        assert_eq!(waypoints.table, "waypoints");
+        assert!(!waypoints.columns.contains(&"updated_at".to_string()));
+        let upserted: Vec<&Value> = waypoints.upserts.iter().map(|r| &r[0]).collect();
+        assert_eq!(
+            upserted,
+            vec![
+                &Value::Text("ADDED".to_string()),
+                &Value::Text("MOVED".to_string())
+            ]
+        );
+        assert_eq!(waypoints.deletes, vec!["GONE".to_string()]);
+    }
+
+    #[test]
+    fn test_identical_databases_have_empty_delta() {
+        let (old, _old_file) = setup_test_db();
+        let (new, _new_file) = setup_test_db();
+        insert_waypoint(&old, "SAME", 39.0);
+        insert_waypoint(&new, "SAME", 39.0);
+
+        assert!(diff(&old, &new, None, "2402").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::import::{ImportControl, ImportPhase};
+use crate::models::uas_zone::{UasRestriction, UasZone};
+use crate::models::Coordinate;
+use crate::navdata::text::{self, TextPolicy};
+use crate::spatial::geometry;
+use serde_json::Value;
+
+const FEET_PER_METER: f64 = 3.28084;
+
+/// Vertices used to approximate an ED-269 circle
+const CIRCLE_SEGMENTS: usize = 36;
+
+fn invalid(message: impl Into<String>) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("ED-269: {}", message.into()))
+}
+
+fn position(value: &Value) -> Result<Coordinate> {
+    // GeoJSON order: [longitude, latitude]
+    match value.as_array().map(Vec::as_slice) {
+        Some([lon, lat, ..]) => match (lat.as_f64(), lon.as_f64()) {
+            (Some(lat), Some(lon)) => Ok(Coordinate::new(lat, lon)),
+            _ => Err(invalid("non-numeric position")),
+        },
+        _ => Err(invalid("position must be [longitude, latitude]")),
+    }
+}
+
+fn projection(value: &Value) -> Result<Vec<Coordinate>> {
+    match value["type"].as_str() {
+        Some("Polygon") => value["coordinates"][0]
+            .as_array()
+            .ok_or_else(|| invalid("polygon without an outer ring"))?
+            .iter()
+            .map(position)
+            .collect(),
+        Some("Circle") => {
+            let center = position(&value["center"])?;
+            let radius_m = value["radius"]
+                .as_f64()
+                .ok_or_else(|| invalid("circle without a radius"))?;
+            Ok((0..CIRCLE_SEGMENTS)
+                .map(|i| {
+                    let bearing = i as f64 * 360.0 / CIRCLE_SEGMENTS as f64;
+                    geometry::destination_point(center, radius_m / 1852.0, bearing)
+                })
+                .collect())
+        }
+        other => Err(invalid(format!("unsupported horizontal projection {:?}", other))),
+    }
+}
+
+fn limit_feet(value: &Value, uom: &str) -> Option<i32> {
+    let limit = value.as_f64()?;
+    let feet = if uom.eq_ignore_ascii_case("M") {
+        limit * FEET_PER_METER
+    } else {
+        limit
+    };
+    Some(feet.round() as i32)
+}
+
+/// Parse an ED-269 `UASZoneList` document
+///
+/// Each entry of a feature's `geometry` array becomes its own zone, since
+/// volumes of one feature may carry different vertical limits. Zones with
+/// several volumes get `-1`, `-2`, ... appended to the identifier. Zones
+/// are stamped `created_at`.
+pub fn parse(json: &str, created_at: i64) -> Result<Vec<UasZone>> {
+    let document: Value = serde_json::from_str(json)?;
+    let features = document["features"]
+        .as_array()
+        .ok_or_else(|| invalid("missing features array"))?;
+
+    let mut zones = Vec::new();
+    for feature in features {
+        let identifier = feature["identifier"]
+            .as_str()
+            .ok_or_else(|| invalid("feature without identifier"))?;
+        let volumes = feature["geometry"]
+            .as_array()
+            .ok_or_else(|| invalid(format!("{} has no geometry", identifier)))?;
+        let reasons: Vec<String> = feature["reason"]
+            .as_array()
+            .map(|r| r.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        for (i, volume) in volumes.iter().enumerate() {
+            let uom = volume["uomDimensions"].as_str().unwrap_or("M");
+            let boundary = projection(&volume["horizontalProjection"])?;
+            if boundary.len() < 3 {
+                return Err(invalid(format!("{} has a degenerate boundary", identifier)));
+            }
+
+            zones.push(UasZone {
+                id: if volumes.len() == 1 {
+                    identifier.to_string()
+                } else {
+                    format!("{}-{}", identifier, i + 1)
+                },
+                name: feature["name"].as_str().unwrap_or(identifier).to_string(),
+                country: feature["country"].as_str().map(str::to_string),
+                restriction: UasRestriction::from_str(feature["restriction"].as_str().unwrap_or("")),
+                reasons: reasons.clone(),
+                lower_limit: limit_feet(&volume["lowerLimit"], uom).unwrap_or(0),
+                upper_limit: limit_feet(&volume["upperLimit"], uom).unwrap_or(i32::MAX),
+                vertical_reference: volume["upperVerticalReference"]
+                    .as_str()
+                    .unwrap_or("AGL")
+                    .to_string(),
+                message: feature["message"].as_str().map(str::to_string),
+                boundary,
+                created_at,
+            });
+        }
+    }
+    Ok(zones)
+}
+
+/// Import an ED-269 document, replacing zones with the same id
+pub fn import(db: &Database, json: &str) -> Result<usize> {
+    import_with(db, json, &ImportControl::default())
+}
+
+/// Import an ED-269 document, reporting progress per zone
+///
+/// Cancellation rolls back every zone written so far.
+pub fn import_with(db: &Database, json: &str, control: &ImportControl) -> Result<usize> {
+    control.report(ImportPhase::Parsing, 0, None)?;
+    let zones = parse(json, db.clock().timestamp())?;
+    let total = Some(zones.len());
+    let conn = db.get_background_conn()?;
+    let tx = conn.unchecked_transaction()?;
+
+    control.report(ImportPhase::Writing, 0, total)?;
+    for (done, zone) in zones.iter().enumerate() {
+        let (min, max) = bounds(&zone.boundary);
+        tx.execute("DELETE FROM uas_zones WHERE id = ?1", [&zone.id])?;
+        tx.execute(
+            "INSERT INTO uas_zones (id, name, country, restriction, reasons, lower_limit,
+                 upper_limit, vertical_reference, message, min_latitude, max_latitude,
+                 min_longitude, max_longitude, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            rusqlite::params![
+                &zone.id,
+                &zone.name,
+                &zone.country,
+                zone.restriction.as_str(),
+                zone.reasons.join(","),
+                zone.lower_limit,
+                zone.upper_limit,
+                &zone.vertical_reference,
+                &zone.message,
+                min.latitude,
+                max.latitude,
+                min.longitude,
+                max.longitude,
+                zone.created_at,
+            ],
+        )?;
+
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO uas_zone_boundaries (id, zone_id, latitude, longitude, sequence)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (sequence, vertex) in zone.boundary.iter().enumerate() {
+            stmt.execute(rusqlite::params![
+                format!("{}:{}", zone.id, sequence),
+                &zone.id,
+                vertex.latitude,
+                vertex.longitude,
+                sequence as i64,
+            ])?;
+        }
+        control.report(ImportPhase::Writing, done + 1, total)?;
+    }
+
+    control.report(ImportPhase::Committing, zones.len(), total)?;
+    tx.commit()?;
+    log::info!("Imported {} UAS zone(s)", zones.len());
+    Ok(zones.len())
+}
+
+/// Import an ED-269 file whose text may not be UTF-8 (e.g. Latin-1 zone names)
+pub fn import_bytes(
+    db: &Database,
+    bytes: &[u8],
+    policy: &TextPolicy,
+    control: &ImportControl,
+) -> Result<usize> {
+    import_with(db, &text::decode(bytes, policy), control)
+}
+
+fn bounds(boundary: &[Coordinate]) -> (Coordinate, Coordinate) {
+    let mut min = Coordinate::new(f64::INFINITY, f64::INFINITY);
+    let mut max = Coordinate::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for c in boundary {
+        min.latitude = min.latitude.min(c.latitude);
+        min.longitude = min.longitude.min(c.longitude);
+        max.latitude = max.latitude.max(c.latitude);
+        max.longitude = max.longitude.max(c.longitude);
+    }
+    (min, max)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const SAMPLE: &str = r#"{
+        "title": "Test zones",
+        "features": [
+            {
+                "identifier": "ZONE-A",
+                "country": "CHN",
+                "name": "Airport CTR",
+                "restriction": "PROHIBITED",
+                "reason": ["AIR_TRAFFIC"],
+                "geometry": [{
+                    "uomDimensions": "M",
+                    "lowerLimit": 0,
+                    "lowerVerticalReference": "AGL",
+                    "upperLimit": 120,
+                    "upperVerticalReference": "AGL",
+                    "horizontalProjection": {
+                        "type": "Polygon",
+                        "coordinates": [[[116.0, 40.0], [116.2, 40.0], [116.2, 40.2], [116.0, 40.2], [116.0, 40.0]]]
+                    }
+                }]
+            },
+            {
+                "identifier": "ZONE-B",
+                "name": "Stadium",
+                "restriction": "REQ_AUTHORISATION",
+                "message": "Contact operator",
+                "geometry": [
+                    {
+                        "uomDimensions": "FT",
+                        "lowerLimit": 0,
+                        "upperLimit": 400,
+                        "upperVerticalReference": "AGL",
+                        "horizontalProjection": { "type": "Circle", "center": [117.0, 39.0], "radius": 1852 }
+                    },
+                    {
+                        "uomDimensions": "FT",
+                        "lowerLimit": 400,
+                        "upperLimit": 1000,
+                        "upperVerticalReference": "AMSL",
+                        "horizontalProjection": { "type": "Circle", "center": [117.0, 39.0], "radius": 3704 }
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    proptest::proptest! {
+        /// Truncated or corrupted files from sync are rejected, never a panic
+        #[test]
+        fn prop_parse_never_panics(
+            cut in 0..SAMPLE.len(),
+            edits in proptest::collection::vec(
+                (0..SAMPLE.len(), proptest::sample::select(b"\"[]{}-09,:e.".to_vec())),
+                0..6,
+            ),
+        ) {
+            let mut bytes = SAMPLE.as_bytes().to_vec();
+            for (at, byte) in edits {
+                bytes[at] = byte;
+            }
+            let _ = parse(&String::from_utf8_lossy(&bytes), 0);
+            let _ = parse(&SAMPLE[..SAMPLE.floor_char_boundary(cut)], 0);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let zones = parse(SAMPLE, 0).unwrap();
+        assert_eq!(zones.len(), 3);
+
+        let a = &zones[0];
+        assert_eq!(a.id, "ZONE-A");
+        assert_eq!(a.restriction, UasRestriction::Prohibited);
+        assert_eq!(a.upper_limit, 394);
+        assert_eq!(a.reasons, vec!["AIR_TRAFFIC"]);
+        assert!(a.contains(Coordinate::new(40.1, 116.1)));
+
+        let b = &zones[1];
+        assert_eq!(b.id, "ZONE-B-1");
+        assert_eq!(b.boundary.len(), CIRCLE_SEGMENTS);
+        assert!((b.boundary[0].distance_to(&Coordinate::new(39.0, 117.0)) - 1.0).abs() < 0.01);
+        assert_eq!(zones[2].vertical_reference, "AMSL");
+
+        assert!(parse(r#"{"features": [{"identifier": "X"}]}"#, 0).is_err());
+        assert!(parse("[]", 0).is_err());
+    }
+
+    #[test]
+    fn test_import_latin1() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let config = crate::Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+
+        let mut bytes = Vec::new();
+        for (i, part) in SAMPLE.split("Airport CTR").enumerate() {
+            if i > 0 {
+                bytes.extend_from_slice(b"Z\xfcrich CTR");
+            }
+            bytes.extend_from_slice(part.as_bytes());
+        }
+        let policy = TextPolicy {
+            encoding: text::SourceEncoding::Latin1,
+            ..Default::default()
+        };
+        assert_eq!(import_bytes(&db, &bytes, &policy, &ImportControl::default()).unwrap(), 3);
+
+        let name: String = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT name FROM uas_zones WHERE id = 'ZONE-A'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(name, "Zürich CTR");
+    }
+}
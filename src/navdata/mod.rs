@@ -0,0 +1,132 @@
+pub mod cycle;
+pub mod dedupe;
+pub mod delta;
+pub mod diff;
+pub mod ed269;
+#[cfg(feature = "import")]
+pub mod openaip;
+#[cfg(feature = "import")]
+pub mod ourairports;
+#[cfg(feature = "import")]
+pub mod seed;
+pub mod text;
+pub mod xplane;
+
+pub use cycle::{AiracCycle, ExpiredAction, ExpiryPolicy, NavdataStatus};
+pub use dedupe::{dedupe, DedupeReport};
+pub use delta::{NavdataDelta, TableDelta};
+pub use diff::diff;
+
+use crate::db::Database;
+use crate::error::Result;
+use rusqlite::Connection;
+
+/// 导航数据表（按外键依赖顺序：父表在前）
+pub const NAVDATA_TABLES: &[&str] = &[
+    "airports",
+    "airport_aliases",
+    "runways",
+    "airport_frequencies",
+    "procedures",
+    "procedure_legs",
+    "charts",
+    "waypoints",
+    "navaids",
+    "airways",
+    "airway_segments",
+    "airspaces",
+    "airspace_boundaries",
+    "uas_zones",
+    "uas_zone_boundaries",
+    "obstacles",
+    "terrain_cells",
+    "hazards",
+    "hazard_boundaries",
+];
+
+/// 清空全部导航数据表及已安装周期记录（不影响用户数据），在一个事务内按依赖顺序删除；
+/// 用于完整重新导入前代替删除数据库文件。返回删除的行数
+pub fn purge_navdata(db: &Database) -> Result<usize> {
+    let conn = db.get_background_conn()?;
+    let tx = conn.unchecked_transaction()?;
+    let mut removed = 0;
+    // Children first, so foreign keys hold at every step
+    for table in NAVDATA_TABLES.iter().rev() {
+        removed += tx.execute(&format!("DELETE FROM {}", table), [])?;
+    }
+    // The cycle no longer describes what's on disk
+    tx.execute("DELETE FROM navdata_cycles", [])?;
+    tx.commit()?;
+    log::info!("Purged navdata ({} rows)", removed);
+    Ok(removed)
+}
+
+/// 参与增量比较的列（不含由触发器维护的 updated_at 和由程序计算的 search_name、geohash）
+pub(crate) fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(columns
+        .into_iter()
+        .filter(|c| !matches!(c.as_str(), "updated_at" | "search_name" | "geohash"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_purge_navdata() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Capital', 40.08, 116.58, 0);
+                 INSERT INTO runways (id, airport_id, ident, created_at)
+                    VALUES ('R1', 'AP1', '01', 0);
+                 INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'ALPHA', 40.0, 116.0, 'FIX', 0),
+                    ('W2', 'BRAVO', 41.0, 117.0, 'FIX', 0);
+                 INSERT INTO airways (id, name, type, created_at) VALUES ('A1', 'A1', 'HIGH', 0);
+                 INSERT INTO airway_segments
+                    (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+                    VALUES ('S1', 'A1', 'W1', 'W2', 1, 0);
+                 INSERT INTO favorites (id, profile_id, item_type, item_id, created_at)
+                    VALUES ('F1', 'default', 'airport', 'AP1', 0);",
+            )
+            .unwrap();
+        cycle::install_cycle(&db, &AiracCycle::from_ident("2401").unwrap()).unwrap();
+
+        assert_eq!(purge_navdata(&db).unwrap(), 6);
+        let conn = db.get_conn().unwrap();
+        for table in NAVDATA_TABLES {
+            let count: i64 = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(count, 0, "{}", table);
+        }
+        drop(conn);
+        assert!(cycle::current_cycle(&db).unwrap().is_none());
+
+        // User data stays
+        let favorites: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(favorites, 1);
+    }
+}
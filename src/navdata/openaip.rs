@@ -0,0 +1,357 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::import::{ImportControl, ImportPhase};
+use crate::models::airport::Airport;
+use crate::models::navaid::{Navaid, NavaidType};
+use crate::models::runway::Runway;
+use crate::models::Coordinate;
+use crate::navdata::seed::{self, SeedData, SeedSummary};
+use crate::navdata::text::{self, TextPolicy};
+use serde_json::Value;
+use std::path::Path;
+
+const FEET_PER_METRE: f64 = 3.28084;
+
+/// OpenAIP airport type for closed aerodromes
+const TYPE_CLOSED: i64 = 8;
+
+fn invalid(file: &str, message: impl Into<String>) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("OpenAIP {}: {}", file, message.into()))
+}
+
+/// Records of an export, either a bare array or the API's `{"items": [...]}`
+fn items(text: &str, file: &str) -> Result<Vec<Value>> {
+    let value: Value = serde_json::from_str(text).map_err(|e| invalid(file, e.to_string()))?;
+    match value {
+        Value::Array(items) => Ok(items),
+        Value::Object(mut object) => match object.remove("items") {
+            Some(Value::Array(items)) => Ok(items),
+            _ => Err(invalid(file, "expected an array of records")),
+        },
+        _ => Err(invalid(file, "expected an array of records")),
+    }
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    Some(value.get(key)?.as_str()?.trim()).filter(|s| !s.is_empty())
+}
+
+/// A number that may also come as a string (frequencies do)
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// GeoJSON point `[lon, lat]`
+fn position(value: &Value) -> Option<Coordinate> {
+    let coordinates = value.get("coordinates")?.as_array()?;
+    let longitude = number(coordinates.first()?)?;
+    let latitude = number(coordinates.get(1)?)?;
+    seed::valid_position(latitude, longitude).then(|| Coordinate::new(latitude, longitude))
+}
+
+/// `{value, unit}` with unit 0 for metres and 1 for feet, in feet
+fn feet(value: &Value) -> Option<f64> {
+    let amount = number(value.get("value")?)?;
+    match value.get("unit").and_then(Value::as_i64) {
+        Some(0) => Some(amount * FEET_PER_METRE),
+        Some(1) | None => Some(amount),
+        Some(_) => None,
+    }
+}
+
+fn surface(code: i64) -> Option<&'static str> {
+    Some(match code {
+        0 => "ASPHALT",
+        1 => "CONCRETE",
+        2 => "GRASS",
+        3 => "SAND",
+        4 => "WATER",
+        5 => "BITUMEN",
+        6 => "BRICK",
+        7 => "MACADAM",
+        8 => "STONE",
+        9 => "CORAL",
+        10 => "CLAY",
+        11 => "LATERITE",
+        12 => "GRAVEL",
+        13 => "EARTH",
+        14 => "ICE",
+        15 => "SNOW",
+        16 => "RUBBER",
+        17 => "METAL",
+        18 => "STEEL",
+        19 => "WOOD",
+        20 => "NON_BITUMINOUS",
+        _ => return None,
+    })
+}
+
+/// Parse an airport export; airports without an ICAO code are skipped,
+/// as OpenAIP lists many private strips that have none
+fn parse_airports(text: &str, file: &str, data: &mut SeedData) -> Result<()> {
+    for item in items(text, file)? {
+        let icao = string(&item, "icaoCode");
+        let name = string(&item, "name");
+        let coordinate = item.get("geometry").and_then(position);
+        let (Some(icao), Some(name), Some(coordinate)) = (icao, name, coordinate) else {
+            data.skipped += 1;
+            continue;
+        };
+        if item.get("type").and_then(Value::as_i64) == Some(TYPE_CLOSED) {
+            data.skipped += 1;
+            continue;
+        }
+
+        let mut airport =
+            Airport::new(String::new(), icao.to_string(), name.to_string(), coordinate);
+        airport.iata = string(&item, "iataCode").map(str::to_string);
+        airport.elevation = item.get("elevation").and_then(feet).map(|ft| ft.round() as i32);
+        airport.country = string(&item, "country").map(str::to_string);
+        let icao = airport.icao.to_uppercase();
+
+        for runway in item.get("runways").and_then(Value::as_array).into_iter().flatten() {
+            let Some(designator) = string(runway, "designator") else {
+                continue;
+            };
+            let dimension = runway.get("dimension");
+            let length = dimension.and_then(|d| d.get("length")).and_then(feet);
+            let width = dimension.and_then(|d| d.get("width")).and_then(feet);
+            let mut end = Runway::new(String::new(), String::new(), designator.to_uppercase());
+            end.heading = runway.get("trueHeading").and_then(number);
+            end.length_ft = length.map(|ft| ft.round() as i32);
+            end.width_ft = width.map(|ft| ft.round() as i32);
+            end.surface = runway
+                .pointer("/surface/mainComposite")
+                .and_then(Value::as_i64)
+                .and_then(surface)
+                .map(str::to_string);
+            end.threshold = runway.pointer("/thresholdLocation/geometry").and_then(position);
+            end.elevation = runway
+                .pointer("/thresholdLocation/elevation")
+                .and_then(feet)
+                .map(|ft| ft.round() as i32);
+            data.runways.push((icao.clone(), end));
+        }
+        data.airports.push(airport);
+    }
+    Ok(())
+}
+
+fn navaid_type(code: i64) -> Option<NavaidType> {
+    match code {
+        0 => Some(NavaidType::DME),
+        1 => Some(NavaidType::TACAN),
+        2 => Some(NavaidType::NDB),
+        3 | 6 => Some(NavaidType::VOR),
+        4 | 5 | 7 | 8 => Some(NavaidType::VORDME),
+        _ => None,
+    }
+}
+
+/// Parse a navaid export; like OurAirports, OpenAIP has no ICAO region
+fn parse_navaids(text: &str, file: &str, data: &mut SeedData) -> Result<()> {
+    for item in items(text, file)? {
+        let ident = string(&item, "identifier");
+        let navaid_type = item.get("type").and_then(Value::as_i64).and_then(navaid_type);
+        let coordinate = item.get("geometry").and_then(position);
+        let (Some(ident), Some(navaid_type), Some(coordinate)) = (ident, navaid_type, coordinate)
+        else {
+            data.skipped += 1;
+            continue;
+        };
+
+        let ndb = navaid_type == NavaidType::NDB;
+        let mut navaid = Navaid::new(String::new(), ident.to_string(), navaid_type, coordinate);
+        // Unit 1 is kHz and 2 MHz; NDBs are stored in kHz, everything else in MHz
+        navaid.frequency = item.get("frequency").and_then(|f| {
+            let amount = number(f.get("value")?)?;
+            match (f.get("unit").and_then(Value::as_i64)?, ndb) {
+                (1, true) | (2, false) => Some(amount),
+                (1, false) => Some(amount / 1000.0),
+                (2, true) => Some(amount * 1000.0),
+                _ => None,
+            }
+        });
+        navaid.elevation = item.get("elevation").and_then(feet).map(|ft| ft.round() as i32);
+        navaid.declination = item.get("magneticDeclination").and_then(number);
+        data.navaids.push(navaid);
+    }
+    Ok(())
+}
+
+/// Seed airports, runways and navaids from OpenAIP JSON exports
+///
+/// Reads every `*_apt.json` and `*_nav.json` in `dir` (the per-country
+/// files OpenAIP publishes), decoding text with `policy`. Fails with
+/// `NotFound` if there are none.
+pub fn import_dir(
+    db: &Database,
+    dir: &Path,
+    policy: &TextPolicy,
+    control: &ImportControl,
+) -> Result<SeedSummary> {
+    control.report(ImportPhase::Parsing, 0, None)?;
+    let mut files: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    let mut data = SeedData::default();
+    let mut found = false;
+    for path in &files {
+        let Some(file) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let name = file.to_lowercase();
+        let parse = if name.ends_with("_apt.json") {
+            parse_airports
+        } else if name.ends_with("_nav.json") {
+            parse_navaids
+        } else {
+            continue;
+        };
+        found = true;
+        parse(&text::decode(&std::fs::read(path)?, policy), file, &mut data)?;
+    }
+    if !found {
+        return Err(AeroBaseError::NotFound(format!(
+            "No OpenAIP exports in {}",
+            dir.display()
+        )));
+    }
+
+    data.stamp(db.clock().timestamp());
+    let summary = seed::write(db, &data, control)?;
+    log::info!(
+        "Imported OpenAIP data: {} airports, {} runway ends, {} navaids, {} skipped",
+        summary.airports,
+        summary.runways,
+        summary.navaids,
+        summary.skipped
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::query::Query;
+    use crate::repository::{AirportRepository, NavaidRepository};
+    use crate::Config;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    const AIRPORTS: &str = r#"[
+        {
+            "name": "Beijing Capital", "icaoCode": "ZBAA", "iataCode": "PEK", "type": 2,
+            "country": "CN",
+            "geometry": {"type": "Point", "coordinates": [116.585, 40.0801]},
+            "elevation": {"value": 35, "unit": 0},
+            "runways": [
+                {
+                    "designator": "01", "trueHeading": 359,
+                    "dimension": {"length": {"value": 3800, "unit": 0},
+                                  "width": {"value": 60, "unit": 0}},
+                    "surface": {"mainComposite": 1},
+                    "thresholdLocation": {
+                        "geometry": {"type": "Point", "coordinates": [116.5945, 40.0667]},
+                        "elevation": {"value": 35, "unit": 0}
+                    }
+                },
+                {"designator": "19", "trueHeading": 179}
+            ]
+        },
+        {"name": "Private Strip", "type": 5,
+         "geometry": {"type": "Point", "coordinates": [116.0, 40.0]}},
+        {"name": "Old Field", "icaoCode": "ZZZZ", "type": 8,
+         "geometry": {"type": "Point", "coordinates": [116.0, 40.0]}}
+    ]"#;
+
+    const NAVAIDS: &str = r#"{"items": [
+        {"name": "Capital", "identifier": "PEK", "type": 4,
+         "frequency": {"value": "114.700", "unit": 2},
+         "geometry": {"type": "Point", "coordinates": [116.6002, 40.0745]},
+         "magneticDeclination": -6.9},
+        {"name": "Peking", "identifier": "PK", "type": 2,
+         "frequency": {"value": "351", "unit": 1},
+         "geometry": {"type": "Point", "coordinates": [116.0, 40.0]}},
+        {"name": "Unknown", "identifier": "UNK", "type": 42,
+         "geometry": {"type": "Point", "coordinates": [116.0, 40.0]}}
+    ]}"#;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut data = SeedData::default();
+        parse_airports(AIRPORTS, "cn_apt.json", &mut data).unwrap();
+        assert_eq!(data.airports.len(), 1);
+        assert_eq!(data.skipped, 2);
+        assert_eq!(data.airports[0].elevation, Some(115));
+        assert_eq!(data.runways.len(), 2);
+        let (icao, rwy01) = &data.runways[0];
+        assert_eq!(icao, "ZBAA");
+        assert_eq!((rwy01.length_ft, rwy01.width_ft), (Some(12467), Some(197)));
+        assert_eq!(rwy01.surface.as_deref(), Some("CONCRETE"));
+        assert!(rwy01.threshold.is_some());
+
+        parse_navaids(NAVAIDS, "cn_nav.json", &mut data).unwrap();
+        assert_eq!(data.navaids.len(), 2);
+        assert_eq!(data.navaids[0].navaid_type, NavaidType::VORDME);
+        assert_eq!(data.navaids[0].frequency, Some(114.7));
+        assert_eq!(data.navaids[1].frequency, Some(351.0));
+        assert_eq!(data.skipped, 3);
+
+        assert!(parse_navaids("{\"name\": 1}", "cn_nav.json", &mut data).is_err());
+    }
+
+    #[test]
+    fn test_import_dir() {
+        let (db, _temp_file) = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let policy = TextPolicy::default();
+        let control = ImportControl::default();
+        assert!(matches!(
+            import_dir(&db, dir.path(), &policy, &control),
+            Err(AeroBaseError::NotFound(_))
+        ));
+
+        std::fs::write(dir.path().join("cn_apt.json"), AIRPORTS).unwrap();
+        std::fs::write(dir.path().join("cn_nav.json"), NAVAIDS).unwrap();
+        let summary = import_dir(&db, dir.path(), &policy, &control).unwrap();
+        assert_eq!(
+            summary,
+            SeedSummary {
+                airports: 1,
+                runways: 2,
+                navaids: 2,
+                skipped: 3,
+            }
+        );
+
+        let airports = AirportRepository::new(Arc::clone(&db)).unwrap();
+        let capital = airports.get_by_icao("ZBAA").unwrap().unwrap();
+        assert_eq!(airports.runways(&capital.id).unwrap().len(), 2);
+
+        // Re-importing matches the region-less navaids by position
+        import_dir(&db, dir.path(), &policy, &control).unwrap();
+        let navaids = NavaidRepository::new(Arc::clone(&db)).unwrap();
+        assert_eq!(navaids.count(&Query::navaids()).unwrap(), 2);
+    }
+}
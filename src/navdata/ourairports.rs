@@ -0,0 +1,318 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::import::{ImportControl, ImportPhase};
+use crate::models::airport::Airport;
+use crate::models::navaid::{Navaid, NavaidType};
+use crate::models::runway::Runway;
+use crate::models::Coordinate;
+use crate::navdata::seed::{self, SeedData, SeedSummary};
+use crate::navdata::text::{self, TextPolicy};
+use csv::StringRecord;
+use std::collections::HashMap;
+use std::path::Path;
+
+fn invalid(file: &str, message: impl Into<String>) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("OurAirports {}: {}", file, message.into()))
+}
+
+/// A CSV file with columns looked up by header name, so columns added to
+/// the export (e.g. `icao_code`) don't shift anything
+struct Table {
+    columns: HashMap<String, usize>,
+    rows: Vec<StringRecord>,
+}
+
+impl Table {
+    fn parse(text: &str, file: &str, required: &[&str]) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+        let columns: HashMap<String, usize> = reader
+            .headers()
+            .map_err(|e| invalid(file, e.to_string()))?
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.trim().to_string(), i))
+            .collect();
+        if let Some(missing) = required.iter().find(|c| !columns.contains_key(**c)) {
+            return Err(invalid(file, format!("missing column {}", missing)));
+        }
+        let rows = reader
+            .records()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| invalid(file, e.to_string()))?;
+        Ok(Self { columns, rows })
+    }
+
+    /// Non-empty, trimmed value of `column`
+    fn get<'a>(&self, row: &'a StringRecord, column: &str) -> Option<&'a str> {
+        let value = row.get(*self.columns.get(column)?)?.trim();
+        Some(value).filter(|v| !v.is_empty())
+    }
+
+    fn number<T: std::str::FromStr>(&self, row: &StringRecord, column: &str) -> Option<T> {
+        self.get(row, column)?.parse().ok()
+    }
+
+    fn position(&self, row: &StringRecord, prefix: &str) -> Option<Coordinate> {
+        let latitude = self.number(row, &format!("{}latitude_deg", prefix))?;
+        let longitude = self.number(row, &format!("{}longitude_deg", prefix))?;
+        seed::valid_position(latitude, longitude).then(|| Coordinate::new(latitude, longitude))
+    }
+}
+
+/// Parse `airports.csv`
+///
+/// The ICAO code is `icao_code` where the export has it, otherwise
+/// OurAirports' own `ident`. Closed airports and rows without a name or
+/// position are counted in `skipped`. Also returns a map from `ident` to
+/// the ICAO code used, for `runways.csv`.
+fn parse_airports(text: &str, data: &mut SeedData) -> Result<HashMap<String, String>> {
+    const FILE: &str = "airports.csv";
+    let required = ["ident", "type", "name", "latitude_deg", "longitude_deg"];
+    let table = Table::parse(text, FILE, &required)?;
+    let mut codes = HashMap::new();
+    for row in &table.rows {
+        let ident = table.get(row, "ident");
+        let name = table.get(row, "name");
+        let position = table.position(row, "");
+        let (Some(ident), Some(name), Some(position)) = (ident, name, position) else {
+            data.skipped += 1;
+            continue;
+        };
+        if table.get(row, "type") == Some("closed") {
+            data.skipped += 1;
+            continue;
+        }
+
+        let icao = table.get(row, "icao_code").unwrap_or(ident);
+        let mut airport = Airport::new(String::new(), icao.to_string(), name.to_string(), position);
+        airport.iata = table.get(row, "iata_code").map(str::to_string);
+        airport.elevation = table.number(row, "elevation_ft");
+        airport.country = table.get(row, "iso_country").map(str::to_string);
+        codes.insert(ident.to_string(), icao.to_uppercase());
+        data.airports.push(airport);
+    }
+    Ok(codes)
+}
+
+/// Parse `runways.csv`; each row gives up to two runway ends
+fn parse_runways(text: &str, codes: &HashMap<String, String>, data: &mut SeedData) -> Result<()> {
+    const FILE: &str = "runways.csv";
+    let table = Table::parse(text, FILE, &["airport_ident", "le_ident", "he_ident"])?;
+    for row in &table.rows {
+        let Some(airport) = table.get(row, "airport_ident") else {
+            data.skipped += 1;
+            continue;
+        };
+        if table.get(row, "closed") == Some("1") {
+            data.skipped += 1;
+            continue;
+        }
+        // Without airports.csv, take the ident as the ICAO code
+        let icao = codes.get(airport).cloned().unwrap_or_else(|| airport.to_uppercase());
+        for end in ["le_", "he_"] {
+            let Some(ident) = table.get(row, &format!("{}ident", end)) else {
+                continue;
+            };
+            let mut runway = Runway::new(String::new(), String::new(), ident.to_uppercase());
+            runway.heading = table.number(row, &format!("{}heading_degT", end));
+            runway.length_ft = table.number(row, "length_ft");
+            runway.width_ft = table.number(row, "width_ft");
+            runway.surface = table.get(row, "surface").map(str::to_uppercase);
+            runway.threshold = table.position(row, end);
+            runway.elevation = table.number(row, &format!("{}elevation_ft", end));
+            data.runways.push((icao.clone(), runway));
+        }
+    }
+    Ok(())
+}
+
+fn navaid_type(kind: &str) -> Option<NavaidType> {
+    match kind.to_uppercase().as_str() {
+        "VOR" => Some(NavaidType::VOR),
+        "VOR-DME" | "VORTAC" => Some(NavaidType::VORDME),
+        "DME" => Some(NavaidType::DME),
+        "NDB" | "NDB-DME" => Some(NavaidType::NDB),
+        "TACAN" => Some(NavaidType::TACAN),
+        _ => None,
+    }
+}
+
+/// Parse `navaids.csv`
+///
+/// The export has no ICAO region, so navaids are stored without one.
+fn parse_navaids(text: &str, data: &mut SeedData) -> Result<()> {
+    const FILE: &str = "navaids.csv";
+    let table = Table::parse(text, FILE, &["ident", "type", "latitude_deg", "longitude_deg"])?;
+    for row in &table.rows {
+        let ident = table.get(row, "ident");
+        let navaid_type = table.get(row, "type").and_then(navaid_type);
+        let position = table.position(row, "");
+        let (Some(ident), Some(navaid_type), Some(position)) = (ident, navaid_type, position) else {
+            data.skipped += 1;
+            continue;
+        };
+
+        let ndb = navaid_type == NavaidType::NDB;
+        let mut navaid = Navaid::new(String::new(), ident.to_string(), navaid_type, position);
+        // NDBs are tuned in kHz, everything else in MHz
+        navaid.frequency = table
+            .number::<f64>(row, "frequency_khz")
+            .map(|khz| if ndb { khz } else { khz / 1000.0 });
+        navaid.elevation = table.number(row, "elevation_ft");
+        navaid.declination = table.number(row, "slaved_variation_deg");
+        data.navaids.push(navaid);
+    }
+    Ok(())
+}
+
+/// Seed airports, runways and navaids from an OurAirports download
+///
+/// `dir` must hold `airports.csv`; `runways.csv` and `navaids.csv` are
+/// imported when present. Text is decoded with `policy`.
+pub fn import_dir(
+    db: &Database,
+    dir: &Path,
+    policy: &TextPolicy,
+    control: &ImportControl,
+) -> Result<SeedSummary> {
+    control.report(ImportPhase::Parsing, 0, None)?;
+    let read = |name: &str| -> Result<Option<String>> {
+        let path = dir.join(name);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(text::decode(&std::fs::read(path)?, policy)))
+    };
+    let airports = read("airports.csv")?.ok_or_else(|| {
+        AeroBaseError::NotFound(format!("No OurAirports airports.csv in {}", dir.display()))
+    })?;
+
+    let mut data = SeedData::default();
+    let codes = parse_airports(&airports, &mut data)?;
+    if let Some(runways) = read("runways.csv")? {
+        parse_runways(&runways, &codes, &mut data)?;
+    }
+    if let Some(navaids) = read("navaids.csv")? {
+        parse_navaids(&navaids, &mut data)?;
+    }
+
+    data.stamp(db.clock().timestamp());
+    let summary = seed::write(db, &data, control)?;
+    log::info!(
+        "Imported OurAirports data: {} airports, {} runway ends, {} navaids, {} skipped",
+        summary.airports,
+        summary.runways,
+        summary.navaids,
+        summary.skipped
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::query::Query;
+    use crate::repository::{AirportRepository, NavaidRepository};
+    use crate::Config;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    const AIRPORTS: &str = r#""id","ident","type","name","latitude_deg","longitude_deg","elevation_ft","continent","iso_country","iso_region","municipality","scheduled_service","gps_code","iata_code","local_code","home_link","wikipedia_link","keywords"
+27248,"ZBAA","large_airport","Beijing Capital International Airport",40.080101,116.584999,116,"AS","CN","CN-11","Beijing","yes","ZBAA","PEK",,,,
+1,"00A","heliport","Total RF Heliport",40.070985,-74.933689,11,"NA","US","US-PA","Bensalem","no","K00A",,"00A",,,
+2,"XX-0001","closed","Old Field",10.0,10.0,,"AF","XX","XX-U-A",,"no",,,,,,
+3,"BAD","small_airport","Nowhere",,,,"AF","XX","XX-U-A",,"no",,,,,,
+"#;
+
+    const RUNWAYS: &str = r#""id","airport_ref","airport_ident","length_ft","width_ft","surface","lighted","closed","le_ident","le_latitude_deg","le_longitude_deg","le_elevation_ft","le_heading_degT","le_displaced_threshold_ft","he_ident","he_latitude_deg","he_longitude_deg","he_elevation_ft","he_heading_degT","he_displaced_threshold_ft"
+1,27248,"ZBAA",12467,197,"CON",1,0,"01",40.0667,116.5945,115,359.3,,"19",40.1009,116.5940,104,179.3,
+2,27248,"ZBAA",10500,164,"asp",1,1,"18L",,,,,,"36R",,,,,
+3,1,"00A",80,80,"ASPH-G",1,0,"H1",,,,,,,,,,,
+4,9,"NONE",5000,100,"ASP",0,0,"09",,,,,,"27",,,,,
+"#;
+
+    const NAVAIDS: &str = r#""id","filename","ident","name","type","frequency_khz","latitude_deg","longitude_deg","elevation_ft","iso_country","dme_frequency_khz","dme_channel","dme_latitude_deg","dme_longitude_deg","dme_elevation_ft","slaved_variation_deg","magnetic_variation_deg","usageType","power","associated_airport"
+1,"Capital_VOR-DME_CN","PEK","Capital","VOR-DME",114700,40.0745,116.6002,100,"CN",1158000,"94X",,,,-6.0,-6.9,"BOTH","HIGH","ZBAA"
+2,"Peking_NDB_CN","PK","Peking","NDB",351,40.0,116.0,,"CN",,,,,,,,"BOTH","MEDIUM",
+3,"Odd_CN","ODD","Odd","VOT",108000,40.0,116.0,,"CN",,,,,,,,,,
+"#;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut data = SeedData::default();
+        let codes = parse_airports(AIRPORTS, &mut data).unwrap();
+        assert_eq!(data.airports.len(), 2);
+        assert_eq!(data.skipped, 2);
+        assert_eq!(data.airports[0].iata.as_deref(), Some("PEK"));
+        assert_eq!(codes["00A"], "00A");
+
+        parse_runways(RUNWAYS, &codes, &mut data).unwrap();
+        let idents: Vec<&str> = data.runways.iter().map(|(_, r)| r.ident.as_str()).collect();
+        assert_eq!(idents, vec!["01", "19", "H1", "09", "27"]);
+        let (icao, rwy01) = &data.runways[0];
+        assert_eq!(icao, "ZBAA");
+        assert_eq!((rwy01.heading, rwy01.length_ft), (Some(359.3), Some(12467)));
+        assert!(rwy01.threshold.is_some());
+
+        parse_navaids(NAVAIDS, &mut data).unwrap();
+        assert_eq!(data.navaids.len(), 2);
+        assert_eq!(data.navaids[0].navaid_type, NavaidType::VORDME);
+        assert_eq!(data.navaids[0].frequency, Some(114.7));
+        assert_eq!(data.navaids[1].frequency, Some(351.0));
+
+        assert!(Table::parse("ident,name\n", "airports.csv", &["latitude_deg"]).is_err());
+    }
+
+    #[test]
+    fn test_import_dir() {
+        let (db, _temp_file) = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let policy = TextPolicy::default();
+        let control = ImportControl::default();
+        assert!(matches!(
+            import_dir(&db, dir.path(), &policy, &control),
+            Err(AeroBaseError::NotFound(_))
+        ));
+
+        std::fs::write(dir.path().join("airports.csv"), AIRPORTS).unwrap();
+        std::fs::write(dir.path().join("runways.csv"), RUNWAYS).unwrap();
+        std::fs::write(dir.path().join("navaids.csv"), NAVAIDS).unwrap();
+        let summary = import_dir(&db, dir.path(), &policy, &control).unwrap();
+        assert_eq!(
+            summary,
+            SeedSummary {
+                airports: 2,
+                runways: 3,
+                navaids: 2,
+                // Closed and position-less airports, the closed runway, the
+                // VOT, and both ends at the unknown airport
+                skipped: 6,
+            }
+        );
+
+        let airports = AirportRepository::new(Arc::clone(&db)).unwrap();
+        let capital = airports.get_by_icao("ZBAA").unwrap().unwrap();
+        assert_eq!(capital.country.as_deref(), Some("CN"));
+        assert_eq!(airports.runways(&capital.id).unwrap().len(), 2);
+
+        // A second run updates in place
+        import_dir(&db, dir.path(), &policy, &control).unwrap();
+        assert_eq!(airports.get_by_icao("ZBAA").unwrap().unwrap().id, capital.id);
+        assert_eq!(airports.runways(&capital.id).unwrap().len(), 2);
+        let navaids = NavaidRepository::new(Arc::clone(&db)).unwrap();
+        assert_eq!(navaids.count(&Query::navaids()).unwrap(), 2);
+    }
+}
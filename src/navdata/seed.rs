@@ -0,0 +1,210 @@
+use crate::db::Database;
+use crate::error::Result;
+use crate::ids::IdStrategy;
+use crate::import::{ImportControl, ImportPhase};
+use crate::models::airport::Airport;
+use crate::models::navaid::Navaid;
+use crate::models::runway::Runway;
+use crate::models::Coordinate;
+use crate::repository::{AirportRepository, NavaidRepository};
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+
+/// Region-less navaids with the same ident and type are taken to be the
+/// same station within this distance
+const NAVAID_MATCH_NM: f64 = 5.0;
+
+/// Rows written by an OurAirports or OpenAIP import
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeedSummary {
+    pub airports: usize,
+    /// Runway ends
+    pub runways: usize,
+    pub navaids: usize,
+    /// Records left out: closed airports and runways, rows without a
+    /// usable code or position, and runways of unknown airports
+    pub skipped: usize,
+}
+
+/// Records parsed from a seed source, ready to write
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SeedData {
+    pub airports: Vec<Airport>,
+    /// Runway ends with the ICAO code of their airport
+    pub runways: Vec<(String, Runway)>,
+    pub navaids: Vec<Navaid>,
+    /// Records left out while parsing
+    pub skipped: usize,
+}
+
+impl SeedData {
+    /// Stamp every record as created at `now`
+    pub(crate) fn stamp(&mut self, now: i64) {
+        self.airports.iter_mut().for_each(|airport| airport.created_at = now);
+        self.runways.iter_mut().for_each(|(_, runway)| runway.created_at = now);
+        self.navaids.iter_mut().for_each(|navaid| navaid.created_at = now);
+    }
+}
+
+/// Whether `latitude`/`longitude` is a position the repositories accept
+pub(crate) fn valid_position(latitude: f64, longitude: f64) -> bool {
+    (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+}
+
+/// Write parsed records in one transaction
+///
+/// Airports are upserted on their ICAO code and navaids on their natural
+/// key, or for region-less sources on ident, type and position. A runway
+/// end updates the stored end with the same designator, keeping its ILS
+/// data. Cancellation rolls everything back.
+pub(crate) fn write(
+    db: &Database,
+    data: &SeedData,
+    control: &ImportControl,
+) -> Result<SeedSummary> {
+    let mut summary = SeedSummary {
+        skipped: data.skipped,
+        ..Default::default()
+    };
+    let total = Some(data.airports.len() + data.runways.len() + data.navaids.len());
+    let conn = db.get_background_conn()?;
+    let tx = conn.unchecked_transaction()?;
+    let ids = db.id_strategy();
+    let now = db.clock().timestamp();
+    control.report(ImportPhase::Writing, 0, total)?;
+    let mut done = 0;
+
+    for airport in &data.airports {
+        AirportRepository::upsert_in(&tx, ids, airport, now)?;
+        summary.airports += 1;
+        done += 1;
+        control.report(ImportPhase::Writing, done, total)?;
+    }
+
+    let mut airport_ids: HashMap<&str, Option<String>> = HashMap::new();
+    for (icao, runway) in &data.runways {
+        if !airport_ids.contains_key(icao.as_str()) {
+            let id = tx
+                .query_row("SELECT id FROM airports WHERE icao = ?1", [icao], |row| row.get(0))
+                .optional()?;
+            airport_ids.insert(icao, id);
+        }
+        match &airport_ids[icao.as_str()] {
+            Some(airport_id) => {
+                write_runway(&tx, ids, now, airport_id, icao, runway)?;
+                summary.runways += 1;
+            }
+            None => summary.skipped += 1,
+        }
+        done += 1;
+        control.report(ImportPhase::Writing, done, total)?;
+    }
+
+    for navaid in &data.navaids {
+        match &navaid.region {
+            Some(_) => {
+                NavaidRepository::upsert_in(&tx, ids, navaid, now)?;
+            }
+            None => match nearby_navaid(&tx, navaid)? {
+                Some(id) => {
+                    let navaid = Navaid { id, ..navaid.clone() };
+                    NavaidRepository::update_in(&tx, ids, &navaid, now)?;
+                }
+                None => {
+                    NavaidRepository::insert_in(&tx, ids, navaid)?;
+                }
+            },
+        }
+        summary.navaids += 1;
+        done += 1;
+        control.report(ImportPhase::Writing, done, total)?;
+    }
+
+    control.report(ImportPhase::Committing, done, total)?;
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// Update the stored runway end with the same designator, or add one
+fn write_runway(
+    conn: &Connection,
+    ids: IdStrategy,
+    now: i64,
+    airport_id: &str,
+    icao: &str,
+    runway: &Runway,
+) -> Result<()> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM runways WHERE airport_id = ?1 AND ident = ?2",
+            [airport_id, &runway.ident],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let threshold = runway.threshold;
+    match existing {
+        Some(id) => {
+            conn.execute(
+                "UPDATE runways SET heading = ?2, length_ft = ?3, width_ft = ?4, surface = ?5,
+                    latitude = ?6, longitude = ?7, elevation = ?8, updated_at = ?9
+                 WHERE id = ?1",
+                rusqlite::params![
+                    id,
+                    runway.heading,
+                    runway.length_ft,
+                    runway.width_ft,
+                    runway.surface,
+                    threshold.map(|c| c.latitude),
+                    threshold.map(|c| c.longitude),
+                    runway.elevation,
+                    now,
+                ],
+            )?;
+        }
+        None => {
+            let id = if runway.id.is_empty() {
+                ids.navdata_id(&[icao, &runway.ident], runway.created_at * 1000)
+            } else {
+                runway.id.clone()
+            };
+            conn.execute(
+                "INSERT INTO runways (id, airport_id, ident, heading, length_ft, width_ft,
+                    surface, latitude, longitude, elevation, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                rusqlite::params![
+                    id,
+                    airport_id,
+                    runway.ident,
+                    runway.heading,
+                    runway.length_ft,
+                    runway.width_ft,
+                    runway.surface,
+                    threshold.map(|c| c.latitude),
+                    threshold.map(|c| c.longitude),
+                    runway.elevation,
+                    runway.created_at,
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Id of a stored region-less navaid with the same ident and type within
+/// `NAVAID_MATCH_NM`
+fn nearby_navaid(conn: &Connection, navaid: &Navaid) -> Result<Option<String>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, latitude, longitude FROM navaids
+         WHERE name = ?1 AND type = ?2 AND region IS NULL ORDER BY created_at, id",
+    )?;
+    let candidates = stmt
+        .query_map(
+            [navaid.name.trim().to_uppercase(), navaid.navaid_type.as_str().to_string()],
+            |row| Ok((row.get::<_, String>(0)?, Coordinate::new(row.get(1)?, row.get(2)?))),
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(candidates
+        .into_iter()
+        .find(|(_, position)| position.distance_to(&navaid.coordinate) <= NAVAID_MATCH_NM)
+        .map(|(id, _)| id))
+}
@@ -0,0 +1,155 @@
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+
+/// 源数据文本字段的字符集
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceEncoding {
+    Utf8,
+    /// ISO-8859-1，按 Windows-1252 解码（其超集）
+    Latin1,
+    ShiftJis,
+    /// GBK，按 GB18030 解码（其超集）
+    Gbk,
+}
+
+impl SourceEncoding {
+    /// Parse an encoding label such as "latin1", "shift_jis" or "gbk"
+    pub fn from_label(label: &str) -> Option<Self> {
+        let encoding = Encoding::for_label(label.trim().as_bytes())?;
+        [
+            SourceEncoding::Utf8,
+            SourceEncoding::Latin1,
+            SourceEncoding::ShiftJis,
+            SourceEncoding::Gbk,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.encoding() == encoding)
+    }
+
+    fn encoding(&self) -> &'static Encoding {
+        match self {
+            SourceEncoding::Utf8 => encoding_rs::UTF_8,
+            SourceEncoding::Latin1 => encoding_rs::WINDOWS_1252,
+            SourceEncoding::ShiftJis => encoding_rs::SHIFT_JIS,
+            SourceEncoding::Gbk => encoding_rs::GBK,
+        }
+    }
+}
+
+/// 导入时文本字段的解码方式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextPolicy {
+    /// 不是合法 UTF-8 的文本按此字符集解码
+    pub encoding: SourceEncoding,
+    /// 按该字符集仍无法解码时，改存 ASCII 音译而不是替换字符
+    pub transliterate_fallback: bool,
+}
+
+impl Default for TextPolicy {
+    fn default() -> Self {
+        Self {
+            encoding: SourceEncoding::Utf8,
+            transliterate_fallback: true,
+        }
+    }
+}
+
+/// Decode a text field from source data; never fails
+///
+/// Valid UTF-8 is taken as is, since feeds that declare a legacy encoding
+/// often mix in UTF-8 records. Anything else is decoded with the policy's
+/// encoding. Bytes that encoding can't decode either become U+FFFD, or,
+/// with `transliterate_fallback`, the whole value is transliterated to ASCII
+/// so a name reads "Z?rich" rather than carrying replacement characters.
+pub fn decode(bytes: &[u8], policy: &TextPolicy) -> String {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    let (text, had_errors) = policy.encoding.encoding().decode_without_bom_handling(bytes);
+    if !had_errors {
+        return text.into_owned();
+    }
+    log::warn!(
+        "Text is not valid {:?}: {}",
+        policy.encoding,
+        String::from_utf8_lossy(bytes)
+    );
+    if policy.transliterate_fallback {
+        transliterate(&text)
+    } else {
+        text.into_owned()
+    }
+}
+
+/// ASCII transliteration ("Zürich" -> "Zurich", "北京" -> "Bei Jing"), with
+/// "?" for characters that have none
+pub fn transliterate(text: &str) -> String {
+    deunicode::deunicode_with_tofu(text, "?")
+}
+
+/// Script-independent search key: the lowercase ASCII transliteration with
+/// everything but letters and digits dropped ("北京首都" -> "beijingshoudu",
+/// "Beijing Capital" -> "beijingcapital")
+pub fn search_key(text: &str) -> String {
+    deunicode::deunicode(text)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(encoding: SourceEncoding) -> TextPolicy {
+        TextPolicy {
+            encoding,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_legacy_encodings() {
+        assert_eq!(decode(b"Z\xfcrich", &policy(SourceEncoding::Latin1)), "Zürich");
+        assert_eq!(decode(b"\x93\x8c\x8b\x9e", &policy(SourceEncoding::ShiftJis)), "東京");
+        assert_eq!(decode(b"\xb1\xb1\xbe\xa9", &policy(SourceEncoding::Gbk)), "北京");
+        // UTF-8 wins even when a legacy encoding is configured
+        assert_eq!(decode("Zürich".as_bytes(), &policy(SourceEncoding::Latin1)), "Zürich");
+    }
+
+    #[test]
+    fn test_undecodable_fallback() {
+        let bytes = b"Z\xfcrich";
+        assert_eq!(decode(bytes, &TextPolicy::default()), "Z?rich");
+
+        let keep = TextPolicy {
+            transliterate_fallback: false,
+            ..Default::default()
+        };
+        assert_eq!(decode(bytes, &keep), "Z\u{fffd}rich");
+
+        // Truncated Shift-JIS sequence
+        assert_eq!(decode(b"\x93\x8c\x8b", &policy(SourceEncoding::ShiftJis)), "Dong ?");
+    }
+
+    #[test]
+    fn test_labels_and_transliteration() {
+        assert_eq!(SourceEncoding::from_label("latin1"), Some(SourceEncoding::Latin1));
+        assert_eq!(SourceEncoding::from_label("Shift_JIS"), Some(SourceEncoding::ShiftJis));
+        assert_eq!(SourceEncoding::from_label(" gbk "), Some(SourceEncoding::Gbk));
+        assert_eq!(SourceEncoding::from_label("utf-8"), Some(SourceEncoding::Utf8));
+        assert_eq!(SourceEncoding::from_label("koi8-r"), None);
+        assert_eq!(transliterate("Zürich"), "Zurich");
+        assert_eq!(transliterate("São Paulo"), "Sao Paulo");
+        assert_eq!(transliterate("北京"), "Bei Jing");
+    }
+
+    #[test]
+    fn test_search_key() {
+        assert_eq!(search_key("北京首都"), "beijingshoudu");
+        assert_eq!(search_key("Beijing Capital"), "beijingcapital");
+        assert_eq!(search_key("Zürich-Kloten"), "zurichkloten");
+        assert_eq!(search_key(" - "), "");
+    }
+}
@@ -0,0 +1,927 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::events::Event;
+use crate::ids::IdStrategy;
+use crate::import::{ImportControl, ImportPhase};
+use crate::models::airway::AirwayType;
+use crate::models::navaid::{Navaid, NavaidType};
+use crate::models::waypoint::{Waypoint, WaypointType, WaypointUsage};
+use crate::models::Coordinate;
+use crate::navdata::cycle::{self, AiracCycle};
+use crate::navdata::text::{self, TextPolicy};
+use crate::repository::{NavaidRepository, WaypointRepository};
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// `earth_fix.dat` versions understood (X-Plane 11 and 12)
+const FIX_VERSIONS: &[u32] = &[1100, 1101, 1200];
+
+/// `earth_nav.dat` versions understood (X-Plane 11 and 12)
+const NAV_VERSIONS: &[u32] = &[1100, 1150, 1200];
+
+/// `earth_awy.dat` versions understood (X-Plane 11 and 12)
+const AWY_VERSIONS: &[u32] = &[1100];
+
+/// Terminal-area column of fixes and navaids not tied to an airport
+const ENROUTE: &str = "ENRT";
+
+fn invalid(file: &str, message: impl Into<String>) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("X-Plane {}: {}", file, message.into()))
+}
+
+/// Version line of an X-Plane data file, e.g.
+/// `1150 Version - data cycle 2401, build 20231204, metadata NavXP1150.`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XPlaneHeader {
+    pub version: u32,
+    /// AIRAC cycle, when the file carries a `YYNN` one
+    pub cycle: Option<String>,
+    pub build: Option<String>,
+}
+
+/// X-Plane navdata texts to import; any of them may be left out
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XPlaneFiles<'a> {
+    /// `earth_fix.dat`
+    pub fixes: Option<&'a str>,
+    /// `earth_nav.dat`
+    pub navaids: Option<&'a str>,
+    /// `earth_awy.dat`
+    pub airways: Option<&'a str>,
+}
+
+/// Rows written by an X-Plane import
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XPlaneSummary {
+    pub cycle: Option<String>,
+    pub waypoints: usize,
+    pub navaids: usize,
+    pub airways: usize,
+    pub segments: usize,
+    /// Records left out: clashing terminal fixes, ILS/localizer records
+    /// and airway legs whose endpoints aren't in the database
+    pub skipped: usize,
+}
+
+/// Parse the header of an X-Plane data file and return it with the
+/// numbered record lines that follow, up to the `99` end marker
+fn split<'a>(
+    text: &'a str,
+    file: &str,
+    supported: &[u32],
+) -> Result<(XPlaneHeader, Vec<(usize, &'a str)>)> {
+    let mut lines = text.trim_start_matches('\u{feff}').lines().enumerate();
+    match lines.next() {
+        Some((_, origin)) if matches!(origin.trim(), "I" | "A") => {}
+        _ => return Err(invalid(file, "missing I/A byte-order line")),
+    }
+    let (_, version_line) = lines.next().ok_or_else(|| invalid(file, "missing version line"))?;
+    let header = parse_header(version_line)
+        .ok_or_else(|| invalid(file, format!("bad version line {:?}", version_line)))?;
+    if !supported.contains(&header.version) {
+        return Err(invalid(
+            file,
+            format!("unsupported version {} (expected one of {:?})", header.version, supported),
+        ));
+    }
+
+    let mut records = Vec::new();
+    for (i, line) in lines {
+        let line = line.trim();
+        if line == "99" {
+            return Ok((header, records));
+        }
+        if !line.is_empty() {
+            records.push((i + 1, line));
+        }
+    }
+    Err(invalid(file, "missing 99 end marker (truncated file?)"))
+}
+
+fn parse_header(line: &str) -> Option<XPlaneHeader> {
+    let version = line.split_whitespace().next()?.parse().ok()?;
+    // Value following `key` up to the next comma, less a closing period
+    let field = |key: &str| {
+        let start = line.find(key)? + key.len();
+        let value = line[start..].split(',').next()?.trim().trim_end_matches('.');
+        Some(value.to_string()).filter(|v| !v.is_empty())
+    };
+    Some(XPlaneHeader {
+        version,
+        // Pre-AIRAC files say e.g. "cycle 2008.10"
+        cycle: field("cycle ").filter(|c| AiracCycle::from_ident(c).is_ok()),
+        build: field("build "),
+    })
+}
+
+fn number<T: std::str::FromStr>(field: Option<&str>, file: &str, line: usize) -> Result<T> {
+    field
+        .and_then(|f| f.parse().ok())
+        .ok_or_else(|| invalid(file, format!("line {}: bad or missing number", line)))
+}
+
+fn text_field<'a>(field: Option<&'a str>, file: &str, line: usize) -> Result<&'a str> {
+    field.ok_or_else(|| invalid(file, format!("line {}: missing field", line)))
+}
+
+/// Parse `earth_fix.dat`
+///
+/// Enroute (`ENRT`) fixes win over terminal fixes with the same ident and
+/// region, and of several terminal fixes only the first is kept, since
+/// waypoints are keyed on ident and region. Fixes are stamped `created_at`;
+/// returns the number left out.
+pub fn parse_fixes(text: &str, created_at: i64) -> Result<(XPlaneHeader, Vec<Waypoint>, usize)> {
+    const FILE: &str = "earth_fix.dat";
+    let (header, records) = split(text, FILE, FIX_VERSIONS)?;
+
+    let mut waypoints: Vec<Waypoint> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+    let mut skipped = 0;
+    for (line, record) in records {
+        // lat lon ident terminal region [type [spoken name]]
+        let mut fields = record.split_whitespace();
+        let latitude: f64 = number(fields.next(), FILE, line)?;
+        let longitude: f64 = number(fields.next(), FILE, line)?;
+        let ident = text_field(fields.next(), FILE, line)?;
+        let terminal = text_field(fields.next(), FILE, line)?;
+        let region = text_field(fields.next(), FILE, line)?;
+
+        let usage = if terminal == ENROUTE {
+            WaypointUsage::Enroute
+        } else {
+            WaypointUsage::Terminal
+        };
+        let mut waypoint = Waypoint::new(
+            String::new(),
+            ident.to_string(),
+            Coordinate::new(latitude, longitude),
+            WaypointType::Fix,
+        );
+        waypoint.region = Some(region.to_string());
+        waypoint.usage = Some(usage);
+        waypoint.created_at = created_at;
+
+        let key = (ident.to_string(), region.to_string());
+        match index.get(&key) {
+            Some(&i) if usage == WaypointUsage::Enroute
+                && waypoints[i].usage == Some(WaypointUsage::Terminal) =>
+            {
+                waypoints[i] = waypoint;
+                skipped += 1;
+            }
+            Some(_) => skipped += 1,
+            None => {
+                index.insert(key, waypoints.len());
+                waypoints.push(waypoint);
+            }
+        }
+    }
+    Ok((header, waypoints, skipped))
+}
+
+/// Navaid type of a VOR (row code 3) or DME (12, 13) from its name suffix
+fn navaid_type(code: u32, name: &str) -> NavaidType {
+    let name = name.to_uppercase();
+    if name.ends_with("TACAN") {
+        NavaidType::TACAN
+    } else if code == 3 && (name.ends_with("VOR-DME") || name.ends_with("VORTAC")) {
+        NavaidType::VORDME
+    } else if code == 3 {
+        NavaidType::VOR
+    } else {
+        NavaidType::DME
+    }
+}
+
+/// Parse `earth_nav.dat`
+///
+/// NDBs (row code 2), VORs (3) and DMEs (12, 13) become navaids; ILS,
+/// localizer, glideslope, marker and GLS records are skipped, as are DMEs
+/// paired with an ILS. A DME sharing ident and region with a VOR upgrades
+/// it to a VOR/DME instead of adding a row. Navaids are stamped
+/// `created_at`; returns the number left out.
+pub fn parse_navaids(text: &str, created_at: i64) -> Result<(XPlaneHeader, Vec<Navaid>, usize)> {
+    const FILE: &str = "earth_nav.dat";
+    let (header, records) = split(text, FILE, NAV_VERSIONS)?;
+
+    let mut navaids: Vec<Navaid> = Vec::new();
+    let mut skipped = 0;
+    for (line, record) in records {
+        // code lat lon elevation frequency range magvar|bias ident terminal region name
+        let mut fields = record.split_whitespace();
+        let code: u32 = number(fields.next(), FILE, line)?;
+        if !matches!(code, 2 | 3 | 12 | 13) {
+            skipped += 1;
+            continue;
+        }
+        let latitude: f64 = number(fields.next(), FILE, line)?;
+        let longitude: f64 = number(fields.next(), FILE, line)?;
+        let elevation: i32 = number(fields.next(), FILE, line)?;
+        let frequency: f64 = number(fields.next(), FILE, line)?;
+        let range: i32 = number(fields.next(), FILE, line)?;
+        let variation: f64 = number(fields.next(), FILE, line)?;
+        let ident = text_field(fields.next(), FILE, line)?;
+        let _terminal = text_field(fields.next(), FILE, line)?;
+        let region = text_field(fields.next(), FILE, line)?;
+        let name = fields.collect::<Vec<_>>().join(" ");
+        // DMEs paired with an ILS or localizer end in e.g. "DME-ILS"
+        let suffix = name.rsplit(' ').next().unwrap_or("").to_uppercase();
+        if suffix.ends_with("-ILS") || suffix.ends_with("-LOC") {
+            skipped += 1;
+            continue;
+        }
+
+        let navaid_type = if code == 2 {
+            NavaidType::NDB
+        } else {
+            navaid_type(code, &name)
+        };
+        if code == 12 {
+            // DME part of a VOR/DME or VORTAC listed just before it
+            let vor = navaids.iter_mut().find(|n| {
+                n.name == ident
+                    && n.region.as_deref() == Some(region)
+                    && matches!(n.navaid_type, NavaidType::VOR | NavaidType::VORDME)
+            });
+            if let Some(vor) = vor {
+                vor.navaid_type = NavaidType::VORDME;
+                continue;
+            }
+        }
+
+        let mut navaid = Navaid::new(
+            String::new(),
+            ident.to_string(),
+            navaid_type,
+            Coordinate::new(latitude, longitude),
+        );
+        // NDBs are listed in kHz, everything else in units of 10 kHz
+        navaid.frequency = Some(if code == 2 { frequency } else { frequency / 100.0 });
+        navaid.range_nm = Some(range);
+        navaid.elevation = Some(elevation);
+        navaid.region = Some(region.to_string());
+        // For VORs this column is the slaved variation; for DMEs a bias
+        if code == 3 {
+            navaid.declination = Some(variation);
+        }
+        navaid.created_at = created_at;
+        navaids.push(navaid);
+    }
+    Ok((header, navaids, skipped))
+}
+
+/// One end of an `earth_awy.dat` leg
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Endpoint {
+    ident: String,
+    region: String,
+    /// 11 fix, 2 NDB, 3 VOR
+    kind: u32,
+}
+
+#[derive(Debug, Clone)]
+struct AirwayLeg {
+    from: Endpoint,
+    to: Endpoint,
+    /// `N` two-way, `F` one-way from -> to, `B` one-way to -> from
+    direction: char,
+    airway_type: AirwayType,
+    /// Feet
+    base: i32,
+    top: i32,
+    names: Vec<String>,
+}
+
+fn parse_airways(text: &str) -> Result<(XPlaneHeader, Vec<AirwayLeg>)> {
+    const FILE: &str = "earth_awy.dat";
+    let (header, records) = split(text, FILE, AWY_VERSIONS)?;
+    let mut legs = Vec::new();
+    for (line, record) in records {
+        // from region type to region type direction class base top names
+        let mut fields = record.split_whitespace();
+        let endpoint = |fields: &mut std::str::SplitWhitespace| -> Result<Endpoint> {
+            Ok(Endpoint {
+                ident: text_field(fields.next(), FILE, line)?.to_string(),
+                region: text_field(fields.next(), FILE, line)?.to_string(),
+                kind: number(fields.next(), FILE, line)?,
+            })
+        };
+        let from = endpoint(&mut fields)?;
+        let to = endpoint(&mut fields)?;
+        let direction = match text_field(fields.next(), FILE, line)? {
+            "N" => 'N',
+            "F" => 'F',
+            "B" => 'B',
+            other => return Err(invalid(FILE, format!("line {}: direction {:?}", line, other))),
+        };
+        let airway_type = match number::<u32>(fields.next(), FILE, line)? {
+            1 => AirwayType::Low,
+            2 => AirwayType::High,
+            _ => AirwayType::Other,
+        };
+        // Flight levels, i.e. hundreds of feet
+        let base = number::<i32>(fields.next(), FILE, line)? * 100;
+        let top = number::<i32>(fields.next(), FILE, line)? * 100;
+        let names = text_field(fields.next(), FILE, line)?
+            .split('-')
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+        legs.push(AirwayLeg {
+            from,
+            to,
+            direction,
+            airway_type,
+            base,
+            top,
+            names,
+        });
+    }
+    Ok((header, legs))
+}
+
+/// The one AIRAC cycle named by the headers, or `InvalidInput` if they
+/// disagree
+fn common_cycle(file_headers: &[(&str, &XPlaneHeader)]) -> Result<Option<String>> {
+    let mut cycle: Option<(&str, &String)> = None;
+    for (file, header) in file_headers {
+        match (&cycle, &header.cycle) {
+            (Some((first, seen)), Some(other)) if *seen != other => {
+                return Err(AeroBaseError::InvalidInput(format!(
+                    "X-Plane files are from different cycles: {} {} and {} {}",
+                    first, seen, file, other
+                )));
+            }
+            (None, Some(other)) => cycle = Some((file, other)),
+            _ => {}
+        }
+    }
+    Ok(cycle.map(|(_, ident)| ident.clone()))
+}
+
+/// Import X-Plane navdata into waypoints, navaids and airways
+pub fn import(db: &Database, files: &XPlaneFiles) -> Result<XPlaneSummary> {
+    import_with(db, files, &ImportControl::default())
+}
+
+/// Import X-Plane navdata, reporting progress per record
+///
+/// Fixes and navaids are upserted on their natural keys, so a re-import of
+/// a newer cycle refreshes rows in place and keeps their ids. Each airway
+/// in `earth_awy.dat` replaces the segments of the stored airway with the
+/// same name. When the headers carry an AIRAC cycle it's recorded as the
+/// installed one. Everything happens in one transaction, which
+/// cancellation rolls back.
+pub fn import_with(
+    db: &Database,
+    files: &XPlaneFiles,
+    control: &ImportControl,
+) -> Result<XPlaneSummary> {
+    control.report(ImportPhase::Parsing, 0, None)?;
+    let now = db.clock().timestamp();
+    let mut summary = XPlaneSummary::default();
+    let mut headers = Vec::new();
+    let mut waypoints = Vec::new();
+    let mut navaids = Vec::new();
+    let mut legs = Vec::new();
+    if let Some(text) = files.fixes {
+        let (header, parsed, skipped) = parse_fixes(text, now)?;
+        headers.push(("earth_fix.dat", header));
+        waypoints = parsed;
+        summary.skipped += skipped;
+    }
+    if let Some(text) = files.navaids {
+        let (header, parsed, skipped) = parse_navaids(text, now)?;
+        headers.push(("earth_nav.dat", header));
+        navaids = parsed;
+        summary.skipped += skipped;
+    }
+    if let Some(text) = files.airways {
+        let (header, parsed) = parse_airways(text)?;
+        headers.push(("earth_awy.dat", header));
+        legs = parsed;
+    }
+    let headers: Vec<_> = headers.iter().map(|(file, header)| (*file, header)).collect();
+    summary.cycle = common_cycle(&headers)?;
+
+    let total = Some(waypoints.len() + navaids.len() + legs.len());
+    let conn = db.get_background_conn()?;
+    let tx = conn.unchecked_transaction()?;
+    let ids = db.id_strategy();
+    control.report(ImportPhase::Writing, 0, total)?;
+    let mut done = 0;
+    for waypoint in &waypoints {
+        WaypointRepository::upsert_in(&tx, ids, waypoint, now)?;
+        done += 1;
+        control.report(ImportPhase::Writing, done, total)?;
+    }
+    summary.waypoints = waypoints.len();
+    for navaid in &navaids {
+        NavaidRepository::upsert_in(&tx, ids, navaid, now)?;
+        done += 1;
+        control.report(ImportPhase::Writing, done, total)?;
+    }
+    summary.navaids = navaids.len();
+    write_airways(&tx, ids, now, &legs, &mut summary, |legs| {
+        done += legs;
+        control.report(ImportPhase::Writing, done, total)
+    })?;
+
+    let cycle_changed = match &summary.cycle {
+        Some(ident) => cycle::record_cycle(&tx, &AiracCycle::from_ident(ident)?, now)?,
+        None => false,
+    };
+    control.report(ImportPhase::Committing, done, total)?;
+    tx.commit()?;
+    if let Some(ident) = summary.cycle.as_ref().filter(|_| cycle_changed) {
+        db.publish(Event::NavdataCycleChanged {
+            cycle: ident.clone(),
+        });
+    }
+    log::info!(
+        "Imported X-Plane navdata (cycle {:?}): {} waypoints, {} navaids, {} airways, \
+         {} segments, {} skipped",
+        summary.cycle,
+        summary.waypoints,
+        summary.navaids,
+        summary.airways,
+        summary.segments,
+        summary.skipped
+    );
+    Ok(summary)
+}
+
+/// Import the `earth_fix.dat`, `earth_nav.dat` and `earth_awy.dat` found in
+/// `dir` (e.g. X-Plane's `Custom Data`), decoding names with `policy`
+///
+/// Fails with `NotFound` if none of the three files is there.
+pub fn import_dir(
+    db: &Database,
+    dir: &Path,
+    policy: &TextPolicy,
+    control: &ImportControl,
+) -> Result<XPlaneSummary> {
+    let read = |name: &str| -> Result<Option<String>> {
+        let path = dir.join(name);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(text::decode(&std::fs::read(path)?, policy)))
+    };
+    let fixes = read("earth_fix.dat")?;
+    let navaids = read("earth_nav.dat")?;
+    let airways = read("earth_awy.dat")?;
+    if fixes.is_none() && navaids.is_none() && airways.is_none() {
+        return Err(AeroBaseError::NotFound(format!(
+            "No X-Plane navdata files in {}",
+            dir.display()
+        )));
+    }
+    let files = XPlaneFiles {
+        fixes: fixes.as_deref(),
+        navaids: navaids.as_deref(),
+        airways: airways.as_deref(),
+    };
+    import_with(db, &files, control)
+}
+
+/// Stored waypoint for an airway endpoint
+///
+/// Fixes must already be in `waypoints`. A VOR or NDB endpoint without a
+/// waypoint row gets one at the navaid's position, since segments
+/// reference waypoints only.
+fn resolve(
+    conn: &Connection,
+    ids: IdStrategy,
+    now: i64,
+    endpoint: &Endpoint,
+) -> Result<Option<(String, Coordinate)>> {
+    let stored = conn
+        .query_row(
+            "SELECT id, latitude, longitude FROM waypoints WHERE name = ?1 AND region = ?2",
+            [&endpoint.ident, &endpoint.region],
+            |row| Ok((row.get(0)?, Coordinate::new(row.get(1)?, row.get(2)?))),
+        )
+        .optional()?;
+    if stored.is_some() {
+        return Ok(stored);
+    }
+
+    let (types, waypoint_type) = match endpoint.kind {
+        2 => ("'NDB'", WaypointType::NDB),
+        3 => ("'VOR', 'VORDME', 'TACAN', 'DME'", WaypointType::VOR),
+        _ => return Ok(None),
+    };
+    let coordinate = conn
+        .query_row(
+            &format!(
+                "SELECT latitude, longitude FROM navaids
+                 WHERE name = ?1 AND region = ?2 AND type IN ({})",
+                types
+            ),
+            [&endpoint.ident, &endpoint.region],
+            |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some(coordinate) = coordinate else {
+        return Ok(None);
+    };
+    let mut waypoint =
+        Waypoint::new(String::new(), endpoint.ident.clone(), coordinate, waypoint_type);
+    waypoint.region = Some(endpoint.region.clone());
+    waypoint.usage = Some(WaypointUsage::Enroute);
+    waypoint.created_at = now;
+    let stored = WaypointRepository::upsert_in(conn, ids, &waypoint, now)?;
+    Ok(Some((stored.id, coordinate)))
+}
+
+/// Write one airway row per name and chain its legs into segments
+///
+/// `progress` hears how many legs were handled after each airway.
+fn write_airways(
+    conn: &Connection,
+    ids: IdStrategy,
+    now: i64,
+    legs: &[AirwayLeg],
+    summary: &mut XPlaneSummary,
+    mut progress: impl FnMut(usize) -> Result<()>,
+) -> Result<()> {
+    let mut resolved: HashMap<Endpoint, Option<(String, Coordinate)>> = HashMap::new();
+    let mut by_name: Vec<(String, Vec<usize>)> = Vec::new();
+    let mut name_index: HashMap<&str, usize> = HashMap::new();
+    for (i, leg) in legs.iter().enumerate() {
+        for endpoint in [&leg.from, &leg.to] {
+            if !resolved.contains_key(endpoint) {
+                resolved.insert(endpoint.clone(), resolve(conn, ids, now, endpoint)?);
+            }
+        }
+        for name in &leg.names {
+            let slot = *name_index.entry(name.as_str()).or_insert_with(|| {
+                by_name.push((name.clone(), Vec::new()));
+                by_name.len() - 1
+            });
+            by_name[slot].1.push(i);
+        }
+    }
+
+    for (name, indices) in &by_name {
+        let mut edges = Vec::new();
+        for &i in indices {
+            let leg = &legs[i];
+            match (&resolved[&leg.from], &resolved[&leg.to]) {
+                (Some(from), Some(to)) => edges.push((from, to, leg)),
+                _ => summary.skipped += 1,
+            }
+        }
+        if edges.is_empty() {
+            progress(indices.len())?;
+            continue;
+        }
+
+        let airway_type = if edges.iter().all(|e| e.2.airway_type == edges[0].2.airway_type) {
+            edges[0].2.airway_type.clone()
+        } else {
+            AirwayType::Other
+        };
+        let min_altitude = edges.iter().map(|e| e.2.base).min();
+        let max_altitude = edges.iter().map(|e| e.2.top).max();
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT id FROM airways WHERE name = ?1 ORDER BY created_at, id LIMIT 1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let airway_id = match existing {
+            Some(id) => {
+                conn.execute(
+                    "UPDATE airways SET type = ?2, min_altitude = ?3, max_altitude = ?4,
+                        updated_at = ?5
+                     WHERE id = ?1",
+                    rusqlite::params![id, airway_type.as_str(), min_altitude, max_altitude, now],
+                )?;
+                conn.execute("DELETE FROM airway_segments WHERE airway_id = ?1", [&id])?;
+                id
+            }
+            None => {
+                let id = ids.navdata_id(&[name.as_str()], now * 1000);
+                conn.execute(
+                    "INSERT INTO airways (id, name, type, min_altitude, max_altitude, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        id,
+                        name,
+                        airway_type.as_str(),
+                        min_altitude,
+                        max_altitude,
+                        now
+                    ],
+                )?;
+                id
+            }
+        };
+        summary.airways += 1;
+
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO airway_segments
+                (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, distance, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for (sequence, (from, to)) in chain(&edges).into_iter().enumerate() {
+            let sequence = sequence as i64 + 1;
+            stmt.execute(rusqlite::params![
+                ids.navdata_id(&[name.as_str(), &sequence.to_string()], now * 1000),
+                airway_id,
+                from.0,
+                to.0,
+                sequence,
+                from.1.distance_to(&to.1),
+                now,
+            ])?;
+            summary.segments += 1;
+        }
+        progress(indices.len())?;
+    }
+    Ok(())
+}
+
+type Stop<'a> = &'a (String, Coordinate);
+
+/// Order an airway's legs into runs from one end to the other
+///
+/// The file lists legs in no particular order, and an airway name may
+/// cover several unconnected stretches (e.g. one per region). Each run
+/// starts at a dead end where there is one. Two-way legs are oriented
+/// along the run; one-way legs keep their direction of flight.
+fn chain<'a>(
+    edges: &[(Stop<'a>, Stop<'a>, &AirwayLeg)],
+) -> Vec<(Stop<'a>, Stop<'a>)> {
+    let mut adjacent: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, (from, to, _)) in edges.iter().enumerate() {
+        adjacent.entry(&from.0).or_default().push(i);
+        adjacent.entry(&to.0).or_default().push(i);
+    }
+    let mut used = HashSet::new();
+    let mut ordered = Vec::new();
+    while used.len() < edges.len() {
+        let unused = |i: &&usize| !used.contains(*i);
+        let start = edges
+            .iter()
+            .flat_map(|(from, to, _)| [&from.0, &to.0])
+            .find(|stop| adjacent[stop.as_str()].iter().filter(unused).count() == 1)
+            .or_else(|| {
+                let (i, _) = edges.iter().enumerate().find(|(i, _)| !used.contains(i))?;
+                Some(&edges[i].0 .0)
+            })
+            .expect("an unused edge remains");
+
+        let mut at = start.as_str();
+        while let Some(&i) = adjacent[at].iter().find(|i| !used.contains(*i)) {
+            used.insert(i);
+            let (from, to, leg) = edges[i];
+            let (next_from, next_to) = if from.0 == at { (from, to) } else { (to, from) };
+            ordered.push(match leg.direction {
+                'F' => (from, to),
+                'B' => (to, from),
+                _ => (next_from, next_to),
+            });
+            at = &next_to.0;
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::query::Query;
+    use crate::Config;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    const FIXES: &str = "I
+1101 Version - data cycle 2401, build 20231204, metadata FixXP1101. Copyright test.
+
+  40.100000000  116.100000000 ALPHA ENRT ZB 2105430
+  40.200000000  116.200000000 BRAVO ENRT ZB 2105430
+  40.300000000  116.300000000 CHARL ENRT ZB 2105430
+  40.050000000  116.050000000 BRAVO ZBAA ZB 4530240
+  31.100000000  121.100000000 ALPHA ENRT ZS 2105430
+99
+";
+
+    const NAVAIDS: &str = "I
+1150 Version - data cycle 2401, build 20231204, metadata NavXP1150. Copyright test.
+
+ 2  40.000000000  116.000000000     0   351  50    0.000 PK   ENRT ZB PEKING NDB
+ 3  40.400000000  116.400000000   100 11470 130   -6.000 PEK  ENRT ZB CAPITAL VOR-DME
+12  40.400000000  116.400000000   100 11470 130    0.000 PEK  ENRT ZB CAPITAL VOR-DME
+ 3  31.000000000  121.000000000    10 11380 130   -5.000 PUD  ENRT ZS PUDONG VOR
+ 4  40.070000000  116.590000000   115 10910  18  180.000 IBAA ZBAA ZB 01 ILS-cat-I
+12  40.070000000  116.590000000   115 10910  18    0.000 IBAA ZBAA ZB 01 DME-ILS
+99
+";
+
+    // ALPHA-BRAVO-CHARL-PEK on A1, listed out of order; PK-ALPHA on B2 is
+    // one-way towards PK; a leg to an unknown fix is skipped
+    const AIRWAYS: &str = "I
+1100 Version - data cycle 2401, build 20231204, metadata AwyXP1100. Copyright test.
+
+BRAVO ZB 11 CHARL ZB 11 N 2 180 450 A1
+ALPHA ZB 11 BRAVO ZB 11 N 2 180 450 A1-B2
+CHARL ZB 11 PEK ZB  3 N 2 180 450 A1
+PK ZB  2 ALPHA ZB 11 B 1  60 180 B2
+ALPHA ZS 11 NOWHR ZS 11 N 1 60 180 B3
+99
+";
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn files() -> XPlaneFiles<'static> {
+        XPlaneFiles {
+            fixes: Some(FIXES),
+            navaids: Some(NAVAIDS),
+            airways: Some(AIRWAYS),
+        }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = parse_header(
+            "1200 Version - data cycle 2401, build 20231204, metadata FixXP1200. Copyright",
+        )
+        .unwrap();
+        assert_eq!(header.version, 1200);
+        assert_eq!(header.cycle.as_deref(), Some("2401"));
+        assert_eq!(header.build.as_deref(), Some("20231204"));
+
+        let old = parse_header("810 Version - DAFIF data cycle 2008.10, build 20081017").unwrap();
+        assert_eq!((old.version, old.cycle), (810, None));
+
+        let legacy = "I\n810 Version - DAFIF data cycle 2008.10\n99\n";
+        assert!(matches!(parse_navaids(legacy, 0), Err(AeroBaseError::InvalidInput(_))));
+        assert!(parse_fixes(&FIXES[..FIXES.len() - 4], 0).is_err());
+        assert!(parse_fixes("1101 Version\n99\n", 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_fixes_and_navaids() {
+        let (header, fixes, skipped) = parse_fixes(FIXES, 0).unwrap();
+        assert_eq!(header.version, 1101);
+        assert_eq!(fixes.len(), 4);
+        // The terminal BRAVO at ZBAA loses to the enroute one
+        assert_eq!(skipped, 1);
+        let bravo = fixes.iter().find(|w| w.name == "BRAVO").unwrap();
+        assert_eq!(bravo.usage, Some(WaypointUsage::Enroute));
+        assert_eq!(bravo.coordinate.latitude, 40.2);
+
+        let (_, navaids, skipped) = parse_navaids(NAVAIDS, 0).unwrap();
+        assert_eq!(skipped, 2);
+        let types: Vec<_> = navaids
+            .iter()
+            .map(|n| (n.name.as_str(), n.navaid_type.clone()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("PK", NavaidType::NDB),
+                ("PEK", NavaidType::VORDME),
+                ("PUD", NavaidType::VOR)
+            ]
+        );
+        assert_eq!(navaids[0].frequency, Some(351.0));
+        assert_eq!(navaids[1].frequency, Some(114.7));
+        assert_eq!(navaids[1].declination, Some(-6.0));
+    }
+
+    #[test]
+    fn test_import() {
+        let (db, _temp_file) = setup_test_db();
+        let summary = import(&db, &files()).unwrap();
+        assert_eq!(
+            summary,
+            XPlaneSummary {
+                cycle: Some("2401".to_string()),
+                waypoints: 4,
+                navaids: 3,
+                airways: 2,
+                segments: 5,
+                skipped: 4,
+            }
+        );
+        assert_eq!(cycle::current_cycle(&db).unwrap().unwrap().ident, "2401");
+
+        let waypoints = WaypointRepository::new(Arc::clone(&db)).unwrap();
+        // The fixes plus waypoints standing in for the PEK and PK endpoints
+        assert_eq!(waypoints.count(&Query::waypoints()).unwrap(), 6);
+        assert_eq!(
+            waypoints.count(&Query::waypoints().waypoint_type(WaypointType::VOR)).unwrap(),
+            1
+        );
+
+        let route = |airway: &str| -> Vec<String> {
+            let conn = db.get_conn().unwrap();
+            let mut stmt = conn
+                .prepare(
+                    "SELECT f.name || '>' || t.name FROM airway_segments s
+                     JOIN airways a ON a.id = s.airway_id
+                     JOIN waypoints f ON f.id = s.from_waypoint_id
+                     JOIN waypoints t ON t.id = s.to_waypoint_id
+                     WHERE a.name = ?1 ORDER BY s.sequence",
+                )
+                .unwrap();
+            stmt.query_map([airway], |row| row.get(0))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap()
+        };
+        assert_eq!(route("A1"), vec!["ALPHA>BRAVO", "BRAVO>CHARL", "CHARL>PEK"]);
+        // Starts at the BRAVO dead end; the one-way leg keeps its direction
+        assert_eq!(route("B2"), vec!["BRAVO>ALPHA", "ALPHA>PK"]);
+        assert!(route("B3").is_empty());
+
+        let (airway_type, min, max): (String, i32, i32) = db
+            .get_conn()
+            .unwrap()
+            .query_row(
+                "SELECT type, min_altitude, max_altitude FROM airways WHERE name = 'B2'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((airway_type.as_str(), min, max), ("OTHER", 6000, 45000));
+    }
+
+    #[test]
+    fn test_reimport_keeps_ids() {
+        let (db, _temp_file) = setup_test_db();
+        import(&db, &files()).unwrap();
+        let waypoints = WaypointRepository::new(Arc::clone(&db)).unwrap();
+        let alpha = &waypoints
+            .find(&Query::waypoints().region("ZB").name_prefix("ALPHA"))
+            .unwrap()[0];
+
+        let moved = FIXES
+            .replace("2401", "2402")
+            .replace("40.100000000  116.100000000", "40.110000000  116.100000000");
+        let files = XPlaneFiles {
+            fixes: Some(&moved),
+            navaids: None,
+            airways: None,
+        };
+        let summary = import(&db, &files).unwrap();
+        assert_eq!(summary.cycle.as_deref(), Some("2402"));
+
+        let refreshed = waypoints.get(&alpha.id).unwrap().unwrap();
+        assert_eq!(refreshed.coordinate.latitude, 40.11);
+        assert_eq!(waypoints.count(&Query::waypoints()).unwrap(), 6);
+        let segments: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM airway_segments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(segments, 5);
+
+        // Headers from different cycles don't mix
+        let mixed = XPlaneFiles {
+            fixes: Some(&moved),
+            navaids: Some(NAVAIDS),
+            airways: None,
+        };
+        assert!(matches!(import(&db, &mixed), Err(AeroBaseError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_import_dir_and_cancel() {
+        let (db, _temp_file) = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let policy = TextPolicy::default();
+        assert!(matches!(
+            import_dir(&db, dir.path(), &policy, &ImportControl::default()),
+            Err(AeroBaseError::NotFound(_))
+        ));
+
+        std::fs::write(dir.path().join("earth_fix.dat"), FIXES).unwrap();
+        std::fs::write(dir.path().join("earth_nav.dat"), NAVAIDS).unwrap();
+        let control = ImportControl::default();
+        control.cancellation_token().cancel();
+        assert!(matches!(
+            import_dir(&db, dir.path(), &policy, &control),
+            Err(AeroBaseError::Cancelled(_))
+        ));
+        let summary = import_dir(&db, dir.path(), &policy, &ImportControl::default()).unwrap();
+        assert_eq!((summary.waypoints, summary.navaids, summary.airways), (4, 3, 0));
+    }
+}
@@ -0,0 +1,218 @@
+use super::{icao, Notam, NotamKind};
+use crate::error::{AeroBaseError, Result};
+use crate::sync::transport::SyncTransport;
+use chrono::DateTime;
+use serde_json::Value;
+
+/// `source` of NOTAMs fetched from the FAA NOTAM API
+pub const FAA_SOURCE: &str = "FAA";
+
+/// Path of the FAA NOTAM API search endpoint, relative to the transport's base URL
+pub const FAA_NOTAM_PATH: &str = "notamapi/v1/notams";
+
+/// Page size requested from the FAA NOTAM API (its maximum)
+const PAGE_SIZE: usize = 1000;
+
+/// One page of an FAA NOTAM API (GeoJSON) response
+#[derive(Debug, Default)]
+pub struct FaaPage {
+    pub notams: Vec<Notam>,
+    /// Items that could not be read as a NOTAM
+    pub skipped: usize,
+    pub total_pages: usize,
+}
+
+/// Parse one page of FAA NOTAM API JSON
+///
+/// The ICAO translation is used when the API provides one, so the
+/// Q) line fields match NOTAMs imported from ICAO text; otherwise the
+/// NOTAM is built from the core fields.
+pub fn parse_page(body: &[u8]) -> Result<FaaPage> {
+    let json: Value = serde_json::from_slice(body).map_err(|e| {
+        AeroBaseError::InvalidInput(format!("Invalid FAA NOTAM response: {}", e))
+    })?;
+    let items = json
+        .get("items")
+        .and_then(Value::as_array)
+        .ok_or_else(|| AeroBaseError::InvalidInput("FAA NOTAM response has no items".into()))?;
+
+    let mut page = FaaPage {
+        total_pages: json.get("totalPages").and_then(Value::as_u64).unwrap_or(1) as usize,
+        ..Default::default()
+    };
+    for item in items {
+        match parse_item(item) {
+            Some(notam) => page.notams.push(notam),
+            None => {
+                log::warn!("Skipping unreadable FAA NOTAM item");
+                page.skipped += 1;
+            }
+        }
+    }
+    Ok(page)
+}
+
+/// Fetch every page of NOTAMs for `location` (ICAO code)
+pub fn fetch(transport: &dyn SyncTransport, location: &str) -> Result<FaaPage> {
+    let mut result = FaaPage::default();
+    let mut page_num = 1;
+    loop {
+        let body = transport.get(
+            FAA_NOTAM_PATH,
+            &[
+                ("icaoLocation", location.to_string()),
+                ("pageSize", PAGE_SIZE.to_string()),
+                ("pageNum", page_num.to_string()),
+            ],
+        )?;
+        let page = parse_page(&body)?;
+        result.notams.extend(page.notams);
+        result.skipped += page.skipped;
+        result.total_pages = page.total_pages;
+        if page_num >= page.total_pages {
+            return Ok(result);
+        }
+        page_num += 1;
+    }
+}
+
+fn parse_item(item: &Value) -> Option<Notam> {
+    let core = item.get("properties")?.get("coreNOTAMData")?;
+    let data = core.get("notam")?;
+    let field = |name: &str| data.get(name).and_then(Value::as_str).filter(|v| !v.is_empty());
+
+    let translated = core
+        .get("notamTranslation")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|t| t.get("type").and_then(Value::as_str) == Some("ICAO"))
+        .find_map(|t| t.get("formattedText").and_then(Value::as_str))
+        .and_then(|text| icao::parse(text).ok());
+    if let Some(mut notam) = translated {
+        notam.source = FAA_SOURCE.to_string();
+        return Some(notam);
+    }
+
+    let kind = match field("type")? {
+        "N" => NotamKind::New,
+        "R" => NotamKind::Replace,
+        "C" => NotamKind::Cancel,
+        _ => return None,
+    };
+    let (valid_to, estimated_end) = match field("effectiveEnd") {
+        None | Some("PERM") => (None, false),
+        Some(end) => {
+            let estimated = end.ends_with("EST");
+            (Some(parse_time(end.trim_end_matches("EST"))?), estimated)
+        }
+    };
+    let area = field("coordinates")
+        .zip(field("radius"))
+        .and_then(|(c, r)| icao::parse_area(&format!("{}{}", c, r)));
+    let level = |name: &str| field(name).and_then(|v| v.parse::<i32>().ok());
+
+    Some(Notam {
+        number: field("number")?.to_string(),
+        kind,
+        references: None,
+        location: field("icaoLocation").or(field("location"))?.to_uppercase(),
+        fir: field("affectedFIR").map(str::to_string),
+        q_code: field("selectionCode").map(str::to_string),
+        lower_limit: level("minimumFL").map(|fl| fl * 100),
+        upper_limit: level("maximumFL").filter(|fl| *fl < 999).map(|fl| fl * 100),
+        center: area.map(|(center, _)| center),
+        radius_nm: area.map(|(_, radius)| radius),
+        valid_from: parse_time(field("effectiveStart")?)?,
+        valid_to,
+        estimated_end,
+        schedule: field("schedule").map(str::to_string),
+        text: field("text")?.to_string(),
+        raw: data.to_string(),
+        source: FAA_SOURCE.to_string(),
+    })
+}
+
+fn parse_time(value: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(value.trim()).ok().map(|t| t.timestamp())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::sync::transport::tests::CannedTransport;
+
+    /// A page with one translated and one core-only NOTAM for KJFK
+    pub(crate) fn page(total_pages: usize, numbers: [&str; 2]) -> String {
+        serde_json::json!({
+            "totalPages": total_pages,
+            "items": [
+                {"properties": {"coreNOTAMData": {
+                    "notam": {"number": numbers[0], "type": "N", "icaoLocation": "KJFK"},
+                    "notamTranslation": [{
+                        "type": "ICAO",
+                        "formattedText": format!(
+                            "{} NOTAMN\nQ) KZNY/QMRLC/IV/NBO/A/000/999/4038N07347W005\n\
+                             A) KJFK B) 2401101200 C) 2401201800\nE) RWY 4L/22R CLSD",
+                            numbers[0]
+                        )
+                    }]
+                }}},
+                {"properties": {"coreNOTAMData": {
+                    "notam": {
+                        "number": numbers[1],
+                        "type": "N",
+                        "location": "JFK",
+                        "icaoLocation": "KJFK",
+                        "affectedFIR": "KZNY",
+                        "selectionCode": "QFAXX",
+                        "minimumFL": "000",
+                        "maximumFL": "050",
+                        "coordinates": "4038N07347W",
+                        "radius": "005",
+                        "effectiveStart": "2024-01-10T12:00:00.000Z",
+                        "effectiveEnd": "PERM",
+                        "text": "AD AP BIRD ACT"
+                    }
+                }}}
+            ]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_page() {
+        let parsed = parse_page(page(1, ["A0100/24", "01/123"]).as_bytes()).unwrap();
+        assert_eq!((parsed.notams.len(), parsed.skipped), (2, 0));
+
+        let translated = &parsed.notams[0];
+        assert_eq!(translated.source, FAA_SOURCE);
+        assert_eq!(translated.q_code.as_deref(), Some("QMRLC"));
+        assert_eq!(translated.valid_to, Some(1_705_773_600));
+
+        let core = &parsed.notams[1];
+        assert_eq!(core.number, "01/123");
+        assert_eq!(core.location, "KJFK");
+        assert_eq!((core.lower_limit, core.upper_limit), (Some(0), Some(5000)));
+        assert!(core.center.unwrap().longitude < -73.0);
+        assert_eq!((core.valid_from, core.valid_to), (1_704_888_000, None));
+
+        let broken = r#"{"items": [{"properties": {}}]}"#;
+        assert_eq!(parse_page(broken.as_bytes()).unwrap().skipped, 1);
+        assert!(parse_page(b"<html>").is_err());
+    }
+
+    #[test]
+    fn test_fetch_follows_pages() {
+        let first = page(2, ["A0100/24", "01/123"]);
+        let second = page(2, ["A0101/24", "01/124"]);
+        let transport = CannedTransport::default().with(FAA_NOTAM_PATH, vec![&first, &second]);
+
+        let fetched = fetch(&transport, "KJFK").unwrap();
+        assert_eq!(fetched.notams.len(), 4);
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].1.contains(&("pageNum".to_string(), "2".to_string())));
+        assert!(requests[0].1.contains(&("icaoLocation".to_string(), "KJFK".to_string())));
+    }
+}
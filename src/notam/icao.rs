@@ -0,0 +1,275 @@
+use super::{Notam, NotamKind};
+use crate::error::{AeroBaseError, Result};
+use crate::models::Coordinate;
+use chrono::NaiveDateTime;
+
+/// `source` of NOTAMs read from ICAO-format text
+pub const ICAO_SOURCE: &str = "ICAO";
+
+/// Item letters in the order they appear after the header
+const ITEMS: [char; 8] = ['Q', 'A', 'B', 'C', 'D', 'E', 'F', 'G'];
+
+/// Parse one NOTAM in the ICAO format (Annex 15), e.g.
+///
+/// ```text
+/// (A1234/24 NOTAMR A1200/24
+/// Q) ZBPE/QMRLC/IV/NBO/A/000/999/4004N11635E005
+/// A) ZBAA B) 2401101200 C) 2401201800 EST
+/// E) RWY 18L/36R CLSD)
+/// ```
+///
+/// Limits and the area of influence come from the Q) line. A cancellation
+/// (NOTAMC) needs no C) or E) item.
+pub fn parse(raw: &str) -> Result<Notam> {
+    let body = raw.trim();
+    let body = body.strip_prefix('(').unwrap_or(body);
+    let body = body.strip_suffix(')').unwrap_or(body).trim();
+
+    let mut found: Vec<(char, usize)> = Vec::new();
+    let mut from = 0;
+    for letter in ITEMS {
+        if let Some(at) = find_item(body, letter, from) {
+            found.push((letter, at));
+            from = at + 2;
+        }
+    }
+    let item = |letter: char| -> Option<&str> {
+        let index = found.iter().position(|(l, _)| *l == letter)?;
+        let start = found[index].1 + 2;
+        let end = found.get(index + 1).map_or(body.len(), |(_, at)| *at);
+        Some(body[start..end].trim()).filter(|v| !v.is_empty())
+    };
+
+    let header_end = found.first().map_or(body.len(), |(_, at)| *at);
+    let mut header = body[..header_end].split_whitespace();
+    let number = header
+        .next()
+        .filter(|n| is_number(n))
+        .ok_or_else(|| invalid(raw, "missing NOTAM number"))?
+        .to_string();
+    let kind = match header.next() {
+        Some("NOTAMN") => NotamKind::New,
+        Some("NOTAMR") => NotamKind::Replace,
+        Some("NOTAMC") => NotamKind::Cancel,
+        _ => return Err(invalid(raw, "missing NOTAMN/R/C")),
+    };
+    let references = header.next().filter(|n| is_number(n)).map(str::to_string);
+    if kind != NotamKind::New && references.is_none() {
+        return Err(invalid(raw, "missing referenced NOTAM"));
+    }
+
+    let location = item('A')
+        .and_then(|a| a.split_whitespace().next())
+        .ok_or_else(|| invalid(raw, "missing A) item"))?
+        .to_uppercase();
+    let valid_from = item('B')
+        .and_then(parse_time)
+        .ok_or_else(|| invalid(raw, "missing or bad B) item"))?;
+    let (valid_to, estimated_end) = match item('C') {
+        None if kind == NotamKind::Cancel => (None, false),
+        None => return Err(invalid(raw, "missing C) item")),
+        Some(c) if c.starts_with("PERM") => (None, false),
+        Some(c) => (
+            Some(parse_time(c).ok_or_else(|| invalid(raw, "bad C) item"))?),
+            c.ends_with("EST"),
+        ),
+    };
+    let text = match item('E') {
+        Some(e) => e.to_string(),
+        None if kind == NotamKind::Cancel => String::new(),
+        None => return Err(invalid(raw, "missing E) item")),
+    };
+
+    let mut notam = Notam {
+        number,
+        kind,
+        references,
+        location,
+        fir: None,
+        q_code: None,
+        lower_limit: None,
+        upper_limit: None,
+        center: None,
+        radius_nm: None,
+        valid_from,
+        valid_to,
+        estimated_end,
+        schedule: item('D').map(str::to_string),
+        text,
+        raw: raw.trim().to_string(),
+        source: ICAO_SOURCE.to_string(),
+    };
+    if let Some(q) = item('Q') {
+        apply_q_line(&mut notam, q);
+    }
+    Ok(notam)
+}
+
+/// Split text holding several NOTAMs, e.g. a briefing dump, and parse each
+///
+/// A NOTAM starts at a line beginning with its number and NOTAMN/R/C.
+pub fn parse_many(text: &str) -> Vec<Result<Notam>> {
+    let mut messages: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let starts_notam = {
+            let mut tokens = line.trim().trim_start_matches('(').split_whitespace();
+            tokens.next().is_some_and(is_number)
+                && tokens.next().is_some_and(|t| t.starts_with("NOTAM"))
+        };
+        match messages.last_mut() {
+            Some(message) if !starts_notam => {
+                message.push('\n');
+                message.push_str(line);
+            }
+            _ if starts_notam => messages.push(line.to_string()),
+            _ => {}
+        }
+    }
+    messages.iter().map(|m| parse(m)).collect()
+}
+
+/// Position of `X)` at or after `from`, preceded by whitespace or the start
+fn find_item(body: &str, letter: char, from: usize) -> Option<usize> {
+    let marker = format!("{})", letter);
+    let mut start = from;
+    while let Some(offset) = body.get(start..)?.find(&marker) {
+        let at = start + offset;
+        if at == 0 || body[..at].ends_with(char::is_whitespace) {
+            return Some(at);
+        }
+        start = at + 1;
+    }
+    None
+}
+
+/// `A1234/24` or domestic `01/123`
+fn is_number(token: &str) -> bool {
+    let Some((serial, year)) = token.split_once('/') else {
+        return false;
+    };
+    !serial.is_empty()
+        && !year.is_empty()
+        && serial.chars().skip(1).all(|c| c.is_ascii_digit())
+        && year.chars().all(|c| c.is_ascii_digit())
+}
+
+/// `YYMMDDhhmm`, optionally followed by `EST`
+fn parse_time(value: &str) -> Option<i64> {
+    let digits = value.split_whitespace().next()?;
+    let digits = digits.strip_suffix("EST").unwrap_or(digits);
+    NaiveDateTime::parse_from_str(digits, "%y%m%d%H%M")
+        .ok()
+        .map(|t| t.and_utc().timestamp())
+}
+
+/// `FIR/QCODE/TRAFFIC/PURPOSE/SCOPE/LOWER/UPPER/ddmmNdddmmEradius`
+fn apply_q_line(notam: &mut Notam, q: &str) {
+    let fields: Vec<&str> = q.split('/').map(str::trim).collect();
+    let field = |i: usize| fields.get(i).copied().filter(|f| !f.is_empty());
+    notam.fir = field(0).map(str::to_string);
+    notam.q_code = field(1).map(str::to_string);
+    notam.lower_limit = field(5).and_then(|f| f.parse::<i32>().ok()).map(|fl| fl * 100);
+    notam.upper_limit = field(6)
+        .and_then(|f| f.parse::<i32>().ok())
+        .filter(|fl| *fl < 999)
+        .map(|fl| fl * 100);
+    if let Some((center, radius)) = field(7).and_then(parse_area) {
+        notam.center = Some(center);
+        notam.radius_nm = Some(radius);
+    }
+}
+
+/// `4004N11635E005`: centre and radius in nautical miles
+pub(crate) fn parse_area(value: &str) -> Option<(Coordinate, f64)> {
+    let value = value.trim();
+    if value.len() < 11 || !value.is_ascii() {
+        return None;
+    }
+    let latitude = value[..2].parse::<f64>().ok()? + value[2..4].parse::<f64>().ok()? / 60.0;
+    let latitude = match &value[4..5] {
+        "N" => latitude,
+        "S" => -latitude,
+        _ => return None,
+    };
+    let longitude = value[5..8].parse::<f64>().ok()? + value[8..10].parse::<f64>().ok()? / 60.0;
+    let longitude = match &value[10..11] {
+        "E" => longitude,
+        "W" => -longitude,
+        _ => return None,
+    };
+    let radius = match &value[11..] {
+        "" => 0.0,
+        digits => digits.parse().ok()?,
+    };
+    Some((Coordinate::new(latitude, longitude), radius))
+}
+
+fn invalid(raw: &str, reason: &str) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid NOTAM ({}): {}", reason, raw.trim()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const REPLACEMENT: &str = "(A1234/24 NOTAMR A1200/24
+Q) ZBPE/QMRLC/IV/NBO/A/000/999/4004N11635E005
+A) ZBAA B) 2401101200 C) 2401201800 EST
+D) DAILY 1200-1800
+E) RWY 18L/36R CLSD DUE TO WIP (PAVING).)";
+
+    #[test]
+    fn test_parse_replacement() {
+        let notam = parse(REPLACEMENT).unwrap();
+        assert_eq!(notam.number, "A1234/24");
+        assert_eq!(notam.kind, NotamKind::Replace);
+        assert_eq!(notam.references.as_deref(), Some("A1200/24"));
+        assert_eq!(notam.location, "ZBAA");
+        assert_eq!(notam.fir.as_deref(), Some("ZBPE"));
+        assert_eq!(notam.q_code.as_deref(), Some("QMRLC"));
+        assert_eq!((notam.lower_limit, notam.upper_limit), (Some(0), None));
+        let center = notam.center.unwrap();
+        assert!((center.latitude - 40.0667).abs() < 1e-3);
+        assert!((center.longitude - 116.5833).abs() < 1e-3);
+        assert_eq!(notam.radius_nm, Some(5.0));
+        assert_eq!(notam.valid_from, 1_704_888_000);
+        assert_eq!(notam.valid_to, Some(1_705_773_600));
+        assert!(notam.estimated_end);
+        assert_eq!(notam.schedule.as_deref(), Some("DAILY 1200-1800"));
+        assert_eq!(notam.text, "RWY 18L/36R CLSD DUE TO WIP (PAVING).");
+        assert_eq!(notam.key(), "ZBAA A1234/24");
+    }
+
+    #[test]
+    fn test_parse_cancel_and_errors() {
+        let cancel = parse(
+            "(A1300/24 NOTAMC A1234/24
+Q) ZBPE/QMRXX/IV/NBO/A/000/999/4004N11635E005
+A) ZBAA B) 2401151000)",
+        )
+        .unwrap();
+        assert_eq!(cancel.kind, NotamKind::Cancel);
+        assert_eq!(cancel.references.as_deref(), Some("A1234/24"));
+        assert_eq!(cancel.valid_to, None);
+
+        let perm =
+            parse("(B0001/24 NOTAMN A) ZSSS B) 2401010000 C) PERM E) NEW TWY J OPEN)").unwrap();
+        assert_eq!((perm.valid_to, perm.estimated_end), (None, false));
+        assert_eq!(perm.upper_limit, None);
+
+        assert!(parse("(A1234/24 NOTAMR A) ZBAA B) 2401101200 C) PERM E) X)").is_err());
+        assert!(parse("(A1234/24 NOTAMN A) ZBAA B) 2401101200 E) X)").is_err());
+        assert!(parse("RWY CLSD").is_err());
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let text = format!(
+            "ZBAA NOTAMS\n{}\n\n(A0002/24 NOTAMN\nA) ZBAA B) 2401010000 C) PERM\nE) ILS 36R U/S)",
+            REPLACEMENT
+        );
+        let parsed = parse_many(&text);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].as_ref().unwrap().text, "ILS 36R U/S");
+    }
+}
@@ -0,0 +1,440 @@
+pub mod faa;
+pub mod icao;
+
+use crate::clock::Clock;
+use crate::db::query::normalize_code;
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::models::Coordinate;
+use crate::sync::transport::SyncTransport;
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// NOTAM 类型：新发、替换或取消
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotamKind {
+    New,
+    Replace,
+    Cancel,
+}
+
+impl NotamKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotamKind::New => "N",
+            NotamKind::Replace => "R",
+            NotamKind::Cancel => "C",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "N" => Some(NotamKind::New),
+            "R" => Some(NotamKind::Replace),
+            "C" => Some(NotamKind::Cancel),
+            _ => None,
+        }
+    }
+}
+
+/// 航行通告（NOTAM）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notam {
+    /// 通告编号，如 A1234/24
+    pub number: String,
+    pub kind: NotamKind,
+    /// 被替换或取消的通告编号
+    pub references: Option<String>,
+    /// A) 项：所涉机场或情报区
+    pub location: String,
+    pub fir: Option<String>,
+    /// Q) 项中的 NOTAM 代码，如 QMRLC
+    pub q_code: Option<String>,
+    /// 下限和上限（英尺），上限不受限时为 None
+    pub lower_limit: Option<i32>,
+    pub upper_limit: Option<i32>,
+    /// 影响区域的中心和半径（海里）
+    pub center: Option<Coordinate>,
+    pub radius_nm: Option<f64>,
+    /// 生效时间（Unix 秒）
+    pub valid_from: i64,
+    /// 失效时间，永久（PERM）时为 None
+    pub valid_to: Option<i64>,
+    /// 失效时间为预计（EST）
+    pub estimated_end: bool,
+    /// D) 项：分时段生效说明
+    pub schedule: Option<String>,
+    /// E) 项：通告正文
+    pub text: String,
+    /// 原始报文
+    pub raw: String,
+    /// 数据来源（ICAO、FAA）
+    pub source: String,
+}
+
+impl Notam {
+    /// Store key: NOTAM numbers are only unique per issuing location
+    pub fn key(&self) -> String {
+        key(&self.location, &self.number)
+    }
+
+    /// Whether the NOTAM is in force at some point of `[from, to]`
+    pub fn is_active(&self, from: i64, to: i64) -> bool {
+        self.valid_from <= to && self.valid_to.is_none_or(|end| end >= from)
+    }
+}
+
+/// NOTAM 导入结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotamImport {
+    /// 新增或替换的通告数
+    pub stored: usize,
+    /// 被取消（或不再由数据源提供）而删除的通告数
+    pub cancelled: usize,
+    /// 无法解析而跳过的报文数
+    pub skipped: usize,
+}
+
+/// 航行通告管理器：导入 ICAO 格式报文和 FAA NOTAM API 数据，并按地点和时间查询
+pub struct NotamManager {
+    db: Arc<Database>,
+    clock: Arc<dyn Clock>,
+}
+
+impl NotamManager {
+    /// 创建新的航行通告管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self {
+            clock: Arc::clone(db.clock()),
+            db,
+        })
+    }
+
+    /// 按 `clock` 记录导入时间
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 保存通告：替换报会删除被替换的通告，取消报只删除被取消的通告
+    pub fn store(&self, notams: &[Notam]) -> Result<NotamImport> {
+        let mut conn = self.db.get_conn()?;
+        let tx = conn.transaction()?;
+        let result = apply(&tx, notams, self.clock.timestamp())?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    /// 解析并保存 ICAO 格式的通告文本（可包含多份报文），无法解析的报文会被跳过
+    pub fn store_icao(&self, text: &str) -> Result<NotamImport> {
+        let mut notams = Vec::new();
+        let mut skipped = 0;
+        for parsed in icao::parse_many(text) {
+            match parsed {
+                Ok(notam) => notams.push(notam),
+                Err(e) => {
+                    log::warn!("Skipping NOTAM: {}", e);
+                    skipped += 1;
+                }
+            }
+        }
+        if notams.is_empty() && skipped == 0 {
+            return Err(AeroBaseError::InvalidInput("No NOTAM found in text".into()));
+        }
+        let mut result = self.store(&notams)?;
+        result.skipped += skipped;
+        Ok(result)
+    }
+
+    /// 通过 FAA NOTAM API 获取各地点的全部现行通告
+    ///
+    /// API 返回的是各地点现行通告的完整集合，因此之前从 FAA 导入、但本次未返回的通告视为已取消并删除。
+    pub fn import_faa(
+        &self,
+        transport: &dyn SyncTransport,
+        locations: &[&str],
+    ) -> Result<NotamImport> {
+        let mut fetched = Vec::with_capacity(locations.len());
+        for location in locations {
+            let location = normalize_code(location);
+            let page = faa::fetch(transport, &location)?;
+            fetched.push((location, page));
+        }
+
+        let now = self.clock.timestamp();
+        let mut conn = self.db.get_conn()?;
+        let tx = conn.transaction()?;
+        let mut result = NotamImport::default();
+        for (location, page) in &fetched {
+            let current: Vec<String> = page.notams.iter().map(Notam::key).collect();
+            let mut stmt =
+                tx.prepare("SELECT id FROM notams WHERE location = ?1 AND source = ?2")?;
+            let stale: Vec<String> = stmt
+                .query_map(rusqlite::params![location, faa::FAA_SOURCE], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?
+                .into_iter()
+                .filter(|id| !current.contains(id))
+                .collect();
+            drop(stmt);
+            for id in &stale {
+                tx.execute("DELETE FROM notams WHERE id = ?1", [id])?;
+            }
+            let applied = apply(&tx, &page.notams, now)?;
+            result.stored += applied.stored;
+            result.cancelled += applied.cancelled + stale.len();
+            result.skipped += page.skipped;
+        }
+        // Lets freshness checks tell an empty answer from no fetch at all
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_metadata (table_name, last_sync, record_count)
+             VALUES ('notams', ?1, (SELECT COUNT(*) FROM notams))",
+            [now],
+        )?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    /// 按发布地点和编号查询通告
+    pub fn get(&self, location: &str, number: &str) -> Result<Option<Notam>> {
+        let conn = self.db.get_conn()?;
+        conn.query_row(
+            &format!("SELECT {} FROM notams WHERE id = ?1", NOTAM_COLUMNS),
+            [key(location, number)],
+            notam_from_row,
+        )
+        .optional()
+        .map_err(Into::into)
+    }
+
+    /// 指定地点在 `[from, to]` 内生效的通告，按生效时间排序
+    pub fn active_for(&self, locations: &[&str], from: i64, to: i64) -> Result<Vec<Notam>> {
+        let conn = self.db.get_conn()?;
+        let mut notams = Vec::new();
+        for location in locations {
+            notams.extend(active(&conn, &normalize_code(location), from, to)?);
+        }
+        notams.sort_by(|a, b| a.valid_from.cmp(&b.valid_from).then(a.number.cmp(&b.number)));
+        Ok(notams)
+    }
+
+    /// 指定地点在某一时刻生效的通告
+    pub fn active_at(&self, location: &str, at: i64) -> Result<Vec<Notam>> {
+        self.active_for(&[location], at, at)
+    }
+
+    /// 删除在 `before` 之前已失效的通告
+    pub fn prune(&self, before: i64) -> Result<usize> {
+        Ok(self.db.get_conn()?.execute(
+            "DELETE FROM notams WHERE valid_to IS NOT NULL AND valid_to < ?1",
+            [before],
+        )?)
+    }
+}
+
+const NOTAM_COLUMNS: &str = "number, kind, reference, location, fir, q_code, lower_limit, \
+    upper_limit, latitude, longitude, radius_nm, valid_from, valid_to, estimated_end, \
+    schedule, text, raw, source";
+
+fn key(location: &str, number: &str) -> String {
+    format!("{} {}", normalize_code(location), number.trim().to_uppercase())
+}
+
+/// Apply NOTAMs in order; a replacement or cancellation removes the NOTAM it
+/// refers to, at the same location
+fn apply(conn: &Connection, notams: &[Notam], now: i64) -> Result<NotamImport> {
+    let mut result = NotamImport::default();
+    for notam in notams {
+        if let Some(reference) = &notam.references {
+            let referenced = key(&notam.location, reference);
+            result.cancelled += conn.execute("DELETE FROM notams WHERE id = ?1", [referenced])?;
+        }
+        if notam.kind == NotamKind::Cancel {
+            continue;
+        }
+        conn.execute(
+            &format!(
+                "INSERT OR REPLACE INTO notams (id, {}, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+                         ?16, ?17, ?18, ?19, ?20)",
+                NOTAM_COLUMNS
+            ),
+            rusqlite::params![
+                notam.key(),
+                notam.number,
+                notam.kind.as_str(),
+                notam.references,
+                normalize_code(&notam.location),
+                notam.fir,
+                notam.q_code,
+                notam.lower_limit,
+                notam.upper_limit,
+                notam.center.map(|c| c.latitude),
+                notam.center.map(|c| c.longitude),
+                notam.radius_nm,
+                notam.valid_from,
+                notam.valid_to,
+                notam.estimated_end,
+                notam.schedule,
+                notam.text,
+                notam.raw,
+                notam.source,
+                now
+            ],
+        )?;
+        result.stored += 1;
+    }
+    Ok(result)
+}
+
+pub(crate) fn active(conn: &Connection, location: &str, from: i64, to: i64) -> Result<Vec<Notam>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM notams
+         WHERE location = ?1 AND valid_from <= ?3 AND (valid_to IS NULL OR valid_to >= ?2)
+         ORDER BY valid_from, number",
+        NOTAM_COLUMNS
+    ))?;
+    let notams = stmt
+        .query_map(rusqlite::params![location, from, to], notam_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(notams)
+}
+
+/// NOTAMs with an area of influence in force at some point of `[from, to]`
+pub(crate) fn active_with_area(conn: &Connection, from: i64, to: i64) -> Result<Vec<Notam>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM notams
+         WHERE latitude IS NOT NULL AND longitude IS NOT NULL
+           AND valid_from <= ?2 AND (valid_to IS NULL OR valid_to >= ?1)
+         ORDER BY valid_from, number",
+        NOTAM_COLUMNS
+    ))?;
+    let notams = stmt
+        .query_map([from, to], notam_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(notams)
+}
+
+fn notam_from_row(row: &Row) -> rusqlite::Result<Notam> {
+    let kind: String = row.get(1)?;
+    let latitude: Option<f64> = row.get(8)?;
+    let longitude: Option<f64> = row.get(9)?;
+    Ok(Notam {
+        number: row.get(0)?,
+        kind: NotamKind::from_str(&kind).unwrap_or(NotamKind::New),
+        references: row.get(2)?,
+        location: row.get(3)?,
+        fir: row.get(4)?,
+        q_code: row.get(5)?,
+        lower_limit: row.get(6)?,
+        upper_limit: row.get(7)?,
+        center: latitude.zip(longitude).map(|(lat, lon)| Coordinate::new(lat, lon)),
+        radius_nm: row.get(10)?,
+        valid_from: row.get(11)?,
+        valid_to: row.get(12)?,
+        estimated_end: row.get(13)?,
+        schedule: row.get(14)?,
+        text: row.get(15)?,
+        raw: row.get(16)?,
+        source: row.get(17)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use crate::sync::transport::tests::CannedTransport;
+    use tempfile::NamedTempFile;
+
+    fn manager() -> (NotamManager, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (NotamManager::new(db).unwrap(), temp_file)
+    }
+
+    #[test]
+    fn test_store_replace_and_cancel() {
+        let (notams, _temp) = manager();
+        let original = "(A1200/24 NOTAMN A) ZBAA B) 2401080000 C) 2401200000 E) RWY 18L CLSD)";
+        let result = notams.store_icao(original).unwrap();
+        assert_eq!((result.stored, result.cancelled), (1, 0));
+        assert!(notams.get("zbaa", "A1200/24").unwrap().is_some());
+
+        let text = format!("{}\n(A0000/24 NOTAMX)", icao::tests::REPLACEMENT);
+        let result = notams.store_icao(&text).unwrap();
+        assert_eq!((result.stored, result.cancelled, result.skipped), (1, 1, 1));
+        assert!(notams.get("ZBAA", "A1200/24").unwrap().is_none());
+
+        // In force from 10 Jan 12:00 to an estimated 20 Jan 18:00
+        let active = notams.active_at("ZBAA", 1_705_000_000).unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0], icao::parse(icao::tests::REPLACEMENT).unwrap());
+        assert!(notams.active_at("ZBAA", 1_704_800_000).unwrap().is_empty());
+        assert!(notams.active_at("ZSSS", 1_705_000_000).unwrap().is_empty());
+
+        let cancel = "(A1300/24 NOTAMC A1234/24\nA) ZBAA B) 2401151000)";
+        let result = notams.store_icao(cancel).unwrap();
+        assert_eq!((result.stored, result.cancelled), (0, 1));
+        assert!(notams.active_at("ZBAA", 1_705_000_000).unwrap().is_empty());
+        assert!(notams.store_icao("NIL").is_err());
+    }
+
+    #[test]
+    fn test_active_for_and_prune() {
+        let (notams, _temp) = manager();
+        let text = "(A0001/24 NOTAMN A) ZBAA B) 2401010000 C) PERM E) NEW TWY J OPEN)
+(A0002/24 NOTAMN A) ZSSS B) 2401050000 C) 2401060000 E) ILS 34L U/S)
+(A0003/24 NOTAMN A) ZSSS B) 2402010000 C) 2402020000 E) VOR U/S)";
+        assert_eq!(notams.store_icao(text).unwrap().stored, 3);
+
+        let january =
+            notams.active_for(&["ZBAA", "zsss"], 1_704_067_200, 1_706_745_599).unwrap();
+        let numbers: Vec<&str> = january.iter().map(|n| n.number.as_str()).collect();
+        assert_eq!(numbers, ["A0001/24", "A0002/24"]);
+
+        assert_eq!(notams.prune(1_706_745_600).unwrap(), 1);
+        assert!(notams.get("ZSSS", "A0002/24").unwrap().is_none());
+        assert!(notams.get("ZBAA", "A0001/24").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_import_faa_replaces_location_set() {
+        let (notams, _temp) = manager();
+        notams
+            .store_icao("(A0009/24 NOTAMN A) KJFK B) 2401010000 C) PERM E) LOCAL NOTE)")
+            .unwrap();
+        let first = faa::tests::page(1, ["A0100/24", "01/123"]);
+        let transport = CannedTransport::default().with(faa::FAA_NOTAM_PATH, vec![&first]);
+        let result = notams.import_faa(&transport, &["kjfk"]).unwrap();
+        assert_eq!((result.stored, result.cancelled), (2, 0));
+
+        let second = faa::tests::page(1, ["A0100/24", "01/124"]);
+        let transport = CannedTransport::default().with(faa::FAA_NOTAM_PATH, vec![&second]);
+        let result = notams.import_faa(&transport, &["KJFK"]).unwrap();
+        assert_eq!((result.stored, result.cancelled), (2, 1));
+        assert!(notams.get("KJFK", "01/123").unwrap().is_none());
+        assert!(notams.get("KJFK", "01/124").unwrap().is_some());
+        // NOTAMs from other sources are left alone
+        assert!(notams.get("KJFK", "A0009/24").unwrap().is_some());
+        let last_sync: i64 = notams
+            .db
+            .get_conn()
+            .unwrap()
+            .query_row(
+                "SELECT last_sync FROM sync_metadata WHERE table_name = 'notams'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(last_sync > 0);
+    }
+}
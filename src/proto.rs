@@ -0,0 +1,533 @@
+use crate::error::{AeroBaseError, Result};
+use crate::flight;
+use crate::models::{self, airport, navaid, waypoint};
+use prost::Message;
+
+// Messages mirror `proto/aerobase.proto` and are declared with prost's derive
+// so building doesn't need `protoc`; keep both in step when adding a field.
+
+#[derive(Clone, Copy, PartialEq, Message)]
+pub struct Coordinate {
+    #[prost(double, tag = "1")]
+    pub latitude: f64,
+    #[prost(double, tag = "2")]
+    pub longitude: f64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Airport {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub icao: String,
+    #[prost(string, optional, tag = "3")]
+    pub iata: Option<String>,
+    #[prost(string, tag = "4")]
+    pub name: String,
+    #[prost(message, optional, tag = "5")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(int32, optional, tag = "6")]
+    pub elevation: Option<i32>,
+    #[prost(string, optional, tag = "7")]
+    pub country: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub region: Option<String>,
+    #[prost(int64, tag = "9")]
+    pub created_at: i64,
+    #[prost(int32, optional, tag = "10")]
+    pub transition_altitude: Option<i32>,
+    #[prost(int32, optional, tag = "11")]
+    pub transition_level: Option<i32>,
+    #[prost(double, optional, tag = "12")]
+    pub magnetic_variation: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Waypoint {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(message, optional, tag = "3")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(string, optional, tag = "4")]
+    pub region: Option<String>,
+    #[prost(string, tag = "5")]
+    pub waypoint_type: String,
+    #[prost(int64, tag = "6")]
+    pub created_at: i64,
+    #[prost(string, optional, tag = "7")]
+    pub usage: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Navaid {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(string, tag = "3")]
+    pub navaid_type: String,
+    #[prost(message, optional, tag = "4")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(double, optional, tag = "5")]
+    pub frequency: Option<f64>,
+    #[prost(int32, optional, tag = "6")]
+    pub range_nm: Option<i32>,
+    #[prost(int32, optional, tag = "7")]
+    pub elevation: Option<i32>,
+    #[prost(string, optional, tag = "8")]
+    pub region: Option<String>,
+    #[prost(int64, tag = "9")]
+    pub created_at: i64,
+    #[prost(double, optional, tag = "10")]
+    pub declination: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FlightPlan {
+    #[prost(string, tag = "1")]
+    pub departure: String,
+    #[prost(string, tag = "2")]
+    pub destination: String,
+    #[prost(string, optional, tag = "3")]
+    pub alternate: Option<String>,
+    #[prost(int32, tag = "4")]
+    pub cruise_altitude: i32,
+    #[prost(int32, tag = "5")]
+    pub cruise_speed: i32,
+    #[prost(string, repeated, tag = "6")]
+    pub route: Vec<String>,
+    #[prost(string, optional, tag = "7")]
+    pub sid: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub star: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub approach: Option<String>,
+    #[prost(message, repeated, tag = "10")]
+    pub altitude_profile: Vec<AltitudeStep>,
+    #[prost(int64, optional, tag = "11")]
+    pub departure_time: Option<i64>,
+    #[prost(string, optional, tag = "12")]
+    pub aircraft: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct AltitudeStep {
+    #[prost(string, tag = "1")]
+    pub waypoint: String,
+    #[prost(int32, tag = "2")]
+    pub altitude: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct RouteWaypoint {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(message, optional, tag = "3")]
+    pub coordinate: Option<Coordinate>,
+    #[prost(double, tag = "4")]
+    pub distance_from_previous: f64,
+    #[prost(double, tag = "5")]
+    pub cumulative_distance: f64,
+    #[prost(int32, tag = "6")]
+    pub estimated_time: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FlightRoute {
+    #[prost(message, optional, tag = "1")]
+    pub plan: Option<FlightPlan>,
+    #[prost(double, tag = "2")]
+    pub total_distance: f64,
+    #[prost(int32, tag = "3")]
+    pub estimated_time: i32,
+    #[prost(message, repeated, tag = "4")]
+    pub waypoints: Vec<RouteWaypoint>,
+}
+
+impl From<models::Coordinate> for Coordinate {
+    fn from(c: models::Coordinate) -> Self {
+        Self {
+            latitude: c.latitude,
+            longitude: c.longitude,
+        }
+    }
+}
+
+impl From<Coordinate> for models::Coordinate {
+    fn from(c: Coordinate) -> Self {
+        models::Coordinate::new(c.latitude, c.longitude)
+    }
+}
+
+/// Unwrap a message field proto3 lets the sender leave out
+fn required<T>(field: Option<T>, message: &str, name: &str) -> Result<T> {
+    field.ok_or_else(|| AeroBaseError::InvalidInput(format!("{} is missing {}", message, name)))
+}
+
+impl From<&airport::Airport> for Airport {
+    fn from(a: &airport::Airport) -> Self {
+        Self {
+            id: a.id.clone(),
+            icao: a.icao.clone(),
+            iata: a.iata.clone(),
+            name: a.name.clone(),
+            coordinate: Some(a.coordinate.into()),
+            elevation: a.elevation,
+            country: a.country.clone(),
+            region: a.region.clone(),
+            created_at: a.created_at,
+            transition_altitude: a.transition_altitude,
+            transition_level: a.transition_level,
+            magnetic_variation: a.magnetic_variation,
+        }
+    }
+}
+
+impl TryFrom<Airport> for airport::Airport {
+    type Error = AeroBaseError;
+
+    fn try_from(a: Airport) -> Result<Self> {
+        Ok(Self {
+            coordinate: required(a.coordinate, "Airport", "coordinate")?.into(),
+            id: a.id,
+            icao: a.icao,
+            iata: a.iata,
+            name: a.name,
+            elevation: a.elevation,
+            country: a.country,
+            region: a.region,
+            created_at: a.created_at,
+            transition_altitude: a.transition_altitude,
+            transition_level: a.transition_level,
+            magnetic_variation: a.magnetic_variation,
+        })
+    }
+}
+
+impl From<&waypoint::Waypoint> for Waypoint {
+    fn from(w: &waypoint::Waypoint) -> Self {
+        Self {
+            id: w.id.clone(),
+            name: w.name.clone(),
+            coordinate: Some(w.coordinate.into()),
+            region: w.region.clone(),
+            waypoint_type: w.waypoint_type.as_str().to_string(),
+            created_at: w.created_at,
+            usage: w.usage.map(|u| u.as_str().to_string()),
+        }
+    }
+}
+
+impl TryFrom<Waypoint> for waypoint::Waypoint {
+    type Error = AeroBaseError;
+
+    fn try_from(w: Waypoint) -> Result<Self> {
+        Ok(Self {
+            coordinate: required(w.coordinate, "Waypoint", "coordinate")?.into(),
+            waypoint_type: waypoint::WaypointType::from_str(&w.waypoint_type),
+            usage: w.usage.as_deref().and_then(waypoint::WaypointUsage::from_str),
+            id: w.id,
+            name: w.name,
+            region: w.region,
+            created_at: w.created_at,
+        })
+    }
+}
+
+impl From<&navaid::Navaid> for Navaid {
+    fn from(n: &navaid::Navaid) -> Self {
+        Self {
+            id: n.id.clone(),
+            name: n.name.clone(),
+            navaid_type: n.navaid_type.as_str().to_string(),
+            coordinate: Some(n.coordinate.into()),
+            frequency: n.frequency,
+            range_nm: n.range_nm,
+            elevation: n.elevation,
+            region: n.region.clone(),
+            created_at: n.created_at,
+            declination: n.declination,
+        }
+    }
+}
+
+impl TryFrom<Navaid> for navaid::Navaid {
+    type Error = AeroBaseError;
+
+    fn try_from(n: Navaid) -> Result<Self> {
+        Ok(Self {
+            coordinate: required(n.coordinate, "Navaid", "coordinate")?.into(),
+            navaid_type: navaid::NavaidType::from_str(&n.navaid_type),
+            id: n.id,
+            name: n.name,
+            frequency: n.frequency,
+            range_nm: n.range_nm,
+            elevation: n.elevation,
+            region: n.region,
+            created_at: n.created_at,
+            declination: n.declination,
+        })
+    }
+}
+
+impl From<&flight::FlightPlan> for FlightPlan {
+    fn from(p: &flight::FlightPlan) -> Self {
+        Self {
+            departure: p.departure.clone(),
+            destination: p.destination.clone(),
+            alternate: p.alternate.clone(),
+            cruise_altitude: p.cruise_altitude,
+            cruise_speed: p.cruise_speed,
+            route: p.route.clone(),
+            sid: p.procedures.sid.clone(),
+            star: p.procedures.star.clone(),
+            approach: p.procedures.approach.clone(),
+            altitude_profile: p
+                .altitude_profile
+                .iter()
+                .map(|step| AltitudeStep {
+                    waypoint: step.waypoint.clone(),
+                    altitude: step.altitude,
+                })
+                .collect(),
+            departure_time: p.departure_time,
+            aircraft: p.aircraft.clone(),
+        }
+    }
+}
+
+impl TryFrom<FlightPlan> for flight::FlightPlan {
+    type Error = AeroBaseError;
+
+    fn try_from(p: FlightPlan) -> Result<Self> {
+        Ok(Self {
+            departure: p.departure,
+            destination: p.destination,
+            alternate: p.alternate,
+            cruise_altitude: p.cruise_altitude,
+            cruise_speed: p.cruise_speed,
+            route: p.route,
+            procedures: flight::PlanProcedures {
+                sid: p.sid,
+                star: p.star,
+                approach: p.approach,
+            },
+            altitude_profile: p
+                .altitude_profile
+                .into_iter()
+                .map(|step| flight::AltitudeStep {
+                    waypoint: step.waypoint,
+                    altitude: step.altitude,
+                })
+                .collect(),
+            departure_time: p.departure_time,
+            aircraft: p.aircraft,
+        })
+    }
+}
+
+impl From<&flight::RouteWaypoint> for RouteWaypoint {
+    fn from(w: &flight::RouteWaypoint) -> Self {
+        Self {
+            id: w.id.clone(),
+            name: w.name.clone(),
+            coordinate: Some(w.coordinate.into()),
+            distance_from_previous: w.distance_from_previous,
+            cumulative_distance: w.cumulative_distance,
+            estimated_time: w.estimated_time,
+        }
+    }
+}
+
+impl TryFrom<RouteWaypoint> for flight::RouteWaypoint {
+    type Error = AeroBaseError;
+
+    fn try_from(w: RouteWaypoint) -> Result<Self> {
+        Ok(Self {
+            coordinate: required(w.coordinate, "RouteWaypoint", "coordinate")?.into(),
+            id: w.id,
+            name: w.name,
+            distance_from_previous: w.distance_from_previous,
+            cumulative_distance: w.cumulative_distance,
+            estimated_time: w.estimated_time,
+        })
+    }
+}
+
+impl From<&flight::FlightRoute> for FlightRoute {
+    fn from(r: &flight::FlightRoute) -> Self {
+        Self {
+            plan: Some((&r.plan).into()),
+            total_distance: r.total_distance,
+            estimated_time: r.estimated_time,
+            waypoints: r.waypoints.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<FlightRoute> for flight::FlightRoute {
+    type Error = AeroBaseError;
+
+    fn try_from(r: FlightRoute) -> Result<Self> {
+        Ok(Self {
+            plan: required(r.plan, "FlightRoute", "plan")?.try_into()?,
+            total_distance: r.total_distance,
+            estimated_time: r.estimated_time,
+            waypoints: r
+                .waypoints
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Encode a model straight to and from protobuf bytes
+pub trait ProtoCodec: Sized {
+    fn to_protobuf(&self) -> Vec<u8>;
+    fn from_protobuf(bytes: &[u8]) -> Result<Self>;
+}
+
+macro_rules! proto_codec {
+    ($($model:ty => $message:ty),* $(,)?) => {
+        $(
+            impl ProtoCodec for $model {
+                fn to_protobuf(&self) -> Vec<u8> {
+                    <$message>::from(self).encode_to_vec()
+                }
+
+                fn from_protobuf(bytes: &[u8]) -> Result<Self> {
+                    <$message>::decode(bytes)
+                        .map_err(|e| AeroBaseError::InvalidInput(format!("Invalid protobuf: {}", e)))?
+                        .try_into()
+                }
+            }
+        )*
+    };
+}
+
+proto_codec! {
+    airport::Airport => Airport,
+    waypoint::Waypoint => Waypoint,
+    navaid::Navaid => Navaid,
+    flight::FlightPlan => FlightPlan,
+    flight::FlightRoute => FlightRoute,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    /// Encode, decode and compare the serde forms, which cover every field
+    fn assert_round_trip<T: ProtoCodec + Serialize>(model: &T) {
+        let decoded = T::from_protobuf(&model.to_protobuf()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(model).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_navdata_round_trip() {
+        let mut airport = airport::Airport::new(
+            "A1".to_string(),
+            "ZBAA".to_string(),
+            "Capital".to_string(),
+            models::Coordinate::new(40.08, 116.58),
+        );
+        airport.iata = Some("PEK".to_string());
+        airport.elevation = Some(116);
+        airport.transition_altitude = Some(9800);
+        airport.transition_level = Some(11800);
+        assert_round_trip(&airport);
+        // Unset optionals stay unset rather than becoming "" or 0
+        airport.iata = None;
+        airport.elevation = None;
+        assert_round_trip(&airport);
+
+        let mut fix = waypoint::Waypoint::new(
+            "W1".to_string(),
+            "ALPHA".to_string(),
+            models::Coordinate::new(-33.9, 151.2),
+            waypoint::WaypointType::GPS,
+        );
+        assert_round_trip(&fix);
+        fix.usage = Some(waypoint::WaypointUsage::Terminal);
+        assert_round_trip(&fix);
+
+        let mut vor = navaid::Navaid::new(
+            "N1".to_string(),
+            "PEK".to_string(),
+            navaid::NavaidType::VORDME,
+            models::Coordinate::new(40.07, 116.6),
+        );
+        vor.frequency = Some(114.7);
+        vor.range_nm = Some(130);
+        vor.declination = Some(-6.0);
+        assert_round_trip(&vor);
+    }
+
+    #[test]
+    fn test_flight_round_trip() {
+        let plan = flight::FlightPlan {
+            departure: "ZBAA".to_string(),
+            destination: "ZSSS".to_string(),
+            alternate: Some("ZSPD".to_string()),
+            cruise_altitude: 35000,
+            cruise_speed: 450,
+            route: vec!["WP1".to_string(), "WP2".to_string()],
+            ..Default::default()
+        };
+        assert_round_trip(&plan);
+        let plan = flight::FlightPlan {
+            procedures: flight::PlanProcedures {
+                sid: Some("ELK1A".to_string()),
+                star: None,
+                approach: Some("I36L".to_string()),
+            },
+            altitude_profile: vec![flight::AltitudeStep {
+                waypoint: "WP2".to_string(),
+                altitude: 37000,
+            }],
+            departure_time: Some(1_700_000_000),
+            aircraft: Some("B-1234".to_string()),
+            ..plan
+        };
+        assert_round_trip(&plan);
+
+        let route = flight::FlightRoute {
+            plan,
+            total_distance: 580.25,
+            estimated_time: 77,
+            waypoints: vec![flight::RouteWaypoint {
+                id: "WP1".to_string(),
+                name: "ONE".to_string(),
+                coordinate: models::Coordinate::new(36.0, 119.0),
+                distance_from_previous: 290.5,
+                cumulative_distance: 290.5,
+                estimated_time: 39,
+            }],
+        };
+        assert_round_trip(&route);
+        assert!(route.to_protobuf().len() < serde_json::to_vec(&route).unwrap().len());
+    }
+
+    #[test]
+    fn test_rejects_incomplete_messages() {
+        let bytes = Waypoint {
+            id: "W1".to_string(),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        assert!(matches!(
+            waypoint::Waypoint::from_protobuf(&bytes),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+        assert!(flight::FlightRoute::from_protobuf(&[0xff, 0xff]).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::clock::{self, Clock};
+use crate::error::{AeroBaseError, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 服务端接口限流配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    /// 每个客户端每秒补充的令牌数
+    pub requests_per_second: f64,
+    /// 令牌桶容量（允许的突发请求数）
+    pub burst: u32,
+    /// 每台设备每天（UTC）的查询配额，None 表示不限
+    pub daily_quota: Option<u32>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 20.0,
+            burst: 40,
+            daily_quota: None,
+        }
+    }
+}
+
+/// How often idle clients and past days are dropped from the limiter
+const SWEEP_INTERVAL_SECS: i64 = 60;
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: DateTime<Utc>,
+}
+
+impl TokenBucket {
+    /// Tokens available at `now`
+    fn available(&self, now: DateTime<Utc>, rate: f64, capacity: f64) -> f64 {
+        let elapsed = (now - self.last_refill).num_microseconds().unwrap_or(i64::MAX).max(0);
+        (self.tokens + elapsed as f64 / 1_000_000.0 * rate).min(capacity)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DailyUsage {
+    day: NaiveDate,
+    count: u32,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    buckets: HashMap<String, TokenBucket>,
+    usage: HashMap<String, DailyUsage>,
+    last_sweep: Option<DateTime<Utc>>,
+}
+
+/// Per-client token buckets plus per-device daily quotas
+///
+/// Request handlers call `check` before doing any database work; the
+/// returned errors map to HTTP 429 / gRPC RESOURCE_EXHAUSTED.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    clock: Arc<dyn Clock>,
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    /// Create a limiter from configuration
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            clock: clock::system(),
+            state: Mutex::new(LimiterState::default()),
+        }
+    }
+
+    /// Refill buckets and roll quotas over by `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Consume one request for `client_id`, counted against `device_id`'s daily quota
+    ///
+    /// A rejected request consumes neither a token nor quota.
+    pub fn check(&self, client_id: &str, device_id: Option<&str>) -> Result<()> {
+        self.check_at(client_id, device_id, self.clock.now())
+    }
+
+    fn check_at(&self, client_id: &str, device_id: Option<&str>, now: DateTime<Utc>) -> Result<()> {
+        let capacity = self.config.burst.max(1) as f64;
+        let rate = self.config.requests_per_second;
+        let today = now.date_naive();
+
+        let mut state = self.state.lock().expect("rate limiter lock poisoned");
+        self.sweep(&mut state, now);
+
+        let tokens = state
+            .buckets
+            .get(client_id)
+            .map_or(capacity, |bucket| bucket.available(now, rate, capacity));
+        if tokens < 1.0 {
+            let retry_after_ms = if rate > 0.0 {
+                (((1.0 - tokens) / rate) * 1000.0).ceil() as u64
+            } else {
+                u64::MAX
+            };
+            return Err(AeroBaseError::RateLimited {
+                client: client_id.to_string(),
+                retry_after_ms,
+            });
+        }
+
+        let quota = device_id.zip(self.config.daily_quota);
+        if let Some((device_id, quota)) = quota {
+            let used = state
+                .usage
+                .get(device_id)
+                .filter(|usage| usage.day == today)
+                .map_or(0, |usage| usage.count);
+            if used >= quota {
+                return Err(AeroBaseError::QuotaExceeded {
+                    device: device_id.to_string(),
+                    limit: quota,
+                });
+            }
+        }
+
+        state.buckets.insert(
+            client_id.to_string(),
+            TokenBucket {
+                tokens: tokens - 1.0,
+                last_refill: now,
+            },
+        );
+        if let Some((device_id, _)) = quota {
+            let usage = state
+                .usage
+                .entry(device_id.to_string())
+                .or_insert(DailyUsage { day: today, count: 0 });
+            if usage.day != today {
+                *usage = DailyUsage { day: today, count: 0 };
+            }
+            usage.count += 1;
+        }
+        Ok(())
+    }
+
+    /// Drop buckets that have refilled, which behave like new ones, and
+    /// usage from past days, at most once per `SWEEP_INTERVAL_SECS`
+    fn sweep(&self, state: &mut LimiterState, now: DateTime<Utc>) {
+        if state
+            .last_sweep
+            .is_some_and(|last| (now - last).num_seconds() < SWEEP_INTERVAL_SECS)
+        {
+            return;
+        }
+        let capacity = self.config.burst.max(1) as f64;
+        let rate = self.config.requests_per_second;
+        let today = now.date_naive();
+        state
+            .buckets
+            .retain(|_, bucket| bucket.available(now, rate, capacity) < capacity);
+        state.usage.retain(|_, usage| usage.day == today);
+        state.last_sweep = Some(now);
+    }
+
+    /// Queries already counted today for a device
+    pub fn used_today(&self, device_id: &str) -> u32 {
+        let today = self.clock.now().date_naive();
+        let state = self.state.lock().expect("rate limiter lock poisoned");
+        state
+            .usage
+            .get(device_id)
+            .filter(|u| u.day == today)
+            .map(|u| u.count)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let clock = Arc::new(MockClock::at_timestamp(1_704_067_200));
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 2.0,
+            burst: 2,
+            daily_quota: None,
+        })
+        .with_clock(clock.clone());
+
+        assert!(limiter.check("tablet", None).is_ok());
+        assert!(limiter.check("tablet", None).is_ok());
+        match limiter.check("tablet", None) {
+            Err(AeroBaseError::RateLimited { retry_after_ms, .. }) => {
+                assert_eq!(retry_after_ms, 500)
+            }
+            other => panic!("expected rate limit, got {:?}", other),
+        }
+
+        // Other clients have their own bucket
+        assert!(limiter.check("laptop", None).is_ok());
+
+        clock.advance(TimeDelta::milliseconds(500));
+        assert!(limiter.check("tablet", None).is_ok());
+    }
+
+    #[test]
+    fn test_daily_quota_resets() {
+        // 23:59:59 UTC on 2024-01-01
+        let clock = Arc::new(MockClock::at_timestamp(1_704_153_599));
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1000.0,
+            burst: 1000,
+            daily_quota: Some(2),
+        })
+        .with_clock(clock.clone());
+
+        assert!(limiter.check("c", Some("dev")).is_ok());
+        assert!(limiter.check("c", Some("dev")).is_ok());
+        assert!(matches!(
+            limiter.check("c", Some("dev")),
+            Err(AeroBaseError::QuotaExceeded { limit: 2, .. })
+        ));
+        assert_eq!(limiter.used_today("dev"), 2);
+
+        clock.advance(TimeDelta::seconds(1));
+        assert_eq!(limiter.used_today("dev"), 0);
+        assert!(limiter.check("c", Some("dev")).is_ok());
+    }
+
+    #[test]
+    fn test_rejections_cost_nothing() {
+        let clock = Arc::new(MockClock::at_timestamp(1_704_067_200));
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1.0,
+            burst: 2,
+            daily_quota: Some(1),
+        })
+        .with_clock(clock.clone());
+
+        assert!(limiter.check("c", Some("dev")).is_ok());
+        // Over quota: the bucket keeps its remaining token for another device
+        assert!(matches!(
+            limiter.check("c", Some("dev")),
+            Err(AeroBaseError::QuotaExceeded { .. })
+        ));
+        assert!(limiter.check("c", Some("other")).is_ok());
+        // Rate limited: the quota of a fresh device is untouched
+        assert!(matches!(
+            limiter.check("c", Some("fresh")),
+            Err(AeroBaseError::RateLimited { .. })
+        ));
+        assert_eq!(limiter.used_today("fresh"), 0);
+    }
+
+    #[test]
+    fn test_idle_clients_are_evicted() {
+        let clock = Arc::new(MockClock::at_timestamp(1_704_067_200));
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1.0,
+            burst: 5,
+            daily_quota: Some(1000),
+        })
+        .with_clock(clock.clone());
+
+        for client in 0..100 {
+            limiter.check(&format!("client-{}", client), Some("dev")).unwrap();
+        }
+        assert_eq!(limiter.state.lock().unwrap().buckets.len(), 100);
+
+        // A minute later every bucket has refilled; only the new client stays
+        clock.advance(TimeDelta::seconds(SWEEP_INTERVAL_SECS));
+        limiter.check("client-new", None).unwrap();
+        {
+            let state = limiter.state.lock().unwrap();
+            assert_eq!(state.buckets.keys().collect::<Vec<_>>(), vec!["client-new"]);
+            assert_eq!(state.usage.len(), 1);
+        }
+
+        // Quota usage goes once its day is over
+        clock.advance(TimeDelta::days(1));
+        limiter.check("client-new", None).unwrap();
+        assert!(limiter.state.lock().unwrap().usage.is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::db::Database;
+use crate::error::Result;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 可按配置文件隐藏的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RestrictedKind {
+    Airport,
+    Airspace,
+}
+
+impl RestrictedKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestrictedKind::Airport => "AIRPORT",
+            RestrictedKind::Airspace => "AIRSPACE",
+        }
+    }
+}
+
+/// Rows hidden from one profile
+///
+/// A row marked with a category is hidden unless the profile is entitled
+/// to that category. Unmarked rows are always visible.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Redaction {
+    airports: HashSet<String>,
+    airspaces: HashSet<String>,
+}
+
+impl Redaction {
+    /// Load the rows hidden from `profile_id`
+    pub fn load(conn: &Connection, profile_id: &str) -> Result<Self> {
+        let mut stmt = conn.prepare_cached(
+            "SELECT item_type, item_id FROM restricted_items
+             WHERE category NOT IN (
+                 SELECT category FROM profile_entitlements WHERE profile_id = ?1)",
+        )?;
+        let mut rows = stmt.query([profile_id])?;
+
+        let mut redaction = Self::default();
+        while let Some(row) = rows.next()? {
+            let kind: String = row.get(0)?;
+            let id: String = row.get(1)?;
+            if kind == RestrictedKind::Airport.as_str() {
+                redaction.airports.insert(id);
+            } else if kind == RestrictedKind::Airspace.as_str() {
+                redaction.airspaces.insert(id);
+            }
+        }
+        Ok(redaction)
+    }
+
+    /// Whether nothing is hidden
+    pub fn is_empty(&self) -> bool {
+        self.airports.is_empty() && self.airspaces.is_empty()
+    }
+
+    pub fn hides_airport(&self, airport_id: &str) -> bool {
+        self.airports.contains(airport_id)
+    }
+
+    pub fn hides_airspace(&self, airspace_id: &str) -> bool {
+        self.airspaces.contains(airspace_id)
+    }
+}
+
+/// 按配置文件权限隐藏敏感数据（军用机场、敏感空域等）的策略
+///
+/// 数据行标记一个类别后，只有拥有该类别权限的配置文件才能在机场仓库和空间查询中
+/// 看到它；未标记的数据对所有配置文件可见。
+pub struct RedactionPolicy {
+    db: Arc<Database>,
+}
+
+impl RedactionPolicy {
+    /// 创建隐藏策略管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// 将数据行标记为某类别（如 "MILITARY"），替换已有类别
+    pub fn restrict(&self, kind: RestrictedKind, item_id: &str, category: &str) -> Result<()> {
+        self.db.get_conn()?.execute(
+            "INSERT INTO restricted_items (item_type, item_id, category) VALUES (?1, ?2, ?3)
+             ON CONFLICT(item_type, item_id) DO UPDATE SET category = excluded.category",
+            rusqlite::params![kind.as_str(), item_id, category],
+        )?;
+        Ok(())
+    }
+
+    /// 取消数据行的标记
+    pub fn unrestrict(&self, kind: RestrictedKind, item_id: &str) -> Result<()> {
+        self.db.get_conn()?.execute(
+            "DELETE FROM restricted_items WHERE item_type = ?1 AND item_id = ?2",
+            rusqlite::params![kind.as_str(), item_id],
+        )?;
+        Ok(())
+    }
+
+    /// 授予配置文件查看某类别的权限
+    pub fn grant(&self, profile_id: &str, category: &str) -> Result<()> {
+        self.db.get_conn()?.execute(
+            "INSERT OR IGNORE INTO profile_entitlements (profile_id, category) VALUES (?1, ?2)",
+            [profile_id, category],
+        )?;
+        Ok(())
+    }
+
+    /// 撤销配置文件查看某类别的权限
+    pub fn revoke(&self, profile_id: &str, category: &str) -> Result<()> {
+        self.db.get_conn()?.execute(
+            "DELETE FROM profile_entitlements WHERE profile_id = ?1 AND category = ?2",
+            [profile_id, category],
+        )?;
+        Ok(())
+    }
+
+    /// 配置文件拥有的权限类别（按名称排序）
+    pub fn entitlements(&self, profile_id: &str) -> Result<Vec<String>> {
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT category FROM profile_entitlements WHERE profile_id = ?1 ORDER BY category",
+        )?;
+        let categories = stmt
+            .query_map([profile_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(categories)
+    }
+
+    /// 对配置文件隐藏的数据行
+    pub fn for_profile(&self, profile_id: &str) -> Result<Redaction> {
+        let conn = self.db.get_conn()?;
+        Redaction::load(&conn, profile_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_entitlements_reveal_categories() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let policy = RedactionPolicy::new(db).unwrap();
+
+        assert!(policy.for_profile("crew").unwrap().is_empty());
+        policy.restrict(RestrictedKind::Airport, "AP1", "MILITARY").unwrap();
+        policy.restrict(RestrictedKind::Airspace, "AS1", "SENSITIVE").unwrap();
+
+        let crew = policy.for_profile("crew").unwrap();
+        assert!(crew.hides_airport("AP1"));
+        assert!(crew.hides_airspace("AS1"));
+        assert!(!crew.hides_airport("AP2"));
+
+        policy.grant("crew", "MILITARY").unwrap();
+        policy.grant("crew", "MILITARY").unwrap();
+        assert_eq!(policy.entitlements("crew").unwrap(), vec!["MILITARY"]);
+        let crew = policy.for_profile("crew").unwrap();
+        assert!(!crew.hides_airport("AP1"));
+        assert!(crew.hides_airspace("AS1"));
+        // Entitlements are per profile
+        assert!(policy.for_profile("guest").unwrap().hides_airport("AP1"));
+
+        policy.revoke("crew", "MILITARY").unwrap();
+        assert!(policy.for_profile("crew").unwrap().hides_airport("AP1"));
+        policy.unrestrict(RestrictedKind::Airport, "AP1").unwrap();
+        assert!(!policy.for_profile("guest").unwrap().hides_airport("AP1"));
+    }
+}
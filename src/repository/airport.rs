@@ -0,0 +1,1388 @@
+use crate::db::query::{
+    airport_from_row, like_contains, like_prefix, navaid_from_row, near_miss_airports,
+    normalize_code, LookupResult, AIRPORT_COLUMNS, AIRPORT_ID_BY_CODE, MAX_AIRPORT_SUGGESTIONS,
+    NAVAID_COLUMNS,
+};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::calculator;
+use crate::history::DEFAULT_PROFILE;
+use crate::ids::IdStrategy;
+use crate::models::{
+    airport::{Airport, AirportAlias, AirportFrequency},
+    chart::{Chart, ChartType},
+    navaid::{Navaid, NavaidType},
+    procedure::{Procedure, ProcedureType},
+    runway::{Localizer, Runway, Wind},
+    Coordinate,
+};
+use crate::navdata::cycle;
+use crate::navdata::text::search_key;
+use crate::redaction::Redaction;
+use crate::repository::check_position;
+use crate::repository::scope::DeleteScope;
+use crate::spatial::geohash::{self, GEOHASH_PRECISION};
+use crate::spatial::geometry;
+use crate::weather::atis::{self, Atis};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Radius used for the navaid list on the airport detail page
+pub const NEARBY_NAVAID_RADIUS_NM: f64 = 25.0;
+
+/// Share of a search score that comes from proximity to the reference point
+pub const SEARCH_DISTANCE_WEIGHT: f64 = 0.5;
+
+/// Distance at which the proximity part of a search score has halved
+pub const SEARCH_DISTANCE_SCALE_NM: f64 = 250.0;
+
+/// Text matches ranked before distance is blended in
+const SEARCH_CANDIDATES: i64 = 1000;
+
+/// A navaid with its distance from the airport reference point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbyNavaid {
+    pub navaid: Navaid,
+    pub distance_nm: f64,
+}
+
+/// Everything an airport detail page renders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportDetails {
+    /// Includes the transition altitude and level
+    pub airport: Airport,
+    /// Runway ends ordered by ident
+    pub runways: Vec<Runway>,
+    pub frequencies: Vec<AirportFrequency>,
+    /// Navaids within `NEARBY_NAVAID_RADIUS_NM`, nearest first
+    pub nearby_navaids: Vec<NearbyNavaid>,
+    pub procedures: Vec<Procedure>,
+    /// Cached ATIS broadcasts for the briefing view, combined first
+    pub atis: Vec<Atis>,
+}
+
+/// An airport search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirportMatch {
+    pub airport: Airport,
+    /// Blended relevance, higher is better (0 to 1)
+    pub score: f64,
+    /// Distance from the reference point, when one was given
+    pub distance_nm: Option<f64>,
+}
+
+/// A runway end with the wind components for landing or departing on it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunwayWind {
+    pub runway: Runway,
+    /// Negative values are tailwind
+    pub headwind: f64,
+    /// Crosswind magnitude
+    pub crosswind: f64,
+    /// True when the crosswind comes from the right
+    pub crosswind_from_right: bool,
+    pub tailwind: f64,
+}
+
+/// A frequency formatted for a radio-management UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TuningFrequency {
+    pub ident: String,
+    /// Navaid type, or "ILS" for a localizer
+    pub kind: String,
+    pub frequency: f64,
+    /// "110.30" for VHF, "352" for NDB (kHz)
+    pub display: String,
+}
+
+impl TuningFrequency {
+    pub(crate) fn new(ident: &str, kind: &str, frequency: f64) -> Self {
+        let display = if kind == NavaidType::NDB.as_str() {
+            format!("{:.0}", frequency)
+        } else {
+            format!("{:.2}", frequency)
+        };
+        Self {
+            ident: ident.to_string(),
+            kind: kind.to_string(),
+            frequency,
+            display,
+        }
+    }
+}
+
+/// Radio setup for flying an approach to one runway end
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproachTuning {
+    pub icao: String,
+    pub runway: String,
+    /// Localizer course when published, otherwise the runway heading
+    pub final_approach_course: Option<f64>,
+    pub localizer: Option<TuningFrequency>,
+    /// Navaids within `NEARBY_NAVAID_RADIUS_NM`, nearest first
+    pub navaids: Vec<TuningFrequency>,
+    /// Approach procedure names for this runway
+    pub procedures: Vec<String>,
+}
+
+pub(crate) const RUNWAY_COLUMNS: &str =
+    "id, airport_id, ident, heading, length_ft, width_ft, surface, latitude, longitude, elevation, created_at, \
+     ils_ident, ils_frequency, ils_course";
+
+pub(crate) fn runway_from_row(row: &Row) -> rusqlite::Result<Runway> {
+    let latitude: Option<f64> = row.get(7)?;
+    let longitude: Option<f64> = row.get(8)?;
+    let ils_ident: Option<String> = row.get(11)?;
+    let ils_frequency: Option<f64> = row.get(12)?;
+    Ok(Runway {
+        id: row.get(0)?,
+        airport_id: row.get(1)?,
+        ident: row.get(2)?,
+        heading: row.get(3)?,
+        length_ft: row.get(4)?,
+        width_ft: row.get(5)?,
+        surface: row.get(6)?,
+        threshold: latitude.zip(longitude).map(|(lat, lon)| Coordinate::new(lat, lon)),
+        elevation: row.get(9)?,
+        ils: match ils_ident.zip(ils_frequency) {
+            Some((ident, frequency)) => Some(Localizer {
+                ident,
+                frequency,
+                course: row.get(13)?,
+            }),
+            None => None,
+        },
+        created_at: row.get(10)?,
+    })
+}
+
+pub(crate) const FREQUENCY_COLUMNS: &str =
+    "id, airport_id, type, frequency, description, created_at";
+
+pub(crate) fn frequency_from_row(row: &Row) -> rusqlite::Result<AirportFrequency> {
+    Ok(AirportFrequency {
+        id: row.get(0)?,
+        airport_id: row.get(1)?,
+        frequency_type: row.get(2)?,
+        frequency: row.get(3)?,
+        description: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+pub(crate) const PROCEDURE_COLUMNS: &str =
+    "id, airport_id, name, type, runway, transition, created_at";
+
+pub(crate) fn procedure_from_row(row: &Row) -> rusqlite::Result<Procedure> {
+    Ok(Procedure {
+        id: row.get(0)?,
+        airport_id: row.get(1)?,
+        name: row.get(2)?,
+        procedure_type: ProcedureType::from_str(&row.get::<_, String>(3)?),
+        runway: row.get(4)?,
+        transition: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+pub(crate) const CHART_COLUMNS: &str =
+    "id, airport_id, procedure_id, identifier, type, title, effective_cycle, file_ref, created_at";
+
+pub(crate) fn chart_from_row(row: &Row) -> rusqlite::Result<Chart> {
+    Ok(Chart {
+        id: row.get(0)?,
+        airport_id: row.get(1)?,
+        procedure_id: row.get(2)?,
+        identifier: row.get(3)?,
+        chart_type: ChartType::from_str(&row.get::<_, String>(4)?),
+        title: row.get(5)?,
+        effective_cycle: row.get(6)?,
+        file_ref: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+pub(crate) const ALIAS_COLUMNS: &str = "id, airport_id, code, kind, created_at";
+
+pub(crate) fn alias_from_row(row: &Row) -> rusqlite::Result<AirportAlias> {
+    Ok(AirportAlias {
+        id: row.get(0)?,
+        airport_id: row.get(1)?,
+        code: row.get(2)?,
+        kind: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// Access to airports and their related navdata
+///
+/// Airports hidden from the repository's profile (see `redaction`) read as
+/// missing. Writes are not filtered by profile.
+pub struct AirportRepository {
+    db: Arc<Database>,
+    profile_id: String,
+}
+
+impl AirportRepository {
+    /// Create a repository for the default profile
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self {
+            db,
+            profile_id: DEFAULT_PROFILE.to_string(),
+        })
+    }
+
+    /// The same repository, filtered by another profile's entitlements
+    pub fn for_profile(&self, profile_id: &str) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            profile_id: profile_id.to_string(),
+        }
+    }
+
+    /// Get an airport by ICAO code
+    ///
+    /// The code is trimmed and upper-cased, and may also be an IATA code or
+    /// an entry in `airport_aliases`.
+    pub fn get_by_icao(&self, icao: &str) -> Result<Option<Airport>> {
+        let conn = self.db.get_conn()?;
+        self.visible_airport(&conn, icao)
+    }
+
+    /// Get an airport by exact IATA code
+    ///
+    /// Unlike `get_by_icao`, ICAO codes and aliases don't match.
+    pub fn get_by_iata(&self, iata: &str) -> Result<Option<Airport>> {
+        let conn = self.db.get_conn()?;
+        let airport = conn
+            .query_row(
+                &format!("SELECT {} FROM airports WHERE iata = ?1 ORDER BY icao", AIRPORT_COLUMNS),
+                [normalize_code(iata)],
+                airport_from_row,
+            )
+            .optional()?;
+        let redaction = Redaction::load(&conn, &self.profile_id)?;
+        Ok(airport.filter(|airport| !redaction.hides_airport(&airport.id)))
+    }
+
+    /// Add an airport
+    ///
+    /// ICAO and IATA codes are trimmed and upper-cased, and an empty id is
+    /// filled in by the configured `IdStrategy`. Fails with `InvalidInput`
+    /// if the id or ICAO code is already taken.
+    pub fn insert(&self, airport: &Airport) -> Result<Airport> {
+        Self::insert_in(&*self.db.get_conn()?, self.db.id_strategy(), airport)
+    }
+
+    fn insert_in(conn: &Connection, ids: IdStrategy, airport: &Airport) -> Result<Airport> {
+        let airport = validated(airport, ids)?;
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM airports WHERE id = ?1 OR icao = ?2)",
+            [&airport.id, &airport.icao],
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Airport {} ({}) already exists",
+                airport.icao, airport.id
+            )));
+        }
+        conn.execute(
+            &format!(
+                "INSERT INTO airports ({}, search_name, geohash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                AIRPORT_COLUMNS
+            ),
+            rusqlite::params![
+                airport.id,
+                airport.icao,
+                airport.iata,
+                airport.name,
+                airport.coordinate.latitude,
+                airport.coordinate.longitude,
+                airport.elevation,
+                airport.country,
+                airport.region,
+                airport.created_at,
+                airport.transition_altitude,
+                airport.transition_level,
+                airport.magnetic_variation,
+                search_key(&airport.name),
+                geohash::encode(airport.coordinate, GEOHASH_PRECISION),
+            ],
+        )?;
+        Ok(airport)
+    }
+
+    /// Replace the stored fields of the airport with the same id
+    ///
+    /// `created_at` is kept from the stored row. Fails with `NotFound` for
+    /// an unknown id and `InvalidInput` if the new ICAO code belongs to
+    /// another airport.
+    pub fn update(&self, airport: &Airport) -> Result<Airport> {
+        Self::update_in(
+            &*self.db.get_conn()?,
+            self.db.id_strategy(),
+            airport,
+            self.db.clock().timestamp(),
+        )
+    }
+
+    fn update_in(
+        conn: &Connection,
+        ids: IdStrategy,
+        airport: &Airport,
+        now: i64,
+    ) -> Result<Airport> {
+        let airport = validated(airport, ids)?;
+        let clash: Option<String> = conn
+            .query_row(
+                "SELECT id FROM airports WHERE icao = ?1 AND id != ?2",
+                [&airport.icao, &airport.id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(other) = clash {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "ICAO code {} is already used by airport {}",
+                airport.icao, other
+            )));
+        }
+        let updated = conn.execute(
+            "UPDATE airports SET icao = ?2, iata = ?3, name = ?4, latitude = ?5, longitude = ?6,
+                elevation = ?7, country = ?8, region = ?9, transition_altitude = ?10,
+                transition_level = ?11, magnetic_variation = ?12, search_name = ?13,
+                geohash = ?14, updated_at = ?15
+             WHERE id = ?1",
+            rusqlite::params![
+                airport.id,
+                airport.icao,
+                airport.iata,
+                airport.name,
+                airport.coordinate.latitude,
+                airport.coordinate.longitude,
+                airport.elevation,
+                airport.country,
+                airport.region,
+                airport.transition_altitude,
+                airport.transition_level,
+                airport.magnetic_variation,
+                search_key(&airport.name),
+                geohash::encode(airport.coordinate, GEOHASH_PRECISION),
+                now,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(AeroBaseError::NotFound(format!("Airport {} not found", airport.id)));
+        }
+        let stored = conn.query_row(
+            &format!("SELECT {} FROM airports WHERE id = ?1", AIRPORT_COLUMNS),
+            [&airport.id],
+            airport_from_row,
+        )?;
+        Ok(stored)
+    }
+
+    /// Insert an airport, or update the stored one with the same ICAO code
+    ///
+    /// Importers go through here so a re-import refreshes rows instead of
+    /// failing on the taken code. An update keeps the stored id and
+    /// `created_at`.
+    pub fn upsert(&self, airport: &Airport) -> Result<Airport> {
+        Self::upsert_in(
+            &*self.db.get_conn()?,
+            self.db.id_strategy(),
+            airport,
+            self.db.clock().timestamp(),
+        )
+    }
+
+    /// `upsert` on `conn`, for importers writing inside their own transaction
+    pub(crate) fn upsert_in(
+        conn: &Connection,
+        ids: IdStrategy,
+        airport: &Airport,
+        now: i64,
+    ) -> Result<Airport> {
+        let mut airport = validated(airport, ids)?;
+        let existing: Option<String> = conn
+            .query_row("SELECT id FROM airports WHERE icao = ?1", [&airport.icao], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        match existing {
+            Some(id) => {
+                airport.id = id;
+                Self::update_in(conn, ids, &airport, now)
+            }
+            None => Self::insert_in(conn, ids, &airport),
+        }
+    }
+
+    /// Delete an airport by id, along with its runways, frequencies,
+    /// aliases and other dependent rows; returns whether it existed
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let removed = self.db.get_conn()?.execute("DELETE FROM airports WHERE id = ?1", [id])?;
+        Ok(removed > 0)
+    }
+
+    /// Delete every airport in `scope`, with dependent rows as in `delete`;
+    /// returns the number of airports removed
+    pub fn delete_where(&self, scope: &DeleteScope) -> Result<usize> {
+        let conn = self.db.get_conn()?;
+        let (condition, params) = scope.condition(&conn, "airports", &["region", "country"])?;
+        let removed = conn.execute(
+            &format!("DELETE FROM airports WHERE {}", condition),
+            rusqlite::params_from_iter(params),
+        )?;
+        log::info!("Deleted {} airports ({:?})", removed, scope);
+        Ok(removed)
+    }
+
+    /// Look up an airport like `get_by_icao`, with "did you mean"
+    /// suggestions when the code doesn't match
+    ///
+    /// Hidden airports read as missing and are never suggested.
+    pub fn lookup(&self, code: &str) -> Result<LookupResult> {
+        let conn = self.db.get_conn()?;
+        if let Some(airport) = self.visible_airport(&conn, code)? {
+            return Ok(LookupResult::Found(airport));
+        }
+        let code = normalize_code(code);
+        let redaction = Redaction::load(&conn, &self.profile_id)?;
+        let suggestions = near_miss_airports(&conn, &code)?
+            .into_iter()
+            .filter(|airport| !redaction.hides_airport(&airport.id))
+            .take(MAX_AIRPORT_SUGGESTIONS)
+            .collect();
+        Ok(LookupResult::Missing { code, suggestions })
+    }
+
+    /// Other codes an airport is known by, ordered by code
+    pub fn aliases(&self, airport_id: &str) -> Result<Vec<AirportAlias>> {
+        let conn = self.db.get_conn()?;
+        if Redaction::load(&conn, &self.profile_id)?.hides_airport(airport_id) {
+            return Ok(Vec::new());
+        }
+        Self::collect(&conn, ALIAS_COLUMNS, "airport_aliases", "code", airport_id, alias_from_row)
+    }
+
+    /// Runway ends at an airport
+    pub fn runways(&self, airport_id: &str) -> Result<Vec<Runway>> {
+        let conn = self.db.get_conn()?;
+        if Redaction::load(&conn, &self.profile_id)?.hides_airport(airport_id) {
+            return Ok(Vec::new());
+        }
+        Self::collect(&conn, RUNWAY_COLUMNS, "runways", "ident", airport_id, runway_from_row)
+    }
+
+    /// Runway ends ranked for the given wind, best headwind first
+    ///
+    /// Ties on headwind go to the smaller crosswind. Runway ends known only
+    /// by designator are turned to true with the airport's magnetic
+    /// variation, and left out when it is unknown, as are ends without a
+    /// usable designator.
+    pub fn best_runway(&self, icao: &str, wind: Wind) -> Result<Vec<RunwayWind>> {
+        let airport = self
+            .get_by_icao(icao)?
+            .ok_or_else(|| AeroBaseError::NotFound(format!("Airport {} not found", icao)))?;
+
+        let mut ranked: Vec<RunwayWind> = self
+            .runways(&airport.id)?
+            .into_iter()
+            .filter_map(|runway| {
+                let heading = runway.effective_heading(airport.magnetic_variation)?;
+                let (headwind, crosswind) =
+                    calculator::calculate_wind_components(wind.direction, wind.speed, heading);
+                Some(RunwayWind {
+                    runway,
+                    headwind,
+                    crosswind: crosswind.abs(),
+                    crosswind_from_right: crosswind > 0.0,
+                    tailwind: (-headwind).max(0.0),
+                })
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| {
+            b.headwind
+                .total_cmp(&a.headwind)
+                .then(a.crosswind.total_cmp(&b.crosswind))
+        });
+        Ok(ranked)
+    }
+
+    /// Approach frequencies and final approach course for a runway end
+    pub fn approach_tuning(&self, icao: &str, runway_ident: &str) -> Result<ApproachTuning> {
+        let conn = self.db.get_conn()?;
+        let tx = conn.unchecked_transaction()?;
+
+        let airport = self
+            .visible_airport(&tx, icao)?
+            .ok_or_else(|| AeroBaseError::NotFound(format!("Airport {} not found", icao)))?;
+        let runway = tx
+            .query_row(
+                &format!(
+                    "SELECT {} FROM runways WHERE airport_id = ?1 AND ident = ?2 COLLATE NOCASE",
+                    RUNWAY_COLUMNS
+                ),
+                [&airport.id, runway_ident],
+                runway_from_row,
+            )
+            .optional()?
+            .ok_or_else(|| {
+                AeroBaseError::NotFound(format!("Runway {} not found at {}", runway_ident, icao))
+            })?;
+
+        let mut stmt = tx.prepare_cached(
+            "SELECT name FROM procedures
+             WHERE airport_id = ?1 AND type = ?2 AND runway = ?3 COLLATE NOCASE
+             ORDER BY name",
+        )?;
+        let procedures = stmt
+            .query_map(
+                [&airport.id, ProcedureType::Approach.as_str(), &runway.ident],
+                |row| row.get(0),
+            )?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+        drop(stmt);
+
+        let navaids = Self::nearby_navaids(&tx, runway.threshold.unwrap_or(airport.coordinate))?
+            .into_iter()
+            .filter_map(|n| {
+                let frequency = n.navaid.frequency?;
+                Some(TuningFrequency::new(&n.navaid.name, n.navaid.navaid_type.as_str(), frequency))
+            })
+            .collect();
+
+        tx.commit()?;
+
+        let variation = airport.magnetic_variation;
+        Ok(ApproachTuning {
+            icao: airport.icao,
+            final_approach_course: runway
+                .ils
+                .as_ref()
+                .and_then(|ils| ils.course)
+                .or_else(|| runway.effective_heading(variation)),
+            localizer: runway
+                .ils
+                .as_ref()
+                .map(|ils| TuningFrequency::new(&ils.ident, "ILS", ils.frequency)),
+            runway: runway.ident,
+            navaids,
+            procedures,
+        })
+    }
+
+    /// Charts indexed for an airport, ordered by type and identifier
+    ///
+    /// Each chart identifier resolves to its latest edition effective in the
+    /// installed AIRAC cycle, so editions loaded ahead for the next cycle
+    /// stay hidden until it is installed. With no cycle installed the latest
+    /// edition is returned.
+    pub fn charts(&self, icao: &str) -> Result<Vec<Chart>> {
+        self.current_charts(icao, None)
+    }
+
+    /// Charts depicting a procedure, matched by name (e.g. "ILS18L") across
+    /// all of its transitions, so a viewer can open the chart for the
+    /// procedure selected in the planner
+    pub fn charts_for_procedure(&self, icao: &str, procedure: &str) -> Result<Vec<Chart>> {
+        self.current_charts(icao, Some(procedure.trim()))
+    }
+
+    fn current_charts(&self, icao: &str, procedure: Option<&str>) -> Result<Vec<Chart>> {
+        let conn = self.db.get_conn()?;
+        let Some(airport) = self.visible_airport(&conn, icao)? else {
+            return Ok(Vec::new());
+        };
+        let cycle = cycle::current_ident(&conn)?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM charts AS c
+             WHERE airport_id = ?1
+               AND (?2 IS NULL OR procedure_id IN (
+                   SELECT id FROM procedures WHERE airport_id = ?1 AND name = ?2 COLLATE NOCASE))
+               AND effective_cycle = (
+                   SELECT MAX(effective_cycle) FROM charts
+                   WHERE airport_id = c.airport_id AND identifier = c.identifier
+                     AND (?3 IS NULL OR effective_cycle <= ?3))
+             ORDER BY type, identifier",
+            CHART_COLUMNS
+        ))?;
+        let charts = stmt
+            .query_map(rusqlite::params![airport.id, procedure, cycle], chart_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(charts)
+    }
+
+    /// Search airports by code or name, best match first
+    ///
+    /// Text relevance ranks an exact ICAO, IATA or alias code (1.0) above a
+    /// code prefix (0.8), a name prefix (0.6) and a name containing the text
+    /// (0.4); names match across scripts as in `AirportQuery::name_contains`.
+    /// With a reference point, `SEARCH_DISTANCE_WEIGHT` of the score comes
+    /// from proximity instead, halving every `SEARCH_DISTANCE_SCALE_NM`, so
+    /// "SAN" typed near California puts KSAN above Sanya. Only the best
+    /// `SEARCH_CANDIDATES` text matches are ranked by distance.
+    pub fn search(
+        &self,
+        text: &str,
+        near: Option<Coordinate>,
+        limit: usize,
+    ) -> Result<Vec<AirportMatch>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        let code = normalize_code(text);
+        let key = search_key(text);
+        // An empty key (punctuation only) must not match every name
+        let key_param = |like: fn(&str) -> Value| {
+            if key.is_empty() {
+                Value::Null
+            } else {
+                like(&key)
+            }
+        };
+
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT * FROM (
+                SELECT {}, CASE
+                    WHEN icao = ?1 OR iata = ?1
+                        OR id IN (SELECT airport_id FROM airport_aliases WHERE code = ?1) THEN 1.0
+                    WHEN icao LIKE ?2 ESCAPE '\\' OR iata LIKE ?2 ESCAPE '\\' THEN 0.8
+                    WHEN name LIKE ?3 ESCAPE '\\' OR search_name LIKE ?4 ESCAPE '\\' THEN 0.6
+                    WHEN name LIKE ?5 ESCAPE '\\' OR search_name LIKE ?6 ESCAPE '\\' THEN 0.4
+                    ELSE 0.0
+                END AS relevance
+                FROM airports
+            ) WHERE relevance > 0 ORDER BY relevance DESC, icao LIMIT ?7",
+            AIRPORT_COLUMNS
+        ))?;
+        let params = [
+            Value::Text(code.clone()),
+            like_prefix(&code),
+            like_prefix(text),
+            key_param(like_prefix),
+            like_contains(text),
+            key_param(like_contains),
+            Value::Integer(SEARCH_CANDIDATES),
+        ];
+        let redaction = Redaction::load(&conn, &self.profile_id)?;
+        let mut matches = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                Ok((airport_from_row(row)?, row.get::<_, f64>("relevance")?))
+            })?
+            .filter(|row| !matches!(row, Ok((airport, _)) if redaction.hides_airport(&airport.id)))
+            .map(|row| {
+                let (airport, relevance) = row?;
+                let distance_nm = near.map(|point| airport.distance_from(point));
+                let score = match distance_nm {
+                    Some(distance) => {
+                        let proximity = 1.0 / (1.0 + distance / SEARCH_DISTANCE_SCALE_NM);
+                        relevance * (1.0 - SEARCH_DISTANCE_WEIGHT)
+                            + proximity * SEARCH_DISTANCE_WEIGHT
+                    }
+                    None => relevance,
+                };
+                Ok(AirportMatch {
+                    airport,
+                    score,
+                    distance_nm,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Stable, so equal scores keep the ICAO order from SQL
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    /// Airports whose name contains `text`, ordered by name
+    ///
+    /// Names match across scripts as in `AirportQuery::name_contains`. Use
+    /// `search` for ranked matching on codes as well as names.
+    pub fn search_by_name(&self, text: &str, limit: usize) -> Result<Vec<Airport>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        let key = search_key(text);
+        let key_param = if key.is_empty() { Value::Null } else { like_contains(&key) };
+
+        let conn = self.db.get_conn()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM airports
+             WHERE name LIKE ?1 ESCAPE '\\' OR search_name LIKE ?2 ESCAPE '\\'
+             ORDER BY name, icao",
+            AIRPORT_COLUMNS
+        ))?;
+        let redaction = Redaction::load(&conn, &self.profile_id)?;
+        let airports = stmt
+            .query_map(rusqlite::params![like_contains(text), key_param], airport_from_row)?
+            .filter(|row| !matches!(row, Ok(airport) if redaction.hides_airport(&airport.id)))
+            .take(limit)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(airports)
+    }
+
+    /// Airport detail aggregate in a single read transaction
+    ///
+    /// All child queries run on one connection against a consistent
+    /// snapshot, so a sync landing mid-read cannot mix cycles.
+    pub fn details(&self, icao: &str) -> Result<Option<AirportDetails>> {
+        let conn = self.db.get_conn()?;
+        let tx = conn.unchecked_transaction()?;
+
+        let Some(airport) = self.visible_airport(&tx, icao)? else {
+            return Ok(None);
+        };
+
+        let runways = Self::collect(&tx, RUNWAY_COLUMNS, "runways", "ident", &airport.id, runway_from_row)?;
+        let frequencies = Self::collect(
+            &tx,
+            FREQUENCY_COLUMNS,
+            "airport_frequencies",
+            "type, frequency",
+            &airport.id,
+            frequency_from_row,
+        )?;
+        let procedures = Self::collect(
+            &tx,
+            PROCEDURE_COLUMNS,
+            "procedures",
+            "type, name, transition",
+            &airport.id,
+            procedure_from_row,
+        )?;
+        let nearby_navaids = Self::nearby_navaids(&tx, airport.coordinate)?;
+        let atis = atis::latest(&tx, &airport.icao)?;
+
+        tx.commit()?;
+
+        Ok(Some(AirportDetails {
+            airport,
+            runways,
+            frequencies,
+            nearby_navaids,
+            procedures,
+            atis,
+        }))
+    }
+
+    fn visible_airport(&self, conn: &Connection, icao: &str) -> Result<Option<Airport>> {
+        let airport = Self::airport_by_icao(conn, icao)?;
+        match airport {
+            Some(airport) if Redaction::load(conn, &self.profile_id)?.hides_airport(&airport.id) => {
+                Ok(None)
+            }
+            airport => Ok(airport),
+        }
+    }
+
+    fn airport_by_icao(conn: &Connection, icao: &str) -> Result<Option<Airport>> {
+        let airport = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM airports WHERE id = {}",
+                    AIRPORT_COLUMNS, AIRPORT_ID_BY_CODE
+                ),
+                [normalize_code(icao)],
+                airport_from_row,
+            )
+            .optional()?;
+        Ok(airport)
+    }
+
+    fn collect<T>(
+        conn: &Connection,
+        columns: &str,
+        table: &str,
+        order_by: &str,
+        airport_id: &str,
+        map: fn(&Row) -> rusqlite::Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM {} WHERE airport_id = ?1 ORDER BY {}",
+            columns, table, order_by
+        ))?;
+        let rows = stmt
+            .query_map([airport_id], map)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn nearby_navaids(conn: &Connection, center: Coordinate) -> Result<Vec<NearbyNavaid>> {
+        let (min, max) = geometry::bounding_box(center, NEARBY_NAVAID_RADIUS_NM);
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM navaids
+             WHERE latitude BETWEEN ?1 AND ?2 AND longitude BETWEEN ?3 AND ?4",
+            NAVAID_COLUMNS
+        ))?;
+        let mut navaids: Vec<NearbyNavaid> = stmt
+            .query_map(
+                rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude],
+                navaid_from_row,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .into_iter()
+            .map(|navaid| NearbyNavaid {
+                distance_nm: navaid.distance_from(center),
+                navaid,
+            })
+            .filter(|n| n.distance_nm <= NEARBY_NAVAID_RADIUS_NM)
+            .collect();
+
+        navaids.sort_by(|a, b| a.distance_nm.total_cmp(&b.distance_nm));
+        Ok(navaids)
+    }
+}
+
+/// Recompute `search_name` for airports written without it (by migrations,
+/// deltas or raw SQL); returns the number of rows changed
+pub(crate) fn refresh_search_names(conn: &Connection) -> Result<usize> {
+    let rows = conn
+        .prepare("SELECT id, name, search_name FROM airports")?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get(2)?))
+        })?
+        .collect::<std::result::Result<Vec<(String, String, Option<String>)>, _>>()?;
+    let mut stmt = conn.prepare("UPDATE airports SET search_name = ?2 WHERE id = ?1")?;
+    let mut changed = 0;
+    for (id, name, stored) in rows {
+        let key = search_key(&name);
+        if stored.as_deref() != Some(key.as_str()) {
+            changed += stmt.execute([&id, &key])?;
+        }
+    }
+    Ok(changed)
+}
+
+/// A copy of `airport` with normalized codes, or `InvalidInput`
+fn validated(airport: &Airport, ids: IdStrategy) -> Result<Airport> {
+    let mut airport = airport.clone();
+    airport.id = airport.id.trim().to_string();
+    airport.icao = normalize_code(&airport.icao);
+    airport.iata = airport.iata.as_deref().map(normalize_code).filter(|iata| !iata.is_empty());
+    airport.name = airport.name.trim().to_string();
+
+    if airport.icao.is_empty() || airport.name.is_empty() {
+        return Err(AeroBaseError::InvalidInput(
+            "Airport ICAO code and name are required".to_string(),
+        ));
+    }
+    if airport.id.is_empty() {
+        airport.id = ids.navdata_id(&[&airport.icao], airport.created_at * 1000);
+    }
+    check_position("Airport", &airport.icao, airport.coordinate)?;
+    Ok(airport)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_airport_details() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports
+                    (id, icao, name, latitude, longitude, transition_altitude, transition_level, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Beijing', 40.08, 116.58, 9800, 11800, 0);
+                 INSERT INTO runways (id, airport_id, ident, heading, length_ft, created_at) VALUES
+                    ('R2', 'AP1', '36R', 360.0, 12467, 0),
+                    ('R1', 'AP1', '18L', 180.0, 12467, 0);
+                 INSERT INTO airport_frequencies (id, airport_id, type, frequency, created_at)
+                    VALUES ('F1', 'AP1', 'TWR', 118.5, 0);
+                 INSERT INTO procedures (id, airport_id, name, type, runway, created_at)
+                    VALUES ('P1', 'AP1', 'ILS18L', 'APPROACH', '18L', 0);
+                 INSERT INTO navaids (id, name, type, latitude, longitude, created_at) VALUES
+                    ('N1', 'PEK', 'VORDME', 40.20, 116.58, 0),
+                    ('N2', 'NEAR', 'NDB', 40.10, 116.58, 0),
+                    ('N3', 'FAR', 'VOR', 41.50, 116.58, 0);
+                 INSERT INTO atis (id, station, kind, letter, text, issued_at, received_at)
+                    VALUES ('ZBAA arr', 'ZBAA', 'arr', 'K', 'ARR INFO K', 10, 20);",
+            )
+            .unwrap();
+
+        let repo = AirportRepository::new(db).unwrap();
+        let details = repo.details("ZBAA").unwrap().unwrap();
+
+        assert_eq!(details.airport.id, "AP1");
+        assert_eq!(details.airport.transition_altitude, Some(9800));
+        assert_eq!(details.airport.transition_level, Some(11800));
+        let idents: Vec<&str> = details.runways.iter().map(|r| r.ident.as_str()).collect();
+        assert_eq!(idents, vec!["18L", "36R"]);
+        assert_eq!(details.frequencies[0].frequency, 118.5);
+        assert_eq!(details.procedures[0].procedure_type, ProcedureType::Approach);
+
+        let navaids: Vec<&str> = details
+            .nearby_navaids
+            .iter()
+            .map(|n| n.navaid.id.as_str())
+            .collect();
+        assert_eq!(navaids, vec!["N2", "N1"]);
+        assert_eq!(details.atis[0].letter, Some('K'));
+
+        assert!(repo.details("XXXX").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lookup_by_alias() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, iata, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'VHHH', 'HKG', 'Hong Kong', 22.31, 113.91, 0);
+                 INSERT INTO airport_aliases (id, airport_id, code, kind, created_at) VALUES
+                    ('AL2', 'AP1', 'VHHX', 'FORMER', 0),
+                    ('AL1', 'AP1', 'CLK', 'LOCAL', 0);",
+            )
+            .unwrap();
+
+        let repo = AirportRepository::new(db).unwrap();
+        for code in ["VHHH", "vhhh", " hkg ", "VHHX", "clk"] {
+            assert_eq!(repo.get_by_icao(code).unwrap().unwrap().id, "AP1", "{}", code);
+        }
+        assert!(repo.details("vhhx").unwrap().is_some());
+
+        let codes: Vec<String> = repo.aliases("AP1").unwrap().into_iter().map(|a| a.code).collect();
+        assert_eq!(codes, vec!["CLK", "VHHX"]);
+    }
+
+    #[test]
+    fn test_lookup_suggestions() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, iata, name, latitude, longitude, created_at) VALUES
+                    ('AP1', 'ZSPD', 'PVG', 'Pudong', 31.14, 121.81, 0),
+                    ('AP2', 'ZSSS', 'SHA', 'Hongqiao', 31.20, 121.34, 0),
+                    ('AP3', 'ZSPX', NULL, 'Hidden', 31.0, 121.0, 0);
+                 INSERT INTO restricted_items (item_type, item_id, category)
+                    VALUES ('AIRPORT', 'AP3', 'MILITARY');",
+            )
+            .unwrap();
+        let repo = AirportRepository::new(db).unwrap();
+
+        assert_eq!(repo.lookup("pvg").unwrap().into_airport().unwrap().id, "AP1");
+
+        // ZSPD is one edit away; ZSSS is two and the hidden ZSPX never shows
+        let missing = repo.lookup("zspw").unwrap();
+        assert!(missing.airport().is_none());
+        let codes: Vec<_> = missing.suggestions().iter().map(|a| a.icao.as_str()).collect();
+        assert_eq!(codes, vec!["ZSPD"]);
+        assert_eq!(missing.did_you_mean().unwrap(), "did you mean ZSPD (Pudong)?");
+
+        // IATA codes count too
+        let missing = repo.lookup("SHX").unwrap();
+        assert_eq!(missing.suggestions()[0].icao, "ZSSS");
+
+        let missing = repo.lookup("ZSPS").unwrap();
+        assert_eq!(
+            missing.did_you_mean().unwrap(),
+            "did you mean ZSPD (Pudong) or ZSSS (Hongqiao)?"
+        );
+        assert!(repo.lookup("KJFK").unwrap().did_you_mean().is_none());
+    }
+
+    #[test]
+    fn test_search_ranking() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, iata, name, latitude, longitude, created_at) VALUES
+                    ('AP1', 'KSAN', 'SAN', 'San Diego', 32.7336, -117.1897, 0),
+                    ('AP2', 'KSJC', 'SJC', 'San Jose', 37.3626, -121.9291, 0),
+                    ('AP3', 'ZJSY', 'SYX', 'Sanya Phoenix', 18.3029, 109.4122, 0),
+                    ('AP4', 'ZBAA', 'PEK', '北京首都', 40.0801, 116.5846, 0);",
+            )
+            .unwrap();
+        refresh_search_names(&db.get_conn().unwrap()).unwrap();
+        let repo = AirportRepository::new(db).unwrap();
+        let icaos = |text: &str, near: Option<Coordinate>| -> Vec<String> {
+            let found = repo.search(text, near, 10).unwrap();
+            found.into_iter().map(|m| m.airport.icao).collect()
+        };
+
+        // Text relevance alone: exact code, then name prefixes by ICAO
+        assert_eq!(icaos("san", None), vec!["KSAN", "KSJC", "ZJSY"]);
+        // Near Hainan, Sanya's name match beats a code match in California
+        let sanya = Coordinate::new(18.25, 109.5);
+        assert_eq!(icaos("SAN", Some(sanya))[0], "ZJSY");
+        let los_angeles = Coordinate::new(34.05, -118.24);
+        assert_eq!(icaos("SAN", Some(los_angeles)), vec!["KSAN", "KSJC", "ZJSY"]);
+
+        assert_eq!(icaos("beijing", None), vec!["ZBAA"]);
+        assert_eq!(icaos("pek", None), vec!["ZBAA"]);
+        assert!(icaos(" ", None).is_empty());
+        assert!(icaos("%", None).is_empty());
+
+        let found = repo.search("sanya", Some(sanya), 1).unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(found[0].distance_nm.unwrap() < 10.0);
+        assert!(found[0].score > 0.75);
+    }
+
+    #[test]
+    fn test_search_name_written() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = AirportRepository::new(Arc::clone(&db)).unwrap();
+        let search_name = |id: &str| -> Option<String> {
+            db.get_conn()
+                .unwrap()
+                .query_row("SELECT search_name FROM airports WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+
+        let mut airport = Airport::new(
+            "AP1".to_string(),
+            "ZBAD".to_string(),
+            "北京大兴".to_string(),
+            Coordinate::new(39.5098, 116.4105),
+        );
+        repo.insert(&airport).unwrap();
+        assert_eq!(search_name("AP1").as_deref(), Some("beijingdaxing"));
+        airport.name = "天津滨海".to_string();
+        repo.update(&airport).unwrap();
+        assert_eq!(search_name("AP1").as_deref(), Some("tianjinbinhai"));
+        assert_eq!(repo.search_by_name("tianjin", 10).unwrap().len(), 1);
+
+        // Raw writes leave it empty until refreshed
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                 VALUES ('AP2', 'ZSPD', '上海浦东', 31.14, 121.81, 0)",
+                [],
+            )
+            .unwrap();
+        assert_eq!(search_name("AP2"), None);
+        assert_eq!(refresh_search_names(&db.get_conn().unwrap()).unwrap(), 1);
+        assert_eq!(search_name("AP2").as_deref(), Some("shanghaipudong"));
+        assert_eq!(refresh_search_names(&db.get_conn().unwrap()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_redacted_airport() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'ZBNY', 'Nanyuan', 39.78, 116.39, 0);
+                 INSERT INTO runways (id, airport_id, ident, heading, created_at)
+                    VALUES ('R1', 'AP1', '18', 180.0, 0);
+                 INSERT INTO restricted_items (item_type, item_id, category)
+                    VALUES ('AIRPORT', 'AP1', 'MILITARY');
+                 INSERT INTO profile_entitlements (profile_id, category) VALUES ('ops', 'MILITARY');",
+            )
+            .unwrap();
+        let public = AirportRepository::new(db).unwrap();
+        let ops = public.for_profile("ops");
+
+        assert!(public.get_by_icao("ZBNY").unwrap().is_none());
+        assert!(public.details("ZBNY").unwrap().is_none());
+        assert!(public.runways("AP1").unwrap().is_empty());
+        assert!(public.search("nanyuan", None, 10).unwrap().is_empty());
+        assert!(matches!(
+            public.best_runway("ZBNY", Wind::new(180.0, 10.0)),
+            Err(AeroBaseError::NotFound(_))
+        ));
+
+        assert_eq!(ops.get_by_icao("ZBNY").unwrap().unwrap().id, "AP1");
+        assert_eq!(ops.details("ZBNY").unwrap().unwrap().runways.len(), 1);
+        assert_eq!(ops.search("nanyuan", None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_airport_crud() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = AirportRepository::new(Arc::clone(&db)).unwrap();
+
+        let mut capital = Airport::new(
+            "AP1".to_string(),
+            " zbaa".to_string(),
+            "Beijing Capital".to_string(),
+            Coordinate::new(40.0801, 116.5846),
+        );
+        capital.iata = Some("pek".to_string());
+        let stored = repo.insert(&capital).unwrap();
+        assert_eq!(stored.icao, "ZBAA");
+        assert_eq!(repo.get_by_iata("PEK").unwrap().unwrap().id, "AP1");
+        assert_eq!(repo.get_by_icao("ZBAA").unwrap().unwrap().name, "Beijing Capital");
+        assert!(matches!(repo.insert(&capital), Err(AeroBaseError::InvalidInput(_))));
+
+        let daxing = Airport::new(
+            "AP2".to_string(),
+            "ZBAD".to_string(),
+            "北京大兴".to_string(),
+            Coordinate::new(39.5098, 116.4105),
+        );
+        repo.insert(&daxing).unwrap();
+        let names = |text: &str| -> Vec<String> {
+            repo.search_by_name(text, 10)
+                .unwrap()
+                .into_iter()
+                .map(|airport| airport.icao)
+                .collect()
+        };
+        assert_eq!(names("beijing"), vec!["ZBAA", "ZBAD"]);
+        assert_eq!(names("Capital"), vec!["ZBAA"]);
+        assert!(names("  ").is_empty());
+
+        let mut renamed = stored.clone();
+        renamed.name = "Beijing Capital International".to_string();
+        renamed.elevation = Some(116);
+        renamed.created_at = 0;
+        let updated = repo.update(&renamed).unwrap();
+        assert_eq!(updated.elevation, Some(116));
+        assert_eq!(updated.created_at, stored.created_at);
+
+        renamed.icao = "ZBAD".to_string();
+        assert!(matches!(repo.update(&renamed), Err(AeroBaseError::InvalidInput(_))));
+        renamed.id = "AP9".to_string();
+        renamed.icao = "ZBXX".to_string();
+        assert!(matches!(repo.update(&renamed), Err(AeroBaseError::NotFound(_))));
+        renamed.coordinate = Coordinate::new(91.0, 0.0);
+        assert!(matches!(repo.insert(&renamed), Err(AeroBaseError::InvalidInput(_))));
+
+        // Upsert matches on the ICAO code and keeps the stored id
+        let mut reimported = daxing.clone();
+        reimported.id = String::new();
+        reimported.elevation = Some(98);
+        let upserted = repo.upsert(&reimported).unwrap();
+        assert_eq!((upserted.id.as_str(), upserted.elevation), ("AP2", Some(98)));
+        reimported.icao = "ZBTJ".to_string();
+        assert_ne!(repo.upsert(&reimported).unwrap().id, "AP2");
+
+        db.get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO runways (id, airport_id, ident, created_at)
+                    VALUES ('R1', 'AP1', '01', 0)",
+                [],
+            )
+            .unwrap();
+        assert!(repo.delete("AP1").unwrap());
+        assert!(!repo.delete("AP1").unwrap());
+        assert!(repo.get_by_iata("PEK").unwrap().is_none());
+        assert!(repo.runways("AP1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_where() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports
+                    (id, icao, name, latitude, longitude, country, region, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Capital', 40.08, 116.58, 'China', 'ZB', 100),
+                           ('AP2', 'ZSPD', 'Pudong', 31.14, 121.81, 'China', 'ZS', 250),
+                           ('AP3', 'RKSI', 'Incheon', 37.46, 126.44, 'Korea', 'RK', 150),
+                           ('AP4', 'RJTT', 'Haneda', 35.55, 139.78, 'Japan', 'RJ', 400);
+                 INSERT INTO runways (id, airport_id, ident, created_at)
+                    VALUES ('R1', 'AP1', '01', 0);
+                 INSERT INTO navdata_cycles (cycle, effective_date, expires_date, installed_at)
+                    VALUES ('2401', '2024-01-25', '2024-02-22', 100),
+                           ('2402', '2024-02-22', '2024-03-21', 200);",
+            )
+            .unwrap();
+        let repo = AirportRepository::new(Arc::clone(&db)).unwrap();
+
+        assert_eq!(repo.delete_where(&DeleteScope::Region("ZB".to_string())).unwrap(), 1);
+        assert!(repo.runways("AP1").unwrap().is_empty());
+        // Loaded while 2401 was current: created in [100, 200)
+        assert_eq!(repo.delete_where(&DeleteScope::Cycle("2401".to_string())).unwrap(), 1);
+        assert!(repo.get_by_icao("RKSI").unwrap().is_none());
+        // The latest cycle runs open-ended
+        assert_eq!(repo.delete_where(&DeleteScope::Cycle("2402".to_string())).unwrap(), 2);
+        assert_eq!(repo.delete_where(&DeleteScope::Country("China".to_string())).unwrap(), 0);
+        assert!(matches!(
+            repo.delete_where(&DeleteScope::Cycle("2313".to_string())),
+            Err(AeroBaseError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_best_runway() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports
+                    (id, icao, name, latitude, longitude, magnetic_variation, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Beijing', 40.08, 116.58, 0.0, 0);
+                 INSERT INTO runways (id, airport_id, ident, heading, created_at) VALUES
+                    ('R1', 'AP1', '18L', NULL, 0),
+                    ('R2', 'AP1', '36R', 360.0, 0),
+                    ('R3', 'AP1', '01', 10.0, 0),
+                    ('R4', 'AP1', 'H1', NULL, 0);",
+            )
+            .unwrap();
+
+        let repo = AirportRepository::new(db).unwrap();
+        let ranked = repo.best_runway("ZBAA", Wind::new(20.0, 20.0)).unwrap();
+
+        let idents: Vec<&str> = ranked.iter().map(|r| r.runway.ident.as_str()).collect();
+        assert_eq!(idents, vec!["01", "36R", "18L"]);
+        assert!(ranked[0].crosswind_from_right);
+        assert!((ranked[0].crosswind - 3.47).abs() < 0.01);
+        assert!((ranked[2].tailwind - 18.79).abs() < 0.01);
+        assert_eq!(ranked[0].tailwind, 0.0);
+
+        assert!(repo.best_runway("XXXX", Wind::new(0.0, 10.0)).is_err());
+    }
+
+    #[test]
+    fn test_best_runway_uses_magnetic_variation() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Beijing', 40.08, 116.58, 0);
+                 INSERT INTO runways (id, airport_id, ident, heading, created_at) VALUES
+                    ('R1', 'AP1', '09', NULL, 0),
+                    ('R2', 'AP1', '08R', 78.0, 0);",
+            )
+            .unwrap();
+        let repo = AirportRepository::new(Arc::clone(&db)).unwrap();
+        let wind = Wind::new(88.0, 20.0);
+        let idents = |variation: Option<f64>| -> Vec<String> {
+            let mut airport = repo.get_by_icao("ZBAA").unwrap().unwrap();
+            airport.magnetic_variation = variation;
+            repo.update(&airport).unwrap();
+            repo.best_runway("ZBAA", wind)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.runway.ident)
+                .collect()
+        };
+
+        // 09 is 088 true with 2° west variation, straight into the wind
+        assert_eq!(idents(Some(-2.0)), vec!["09", "08R"]);
+        // With 10° east it is 100 true, further off than 08R
+        assert_eq!(idents(Some(10.0)), vec!["08R", "09"]);
+        // Unknown variation: 09 has no true heading
+        assert_eq!(idents(None), vec!["08R"]);
+    }
+
+    #[test]
+    fn test_approach_tuning() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Beijing', 40.08, 116.58, 0);
+                 INSERT INTO runways
+                    (id, airport_id, ident, heading, latitude, longitude, ils_ident, ils_frequency, ils_course, created_at)
+                    VALUES ('R1', 'AP1', '18L', 179.0, 40.10, 116.58, 'IBAA', 110.3, 178.5, 0),
+                           ('R2', 'AP1', '36R', 359.0, 40.06, 116.58, NULL, NULL, NULL, 0);
+                 INSERT INTO procedures (id, airport_id, name, type, runway, created_at) VALUES
+                    ('P1', 'AP1', 'ILS18L', 'APPROACH', '18L', 0),
+                    ('P2', 'AP1', 'RNAV18L', 'APPROACH', '18L', 0),
+                    ('P3', 'AP1', 'ELKUR1A', 'STAR', '18L', 0);
+                 INSERT INTO navaids (id, name, type, latitude, longitude, frequency, created_at) VALUES
+                    ('N1', 'PEK', 'VORDME', 40.20, 116.58, 114.7, 0),
+                    ('N2', 'BA', 'NDB', 40.12, 116.58, 352.0, 0),
+                    ('N3', 'XX', 'DME', 40.11, 116.58, NULL, 0);",
+            )
+            .unwrap();
+
+        let repo = AirportRepository::new(db).unwrap();
+        let tuning = repo.approach_tuning("ZBAA", "18l").unwrap();
+
+        assert_eq!(tuning.runway, "18L");
+        assert_eq!(tuning.final_approach_course, Some(178.5));
+        let localizer = tuning.localizer.unwrap();
+        assert_eq!((localizer.ident.as_str(), localizer.display.as_str()), ("IBAA", "110.30"));
+        assert_eq!(tuning.procedures, vec!["ILS18L", "RNAV18L"]);
+
+        let displays: Vec<&str> = tuning.navaids.iter().map(|n| n.display.as_str()).collect();
+        assert_eq!(displays, vec!["352", "114.70"]);
+
+        let visual = repo.approach_tuning("ZBAA", "36R").unwrap();
+        assert_eq!(visual.final_approach_course, Some(359.0));
+        assert!(visual.localizer.is_none());
+
+        assert!(repo.approach_tuning("ZBAA", "09").is_err());
+    }
+
+    #[test]
+    fn test_charts() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at)
+                    VALUES ('AP1', 'ZBAA', 'Beijing', 40.08, 116.58, 0);
+                 INSERT INTO procedures (id, airport_id, name, type, runway, transition, created_at)
+                    VALUES ('P1', 'AP1', 'ILS18L', 'APPROACH', '18L', NULL, 0),
+                           ('P2', 'AP1', 'ILS18L', 'APPROACH', '18L', 'VYK', 0),
+                           ('P3', 'AP1', 'RNAV36R', 'APPROACH', '36R', NULL, 0);
+                 INSERT INTO charts
+                    (id, airport_id, procedure_id, identifier, type, title, effective_cycle,
+                     file_ref, created_at)
+                    VALUES
+                    ('C1', 'AP1', NULL, 'ZBAA-2A', 'AIRPORT', 'Aerodrome chart', '2409', 'zbaa/2a.pdf', 0),
+                    ('C2', 'AP1', 'P1', 'ZBAA-7A', 'APPROACH', 'ILS 18L', '2409', 'zbaa/7a-2409.pdf', 0),
+                    ('C3', 'AP1', 'P1', 'ZBAA-7A', 'APPROACH', 'ILS 18L', '2410', 'zbaa/7a-2410.pdf', 0),
+                    ('C4', 'AP1', 'P2', 'ZBAA-7B', 'APPROACH', 'ILS 18L via VYK', '2409', 'zbaa/7b.pdf', 0),
+                    ('C5', 'AP1', 'P3', 'ZBAA-7C', 'APPROACH', 'RNAV 36R', '2409', 'zbaa/7c.pdf', 0);",
+            )
+            .unwrap();
+        cycle::install_cycle(&db, &cycle::AiracCycle::from_ident("2409").unwrap()).unwrap();
+        let repo = AirportRepository::new(Arc::clone(&db)).unwrap();
+
+        let charts = repo.charts("zbaa").unwrap();
+        let ids: Vec<&str> = charts.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["C1", "C2", "C4", "C5"]);
+        assert_eq!(charts[0].chart_type, ChartType::Airport);
+
+        let ils: Vec<String> = repo
+            .charts_for_procedure("ZBAA", "ils18l")
+            .unwrap()
+            .into_iter()
+            .map(|c| c.file_ref)
+            .collect();
+        assert_eq!(ils, vec!["zbaa/7a-2409.pdf", "zbaa/7b.pdf"]);
+
+        // The next cycle's edition takes over once that cycle is installed
+        cycle::install_cycle(&db, &cycle::AiracCycle::from_ident("2410").unwrap()).unwrap();
+        let ils = repo.charts_for_procedure("ZBAA", "ILS18L").unwrap();
+        assert_eq!(ils[0].file_ref, "zbaa/7a-2410.pdf");
+        assert!(repo.charts_for_procedure("ZBAA", "VOR18L").unwrap().is_empty());
+        assert!(repo.charts("ZZZZ").unwrap().is_empty());
+    }
+}
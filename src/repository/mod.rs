@@ -0,0 +1,27 @@
+pub mod airport;
+pub mod navaid;
+pub mod scope;
+pub mod waypoint;
+
+pub use airport::{
+    AirportDetails, AirportMatch, AirportRepository, ApproachTuning, NearbyNavaid, RunwayWind,
+    TuningFrequency,
+};
+pub use navaid::NavaidRepository;
+pub use scope::DeleteScope;
+pub use waypoint::WaypointRepository;
+
+use crate::error::{AeroBaseError, Result};
+use crate::models::Coordinate;
+
+/// `InvalidInput` unless `coordinate` is on the globe
+pub(crate) fn check_position(kind: &str, ident: &str, coordinate: Coordinate) -> Result<()> {
+    let Coordinate { latitude, longitude } = coordinate;
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "{} {} position {}, {} is out of range",
+            kind, ident, latitude, longitude
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use crate::db::query::{navaid_from_row, normalize_code, NavaidQuery, NAVAID_COLUMNS};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::ids::IdStrategy;
+use crate::models::navaid::Navaid;
+use crate::repository::check_position;
+use crate::repository::scope::DeleteScope;
+use rusqlite::{Connection, OptionalExtension};
+use std::sync::Arc;
+
+/// Typed access to the `navaids` table
+///
+/// Filter with `Query::navaids()`, e.g. by region, type or name prefix, and
+/// pass the builder to `find`.
+pub struct NavaidRepository {
+    db: Arc<Database>,
+}
+
+impl NavaidRepository {
+    /// Create a repository
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Get a navaid by id
+    pub fn get(&self, id: &str) -> Result<Option<Navaid>> {
+        let navaid = self
+            .db
+            .get_conn()?
+            .query_row(
+                &format!("SELECT {} FROM navaids WHERE id = ?1", NAVAID_COLUMNS),
+                [id],
+                navaid_from_row,
+            )
+            .optional()?;
+        Ok(navaid)
+    }
+
+    /// Navaids matching `query`, ordered by name
+    pub fn find(&self, query: &NavaidQuery) -> Result<Vec<Navaid>> {
+        query.fetch(&self.db)
+    }
+
+    /// Number of navaids matching `query`, ignoring its limit and offset
+    pub fn count(&self, query: &NavaidQuery) -> Result<i64> {
+        query.count(&self.db)
+    }
+
+    /// Add a navaid
+    ///
+    /// The name (ident) and region are trimmed and upper-cased, and an
+    /// empty id is filled in by the configured `IdStrategy`. Fails with
+    /// `InvalidInput` if the id is already taken.
+    pub fn insert(&self, navaid: &Navaid) -> Result<Navaid> {
+        Self::insert_in(&*self.db.get_conn()?, self.db.id_strategy(), navaid)
+    }
+
+    pub(crate) fn insert_in(conn: &Connection, ids: IdStrategy, navaid: &Navaid) -> Result<Navaid> {
+        let navaid = validated(navaid, ids)?;
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM navaids WHERE id = ?1)",
+            [&navaid.id],
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Navaid {} already exists",
+                navaid.id
+            )));
+        }
+        Self::check_natural_key(conn, &navaid)?;
+        conn.execute(
+            &format!(
+                "INSERT INTO navaids ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                NAVAID_COLUMNS
+            ),
+            rusqlite::params![
+                navaid.id,
+                navaid.name,
+                navaid.navaid_type.as_str(),
+                navaid.coordinate.latitude,
+                navaid.coordinate.longitude,
+                navaid.frequency,
+                navaid.range_nm,
+                navaid.elevation,
+                navaid.region,
+                navaid.created_at,
+                navaid.declination,
+            ],
+        )?;
+        Ok(navaid)
+    }
+
+    /// Replace the stored fields of the navaid with the same id
+    ///
+    /// `created_at` is kept from the stored row. Fails with `NotFound` for
+    /// an unknown id.
+    pub fn update(&self, navaid: &Navaid) -> Result<Navaid> {
+        Self::update_in(
+            &*self.db.get_conn()?,
+            self.db.id_strategy(),
+            navaid,
+            self.db.clock().timestamp(),
+        )
+    }
+
+    pub(crate) fn update_in(
+        conn: &Connection,
+        ids: IdStrategy,
+        navaid: &Navaid,
+        now: i64,
+    ) -> Result<Navaid> {
+        let navaid = validated(navaid, ids)?;
+        Self::check_natural_key(conn, &navaid)?;
+        let updated = conn.execute(
+            "UPDATE navaids SET name = ?2, type = ?3, latitude = ?4, longitude = ?5,
+                frequency = ?6, range_nm = ?7, elevation = ?8, region = ?9, declination = ?10,
+                updated_at = ?11
+             WHERE id = ?1",
+            rusqlite::params![
+                navaid.id,
+                navaid.name,
+                navaid.navaid_type.as_str(),
+                navaid.coordinate.latitude,
+                navaid.coordinate.longitude,
+                navaid.frequency,
+                navaid.range_nm,
+                navaid.elevation,
+                navaid.region,
+                navaid.declination,
+                now,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(AeroBaseError::NotFound(format!("Navaid {} not found", navaid.id)));
+        }
+        let stored = conn.query_row(
+            &format!("SELECT {} FROM navaids WHERE id = ?1", NAVAID_COLUMNS),
+            [&navaid.id],
+            navaid_from_row,
+        )?;
+        Ok(stored)
+    }
+
+    /// Insert a navaid, or update the stored one with the same ident, type
+    /// and region
+    ///
+    /// Importers go through here so a re-import refreshes rows instead of
+    /// duplicating them. An update keeps the stored id and `created_at`.
+    /// Navaids without a region match among region-less rows.
+    pub fn upsert(&self, navaid: &Navaid) -> Result<Navaid> {
+        Self::upsert_in(
+            &*self.db.get_conn()?,
+            self.db.id_strategy(),
+            navaid,
+            self.db.clock().timestamp(),
+        )
+    }
+
+    /// `upsert` on `conn`, for importers writing inside their own transaction
+    pub(crate) fn upsert_in(
+        conn: &Connection,
+        ids: IdStrategy,
+        navaid: &Navaid,
+        now: i64,
+    ) -> Result<Navaid> {
+        let mut navaid = validated(navaid, ids)?;
+        match Self::natural_match(conn, &navaid)? {
+            Some(id) => {
+                navaid.id = id;
+                Self::update_in(conn, ids, &navaid, now)
+            }
+            None => Self::insert_in(conn, ids, &navaid),
+        }
+    }
+
+    /// Delete a navaid by id; returns whether it existed
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let removed = self.db.get_conn()?.execute("DELETE FROM navaids WHERE id = ?1", [id])?;
+        Ok(removed > 0)
+    }
+
+    /// Delete every navaid in `scope`; returns the number removed
+    ///
+    /// Navaids have no country, so `DeleteScope::Country` is rejected.
+    pub fn delete_where(&self, scope: &DeleteScope) -> Result<usize> {
+        let conn = self.db.get_conn()?;
+        let (condition, params) = scope.condition(&conn, "navaids", &["region"])?;
+        let removed = conn.execute(
+            &format!("DELETE FROM navaids WHERE {}", condition),
+            rusqlite::params_from_iter(params),
+        )?;
+        log::info!("Deleted {} navaids ({:?})", removed, scope);
+        Ok(removed)
+    }
+
+    /// Id of the oldest stored navaid with the same ident, type and region
+    fn natural_match(conn: &Connection, navaid: &Navaid) -> Result<Option<String>> {
+        let id = conn
+            .query_row(
+                "SELECT id FROM navaids WHERE name = ?1 AND type = ?2 AND region IS ?3
+                 ORDER BY created_at, id LIMIT 1",
+                rusqlite::params![navaid.name, navaid.navaid_type.as_str(), navaid.region],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    /// `InvalidInput` if another navaid holds the same ident, type and
+    /// region
+    ///
+    /// Region-less navaids aren't unique, as in the schema.
+    fn check_natural_key(conn: &Connection, navaid: &Navaid) -> Result<()> {
+        let Some(region) = &navaid.region else {
+            return Ok(());
+        };
+        match Self::natural_match(conn, navaid)? {
+            Some(other) if other != navaid.id => Err(AeroBaseError::InvalidInput(format!(
+                "{} {} in region {} already exists as {}",
+                navaid.navaid_type.as_str(),
+                navaid.name,
+                region,
+                other
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A copy of `navaid` with normalized codes, or `InvalidInput`
+fn validated(navaid: &Navaid, ids: IdStrategy) -> Result<Navaid> {
+    let mut navaid = navaid.clone();
+    navaid.id = navaid.id.trim().to_string();
+    navaid.name = normalize_code(&navaid.name);
+    navaid.region = navaid.region.as_deref().map(normalize_code).filter(|r| !r.is_empty());
+
+    if navaid.name.is_empty() {
+        return Err(AeroBaseError::InvalidInput("Navaid name is required".to_string()));
+    }
+    if navaid.id.is_empty() {
+        navaid.id = ids.navdata_id(
+            &[
+                navaid.region.as_deref().unwrap_or(""),
+                &navaid.name,
+                navaid.navaid_type.as_str(),
+            ],
+            navaid.created_at * 1000,
+        );
+    }
+    check_position("Navaid", &navaid.name, navaid.coordinate)?;
+    Ok(navaid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::query::Query;
+    use crate::models::navaid::NavaidType;
+    use crate::models::Coordinate;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn navaid(id: &str, name: &str, region: &str, navaid_type: NavaidType) -> Navaid {
+        let coordinate = Coordinate::new(40.2, 116.6);
+        let mut navaid = Navaid::new(id.to_string(), name.to_string(), navaid_type, coordinate);
+        navaid.region = Some(region.to_string());
+        navaid
+    }
+
+    #[test]
+    fn test_navaid_crud() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = NavaidRepository::new(Arc::clone(&db)).unwrap();
+
+        let mut pek = navaid("N1", "pek", "zb", NavaidType::VORDME);
+        pek.frequency = Some(114.7);
+        let stored = repo.insert(&pek).unwrap();
+        assert_eq!((stored.name.as_str(), stored.region.as_deref()), ("PEK", Some("ZB")));
+        repo.insert(&navaid("N2", "PK", "ZB", NavaidType::NDB)).unwrap();
+        repo.insert(&navaid("N3", "PUD", "ZS", NavaidType::VOR)).unwrap();
+        assert!(matches!(repo.insert(&pek), Err(AeroBaseError::InvalidInput(_))));
+
+        let names = |query: NavaidQuery| -> Vec<String> {
+            repo.find(&query).unwrap().into_iter().map(|n| n.name).collect()
+        };
+        assert_eq!(names(Query::navaids().name_prefix("P")), vec!["PEK", "PK", "PUD"]);
+        assert_eq!(names(Query::navaids().navaid_type(NavaidType::NDB)), vec!["PK"]);
+        assert_eq!(repo.count(&Query::navaids().region("ZB")).unwrap(), 2);
+
+        let mut retuned = stored.clone();
+        retuned.frequency = Some(114.8);
+        retuned.declination = Some(-6.0);
+        repo.update(&retuned).unwrap();
+        let fetched = repo.get("N1").unwrap().unwrap();
+        assert_eq!((fetched.frequency, fetched.declination), (Some(114.8), Some(-6.0)));
+        retuned.coordinate = Coordinate::new(0.0, 181.0);
+        assert!(matches!(repo.update(&retuned), Err(AeroBaseError::InvalidInput(_))));
+
+        assert_eq!(repo.delete_where(&DeleteScope::Region("ZB".to_string())).unwrap(), 2);
+        assert!(repo.delete("N3").unwrap());
+        assert!(repo.get("N3").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_navaid_upsert() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = NavaidRepository::new(db).unwrap();
+        let vor = repo.upsert(&navaid("N1", "PEK", "ZB", NavaidType::VOR)).unwrap();
+
+        let mut retuned = navaid("N9", "PEK", "ZB", NavaidType::VOR);
+        retuned.frequency = Some(114.7);
+        let updated = repo.upsert(&retuned).unwrap();
+        assert_eq!((updated.id.as_str(), updated.frequency), (vor.id.as_str(), Some(114.7)));
+
+        // Same ident, different type: a separate navaid
+        repo.upsert(&navaid("N2", "PEK", "ZB", NavaidType::NDB)).unwrap();
+        assert_eq!(repo.count(&Query::navaids()).unwrap(), 2);
+
+        let mut clash = navaid("N2", "PEK", "ZB", NavaidType::VOR);
+        clash.frequency = Some(115.0);
+        assert!(matches!(repo.update(&clash), Err(AeroBaseError::InvalidInput(_))));
+    }
+}
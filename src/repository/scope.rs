@@ -0,0 +1,66 @@
+use crate::error::{AeroBaseError, Result};
+use crate::navdata::cycle::AiracCycle;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension};
+
+/// Rows selected by a repository's `delete_where`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeleteScope {
+    /// ICAO region code, e.g. "ZB"
+    Region(String),
+    /// Country name as stored by the importer
+    Country(String),
+    /// Rows loaded while the given AIRAC cycle was the installed one, i.e.
+    /// created between its `installed_at` and the next cycle's
+    Cycle(String),
+}
+
+impl DeleteScope {
+    /// SQL condition and parameters selecting the scope's rows in `table`
+    ///
+    /// `columns` lists which of `region` and `country` the table has.
+    pub(crate) fn condition(
+        &self,
+        conn: &Connection,
+        table: &str,
+        columns: &[&str],
+    ) -> Result<(String, Vec<Value>)> {
+        let (column, value) = match self {
+            DeleteScope::Region(region) => ("region", region),
+            DeleteScope::Country(country) => ("country", country),
+            DeleteScope::Cycle(ident) => {
+                let (from, until) = cycle_window(conn, ident)?;
+                return Ok((
+                    "created_at >= ? AND created_at < ?".to_string(),
+                    vec![Value::Integer(from), Value::Integer(until)],
+                ));
+            }
+        };
+        if !columns.contains(&column) {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "{} can't be filtered by {}",
+                table, column
+            )));
+        }
+        Ok((format!("{} = ?", column), vec![Value::Text(value.trim().to_string())]))
+    }
+}
+
+/// `installed_at` of `ident` and of the cycle installed after it
+fn cycle_window(conn: &Connection, ident: &str) -> Result<(i64, i64)> {
+    let ident = AiracCycle::from_ident(ident)?.ident;
+    let from: i64 = conn
+        .query_row(
+            "SELECT installed_at FROM navdata_cycles WHERE cycle = ?1",
+            [&ident],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AeroBaseError::NotFound(format!("Navdata cycle {} not installed", ident)))?;
+    let until: Option<i64> = conn.query_row(
+        "SELECT MIN(installed_at) FROM navdata_cycles WHERE installed_at > ?1",
+        [from],
+        |row| row.get(0),
+    )?;
+    Ok((from, until.unwrap_or(i64::MAX)))
+}
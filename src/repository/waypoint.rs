@@ -0,0 +1,481 @@
+use crate::db::query::{normalize_code, waypoint_from_row, WaypointQuery, WAYPOINT_COLUMNS};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::ids::IdStrategy;
+use crate::models::waypoint::Waypoint;
+use crate::repository::check_position;
+use crate::repository::scope::DeleteScope;
+use crate::spatial::geohash::{self, GEOHASH_PRECISION};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior};
+use std::sync::Arc;
+
+/// Typed access to the `waypoints` table
+///
+/// Filter with `Query::waypoints()`, e.g. by region, type or name prefix,
+/// and pass the builder to `find`.
+pub struct WaypointRepository {
+    db: Arc<Database>,
+}
+
+impl WaypointRepository {
+    /// Create a repository
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Get a waypoint by id
+    pub fn get(&self, id: &str) -> Result<Option<Waypoint>> {
+        let waypoint = self
+            .db
+            .get_conn()?
+            .query_row(
+                &format!("SELECT {} FROM waypoints WHERE id = ?1", WAYPOINT_COLUMNS),
+                [id],
+                waypoint_from_row,
+            )
+            .optional()?;
+        Ok(waypoint)
+    }
+
+    /// Waypoints matching `query`, ordered by name
+    pub fn find(&self, query: &WaypointQuery) -> Result<Vec<Waypoint>> {
+        query.fetch(&self.db)
+    }
+
+    /// Number of waypoints matching `query`, ignoring its limit and offset
+    pub fn count(&self, query: &WaypointQuery) -> Result<i64> {
+        query.count(&self.db)
+    }
+
+    /// Add a waypoint
+    ///
+    /// The name and region are trimmed and upper-cased, and an
+    /// empty id is filled in by the configured `IdStrategy`. Fails with
+    /// `InvalidInput` if the id is already taken.
+    pub fn insert(&self, waypoint: &Waypoint) -> Result<Waypoint> {
+        Self::insert_in(&*self.db.get_conn()?, self.db.id_strategy(), waypoint)
+    }
+
+    pub(crate) fn insert_in(conn: &Connection, ids: IdStrategy, waypoint: &Waypoint) -> Result<Waypoint> {
+        let waypoint = validated(waypoint, ids)?;
+        if Self::exists(conn, &waypoint.id)? {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Waypoint {} already exists",
+                waypoint.id
+            )));
+        }
+        Self::check_natural_key(conn, &waypoint)?;
+        conn.execute(
+            &format!(
+                "INSERT INTO waypoints ({}, geohash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                WAYPOINT_COLUMNS
+            ),
+            rusqlite::params![
+                waypoint.id,
+                waypoint.name,
+                waypoint.coordinate.latitude,
+                waypoint.coordinate.longitude,
+                waypoint.region,
+                waypoint.waypoint_type.as_str(),
+                waypoint.created_at,
+                waypoint.usage.map(|usage| usage.as_str().to_string()),
+                geohash::encode(waypoint.coordinate, GEOHASH_PRECISION),
+            ],
+        )?;
+        Ok(waypoint)
+    }
+
+    /// Replace the stored fields of the waypoint with the same id
+    ///
+    /// `created_at` is kept from the stored row. Fails with `NotFound` for
+    /// an unknown id.
+    pub fn update(&self, waypoint: &Waypoint) -> Result<Waypoint> {
+        Self::update_in(
+            &*self.db.get_conn()?,
+            self.db.id_strategy(),
+            waypoint,
+            self.db.clock().timestamp(),
+        )
+    }
+
+    fn update_in(
+        conn: &Connection,
+        ids: IdStrategy,
+        waypoint: &Waypoint,
+        now: i64,
+    ) -> Result<Waypoint> {
+        let waypoint = validated(waypoint, ids)?;
+        Self::check_natural_key(conn, &waypoint)?;
+        let updated = conn.execute(
+            "UPDATE waypoints SET name = ?2, latitude = ?3, longitude = ?4, region = ?5,
+                type = ?6, usage = ?7, geohash = ?8, updated_at = ?9
+             WHERE id = ?1",
+            rusqlite::params![
+                waypoint.id,
+                waypoint.name,
+                waypoint.coordinate.latitude,
+                waypoint.coordinate.longitude,
+                waypoint.region,
+                waypoint.waypoint_type.as_str(),
+                waypoint.usage.map(|usage| usage.as_str().to_string()),
+                geohash::encode(waypoint.coordinate, GEOHASH_PRECISION),
+                now,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(AeroBaseError::NotFound(format!("Waypoint {} not found", waypoint.id)));
+        }
+        let stored = conn.query_row(
+            &format!("SELECT {} FROM waypoints WHERE id = ?1", WAYPOINT_COLUMNS),
+            [&waypoint.id],
+            waypoint_from_row,
+        )?;
+        Ok(stored)
+    }
+
+    /// Insert a waypoint, or update the stored one with the same ident and
+    /// region
+    ///
+    /// Importers go through here so a re-import refreshes rows instead of
+    /// duplicating them. An update keeps the stored id and `created_at`.
+    /// Waypoints without a region match on ident among region-less rows.
+    pub fn upsert(&self, waypoint: &Waypoint) -> Result<Waypoint> {
+        Self::upsert_in(
+            &*self.db.get_conn()?,
+            self.db.id_strategy(),
+            waypoint,
+            self.db.clock().timestamp(),
+        )
+    }
+
+    /// `upsert` on `conn`, for importers writing inside their own transaction
+    pub(crate) fn upsert_in(
+        conn: &Connection,
+        ids: IdStrategy,
+        waypoint: &Waypoint,
+        now: i64,
+    ) -> Result<Waypoint> {
+        let mut waypoint = validated(waypoint, ids)?;
+        match Self::natural_match(conn, &waypoint)? {
+            Some(id) => {
+                waypoint.id = id;
+                Self::update_in(conn, ids, &waypoint, now)
+            }
+            None => Self::insert_in(conn, ids, &waypoint),
+        }
+    }
+
+    /// Delete a waypoint by id; returns whether it existed
+    ///
+    /// Fails with `InvalidInput` naming the airways that use the waypoint,
+    /// which the schema would refuse anyway. Remove or reroute them first.
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let mut conn = self.db.get_conn()?;
+        // Write-locked from the check on, so no segment can be added before the delete
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        Self::check_unused(&tx, "id = ?", vec![Value::Text(id.to_string())])?;
+        let removed = tx.execute("DELETE FROM waypoints WHERE id = ?1", [id])?;
+        tx.commit()?;
+        Ok(removed > 0)
+    }
+
+    /// Delete every waypoint in `scope`; returns the number removed
+    ///
+    /// Waypoints have no country, so `DeleteScope::Country` is rejected.
+    /// Nothing is deleted if an airway uses any of them, as in `delete`.
+    pub fn delete_where(&self, scope: &DeleteScope) -> Result<usize> {
+        let mut conn = self.db.get_conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let (condition, params) = scope.condition(&tx, "waypoints", &["region"])?;
+        Self::check_unused(&tx, &condition, params.clone())?;
+        let removed = tx.execute(
+            &format!("DELETE FROM waypoints WHERE {}", condition),
+            rusqlite::params_from_iter(params),
+        )?;
+        tx.commit()?;
+        log::info!("Deleted {} waypoints ({:?})", removed, scope);
+        Ok(removed)
+    }
+
+    /// `InvalidInput` naming every airway with a segment at a waypoint
+    /// matching `condition`
+    fn check_unused(conn: &Connection, condition: &str, params: Vec<Value>) -> Result<()> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT DISTINCT a.name FROM airway_segments s
+             JOIN airways a ON a.id = s.airway_id
+             WHERE s.from_waypoint_id IN (SELECT id FROM waypoints WHERE {0})
+                OR s.to_waypoint_id IN (SELECT id FROM waypoints WHERE {0})
+             ORDER BY a.name",
+            condition
+        ))?;
+        // The condition appears twice, so its parameters are bound twice
+        let bound = params.iter().chain(params.iter()).cloned();
+        let airways = stmt
+            .query_map(rusqlite::params_from_iter(bound), |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        if airways.is_empty() {
+            return Ok(());
+        }
+        Err(AeroBaseError::InvalidInput(format!(
+            "Waypoint is used by airway(s) {}",
+            airways.join(", ")
+        )))
+    }
+
+    /// Id of the oldest stored waypoint with the same ident and region
+    pub(crate) fn natural_match(conn: &Connection, waypoint: &Waypoint) -> Result<Option<String>> {
+        let id = conn
+            .query_row(
+                "SELECT id FROM waypoints WHERE name = ?1 AND region IS ?2
+                 ORDER BY created_at, id LIMIT 1",
+                rusqlite::params![waypoint.name, waypoint.region],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    /// `InvalidInput` if another waypoint holds the same ident and region
+    ///
+    /// Region-less waypoints aren't unique, as in the schema.
+    fn check_natural_key(conn: &Connection, waypoint: &Waypoint) -> Result<()> {
+        let Some(region) = &waypoint.region else {
+            return Ok(());
+        };
+        match Self::natural_match(conn, waypoint)? {
+            Some(other) if other != waypoint.id => Err(AeroBaseError::InvalidInput(format!(
+                "Waypoint {} in region {} already exists as {}",
+                waypoint.name, region, other
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn exists(conn: &Connection, id: &str) -> Result<bool> {
+        let exists = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM waypoints WHERE id = ?1)",
+            [id],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+}
+
+/// A copy of `waypoint` with normalized codes, or `InvalidInput`
+fn validated(waypoint: &Waypoint, ids: IdStrategy) -> Result<Waypoint> {
+    let mut waypoint = waypoint.clone();
+    waypoint.id = waypoint.id.trim().to_string();
+    waypoint.name = normalize_code(&waypoint.name);
+    waypoint.region = waypoint.region.as_deref().map(normalize_code).filter(|r| !r.is_empty());
+
+    if waypoint.name.is_empty() {
+        return Err(AeroBaseError::InvalidInput("Waypoint name is required".to_string()));
+    }
+    if waypoint.id.is_empty() {
+        let region = waypoint.region.as_deref().unwrap_or("");
+        waypoint.id = ids.navdata_id(&[region, &waypoint.name], waypoint.created_at * 1000);
+    }
+    check_position("Waypoint", &waypoint.name, waypoint.coordinate)?;
+    Ok(waypoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::db::query::Query;
+    use crate::models::waypoint::{WaypointType, WaypointUsage};
+    use crate::models::Coordinate;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn waypoint(id: &str, name: &str, region: &str, waypoint_type: WaypointType) -> Waypoint {
+        let coordinate = Coordinate::new(40.0, 116.0);
+        let mut waypoint =
+            Waypoint::new(id.to_string(), name.to_string(), coordinate, waypoint_type);
+        waypoint.region = Some(region.to_string());
+        waypoint
+    }
+
+    #[test]
+    fn test_waypoint_crud() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = WaypointRepository::new(Arc::clone(&db)).unwrap();
+
+        let stored = repo.insert(&waypoint("W1", " alpha", "zb", WaypointType::Fix)).unwrap();
+        assert_eq!(stored.name, "ALPHA");
+        assert_eq!(stored.region.as_deref(), Some("ZB"));
+        repo.insert(&waypoint("W2", "ALTER", "ZB", WaypointType::GPS)).unwrap();
+        repo.insert(&waypoint("W3", "BRAVO", "ZS", WaypointType::Fix)).unwrap();
+        assert!(matches!(
+            repo.insert(&waypoint("W1", "DUPE", "ZB", WaypointType::Fix)),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+
+        let names = |query: WaypointQuery| -> Vec<String> {
+            repo.find(&query).unwrap().into_iter().map(|w| w.name).collect()
+        };
+        assert_eq!(names(Query::waypoints().name_prefix("AL")), vec!["ALPHA", "ALTER"]);
+        let fixes = Query::waypoints().region("ZB").waypoint_type(WaypointType::Fix);
+        assert_eq!(names(fixes), vec!["ALPHA"]);
+        assert_eq!(repo.count(&Query::waypoints().region("ZS")).unwrap(), 1);
+
+        let mut moved = stored.clone();
+        moved.coordinate = Coordinate::new(41.0, 117.0);
+        moved.usage = Some(WaypointUsage::Enroute);
+        let updated = repo.update(&moved).unwrap();
+        assert_eq!(updated.coordinate.latitude, 41.0);
+        // The geohash cell follows the move
+        let cells = crate::spatial::query::find_waypoints_in_cells(
+            &db,
+            &[geohash::encode(moved.coordinate, 4)],
+        )
+        .unwrap();
+        assert_eq!(cells.iter().map(|w| w.id.as_str()).collect::<Vec<_>>(), vec!["W1"]);
+        assert_eq!(repo.get("W1").unwrap().unwrap().usage, Some(WaypointUsage::Enroute));
+        // Renumbering onto a taken ident and region is a clash
+        moved.id = "W9".to_string();
+        assert!(matches!(repo.update(&moved), Err(AeroBaseError::InvalidInput(_))));
+        moved.name = "ZULU".to_string();
+        assert!(matches!(repo.update(&moved), Err(AeroBaseError::NotFound(_))));
+
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airways (id, name, type, created_at) VALUES ('A1', 'A461', 'HIGH', 0);
+                 INSERT INTO airway_segments
+                    (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+                    VALUES ('S1', 'A1', 'W1', 'W3', 1, 0);",
+            )
+            .unwrap();
+        assert!(repo.delete("W2").unwrap());
+        assert!(!repo.delete("W2").unwrap());
+        assert!(matches!(
+            repo.delete_where(&DeleteScope::Country("China".to_string())),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+
+        // Waypoints on an airway stay until the airway is gone
+        assert!(matches!(repo.delete("W3"), Err(AeroBaseError::InvalidInput(_))));
+        db.get_conn().unwrap().execute("DELETE FROM airways WHERE id = 'A1'", []).unwrap();
+        assert!(repo.delete("W3").unwrap());
+        assert_eq!(repo.delete_where(&DeleteScope::Region("ZB".to_string())).unwrap(), 1);
+        assert_eq!(repo.count(&Query::waypoints()).unwrap(), 0);
+
+        // Missing ids come from the configured strategy
+        let generated = repo.insert(&waypoint(" ", "CHARLIE", "ZB", WaypointType::Fix)).unwrap();
+        assert!(!generated.id.trim().is_empty());
+        assert_eq!(repo.get(&generated.id).unwrap().unwrap().name, "CHARLIE");
+    }
+
+    #[test]
+    fn test_update_stamps_service_clock() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let clock = Arc::new(MockClock::at_timestamp(5_000));
+        let db = Arc::new(Database::new(&config).unwrap().with_clock(clock.clone()));
+        db.migrate().unwrap();
+        let repo = WaypointRepository::new(Arc::clone(&db)).unwrap();
+
+        let mut stored = repo.insert(&waypoint("W1", "ALPHA", "ZB", WaypointType::Fix)).unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        stored.usage = Some(WaypointUsage::Terminal);
+        repo.update(&stored).unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        repo.upsert(&stored).unwrap();
+
+        let updated_at: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT updated_at FROM waypoints WHERE id = 'W1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(updated_at, 5_120);
+    }
+
+    #[test]
+    fn test_delete_used_waypoint() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = WaypointRepository::new(Arc::clone(&db)).unwrap();
+        for (id, name) in [("W1", "ALPHA"), ("W2", "BRAVO"), ("W3", "CHARLIE")] {
+            repo.insert(&waypoint(id, name, "ZB", WaypointType::Fix)).unwrap();
+        }
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airways (id, name, type, created_at) VALUES
+                    ('A1', 'A461', 'HIGH', 0), ('A2', 'B208', 'HIGH', 0);
+                 INSERT INTO airway_segments
+                    (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+                    VALUES ('S1', 'A1', 'W1', 'W2', 1, 0), ('S2', 'A2', 'W3', 'W2', 1, 0);",
+            )
+            .unwrap();
+
+        // The error names every airway through the waypoint
+        match repo.delete("W2") {
+            Err(AeroBaseError::InvalidInput(message)) => assert!(message.ends_with("A461, B208")),
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+        match repo.delete_where(&DeleteScope::Region("ZB".to_string())) {
+            Err(AeroBaseError::InvalidInput(message)) => assert!(message.ends_with("A461, B208")),
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+
+        // Nothing was deleted, and both airways keep their segments
+        assert_eq!(repo.count(&Query::waypoints()).unwrap(), 3);
+        let segments: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM airway_segments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(segments, 2);
+    }
+
+    #[test]
+    fn test_waypoint_upsert() {
+        let (db, _temp_file) = setup_test_db();
+        let repo = WaypointRepository::new(db).unwrap();
+        let first = repo.upsert(&waypoint("", "ALPHA", "ZB", WaypointType::Fix)).unwrap();
+
+        // A re-import with a fresh id updates the stored row in place
+        let mut moved = waypoint("OTHER", "alpha", "zb", WaypointType::GPS);
+        moved.coordinate = Coordinate::new(40.5, 116.5);
+        let second = repo.upsert(&moved).unwrap();
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.created_at, first.created_at);
+        assert_eq!(second.waypoint_type, WaypointType::GPS);
+        assert_eq!(repo.count(&Query::waypoints()).unwrap(), 1);
+
+        // Another region is another waypoint
+        repo.upsert(&waypoint("", "ALPHA", "ZS", WaypointType::Fix)).unwrap();
+        assert_eq!(repo.count(&Query::waypoints()).unwrap(), 2);
+        assert!(matches!(
+            repo.insert(&waypoint("W9", "ALPHA", "ZB", WaypointType::Fix)),
+            Err(AeroBaseError::InvalidInput(_))
+        ));
+
+        let mut regionless = waypoint("", "BRAVO", "", WaypointType::Fix);
+        regionless.region = None;
+        let bravo = repo.upsert(&regionless).unwrap();
+        assert_eq!(repo.upsert(&regionless).unwrap().id, bravo.id);
+    }
+}
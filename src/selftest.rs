@@ -0,0 +1,321 @@
+use crate::db::Database;
+use crate::encryption::AesEncryptor;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::airways::{self, RouteConstraints};
+use crate::models::Coordinate;
+use crate::spatial::query;
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Bytes encrypted and decrypted by the encryption check
+const ENCRYPTION_PAYLOAD: usize = 64 * 1024;
+/// Writes timed by the fsync probe
+const FSYNC_ROUNDS: usize = 8;
+const FSYNC_BLOCK: usize = 4096;
+
+/// Known airports, fixes and one airway loaded into the scratch database
+const FIXTURE: &str = "
+    INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+        ('ST1', 'ZZST', 'Self test origin', 40.0, 116.0, 0),
+        ('ST2', 'ZZSU', 'Self test nearby', 40.3, 116.2, 0),
+        ('ST3', 'ZZSV', 'Self test far', 45.0, 120.0, 0),
+        ('ST4', 'ZZSW', 'Self test destination', 38.6, 117.4, 0);
+    INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+        ('STW1', 'STONE', 39.5, 116.5, 'FIX', 0),
+        ('STW2', 'STTWO', 39.0, 117.0, 'FIX', 0);
+    INSERT INTO airways (id, name, type, created_at) VALUES ('STA1', 'ST1', 'HIGH', 0);
+    INSERT INTO airway_segments
+        (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+        VALUES ('STS1', 'STA1', 'STW1', 'STW2', 1, 0);";
+
+/// 自检各项的耗时上限（毫秒）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfTestLimits {
+    pub spatial_query_ms: f64,
+    pub route_ms: f64,
+    pub encryption_ms: f64,
+    /// 单次 4 KiB 写入加 fsync 的最长耗时
+    pub fsync_ms: f64,
+}
+
+impl Default for SelfTestLimits {
+    fn default() -> Self {
+        Self {
+            spatial_query_ms: 100.0,
+            route_ms: 250.0,
+            encryption_ms: 100.0,
+            fsync_ms: 100.0,
+        }
+    }
+}
+
+/// 一项自检结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestCheck {
+    pub name: String,
+    /// 结果正确且未超过耗时上限
+    pub passed: bool,
+    pub duration_ms: f64,
+    pub limit_ms: f64,
+    /// 测得的数值，或失败原因
+    pub detail: String,
+}
+
+/// 启动自检报告
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub checks: Vec<SelfTestCheck>,
+    pub duration_ms: f64,
+}
+
+impl SelfTestReport {
+    /// 所有检查项均通过
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// 未通过的检查项
+    pub fn failures(&self) -> Vec<&SelfTestCheck> {
+        self.checks.iter().filter(|check| !check.passed).collect()
+    }
+}
+
+/// Scratch files live next to the database so the probe hits the same storage
+pub fn scratch_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".selftest");
+    PathBuf::from(path)
+}
+
+/// Run the self-test battery against scratch files at `scratch`
+///
+/// The spatial and route checks use a fresh database loaded with a known
+/// fixture, so results can be compared with exact expectations and the
+/// live database is never written. All scratch files are removed after.
+pub fn run(scratch: &Path, limits: &SelfTestLimits) -> SelfTestReport {
+    let started = Instant::now();
+    let fixture = fixture_database(scratch);
+
+    let mut checks = vec![
+        timed("spatial_query", limits.spatial_query_ms, || spatial_query(fixture.as_ref()?)),
+        timed("route", limits.route_ms, || route(fixture.as_ref()?)),
+        timed("encryption", limits.encryption_ms, encryption),
+    ];
+    drop(fixture);
+    let _ = std::fs::remove_file(scratch);
+
+    let fsync_path = {
+        let mut path = scratch.as_os_str().to_owned();
+        path.push("-fsync");
+        PathBuf::from(path)
+    };
+    checks.push(fsync_probe(&fsync_path, limits.fsync_ms));
+    let _ = std::fs::remove_file(&fsync_path);
+
+    let report = SelfTestReport {
+        checks,
+        duration_ms: elapsed_ms(started),
+    };
+    for check in report.failures() {
+        log::warn!(
+            "自检 {} 未通过（{:.1} ms，上限 {:.0} ms）: {}",
+            check.name,
+            check.duration_ms,
+            check.limit_ms,
+            check.detail
+        );
+    }
+    report
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Run `check`, failing it on an error or when it takes longer than `limit_ms`
+fn timed(
+    name: &str,
+    limit_ms: f64,
+    check: impl FnOnce() -> std::result::Result<String, String>,
+) -> SelfTestCheck {
+    let started = Instant::now();
+    let result = check();
+    let duration_ms = elapsed_ms(started);
+    let (passed, detail) = match result {
+        Ok(detail) if duration_ms <= limit_ms => (true, detail),
+        Ok(detail) => (false, format!("too slow; {}", detail)),
+        Err(reason) => (false, reason),
+    };
+    SelfTestCheck {
+        name: name.to_string(),
+        passed,
+        duration_ms,
+        limit_ms,
+        detail,
+    }
+}
+
+fn fixture_database(path: &Path) -> std::result::Result<Database, String> {
+    let _ = std::fs::remove_file(path);
+    let config = Config {
+        db_path: path.to_path_buf(),
+        enable_wal: false,
+        pool_size: 1,
+        ..Default::default()
+    };
+    let setup = || -> Result<Database> {
+        // Fail fast on an unusable location rather than in the pool's retries
+        std::fs::File::create(path)?;
+        let db = Database::new(&config)?;
+        db.migrate()?;
+        db.get_conn()?.execute_batch(FIXTURE)?;
+        Ok(db)
+    };
+    setup().map_err(|e| format!("scratch database: {}", e))
+}
+
+fn spatial_query(db: &Database) -> std::result::Result<String, String> {
+    let center = Coordinate::new(40.0, 116.0);
+    let mut found: Vec<String> = query::find_airports_within(db, center, 30.0)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|airport| airport.icao)
+        .collect();
+    found.sort();
+    if found != ["ZZST", "ZZSU"] {
+        return Err(format!("expected ZZST and ZZSU within 30 nm, found {:?}", found));
+    }
+    Ok(format!("{} airports within 30 nm", found.len()))
+}
+
+fn route(db: &Database) -> std::result::Result<String, String> {
+    let found = airways::find_route(db, "ZZST", "ZZSW", &RouteConstraints::new(35000, 450))
+        .map_err(|e| e.to_string())?;
+    if found.route.plan.route != ["STW1", "STW2"] {
+        return Err(format!("expected STONE ST1 STTWO, found {}", found.route_string()));
+    }
+    let points = [
+        Coordinate::new(40.0, 116.0),
+        Coordinate::new(39.5, 116.5),
+        Coordinate::new(39.0, 117.0),
+        Coordinate::new(38.6, 117.4),
+    ];
+    let expected: f64 = points.windows(2).map(|pair| pair[0].distance_to(&pair[1])).sum();
+    if (found.route.total_distance - expected).abs() > 0.01 {
+        return Err(format!(
+            "route distance {:.2} nm, expected {:.2} nm",
+            found.route.total_distance, expected
+        ));
+    }
+    Ok(format!("{:.1} nm over {} legs", expected, found.legs.len()))
+}
+
+fn encryption() -> std::result::Result<String, String> {
+    let round_trip = || -> Result<bool> {
+        let encryptor = AesEncryptor::new()?;
+        let plaintext: Vec<u8> = (0..ENCRYPTION_PAYLOAD).map(|i| (i % 251) as u8).collect();
+        let encrypted = encryptor.encrypt(&plaintext)?;
+        Ok(encryptor.decrypt(&encrypted)? == plaintext)
+    };
+    match round_trip() {
+        Ok(true) => Ok(format!("{} KiB round trip", ENCRYPTION_PAYLOAD / 1024)),
+        Ok(false) => Err("decrypted data differs from the original".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Time `FSYNC_ROUNDS` block writes, each followed by an fsync; the slowest
+/// one is held to the limit
+fn fsync_probe(path: &Path, limit_ms: f64) -> SelfTestCheck {
+    let started = Instant::now();
+    let probe = || -> Result<Vec<f64>> {
+        let mut file = std::fs::File::create(path)?;
+        let block = [0x5a_u8; FSYNC_BLOCK];
+        let mut latencies = Vec::with_capacity(FSYNC_ROUNDS);
+        for _ in 0..FSYNC_ROUNDS {
+            let write = Instant::now();
+            file.write_all(&block)?;
+            file.sync_all()?;
+            latencies.push(elapsed_ms(write));
+        }
+        Ok(latencies)
+    };
+    let result = probe();
+    let duration_ms = elapsed_ms(started);
+
+    let (passed, detail) = match result {
+        Ok(mut latencies) => {
+            latencies.sort_by(f64::total_cmp);
+            let median = latencies[latencies.len() / 2];
+            let max = latencies[latencies.len() - 1];
+            let detail = format!(
+                "median {:.2} ms, max {:.2} ms over {} writes",
+                median, max, FSYNC_ROUNDS
+            );
+            (max <= limit_ms, detail)
+        }
+        Err(AeroBaseError::Io(e)) => (false, format!("fsync probe: {}", e)),
+        Err(e) => (false, e.to_string()),
+    };
+    SelfTestCheck {
+        name: "fsync".to_string(),
+        passed,
+        duration_ms,
+        limit_ms,
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_self_test() {
+        let dir = tempdir().unwrap();
+        let scratch = scratch_path(&dir.path().join("aerobase.db"));
+
+        // Generous limits: debug builds on shared CI runners are slow
+        let limits = SelfTestLimits {
+            spatial_query_ms: 10_000.0,
+            route_ms: 10_000.0,
+            encryption_ms: 10_000.0,
+            fsync_ms: 10_000.0,
+        };
+        let report = run(&scratch, &limits);
+        let names: Vec<_> = report.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["spatial_query", "route", "encryption", "fsync"]);
+        assert!(report.passed(), "{:?}", report.failures());
+        assert!(report.checks[3].detail.contains("over 8 writes"));
+        // Nothing is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let strict = SelfTestLimits {
+            fsync_ms: 0.0,
+            ..limits
+        };
+        let report = run(&scratch, &strict);
+        assert!(!report.passed());
+        assert_eq!(report.failures()[0].name, "fsync");
+    }
+
+    #[test]
+    fn test_unusable_scratch_location() {
+        let dir = tempdir().unwrap();
+        let scratch = dir.path().join("missing").join("aerobase.db.selftest");
+
+        let report = run(&scratch, &SelfTestLimits::default());
+        let failed: Vec<_> = report
+            .failures()
+            .iter()
+            .map(|check| check.name.as_str())
+            .filter(|name| *name != "encryption")
+            .collect();
+        assert_eq!(failed, vec!["spatial_query", "route", "fsync"]);
+        assert!(report.checks[0].detail.starts_with("scratch database"));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::clock::{self, Clock};
+use crate::error::{AeroBaseError, Result};
+use crate::events::{Event, EventBus};
+use crate::flight::FlightRoute;
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+use crate::storage::tracks::TrackPoint;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// `source` of the position events published by the simulator
+pub const SIMULATION_SOURCE: &str = "simulation";
+
+/// 航线回放配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationConfig {
+    /// 回放速度倍数（1.0 为实时，60.0 表示一分钟飞行一秒播完）
+    pub speed_multiplier: f64,
+    /// 相邻两个位置之间的模拟秒数
+    pub interval_secs: u32,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            speed_multiplier: 1.0,
+            interval_secs: 1,
+        }
+    }
+}
+
+/// 模拟位置源：按巡航速度沿计算好的航线回放位置
+///
+/// 发布的 `PositionUpdated` 事件与真实位置源相同，下游应用无需 GPS 硬件即可
+/// 演示和集成测试轨迹记录等功能。
+pub struct RouteSimulator {
+    /// Route points with their cumulative distance in nautical miles
+    points: Vec<(f64, Coordinate)>,
+    ground_speed_kt: f64,
+    altitude: i32,
+    config: SimulationConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl RouteSimulator {
+    /// 为航线创建模拟器；航线至少需要两个航路点和正的巡航速度
+    pub fn new(route: &FlightRoute) -> Result<Self> {
+        if route.waypoints.len() < 2 {
+            return Err(AeroBaseError::InvalidInput(
+                "Simulated route needs at least two waypoints".to_string(),
+            ));
+        }
+        if route.plan.cruise_speed <= 0 {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Invalid cruise speed for simulation: {}",
+                route.plan.cruise_speed
+            )));
+        }
+
+        let mut cumulative = 0.0;
+        let mut points = Vec::with_capacity(route.waypoints.len());
+        for (i, waypoint) in route.waypoints.iter().enumerate() {
+            if i > 0 {
+                cumulative += geometry::great_circle_distance(
+                    route.waypoints[i - 1].coordinate,
+                    waypoint.coordinate,
+                );
+            }
+            points.push((cumulative, waypoint.coordinate));
+        }
+
+        Ok(Self {
+            points,
+            ground_speed_kt: route.plan.cruise_speed as f64,
+            altitude: route.plan.cruise_altitude,
+            config: SimulationConfig::default(),
+            clock: clock::system(),
+        })
+    }
+
+    /// 设置回放配置
+    pub fn with_config(mut self, config: SimulationConfig) -> Result<Self> {
+        let multiplier = config.speed_multiplier;
+        if multiplier.is_nan() || multiplier <= 0.0 || config.interval_secs == 0 {
+            return Err(AeroBaseError::InvalidInput(
+                "Speed multiplier and interval must be positive".to_string(),
+            ));
+        }
+        self.config = config;
+        Ok(self)
+    }
+
+    /// 使用指定时钟作为第一个位置的时间
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 按巡航速度飞完航线所需的秒数
+    pub fn duration_secs(&self) -> f64 {
+        self.total_distance() / self.ground_speed_kt * 3600.0
+    }
+
+    /// 起飞后 `elapsed_secs` 秒的位置（超过航线时长时停在终点）
+    pub fn position_at(&self, elapsed_secs: f64) -> Coordinate {
+        let distance = (self.ground_speed_kt * elapsed_secs.max(0.0) / 3600.0)
+            .min(self.total_distance());
+        let segment = self
+            .points
+            .windows(2)
+            .find(|pair| distance <= pair[1].0)
+            .unwrap_or(&self.points[self.points.len() - 2..]);
+        let (start_distance, from) = segment[0];
+        let (_, to) = segment[1];
+        if distance - start_distance <= 0.0 {
+            return from;
+        }
+        geometry::destination_point(
+            from,
+            distance - start_distance,
+            geometry::initial_bearing(from, to),
+        )
+    }
+
+    /// 整条航线的模拟位置（每 `interval_secs` 一个，最后一个在终点）
+    pub fn positions(&self) -> Vec<TrackPoint> {
+        let start = self.clock.timestamp();
+        let duration = self.duration_secs();
+        let interval = self.config.interval_secs as f64;
+
+        let mut elapsed: f64 = 0.0;
+        let mut positions = Vec::new();
+        loop {
+            let elapsed_now = elapsed.min(duration);
+            positions.push(TrackPoint {
+                recorded_at: start + elapsed_now.ceil() as i64,
+                position: self.position_at(elapsed_now),
+                altitude: Some(self.altitude),
+                ground_speed: Some(self.ground_speed_kt.round() as i32),
+            });
+            if elapsed >= duration {
+                break;
+            }
+            elapsed += interval;
+        }
+        positions
+    }
+
+    /// 在后台按回放速度向事件总线发布位置，返回发布的位置数
+    pub fn spawn(self, bus: Arc<EventBus>) -> JoinHandle<usize> {
+        let pause = Duration::from_secs_f64(
+            self.config.interval_secs as f64 / self.config.speed_multiplier,
+        );
+        let positions = self.positions();
+        tokio::spawn(async move {
+            for (i, point) in positions.iter().enumerate() {
+                if i > 0 {
+                    tokio::time::sleep(pause).await;
+                }
+                bus.publish(Event::PositionUpdated {
+                    source: SIMULATION_SOURCE.to_string(),
+                    point: *point,
+                });
+            }
+            positions.len()
+        })
+    }
+
+    fn total_distance(&self) -> f64 {
+        self.points[self.points.len() - 1].0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::db::Database;
+    use crate::flight::{FlightPlan, RouteWaypoint};
+    use crate::storage::{StorageConfig, StorageManager};
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn route() -> FlightRoute {
+        let waypoint = |id: &str, latitude: f64| RouteWaypoint {
+            id: id.to_string(),
+            name: id.to_string(),
+            coordinate: Coordinate::new(latitude, 116.0),
+            distance_from_previous: 0.0,
+            cumulative_distance: 0.0,
+            estimated_time: 0,
+        };
+        FlightRoute {
+            plan: FlightPlan {
+                departure: "AAAA".to_string(),
+                destination: "BBBB".to_string(),
+                alternate: None,
+                cruise_altitude: 8000,
+                cruise_speed: 120,
+                route: vec!["MID".to_string()],
+                ..Default::default()
+            },
+            total_distance: 0.0,
+            estimated_time: 0,
+            waypoints: vec![waypoint("AAAA", 40.0), waypoint("MID", 40.5), waypoint("BBBB", 41.0)],
+        }
+    }
+
+    #[test]
+    fn test_positions_follow_route() {
+        let simulator = RouteSimulator::new(&route())
+            .unwrap()
+            .with_config(SimulationConfig {
+                speed_multiplier: 1.0,
+                interval_secs: 60,
+            })
+            .unwrap()
+            .with_clock(Arc::new(MockClock::at_timestamp(1_000)));
+
+        // 60 nm at 120 kt
+        assert!((simulator.duration_secs() - 1800.0).abs() < 5.0);
+        let positions = simulator.positions();
+        let expected = (simulator.duration_secs() / 60.0).ceil() as usize + 1;
+        assert_eq!(positions.len(), expected);
+        assert_eq!(positions[0].recorded_at, 1_000);
+        assert_eq!(positions[0].position, Coordinate::new(40.0, 116.0));
+        assert_eq!(positions[1].recorded_at, 1_060);
+        assert!((positions[15].position.latitude - 40.5).abs() < 0.02);
+
+        let last = positions.last().unwrap();
+        assert!((last.position.latitude - 41.0).abs() < 1e-6);
+        assert_eq!(last.altitude, Some(8000));
+        assert_eq!(last.ground_speed, Some(120));
+        assert!(positions.windows(2).all(|w| w[0].recorded_at < w[1].recorded_at));
+    }
+
+    #[test]
+    fn test_rejects_invalid_input() {
+        let mut short = route();
+        short.waypoints.truncate(1);
+        assert!(RouteSimulator::new(&short).is_err());
+
+        let config = SimulationConfig {
+            speed_multiplier: 0.0,
+            interval_secs: 1,
+        };
+        assert!(RouteSimulator::new(&route()).unwrap().with_config(config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_playback_feeds_track_recorder() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let storage = Arc::new(StorageManager::new(db, StorageConfig::default()).unwrap());
+
+        let bus = Arc::new(EventBus::new());
+        let recorder = {
+            let storage = storage.clone();
+            let receiver = bus.subscribe();
+            tokio::spawn(async move { storage.record_positions("SIM", receiver).await })
+        };
+
+        let simulator = RouteSimulator::new(&route())
+            .unwrap()
+            .with_config(SimulationConfig {
+                speed_multiplier: 60_000.0,
+                interval_secs: 300,
+            })
+            .unwrap();
+        let expected = simulator.positions().len();
+        let published = simulator.spawn(bus.clone()).await.unwrap();
+        drop(bus);
+
+        assert!(expected > 2);
+        assert_eq!(published, expected);
+        assert_eq!(recorder.await.unwrap().unwrap(), expected);
+        assert_eq!(storage.track("SIM").unwrap().len(), expected);
+    }
+}
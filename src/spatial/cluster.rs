@@ -0,0 +1,192 @@
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::airport::Airport;
+use crate::models::waypoint::Waypoint;
+use crate::models::Coordinate;
+use crate::spatial::{query, render};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Width of a clustering cell, in screen pixels
+const CLUSTER_CELL_PIXELS: f64 = 64.0;
+
+/// Members returned with each cluster for labelling
+pub const MAX_REPRESENTATIVES: usize = 3;
+
+/// Points sharing one grid cell at the current zoom
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cluster<T> {
+    /// Mean position of every member
+    pub center: Coordinate,
+    pub count: usize,
+    /// Corners of the members' bounding box, for zoom-to-cluster
+    pub min: Coordinate,
+    pub max: Coordinate,
+    /// Most prominent members, at most `MAX_REPRESENTATIVES`
+    pub representatives: Vec<T>,
+}
+
+/// Group points into cells of a fixed screen size
+///
+/// The grid is anchored at 0°/0° rather than at the viewport, so clusters
+/// stay put while the map pans. `rank` orders members within a cell; the
+/// first ones become the representatives.
+fn cluster<T, K: Ord>(
+    items: Vec<T>,
+    zoom: u8,
+    position: impl Fn(&T) -> Coordinate,
+    rank: impl Fn(&T) -> K,
+) -> Vec<Cluster<T>> {
+    let cell = render::degrees_per_pixel(zoom) * CLUSTER_CELL_PIXELS;
+
+    let mut cells: BTreeMap<(i64, i64), Vec<T>> = BTreeMap::new();
+    for item in items {
+        let c = position(&item);
+        let key = (
+            (c.latitude / cell).floor() as i64,
+            (c.longitude / cell).floor() as i64,
+        );
+        cells.entry(key).or_default().push(item);
+    }
+
+    cells
+        .into_values()
+        .map(|mut members| {
+            let count = members.len();
+            let positions: Vec<Coordinate> = members.iter().map(&position).collect();
+            let mut min = positions[0];
+            let mut max = positions[0];
+            let (mut lat_sum, mut lon_sum) = (0.0, 0.0);
+            for p in &positions {
+                min.latitude = min.latitude.min(p.latitude);
+                min.longitude = min.longitude.min(p.longitude);
+                max.latitude = max.latitude.max(p.latitude);
+                max.longitude = max.longitude.max(p.longitude);
+                lat_sum += p.latitude;
+                lon_sum += p.longitude;
+            }
+
+            members.sort_by_key(&rank);
+            members.truncate(MAX_REPRESENTATIVES);
+            Cluster {
+                center: Coordinate::new(lat_sum / count as f64, lon_sum / count as f64),
+                count,
+                min,
+                max,
+                representatives: members,
+            }
+        })
+        .collect()
+}
+
+/// Cluster the airports inside `min`..`max` for display at `zoom`
+///
+/// Airports with an IATA code (typically those with scheduled service)
+/// are preferred as representatives.
+pub fn cluster_airports(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+    zoom: u8,
+) -> Result<Vec<Cluster<Airport>>> {
+    Ok(cluster_airport_list(query::find_airports_in(db, min, max)?, zoom))
+}
+
+/// Cluster already-fetched airports, ranked as in `cluster_airports`
+pub(crate) fn cluster_airport_list(airports: Vec<Airport>, zoom: u8) -> Vec<Cluster<Airport>> {
+    cluster(
+        airports,
+        zoom,
+        |a| a.coordinate,
+        |a| (!a.has_iata(), a.icao.clone()),
+    )
+}
+
+/// Cluster the waypoints inside `min`..`max` for display at `zoom`
+pub fn cluster_waypoints(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+    zoom: u8,
+) -> Result<Vec<Cluster<Waypoint>>> {
+    let waypoints = query::find_waypoints_in(db, min, max)?;
+    Ok(cluster(waypoints, zoom, |w| w.coordinate, |w| w.name.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_cluster_airports() {
+        let (db, _temp_file) = setup_test_db();
+        // Four airports around Beijing within ~0.3°, one in Shanghai
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, iata, name, latitude, longitude, created_at) VALUES
+                    ('1', 'ZBAD', 'PKX', 'Daxing', 39.51, 116.41, 0),
+                    ('2', 'ZBAA', 'PEK', 'Capital', 39.51, 116.58, 0),
+                    ('3', 'ZBNY', NULL, 'Nanyuan', 39.78, 116.39, 0),
+                    ('4', 'ZBDC', NULL, 'Field', 39.60, 116.50, 0),
+                    ('5', 'ZSPD', 'PVG', 'Pudong', 31.14, 121.81, 0);",
+            )
+            .unwrap();
+        let (min, max) = (Coordinate::new(20.0, 100.0), Coordinate::new(50.0, 130.0));
+
+        // At zoom 5 a cell is ~2.8°: Beijing airports merge, Shanghai stands alone
+        let clusters = cluster_airports(&db, min, max, 5).unwrap();
+        assert_eq!(clusters.len(), 2);
+        let beijing = clusters.iter().find(|c| c.count == 4).unwrap();
+        assert!((beijing.center.latitude - 39.6).abs() < 0.01);
+        let representatives: Vec<&str> = beijing
+            .representatives
+            .iter()
+            .map(|a| a.icao.as_str())
+            .collect();
+        assert_eq!(representatives, vec!["ZBAA", "ZBAD", "ZBDC"]);
+        assert_eq!(beijing.min.latitude, 39.51);
+        assert_eq!(beijing.max.longitude, 116.58);
+
+        // At zoom 12 every airport is its own cluster
+        let clusters = cluster_airports(&db, min, max, 12).unwrap();
+        assert_eq!(clusters.len(), 5);
+        assert!(clusters.iter().all(|c| c.count == 1 && c.representatives.len() == 1));
+    }
+
+    #[test]
+    fn test_cluster_waypoints() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'BRAVO', 10.1, 10.1, 'FIX', 0),
+                    ('W2', 'ALPHA', 10.2, 10.2, 'FIX', 0),
+                    ('W3', 'OUTSIDE', 60.0, 60.0, 'FIX', 0);",
+            )
+            .unwrap();
+
+        let clusters =
+            cluster_waypoints(&db, Coordinate::new(0.0, 0.0), Coordinate::new(20.0, 20.0), 4)
+                .unwrap();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].count, 2);
+        assert_eq!(clusters[0].representatives[0].name, "ALPHA");
+    }
+}
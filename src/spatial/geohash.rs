@@ -0,0 +1,161 @@
+use crate::error::{AeroBaseError, Result};
+use crate::models::Coordinate;
+
+/// Precision of the `geohash` columns on airports and waypoints (~150 m cells)
+pub const GEOHASH_PRECISION: usize = 7;
+
+/// Largest cell list `cells_covering` will produce
+const MAX_COVERING_CELLS: usize = 10_000;
+
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Encode a coordinate as a geohash of `precision` characters
+pub fn encode(coord: Coordinate, precision: usize) -> String {
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+
+    for _ in 0..precision {
+        let mut index = 0;
+        for _ in 0..5 {
+            // Bits alternate longitude, latitude, starting with longitude
+            let (range, value) = if even {
+                (&mut lon_range, coord.longitude)
+            } else {
+                (&mut lat_range, coord.latitude)
+            };
+            let mid = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= mid {
+                index |= 1;
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+        hash.push(BASE32[index] as char);
+    }
+    hash
+}
+
+/// South-west and north-east corners of a geohash cell
+pub fn decode_bounds(hash: &str) -> Result<(Coordinate, Coordinate)> {
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut even = true;
+
+    for ch in hash.chars() {
+        let index = BASE32
+            .iter()
+            .position(|&b| b as char == ch)
+            .ok_or_else(|| invalid(hash))?;
+        for bit in (0..5).rev() {
+            let range = if even { &mut lon_range } else { &mut lat_range };
+            let mid = (range.0 + range.1) / 2.0;
+            if index >> bit & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+    }
+    Ok((
+        Coordinate::new(lat_range.0, lon_range.0),
+        Coordinate::new(lat_range.1, lon_range.1),
+    ))
+}
+
+/// Check that `hash` is a non-empty geohash no longer than the stored precision
+pub fn validate(hash: &str) -> Result<()> {
+    if hash.is_empty()
+        || hash.len() > GEOHASH_PRECISION
+        || !hash.bytes().all(|b| BASE32.contains(&b))
+    {
+        return Err(invalid(hash));
+    }
+    Ok(())
+}
+
+/// Cells of `precision` characters covering `min`..`max`
+pub fn cells_covering(min: Coordinate, max: Coordinate, precision: usize) -> Result<Vec<String>> {
+    if precision == 0 || precision > GEOHASH_PRECISION {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "Geohash precision must be between 1 and {}",
+            GEOHASH_PRECISION
+        )));
+    }
+    let (cell_min, cell_max) = decode_bounds(&encode(min, precision))?;
+    let lat_step = cell_max.latitude - cell_min.latitude;
+    let lon_step = cell_max.longitude - cell_min.longitude;
+
+    let rows = ((max.latitude - cell_min.latitude) / lat_step).floor().max(0.0) as usize + 1;
+    let cols = ((max.longitude - cell_min.longitude) / lon_step).floor().max(0.0) as usize + 1;
+    if rows.saturating_mul(cols) > MAX_COVERING_CELLS {
+        return Err(AeroBaseError::InvalidInput(format!(
+            "Area needs {} geohash cells at precision {}; use a coarser precision",
+            rows.saturating_mul(cols),
+            precision
+        )));
+    }
+
+    let mut cells = Vec::with_capacity(rows * cols);
+    for row in 0..rows {
+        for col in 0..cols {
+            // Encode each cell's centre so floating point edges can't skip a cell
+            let center = Coordinate::new(
+                (cell_min.latitude + (row as f64 + 0.5) * lat_step).min(90.0),
+                (cell_min.longitude + (col as f64 + 0.5) * lon_step).min(180.0),
+            );
+            cells.push(encode(center, precision));
+        }
+    }
+    cells.sort();
+    cells.dedup();
+    Ok(cells)
+}
+
+fn invalid(hash: &str) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid geohash {:?}", hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        // Reference value for 57.64911, 10.40744
+        assert_eq!(encode(Coordinate::new(57.64911, 10.40744), 11), "u4pruydqqvj");
+
+        let (min, max) = decode_bounds("wx4g0").unwrap();
+        let center = Coordinate::new(39.9042, 116.4074);
+        assert!(min.latitude <= center.latitude && center.latitude <= max.latitude);
+        assert!(min.longitude <= center.longitude && center.longitude <= max.longitude);
+        assert_eq!(&encode(center, 7)[..5], "wx4g0");
+
+        assert!(decode_bounds("wx4a").is_err());
+        assert!(validate("wx4g0").is_ok());
+        assert!(validate("").is_err());
+        assert!(validate("wx4g0ec1").is_err());
+    }
+
+    #[test]
+    fn test_cells_covering() {
+        let (min, max) = decode_bounds("wx4g").unwrap();
+        // A box inside one cell is covered by that cell alone
+        let inner_min = Coordinate::new(min.latitude + 0.01, min.longitude + 0.01);
+        let inner_max = Coordinate::new(max.latitude - 0.01, max.longitude - 0.01);
+        assert_eq!(cells_covering(inner_min, inner_max, 4).unwrap(), vec!["wx4g"]);
+
+        // The whole cell at the next precision is its 32 children
+        let children = cells_covering(inner_min, inner_max, 5).unwrap();
+        assert_eq!(children.len(), 32);
+        assert!(children.iter().all(|c| c.starts_with("wx4g")));
+
+        let world = cells_covering(Coordinate::new(-90.0, -180.0), Coordinate::new(90.0, 180.0), 1);
+        assert_eq!(world.unwrap().len(), 32);
+        assert!(cells_covering(min, max, 0).is_err());
+        assert!(cells_covering(Coordinate::new(-90.0, -180.0), Coordinate::new(90.0, 180.0), 5).is_err());
+    }
+}
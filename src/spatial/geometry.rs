@@ -39,6 +39,55 @@ pub fn point_in_polygon(point: Coordinate, vertices: &[Coordinate]) -> bool {
     polygon.contains(&pt)
 }
 
+/// Check if the straight segment `from`-`to` touches a polygon, either
+/// crossing its boundary or lying inside it
+///
+/// Like `point_in_polygon`, this works on plain latitude/longitude, which
+/// is close enough for the short legs of an airway network.
+pub fn segment_crosses_polygon(from: Coordinate, to: Coordinate, vertices: &[Coordinate]) -> bool {
+    use geo::{Intersects, Line};
+
+    if vertices.len() < 3 {
+        return false;
+    }
+    let coords: Vec<Coord<f64>> = vertices
+        .iter()
+        .map(|c| Coord {
+            x: c.longitude,
+            y: c.latitude,
+        })
+        .collect();
+    let polygon = Polygon::new(LineString::new(coords), vec![]);
+    let line = Line::new(
+        Coord { x: from.longitude, y: from.latitude },
+        Coord { x: to.longitude, y: to.latitude },
+    );
+    line.intersects(&polygon)
+}
+
+/// Simplify a polyline (Ramer-Douglas-Peucker), keeping both endpoints
+///
+/// `tolerance_deg` is measured in degrees of latitude/longitude, so it suits
+/// screen-space simplification of map geometry rather than navigation.
+pub fn simplify(points: &[Coordinate], tolerance_deg: f64) -> Vec<Coordinate> {
+    use geo::SimplifyIdx;
+
+    if points.len() < 3 || tolerance_deg <= 0.0 {
+        return points.to_vec();
+    }
+    let line: LineString<f64> = points
+        .iter()
+        .map(|c| Coord {
+            x: c.longitude,
+            y: c.latitude,
+        })
+        .collect();
+    line.simplify_idx(&tolerance_deg)
+        .into_iter()
+        .map(|i| points[i])
+        .collect()
+}
+
 /// Calculate great circle distance in nautical miles
 pub fn great_circle_distance(from: Coordinate, to: Coordinate) -> f64 {
     from.distance_to(&to)
@@ -65,9 +114,153 @@ pub fn destination_point(
     Coordinate::new(dest.y(), dest.x())
 }
 
+/// Mean earth radius in nautical miles
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// Signed cross-track distance in nautical miles from the great circle
+/// through `from` and `to` (positive right of course)
+pub fn cross_track_distance(point: Coordinate, from: Coordinate, to: Coordinate) -> f64 {
+    let d13 = from.distance_to(&point) / EARTH_RADIUS_NM;
+    let delta = (from.bearing_to(&point) - from.bearing_to(&to)).to_radians();
+    (d13.sin() * delta.sin()).asin() * EARTH_RADIUS_NM
+}
+
+/// Along-track distance in nautical miles from `from` to the foot of the
+/// perpendicular from `point` (negative behind `from`)
+pub fn along_track_distance(point: Coordinate, from: Coordinate, to: Coordinate) -> f64 {
+    let d13 = from.distance_to(&point) / EARTH_RADIUS_NM;
+    let delta = (from.bearing_to(&point) - from.bearing_to(&to)).to_radians();
+    let xt = cross_track_distance(point, from, to) / EARTH_RADIUS_NM;
+    let along = (d13.cos() / xt.cos()).clamp(-1.0, 1.0).acos() * EARTH_RADIUS_NM;
+    along * delta.cos().signum()
+}
+
+/// Distance in nautical miles from a point to the leg `from`-`to`
+///
+/// Points beyond either end are measured to that endpoint.
+pub fn distance_to_leg(point: Coordinate, from: Coordinate, to: Coordinate) -> f64 {
+    let along = along_track_distance(point, from, to);
+    if along <= 0.0 {
+        point.distance_to(&from)
+    } else if along >= from.distance_to(&to) {
+        point.distance_to(&to)
+    } else {
+        cross_track_distance(point, from, to).abs()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    /// Positions away from the poles, where bearings are well defined
+    fn coordinate() -> impl Strategy<Value = Coordinate> {
+        (-80.0..80.0f64, -180.0..180.0f64).prop_map(|(lat, lon)| Coordinate::new(lat, lon))
+    }
+
+    /// Difference between two bearings in degrees, in [0, 180]
+    fn bearing_difference(a: f64, b: f64) -> f64 {
+        let diff = (a - b).rem_euclid(360.0);
+        diff.min(360.0 - diff)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_distance_symmetric(a in coordinate(), b in coordinate()) {
+            let ab = great_circle_distance(a, b);
+            prop_assert!(ab >= 0.0);
+            prop_assert!((ab - great_circle_distance(b, a)).abs() < 1e-6);
+            prop_assert!(great_circle_distance(a, a) < 1e-9);
+            // Never more than half the circumference
+            prop_assert!(ab <= std::f64::consts::PI * EARTH_RADIUS_NM + 1.0);
+        }
+
+        #[test]
+        fn prop_triangle_inequality(a in coordinate(), b in coordinate(), c in coordinate()) {
+            let direct = great_circle_distance(a, c);
+            prop_assert!(direct <= great_circle_distance(a, b) + great_circle_distance(b, c) + 1e-6);
+        }
+
+        #[test]
+        fn prop_destination_round_trip(
+            start in coordinate(),
+            distance in 1.0..3000.0f64,
+            bearing in 0.0..360.0f64,
+        ) {
+            let dest = destination_point(start, distance, bearing);
+            prop_assert!((-90.0..=90.0).contains(&dest.latitude));
+            prop_assert!((great_circle_distance(start, dest) - distance).abs() < 0.01);
+            prop_assert!(bearing_difference(initial_bearing(start, dest), bearing) < 0.01);
+
+            // Flying the reverse bearing from the destination comes back
+            let reverse = initial_bearing(dest, start);
+            let back = destination_point(dest, distance, reverse);
+            prop_assert!(great_circle_distance(back, start) < 0.01);
+        }
+
+        #[test]
+        fn prop_leg_distances(
+            from in coordinate(),
+            leg_length in 1.0..500.0f64,
+            leg_bearing in 0.0..360.0f64,
+            offset in 0.0..500.0f64,
+            offset_bearing in 0.0..360.0f64,
+        ) {
+            // Route-sized legs with the point in the same part of the world
+            let to = destination_point(from, leg_length, leg_bearing);
+            let point = destination_point(from, offset, offset_bearing);
+            let cross = cross_track_distance(point, from, to);
+            prop_assert!(cross.abs() <= great_circle_distance(point, from) + 1e-6);
+            let leg = distance_to_leg(point, from, to);
+            prop_assert!(leg >= 0.0);
+            prop_assert!(leg <= great_circle_distance(point, from).min(great_circle_distance(point, to)) + 1e-6);
+        }
+
+        #[test]
+        fn prop_rectangle_containment(
+            lat in -60.0..60.0f64,
+            lon in -170.0..170.0f64,
+            height in 0.01..10.0f64,
+            width in 0.01..10.0f64,
+            u in 0.0..1.0f64,
+            v in 0.0..1.0f64,
+            closed in any::<bool>(),
+        ) {
+            let mut ring = vec![
+                Coordinate::new(lat, lon),
+                Coordinate::new(lat, lon + width),
+                Coordinate::new(lat + height, lon + width),
+                Coordinate::new(lat + height, lon),
+            ];
+            if closed {
+                ring.push(ring[0]);
+            }
+            let inside = Coordinate::new(lat + height * (0.01 + 0.98 * u), lon + width * (0.01 + 0.98 * v));
+            prop_assert!(point_in_polygon(inside, &ring));
+
+            let outside = Coordinate::new(lat + height * (1.01 + u), lon + width * v);
+            prop_assert!(!point_in_polygon(outside, &ring));
+
+            // Vertices are on the boundary, which doesn't count as inside
+            for vertex in &ring {
+                prop_assert!(!point_in_polygon(*vertex, &ring));
+            }
+            // Fewer than three vertices never encloses anything
+            prop_assert!(!point_in_polygon(inside, &ring[..2]));
+        }
+
+        #[test]
+        fn prop_simplify_keeps_endpoints(
+            points in prop::collection::vec(coordinate(), 0..40),
+            tolerance in 0.0..5.0f64,
+        ) {
+            let simplified = simplify(&points, tolerance);
+            prop_assert!(simplified.len() <= points.len());
+            prop_assert_eq!(simplified.first(), points.first());
+            prop_assert_eq!(simplified.last(), points.last());
+        }
+    }
 
     #[test]
     fn test_bounding_box() {
@@ -94,6 +287,39 @@ mod tests {
         assert!(!point_in_polygon(Coordinate::new(2.0, 2.0), &polygon));
     }
 
+    #[test]
+    fn test_segment_crosses_polygon() {
+        let polygon = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 1.0),
+            Coordinate::new(1.0, 1.0),
+            Coordinate::new(1.0, 0.0),
+        ];
+
+        // Straight through, with both ends outside
+        assert!(segment_crosses_polygon(
+            Coordinate::new(0.5, -1.0),
+            Coordinate::new(0.5, 2.0),
+            &polygon
+        ));
+        // Wholly inside
+        assert!(segment_crosses_polygon(
+            Coordinate::new(0.2, 0.2),
+            Coordinate::new(0.8, 0.8),
+            &polygon
+        ));
+        assert!(!segment_crosses_polygon(
+            Coordinate::new(2.0, -1.0),
+            Coordinate::new(2.0, 2.0),
+            &polygon
+        ));
+        assert!(!segment_crosses_polygon(
+            Coordinate::new(0.5, -1.0),
+            Coordinate::new(0.5, 2.0),
+            &polygon[..2]
+        ));
+    }
+
     #[test]
     fn test_destination_point() {
         let start = Coordinate::new(0.0, 0.0);
@@ -103,4 +329,34 @@ mod tests {
         assert!(dest.latitude > 0.9 && dest.latitude < 1.1);
         assert!(dest.longitude.abs() < 0.1);
     }
+
+    #[test]
+    fn test_cross_track_distance() {
+        let from = Coordinate::new(0.0, 0.0);
+        let to = Coordinate::new(2.0, 0.0);
+
+        // 0.1 degree east of a northbound leg is ~6 nm right of course
+        let right = Coordinate::new(1.0, 0.1);
+        assert!((cross_track_distance(right, from, to) - 6.0).abs() < 0.1);
+        assert!((cross_track_distance(Coordinate::new(1.0, -0.1), from, to) + 6.0).abs() < 0.1);
+        assert!((along_track_distance(right, from, to) - 60.0).abs() < 0.1);
+
+        let behind = Coordinate::new(-0.5, 0.0);
+        assert!(along_track_distance(behind, from, to) < 0.0);
+        assert!((distance_to_leg(behind, from, to) - 30.0).abs() < 0.1);
+        assert!((distance_to_leg(right, from, to) - 6.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_simplify() {
+        let points = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.001, 0.5),
+            Coordinate::new(0.0, 1.0),
+            Coordinate::new(0.5, 1.0),
+        ];
+        let simplified = simplify(&points, 0.01);
+        assert_eq!(simplified, vec![points[0], points[2], points[3]]);
+        assert_eq!(simplify(&points, 0.0), points);
+    }
 }
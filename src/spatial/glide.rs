@@ -0,0 +1,195 @@
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::models::airport::Airport;
+use crate::models::runway::Wind;
+use crate::models::Coordinate;
+use crate::spatial::{geometry, query};
+use serde::{Deserialize, Serialize};
+
+const FEET_PER_NM: f64 = 6076.12;
+
+/// Vertices used to approximate the footprint
+const FOOTPRINT_SEGMENTS: usize = 72;
+
+/// Engine-out glide parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlideParameters {
+    /// Height above the terrain, in feet
+    pub altitude_agl: i32,
+    /// Distance gained per unit of height lost (e.g. 9.0 for 9:1)
+    pub glide_ratio: f64,
+    /// Best glide true airspeed, in knots
+    pub glide_speed_kt: f64,
+    pub wind: Wind,
+}
+
+impl GlideParameters {
+    /// Still-air glide distance
+    pub fn still_air_range_nm(&self) -> f64 {
+        self.altitude_agl as f64 * self.glide_ratio / FEET_PER_NM
+    }
+
+    /// Time until reaching the ground at best glide
+    pub fn time_aloft_hours(&self) -> f64 {
+        self.still_air_range_nm() / self.glide_speed_kt
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.altitude_agl < 0
+            || self.glide_ratio.is_nan()
+            || self.glide_ratio <= 0.0
+            || self.glide_speed_kt.is_nan()
+            || self.glide_speed_kt <= 0.0
+        {
+            return Err(AeroBaseError::InvalidInput(
+                "Glide altitude must be non-negative, glide ratio and speed positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Ground area reachable in a glide
+///
+/// In a steady wind every still-air glide ends displaced downwind by the
+/// same amount, so the footprint is the still-air circle shifted downwind.
+/// Terrain is treated as level with the ground below the aircraft.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlideFootprint {
+    pub center: Coordinate,
+    pub radius_nm: f64,
+    pub boundary: Vec<Coordinate>,
+}
+
+impl GlideFootprint {
+    pub fn contains(&self, point: Coordinate) -> bool {
+        self.center.distance_to(&point) <= self.radius_nm
+    }
+
+    /// Height left on arrival over `point`, in feet; negative when out of reach
+    pub fn arrival_height(&self, point: Coordinate, params: &GlideParameters) -> i32 {
+        let used = self.center.distance_to(&point) / self.radius_nm;
+        (params.altitude_agl as f64 * (1.0 - used)).round() as i32
+    }
+}
+
+/// Airport within gliding range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReachableAirport {
+    pub airport: Airport,
+    pub distance_nm: f64,
+    pub bearing: f64,
+    pub arrival_height: i32, // 英尺
+}
+
+/// Compute the glide footprint from `position`
+pub fn footprint(position: Coordinate, params: &GlideParameters) -> Result<GlideFootprint> {
+    params.validate()?;
+
+    let radius_nm = params.still_air_range_nm();
+    let drift_nm = params.wind.speed * params.time_aloft_hours();
+    let downwind = (params.wind.direction + 180.0).rem_euclid(360.0);
+    let center = geometry::destination_point(position, drift_nm, downwind);
+
+    let boundary = (0..FOOTPRINT_SEGMENTS)
+        .map(|i| {
+            let bearing = i as f64 * 360.0 / FOOTPRINT_SEGMENTS as f64;
+            geometry::destination_point(center, radius_nm, bearing)
+        })
+        .collect();
+
+    Ok(GlideFootprint {
+        center,
+        radius_nm,
+        boundary,
+    })
+}
+
+/// Airports inside the glide footprint, highest arrival height first
+pub fn reachable_airports(
+    db: &Database,
+    position: Coordinate,
+    params: &GlideParameters,
+) -> Result<Vec<ReachableAirport>> {
+    let footprint = footprint(position, params)?;
+
+    let mut reachable: Vec<ReachableAirport> =
+        query::find_airports_within(db, footprint.center, footprint.radius_nm)?
+            .into_iter()
+            .map(|airport| ReachableAirport {
+                distance_nm: position.distance_to(&airport.coordinate),
+                bearing: position.bearing_to(&airport.coordinate).rem_euclid(360.0),
+                arrival_height: footprint.arrival_height(airport.coordinate, params),
+                airport,
+            })
+            .collect();
+    reachable.sort_by_key(|r| std::cmp::Reverse(r.arrival_height));
+    Ok(reachable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn params(wind: Wind) -> GlideParameters {
+        // 6076 ft at 10:1 glides 10 nm in still air, six minutes at 100 kt
+        GlideParameters {
+            altitude_agl: 6076,
+            glide_ratio: 10.0,
+            glide_speed_kt: 100.0,
+            wind,
+        }
+    }
+
+    #[test]
+    fn test_footprint_drifts_downwind() {
+        let position = Coordinate::new(30.0, 120.0);
+        let calm = footprint(position, &params(Wind::new(0.0, 0.0))).unwrap();
+        assert!((calm.radius_nm - 10.0).abs() < 0.01);
+        assert!(calm.center.distance_to(&position) < 0.001);
+        assert_eq!(calm.boundary.len(), FOOTPRINT_SEGMENTS);
+
+        // 30 kt from the north for six minutes pushes the circle 3 nm south
+        let windy = footprint(position, &params(Wind::new(0.0, 30.0))).unwrap();
+        assert!((windy.center.distance_to(&position) - 3.0).abs() < 0.01);
+        assert!(windy.center.latitude < position.latitude);
+        assert!(windy.contains(geometry::destination_point(position, 12.5, 180.0)));
+        assert!(!windy.contains(geometry::destination_point(position, 7.5, 0.0)));
+
+        let mut bad = params(Wind::new(0.0, 0.0));
+        bad.glide_ratio = 0.0;
+        assert!(footprint(position, &bad).is_err());
+    }
+
+    #[test]
+    fn test_reachable_airports() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'NEAR', 'Near', 29.95, 120.0, 0),
+                    ('A2', 'SOUT', 'South', 29.8, 120.0, 0),
+                    ('A3', 'NORT', 'North', 30.15, 120.0, 0);",
+            )
+            .unwrap();
+
+        let position = Coordinate::new(30.0, 120.0);
+        let reachable = reachable_airports(&db, position, &params(Wind::new(0.0, 30.0))).unwrap();
+        let icaos: Vec<&str> = reachable.iter().map(|r| r.airport.icao.as_str()).collect();
+        // NORT is 9 nm upwind, beyond the 7 nm the headwind allows
+        assert_eq!(icaos, vec!["NEAR", "SOUT"]);
+        assert!((reachable[0].bearing - 180.0).abs() < 0.1);
+        assert!(reachable[1].arrival_height > 0);
+    }
+}
@@ -1,21 +1,56 @@
+pub mod cluster;
+pub mod geohash;
 pub mod geometry;
+pub mod glide;
 pub mod index;
 pub mod query;
+pub mod render;
 
 use crate::db::Database;
 use crate::error::Result;
-use crate::models::{airport::Airport, waypoint::Waypoint, Coordinate};
+use crate::history::DEFAULT_PROFILE;
+use crate::models::{
+    airport::Airport, hazard::Hazard, uas_zone::UasZone, waypoint::Waypoint, Coordinate,
+};
+use crate::redaction::Redaction;
 use std::sync::Arc;
 
 /// 空间查询引擎
+///
+/// 机场和空域结果按当前配置文件的权限过滤（见 `redaction`）。
 pub struct SpatialEngine {
     db: Arc<Database>,
+    profile_id: String,
 }
 
 impl SpatialEngine {
-    /// 创建新的空间查询引擎
+    /// 创建使用默认配置文件的空间查询引擎
     pub fn new(db: Arc<Database>) -> Result<Self> {
-        Ok(Self { db })
+        Ok(Self {
+            db,
+            profile_id: DEFAULT_PROFILE.to_string(),
+        })
+    }
+
+    /// 切换到指定配置文件（按其权限隐藏数据）
+    pub fn for_profile(&self, profile_id: &str) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            profile_id: profile_id.to_string(),
+        }
+    }
+
+    fn redaction(&self) -> Result<Redaction> {
+        let conn = self.db.get_conn()?;
+        Redaction::load(&conn, &self.profile_id)
+    }
+
+    fn visible_airports(&self, airports: Vec<Airport>) -> Result<Vec<Airport>> {
+        let redaction = self.redaction()?;
+        Ok(airports
+            .into_iter()
+            .filter(|a| !redaction.hides_airport(&a.id))
+            .collect())
     }
 
     /// 查找半径范围内的航路点
@@ -33,7 +68,17 @@ impl SpatialEngine {
         center: Coordinate,
         radius_nm: f64,
     ) -> Result<Vec<Airport>> {
-        query::find_airports_within(&self.db, center, radius_nm)
+        self.visible_airports(query::find_airports_within(&self.db, center, radius_nm)?)
+    }
+
+    /// 查找位于指定 geohash 网格内的机场
+    pub fn find_airports_in_cells(&self, cells: &[String]) -> Result<Vec<Airport>> {
+        self.visible_airports(query::find_airports_in_cells(&self.db, cells)?)
+    }
+
+    /// 查找位于指定 geohash 网格内的航路点
+    pub fn find_waypoints_in_cells(&self, cells: &[String]) -> Result<Vec<Waypoint>> {
+        query::find_waypoints_in_cells(&self.db, cells)
     }
 
     /// 查找最近的航路点
@@ -43,7 +88,98 @@ impl SpatialEngine {
 
     /// 查找最近的机场
     pub fn find_nearest_airport(&self, coord: Coordinate) -> Result<Option<Airport>> {
-        query::find_nearest_airport(&self.db, coord)
+        let airports = self.find_airports_within(coord, query::NEAREST_SEARCH_RADIUS_NM)?;
+        Ok(airports
+            .into_iter()
+            .min_by(|a, b| a.distance_from(coord).total_cmp(&b.distance_from(coord))))
+    }
+
+    /// 按缩放级别聚合边界框内的机场
+    pub fn cluster_airports(
+        &self,
+        min: Coordinate,
+        max: Coordinate,
+        zoom: u8,
+    ) -> Result<Vec<cluster::Cluster<Airport>>> {
+        let airports = self.visible_airports(query::find_airports_in(&self.db, min, max)?)?;
+        Ok(cluster::cluster_airport_list(airports, zoom))
+    }
+
+    /// 按缩放级别聚合边界框内的航路点
+    pub fn cluster_waypoints(
+        &self,
+        min: Coordinate,
+        max: Coordinate,
+        zoom: u8,
+    ) -> Result<Vec<cluster::Cluster<Waypoint>>> {
+        cluster::cluster_waypoints(&self.db, min, max, zoom)
+    }
+
+    /// 获取边界框内的航路中心线（按缩放级别简化）
+    pub fn airway_polylines(
+        &self,
+        min: Coordinate,
+        max: Coordinate,
+        zoom: u8,
+    ) -> Result<Vec<render::AirwayPolyline>> {
+        render::airway_polylines(&self.db, min, max, zoom)
+    }
+
+    /// 获取边界框内的空域轮廓（按缩放级别简化）
+    pub fn airspace_outlines(
+        &self,
+        min: Coordinate,
+        max: Coordinate,
+        zoom: u8,
+    ) -> Result<Vec<render::AirspaceOutline>> {
+        let redaction = self.redaction()?;
+        Ok(render::airspace_outlines(&self.db, min, max, zoom)?
+            .into_iter()
+            .filter(|outline| !redaction.hides_airspace(&outline.airspace_id))
+            .collect())
+    }
+
+    /// 边界框内的空域多边形，GeoJSON FeatureCollection 格式（不简化）
+    pub fn airspaces_geojson(&self, min: Coordinate, max: Coordinate) -> Result<serde_json::Value> {
+        let redaction = self.redaction()?;
+        let airspaces: Vec<_> = query::find_airspaces_in(&self.db, min, max)?
+            .into_iter()
+            .filter(|(airspace, _)| !redaction.hides_airspace(&airspace.id))
+            .collect();
+        Ok(crate::export::geojson::airspace_features(&airspaces))
+    }
+
+    /// 边界框内的航路航段，GeoJSON FeatureCollection 格式
+    pub fn airways_geojson(&self, min: Coordinate, max: Coordinate) -> Result<serde_json::Value> {
+        crate::export::geojson::airways(&self.db, min, max)
+    }
+
+    /// 计算发动机失效后可滑翔到达的机场
+    pub fn find_reachable_airports(
+        &self,
+        position: Coordinate,
+        params: &glide::GlideParameters,
+    ) -> Result<Vec<glide::ReachableAirport>> {
+        let redaction = self.redaction()?;
+        Ok(glide::reachable_airports(&self.db, position, params)?
+            .into_iter()
+            .filter(|r| !redaction.hides_airport(&r.airport.id))
+            .collect())
+    }
+
+    /// 查找包含该坐标的无人机地理区域
+    pub fn find_uas_zones_at(&self, coord: Coordinate) -> Result<Vec<UasZone>> {
+        query::find_uas_zones_at(&self.db, coord)
+    }
+
+    /// 查找与边界框相交的无人机地理区域
+    pub fn find_uas_zones_in(&self, min: Coordinate, max: Coordinate) -> Result<Vec<UasZone>> {
+        query::find_uas_zones_in(&self.db, min, max)
+    }
+
+    /// 查找与边界框相交且在指定时刻生效的动态危险区
+    pub fn find_hazards_at_time(&self, min: Coordinate, max: Coordinate, at: i64) -> Result<Vec<Hazard>> {
+        query::find_hazards_in(&self.db, min, max, at, at)
     }
 }
 
@@ -60,6 +196,7 @@ mod tests {
             db_path: temp_file.path().to_path_buf(),
             enable_wal: false,
             pool_size: 1,
+            ..Default::default()
         };
 
         let db = Arc::new(Database::new(&config).unwrap());
@@ -68,4 +205,59 @@ mod tests {
         let engine = SpatialEngine::new(db);
         assert!(engine.is_ok());
     }
+
+    #[test]
+    fn test_redacted_for_profile() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('AP1', 'ZBNY', 'Nanyuan', 39.78, 116.39, 0),
+                    ('AP2', 'ZBAA', 'Capital', 40.08, 116.58, 0);
+                 INSERT INTO airspaces (id, name, type, created_at)
+                    VALUES ('AS1', 'R1', 'RESTRICTED', 0);
+                 INSERT INTO airspace_boundaries (id, airspace_id, latitude, longitude, sequence) VALUES
+                    ('B1', 'AS1', 39.7, 116.3, 1),
+                    ('B2', 'AS1', 39.9, 116.3, 2),
+                    ('B3', 'AS1', 39.9, 116.5, 3);
+                 INSERT INTO restricted_items (item_type, item_id, category) VALUES
+                    ('AIRPORT', 'AP1', 'MILITARY'),
+                    ('AIRSPACE', 'AS1', 'MILITARY');
+                 INSERT INTO profile_entitlements (profile_id, category) VALUES ('ops', 'MILITARY');",
+            )
+            .unwrap();
+
+        let center = Coordinate::new(39.78, 116.39);
+        let (min, max) = (Coordinate::new(39.0, 116.0), Coordinate::new(41.0, 117.0));
+        let public = SpatialEngine::new(Arc::clone(&db)).unwrap();
+        let ops = public.for_profile("ops");
+
+        let ids = |airports: Vec<Airport>| -> Vec<String> {
+            airports.into_iter().map(|a| a.id).collect()
+        };
+        assert_eq!(ids(public.find_airports_within(center, 50.0).unwrap()), vec!["AP2"]);
+        assert_eq!(ids(ops.find_airports_within(center, 50.0).unwrap()).len(), 2);
+        // The nearest visible airport, not None
+        assert_eq!(public.find_nearest_airport(center).unwrap().unwrap().id, "AP2");
+        assert_eq!(ops.find_nearest_airport(center).unwrap().unwrap().id, "AP1");
+
+        let clustered: usize = public
+            .cluster_airports(min, max, 1)
+            .unwrap()
+            .iter()
+            .map(|c| c.count)
+            .sum();
+        assert_eq!(clustered, 1);
+        assert!(public.airspace_outlines(min, max, 8).unwrap().is_empty());
+        assert_eq!(ops.airspace_outlines(min, max, 8).unwrap().len(), 1);
+    }
 }
@@ -1,9 +1,17 @@
+use crate::db::query::{airport_from_row, waypoint_from_row, AIRPORT_COLUMNS, WAYPOINT_COLUMNS};
 use crate::db::Database;
 use crate::error::Result;
 use crate::models::{
-    airport::Airport, waypoint::{Waypoint, WaypointType}, Coordinate,
+    airport::Airport,
+    airspace::{Airspace, AirspaceClass, AirspaceType, LevelScheme},
+    hazard::{Hazard, HazardKind},
+    obstacle::{Obstacle, TerrainCell},
+    uas_zone::{UasRestriction, UasZone},
+    waypoint::Waypoint,
+    Coordinate,
 };
-use crate::spatial::geometry;
+use crate::spatial::{geohash, geometry};
+use rusqlite::Connection;
 
 /// Find waypoints within a radius
 pub fn find_waypoints_within(
@@ -11,34 +19,35 @@ pub fn find_waypoints_within(
     center: Coordinate,
     radius_nm: f64,
 ) -> Result<Vec<Waypoint>> {
-    let conn = db.get_conn()?;
-    
     // Get bounding box for initial filtering
     let (min, max) = geometry::bounding_box(center, radius_nm);
+
+    let waypoints = find_waypoints_in(db, min, max)?
+        .into_iter()
+        .filter(|wp| wp.distance_from(center) <= radius_nm)
+        .collect();
+
+    Ok(waypoints)
+}
+
+/// Find waypoints inside `min`..`max`
+pub fn find_waypoints_in(db: &Database, min: Coordinate, max: Coordinate) -> Result<Vec<Waypoint>> {
+    let conn = db.get_conn()?;
     
-    let mut stmt = conn.prepare(
-        "SELECT id, name, latitude, longitude, region, type, created_at
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM waypoints
          WHERE latitude BETWEEN ?1 AND ?2
            AND longitude BETWEEN ?3 AND ?4",
-    )?;
+        WAYPOINT_COLUMNS
+    ))?;
     
     let waypoints: Vec<Waypoint> = stmt
         .query_map(
             rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude],
-            |row| {
-                Ok(Waypoint {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    coordinate: Coordinate::new(row.get(2)?, row.get(3)?),
-                    region: row.get(4)?,
-                    waypoint_type: WaypointType::from_str(&row.get::<_, String>(5)?),
-                    created_at: row.get(6)?,
-                })
-            },
+            waypoint_from_row,
         )?
         .filter_map(|wp| wp.ok())
-        .filter(|wp| wp.distance_from(center) <= radius_nm)
         .collect();
     
     Ok(waypoints)
@@ -50,45 +59,139 @@ pub fn find_airports_within(
     center: Coordinate,
     radius_nm: f64,
 ) -> Result<Vec<Airport>> {
-    let conn = db.get_conn()?;
-    
     let (min, max) = geometry::bounding_box(center, radius_nm);
+
+    let airports = find_airports_in(db, min, max)?
+        .into_iter()
+        .filter(|ap| ap.distance_from(center) <= radius_nm)
+        .collect();
+
+    Ok(airports)
+}
+
+/// Find airports inside `min`..`max`
+pub fn find_airports_in(db: &Database, min: Coordinate, max: Coordinate) -> Result<Vec<Airport>> {
+    let conn = db.get_conn()?;
     
-    let mut stmt = conn.prepare(
-        "SELECT id, icao, iata, name, latitude, longitude, elevation, country, region, created_at
-         FROM airports
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM airports
          WHERE latitude BETWEEN ?1 AND ?2
            AND longitude BETWEEN ?3 AND ?4",
-    )?;
+        AIRPORT_COLUMNS
+    ))?;
     
     let airports: Vec<Airport> = stmt
         .query_map(
             rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude],
-            |row| {
-                Ok(Airport {
-                    id: row.get(0)?,
-                    icao: row.get(1)?,
-                    iata: row.get(2)?,
-                    name: row.get(3)?,
-                    coordinate: Coordinate::new(row.get(4)?, row.get(5)?),
-                    elevation: row.get(6)?,
-                    country: row.get(7)?,
-                    region: row.get(8)?,
-                    created_at: row.get(9)?,
-                })
-            },
+            airport_from_row,
         )?
         .filter_map(|ap| ap.ok())
-        .filter(|ap| ap.distance_from(center) <= radius_nm)
         .collect();
     
     Ok(airports)
 }
 
+/// `WHERE` clause and bounds matching rows whose geohash falls in any of `cells`
+///
+/// Each cell becomes a range scan on the geohash index: every hash with the
+/// cell as prefix sorts between the cell and the cell followed by `{`, the
+/// character after `z`.
+fn cell_ranges(cells: &[String]) -> Result<(String, Vec<String>)> {
+    let mut cells: Vec<&str> = cells.iter().map(String::as_str).collect();
+    cells.sort_unstable();
+    cells.dedup();
+
+    let mut clauses = Vec::with_capacity(cells.len());
+    let mut bounds = Vec::with_capacity(cells.len() * 2);
+    for cell in cells {
+        geohash::validate(cell)?;
+        clauses.push(format!(
+            "(geohash >= ?{} AND geohash < ?{})",
+            bounds.len() + 1,
+            bounds.len() + 2
+        ));
+        bounds.push(cell.to_string());
+        bounds.push(format!("{}{{", cell));
+    }
+    Ok((clauses.join(" OR "), bounds))
+}
+
+/// Recompute `geohash` for airports and waypoints written without it (by
+/// migrations, deltas or raw SQL); returns the number of rows changed
+pub(crate) fn refresh_geohashes(conn: &Connection) -> Result<usize> {
+    let mut changed = 0;
+    for table in ["airports", "waypoints"] {
+        let rows = conn
+            .prepare(&format!("SELECT id, latitude, longitude, geohash FROM {}", table))?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    Coordinate::new(row.get(1)?, row.get(2)?),
+                    row.get(3)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<(String, Coordinate, Option<String>)>, _>>()?;
+        let mut stmt = conn.prepare(&format!("UPDATE {} SET geohash = ?2 WHERE id = ?1", table))?;
+        for (id, coordinate, stored) in rows {
+            let hash = geohash::encode(coordinate, geohash::GEOHASH_PRECISION);
+            if stored.as_deref() != Some(hash.as_str()) {
+                changed += stmt.execute([&id, &hash])?;
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// Find airports inside any of the given geohash cells
+pub fn find_airports_in_cells(db: &Database, cells: &[String]) -> Result<Vec<Airport>> {
+    if cells.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (filter, bounds) = cell_ranges(cells)?;
+    let conn = db.get_conn()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM airports
+         WHERE {}
+         ORDER BY geohash",
+        AIRPORT_COLUMNS, filter
+    ))?;
+    let airports = stmt
+        .query_map(rusqlite::params_from_iter(bounds), airport_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(airports)
+}
+
+/// Find waypoints inside any of the given geohash cells
+pub fn find_waypoints_in_cells(db: &Database, cells: &[String]) -> Result<Vec<Waypoint>> {
+    if cells.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (filter, bounds) = cell_ranges(cells)?;
+    let conn = db.get_conn()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM waypoints
+         WHERE {}
+         ORDER BY geohash",
+        WAYPOINT_COLUMNS, filter
+    ))?;
+    let waypoints = stmt
+        .query_map(rusqlite::params_from_iter(bounds), waypoint_from_row)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(waypoints)
+}
+
+/// Radius searched by the nearest-point lookups
+pub const NEAREST_SEARCH_RADIUS_NM: f64 = 500.0;
+
 /// Find nearest waypoint
 pub fn find_nearest_waypoint(db: &Database, coord: Coordinate) -> Result<Option<Waypoint>> {
     // Search within 500 nm and find the closest
-    let waypoints = find_waypoints_within(db, coord, 500.0)?;
+    let waypoints = find_waypoints_within(db, coord, NEAREST_SEARCH_RADIUS_NM)?;
     
     let nearest = waypoints
         .into_iter()
@@ -103,7 +206,7 @@ pub fn find_nearest_waypoint(db: &Database, coord: Coordinate) -> Result<Option<
 
 /// Find nearest airport
 pub fn find_nearest_airport(db: &Database, coord: Coordinate) -> Result<Option<Airport>> {
-    let airports = find_airports_within(db, coord, 500.0)?;
+    let airports = find_airports_within(db, coord, NEAREST_SEARCH_RADIUS_NM)?;
     
     let nearest = airports
         .into_iter()
@@ -116,6 +219,235 @@ pub fn find_nearest_airport(db: &Database, coord: Coordinate) -> Result<Option<A
     Ok(nearest)
 }
 
+/// Find UAS zones whose bounding box overlaps `min`..`max`
+pub fn find_uas_zones_in(db: &Database, min: Coordinate, max: Coordinate) -> Result<Vec<UasZone>> {
+    let conn = db.get_conn()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, name, country, restriction, reasons, lower_limit, upper_limit,
+                vertical_reference, message, created_at
+         FROM uas_zones
+         WHERE max_latitude >= ?1 AND min_latitude <= ?2
+           AND max_longitude >= ?3 AND min_longitude <= ?4
+         ORDER BY id",
+    )?;
+    let mut zones: Vec<UasZone> = stmt
+        .query_map(
+            rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude],
+            |row| {
+                let reasons: Option<String> = row.get(4)?;
+                Ok(UasZone {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    country: row.get(2)?,
+                    restriction: UasRestriction::from_str(&row.get::<_, String>(3)?),
+                    reasons: reasons
+                        .filter(|r| !r.is_empty())
+                        .map(|r| r.split(',').map(str::to_string).collect())
+                        .unwrap_or_default(),
+                    lower_limit: row.get(5)?,
+                    upper_limit: row.get(6)?,
+                    vertical_reference: row.get(7)?,
+                    message: row.get(8)?,
+                    boundary: Vec::new(),
+                    created_at: row.get(9)?,
+                })
+            },
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut boundary = conn.prepare(
+        "SELECT latitude, longitude FROM uas_zone_boundaries
+         WHERE zone_id = ?1 ORDER BY sequence",
+    )?;
+    for zone in zones.iter_mut() {
+        zone.boundary = boundary
+            .query_map([&zone.id], |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+    }
+
+    Ok(zones)
+}
+
+/// Find UAS zones containing a point
+pub fn find_uas_zones_at(db: &Database, point: Coordinate) -> Result<Vec<UasZone>> {
+    let zones = find_uas_zones_in(db, point, point)?
+        .into_iter()
+        .filter(|zone| zone.contains(point))
+        .collect();
+    Ok(zones)
+}
+
+/// Find dynamic hazards overlapping `min`..`max` and active at some time in
+/// `from..=to`
+pub fn find_hazards_in(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+    from: i64,
+    to: i64,
+) -> Result<Vec<Hazard>> {
+    let conn = db.get_conn()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, name, lower_limit, upper_limit, valid_from, valid_to, message
+         FROM hazards
+         WHERE max_latitude >= ?1 AND min_latitude <= ?2
+           AND max_longitude >= ?3 AND min_longitude <= ?4
+           AND valid_from <= ?6 AND (valid_to IS NULL OR valid_to >= ?5)
+         ORDER BY id",
+    )?;
+    let mut hazards: Vec<Hazard> = stmt
+        .query_map(
+            rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude, from, to],
+            |row| {
+                Ok(Hazard {
+                    id: row.get(0)?,
+                    kind: HazardKind::from_str(&row.get::<_, String>(1)?),
+                    name: row.get(2)?,
+                    lower_limit: row.get(3)?,
+                    upper_limit: row.get(4)?,
+                    valid_from: row.get(5)?,
+                    valid_to: row.get(6)?,
+                    message: row.get(7)?,
+                    boundary: Vec::new(),
+                })
+            },
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut boundary = conn.prepare(
+        "SELECT latitude, longitude FROM hazard_boundaries
+         WHERE hazard_id = ?1 ORDER BY sequence",
+    )?;
+    for hazard in hazards.iter_mut() {
+        hazard.boundary = boundary
+            .query_map([&hazard.id], |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+    }
+
+    Ok(hazards)
+}
+
+/// Find obstacles inside `min`..`max`
+pub fn find_obstacles_in(db: &Database, min: Coordinate, max: Coordinate) -> Result<Vec<Obstacle>> {
+    let conn = db.get_conn()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, name, type, latitude, longitude, elevation, height, lighted, created_at
+         FROM obstacles
+         WHERE latitude BETWEEN ?1 AND ?2
+           AND longitude BETWEEN ?3 AND ?4",
+    )?;
+    let obstacles = stmt
+        .query_map(
+            rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude],
+            |row| {
+                Ok(Obstacle {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    obstacle_type: row.get(2)?,
+                    coordinate: Coordinate::new(row.get(3)?, row.get(4)?),
+                    elevation: row.get(5)?,
+                    height: row.get(6)?,
+                    lighted: row.get(7)?,
+                    created_at: row.get(8)?,
+                })
+            },
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(obstacles)
+}
+
+/// Find terrain cells covering `min`..`max`
+pub fn find_terrain_cells_in(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+) -> Result<Vec<TerrainCell>> {
+    let conn = db.get_conn()?;
+    let (min_lat, min_lon) = TerrainCell::index(min);
+    let (max_lat, max_lon) = TerrainCell::index(max);
+
+    let mut stmt = conn.prepare(
+        "SELECT cell_lat, cell_lon, max_elevation
+         FROM terrain_cells
+         WHERE cell_lat BETWEEN ?1 AND ?2
+           AND cell_lon BETWEEN ?3 AND ?4",
+    )?;
+    let cells = stmt
+        .query_map(rusqlite::params![min_lat, max_lat, min_lon, max_lon], |row| {
+            Ok(TerrainCell {
+                cell_lat: row.get(0)?,
+                cell_lon: row.get(1)?,
+                max_elevation: row.get(2)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(cells)
+}
+
+/// Find airspaces whose boundary overlaps `min`..`max`, with their polygons
+pub fn find_airspaces_in(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+) -> Result<Vec<(Airspace, Vec<Coordinate>)>> {
+    let conn = db.get_conn()?;
+
+    // One pass over the boundary rows, ordered so each airspace's vertices
+    // arrive together and in sequence
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.name, a.type, a.class, a.lower_limit, a.upper_limit, a.created_at,
+                v.latitude, v.longitude, a.rvsm, a.level_scheme
+         FROM airspaces a
+         JOIN (SELECT airspace_id FROM airspace_boundaries
+               GROUP BY airspace_id
+               HAVING MAX(latitude) >= ?1 AND MIN(latitude) <= ?2
+                  AND MAX(longitude) >= ?3 AND MIN(longitude) <= ?4) b
+           ON b.airspace_id = a.id
+         JOIN airspace_boundaries v ON v.airspace_id = a.id
+         ORDER BY a.id, v.sequence",
+    )?;
+    let mut rows = stmt.query(rusqlite::params![
+        min.latitude,
+        max.latitude,
+        min.longitude,
+        max.longitude
+    ])?;
+
+    let mut result: Vec<(Airspace, Vec<Coordinate>)> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let vertex = Coordinate::new(row.get(7)?, row.get(8)?);
+        match result.last_mut() {
+            Some((airspace, vertices)) if airspace.id == id => vertices.push(vertex),
+            _ => result.push((
+                Airspace {
+                    id,
+                    name: row.get(1)?,
+                    airspace_type: AirspaceType::from_str(&row.get::<_, String>(2)?),
+                    class: row
+                        .get::<_, Option<String>>(3)?
+                        .map(|c| AirspaceClass::from_str(&c)),
+                    lower_limit: row.get(4)?,
+                    upper_limit: row.get(5)?,
+                    created_at: row.get(6)?,
+                    rvsm: row.get(9)?,
+                    level_scheme: row
+                        .get::<_, Option<String>>(10)?
+                        .and_then(|s| LevelScheme::from_str(&s)),
+                },
+                vec![vertex],
+            )),
+        }
+    }
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,6 +462,7 @@ mod tests {
             db_path: temp_file.path().to_path_buf(),
             enable_wal: false,
             pool_size: 1,
+            ..Default::default()
         };
 
         let db = Arc::new(Database::new(&config).unwrap());
@@ -143,9 +476,85 @@ mod tests {
             [],
         )
         .unwrap();
+        // Give back the pool's only connection before the query takes one
+        drop(conn);
 
         let center = Coordinate::new(39.9042, 116.4074);
         let result = find_waypoints_within(&db, center, 50.0).unwrap();
         assert_eq!(result.len(), 1);
     }
+
+    #[test]
+    fn test_find_in_cells() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+
+        let conn = db.get_conn().unwrap();
+        conn.execute_batch(
+            "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                ('1', 'ZBAA', 'Capital', 40.0801, 116.5846, 0),
+                ('2', 'ZSPD', 'Pudong', 31.1443, 121.8083, 0);
+             INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                VALUES ('WP1', 'TEST', 39.9042, 116.4074, 'FIX', 0);",
+        )
+        .unwrap();
+        // Rows written without the column get it from the refresh, which
+        // also follows moves made behind the repositories' back
+        assert_eq!(refresh_geohashes(&conn).unwrap(), 3);
+        conn.execute("UPDATE waypoints SET latitude = 39.91 WHERE id = 'WP1'", [])
+            .unwrap();
+        assert_eq!(refresh_geohashes(&conn).unwrap(), 1);
+        let hash: String = conn
+            .query_row("SELECT geohash FROM waypoints WHERE id = 'WP1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(hash, geohash::encode(Coordinate::new(39.91, 116.4074), 7));
+        drop(conn);
+
+        let beijing = vec!["wx4".to_string()];
+        let airports = find_airports_in_cells(&db, &beijing).unwrap();
+        assert_eq!(airports.len(), 1);
+        assert_eq!(airports[0].icao, "ZBAA");
+        assert_eq!(find_waypoints_in_cells(&db, &beijing).unwrap().len(), 1);
+
+        let both = vec!["wx4".to_string(), "wtw".to_string(), "wx4".to_string()];
+        assert_eq!(find_airports_in_cells(&db, &both).unwrap().len(), 2);
+        assert!(find_airports_in_cells(&db, &[]).unwrap().is_empty());
+        assert!(find_airports_in_cells(&db, &["wx4'".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_find_uas_zones() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        crate::navdata::ed269::import(&db, crate::navdata::ed269::tests::SAMPLE).unwrap();
+
+        let inside = find_uas_zones_at(&db, Coordinate::new(40.1, 116.1)).unwrap();
+        assert_eq!(inside.len(), 1);
+        assert_eq!(inside[0].restriction, UasRestriction::Prohibited);
+        assert_eq!(inside[0].boundary.len(), 5);
+
+        // Inside the 2 nm ring but outside the 1 nm one
+        let ring = find_uas_zones_at(&db, Coordinate::new(39.025, 117.0)).unwrap();
+        let ids: Vec<&str> = ring.iter().map(|z| z.id.as_str()).collect();
+        assert_eq!(ids, vec!["ZONE-B-2"]);
+
+        let all = find_uas_zones_in(&db, Coordinate::new(38.0, 115.0), Coordinate::new(41.0, 118.0)).unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(find_uas_zones_at(&db, Coordinate::new(0.0, 0.0)).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,236 @@
+use crate::db::Database;
+use crate::error::Result;
+use crate::models::airspace::{AirspaceClass, AirspaceType};
+use crate::models::airway::AirwayType;
+use crate::models::Coordinate;
+use crate::spatial::{geometry, query};
+use serde::{Deserialize, Serialize};
+
+/// Simplification tolerance, in screen pixels
+const SIMPLIFY_PIXELS: f64 = 1.0;
+
+/// Web-mercator tile size
+const TILE_SIZE: f64 = 256.0;
+
+/// Degrees covered by one pixel at `zoom` (equatorial, web-mercator)
+pub fn degrees_per_pixel(zoom: u8) -> f64 {
+    360.0 / (TILE_SIZE * 2f64.powi(zoom.min(30) as i32))
+}
+
+/// Simplification tolerance in degrees at `zoom`
+pub fn zoom_tolerance(zoom: u8) -> f64 {
+    degrees_per_pixel(zoom) * SIMPLIFY_PIXELS
+}
+
+/// Connected run of airway segments, ready to draw as one line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirwayPolyline {
+    pub airway_id: String,
+    pub name: String,
+    pub airway_type: AirwayType,
+    pub points: Vec<Coordinate>,
+}
+
+/// Airspace polygon with the attributes a map layer styles by
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirspaceOutline {
+    pub airspace_id: String,
+    pub name: String,
+    pub airspace_type: AirspaceType,
+    pub class: Option<AirspaceClass>,
+    pub lower_limit: Option<i32>,
+    pub upper_limit: Option<i32>,
+    pub boundary: Vec<Coordinate>,
+}
+
+/// Airway centerlines crossing `min`..`max`, simplified for `zoom`
+///
+/// Segments come back from one query in airway order and are chained while
+/// each starts where the previous one ended; a gap (or a segment outside
+/// the box) starts a new polyline.
+pub fn airway_polylines(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+    zoom: u8,
+) -> Result<Vec<AirwayPolyline>> {
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.name, a.type, s.from_waypoint_id, s.to_waypoint_id,
+                f.latitude, f.longitude, t.latitude, t.longitude
+         FROM airway_segments s
+         JOIN airways a ON a.id = s.airway_id
+         JOIN waypoints f ON f.id = s.from_waypoint_id
+         JOIN waypoints t ON t.id = s.to_waypoint_id
+         WHERE MAX(f.latitude, t.latitude) >= ?1 AND MIN(f.latitude, t.latitude) <= ?2
+           AND MAX(f.longitude, t.longitude) >= ?3 AND MIN(f.longitude, t.longitude) <= ?4
+         ORDER BY a.id, s.sequence",
+    )?;
+    let mut rows = stmt.query(rusqlite::params![
+        min.latitude,
+        max.latitude,
+        min.longitude,
+        max.longitude
+    ])?;
+
+    let mut polylines: Vec<AirwayPolyline> = Vec::new();
+    let mut last_waypoint = String::new();
+    while let Some(row) = rows.next()? {
+        let airway_id: String = row.get(0)?;
+        let from_id: String = row.get(3)?;
+        let to_id: String = row.get(4)?;
+        let from = Coordinate::new(row.get(5)?, row.get(6)?);
+        let to = Coordinate::new(row.get(7)?, row.get(8)?);
+
+        match polylines.last_mut() {
+            Some(line) if line.airway_id == airway_id && last_waypoint == from_id => {
+                line.points.push(to)
+            }
+            _ => polylines.push(AirwayPolyline {
+                airway_id,
+                name: row.get(1)?,
+                airway_type: AirwayType::from_str(&row.get::<_, String>(2)?),
+                points: vec![from, to],
+            }),
+        }
+        last_waypoint = to_id;
+    }
+
+    let tolerance = zoom_tolerance(zoom);
+    for line in polylines.iter_mut() {
+        line.points = geometry::simplify(&line.points, tolerance);
+    }
+    Ok(polylines)
+}
+
+/// Airspace outlines overlapping `min`..`max`, simplified for `zoom`
+///
+/// Outlines that would collapse below a triangle keep their full boundary.
+pub fn airspace_outlines(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+    zoom: u8,
+) -> Result<Vec<AirspaceOutline>> {
+    let tolerance = zoom_tolerance(zoom);
+
+    let outlines = query::find_airspaces_in(db, min, max)?
+        .into_iter()
+        .map(|(airspace, boundary)| {
+            // Close the ring so the closing edge is simplified too
+            let mut ring = boundary.clone();
+            ring.push(boundary[0]);
+            let mut simplified = geometry::simplify(&ring, tolerance);
+            simplified.pop();
+
+            AirspaceOutline {
+                airspace_id: airspace.id,
+                name: airspace.name,
+                airspace_type: airspace.airspace_type,
+                class: airspace.class,
+                lower_limit: airspace.lower_limit,
+                upper_limit: airspace.upper_limit,
+                boundary: if simplified.len() >= 3 {
+                    simplified
+                } else {
+                    boundary
+                },
+            }
+        })
+        .collect();
+    Ok(outlines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_airway_polylines() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at) VALUES
+                    ('W1', 'W1', 30.0, 120.0, 'FIX', 0),
+                    ('W2', 'W2', 30.001, 120.5, 'FIX', 0),
+                    ('W3', 'W3', 30.0, 121.0, 'FIX', 0),
+                    ('W4', 'W4', 31.0, 121.0, 'FIX', 0),
+                    ('W5', 'W5', 45.0, 130.0, 'FIX', 0);
+                 INSERT INTO airways (id, name, type, created_at) VALUES
+                    ('A1', 'A1', 'HIGH', 0),
+                    ('B2', 'B2', 'LOW', 0);
+                 INSERT INTO airway_segments (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at) VALUES
+                    ('S1', 'A1', 'W1', 'W2', 1, 0),
+                    ('S2', 'A1', 'W2', 'W3', 2, 0),
+                    ('S3', 'A1', 'W3', 'W4', 3, 0),
+                    ('S4', 'B2', 'W4', 'W5', 1, 0),
+                    ('S5', 'B2', 'W1', 'W3', 2, 0);",
+            )
+            .unwrap();
+
+        let (min, max) = (Coordinate::new(29.0, 119.0), Coordinate::new(32.0, 122.0));
+        let lines = airway_polylines(&db, min, max, 12).unwrap();
+        // A1 as one chain; B2's W4-W5 leaves the box but still crosses it,
+        // and W1-W3 doesn't continue from W5, so B2 splits in two
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].airway_id, "A1");
+        assert_eq!(lines[0].airway_type, AirwayType::High);
+        assert_eq!(lines[0].points.len(), 4);
+        assert_eq!(lines[1].airway_id, "B2");
+        assert_eq!(lines[2].points.len(), 2);
+
+        // Zoomed out, the near-straight W1-W2-W3 collapses
+        let lines = airway_polylines(&db, min, max, 2).unwrap();
+        assert_eq!(lines[0].points.len(), 3);
+
+        let empty = airway_polylines(&db, Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), 5);
+        assert!(empty.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_airspace_outlines() {
+        let (db, _temp_file) = setup_test_db();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airspaces (id, name, type, class, lower_limit, upper_limit, created_at)
+                    VALUES ('CTR1', 'City CTR', 'CTR', 'D', 0, 2500, 0);
+                 INSERT INTO airspace_boundaries (id, airspace_id, latitude, longitude, sequence) VALUES
+                    ('B1', 'CTR1', 30.0, 120.0, 0),
+                    ('B2', 'CTR1', 30.0, 120.25, 1),
+                    ('B3', 'CTR1', 30.0005, 120.5, 2),
+                    ('B4', 'CTR1', 30.5, 120.5, 3),
+                    ('B5', 'CTR1', 30.5, 120.0, 4);",
+            )
+            .unwrap();
+
+        let (min, max) = (Coordinate::new(29.0, 119.0), Coordinate::new(31.0, 121.0));
+        let detailed = airspace_outlines(&db, min, max, 14).unwrap();
+        assert_eq!(detailed.len(), 1);
+        assert_eq!(detailed[0].class, Some(AirspaceClass::ClassD));
+        assert_eq!(detailed[0].boundary.len(), 5);
+
+        let coarse = airspace_outlines(&db, min, max, 6).unwrap();
+        assert_eq!(coarse[0].boundary.len(), 4);
+
+        // At world zoom the outline would collapse, so it keeps every vertex
+        let world = airspace_outlines(&db, min, max, 0).unwrap();
+        assert_eq!(world[0].boundary.len(), 5);
+    }
+}
@@ -0,0 +1,402 @@
+pub mod tiles;
+pub mod tracks;
+
+use crate::db::Database;
+use crate::error::Result;
+use crate::events::Event;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+use tracks::TrackPoint;
+
+/// Approximate on-disk bytes of one track point, including its index entry
+const TRACK_POINT_BYTES: i64 = 48;
+
+/// Approximate on-disk bytes of one winds-aloft grid point
+const GRID_POINT_BYTES: i64 = 64;
+
+/// Per-tile overhead on top of the tile data
+const TILE_OVERHEAD_BYTES: i64 = 32;
+
+/// 受存储配额管理的大体积数据集
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dataset {
+    /// 飞行轨迹，按轨迹淘汰
+    Tracks,
+    /// 高空风温格点，按预报时次淘汰
+    WeatherGrids,
+    /// 地图瓦片缓存，按瓦片淘汰
+    Tiles,
+}
+
+impl Dataset {
+    pub const ALL: [Dataset; 3] = [Dataset::Tracks, Dataset::WeatherGrids, Dataset::Tiles];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Dataset::Tracks => "TRACKS",
+            Dataset::WeatherGrids => "WEATHER_GRIDS",
+            Dataset::Tiles => "TILES",
+        }
+    }
+
+    /// Evictable units of the dataset with their approximate size in bytes
+    fn units_sql(&self) -> String {
+        match self {
+            Dataset::Tracks => format!(
+                "SELECT track_id, COUNT(*) * {} FROM track_points GROUP BY track_id",
+                TRACK_POINT_BYTES
+            ),
+            Dataset::WeatherGrids => format!(
+                "SELECT CAST(valid_time AS TEXT), COUNT(*) * {} FROM winds_aloft GROUP BY valid_time",
+                GRID_POINT_BYTES
+            ),
+            Dataset::Tiles => format!(
+                "SELECT layer || '/' || zoom || '/' || x || '/' || y, length(data) + {} FROM map_tiles",
+                TILE_OVERHEAD_BYTES
+            ),
+        }
+    }
+
+    fn delete_unit(&self, conn: &Connection, unit: &str) -> Result<usize> {
+        match self {
+            Dataset::Tracks => tracks::delete(conn, unit),
+            Dataset::WeatherGrids => Ok(conn.execute(
+                "DELETE FROM winds_aloft WHERE valid_time = CAST(?1 AS INTEGER)",
+                [unit],
+            )?),
+            Dataset::Tiles => tiles::delete(conn, unit),
+        }
+    }
+}
+
+/// 大体积数据的存储配额（字节），None 表示不限
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageConfig {
+    pub tracks: Option<u64>,
+    pub weather_grids: Option<u64>,
+    pub tiles: Option<u64>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            tracks: Some(64 * 1024 * 1024),
+            weather_grids: Some(128 * 1024 * 1024),
+            tiles: Some(512 * 1024 * 1024),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// 数据集的配额
+    pub fn quota(&self, dataset: Dataset) -> Option<u64> {
+        match dataset {
+            Dataset::Tracks => self.tracks,
+            Dataset::WeatherGrids => self.weather_grids,
+            Dataset::Tiles => self.tiles,
+        }
+    }
+
+    fn set_quota(&mut self, dataset: Dataset, quota: Option<u64>) {
+        match dataset {
+            Dataset::Tracks => self.tracks = quota,
+            Dataset::WeatherGrids => self.weather_grids = quota,
+            Dataset::Tiles => self.tiles = quota,
+        }
+    }
+}
+
+/// 数据集的存储占用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetUsage {
+    pub dataset: Dataset,
+    /// 估算的占用字节数
+    pub bytes: u64,
+    /// 可淘汰单元数（轨迹数、预报时次数或瓦片数）
+    pub units: usize,
+    pub quota: Option<u64>,
+}
+
+/// Record that units of `dataset` were just written or read
+pub(crate) fn touch(conn: &Connection, dataset: Dataset, units: &[String], now: i64) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO storage_access (dataset, unit, accessed_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(dataset, unit) DO UPDATE SET accessed_at = excluded.accessed_at",
+    )?;
+    for unit in units {
+        stmt.execute(rusqlite::params![dataset.as_str(), unit, now])?;
+    }
+    Ok(())
+}
+
+/// Units of `dataset` with their size, least recently used first
+fn units_by_access(conn: &Connection, dataset: Dataset) -> Result<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare(&dataset.units_sql())?;
+    let sizes = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare("SELECT unit, accessed_at FROM storage_access WHERE dataset = ?1")?;
+    let accessed: HashMap<String, i64> = stmt
+        .query_map([dataset.as_str()], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+
+    // Access records of units removed some other way (pruning, deletes)
+    let present: HashSet<&str> = sizes.iter().map(|(unit, _)| unit.as_str()).collect();
+    for unit in accessed.keys().filter(|unit| !present.contains(unit.as_str())) {
+        conn.execute(
+            "DELETE FROM storage_access WHERE dataset = ?1 AND unit = ?2",
+            rusqlite::params![dataset.as_str(), unit],
+        )?;
+    }
+
+    let mut units = sizes;
+    units.sort_by(|(a, _), (b, _)| {
+        let last_used = |unit: &String| accessed.get(unit).copied().unwrap_or(0);
+        last_used(a).cmp(&last_used(b)).then_with(|| a.cmp(b))
+    });
+    Ok(units)
+}
+
+/// Evict least recently used units until `dataset` fits in `quota`,
+/// returning the number of units evicted
+///
+/// The most recently used unit is never evicted, so the track being
+/// recorded or the tile on screen survives even when it alone is over quota.
+pub(crate) fn evict(conn: &Connection, dataset: Dataset, quota: u64) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let units = units_by_access(&tx, dataset)?;
+    let mut total: i64 = units.iter().map(|(_, size)| size).sum();
+    let mut evicted = 0;
+    for (unit, size) in units.iter().take(units.len().saturating_sub(1)) {
+        if total <= quota as i64 {
+            break;
+        }
+        dataset.delete_unit(&tx, unit)?;
+        tx.execute(
+            "DELETE FROM storage_access WHERE dataset = ?1 AND unit = ?2",
+            rusqlite::params![dataset.as_str(), unit],
+        )?;
+        total -= size;
+        evicted += 1;
+    }
+    tx.commit()?;
+    if evicted > 0 {
+        log::info!("{} 超出存储配额，已淘汰 {} 项", dataset.as_str(), evicted);
+    }
+    Ok(evicted)
+}
+
+/// 大体积数据（飞行轨迹、高空风温格点、地图瓦片）的存储与配额管理
+pub struct StorageManager {
+    db: Arc<Database>,
+    config: RwLock<StorageConfig>,
+}
+
+impl StorageManager {
+    /// 创建存储管理器
+    pub fn new(db: Arc<Database>, config: StorageConfig) -> Result<Self> {
+        Ok(Self {
+            db,
+            config: RwLock::new(config),
+        })
+    }
+
+    /// 修改数据集的配额并立即执行淘汰
+    pub fn set_quota(&self, dataset: Dataset, quota: Option<u64>) -> Result<usize> {
+        self.config.write().unwrap().set_quota(dataset, quota);
+        self.enforce(dataset)
+    }
+
+    /// 各数据集的当前占用
+    pub fn usage(&self) -> Result<Vec<DatasetUsage>> {
+        let conn = self.db.get_conn()?;
+        let config = self.config.read().unwrap().clone();
+        Dataset::ALL
+            .iter()
+            .map(|&dataset| {
+                let units = units_by_access(&conn, dataset)?;
+                Ok(DatasetUsage {
+                    dataset,
+                    bytes: units.iter().map(|(_, size)| *size as u64).sum(),
+                    units: units.len(),
+                    quota: config.quota(dataset),
+                })
+            })
+            .collect()
+    }
+
+    /// 按最近最少使用淘汰超出配额的数据，返回淘汰的单元数
+    pub fn enforce(&self, dataset: Dataset) -> Result<usize> {
+        let Some(quota) = self.config.read().unwrap().quota(dataset) else {
+            return Ok(0);
+        };
+        evict(&*self.db.get_background_conn()?, dataset, quota)
+    }
+
+    /// 对所有数据集执行配额淘汰
+    pub fn enforce_all(&self) -> Result<usize> {
+        Dataset::ALL.iter().map(|&dataset| self.enforce(dataset)).sum()
+    }
+
+    /// 记录一批轨迹点；超出配额时淘汰最久未使用的其他轨迹
+    pub fn record_track(&self, track_id: &str, points: &[TrackPoint]) -> Result<usize> {
+        {
+            let conn = self.db.get_conn()?;
+            tracks::store(&conn, track_id, points)?;
+            self.touch(&conn, Dataset::Tracks, &[track_id.to_string()])?;
+        }
+        self.enforce(Dataset::Tracks)?;
+        Ok(points.len())
+    }
+
+    /// 将事件总线上的位置更新逐点记录到轨迹，直到总线关闭；返回记录的点数
+    pub async fn record_positions(
+        &self,
+        track_id: &str,
+        mut receiver: broadcast::Receiver<Event>,
+    ) -> Result<usize> {
+        let mut recorded = 0;
+        loop {
+            match receiver.recv().await {
+                Ok(Event::PositionUpdated { point, .. }) => {
+                    recorded += self.record_track(track_id, &[point])?;
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("轨迹 {} 记录滞后，丢失 {} 个事件", track_id, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        Ok(recorded)
+    }
+
+    /// 读取轨迹（按时间排序）
+    pub fn track(&self, track_id: &str) -> Result<Vec<TrackPoint>> {
+        let conn = self.db.get_conn()?;
+        let points = tracks::load(&conn, track_id)?;
+        if !points.is_empty() {
+            self.touch(&conn, Dataset::Tracks, &[track_id.to_string()])?;
+        }
+        Ok(points)
+    }
+
+    /// 删除轨迹
+    pub fn delete_track(&self, track_id: &str) -> Result<usize> {
+        tracks::delete(&*self.db.get_conn()?, track_id)
+    }
+
+    /// 缓存地图瓦片；超出配额时淘汰最久未显示的瓦片
+    pub fn put_tile(&self, layer: &str, zoom: u8, x: u32, y: u32, data: &[u8]) -> Result<()> {
+        {
+            let conn = self.db.get_conn()?;
+            tiles::store(&conn, layer, zoom, x, y, data, self.db.clock().timestamp())?;
+            self.touch(&conn, Dataset::Tiles, &[tiles::key(layer, zoom, x, y)])?;
+        }
+        self.enforce(Dataset::Tiles)?;
+        Ok(())
+    }
+
+    /// 读取缓存的地图瓦片
+    pub fn tile(&self, layer: &str, zoom: u8, x: u32, y: u32) -> Result<Option<Vec<u8>>> {
+        let conn = self.db.get_conn()?;
+        let data = tiles::load(&conn, layer, zoom, x, y)?;
+        if data.is_some() {
+            self.touch(&conn, Dataset::Tiles, &[tiles::key(layer, zoom, x, y)])?;
+        }
+        Ok(data)
+    }
+
+    pub(crate) fn touch(&self, conn: &Connection, dataset: Dataset, units: &[String]) -> Result<()> {
+        touch(conn, dataset, units, self.db.clock().timestamp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Coordinate;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup(config: StorageConfig) -> (StorageManager, Arc<Database>, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&db_config).unwrap());
+        db.migrate().unwrap();
+        (StorageManager::new(Arc::clone(&db), config).unwrap(), db, temp_file)
+    }
+
+    #[test]
+    fn test_tile_lru_eviction() {
+        let (storage, db, _temp_file) = setup(StorageConfig {
+            tiles: Some(3 * (1000 + TILE_OVERHEAD_BYTES as u64)),
+            ..Default::default()
+        });
+        let data = vec![7u8; 1000];
+        for x in 0..3 {
+            storage.put_tile("base", 8, x, 0, &data).unwrap();
+        }
+        // Same-second writes: make the access order explicit
+        {
+            let conn = db.get_conn().unwrap();
+            for x in 0..3 {
+                touch(&conn, Dataset::Tiles, &[tiles::key("base", 8, x, 0)], 100 + x as i64).unwrap();
+            }
+            // Tile 0 was viewed again, so tile 1 is now the oldest
+            touch(&conn, Dataset::Tiles, &[tiles::key("base", 8, 0, 0)], 200).unwrap();
+        }
+
+        storage.put_tile("base", 8, 3, 0, &data).unwrap();
+        assert!(storage.tile("base", 8, 1, 0).unwrap().is_none());
+        for x in [0, 2, 3] {
+            assert!(storage.tile("base", 8, x, 0).unwrap().is_some());
+        }
+
+        let usage = storage.usage().unwrap();
+        let tiles = usage.iter().find(|u| u.dataset == Dataset::Tiles).unwrap();
+        assert_eq!(tiles.units, 3);
+        assert!(tiles.bytes <= tiles.quota.unwrap());
+    }
+
+    #[test]
+    fn test_active_track_survives() {
+        let (storage, db, _temp_file) = setup(StorageConfig {
+            tracks: Some(10 * TRACK_POINT_BYTES as u64),
+            ..Default::default()
+        });
+        let points = |n: i64| -> Vec<TrackPoint> {
+            (0..n)
+                .map(|t| TrackPoint {
+                    recorded_at: t,
+                    position: Coordinate::new(40.0, 116.0),
+                    altitude: None,
+                    ground_speed: None,
+                })
+                .collect()
+        };
+        storage.record_track("old", &points(6)).unwrap();
+        touch(&db.get_conn().unwrap(), Dataset::Tracks, &["old".to_string()], 0).unwrap();
+
+        // Over quota: the older track goes, the one being recorded stays
+        // even though it alone exceeds the quota
+        storage.record_track("current", &points(12)).unwrap();
+        assert!(storage.track("old").unwrap().is_empty());
+        assert_eq!(storage.track("current").unwrap().len(), 12);
+
+        // Lifting the quota stops eviction
+        storage.set_quota(Dataset::Tracks, None).unwrap();
+        storage.record_track("next", &points(12)).unwrap();
+        assert_eq!(storage.track("current").unwrap().len(), 12);
+        assert_eq!(storage.enforce_all().unwrap(), 0);
+    }
+}
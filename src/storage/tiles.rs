@@ -0,0 +1,69 @@
+use crate::error::Result;
+use rusqlite::{Connection, OptionalExtension};
+
+/// Storage unit key of a cached tile
+pub fn key(layer: &str, zoom: u8, x: u32, y: u32) -> String {
+    format!("{}/{}/{}/{}", layer, zoom, x, y)
+}
+
+/// Inverse of [`key`]; the layer name may itself contain `/`
+pub fn parse_key(key: &str) -> Option<(&str, u8, u32, u32)> {
+    let mut parts = key.rsplitn(4, '/');
+    let y = parts.next()?.parse().ok()?;
+    let x = parts.next()?.parse().ok()?;
+    let zoom = parts.next()?.parse().ok()?;
+    Some((parts.next()?, zoom, x, y))
+}
+
+/// Insert or replace a cached tile, stamped `created_at`
+pub fn store(
+    conn: &Connection,
+    layer: &str,
+    zoom: u8,
+    x: u32,
+    y: u32,
+    data: &[u8],
+    created_at: i64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO map_tiles (layer, zoom, x, y, data, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![layer, zoom, x, y, data, created_at],
+    )?;
+    Ok(())
+}
+
+/// Cached tile data, if present
+pub fn load(conn: &Connection, layer: &str, zoom: u8, x: u32, y: u32) -> Result<Option<Vec<u8>>> {
+    conn.query_row(
+        "SELECT data FROM map_tiles WHERE layer = ?1 AND zoom = ?2 AND x = ?3 AND y = ?4",
+        rusqlite::params![layer, zoom, x, y],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+/// Remove a cached tile by its storage unit key
+pub fn delete(conn: &Connection, key: &str) -> Result<usize> {
+    let Some((layer, zoom, x, y)) = parse_key(key) else {
+        return Ok(0);
+    };
+    Ok(conn.execute(
+        "DELETE FROM map_tiles WHERE layer = ?1 AND zoom = ?2 AND x = ?3 AND y = ?4",
+        rusqlite::params![layer, zoom, x, y],
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_round_trip() {
+        assert_eq!(key("vfr/sectional", 9, 420, 195), "vfr/sectional/9/420/195");
+        assert_eq!(parse_key("vfr/sectional/9/420/195"), Some(("vfr/sectional", 9, 420, 195)));
+        assert_eq!(parse_key("base/300/1/1"), None);
+        assert_eq!(parse_key("1/2/3"), None);
+    }
+}
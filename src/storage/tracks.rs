@@ -0,0 +1,100 @@
+use crate::error::Result;
+use crate::models::Coordinate;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// One recorded position of a flown track
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackPoint {
+    /// Unix seconds
+    pub recorded_at: i64,
+    pub position: Coordinate,
+    /// Feet
+    pub altitude: Option<i32>,
+    /// Knots
+    pub ground_speed: Option<i32>,
+}
+
+/// Append points to a track; a point at an already recorded time replaces it
+pub fn store(conn: &Connection, track_id: &str, points: &[TrackPoint]) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO track_points
+                 (track_id, recorded_at, latitude, longitude, altitude, ground_speed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for point in points {
+            stmt.execute(rusqlite::params![
+                track_id,
+                point.recorded_at,
+                point.position.latitude,
+                point.position.longitude,
+                point.altitude,
+                point.ground_speed,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(points.len())
+}
+
+/// Points of a track in time order; empty when the track is unknown
+pub fn load(conn: &Connection, track_id: &str) -> Result<Vec<TrackPoint>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT recorded_at, latitude, longitude, altitude, ground_speed
+         FROM track_points WHERE track_id = ?1 ORDER BY recorded_at",
+    )?;
+    let points = stmt
+        .query_map([track_id], |row| {
+            Ok(TrackPoint {
+                recorded_at: row.get(0)?,
+                position: Coordinate::new(row.get(1)?, row.get(2)?),
+                altitude: row.get(3)?,
+                ground_speed: row.get(4)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(points)
+}
+
+/// Remove a track, returning the number of points deleted
+pub fn delete(conn: &Connection, track_id: &str) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM track_points WHERE track_id = ?1", [track_id])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_store_and_load() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        let conn = db.get_conn().unwrap();
+
+        let point = |t: i64, alt: i32| TrackPoint {
+            recorded_at: t,
+            position: Coordinate::new(40.0 + t as f64 * 0.001, 116.0),
+            altitude: Some(alt),
+            ground_speed: None,
+        };
+        store(&conn, "T1", &[point(20, 3000), point(10, 2000)]).unwrap();
+        store(&conn, "T1", &[point(20, 3100)]).unwrap();
+
+        let points = load(&conn, "T1").unwrap();
+        assert_eq!(points, vec![point(10, 2000), point(20, 3100)]);
+        assert!(load(&conn, "T2").unwrap().is_empty());
+        assert_eq!(delete(&conn, "T1").unwrap(), 2);
+    }
+}
@@ -0,0 +1,428 @@
+use crate::attachments;
+use crate::clock::Clock;
+use crate::db::schema::{USER_SCHEMA, USER_TABLES};
+use crate::db::Database;
+use crate::encryption::{generate_salt, AesEncryptor, EncryptedData};
+use crate::error::{AeroBaseError, Result};
+use crate::import::{ImportControl, ImportPhase};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::TimeDelta;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Backup format version
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// `sync_metadata` key recording the last successful backup
+const LAST_BACKUP_KEY: &str = "user_backup";
+
+/// Stores encrypted user-data backups by name
+///
+/// The crate ships no HTTP client; the host application plugs in the sync
+/// endpoint or any blob store.
+pub trait BackupStore: Send + Sync {
+    fn put(&self, name: &str, data: &[u8]) -> Result<()>;
+
+    /// The stored backup, or None if there is none under `name`
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// How often and under which name user data is backed up
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupConfig {
+    /// Minimum time between periodic backups
+    pub interval: TimeDelta,
+    /// Name of the backup in the store, shared by all of a user's devices
+    pub name: String,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            interval: TimeDelta::hours(24),
+            name: "user-data".to_string(),
+        }
+    }
+}
+
+/// Encrypted backup as written to the store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedBackup {
+    format_version: u32,
+    created_at: i64,
+    salt: String,
+    payload: EncryptedData,
+}
+
+/// Outcome of a backup
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupSummary {
+    pub created_at: i64,
+    /// Size of the database snapshot before encryption
+    pub bytes: usize,
+}
+
+/// Outcome of a restore
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoreSummary {
+    /// When the restored backup was taken
+    pub created_at: i64,
+    /// Rows restored per user table
+    pub tables: Vec<(String, usize)>,
+}
+
+/// Periodic encrypted backup of the user-data database
+///
+/// Only the attached user-data file (`Config::user_db_path`) is backed up;
+/// navdata is replaceable and comes from the navdata feed.
+pub struct UserDataBackup {
+    db: Arc<Database>,
+    store: Arc<dyn BackupStore>,
+    config: BackupConfig,
+    clock: Arc<dyn Clock>,
+    attachment_dir: Option<PathBuf>,
+}
+
+impl UserDataBackup {
+    /// Create a backup service writing to `store`
+    pub fn new(db: Arc<Database>, store: Arc<dyn BackupStore>, config: BackupConfig) -> Self {
+        Self {
+            clock: Arc::clone(db.clock()),
+            db,
+            store,
+            config,
+            attachment_dir: None,
+        }
+    }
+
+    /// Decide when backups are due with `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Include attachment content kept on disk under `dir` in snapshots
+    pub fn with_attachment_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.attachment_dir = dir;
+        self
+    }
+
+    fn user_db_path(&self) -> Result<&Path> {
+        self.db.user_db_path().ok_or_else(|| {
+            AeroBaseError::InvalidInput("No separate user-data database is configured".to_string())
+        })
+    }
+
+    /// Consistent copy of the user-data database, taken while it stays in use
+    ///
+    /// `VACUUM INTO` reads through SQLite, so changes still in the WAL are
+    /// included and writers are not blocked; copying the file itself would
+    /// miss them or catch a half-written page. Attachment content kept on
+    /// disk is copied into the snapshot, so a restore brings it back too.
+    pub fn snapshot(&self) -> Result<Vec<u8>> {
+        let path = sibling(self.user_db_path()?, ".snapshot");
+        let _ = fs::remove_file(&path);
+        self.db
+            .get_conn()?
+            .execute(&format!("VACUUM {} INTO ?1", USER_SCHEMA), [path.to_string_lossy()])?;
+        if let Some(dir) = &self.attachment_dir {
+            let inlined = Connection::open(&path)
+                .map_err(Into::into)
+                .and_then(|conn| attachments::inline_blobs(&conn, dir));
+            if let Err(e) = inlined {
+                let _ = fs::remove_file(&path);
+                return Err(e);
+            }
+        }
+        let bytes = fs::read(&path);
+        let _ = fs::remove_file(&path);
+        Ok(bytes?)
+    }
+
+    /// Encrypt a snapshot with `password` and upload it
+    pub fn backup(&self, password: &str) -> Result<BackupSummary> {
+        if password.is_empty() {
+            return Err(AeroBaseError::InvalidInput(
+                "Backup password must not be empty".to_string(),
+            ));
+        }
+        let snapshot = self.snapshot()?;
+        let created_at = self.clock.timestamp();
+
+        let salt = generate_salt();
+        let sealed = SealedBackup {
+            format_version: BACKUP_FORMAT_VERSION,
+            created_at,
+            salt: general_purpose::STANDARD.encode(&salt),
+            payload: AesEncryptor::from_password(password, &salt)?.encrypt(&snapshot)?,
+        };
+        self.store.put(&self.config.name, &serde_json::to_vec(&sealed)?)?;
+
+        self.db.get_conn()?.execute(
+            "INSERT OR REPLACE INTO sync_metadata (table_name, last_sync) VALUES (?1, ?2)",
+            rusqlite::params![LAST_BACKUP_KEY, created_at],
+        )?;
+        log::info!("User data backed up ({} bytes)", snapshot.len());
+        Ok(BackupSummary {
+            created_at,
+            bytes: snapshot.len(),
+        })
+    }
+
+    /// When this device last backed up successfully
+    pub fn last_backup(&self) -> Result<Option<i64>> {
+        Ok(self
+            .db
+            .get_conn()?
+            .query_row(
+                "SELECT last_sync FROM sync_metadata WHERE table_name = ?1",
+                [LAST_BACKUP_KEY],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Back up if the configured interval has passed since the last backup
+    pub fn backup_if_due(&self, password: &str) -> Result<Option<BackupSummary>> {
+        let due = match self.last_backup()? {
+            Some(last) => self.clock.timestamp() - last >= self.config.interval.num_seconds(),
+            None => true,
+        };
+        if due {
+            self.backup(password).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Download the backup and merge it into the user tables
+    ///
+    /// Rows from the backup replace local rows with the same key; other
+    /// local rows are kept. Returns None when the store has no backup.
+    pub fn restore(&self, password: &str) -> Result<Option<RestoreSummary>> {
+        self.restore_with(password, &ImportControl::default())
+    }
+
+    /// Restore, reporting each user table merged as a record
+    ///
+    /// Cancellation rolls back every table merged so far.
+    pub fn restore_with(&self, password: &str, control: &ImportControl) -> Result<Option<RestoreSummary>> {
+        control.report(ImportPhase::Parsing, 0, None)?;
+        let Some(bytes) = self.store.get(&self.config.name)? else {
+            return Ok(None);
+        };
+        let sealed: SealedBackup = serde_json::from_slice(&bytes)?;
+        if sealed.format_version > BACKUP_FORMAT_VERSION {
+            return Err(AeroBaseError::InvalidInput(format!(
+                "Unsupported backup format version {}",
+                sealed.format_version
+            )));
+        }
+        let salt = general_purpose::STANDARD
+            .decode(&sealed.salt)
+            .map_err(|e| AeroBaseError::InvalidInput(format!("Invalid backup salt: {}", e)))?;
+        let snapshot = AesEncryptor::from_password(password, &salt)?.decrypt(&sealed.payload)?;
+
+        let path = sibling(self.user_db_path()?, ".restore");
+        fs::write(&path, &snapshot)?;
+        let merged = merge(&self.db, &path, control);
+        let _ = fs::remove_file(&path);
+        let tables = merged?;
+
+        log::info!("User data restored from backup taken at {}", sealed.created_at);
+        Ok(Some(RestoreSummary {
+            created_at: sealed.created_at,
+            tables,
+        }))
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Copy the user tables of the snapshot at `path` into the live database
+///
+/// Columns the snapshot lacks (an older schema) keep their defaults.
+fn merge(db: &Database, path: &Path, control: &ImportControl) -> Result<Vec<(String, usize)>> {
+    let conn = db.get_background_conn()?;
+    conn.execute("ATTACH DATABASE ?1 AS restored", [path.to_string_lossy()])?;
+    let result = (|| {
+        let tx = conn.unchecked_transaction()?;
+        let mut tables = Vec::new();
+        let total = Some(USER_TABLES.len());
+        control.report(ImportPhase::Writing, 0, total)?;
+        for (done, table) in USER_TABLES.iter().enumerate() {
+            let columns: Vec<String> = tx
+                .prepare(
+                    "SELECT name FROM pragma_table_info(?1, 'restored')
+                     WHERE name IN (SELECT name FROM pragma_table_info(?1, ?2))
+                     ORDER BY cid",
+                )?
+                .query_map([*table, USER_SCHEMA], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?;
+            if !columns.is_empty() {
+                let columns = columns.join(", ");
+                let rows = tx.execute(
+                    &format!(
+                        "INSERT OR REPLACE INTO {schema}.{table} ({columns})
+                         SELECT {columns} FROM restored.{table}",
+                        schema = USER_SCHEMA,
+                    ),
+                    [],
+                )?;
+                tables.push((table.to_string(), rows));
+            }
+            control.report(ImportPhase::Writing, done + 1, total)?;
+        }
+        control.report(ImportPhase::Committing, USER_TABLES.len(), total)?;
+        tx.commit()?;
+        Ok(tables)
+    })();
+    conn.execute("DETACH DATABASE restored", [])?;
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::Config;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    pub(crate) struct MemoryStore {
+        blobs: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl BackupStore for MemoryStore {
+        fn put(&self, name: &str, data: &[u8]) -> Result<()> {
+            self.blobs.lock().unwrap().insert(name.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.blobs.lock().unwrap().get(name).cloned())
+        }
+    }
+
+    pub(crate) fn setup(dir: &Path, name: &str) -> Arc<Database> {
+        let config = Config {
+            db_path: dir.join(format!("{}-navdata.db", name)),
+            user_db_path: Some(dir.join(format!("{}-user.db", name))),
+            enable_wal: true,
+            pool_size: 2,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        db
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = TempDir::new().unwrap();
+        let store = Arc::new(MemoryStore::default());
+        let clock = Arc::new(MockClock::at_timestamp(1_700_000_000));
+
+        let source = setup(dir.path(), "old");
+        source
+            .get_conn()
+            .unwrap()
+            .execute(
+                "INSERT INTO favorites (id, profile_id, item_type, item_id, created_at)
+                 VALUES ('F1', 'default', 'AIRPORT', 'ZBAA', 0)",
+                [],
+            )
+            .unwrap();
+        let backups = UserDataBackup::new(Arc::clone(&source), store.clone(), BackupConfig::default())
+            .with_clock(clock.clone());
+        assert!(backups.backup_if_due("secret").unwrap().is_some());
+        assert_eq!(backups.last_backup().unwrap(), Some(1_700_000_000));
+        // Not due again until the interval has passed
+        clock.advance(TimeDelta::hours(23));
+        assert!(backups.backup_if_due("secret").unwrap().is_none());
+        clock.advance(TimeDelta::hours(1));
+        assert!(backups.backup_if_due("secret").unwrap().is_some());
+
+        // Navdata never goes into the backup
+        let snapshot = dir.path().join("snapshot.db");
+        fs::write(&snapshot, backups.snapshot().unwrap()).unwrap();
+        let conn = rusqlite::Connection::open(&snapshot).unwrap();
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        assert!(tables.contains(&"favorites".to_string()));
+        assert!(!tables.contains(&"airports".to_string()));
+
+        let target = setup(dir.path(), "new");
+        let restore = UserDataBackup::new(Arc::clone(&target), store.clone(), BackupConfig::default());
+        assert!(restore.restore("wrong").is_err());
+        let summary = restore.restore("secret").unwrap().unwrap();
+        assert_eq!(summary.created_at, 1_700_086_400);
+        assert!(summary.tables.contains(&("favorites".to_string(), 1)));
+        let count: i64 = target
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let empty = UserDataBackup::new(target, Arc::new(MemoryStore::default()), BackupConfig::default());
+        assert_eq!(empty.restore("secret").unwrap(), None);
+    }
+
+    #[test]
+    fn test_backup_includes_attachments_on_disk() {
+        use crate::attachments::{AttachmentConfig, AttachmentStore};
+
+        let dir = TempDir::new().unwrap();
+        let store = Arc::new(MemoryStore::default());
+        let key = || AesEncryptor::from_key(vec![3; 32]).unwrap();
+        let on_disk = AttachmentConfig {
+            blob_dir: Some(dir.path().join("blobs")),
+            ..Default::default()
+        };
+
+        let source = setup(dir.path(), "old");
+        let attachments = AttachmentStore::new(Arc::clone(&source), on_disk.clone(), key());
+        let attachment = attachments.add("sheet.pdf", "application/pdf", b"%PDF").unwrap();
+        UserDataBackup::new(source, store.clone(), BackupConfig::default())
+            .with_attachment_dir(on_disk.blob_dir)
+            .backup("secret")
+            .unwrap();
+
+        let target = setup(dir.path(), "new");
+        UserDataBackup::new(Arc::clone(&target), store, BackupConfig::default())
+            .restore("secret")
+            .unwrap();
+        let restored = AttachmentStore::new(target, AttachmentConfig::default(), key());
+        assert_eq!(restored.read(&attachment.id).unwrap(), b"%PDF");
+    }
+
+    #[test]
+    fn test_requires_user_db() {
+        let dir = TempDir::new().unwrap();
+        let config = Config {
+            db_path: dir.path().join("aerobase.db"),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let backups = UserDataBackup::new(db, Arc::new(MemoryStore::default()), BackupConfig::default());
+        assert!(matches!(backups.backup("secret"), Err(AeroBaseError::InvalidInput(_))));
+    }
+}
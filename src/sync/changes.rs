@@ -0,0 +1,331 @@
+use crate::db::schema::{TIMESTAMPED_TABLES, USER_SCHEMA, USER_TABLES};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::events::Event;
+use rusqlite::{Connection, OptionalExtension};
+
+fn check_table(table: &str) -> Result<()> {
+    if TIMESTAMPED_TABLES.contains(&table) {
+        Ok(())
+    } else {
+        Err(AeroBaseError::InvalidInput(format!(
+            "Table {} is not tracked for changes",
+            table
+        )))
+    }
+}
+
+/// Rows of one table changed after a change sequence number
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangeSet {
+    /// Ids of rows inserted or updated, oldest change first
+    pub modified: Vec<String>,
+    /// Ids of rows deleted
+    pub deleted: Vec<String>,
+    /// Sequence number of the newest change seen; pass it back as `since`
+    pub seq: i64,
+}
+
+impl ChangeSet {
+    /// Number of changed rows
+    pub fn len(&self) -> usize {
+        self.modified.len() + self.deleted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Changes to `table` after sequence number `since` (0 for all rows)
+///
+/// Each row appears once, under its latest change, so edits made within the
+/// same second are never lost and a deleted row shows up in `deleted`.
+pub fn changes_since(db: &Database, table: &str, since: i64) -> Result<ChangeSet> {
+    check_table(table)?;
+    let conn = db.get_conn()?;
+    let schema = schema_of(&conn, table)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT seq, row_id, deleted FROM {}.change_log
+         WHERE table_name = ?1 AND seq > ?2 ORDER BY seq",
+        schema
+    ))?;
+    let mut rows = stmt.query(rusqlite::params![table, since])?;
+
+    let mut changes = ChangeSet {
+        seq: since,
+        ..Default::default()
+    };
+    while let Some(row) = rows.next()? {
+        changes.seq = row.get(0)?;
+        if row.get::<_, bool>(2)? {
+            changes.deleted.push(row.get(1)?);
+        } else {
+            changes.modified.push(row.get(1)?);
+        }
+    }
+    Ok(changes)
+}
+
+/// Ids of rows in `table` created or changed at or after `since` (Unix seconds)
+///
+/// The bound is inclusive because `updated_at` has second resolution: rows
+/// changed in the same second as the previous call are returned again and
+/// callers must de-duplicate. Deleted rows are not reported; use
+/// `changes_since` to observe removals.
+pub fn modified_ids_since(db: &Database, table: &str, since: i64) -> Result<Vec<String>> {
+    check_table(table)?;
+    let conn = db.get_conn()?;
+    // Table name is validated against TIMESTAMPED_TABLES above
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id FROM {} WHERE updated_at >= ?1 ORDER BY updated_at, id",
+        table
+    ))?;
+    let ids = stmt
+        .query_map([since], |row| row.get(0))?
+        .collect::<std::result::Result<Vec<String>, _>>()?;
+    Ok(ids)
+}
+
+/// Timestamp of the last successful sync of `table`, if any
+pub fn last_sync(db: &Database, table: &str) -> Result<Option<i64>> {
+    check_table(table)?;
+    let conn = db.get_conn()?;
+    let last = conn
+        .query_row(
+            "SELECT last_sync FROM sync_metadata WHERE table_name = ?1",
+            [table],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(last)
+}
+
+/// Change sequence number the last successful sync of `table` covered
+pub fn last_sync_seq(db: &Database, table: &str) -> Result<i64> {
+    check_table(table)?;
+    let conn = db.get_conn()?;
+    let seq = conn
+        .query_row(
+            "SELECT change_seq FROM sync_metadata WHERE table_name = ?1",
+            [table],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(seq.unwrap_or(0))
+}
+
+/// Record a successful sync of `table` at `timestamp`, covering changes up
+/// to `seq` (the `ChangeSet::seq` that was sent), and publish `SyncCompleted`
+pub fn record_sync(db: &Database, table: &str, timestamp: i64, seq: i64) -> Result<()> {
+    check_table(table)?;
+    let conn = db.get_conn()?;
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO sync_metadata (table_name, last_sync, record_count, change_seq)
+             VALUES (?1, ?2, (SELECT COUNT(*) FROM {}), ?3)",
+            table
+        ),
+        rusqlite::params![table, timestamp, seq],
+    )?;
+    db.publish(Event::SyncCompleted {
+        table: table.to_string(),
+        timestamp,
+    });
+    Ok(())
+}
+
+/// Number of rows changed or deleted since the last sync, per tracked table
+pub fn pending_changes(db: &Database) -> Result<Vec<(String, usize)>> {
+    let mut pending = Vec::new();
+    for table in TIMESTAMPED_TABLES {
+        let count = changes_since(db, table, last_sync_seq(db, table)?)?.len();
+        if count > 0 {
+            pending.push((table.to_string(), count));
+        }
+    }
+    Ok(pending)
+}
+
+/// Install the triggers feeding `change_log` on the tracked tables in `schema`
+///
+/// Tables that already had them are left alone; the rows of a table seen
+/// for the first time are logged as modified so a first sync picks them up.
+pub(crate) fn track_changes(conn: &Connection, schema: &str) -> Result<()> {
+    for table in TIMESTAMPED_TABLES {
+        let present: (bool, bool) = conn.query_row(
+            &format!(
+                "SELECT EXISTS(SELECT 1 FROM {schema}.sqlite_master WHERE type = 'table' AND name = ?1),
+                        EXISTS(SELECT 1 FROM {schema}.sqlite_master WHERE type = 'trigger' AND name = ?2)"
+            ),
+            [table.to_string(), format!("trg_{}_log_insert", table)],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if !present.0 || present.1 {
+            continue;
+        }
+
+        // Each trigger replaces the row's previous entry rather than relying
+        // on a conflict clause, which the firing statement's OR IGNORE etc.
+        // would override
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER {schema}.trg_{table}_log_insert AFTER INSERT ON {table}
+             BEGIN
+                 DELETE FROM change_log WHERE table_name = '{table}' AND row_id = NEW.id;
+                 INSERT INTO change_log (table_name, row_id) VALUES ('{table}', NEW.id);
+             END;
+             CREATE TRIGGER {schema}.trg_{table}_log_update AFTER UPDATE ON {table}
+             BEGIN
+                 DELETE FROM change_log WHERE table_name = '{table}' AND row_id IN (OLD.id, NEW.id);
+                 INSERT INTO change_log (table_name, row_id, deleted)
+                     SELECT '{table}', OLD.id, 1 WHERE OLD.id IS NOT NEW.id;
+                 INSERT INTO change_log (table_name, row_id) VALUES ('{table}', NEW.id);
+             END;
+             CREATE TRIGGER {schema}.trg_{table}_log_delete AFTER DELETE ON {table}
+             BEGIN
+                 DELETE FROM change_log WHERE table_name = '{table}' AND row_id = OLD.id;
+                 INSERT INTO change_log (table_name, row_id, deleted) VALUES ('{table}', OLD.id, 1);
+             END;
+             INSERT INTO {schema}.change_log (table_name, row_id)
+                 SELECT '{table}', id FROM {schema}.{table}
+                 WHERE id NOT IN (SELECT row_id FROM {schema}.change_log WHERE table_name = '{table}')
+                 ORDER BY updated_at, id;"
+        ))?;
+    }
+    Ok(())
+}
+
+/// Schema holding `table`: the user-data database once the table moved there
+fn schema_of(conn: &Connection, table: &str) -> Result<&'static str> {
+    if USER_TABLES.contains(&table) {
+        let moved: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_list WHERE schema = ?1 AND name = ?2)",
+            [USER_SCHEMA, table],
+            |row| row.get(0),
+        )?;
+        if moved {
+            return Ok(USER_SCHEMA);
+        }
+    }
+    Ok("main")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::flight::repository::FlightPlanRepository;
+    use crate::flight::FlightPlanBuilder;
+    use crate::Config;
+    use std::sync::Arc;
+    use tempfile::{tempdir, NamedTempFile};
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    #[test]
+    fn test_pending_changes_since_last_sync() {
+        let (db, _temp_file) = setup_test_db();
+
+        let conn = db.get_conn().unwrap();
+        conn.execute_batch(
+            "INSERT INTO waypoints (id, name, latitude, longitude, created_at)
+             VALUES ('WP1', 'AAA', 0.0, 0.0, 50), ('WP2', 'BBB', 1.0, 1.0, 150);",
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_eq!(pending_changes(&db).unwrap(), vec![("waypoints".to_string(), 2)]);
+
+        let sent = changes_since(&db, "waypoints", last_sync_seq(&db, "waypoints").unwrap()).unwrap();
+        record_sync(&db, "waypoints", 100, sent.seq).unwrap();
+        assert_eq!(last_sync(&db, "waypoints").unwrap(), Some(100));
+        assert!(pending_changes(&db).unwrap().is_empty());
+        assert_eq!(modified_ids_since(&db, "waypoints", 100).unwrap(), vec!["WP2"]);
+
+        assert!(modified_ids_since(&db, "devices; DROP TABLE waypoints", 0).is_err());
+    }
+
+    #[test]
+    fn test_changes_within_one_second_and_deletes() {
+        let (db, _temp_file) = setup_test_db();
+        let conn = db.get_conn().unwrap();
+        conn.execute_batch(
+            "INSERT INTO waypoints (id, name, latitude, longitude, created_at)
+             VALUES ('WP1', 'AAA', 0.0, 0.0, 100), ('WP2', 'BBB', 1.0, 1.0, 100);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let first = changes_since(&db, "waypoints", 0).unwrap();
+        assert_eq!(first.modified, vec!["WP1", "WP2"]);
+
+        // Same second as the rows above: a timestamp cursor can't tell these apart
+        db.get_conn().unwrap().execute_batch(
+            "UPDATE waypoints SET name = 'AAB', updated_at = 100 WHERE id = 'WP1';
+             INSERT OR IGNORE INTO waypoints (id, name, latitude, longitude, created_at)
+             VALUES ('WP3', 'CCC', 2.0, 2.0, 100);
+             DELETE FROM waypoints WHERE id = 'WP2';",
+        )
+        .unwrap();
+
+        let next = changes_since(&db, "waypoints", first.seq).unwrap();
+        assert_eq!(next.modified, vec!["WP1", "WP3"]);
+        assert_eq!(next.deleted, vec!["WP2"]);
+        assert!(changes_since(&db, "waypoints", next.seq).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_user_tables_tracked_in_user_database() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            db_path: dir.path().join("navdata.db"),
+            user_db_path: Some(dir.path().join("user.db")),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let clock = Arc::new(MockClock::at_timestamp(1_000));
+        let db = Arc::new(Database::new(&config).unwrap().with_clock(clock.clone()));
+        db.migrate().unwrap();
+
+        let repo = FlightPlanRepository::new(Arc::clone(&db)).unwrap();
+        let plan = FlightPlanBuilder::new()
+            .departure("ZBAA")
+            .destination("ZSSS")
+            .cruise_altitude(35000)
+            .cruise_speed(450)
+            .build()
+            .unwrap();
+        let stored = repo.insert(&plan, None, None).unwrap();
+        let first = changes_since(&db, "flight_plans", 0).unwrap();
+        assert_eq!(first.modified, vec![stored.id.clone()]);
+
+        clock.advance(chrono::Duration::seconds(30));
+        let updated = repo.update(&stored.id, &plan, Some("Shuttle"), 1).unwrap();
+        assert_eq!(updated.updated_at, 1_030);
+        repo.delete(&stored.id, 2).unwrap();
+
+        let next = changes_since(&db, "flight_plans", first.seq).unwrap();
+        assert!(next.modified.is_empty());
+        assert_eq!(next.deleted, vec![stored.id]);
+
+        // Tracking survives reopening, and doesn't log the rows twice
+        drop(repo);
+        drop(db);
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        assert_eq!(changes_since(&db, "flight_plans", 0).unwrap().len(), 1);
+    }
+}
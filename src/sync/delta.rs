@@ -0,0 +1,338 @@
+use crate::db::schema::TIMESTAMPED_TABLES;
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::events::Event;
+use crate::import::{ImportControl, ImportPhase};
+use crate::navdata::cycle::{record_cycle, AiracCycle};
+use crate::navdata::{table_columns, NavdataDelta, NAVDATA_TABLES};
+use rsa::RsaPublicKey;
+use rusqlite::types::Value;
+
+/// Rows touched by applying a delta
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeltaSummary {
+    pub upserted: usize,
+    pub deleted: usize,
+}
+
+/// Verify a signed package and apply it
+pub fn apply_package(db: &Database, package: &[u8], public_key: &RsaPublicKey) -> Result<DeltaSummary> {
+    apply_package_with(db, package, public_key, &ImportControl::default())
+}
+
+/// Verify a signed package and apply it, reporting progress
+pub fn apply_package_with(
+    db: &Database,
+    package: &[u8],
+    public_key: &RsaPublicKey,
+    control: &ImportControl,
+) -> Result<DeltaSummary> {
+    control.report(ImportPhase::Parsing, 0, None)?;
+    let delta = NavdataDelta::decode(package, public_key)?;
+    apply_with(db, &delta, control)
+}
+
+/// Apply a navdata delta in a single transaction
+///
+/// Deletes run child tables first and upserts parent tables first, so foreign
+/// keys hold at every step. Upserts update in place rather than replacing,
+/// which keeps dependent rows from cascading away. When `to_cycle` is an
+/// AIRAC identifier it is recorded as the installed cycle.
+pub fn apply(db: &Database, delta: &NavdataDelta) -> Result<DeltaSummary> {
+    apply_with(db, delta, &ImportControl::default())
+}
+
+/// Apply a navdata delta, reporting each delete and upsert as a record
+///
+/// Cancellation leaves the database as it was before the delta.
+pub fn apply_with(db: &Database, delta: &NavdataDelta, control: &ImportControl) -> Result<DeltaSummary> {
+    let conn = db.get_background_conn()?;
+
+    for table in &delta.tables {
+        if !NAVDATA_TABLES.contains(&table.table.as_str()) {
+            return Err(AeroBaseError::Sync(format!(
+                "Delta touches non-navdata table {}",
+                table.table
+            )));
+        }
+        let known = table_columns(&conn, &table.table)?;
+        if let Some(unknown) = table.columns.iter().find(|c| !known.contains(c)) {
+            return Err(AeroBaseError::Sync(format!(
+                "Delta column {}.{} does not exist",
+                table.table, unknown
+            )));
+        }
+        if !table.upserts.is_empty() && !table.columns.iter().any(|c| c == "id") {
+            return Err(AeroBaseError::Sync(format!(
+                "Delta for {} has no id column",
+                table.table
+            )));
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let now = db.clock().timestamp();
+    let mut summary = DeltaSummary::default();
+    let total = Some(delta.change_count());
+    let mut processed = 0;
+    control.report(ImportPhase::Writing, processed, total)?;
+
+    for name in NAVDATA_TABLES.iter().rev() {
+        for table in delta.tables.iter().filter(|t| t.table == *name) {
+            // Table name is validated against NAVDATA_TABLES above
+            let mut stmt = tx.prepare(&format!("DELETE FROM {} WHERE id = ?1", table.table))?;
+            for id in &table.deletes {
+                summary.deleted += stmt.execute([id])?;
+                processed += 1;
+                control.report(ImportPhase::Writing, processed, total)?;
+            }
+        }
+    }
+
+    for name in NAVDATA_TABLES {
+        for table in delta.tables.iter().filter(|t| t.table == *name && !t.upserts.is_empty()) {
+            // updated_at is never part of a delta; stamp tracked tables with the apply time
+            let stamped = TIMESTAMPED_TABLES.contains(&table.table.as_str());
+            let mut columns = table.columns.clone();
+            if stamped {
+                columns.push("updated_at".to_string());
+            }
+            let placeholders: Vec<String> =
+                (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+            let assignments: Vec<String> = columns
+                .iter()
+                .filter(|c| *c != "id")
+                .map(|c| format!("{} = excluded.{}", c, c))
+                .collect();
+            // Column names are validated against the table's schema above
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+                table.table,
+                columns.join(", "),
+                placeholders.join(", "),
+                assignments.join(", ")
+            );
+
+            let mut stmt = tx.prepare(&sql)?;
+            for row in &table.upserts {
+                if row.len() != table.columns.len() {
+                    return Err(AeroBaseError::Sync(format!(
+                        "Delta row for {} has {} values for {} columns",
+                        table.table,
+                        row.len(),
+                        table.columns.len()
+                    )));
+                }
+                let stamp = stamped.then_some(Value::Integer(now));
+                summary.upserted +=
+                    stmt.execute(rusqlite::params_from_iter(row.iter().chain(stamp.as_ref())))?;
+                processed += 1;
+                control.report(ImportPhase::Writing, processed, total)?;
+            }
+        }
+    }
+
+    if delta.tables.iter().any(|t| t.table == "airports") {
+        crate::repository::airport::refresh_search_names(&tx)?;
+    }
+    if delta.tables.iter().any(|t| t.table == "airports" || t.table == "waypoints") {
+        crate::spatial::query::refresh_geohashes(&tx)?;
+    }
+    let cycle_changed = match AiracCycle::from_ident(&delta.to_cycle) {
+        Ok(cycle) => record_cycle(&tx, &cycle, now)?,
+        Err(_) => false,
+    };
+
+    control.report(ImportPhase::Committing, processed, total)?;
+    tx.commit()?;
+    if cycle_changed {
+        db.publish(Event::NavdataCycleChanged {
+            cycle: delta.to_cycle.clone(),
+        });
+    }
+
+    log::info!(
+        "Applied navdata delta {} -> {}: {} upsert(s), {} delete(s)",
+        delta.from_cycle.as_deref().unwrap_or("-"),
+        delta.to_cycle,
+        summary.upserted,
+        summary.deleted
+    );
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navdata::{diff, TableDelta};
+    use crate::Config;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> (Database, NamedTempFile) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 2,
+            ..Default::default()
+        };
+
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        (db, temp_file)
+    }
+
+    fn seed(db: &Database, sql: &str) {
+        db.get_conn().unwrap().execute_batch(sql).unwrap();
+    }
+
+    #[test]
+    fn test_apply_diff_converges() {
+        let (device, _device_file) = setup_test_db();
+        let (server, _server_file) = setup_test_db();
+
+        let base = "
+            INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                VALUES ('A', 'A', 39.0, 116.0, 'FIX', 0), ('B', 'B', 40.0, 116.0, 'FIX', 0);
+            INSERT INTO airways (id, name, type, created_at) VALUES ('W1', 'W1', 'HIGH', 0);
+            INSERT INTO airway_segments (id, airway_id, from_waypoint_id, to_waypoint_id, sequence, created_at)
+                VALUES ('S1', 'W1', 'A', 'B', 1, 0);
+        ";
+        seed(&device, base);
+        seed(&device, "INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                       VALUES ('OLD', 'OLD', 1.0, 1.0, 'FIX', 0)");
+        seed(&server, base);
+        seed(&server, "
+            UPDATE waypoints SET latitude = 39.5 WHERE id = 'A';
+            INSERT INTO waypoints (id, name, latitude, longitude, type, created_at)
+                VALUES ('C', 'C', 41.0, 116.0, 'FIX', 0);
+        ");
+
+        let delta = diff(&device, &server, Some("2401"), "2402").unwrap();
+        let summary = apply(&device, &delta).unwrap();
+        assert_eq!(summary, DeltaSummary { upserted: 2, deleted: 1 });
+
+        // Updating A in place must not cascade-delete its airway segment
+        let conn = device.get_conn().unwrap();
+        let segments: i64 = conn
+            .query_row("SELECT COUNT(*) FROM airway_segments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(segments, 1);
+        drop(conn);
+
+        assert!(diff(&device, &server, None, "2402").unwrap().is_empty());
+        assert_eq!(
+            crate::navdata::cycle::current_cycle(&device).unwrap().unwrap().ident,
+            "2402"
+        );
+    }
+
+    #[test]
+    fn test_hazards_between_cycles() {
+        let (device, _device_file) = setup_test_db();
+        let (server, _server_file) = setup_test_db();
+        seed(&server, "
+            INSERT INTO hazards (id, kind, name, valid_from, min_latitude, max_latitude,
+                min_longitude, max_longitude, created_at)
+                VALUES ('ASH1', 'VOLCANIC_ASH', 'Ash cloud', 0, 0.0, 1.0, 0.0, 1.0, 0);
+            INSERT INTO hazard_boundaries (id, hazard_id, latitude, longitude, sequence) VALUES
+                ('ASH1-0', 'ASH1', 0.0, 0.0, 0),
+                ('ASH1-1', 'ASH1', 1.0, 0.0, 1),
+                ('ASH1-2', 'ASH1', 1.0, 1.0, 2);
+        ");
+
+        // Not an AIRAC identifier, so the installed cycle is left alone
+        let delta = diff(&device, &server, None, "hazards-1").unwrap();
+        assert_eq!(apply(&device, &delta).unwrap(), DeltaSummary { upserted: 4, deleted: 0 });
+        assert!(crate::navdata::cycle::current_cycle(&device).unwrap().is_none());
+
+        seed(&server, "DELETE FROM hazards WHERE id = 'ASH1'");
+        let delta = diff(&device, &server, None, "hazards-2").unwrap();
+        apply(&device, &delta).unwrap();
+        let boundaries: i64 = device
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM hazard_boundaries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(boundaries, 0);
+    }
+
+    #[test]
+    fn test_rejects_unknown_tables_and_columns() {
+        let (db, _temp_file) = setup_test_db();
+        let mut delta = NavdataDelta {
+            from_cycle: None,
+            to_cycle: "2402".to_string(),
+            tables: vec![TableDelta {
+                table: "flight_plans".to_string(),
+                columns: vec!["id".to_string()],
+                upserts: vec![],
+                deletes: vec!["X".to_string()],
+            }],
+        };
+        assert!(matches!(apply(&db, &delta), Err(AeroBaseError::Sync(_))));
+
+        delta.tables[0].table = "waypoints".to_string();
+        delta.tables[0].columns = vec!["id; DROP TABLE waypoints".to_string()];
+        assert!(matches!(apply(&db, &delta), Err(AeroBaseError::Sync(_))));
+    }
+
+    #[test]
+    fn test_cancel_rolls_back() {
+        use crate::import::{ImportControl, ImportPhase};
+        use rusqlite::types::Value;
+
+        let (db, _temp_file) = setup_test_db();
+        let rows = (0..500)
+            .map(|i| {
+                vec![
+                    Value::Text(format!("WP{}", i)),
+                    Value::Text(format!("WP{}", i)),
+                    Value::Real(30.0),
+                    Value::Real(116.0 + i as f64 * 0.001),
+                    Value::Integer(0),
+                ]
+            })
+            .collect();
+        let delta = NavdataDelta {
+            from_cycle: None,
+            to_cycle: "2402".to_string(),
+            tables: vec![TableDelta {
+                table: "waypoints".to_string(),
+                columns: ["id", "name", "latitude", "longitude", "created_at"]
+                    .map(String::from)
+                    .to_vec(),
+                upserts: rows,
+                deletes: vec![],
+            }],
+        };
+
+        // Cancel from the progress callback part-way through the writes
+        let token = crate::import::CancellationToken::new();
+        let canceller = token.clone();
+        let (control, progress) = ImportControl::new()
+            .with_cancellation(token)
+            .on_progress(move |p| {
+                if p.processed >= 200 {
+                    canceller.cancel();
+                }
+            })
+            .watch();
+        assert!(matches!(apply_with(&db, &delta, &control), Err(AeroBaseError::Cancelled(_))));
+        assert_eq!(progress.borrow().phase, ImportPhase::Writing);
+        assert_eq!(progress.borrow().total, Some(500));
+
+        let count: i64 = db
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM waypoints", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(crate::navdata::cycle::current_cycle(&db).unwrap().is_none());
+
+        let (control, progress) = ImportControl::new().watch();
+        assert_eq!(apply_with(&db, &delta, &control).unwrap().upserted, 500);
+        assert_eq!(progress.borrow().phase, ImportPhase::Committing);
+    }
+}
@@ -1,5 +1,12 @@
+pub mod backup;
+pub mod changes;
+pub mod delta;
+pub mod scheduler;
 pub mod client;
 pub mod strategy;
+pub mod transport;
+
+pub use scheduler::{SyncConfig, SyncScheduler, SyncWindow};
 
 use crate::error::Result;
 
@@ -0,0 +1,233 @@
+use crate::clock::{self, Clock};
+use crate::error::{AeroBaseError, Result};
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta};
+use std::io::Read;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A daily time window (local time) during which sync may run
+///
+/// `end` before `start` means the window wraps past midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl SyncWindow {
+    /// Create a window from `start` (inclusive) to `end` (exclusive)
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { start, end }
+    }
+
+    /// Whether `time` falls inside the window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Sync bandwidth and scheduling limits
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncConfig {
+    /// Maximum transfer rate in bytes per second, None for unlimited
+    pub max_bandwidth: Option<u64>,
+    /// Allowed local-time windows; empty means sync may run at any time
+    pub windows: Vec<SyncWindow>,
+}
+
+/// Enforces `SyncConfig` around sync runs
+pub struct SyncScheduler {
+    config: SyncConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl SyncScheduler {
+    /// Create a scheduler from configuration
+    pub fn new(config: SyncConfig) -> Self {
+        Self {
+            config,
+            clock: clock::system(),
+        }
+    }
+
+    /// Check windows against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether sync may run at local time `now`
+    pub fn is_open_at(&self, now: NaiveDateTime) -> bool {
+        self.config.windows.is_empty() || self.config.windows.iter().any(|w| w.contains(now.time()))
+    }
+
+    /// Time until the next window opens, zero if one is open at `now`
+    pub fn until_open(&self, now: NaiveDateTime) -> Duration {
+        if self.is_open_at(now) {
+            return Duration::ZERO;
+        }
+
+        self.config
+            .windows
+            .iter()
+            .map(|w| {
+                let mut start = now.date().and_time(w.start);
+                if start <= now {
+                    start += TimeDelta::days(1);
+                }
+                (start - now).to_std().unwrap_or_default()
+            })
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Run `sync` only if a window is open now
+    pub fn run_if_open<T>(&self, sync: impl FnOnce() -> Result<T>) -> Result<T> {
+        let now = self.clock.local_now();
+        if !self.is_open_at(now) {
+            return Err(AeroBaseError::Sync(format!(
+                "Outside allowed sync window, next opens in {} min",
+                self.until_open(now).as_secs() / 60
+            )));
+        }
+        sync()
+    }
+
+    /// Sleep until a window is open, then run `sync`
+    pub async fn run_in_window<T>(&self, sync: impl FnOnce() -> Result<T>) -> Result<T> {
+        let wait = self.until_open(self.clock.local_now());
+        if !wait.is_zero() {
+            log::info!("Waiting {} min for the next sync window", wait.as_secs() / 60);
+            tokio::time::sleep(wait).await;
+        }
+        sync()
+    }
+
+    /// Wrap a download stream so it honours `max_bandwidth`
+    pub fn throttle<R: Read>(&self, inner: R) -> ThrottledReader<R> {
+        ThrottledReader::new(inner, self.config.max_bandwidth)
+    }
+}
+
+/// Reader that sleeps to keep its average rate under a byte-per-second cap
+pub struct ThrottledReader<R> {
+    inner: R,
+    bytes_per_sec: Option<u64>,
+    started: Instant,
+    transferred: u64,
+}
+
+impl<R: Read> ThrottledReader<R> {
+    /// Wrap `inner`; `None` disables throttling
+    pub fn new(inner: R, bytes_per_sec: Option<u64>) -> Self {
+        Self {
+            inner,
+            bytes_per_sec: bytes_per_sec.filter(|&b| b > 0),
+            started: Instant::now(),
+            transferred: 0,
+        }
+    }
+
+    /// Bytes read so far
+    pub fn transferred(&self) -> u64 {
+        self.transferred
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(rate) = self.bytes_per_sec else {
+            let n = self.inner.read(buf)?;
+            self.transferred += n as u64;
+            return Ok(n);
+        };
+
+        // Never pull more than one second's worth at a time
+        let limit = buf.len().min(rate as usize).max(1);
+        let n = self.inner.read(&mut buf[..limit])?;
+        self.transferred += n as u64;
+
+        let due = Duration::from_secs_f64(self.transferred as f64 / rate as f64);
+        let elapsed = self.started.elapsed();
+        if due > elapsed {
+            std::thread::sleep(due - elapsed);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_windows() {
+        let scheduler = SyncScheduler::new(SyncConfig {
+            max_bandwidth: None,
+            windows: vec![
+                SyncWindow::new(time(2, 0), time(4, 0)),
+                SyncWindow::new(time(23, 0), time(0, 30)),
+            ],
+        });
+
+        assert!(scheduler.is_open_at(at(2, 0)));
+        assert!(!scheduler.is_open_at(at(4, 0)));
+        assert!(scheduler.is_open_at(at(0, 15)));
+        assert!(scheduler.is_open_at(at(23, 30)));
+
+        assert_eq!(scheduler.until_open(at(3, 0)), Duration::ZERO);
+        assert_eq!(scheduler.until_open(at(1, 0)), Duration::from_secs(3600));
+        assert_eq!(scheduler.until_open(at(22, 0)), Duration::from_secs(3600));
+        assert_eq!(scheduler.until_open(at(5, 0)), Duration::from_secs(18 * 3600));
+
+        let always = SyncScheduler::new(SyncConfig::default());
+        assert!(always.is_open_at(at(12, 0)));
+        assert!(always.run_if_open(|| Ok(1)).is_ok());
+    }
+
+    #[test]
+    fn test_run_if_open_uses_clock() {
+        let clock = Arc::new(crate::clock::MockClock::new(at(1, 0).and_utc()));
+        let scheduler = SyncScheduler::new(SyncConfig {
+            max_bandwidth: None,
+            windows: vec![SyncWindow::new(time(2, 0), time(4, 0))],
+        })
+        .with_clock(clock.clone());
+
+        match scheduler.run_if_open(|| Ok(1)) {
+            Err(AeroBaseError::Sync(message)) => assert!(message.contains("60 min")),
+            other => panic!("expected sync window error, got {:?}", other),
+        }
+        clock.advance(TimeDelta::hours(1));
+        assert_eq!(scheduler.run_if_open(|| Ok(1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_throttled_reader() {
+        let data = vec![0u8; 300];
+        let mut reader = ThrottledReader::new(data.as_slice(), Some(1000));
+        let start = Instant::now();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+
+        assert_eq!(out.len(), 300);
+        assert_eq!(reader.transferred(), 300);
+        assert!(start.elapsed() >= Duration::from_millis(290));
+    }
+}
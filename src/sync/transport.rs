@@ -0,0 +1,54 @@
+use crate::error::Result;
+
+/// Fetches data from a remote service for the feed adapters (NOTAMs, ATIS)
+///
+/// The crate ships no HTTP client; the host application plugs in its own,
+/// adding whatever the service requires (base URL, API keys, retries).
+pub trait SyncTransport: Send + Sync {
+    /// GET `path` with `query` parameters and return the response body
+    fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Vec<u8>>;
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Path and query of a recorded request
+    pub(crate) type Request = (String, Vec<(String, String)>);
+
+    /// Serves canned bodies by path and records every request
+    #[derive(Default)]
+    pub(crate) struct CannedTransport {
+        pub(crate) bodies: HashMap<String, Vec<Vec<u8>>>,
+        pub(crate) requests: Mutex<Vec<Request>>,
+    }
+
+    impl CannedTransport {
+        /// Respond to successive requests for `path` with `bodies` in order
+        pub(crate) fn with(mut self, path: &str, bodies: Vec<&str>) -> Self {
+            self.bodies.insert(
+                path.to_string(),
+                bodies.into_iter().map(|b| b.as_bytes().to_vec()).collect(),
+            );
+            self
+        }
+    }
+
+    impl SyncTransport for CannedTransport {
+        fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Vec<u8>> {
+            let mut requests = self.requests.lock().unwrap();
+            let served = requests.iter().filter(|(p, _)| p == path).count();
+            requests.push((
+                path.to_string(),
+                query.iter().map(|(k, v)| (k.to_string(), v.clone())).collect(),
+            ));
+            self.bodies
+                .get(path)
+                .and_then(|bodies| bodies.get(served.min(bodies.len().saturating_sub(1))))
+                .cloned()
+                .ok_or_else(|| crate::error::AeroBaseError::NotFound(path.to_string()))
+        }
+    }
+}
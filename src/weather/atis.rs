@@ -0,0 +1,233 @@
+use crate::db::query::normalize_code;
+use crate::error::{AeroBaseError, Result};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use rusqlite::{Connection, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Path prefix of the D-ATIS endpoint; the station code is appended
+pub const DATIS_PATH: &str = "api";
+
+/// Which traffic a broadcast is for; busy airports split arrival and departure ATIS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AtisKind {
+    Combined,
+    Arrival,
+    Departure,
+}
+
+impl AtisKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AtisKind::Combined => "combined",
+            AtisKind::Arrival => "arr",
+            AtisKind::Departure => "dep",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "combined" => Some(AtisKind::Combined),
+            "arr" | "arrival" => Some(AtisKind::Arrival),
+            "dep" | "departure" => Some(AtisKind::Departure),
+            _ => None,
+        }
+    }
+}
+
+/// An ATIS broadcast in text form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Atis {
+    pub station: String,
+    pub kind: AtisKind,
+    /// Information letter (A-Z), when it could be read
+    pub letter: Option<char>,
+    pub text: String,
+    /// Observation time from the broadcast (Unix seconds), or `received_at`
+    pub issued_at: i64,
+    pub received_at: i64,
+}
+
+impl Atis {
+    /// Build from broadcast text, reading the letter and time from it
+    pub fn new(station: &str, kind: AtisKind, text: &str, received: DateTime<Utc>) -> Self {
+        let text = text.trim().to_string();
+        Self {
+            station: normalize_code(station),
+            kind,
+            letter: information_letter(&text),
+            issued_at: observation_time(&text, received).unwrap_or(received.timestamp()),
+            received_at: received.timestamp(),
+            text,
+        }
+    }
+}
+
+/// Parse a D-ATIS response: a JSON array of
+/// `{"airport", "type": "combined" | "arr" | "dep", "code", "datis"}`
+pub fn parse_datis(body: &[u8], received: DateTime<Utc>) -> Result<Vec<Atis>> {
+    let json: Value = serde_json::from_slice(body)
+        .map_err(|e| AeroBaseError::InvalidInput(format!("Invalid D-ATIS response: {}", e)))?;
+    if let Some(error) = json.get("error").and_then(Value::as_str) {
+        return Err(AeroBaseError::NotFound(format!("D-ATIS: {}", error)));
+    }
+    let items = json
+        .as_array()
+        .ok_or_else(|| AeroBaseError::InvalidInput("D-ATIS response is not a list".into()))?;
+
+    let mut broadcasts = Vec::with_capacity(items.len());
+    for item in items {
+        let field = |name: &str| item.get(name).and_then(Value::as_str).map(str::trim);
+        let (Some(station), Some(text)) = (field("airport"), field("datis")) else {
+            log::warn!("Skipping D-ATIS item without airport or text");
+            continue;
+        };
+        let kind = field("type").and_then(AtisKind::from_str).unwrap_or(AtisKind::Combined);
+        let mut atis = Atis::new(station, kind, text, received);
+        if let Some(code) = field("code").and_then(|c| c.chars().next()) {
+            atis.letter = Some(code.to_ascii_uppercase());
+        }
+        broadcasts.push(atis);
+    }
+    Ok(broadcasts)
+}
+
+/// Cache `broadcasts`, one per station and kind; `replace` drops everything
+/// cached for that station first, so a fetch result becomes the full set
+pub(crate) fn store(
+    conn: &Connection,
+    broadcasts: &[Atis],
+    replace: Option<&str>,
+) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    if let Some(station) = replace {
+        tx.execute("DELETE FROM atis WHERE station = ?1", [normalize_code(station)])?;
+    }
+    for atis in broadcasts {
+        tx.execute(
+            "INSERT OR REPLACE INTO atis (id, station, kind, letter, text, issued_at, received_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                format!("{} {}", atis.station, atis.kind.as_str()),
+                atis.station,
+                atis.kind.as_str(),
+                atis.letter.map(String::from),
+                atis.text,
+                atis.issued_at,
+                atis.received_at
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(broadcasts.len())
+}
+
+/// Cached broadcasts for `station`: combined, arrival, departure
+pub(crate) fn latest(conn: &Connection, station: &str) -> Result<Vec<Atis>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT station, kind, letter, text, issued_at, received_at FROM atis
+         WHERE station = ?1
+         ORDER BY CASE kind WHEN 'combined' THEN 0 WHEN 'arr' THEN 1 ELSE 2 END",
+    )?;
+    let broadcasts = stmt
+        .query_map([normalize_code(station)], atis_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(broadcasts)
+}
+
+pub(crate) fn prune(conn: &Connection, before: i64) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM atis WHERE received_at < ?1", [before])?)
+}
+
+fn atis_from_row(row: &Row) -> rusqlite::Result<Atis> {
+    let kind: String = row.get(1)?;
+    let letter: Option<String> = row.get(2)?;
+    Ok(Atis {
+        station: row.get(0)?,
+        kind: AtisKind::from_str(&kind).unwrap_or(AtisKind::Combined),
+        letter: letter.and_then(|l| l.chars().next()),
+        text: row.get(3)?,
+        issued_at: row.get(4)?,
+        received_at: row.get(5)?,
+    })
+}
+
+/// `... INFORMATION B ...` or `... INFO B ...`
+fn information_letter(text: &str) -> Option<char> {
+    let mut words = text.split_whitespace();
+    while let Some(word) = words.next() {
+        if matches!(word, "INFO" | "INFORMATION") {
+            let next = words.next()?.trim_end_matches(['.', ',']);
+            let mut chars = next.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+                _ => None,
+            };
+        }
+    }
+    None
+}
+
+/// First `hhmmZ` group, on the latest day that doesn't put it in the future
+fn observation_time(text: &str, received: DateTime<Utc>) -> Option<i64> {
+    let group = text
+        .split_whitespace()
+        .map(|w| w.trim_end_matches(['.', ',']))
+        .find(|w| {
+            w.len() == 5 && w.ends_with('Z') && w[..4].chars().all(|c| c.is_ascii_digit())
+        })?;
+    let time = NaiveTime::parse_from_str(&group[..4], "%H%M").ok()?;
+    let mut issued = received.date_naive().and_time(time).and_utc();
+    // Allow for clock skew between the broadcast and the device
+    if issued > received + Duration::minutes(10) {
+        issued -= Duration::days(1);
+    }
+    Some(issued.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_datis() {
+        let received = Utc.with_ymd_and_hms(2026, 10, 17, 0, 5, 0).unwrap();
+        let body = r#"[
+            {"airport": "KJFK", "type": "arr", "code": "A",
+             "datis": "JFK ARR INFO A 2351Z. 31012KT 10SM FEW250. ILS RWY 4R APCH IN USE."},
+            {"airport": "KJFK", "type": "dep", "code": "b",
+             "datis": "JFK DEP INFO B 0001Z. DEPG RWY 4L."},
+            {"airport": "KJFK", "type": "dep"}
+        ]"#;
+        let atis = parse_datis(body.as_bytes(), received).unwrap();
+        assert_eq!(atis.len(), 2);
+        assert_eq!((atis[0].kind, atis[0].letter), (AtisKind::Arrival, Some('A')));
+        // 2351Z is the evening before
+        let previous = Utc.with_ymd_and_hms(2026, 10, 16, 23, 51, 0).unwrap();
+        assert_eq!(atis[0].issued_at, previous.timestamp());
+        assert_eq!((atis[1].kind, atis[1].letter), (AtisKind::Departure, Some('B')));
+        assert_eq!(atis[1].received_at, received.timestamp());
+
+        assert!(parse_datis(br#"{"error": "Station not found"}"#, received).is_err());
+        assert!(parse_datis(b"<html>", received).is_err());
+    }
+
+    #[test]
+    fn test_new_from_text() {
+        let received = Utc.with_ymd_and_hms(2026, 10, 17, 6, 2, 0).unwrap();
+        let atis = Atis::new(
+            "zbaa",
+            AtisKind::Combined,
+            "BEIJING INFORMATION K, 0600Z, RWY 36R",
+            received,
+        );
+        assert_eq!(atis.station, "ZBAA");
+        assert_eq!(atis.letter, Some('K'));
+        assert_eq!(atis.issued_at, received.timestamp() - 120);
+
+        let untimed = Atis::new("ZBAA", AtisKind::Combined, "RWY 36R IN USE", received);
+        assert_eq!((untimed.letter, untimed.issued_at), (None, received.timestamp()));
+    }
+}
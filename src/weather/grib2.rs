@@ -0,0 +1,407 @@
+use super::winds::{wind_from_components, WindsAloft};
+use crate::error::{AeroBaseError, Result};
+use crate::models::Coordinate;
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// Fields read from a GRIB2 file; everything else is skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GribParameter {
+    /// Eastward wind, m/s
+    WindU,
+    /// Northward wind, m/s
+    WindV,
+    /// Kelvin
+    Temperature,
+}
+
+impl GribParameter {
+    /// Discipline 0 (meteorological), table 4.2 category and number
+    fn from_codes(discipline: u8, category: u8, number: u8) -> Option<Self> {
+        match (discipline, category, number) {
+            (0, 2, 2) => Some(GribParameter::WindU),
+            (0, 2, 3) => Some(GribParameter::WindV),
+            (0, 0, 0) => Some(GribParameter::Temperature),
+            _ => None,
+        }
+    }
+}
+
+/// Regular latitude/longitude grid (template 3.0)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLonGrid {
+    pub first: Coordinate,
+    pub ni: usize,
+    pub nj: usize,
+    /// Signed steps in degrees along a row and between rows
+    pub di: f64,
+    pub dj: f64,
+}
+
+impl LatLonGrid {
+    /// Position of the `index`th value, rows of `ni` points
+    pub fn position(&self, index: usize) -> Coordinate {
+        let (i, j) = (index % self.ni, index / self.ni);
+        let longitude = (self.first.longitude + i as f64 * self.di + 180.0).rem_euclid(360.0) - 180.0;
+        Coordinate::new(self.first.latitude + j as f64 * self.dj, longitude)
+    }
+}
+
+/// One decoded field on an isobaric surface
+#[derive(Debug, Clone)]
+pub struct GribField {
+    pub parameter: GribParameter,
+    pub level_hpa: i32,
+    pub valid_time: i64,
+    pub grid: LatLonGrid,
+    /// `None` where the bitmap marks a missing value
+    pub values: Vec<Option<f64>>,
+}
+
+fn invalid(message: impl Into<String>) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid GRIB2: {}", message.into()))
+}
+
+fn uint(bytes: &[u8], offset: usize, len: usize) -> Result<u64> {
+    let slice = bytes
+        .get(offset..offset + len)
+        .ok_or_else(|| invalid("section truncated"))?;
+    Ok(slice.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+}
+
+/// GRIB2 stores signed integers as sign and magnitude
+fn signed(bytes: &[u8], offset: usize, len: usize) -> Result<i64> {
+    let raw = uint(bytes, offset, len)?;
+    let sign_bit = 1u64 << (len * 8 - 1);
+    let magnitude = (raw & !sign_bit) as i64;
+    Ok(if raw & sign_bit != 0 { -magnitude } else { magnitude })
+}
+
+/// Reads `bits`-wide unsigned values packed MSB first
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: u8) -> Result<u64> {
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let byte = self
+                .data
+                .get(self.position / 8)
+                .ok_or_else(|| invalid("data section truncated"))?;
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = value << 1 | bit as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Default)]
+struct MessageState<'a> {
+    discipline: u8,
+    reference_time: i64,
+    grid: Option<LatLonGrid>,
+    /// Parameter, level and forecast offset; `None` for unsupported products
+    product: Option<(GribParameter, i32, i64)>,
+    packing: Option<(f32, i64, i64, u8)>,
+    bitmap: Option<&'a [u8]>,
+}
+
+fn reference_time(section: &[u8]) -> Result<i64> {
+    let field = |offset| uint(section, offset, 1).map(|v| v as u32);
+    let date = NaiveDate::from_ymd_opt(uint(section, 12, 2)? as i32, field(14)?, field(15)?)
+        .and_then(|d| d.and_hms_opt(field(16).ok()?, field(17).ok()?, field(18).ok()?))
+        .ok_or_else(|| invalid("bad reference time"))?;
+    Ok(date.and_utc().timestamp())
+}
+
+/// Section 3; only template 3.0 grids without scan reordering are supported
+fn grid(section: &[u8]) -> Result<Option<LatLonGrid>> {
+    if uint(section, 12, 2)? != 0 {
+        return Ok(None);
+    }
+    let scanning = uint(section, 71, 1)?;
+    if scanning & 0x20 != 0 {
+        return Ok(None);
+    }
+    let micro = |offset| signed(section, offset, 4).map(|v| v as f64 * 1e-6);
+    let di = micro(63)?;
+    let dj = micro(67)?;
+    Ok(Some(LatLonGrid {
+        first: Coordinate::new(micro(46)?, micro(50)?),
+        ni: uint(section, 30, 4)? as usize,
+        nj: uint(section, 34, 4)? as usize,
+        di: if scanning & 0x80 != 0 { -di } else { di },
+        dj: if scanning & 0x40 != 0 { dj } else { -dj },
+    }))
+}
+
+/// Section 4; only template 4.0 on isobaric surfaces (type 100, in Pa)
+fn product(section: &[u8], discipline: u8) -> Result<Option<(GribParameter, i32, i64)>> {
+    if uint(section, 7, 2)? != 0 {
+        return Ok(None);
+    }
+    let category = uint(section, 9, 1)? as u8;
+    let number = uint(section, 10, 1)? as u8;
+    let Some(parameter) = GribParameter::from_codes(discipline, category, number) else {
+        return Ok(None);
+    };
+    if uint(section, 22, 1)? != 100 {
+        return Ok(None);
+    }
+    let scale = signed(section, 23, 1)? as i32;
+    let pascals = uint(section, 24, 4)? as f64 / 10f64.powi(scale);
+    let forecast = uint(section, 18, 4)? as i64;
+    let offset = match uint(section, 17, 1)? {
+        0 => Duration::minutes(forecast),
+        1 => Duration::hours(forecast),
+        2 => Duration::days(forecast),
+        _ => return Ok(None),
+    };
+    Ok(Some((parameter, (pascals / 100.0).round() as i32, offset.num_seconds())))
+}
+
+/// Section 5; only simple packing (template 5.0)
+fn packing(section: &[u8]) -> Result<Option<(f32, i64, i64, u8)>> {
+    if uint(section, 9, 2)? != 0 {
+        return Ok(None);
+    }
+    let reference = f32::from_bits(uint(section, 11, 4)? as u32);
+    let binary_scale = signed(section, 15, 2)?;
+    let decimal_scale = signed(section, 17, 2)?;
+    Ok(Some((reference, binary_scale, decimal_scale, uint(section, 19, 1)? as u8)))
+}
+
+fn unpack(state: &MessageState, data: &[u8]) -> Result<Option<GribField>> {
+    let (Some(grid), Some((parameter, level_hpa, offset)), Some((reference, binary, decimal, bits))) =
+        (state.grid, state.product, state.packing)
+    else {
+        return Ok(None);
+    };
+    let points = grid.ni * grid.nj;
+    let scale = 2f64.powi(binary as i32);
+    let divisor = 10f64.powi(decimal as i32);
+    let mut reader = BitReader { data, position: 0 };
+    let mut values = Vec::with_capacity(points);
+    for index in 0..points {
+        let present = state.bitmap.is_none_or(|bitmap| {
+            bitmap.get(index / 8).is_some_and(|b| b >> (7 - index % 8) & 1 == 1)
+        });
+        if present {
+            let packed = if bits == 0 { 0 } else { reader.read(bits)? };
+            values.push(Some((reference as f64 + packed as f64 * scale) / divisor));
+        } else {
+            values.push(None);
+        }
+    }
+    Ok(Some(GribField {
+        parameter,
+        level_hpa,
+        valid_time: state.reference_time + offset,
+        grid,
+        values,
+    }))
+}
+
+/// Decode the supported fields from a GRIB2 file of one or more messages
+///
+/// U/V wind and temperature on isobaric levels, regular lat/lon grids and
+/// simple packing are read; other products in the file are skipped.
+pub fn decode(bytes: &[u8]) -> Result<Vec<GribField>> {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        if bytes.get(offset..offset + 4) != Some(b"GRIB".as_slice()) {
+            return Err(invalid(format!("missing GRIB indicator at byte {}", offset)));
+        }
+        if uint(bytes, offset + 7, 1)? != 2 {
+            return Err(invalid("only edition 2 is supported"));
+        }
+        let length = uint(bytes, offset + 8, 8)? as usize;
+        let message = bytes
+            .get(offset..offset + length)
+            .ok_or_else(|| invalid("message truncated"))?;
+        let mut state = MessageState {
+            discipline: message[6],
+            ..Default::default()
+        };
+
+        let mut position = 16;
+        while message.get(position..position + 4) != Some(b"7777".as_slice()) {
+            let section_length = uint(message, position, 4)? as usize;
+            let section = message
+                .get(position..position + section_length)
+                .filter(|_| section_length > 4)
+                .ok_or_else(|| invalid("section truncated"))?;
+            match section[4] {
+                1 => state.reference_time = reference_time(section)?,
+                3 => state.grid = grid(section)?,
+                4 => state.product = product(section, state.discipline)?,
+                5 => state.packing = packing(section)?,
+                6 => match section[5] {
+                    0 => state.bitmap = Some(&section[6..]),
+                    255 => state.bitmap = None,
+                    // 254 reuses the previous bitmap
+                    254 => {}
+                    _ => return Err(invalid("predefined bitmaps are not supported")),
+                },
+                7 => fields.extend(unpack(&state, &section[5..])?),
+                _ => {}
+            }
+            position += section_length;
+        }
+        offset += length;
+    }
+    Ok(fields)
+}
+
+/// Combine U, V and temperature fields into winds-aloft grid points
+///
+/// Points need both wind components; temperature is attached when a field
+/// for the same level and time is present on the same grid.
+pub fn winds_aloft(fields: &[GribField]) -> Vec<WindsAloft> {
+    let mut by_key: HashMap<(i64, i32), HashMap<GribParameter, &GribField>> = HashMap::new();
+    for field in fields {
+        by_key
+            .entry((field.valid_time, field.level_hpa))
+            .or_default()
+            .insert(field.parameter, field);
+    }
+
+    let mut points = Vec::new();
+    for ((valid_time, level_hpa), parameters) in by_key {
+        let (Some(u), Some(v)) = (parameters.get(&GribParameter::WindU), parameters.get(&GribParameter::WindV)) else {
+            continue;
+        };
+        if u.grid != v.grid {
+            continue;
+        }
+        let temperature = parameters
+            .get(&GribParameter::Temperature)
+            .filter(|t| t.grid == u.grid);
+        for (index, (u_value, v_value)) in u.values.iter().zip(&v.values).enumerate() {
+            let (Some(u_value), Some(v_value)) = (u_value, v_value) else {
+                continue;
+            };
+            points.push(WindsAloft {
+                valid_time,
+                level_hpa,
+                position: u.grid.position(index),
+                wind: wind_from_components(*u_value, *v_value),
+                temperature_c: temperature.and_then(|t| t.values[index]).map(|k| k - 273.15),
+            });
+        }
+    }
+    points.sort_by(|a, b| {
+        (a.valid_time, a.level_hpa)
+            .cmp(&(b.valid_time, b.level_hpa))
+            .then(b.position.latitude.total_cmp(&a.position.latitude))
+            .then(a.position.longitude.total_cmp(&b.position.longitude))
+    });
+    points
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn section(number: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 5) as u32).to_be_bytes().to_vec();
+        out.push(number);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn sign_magnitude(value: i64, len: usize) -> Vec<u8> {
+        let mut raw = value.unsigned_abs();
+        if value < 0 {
+            raw |= 1 << (len * 8 - 1);
+        }
+        raw.to_be_bytes()[8 - len..].to_vec()
+    }
+
+    /// A 2 x 2 grid message at 30N..29N, 120E..121E, 06Z + 6 h, 250 hPa,
+    /// values packed with 8 bits as `value = packed / 10`
+    pub(crate) fn message(category: u8, number: u8, packed: [u8; 4], bitmap: Option<u8>) -> Vec<u8> {
+        let mut identification = vec![0; 16];
+        identification[7..14].copy_from_slice(&[0x07, 0xEA, 10, 17, 6, 0, 0]);
+
+        let mut grid = vec![0u8; 67];
+        grid[0] = 0;
+        grid[1..5].copy_from_slice(&4u32.to_be_bytes());
+        grid[25..29].copy_from_slice(&2u32.to_be_bytes());
+        grid[29..33].copy_from_slice(&2u32.to_be_bytes());
+        grid[41..45].copy_from_slice(&sign_magnitude(30_000_000, 4));
+        grid[45..49].copy_from_slice(&sign_magnitude(120_000_000, 4));
+        grid[58..62].copy_from_slice(&1_000_000u32.to_be_bytes());
+        grid[62..66].copy_from_slice(&1_000_000u32.to_be_bytes());
+        grid[66] = 0;
+
+        let mut product = vec![0u8; 29];
+        product[4] = category;
+        product[5] = number;
+        product[12] = 1;
+        product[13..17].copy_from_slice(&6u32.to_be_bytes());
+        product[17] = 100;
+        product[19..23].copy_from_slice(&25_000u32.to_be_bytes());
+
+        let mut representation = vec![0u8; 16];
+        representation[0..4].copy_from_slice(&4u32.to_be_bytes());
+        representation[6..10].copy_from_slice(&0f32.to_bits().to_be_bytes());
+        representation[12..14].copy_from_slice(&sign_magnitude(1, 2));
+        representation[14] = 8;
+
+        let (bitmap_section, data) = match bitmap {
+            Some(bits) => {
+                let present: Vec<u8> = (0..4).filter(|i| bits >> (7 - i) & 1 == 1).map(|i| packed[i]).collect();
+                (section(6, &[0, bits]), present)
+            }
+            None => (section(6, &[255]), packed.to_vec()),
+        };
+
+        let mut body = Vec::new();
+        body.extend(section(1, &identification));
+        body.extend(section(3, &grid));
+        body.extend(section(4, &product));
+        body.extend(section(5, &representation));
+        body.extend(bitmap_section);
+        body.extend(section(7, &data));
+        body.extend_from_slice(b"7777");
+
+        let mut out = b"GRIB\0\0\0\x02".to_vec();
+        out.extend_from_slice(&((body.len() + 16) as u64).to_be_bytes());
+        out.extend(body);
+        out
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut file = message(2, 2, [100, 200, 0, 50], None);
+        file.extend(message(2, 3, [0, 0, 0, 50], Some(0b1011_0000)));
+        // Relative humidity is skipped
+        file.extend(message(1, 1, [1, 2, 3, 4], None));
+
+        let fields = decode(&file).unwrap();
+        assert_eq!(fields.len(), 2);
+        let u = &fields[0];
+        assert_eq!(u.parameter, GribParameter::WindU);
+        assert_eq!(u.level_hpa, 250);
+        assert_eq!(u.valid_time, chrono::DateTime::parse_from_rfc3339("2026-10-17T12:00:00Z").unwrap().timestamp());
+        assert_eq!(u.values, vec![Some(10.0), Some(20.0), Some(0.0), Some(5.0)]);
+        assert_eq!(u.grid.position(3), Coordinate::new(29.0, 121.0));
+        assert_eq!(fields[1].values, vec![Some(0.0), None, Some(0.0), Some(5.0)]);
+
+        let points = winds_aloft(&fields);
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].position, Coordinate::new(30.0, 120.0));
+        assert!((points[0].wind.direction - 270.0).abs() < 1e-9);
+        assert!((points[2].wind.direction - 225.0).abs() < 1e-9);
+        assert_eq!(points[0].temperature_c, None);
+
+        assert!(decode(b"GRIB\0\0\0\x01").is_err());
+        assert!(decode(&file[..file.len() - 10]).is_err());
+    }
+}
@@ -0,0 +1,433 @@
+pub mod atis;
+/// GRIB2 wind/temperature decoding for the winds-aloft store
+#[cfg(feature = "grib2")]
+pub mod grib2;
+pub mod sigmet;
+pub mod taf;
+pub mod winds;
+
+use crate::db::query::normalize_code;
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::FlightRoute;
+use crate::import::ImportControl;
+use crate::models::Coordinate;
+use crate::storage::{Dataset, StorageManager};
+use crate::sync::transport::SyncTransport;
+use atis::Atis;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use sigmet::{RouteSigmet, Sigmet, SigmetMessage};
+use std::sync::Arc;
+use winds::WindsAloft;
+use taf::Taf;
+
+/// 气象数据（TAF 预报、SIGMET/AIRMET、高空风温）管理器
+pub struct WeatherManager {
+    db: Arc<Database>,
+    storage: Option<Arc<StorageManager>>,
+}
+
+impl WeatherManager {
+    /// 创建新的气象数据管理器
+    pub fn new(db: Arc<Database>) -> Result<Self> {
+        Ok(Self { db, storage: None })
+    }
+
+    /// Keep winds-aloft grids under the storage quota of `storage`
+    pub fn with_storage(mut self, storage: Arc<StorageManager>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// 解析并保存刚收到的 TAF（同站同发布时间的更正报会替换原报）
+    pub fn store_taf(&self, raw: &str) -> Result<Taf> {
+        self.store_taf_received(raw, self.db.clock().now())
+    }
+
+    /// 解析并保存 TAF，`received` 用于确定报文中日期所属的月份
+    pub fn store_taf_received(&self, raw: &str, received: DateTime<Utc>) -> Result<Taf> {
+        let taf = taf::parse(raw, received)?;
+        self.db.get_conn()?.execute(
+            "INSERT INTO tafs (id, station, issued_at, valid_from, valid_to, raw, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (station, issued_at) DO UPDATE SET
+                valid_from = excluded.valid_from,
+                valid_to = excluded.valid_to,
+                raw = excluded.raw",
+            rusqlite::params![
+                self.db.new_id(),
+                taf.station,
+                taf.issued_at,
+                taf.valid_from,
+                taf.valid_to,
+                taf.raw,
+                self.db.clock().timestamp()
+            ],
+        )?;
+        Ok(taf)
+    }
+
+    /// 某机场最新发布的 TAF
+    pub fn latest_taf(&self, station: &str) -> Result<Option<Taf>> {
+        load_taf(
+            &*self.db.get_conn()?,
+            "SELECT raw, issued_at FROM tafs WHERE station = ?1
+             ORDER BY issued_at DESC LIMIT 1",
+            rusqlite::params![normalize_code(station)],
+        )
+    }
+
+    /// 覆盖指定时刻的最新 TAF
+    pub fn taf_valid_at(&self, station: &str, at: i64) -> Result<Option<Taf>> {
+        valid_taf(&*self.db.get_conn()?, station, at)
+    }
+
+    /// 解析并保存刚收到的 SIGMET/AIRMET（同编号的报文会被替换，CNL 报文删除原报）
+    pub fn store_sigmet(&self, raw: &str) -> Result<SigmetMessage> {
+        self.store_sigmet_received(raw, self.db.clock().now())
+    }
+
+    /// 解析并保存 SIGMET/AIRMET，`received` 用于确定报文中日期所属的月份
+    pub fn store_sigmet_received(&self, raw: &str, received: DateTime<Utc>) -> Result<SigmetMessage> {
+        let message = sigmet::parse(raw, received)?;
+        sigmet::store(&self.db, &message)?;
+        Ok(message)
+    }
+
+    /// 指定时刻有效的全部 SIGMET/AIRMET
+    pub fn active_sigmets(&self, at: i64) -> Result<Vec<Sigmet>> {
+        sigmet::find_sigmets_in(
+            &self.db,
+            Coordinate::new(-90.0, -180.0),
+            Coordinate::new(90.0, 180.0),
+            at,
+            at,
+        )
+    }
+
+    /// 航线在飞越时段内穿过的 SIGMET/AIRMET 区域
+    pub fn sigmets_along_route(&self, route: &FlightRoute, departure_time: i64) -> Result<Vec<RouteSigmet>> {
+        sigmet::sigmets_along_route(&self.db, route, departure_time)
+    }
+
+    /// 保存高空风温格点（同一时次、高度层和位置的数据会被替换）
+    pub fn store_winds_aloft(&self, points: &[WindsAloft]) -> Result<usize> {
+        self.store_winds_aloft_with(points, &ImportControl::default())
+    }
+
+    /// 保存高空风温格点，报告进度并支持取消（取消时不保存任何格点）
+    pub fn store_winds_aloft_with(&self, points: &[WindsAloft], control: &ImportControl) -> Result<usize> {
+        let conn = self.db.get_background_conn()?;
+        let stored = winds::store_with(&conn, points, self.db.clock().timestamp(), control)?;
+        if let Some(storage) = &self.storage {
+            let mut valid_times: Vec<String> = points.iter().map(|p| p.valid_time.to_string()).collect();
+            valid_times.sort();
+            valid_times.dedup();
+            storage.touch(&conn, Dataset::WeatherGrids, &valid_times)?;
+            drop(conn);
+            storage.enforce(Dataset::WeatherGrids)?;
+        }
+        Ok(stored)
+    }
+
+    /// 解码 GRIB2 预报文件中的 U/V 风和温度并存入高空风温数据，返回保存的格点数
+    #[cfg(feature = "grib2")]
+    pub fn import_grib2(&self, bytes: &[u8]) -> Result<usize> {
+        self.import_grib2_with(bytes, &ImportControl::default())
+    }
+
+    /// 导入 GRIB2 预报文件，报告进度并支持取消
+    #[cfg(feature = "grib2")]
+    pub fn import_grib2_with(&self, bytes: &[u8], control: &ImportControl) -> Result<usize> {
+        control.report(crate::import::ImportPhase::Parsing, 0, None)?;
+        let fields = grib2::decode(bytes)?;
+        self.store_winds_aloft_with(&grib2::winds_aloft(&fields), control)
+    }
+
+    /// 指定位置、气压高度和时刻最接近的高空风温
+    pub fn winds_aloft_at(
+        &self,
+        position: Coordinate,
+        altitude_ft: i32,
+        at: i64,
+    ) -> Result<Option<WindsAloft>> {
+        let conn = self.db.get_conn()?;
+        let winds = winds::winds_at(&conn, position, altitude_ft, at)?;
+        if let (Some(storage), Some(winds)) = (&self.storage, &winds) {
+            storage.touch(&conn, Dataset::WeatherGrids, &[winds.valid_time.to_string()])?;
+        }
+        Ok(winds)
+    }
+
+    /// 删除早于指定时刻的高空风温预报
+    pub fn prune_winds_aloft(&self, before: i64) -> Result<usize> {
+        winds::prune(&*self.db.get_conn()?, before)
+    }
+
+    /// 缓存一条 ATIS 通播（如手动录入的语音 ATIS），替换同机场同类型的旧通播
+    pub fn store_atis(&self, atis: &Atis) -> Result<()> {
+        atis::store(&*self.db.get_conn()?, std::slice::from_ref(atis), None)?;
+        Ok(())
+    }
+
+    /// 通过 `transport` 获取机场当前的 D-ATIS 并替换该机场的缓存
+    pub fn fetch_atis(&self, transport: &dyn SyncTransport, station: &str) -> Result<Vec<Atis>> {
+        self.fetch_atis_received(transport, station, self.db.clock().now())
+    }
+
+    /// 获取 D-ATIS，`received` 用于确定通播中观测时间所属的日期
+    pub fn fetch_atis_received(
+        &self,
+        transport: &dyn SyncTransport,
+        station: &str,
+        received: DateTime<Utc>,
+    ) -> Result<Vec<Atis>> {
+        let station = normalize_code(station);
+        let body = transport.get(&format!("{}/{}", atis::DATIS_PATH, station), &[])?;
+        let broadcasts: Vec<Atis> = atis::parse_datis(&body, received)?
+            .into_iter()
+            .filter(|a| a.station == station)
+            .collect();
+        atis::store(&*self.db.get_conn()?, &broadcasts, Some(&station))?;
+        Ok(broadcasts)
+    }
+
+    /// 机场缓存的 ATIS 通播（综合、进场、离场各一条）
+    pub fn atis(&self, station: &str) -> Result<Vec<Atis>> {
+        atis::latest(&*self.db.get_conn()?, station)
+    }
+
+    /// 删除早于指定时刻收到的 ATIS 通播
+    pub fn prune_atis(&self, before: i64) -> Result<usize> {
+        atis::prune(&*self.db.get_conn()?, before)
+    }
+}
+
+/// Latest stored TAF for `station` whose validity includes `at`
+pub(crate) fn valid_taf(conn: &Connection, station: &str, at: i64) -> Result<Option<Taf>> {
+    load_taf(
+        conn,
+        "SELECT raw, issued_at FROM tafs
+         WHERE station = ?1 AND valid_from <= ?2 AND valid_to >= ?2
+         ORDER BY issued_at DESC LIMIT 1",
+        rusqlite::params![normalize_code(station), at],
+    )
+}
+
+fn load_taf(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<Option<Taf>> {
+    let row: Option<(String, i64)> = conn
+        .query_row(sql, params, |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()?;
+    row.map(|(raw, issued_at)| {
+        let issued = DateTime::from_timestamp(issued_at, 0)
+            .ok_or_else(|| AeroBaseError::InvalidInput(format!("Bad TAF issue time {}", issued_at)))?;
+        taf::parse(&raw, issued)
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::transport::tests::CannedTransport;
+    use crate::Config;
+    use atis::AtisKind;
+    use chrono::TimeZone;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_store_and_lookup() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let weather = WeatherManager::new(db).unwrap();
+
+        let received = Utc.with_ymd_and_hms(2026, 10, 17, 5, 10, 0).unwrap();
+        weather
+            .store_taf_received("TAF ZBAA 170500Z 1706/1812 9999 SCT030", received)
+            .unwrap();
+        weather
+            .store_taf_received("TAF ZBAA 171100Z 1712/1818 4000 BR BKN008", received)
+            .unwrap();
+        // Amendment of the first forecast replaces it
+        weather
+            .store_taf_received("TAF AMD ZBAA 170500Z 1706/1812 3000 BR OVC004", received)
+            .unwrap();
+
+        let latest = weather.latest_taf("zbaa").unwrap().unwrap();
+        assert_eq!(latest.periods[0].ceiling_ft, Some(800));
+
+        let at = |hour| Utc.with_ymd_and_hms(2026, 10, 17, hour, 0, 0).unwrap().timestamp();
+        let early = weather.taf_valid_at("ZBAA", at(8)).unwrap().unwrap();
+        assert_eq!(early.periods[0].ceiling_ft, Some(400));
+        assert_eq!(early.raw, "TAF AMD ZBAA 170500Z 1706/1812 3000 BR OVC004");
+        assert!(weather.taf_valid_at("ZBAA", at(2)).unwrap().is_none());
+        assert!(weather.latest_taf("ZSSS").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_atis_cache() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let weather = WeatherManager::new(db).unwrap();
+
+        let received = Utc.with_ymd_and_hms(2026, 10, 17, 12, 5, 0).unwrap();
+        let split = r#"[
+            {"airport": "KJFK", "type": "dep", "code": "C", "datis": "JFK DEP INFO C 1151Z."},
+            {"airport": "KJFK", "type": "arr", "code": "D", "datis": "JFK ARR INFO D 1151Z."}
+        ]"#;
+        let combined =
+            r#"[{"airport": "KJFK", "type": "combined", "datis": "JFK ATIS INFO E 1251Z."}]"#;
+        let transport = CannedTransport::default().with("api/KJFK", vec![split, combined]);
+
+        assert_eq!(weather.fetch_atis_received(&transport, "kjfk", received).unwrap().len(), 2);
+        let cached = weather.atis("KJFK").unwrap();
+        let kinds: Vec<AtisKind> = cached.iter().map(|a| a.kind).collect();
+        assert_eq!(kinds, [AtisKind::Arrival, AtisKind::Departure]);
+        assert_eq!(cached[0].issued_at, received.timestamp() - 840);
+
+        // The next fetch replaces the split broadcasts
+        let later = received + chrono::Duration::hours(1);
+        weather.fetch_atis_received(&transport, "KJFK", later).unwrap();
+        let cached = weather.atis("KJFK").unwrap();
+        assert_eq!(cached.len(), 1);
+        assert_eq!((cached[0].kind, cached[0].letter), (AtisKind::Combined, Some('E')));
+
+        let manual = Atis::new("ZBAA", AtisKind::Combined, "INFORMATION K 1200Z", received);
+        weather.store_atis(&manual).unwrap();
+        assert_eq!(weather.atis("zbaa").unwrap(), vec![manual]);
+        assert_eq!(weather.prune_atis(later.timestamp()).unwrap(), 1);
+        assert!(weather.atis("ZBAA").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_winds_aloft() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let weather = WeatherManager::new(db).unwrap();
+
+        let point = |valid_time, level_hpa, latitude, speed| WindsAloft {
+            valid_time,
+            level_hpa,
+            position: Coordinate::new(latitude, 116.0),
+            wind: crate::models::runway::Wind::new(270.0, speed),
+            temperature_c: Some(-50.0),
+        };
+        weather
+            .store_winds_aloft(&[
+                point(21600, 250, 40.0, 80.0),
+                point(21600, 250, 41.0, 90.0),
+                point(21600, 500, 40.0, 40.0),
+                point(43200, 250, 40.0, 120.0),
+            ])
+            .unwrap();
+
+        // FL340 is about 250 hPa; 06Z is the closer forecast to 08Z
+        let wind = weather
+            .winds_aloft_at(Coordinate::new(40.2, 116.1), 34000, 28800)
+            .unwrap()
+            .unwrap();
+        assert_eq!((wind.valid_time, wind.level_hpa), (21600, 250));
+        assert_eq!(wind.wind.speed, 80.0);
+        let low = weather.winds_aloft_at(Coordinate::new(40.2, 116.1), 18000, 0).unwrap().unwrap();
+        assert_eq!(low.level_hpa, 500);
+        assert!(weather.winds_aloft_at(Coordinate::new(30.0, 116.0), 34000, 0).unwrap().is_none());
+
+        assert_eq!(weather.prune_winds_aloft(43200).unwrap(), 3);
+        let later = weather.winds_aloft_at(Coordinate::new(40.0, 116.0), 34000, 0).unwrap().unwrap();
+        assert_eq!(later.wind.speed, 120.0);
+    }
+
+    #[test]
+    fn test_winds_aloft_quota() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        // Room for about one forecast time of four points
+        let storage = Arc::new(
+            StorageManager::new(
+                Arc::clone(&db),
+                crate::storage::StorageConfig {
+                    weather_grids: Some(300),
+                    ..Default::default()
+                },
+            )
+            .unwrap(),
+        );
+        let weather = WeatherManager::new(db).unwrap().with_storage(storage);
+
+        let grid = |valid_time| -> Vec<WindsAloft> {
+            (0..4)
+                .map(|i| WindsAloft {
+                    valid_time,
+                    level_hpa: 250,
+                    position: Coordinate::new(40.0 + i as f64, 116.0),
+                    wind: crate::models::runway::Wind::new(270.0, 50.0),
+                    temperature_c: None,
+                })
+                .collect()
+        };
+        weather.store_winds_aloft(&grid(0)).unwrap();
+        weather.store_winds_aloft(&grid(21600)).unwrap();
+
+        // The 00Z grid was evicted (both were used in the same second;
+        // ties go by unit key)
+        let wind = weather.winds_aloft_at(Coordinate::new(40.0, 116.0), 34000, 0).unwrap().unwrap();
+        assert_eq!(wind.valid_time, 21600);
+    }
+
+    #[cfg(feature = "grib2")]
+    #[test]
+    fn test_import_grib2() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Arc::new(Database::new(&config).unwrap());
+        db.migrate().unwrap();
+        let weather = WeatherManager::new(db).unwrap();
+
+        let mut file = grib2::tests::message(2, 2, [100, 200, 0, 50], None);
+        file.extend(grib2::tests::message(2, 3, [0, 0, 0, 50], None));
+        file.extend(grib2::tests::message(0, 0, [200, 210, 220, 230], None));
+        assert_eq!(weather.import_grib2(&file).unwrap(), 4);
+
+        let valid_time = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap().timestamp();
+        let wind = weather
+            .winds_aloft_at(Coordinate::new(30.0, 120.0), 34000, valid_time)
+            .unwrap()
+            .unwrap();
+        assert!((wind.wind.direction - 270.0).abs() < 1e-9);
+        assert!((wind.temperature_c.unwrap() - (20.0 - 273.15)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,538 @@
+use super::taf::{digits, resolve_time};
+use crate::db::Database;
+use crate::error::{AeroBaseError, Result};
+use crate::flight::profile::{self, crossing_spans, route_samples};
+use crate::flight::FlightRoute;
+use crate::models::Coordinate;
+use crate::spatial::geometry;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Distance between route samples checked against advisory polygons
+const ROUTE_SAMPLE_SPACING_NM: f64 = 1.0;
+
+/// SIGMET or AIRMET
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdvisoryKind {
+    Sigmet,
+    Airmet,
+}
+
+impl AdvisoryKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AdvisoryKind::Sigmet => "SIGMET",
+            AdvisoryKind::Airmet => "AIRMET",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "SIGMET" => Some(AdvisoryKind::Sigmet),
+            "AIRMET" => Some(AdvisoryKind::Airmet),
+            _ => None,
+        }
+    }
+}
+
+/// A SIGMET or AIRMET with its polygon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sigmet {
+    /// FIR, kind and sequence number, e.g. "ZBPE:SIGMET:3"
+    pub id: String,
+    pub kind: AdvisoryKind,
+    pub fir: String,
+    pub series: String,
+    /// e.g. "EMBD TS", "SEV TURB"
+    pub phenomenon: String,
+    pub valid_from: i64,
+    pub valid_to: i64,
+    /// Feet; `None` for the surface
+    pub lower_limit: Option<i32>,
+    /// Feet; `None` when no top is given
+    pub upper_limit: Option<i32>,
+    pub boundary: Vec<Coordinate>,
+    pub raw: String,
+}
+
+impl Sigmet {
+    pub fn is_valid_at(&self, at: i64) -> bool {
+        at >= self.valid_from && at <= self.valid_to
+    }
+
+    pub fn applies_at(&self, altitude: i32) -> bool {
+        self.lower_limit.is_none_or(|l| altitude >= l) && self.upper_limit.is_none_or(|u| altitude <= u)
+    }
+
+    pub fn contains(&self, point: Coordinate) -> bool {
+        geometry::point_in_polygon(point, &self.boundary)
+    }
+}
+
+/// A decoded advisory message
+#[derive(Debug, Clone, PartialEq)]
+pub enum SigmetMessage {
+    Issued(Sigmet),
+    /// CNL: withdraws the advisory with this id
+    Cancelled(String),
+}
+
+fn invalid(message: impl Into<String>) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid SIGMET: {}", message.into()))
+}
+
+fn advisory_id(fir: &str, kind: AdvisoryKind, series: &str) -> String {
+    format!("{}:{}:{}", fir, kind.as_str(), series)
+}
+
+/// DDHHMM/DDHHMM validity
+fn validity(token: &str, after: DateTime<Utc>) -> Option<(i64, i64)> {
+    if token.len() != 13 || token.as_bytes()[6] != b'/' {
+        return None;
+    }
+    let from = resolve_time(digits(token, 0..2)?, digits(token, 2..4)?, digits(token, 4..6)?, after)?;
+    let from_time = DateTime::from_timestamp(from, 0)?;
+    let to = resolve_time(digits(token, 7..9)?, digits(token, 9..11)?, digits(token, 11..13)?, from_time)?;
+    Some((from, to))
+}
+
+/// N3930 / E11600 / S05 style latitude or longitude
+fn coordinate_part(token: &str, degree_digits: usize) -> Option<f64> {
+    let sign = match token.chars().next()? {
+        'N' | 'E' => 1.0,
+        'S' | 'W' => -1.0,
+        _ => return None,
+    };
+    let body = &token[1..];
+    let degrees = digits(body, 0..degree_digits)? as f64;
+    let minutes = match body.len() - degree_digits {
+        0 => 0.0,
+        2 => digits(body, degree_digits..degree_digits + 2)? as f64,
+        _ => return None,
+    };
+    Some(sign * (degrees + minutes / 60.0))
+}
+
+/// Vertices after "WI", given as "N3930 E11600 - N4000 E11700 - ..."
+fn polygon(tokens: &[&str]) -> Result<Vec<Coordinate>> {
+    let mut boundary = Vec::new();
+    let mut iter = tokens.iter().filter(|t| **t != "-").peekable();
+    while let Some(lat) = iter.peek().and_then(|t| coordinate_part(t.trim_end_matches('-'), 2)) {
+        iter.next();
+        let lon = iter
+            .next()
+            .and_then(|t| coordinate_part(t.trim_end_matches('-'), 3))
+            .ok_or_else(|| invalid("latitude without longitude"))?;
+        boundary.push(Coordinate::new(lat, lon));
+    }
+    if boundary.len() < 3 {
+        return Err(invalid("polygon needs at least three points"));
+    }
+    Ok(boundary)
+}
+
+/// FL380 or 5000FT in feet
+fn level(token: &str) -> Option<i32> {
+    if let Some(fl) = token.strip_prefix("FL") {
+        return fl.parse::<i32>().ok().map(|l| l * 100);
+    }
+    token.strip_suffix("FT")?.parse().ok()
+}
+
+/// Vertical extent: TOP FL380, ABV FL300, BLW FL100, SFC/FL100 or FL250/380
+fn levels(tokens: &[&str]) -> (Option<i32>, Option<i32>) {
+    for (i, token) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1).copied().unwrap_or_default();
+        match *token {
+            "TOP" => {
+                let top = if next == "ABV" || next == "BLW" {
+                    tokens.get(i + 2).copied().unwrap_or_default()
+                } else {
+                    next
+                };
+                return (None, level(top));
+            }
+            "ABV" => return (level(next), None),
+            "BLW" => return (None, level(next)),
+            _ => {}
+        }
+        if let Some((low, high)) = token.split_once('/') {
+            let high = level(high).or_else(|| high.parse::<i32>().ok().map(|l| l * 100));
+            if low == "SFC" {
+                return (None, high);
+            }
+            if let (Some(low), Some(high)) = (level(low), high) {
+                return (Some(low), Some(high));
+            }
+        }
+    }
+    (None, None)
+}
+
+/// Parse a SIGMET or AIRMET in the ICAO Annex 3 format
+///
+/// Only polygon ("WI ...") areas are supported. `reference` is any time in
+/// the month the message was issued, normally when it was received.
+pub fn parse(raw: &str, reference: DateTime<Utc>) -> Result<SigmetMessage> {
+    let text = raw.trim().trim_end_matches('=').trim();
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+
+    let fir = tokens.first().copied().ok_or_else(|| invalid("empty"))?;
+    if fir.len() != 4 {
+        return Err(invalid(format!("bad FIR {}", fir)));
+    }
+    let kind = tokens
+        .get(1)
+        .and_then(|t| AdvisoryKind::from_str(t))
+        .ok_or_else(|| invalid("expected SIGMET or AIRMET"))?;
+    let valid_at = tokens
+        .iter()
+        .position(|t| *t == "VALID")
+        .ok_or_else(|| invalid("missing VALID"))?;
+    let series = tokens[2..valid_at].join(" ");
+    let (valid_from, valid_to) = tokens
+        .get(valid_at + 1)
+        .and_then(|t| validity(t, reference - Duration::days(2)))
+        .ok_or_else(|| invalid("bad validity"))?;
+    let id = advisory_id(fir, kind, &series);
+
+    // The body starts after the "<FIR> <name> FIR" (or UIR, FIR/UIR, CTA) line
+    let body_start = tokens
+        .iter()
+        .position(|t| matches!(*t, "FIR" | "UIR" | "FIR/UIR" | "CTA"))
+        .map_or(valid_at + 3, |i| i + 1);
+    let body = tokens.get(body_start..).unwrap_or_default();
+
+    if body.first() == Some(&"CNL") {
+        return Ok(SigmetMessage::Cancelled(id));
+    }
+
+    let phenomenon = body
+        .iter()
+        .take_while(|t| !matches!(**t, "OBS" | "FCST" | "WI"))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let within = body
+        .iter()
+        .position(|t| *t == "WI")
+        .ok_or_else(|| invalid("only polygon (WI) areas are supported"))?;
+    let boundary = polygon(&body[within + 1..])?;
+    let (lower_limit, upper_limit) = levels(&body[within + 1..]);
+
+    Ok(SigmetMessage::Issued(Sigmet {
+        id,
+        kind,
+        fir: fir.to_string(),
+        series,
+        phenomenon,
+        valid_from,
+        valid_to,
+        lower_limit,
+        upper_limit,
+        boundary,
+        raw: text.to_string(),
+    }))
+}
+
+fn bounds(boundary: &[Coordinate]) -> (Coordinate, Coordinate) {
+    let mut min = boundary[0];
+    let mut max = boundary[0];
+    for c in boundary {
+        min = Coordinate::new(min.latitude.min(c.latitude), min.longitude.min(c.longitude));
+        max = Coordinate::new(max.latitude.max(c.latitude), max.longitude.max(c.longitude));
+    }
+    (min, max)
+}
+
+/// Store an issued advisory, replacing any with the same id, or delete a
+/// cancelled one
+pub fn store(db: &Database, message: &SigmetMessage) -> Result<()> {
+    let conn = db.get_conn()?;
+    let tx = conn.unchecked_transaction()?;
+    match message {
+        SigmetMessage::Cancelled(id) => {
+            tx.execute("DELETE FROM sigmets WHERE id = ?1", [id])?;
+        }
+        SigmetMessage::Issued(sigmet) => {
+            let (min, max) = bounds(&sigmet.boundary);
+            tx.execute("DELETE FROM sigmets WHERE id = ?1", [&sigmet.id])?;
+            tx.execute(
+                "INSERT INTO sigmets (id, kind, fir, series, phenomenon, valid_from, valid_to,
+                     lower_limit, upper_limit, raw, min_latitude, max_latitude, min_longitude,
+                     max_longitude, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                rusqlite::params![
+                    &sigmet.id,
+                    sigmet.kind.as_str(),
+                    &sigmet.fir,
+                    &sigmet.series,
+                    &sigmet.phenomenon,
+                    sigmet.valid_from,
+                    sigmet.valid_to,
+                    sigmet.lower_limit,
+                    sigmet.upper_limit,
+                    &sigmet.raw,
+                    min.latitude,
+                    max.latitude,
+                    min.longitude,
+                    max.longitude,
+                    db.clock().timestamp(),
+                ],
+            )?;
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO sigmet_boundaries (id, sigmet_id, latitude, longitude, sequence)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (sequence, vertex) in sigmet.boundary.iter().enumerate() {
+                stmt.execute(rusqlite::params![
+                    format!("{}:{}", sigmet.id, sequence),
+                    &sigmet.id,
+                    vertex.latitude,
+                    vertex.longitude,
+                    sequence as i64,
+                ])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Advisories overlapping `min`..`max` and valid at some time in `from..=to`
+pub fn find_sigmets_in(
+    db: &Database,
+    min: Coordinate,
+    max: Coordinate,
+    from: i64,
+    to: i64,
+) -> Result<Vec<Sigmet>> {
+    let conn = db.get_conn()?;
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, fir, series, phenomenon, valid_from, valid_to, lower_limit,
+                upper_limit, raw
+         FROM sigmets
+         WHERE max_latitude >= ?1 AND min_latitude <= ?2
+           AND max_longitude >= ?3 AND min_longitude <= ?4
+           AND valid_to >= ?5 AND valid_from <= ?6
+         ORDER BY valid_from, id",
+    )?;
+    let mut sigmets = stmt
+        .query_map(
+            rusqlite::params![min.latitude, max.latitude, min.longitude, max.longitude, from, to],
+            |row| {
+                Ok(Sigmet {
+                    id: row.get(0)?,
+                    kind: AdvisoryKind::from_str(&row.get::<_, String>(1)?)
+                        .unwrap_or(AdvisoryKind::Sigmet),
+                    fir: row.get(2)?,
+                    series: row.get(3)?,
+                    phenomenon: row.get(4)?,
+                    valid_from: row.get(5)?,
+                    valid_to: row.get(6)?,
+                    lower_limit: row.get(7)?,
+                    upper_limit: row.get(8)?,
+                    boundary: Vec::new(),
+                    raw: row.get(9)?,
+                })
+            },
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut boundary = conn.prepare_cached(
+        "SELECT latitude, longitude FROM sigmet_boundaries
+         WHERE sigmet_id = ?1 ORDER BY sequence",
+    )?;
+    for sigmet in sigmets.iter_mut() {
+        sigmet.boundary = boundary
+            .query_map([&sigmet.id], |row| Ok(Coordinate::new(row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+    }
+    Ok(sigmets)
+}
+
+/// Stretch of a route flown inside an advisory while it is valid
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteSigmet {
+    pub sigmet: Sigmet,
+    pub from_nm: f64,
+    pub to_nm: f64,
+    /// Estimated times at `from_nm` and `to_nm`, Unix seconds
+    pub enter_at: i64,
+    pub exit_at: i64,
+}
+
+/// Advisories the route flies through while they are valid
+///
+/// Positions are timed from `departure_time` at the plan's cruise speed,
+/// and compared with each advisory's vertical limits at the cruise altitude.
+pub fn sigmets_along_route(
+    db: &Database,
+    route: &FlightRoute,
+    departure_time: i64,
+) -> Result<Vec<RouteSigmet>> {
+    if route.waypoints.is_empty() || route.plan.cruise_speed <= 0 {
+        return Err(AeroBaseError::InvalidInput(
+            "Route needs waypoints and a positive cruise speed".to_string(),
+        ));
+    }
+    let time_at = |distance| profile::time_at(route, departure_time, distance);
+
+    let samples = route_samples(route, ROUTE_SAMPLE_SPACING_NM);
+    let (min, max) = bounds(&samples.iter().map(|s| s.1).collect::<Vec<_>>());
+    let candidates = find_sigmets_in(db, min, max, departure_time, time_at(route.total_distance))?;
+
+    let mut crossings = Vec::new();
+    for sigmet in candidates {
+        if !sigmet.applies_at(route.plan.cruise_altitude) {
+            continue;
+        }
+        let spans = crossing_spans(samples.iter().copied(), |distance, point| {
+            sigmet.is_valid_at(time_at(distance)) && sigmet.contains(point)
+        });
+        for (from_nm, to_nm) in spans {
+            crossings.push(RouteSigmet {
+                sigmet: sigmet.clone(),
+                from_nm,
+                to_nm,
+                enter_at: time_at(from_nm),
+                exit_at: time_at(to_nm),
+            });
+        }
+    }
+    crossings.sort_by(|a, b| a.from_nm.total_cmp(&b.from_nm));
+    Ok(crossings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{planner, FlightPlan};
+    use crate::Config;
+    use chrono::TimeZone;
+    use tempfile::NamedTempFile;
+
+    const EMBD_TS: &str = "ZBPE SIGMET 3 VALID 170600/171000 ZBAA- ZBPE BEIJING FIR \
+        EMBD TS OBS AT 0550Z WI N0030 E00030 - N0030 E00100 - S0030 E00100 - S0030 E00030 - N0030 E00030 \
+        TOP FL380 MOV E 20KT NC=";
+
+    fn reference() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 17, 5, 55, 0).unwrap()
+    }
+
+    fn at(hour: u32, minute: u32) -> i64 {
+        Utc.with_ymd_and_hms(2026, 10, 17, hour, minute, 0).unwrap().timestamp()
+    }
+
+    proptest::proptest! {
+        /// Garbled messages from sync are rejected, never a panic
+        #[test]
+        fn prop_parse_never_panics(
+            tokens in proptest::collection::vec(
+                proptest::prop_oneof![
+                    proptest::sample::select(EMBD_TS.split_whitespace().map(String::from).collect::<Vec<_>>()),
+                    "[NSEW][0-9]{0,7}",
+                    "[A-Z0-9/-]{1,12}",
+                ],
+                0..32,
+            ),
+            noise in "\\PC{0,40}",
+        ) {
+            let _ = parse(&tokens.join(" "), reference());
+            let _ = parse(&noise, reference());
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let SigmetMessage::Issued(sigmet) = parse(EMBD_TS, reference()).unwrap() else {
+            panic!("expected an issued SIGMET");
+        };
+        assert_eq!(sigmet.id, "ZBPE:SIGMET:3");
+        assert_eq!(sigmet.phenomenon, "EMBD TS");
+        assert_eq!((sigmet.valid_from, sigmet.valid_to), (at(6, 0), at(10, 0)));
+        assert_eq!((sigmet.lower_limit, sigmet.upper_limit), (None, Some(38000)));
+        assert_eq!(sigmet.boundary.len(), 5);
+        assert_eq!(sigmet.boundary[2], Coordinate::new(-0.5, 1.0));
+
+        let airmet = "ZBPE AIRMET A1 VALID 170600/171000 ZBAA- ZBPE BEIJING FIR \
+            MOD TURB FCST WI N40 E116 - N41 E116 - N41 E117 FL050/120 STNR NC";
+        let SigmetMessage::Issued(airmet) = parse(airmet, reference()).unwrap() else {
+            panic!("expected an issued AIRMET");
+        };
+        assert_eq!(airmet.kind, AdvisoryKind::Airmet);
+        assert_eq!(airmet.phenomenon, "MOD TURB");
+        assert_eq!((airmet.lower_limit, airmet.upper_limit), (Some(5000), Some(12000)));
+
+        let cancel = "ZBPE SIGMET 4 VALID 170800/171000 ZBAA- ZBPE BEIJING FIR CNL SIGMET 3 170600/171000=";
+        assert_eq!(
+            parse(cancel, reference()).unwrap(),
+            SigmetMessage::Cancelled("ZBPE:SIGMET:4".to_string())
+        );
+
+        let entire = "ZBPE SIGMET 5 VALID 170600/171000 ZBAA- ZBPE BEIJING FIR SEV TURB FCST ENTIRE FIR";
+        assert!(parse(entire, reference()).is_err());
+    }
+
+    #[test]
+    fn test_sigmets_along_route() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            db_path: temp_file.path().to_path_buf(),
+            enable_wal: false,
+            pool_size: 1,
+            ..Default::default()
+        };
+        let db = Database::new(&config).unwrap();
+        db.migrate().unwrap();
+        db.get_conn()
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO airports (id, icao, name, latitude, longitude, created_at) VALUES
+                    ('A1', 'WEST', 'West', 0.0, 0.0, 0),
+                    ('A2', 'EAST', 'East', 0.0, 2.0, 0);",
+            )
+            .unwrap();
+        store(&db, &parse(EMBD_TS, reference()).unwrap()).unwrap();
+
+        // 120 nm east at 120 kt: the cell (30-60 nm) is crossed 06:15-06:30
+        let plan = FlightPlan {
+            departure: "WEST".to_string(),
+            destination: "EAST".to_string(),
+            alternate: None,
+            cruise_altitude: 35000,
+            cruise_speed: 120,
+            route: vec![],
+            ..Default::default()
+        };
+        let route = planner::calculate_route(&db, &plan).unwrap();
+
+        let crossings = sigmets_along_route(&db, &route, at(6, 0)).unwrap();
+        assert_eq!(crossings.len(), 1);
+        let crossing = &crossings[0];
+        assert_eq!(crossing.sigmet.id, "ZBPE:SIGMET:3");
+        assert!((crossing.from_nm - 31.0).abs() < 0.01 && (crossing.to_nm - 60.0).abs() < 0.01);
+        assert_eq!(crossing.enter_at, at(6, 15) + 30);
+
+        // Departing at 09:45 reaches the cell just after it expires
+        let late = sigmets_along_route(&db, &route, at(9, 15)).unwrap();
+        assert_eq!(late.len(), 1);
+        assert!(late[0].exit_at <= at(10, 0));
+        assert!(sigmets_along_route(&db, &route, at(9, 45)).unwrap().is_empty());
+
+        // Above the tops
+        let high = FlightRoute {
+            plan: FlightPlan {
+                cruise_altitude: 39000,
+                ..plan.clone()
+            },
+            ..route.clone()
+        };
+        assert!(sigmets_along_route(&db, &high, at(6, 0)).unwrap().is_empty());
+
+        let cancel = "ZBPE SIGMET 3 VALID 170700/171000 ZBAA- ZBPE BEIJING FIR CNL SIGMET 2 170600/171000";
+        store(&db, &parse(cancel, reference()).unwrap()).unwrap();
+        assert!(sigmets_along_route(&db, &route, at(6, 0)).unwrap().is_empty());
+    }
+}
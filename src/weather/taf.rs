@@ -0,0 +1,468 @@
+use crate::error::{AeroBaseError, Result};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Visibility reported as 9999 or CAVOK: 10 km or more
+pub const VISIBILITY_10KM: u32 = 10_000;
+
+const METRES_PER_SM: f64 = 1609.344;
+
+/// How a forecast group relates to the conditions before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    /// The forecast's opening conditions
+    Base,
+    /// FM: replaces everything from its start time
+    From,
+    /// BECMG: changes the given elements at some point during the group
+    Becoming,
+    /// TEMPO: temporary fluctuations within the group
+    Tempo,
+    /// PROB30/PROB40, with or without TEMPO
+    Probability(u8),
+}
+
+impl ChangeKind {
+    /// TEMPO and PROB groups don't change the prevailing conditions
+    pub fn is_temporary(&self) -> bool {
+        matches!(self, ChangeKind::Tempo | ChangeKind::Probability(_))
+    }
+}
+
+/// Ceiling and visibility; `None` means unrestricted or not reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conditions {
+    /// Metres
+    pub visibility_m: Option<u32>,
+    /// Lowest BKN/OVC layer or vertical visibility, feet above ground
+    pub ceiling_ft: Option<i32>,
+}
+
+impl Conditions {
+    /// The lower of each element
+    pub fn worst(self, other: Conditions) -> Conditions {
+        Conditions {
+            visibility_m: lower(self.visibility_m, other.visibility_m),
+            ceiling_ft: lower(self.ceiling_ft, other.ceiling_ft),
+        }
+    }
+}
+
+fn lower<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// One forecast group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TafPeriod {
+    pub change: ChangeKind,
+    pub from: i64,
+    pub to: i64,
+    /// Metres, when the group gives a visibility
+    pub visibility_m: Option<u32>,
+    /// Whether the group reports sky condition (clouds, NSC, CAVOK, ...)
+    pub sky_reported: bool,
+    pub ceiling_ft: Option<i32>,
+}
+
+impl TafPeriod {
+    /// `base` with the elements this group reports replaced
+    fn apply_to(&self, base: Conditions) -> Conditions {
+        Conditions {
+            visibility_m: self.visibility_m.or(base.visibility_m),
+            ceiling_ft: if self.sky_reported {
+                self.ceiling_ft
+            } else {
+                base.ceiling_ft
+            },
+        }
+    }
+}
+
+/// A decoded terminal aerodrome forecast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Taf {
+    pub station: String,
+    pub issued_at: i64,
+    pub valid_from: i64,
+    pub valid_to: i64,
+    pub periods: Vec<TafPeriod>,
+    pub raw: String,
+}
+
+/// A stretch of time with fixed forecast conditions
+struct Segment {
+    from: i64,
+    to: i64,
+    temporary: bool,
+    conditions: Conditions,
+}
+
+impl Taf {
+    fn segments(&self) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut current = Conditions::default();
+        let mut start = self.valid_from;
+
+        for period in &self.periods {
+            match period.change {
+                ChangeKind::Base => current = period.apply_to(Conditions::default()),
+                ChangeKind::From => {
+                    segments.push(Segment {
+                        from: start,
+                        to: period.from,
+                        temporary: false,
+                        conditions: current,
+                    });
+                    current = period.apply_to(Conditions::default());
+                    start = period.from;
+                }
+                // The change may happen anywhere in the group, so both the old
+                // and new conditions cover all of it
+                ChangeKind::Becoming => {
+                    segments.push(Segment {
+                        from: start,
+                        to: period.to,
+                        temporary: false,
+                        conditions: current,
+                    });
+                    current = period.apply_to(current);
+                    start = period.from;
+                }
+                ChangeKind::Tempo | ChangeKind::Probability(_) => segments.push(Segment {
+                    from: period.from,
+                    to: period.to,
+                    temporary: true,
+                    conditions: period.apply_to(current),
+                }),
+            }
+        }
+        segments.push(Segment {
+            from: start,
+            to: self.valid_to,
+            temporary: false,
+            conditions: current,
+        });
+        segments
+    }
+
+    /// Worst ceiling and visibility forecast at any time in `from..=to`
+    ///
+    /// TEMPO and PROB groups count only with `include_temporary`. Returns
+    /// `None` when the window is outside the forecast's validity.
+    pub fn worst_between(&self, from: i64, to: i64, include_temporary: bool) -> Option<Conditions> {
+        if to < self.valid_from || from > self.valid_to {
+            return None;
+        }
+        self.segments()
+            .into_iter()
+            .filter(|s| s.from <= to && s.to >= from && s.from < s.to)
+            .filter(|s| include_temporary || !s.temporary)
+            .map(|s| s.conditions)
+            .reduce(Conditions::worst)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> AeroBaseError {
+    AeroBaseError::InvalidInput(format!("Invalid TAF: {}", message.into()))
+}
+
+pub(super) fn digits(s: &str, range: std::ops::Range<usize>) -> Option<u32> {
+    s.get(range).filter(|d| d.bytes().all(|b| b.is_ascii_digit()))?.parse().ok()
+}
+
+/// Day of month, hour and minute after `after`, rolling into the next month
+/// when the day is earlier; hour 24 is midnight of the next day
+pub(super) fn resolve_time(day: u32, hour: u32, minute: u32, after: DateTime<Utc>) -> Option<i64> {
+    let (mut year, mut month) = (after.year(), after.month());
+    if day < after.day() {
+        month += 1;
+        if month > 12 {
+            month = 1;
+            year += 1;
+        }
+    }
+    let midnight = Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).single()?;
+    Some((midnight + Duration::hours(hour as i64) + Duration::minutes(minute as i64)).timestamp())
+}
+
+/// DDHH/DDHH validity group
+fn validity(token: &str, after: DateTime<Utc>) -> Option<(i64, i64)> {
+    if token.len() != 9 || token.as_bytes()[4] != b'/' {
+        return None;
+    }
+    let from = resolve_time(digits(token, 0..2)?, digits(token, 2..4)?, 0, after)?;
+    let from_time = DateTime::from_timestamp(from, 0)?;
+    let to = resolve_time(digits(token, 5..7)?, digits(token, 7..9)?, 0, from_time)?;
+    Some((from, to))
+}
+
+/// Visibility in metres from a 4-digit or statute-mile group
+fn visibility(token: &str, previous: Option<&str>) -> Option<u32> {
+    if let Some(metres) = digits(token, 0..4).filter(|_| token.len() == 4) {
+        return Some(if metres == 9999 { VISIBILITY_10KM } else { metres });
+    }
+    let miles = token.strip_suffix("SM")?;
+    // P6SM: more than six miles
+    if miles.starts_with('P') {
+        return Some(VISIBILITY_10KM);
+    }
+    let miles = miles.trim_start_matches('M');
+    let mut value = match miles.split_once('/') {
+        Some((n, d)) => n.parse::<f64>().ok()? / d.parse::<f64>().ok()?,
+        None => miles.parse::<f64>().ok()?,
+    };
+    // "1 1/2SM" arrives as two tokens
+    if let Some(whole) = previous.and_then(|p| p.parse::<u32>().ok()) {
+        if whole < 10 {
+            value += whole as f64;
+        }
+    }
+    Some(((value * METRES_PER_SM).round() as u32).min(VISIBILITY_10KM))
+}
+
+/// Ceiling height in feet from BKN/OVC/VV layers
+fn ceiling_layer(token: &str) -> Option<i32> {
+    let height = if token.starts_with("BKN") || token.starts_with("OVC") {
+        digits(token, 3..6)?
+    } else if token.starts_with("VV") {
+        digits(token, 2..5)?
+    } else {
+        return None;
+    };
+    Some(height as i32 * 100)
+}
+
+fn is_cloud_layer(token: &str) -> bool {
+    ["FEW", "SCT", "BKN", "OVC"]
+        .iter()
+        .any(|p| token.starts_with(p) && digits(token, 3..6).is_some())
+        || ceiling_layer(token).is_some()
+}
+
+/// Parse a TAF
+///
+/// `reference` is any time in the month the TAF was issued, normally the
+/// time it was received; day-of-month groups are resolved against it.
+/// Weather, wind and temperature groups are skipped.
+pub fn parse(raw: &str, reference: DateTime<Utc>) -> Result<Taf> {
+    let text = raw.trim().trim_end_matches('=');
+    let mut tokens = text
+        .split_whitespace()
+        .skip_while(|t| matches!(*t, "TAF" | "AMD" | "COR"))
+        .peekable();
+
+    let station = tokens.next().ok_or_else(|| invalid("empty"))?.to_string();
+    if station.len() != 4 || !station.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err(invalid(format!("bad station {}", station)));
+    }
+
+    let issued = tokens.next().ok_or_else(|| invalid("missing issue time"))?;
+    if issued.len() != 7 || !issued.ends_with('Z') {
+        return Err(invalid(format!("bad issue time {}", issued)));
+    }
+    // Issued up to a day before `reference`, possibly last month
+    let reference_start = reference - Duration::days(2);
+    let issued_at = resolve_time(
+        digits(issued, 0..2).ok_or_else(|| invalid("bad issue day"))?,
+        digits(issued, 2..4).ok_or_else(|| invalid("bad issue hour"))?,
+        digits(issued, 4..6).ok_or_else(|| invalid("bad issue minute"))?,
+        reference_start,
+    )
+    .ok_or_else(|| invalid("bad issue time"))?;
+    let issued_time = DateTime::from_timestamp(issued_at, 0).ok_or_else(|| invalid("bad issue time"))?;
+
+    let valid = tokens.next().ok_or_else(|| invalid("missing validity"))?;
+    let (valid_from, valid_to) =
+        validity(valid, issued_time - Duration::hours(1)).ok_or_else(|| invalid("bad validity"))?;
+    let valid_from_time = DateTime::from_timestamp(valid_from, 0).ok_or_else(|| invalid("bad validity"))?;
+
+    let mut periods = vec![TafPeriod {
+        change: ChangeKind::Base,
+        from: valid_from,
+        to: valid_to,
+        visibility_m: None,
+        sky_reported: false,
+        ceiling_ft: None,
+    }];
+    let mut previous: Option<&str> = None;
+
+    while let Some(token) = tokens.next() {
+        let change = if let Some(time) = token.strip_prefix("FM") {
+            let from = (time.len() == 6)
+                .then(|| {
+                    resolve_time(digits(time, 0..2)?, digits(time, 2..4)?, digits(time, 4..6)?, valid_from_time)
+                })
+                .flatten()
+                .ok_or_else(|| invalid(format!("bad group {}", token)))?;
+            Some((ChangeKind::From, from, valid_to))
+        } else if matches!(token, "BECMG" | "TEMPO") || token.starts_with("PROB") {
+            let change = match token {
+                "BECMG" => ChangeKind::Becoming,
+                "TEMPO" => ChangeKind::Tempo,
+                _ => ChangeKind::Probability(
+                    digits(token, 4..6).ok_or_else(|| invalid(format!("bad group {}", token)))? as u8,
+                ),
+            };
+            if tokens.peek() == Some(&"TEMPO") && matches!(change, ChangeKind::Probability(_)) {
+                tokens.next();
+            }
+            let window = tokens.next().ok_or_else(|| invalid(format!("{} without a time", token)))?;
+            let (from, to) = validity(window, valid_from_time - Duration::hours(1))
+                .ok_or_else(|| invalid(format!("bad group time {}", window)))?;
+            Some((change, from, to))
+        } else {
+            None
+        };
+
+        if let Some((change, from, to)) = change {
+            periods.push(TafPeriod {
+                change,
+                from,
+                to,
+                visibility_m: None,
+                sky_reported: false,
+                ceiling_ft: None,
+            });
+            previous = None;
+            continue;
+        }
+
+        let period = periods.last_mut().expect("base period");
+        match token {
+            "CAVOK" => {
+                period.visibility_m = Some(VISIBILITY_10KM);
+                period.sky_reported = true;
+                period.ceiling_ft = None;
+            }
+            "NSC" | "SKC" | "CLR" | "NCD" => {
+                period.sky_reported = true;
+                period.ceiling_ft = None;
+            }
+            _ if is_cloud_layer(token) => {
+                if !period.sky_reported {
+                    period.sky_reported = true;
+                    period.ceiling_ft = None;
+                }
+                if let Some(height) = ceiling_layer(token) {
+                    period.ceiling_ft = Some(period.ceiling_ft.map_or(height, |c| c.min(height)));
+                }
+            }
+            // Wind groups are 5 digits plus units, so 4 digits is visibility
+            _ if period.visibility_m.is_none() => {
+                period.visibility_m = visibility(token, previous);
+            }
+            _ => {}
+        }
+        previous = Some(token);
+    }
+
+    Ok(Taf {
+        station,
+        issued_at,
+        valid_from,
+        valid_to,
+        periods,
+        raw: text.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "TAF ZBAA 170500Z 1706/1812 36004MPS 6000 SCT030 \
+        BECMG 1710/1712 9999 BKN040 \
+        TEMPO 1714/1718 3000 TSRA BKN010 FEW030CB \
+        FM180200 18005KT CAVOK \
+        PROB30 TEMPO 1804/1806 1500 BR OVC005=";
+
+    fn at(day: u32, hour: u32) -> i64 {
+        Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap().timestamp()
+    }
+
+    proptest::proptest! {
+        /// Garbled reports from sync are rejected, never a panic
+        #[test]
+        fn prop_parse_never_panics(
+            tokens in proptest::collection::vec(
+                proptest::prop_oneof![
+                    proptest::sample::select(SAMPLE.split_whitespace().map(String::from).collect::<Vec<_>>()),
+                    "[A-Z0-9/]{1,12}",
+                ],
+                0..24,
+            ),
+            noise in "\\PC{0,40}",
+        ) {
+            let reference = Utc.with_ymd_and_hms(2026, 10, 17, 5, 0, 0).unwrap();
+            let _ = parse(&format!("TAF {}", tokens.join(" ")), reference);
+            let _ = parse(&noise, reference);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let reference = Utc.with_ymd_and_hms(2026, 10, 17, 5, 10, 0).unwrap();
+        let taf = parse(SAMPLE, reference).unwrap();
+
+        assert_eq!(taf.station, "ZBAA");
+        assert_eq!(taf.issued_at, at(17, 5));
+        assert_eq!((taf.valid_from, taf.valid_to), (at(17, 6), at(18, 12)));
+        let kinds: Vec<ChangeKind> = taf.periods.iter().map(|p| p.change).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ChangeKind::Base,
+                ChangeKind::Becoming,
+                ChangeKind::Tempo,
+                ChangeKind::From,
+                ChangeKind::Probability(30)
+            ]
+        );
+        assert_eq!(taf.periods[0].visibility_m, Some(6000));
+        assert!(taf.periods[0].sky_reported && taf.periods[0].ceiling_ft.is_none());
+        assert_eq!(taf.periods[2].ceiling_ft, Some(1000));
+        assert_eq!(taf.periods[3].from, at(18, 2));
+    }
+
+    #[test]
+    fn test_worst_between() {
+        let reference = Utc.with_ymd_and_hms(2026, 10, 17, 5, 10, 0).unwrap();
+        let taf = parse(SAMPLE, reference).unwrap();
+
+        // Before BECMG: 6000 m, no ceiling
+        let early = taf.worst_between(at(17, 7), at(17, 8), true).unwrap();
+        assert_eq!(early, Conditions { visibility_m: Some(6000), ceiling_ft: None });
+
+        // After BECMG, inside TEMPO
+        let tempo = taf.worst_between(at(17, 15), at(17, 16), true).unwrap();
+        assert_eq!(tempo, Conditions { visibility_m: Some(3000), ceiling_ft: Some(1000) });
+        let prevailing = taf.worst_between(at(17, 15), at(17, 16), false).unwrap();
+        assert_eq!(prevailing, Conditions { visibility_m: Some(VISIBILITY_10KM), ceiling_ft: Some(4000) });
+
+        // FM CAVOK, then PROB30 TEMPO fog
+        let late = taf.worst_between(at(18, 3), at(18, 4), false).unwrap();
+        assert_eq!(late, Conditions { visibility_m: Some(VISIBILITY_10KM), ceiling_ft: None });
+        let fog = taf.worst_between(at(18, 4), at(18, 5), true).unwrap();
+        assert_eq!(fog, Conditions { visibility_m: Some(1500), ceiling_ft: Some(500) });
+
+        assert!(taf.worst_between(at(19, 0), at(19, 1), true).is_none());
+    }
+
+    #[test]
+    fn test_statute_miles_and_month_rollover() {
+        let reference = Utc.with_ymd_and_hms(2026, 11, 1, 0, 30, 0).unwrap();
+        let taf = parse("KJFK 312330Z 0100/0206 1 1/2SM BR OVC008 FM011200 P6SM SCT250", reference).unwrap();
+        assert_eq!(taf.issued_at, Utc.with_ymd_and_hms(2026, 10, 31, 23, 30, 0).unwrap().timestamp());
+        assert_eq!(taf.valid_from, Utc.with_ymd_and_hms(2026, 11, 1, 0, 0, 0).unwrap().timestamp());
+        assert_eq!(taf.periods[0].visibility_m, Some(2414));
+        assert_eq!(taf.periods[0].ceiling_ft, Some(800));
+        assert_eq!(taf.periods[1].visibility_m, Some(VISIBILITY_10KM));
+
+        assert!(parse("TAF", reference).is_err());
+        assert!(parse("KJFK 3123Z 0100/0206", reference).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::Result;
+use crate::import::{ImportControl, ImportPhase};
+use crate::models::runway::Wind;
+use crate::models::Coordinate;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Grid points further than this from a position are not used for it
+const MAX_GRID_DISTANCE_DEG: f64 = 1.5;
+
+/// Wind and temperature at one grid point, pressure level and forecast time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindsAloft {
+    pub valid_time: i64,
+    pub level_hpa: i32,
+    pub position: Coordinate,
+    pub wind: Wind,
+    /// Degrees Celsius
+    pub temperature_c: Option<f64>,
+}
+
+/// Pressure (hPa) at a pressure altitude in the ISA
+pub fn pressure_at(altitude_ft: f64) -> f64 {
+    if altitude_ft <= 36089.0 {
+        1013.25 * (1.0 - 6.8756e-6 * altitude_ft).powf(5.2559)
+    } else {
+        226.32 * (-(altitude_ft - 36089.0) / 20806.0).exp()
+    }
+}
+
+/// Wind blowing from, in knots, for eastward/northward components in m/s
+pub fn wind_from_components(u: f64, v: f64) -> Wind {
+    let speed = (u * u + v * v).sqrt() * 1.943844;
+    let direction = if speed < 0.05 {
+        0.0
+    } else {
+        (-u).atan2(-v).to_degrees().rem_euclid(360.0)
+    };
+    Wind::new(direction, speed)
+}
+
+/// Insert or replace grid points, stamped `now`
+pub fn store(conn: &Connection, points: &[WindsAloft], now: i64) -> Result<usize> {
+    store_with(conn, points, now, &ImportControl::default())
+}
+
+/// Insert or replace grid points, reporting progress per point
+pub fn store_with(
+    conn: &Connection,
+    points: &[WindsAloft],
+    now: i64,
+    control: &ImportControl,
+) -> Result<usize> {
+    let total = Some(points.len());
+    let tx = conn.unchecked_transaction()?;
+    control.report(ImportPhase::Writing, 0, total)?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO winds_aloft (valid_time, level_hpa, latitude, longitude,
+                 wind_direction, wind_speed, temperature, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for (done, point) in points.iter().enumerate() {
+            stmt.execute(rusqlite::params![
+                point.valid_time,
+                point.level_hpa,
+                point.position.latitude,
+                point.position.longitude,
+                point.wind.direction,
+                point.wind.speed,
+                point.temperature_c,
+                now,
+            ])?;
+            control.report(ImportPhase::Writing, done + 1, total)?;
+        }
+    }
+    control.report(ImportPhase::Committing, points.len(), total)?;
+    tx.commit()?;
+    Ok(points.len())
+}
+
+/// Nearest stored grid point to `position`, on the pressure level closest
+/// to `altitude_ft` and the forecast time closest to `at`
+pub(crate) fn winds_at(
+    conn: &Connection,
+    position: Coordinate,
+    altitude_ft: i32,
+    at: i64,
+) -> Result<Option<WindsAloft>> {
+    let valid_time: Option<i64> = conn
+        .query_row(
+            "SELECT valid_time FROM winds_aloft
+             GROUP BY valid_time ORDER BY ABS(valid_time - ?1), valid_time LIMIT 1",
+            [at],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(valid_time) = valid_time else {
+        return Ok(None);
+    };
+
+    let pressure = pressure_at(altitude_ft as f64);
+    let level: i32 = conn.query_row(
+        "SELECT level_hpa FROM winds_aloft WHERE valid_time = ?1
+         GROUP BY level_hpa ORDER BY ABS(level_hpa - ?2), level_hpa LIMIT 1",
+        rusqlite::params![valid_time, pressure],
+        |row| row.get(0),
+    )?;
+
+    conn.query_row(
+        "SELECT latitude, longitude, wind_direction, wind_speed, temperature
+         FROM winds_aloft
+         WHERE valid_time = ?1 AND level_hpa = ?2
+           AND latitude BETWEEN ?3 - ?5 AND ?3 + ?5
+           AND longitude BETWEEN ?4 - ?5 AND ?4 + ?5
+         ORDER BY (latitude - ?3) * (latitude - ?3) + (longitude - ?4) * (longitude - ?4)
+         LIMIT 1",
+        rusqlite::params![
+            valid_time,
+            level,
+            position.latitude,
+            position.longitude,
+            MAX_GRID_DISTANCE_DEG
+        ],
+        |row| {
+            Ok(WindsAloft {
+                valid_time,
+                level_hpa: level,
+                position: Coordinate::new(row.get(0)?, row.get(1)?),
+                wind: Wind::new(row.get(2)?, row.get(3)?),
+                temperature_c: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+/// Remove forecasts valid before `before`
+pub fn prune(conn: &Connection, before: i64) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM winds_aloft WHERE valid_time < ?1", [before])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure_and_components() {
+        assert!((pressure_at(0.0) - 1013.25).abs() < 0.01);
+        assert!((pressure_at(18000.0) - 506.0).abs() < 1.0);
+        assert!((pressure_at(34000.0) - 250.0).abs() < 1.0);
+        assert!((pressure_at(39000.0) - 197.0).abs() < 1.0);
+
+        // Westerly: blowing towards the east, from 270
+        let west = wind_from_components(10.0, 0.0);
+        assert!((west.direction - 270.0).abs() < 1e-9);
+        assert!((west.speed - 19.44).abs() < 0.01);
+        let north = wind_from_components(0.0, -5.0);
+        assert!(north.direction.abs() < 1e-9);
+        assert_eq!(wind_from_components(0.0, 0.0).speed, 0.0);
+    }
+}